@@ -0,0 +1,263 @@
+//! Configuracao por ambiente: `pordosol.<ambiente>.proj` aplicado sobre o `pordosol.proj`.
+//!
+//! O ambiente vem de `--ambiente` ou, sem a flag, de `PORDOSOL_AMBIENTE`. Regras da mescla:
+//!
+//! - objeto sobre objeto: mescla chave a chave, recursivamente (chaves so da base ficam);
+//! - qualquer outro caso (array, texto, numero, booleano, `null` ou tipos diferentes):
+//!   o valor do ambiente substitui o da base por inteiro;
+//! - chaves novas de um objeto entram depois das existentes, que mantem a ordem da base.
+//!
+//! Quem le a configuracao (`carregar_configuracao_projeto`, `dependencias`) recebe o resultado
+//! mesclado e valida esse resultado; comandos que gravam no projeto continuam alterando so a base.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+
+use anyhow::Result;
+use serde_json::Value;
+
+use crate::erros::ErroCli;
+use crate::saida::detalhe;
+
+/// Ambiente ativo e se ele veio de `--ambiente` (arquivo ausente vira aviso so nesse caso).
+struct AmbienteAtivo {
+    nome: String,
+    da_flag: bool,
+}
+
+static AMBIENTE: OnceLock<Option<AmbienteAtivo>> = OnceLock::new();
+
+/// Avisos sobre o arquivo do ambiente saem uma vez por invocacao (a leitura se repete).
+static AVISO_EMITIDO: AtomicBool = AtomicBool::new(false);
+
+fn nome_valido(nome: &str) -> bool {
+    !nome.is_empty()
+        && nome
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Registra o ambiente da invocacao: `--ambiente` tem precedencia sobre `PORDOSOL_AMBIENTE`.
+pub fn definir_ambiente(flag: Option<&str>) -> Result<()> {
+    let ativo = match flag {
+        Some(nome) => Some((nome.trim().to_string(), true)),
+        None => std::env::var("PORDOSOL_AMBIENTE")
+            .ok()
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+            .map(|v| (v, false)),
+    };
+    if let Some((nome, da_flag)) = &ativo {
+        if !nome_valido(nome) {
+            return Err(ErroCli::ProjetoInvalido(format!(
+                "Ambiente invalido '{}' ({}): use letras, numeros, '-' ou '_'",
+                nome,
+                if *da_flag {
+                    "--ambiente"
+                } else {
+                    "PORDOSOL_AMBIENTE"
+                }
+            ))
+            .into());
+        }
+    }
+    AMBIENTE
+        .set(ativo.map(|(nome, da_flag)| AmbienteAtivo { nome, da_flag }))
+        .ok();
+    Ok(())
+}
+
+pub fn ambiente_ativo() -> Option<&'static str> {
+    AMBIENTE.get()?.as_ref().map(|a| a.nome.as_str())
+}
+
+/// `pordosol.<ambiente>.proj` do ambiente ativo, exista ou nao.
+pub fn arquivo_ambiente(raiz: &Path) -> Option<PathBuf> {
+    ambiente_ativo().map(|nome| raiz.join(format!("pordosol.{}.proj", nome)))
+}
+
+fn avisar_uma_vez(msg: String) {
+    if !AVISO_EMITIDO.swap(true, Ordering::Relaxed) {
+        eprintln!("Aviso: {}", msg);
+    }
+}
+
+/// Conteudo do arquivo do ambiente ativo, se houver.
+///
+/// Arquivo ausente so gera aviso quando o ambiente veio de `--ambiente`; JSON invalido
+/// sempre avisa e o arquivo e ignorado.
+pub fn carregar_ambiente(raiz: &Path) -> Option<(PathBuf, Value)> {
+    let arquivo = arquivo_ambiente(raiz)?;
+    if !arquivo.is_file() {
+        let da_flag = AMBIENTE
+            .get()
+            .and_then(Option::as_ref)
+            .is_some_and(|a| a.da_flag);
+        if da_flag {
+            avisar_uma_vez(format!(
+                "{} nao existe; usando so o pordosol.proj",
+                arquivo.display()
+            ));
+        } else {
+            detalhe!(
+                "{} nao existe; usando so o pordosol.proj",
+                arquivo.display()
+            );
+        }
+        return None;
+    }
+    let lido = fs::read_to_string(&arquivo)
+        .map_err(|e| e.to_string())
+        .and_then(|t| serde_json::from_str::<Value>(&t).map_err(|e| e.to_string()));
+    match lido {
+        Ok(valor @ Value::Object(_)) => Some((arquivo, valor)),
+        Ok(_) => {
+            avisar_uma_vez(format!(
+                "{} deve conter um objeto JSON; arquivo ignorado",
+                arquivo.display()
+            ));
+            None
+        }
+        Err(e) => {
+            avisar_uma_vez(format!(
+                "{} invalido ({}); arquivo ignorado",
+                arquivo.display(),
+                e
+            ));
+            None
+        }
+    }
+}
+
+/// Aplica `camada` sobre `base` segundo as regras do modulo.
+pub fn mesclar(base: &mut Value, camada: Value) {
+    match (base, camada) {
+        (Value::Object(b), Value::Object(c)) => {
+            for (chave, valor) in c {
+                match b.get_mut(&chave) {
+                    Some(existente) => mesclar(existente, valor),
+                    None => {
+                        b.insert(chave, valor);
+                    }
+                }
+            }
+        }
+        (base, camada) => *base = camada,
+    }
+}
+
+/// Mescla um objeto do ambiente sobre entradas ordenadas da base (ex.: `dependencias`).
+pub fn mesclar_entradas(entradas: &mut Vec<(String, Value)>, camada: Option<&Value>) {
+    let Some(Value::Object(c)) = camada else {
+        return;
+    };
+    for (chave, valor) in c {
+        match entradas.iter_mut().find(|(k, _)| k == chave) {
+            Some((_, existente)) => mesclar(existente, valor.clone()),
+            None => entradas.push((chave.clone(), valor.clone())),
+        }
+    }
+}
+
+/// Nome do arquivo de uma camada, para `config origem`.
+pub fn nome_arquivo(arquivo: &Path) -> String {
+    arquivo
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn mescla_objetos_e_substitui_arrays_e_escalares() {
+        let mut base = json!({
+            "nome": "app",
+            "configuracao": {
+                "pasta_saida": "build",
+                "pre_checks": ["lint", "fmt"],
+                "otimizacao": false
+            },
+            "dependencias": {
+                "util": {"path": "../util", "versao": "^1.0"},
+                "log": "1.2.0"
+            },
+            "perfis": {
+                "ci": {"avisos_como_erros": false, "flags": ["-a"]}
+            }
+        });
+        mesclar(
+            &mut base,
+            json!({
+                "configuracao": {"pre_checks": ["lint"], "otimizacao": true},
+                "dependencias": {
+                    "util": {"versao": "^2.0"},
+                    "log": {"path": "../log"},
+                    "http": "0.3.0"
+                },
+                "perfis": {
+                    "ci": {"avisos_como_erros": true, "flags": ["-b", "-c"]},
+                    "dev": null
+                }
+            }),
+        );
+        assert_eq!(
+            base,
+            json!({
+                "nome": "app",
+                "configuracao": {
+                    "pasta_saida": "build",
+                    "pre_checks": ["lint"],
+                    "otimizacao": true
+                },
+                "dependencias": {
+                    "util": {"path": "../util", "versao": "^2.0"},
+                    "log": {"path": "../log"},
+                    "http": "0.3.0"
+                },
+                "perfis": {
+                    "ci": {"avisos_como_erros": true, "flags": ["-b", "-c"]},
+                    "dev": null
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn entradas_mantem_a_ordem_da_base_e_acrescentam_novas() {
+        let mut entradas = vec![
+            ("zeta".to_string(), json!({"path": "../zeta"})),
+            ("alfa".to_string(), json!("1.0.0")),
+        ];
+        mesclar_entradas(
+            &mut entradas,
+            Some(&json!({"beta": "2.0.0", "zeta": {"versao": "^1"}})),
+        );
+        assert_eq!(
+            entradas,
+            vec![
+                (
+                    "zeta".to_string(),
+                    json!({"path": "../zeta", "versao": "^1"})
+                ),
+                ("alfa".to_string(), json!("1.0.0")),
+                ("beta".to_string(), json!("2.0.0")),
+            ]
+        );
+    }
+
+    #[test]
+    fn nomes_de_ambiente() {
+        assert!(nome_valido("ci"));
+        assert!(nome_valido("dev-local_2"));
+        assert!(!nome_valido(""));
+        assert!(!nome_valido("../x"));
+        assert!(!nome_valido("c i"));
+    }
+}
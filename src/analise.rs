@@ -0,0 +1,456 @@
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+
+use crate::construir::{artefato_registrado, pasta_saida_projeto, resolver_nome_saida, Perfil};
+use crate::toolchain::{listar_prs, localizar_raiz};
+
+/// Historico de tamanhos dos artefatos de bytecode, uma linha JSON por build.
+pub const ARQUIVO_TAMANHOS: &str = ".tamanhos.jsonl";
+
+/// Quantos itens `analisar tamanho` mostra no ranking de crescimento.
+const MAX_CRESCIMENTO: usize = 5;
+
+/// Fonte do projeto, com o nome do artefato que um build por arquivo geraria para ele.
+#[derive(Clone, Debug)]
+pub struct FonteAnalisada {
+    pub caminho: String,
+    pub artefato: String,
+    pub linhas: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct LinhaTamanho {
+    pub fonte: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub artefato: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bytes: Option<u64>,
+    pub linhas: usize,
+    /// Parcela do total de bytes (por arquivo) ou de linhas (artefato unico)
+    pub percentual: f64,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Crescimento {
+    pub artefato: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fonte: Option<String>,
+    pub anterior: u64,
+    pub atual: u64,
+    pub delta: i64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ModoAnalise {
+    /// Cada fonte tem o proprio artefato
+    PorArquivo,
+    /// So ha um artefato: linhas de codigo servem de estimativa
+    Linhas,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct RelatorioTamanho {
+    pub modo: ModoAnalise,
+    pub total_bytes: u64,
+    pub fontes: Vec<LinhaTamanho>,
+    pub crescimento: Vec<Crescimento>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nota: Option<String>,
+}
+
+/// Tamanho de cada artefato conhecido, seguindo o manifesto da pasta de saida.
+pub fn tamanhos_artefatos<'a>(
+    saida_dir: &Path,
+    nomes: impl IntoIterator<Item = &'a str>,
+) -> BTreeMap<String, u64> {
+    nomes
+        .into_iter()
+        .filter_map(|nome| {
+            let meta = fs::metadata(artefato_registrado(saida_dir, nome)).ok()?;
+            meta.is_file().then(|| (nome.to_string(), meta.len()))
+        })
+        .collect()
+}
+
+/// Relaciona fontes e artefatos; sem um artefato por fonte, cai para a contagem de linhas.
+///
+/// `principal` e o artefato monolitico usado como total nesse caso.
+pub fn correlacionar(
+    fontes: &[FonteAnalisada],
+    tamanhos: &BTreeMap<String, u64>,
+    principal: Option<&str>,
+) -> RelatorioTamanho {
+    let por_arquivo = fontes.len() > 1 && fontes.iter().all(|f| tamanhos.contains_key(&f.artefato));
+
+    let (modo, total_bytes, mut linhas, nota) = if por_arquivo {
+        let total: u64 = fontes.iter().map(|f| tamanhos[&f.artefato]).sum();
+        let linhas = fontes
+            .iter()
+            .map(|f| {
+                let bytes = tamanhos[&f.artefato];
+                LinhaTamanho {
+                    fonte: f.caminho.clone(),
+                    artefato: Some(f.artefato.clone()),
+                    bytes: Some(bytes),
+                    linhas: f.linhas,
+                    percentual: percentual(bytes, total),
+                }
+            })
+            .collect::<Vec<_>>();
+        (ModoAnalise::PorArquivo, total, linhas, None)
+    } else {
+        let total_linhas: usize = fontes.iter().map(|f| f.linhas).sum();
+        let total = principal
+            .and_then(|p| tamanhos.get(p))
+            .copied()
+            .unwrap_or_else(|| tamanhos.values().copied().max().unwrap_or(0));
+        let linhas = fontes
+            .iter()
+            .map(|f| LinhaTamanho {
+                fonte: f.caminho.clone(),
+                artefato: None,
+                bytes: None,
+                linhas: f.linhas,
+                percentual: percentual(f.linhas as u64, total_linhas as u64),
+            })
+            .collect::<Vec<_>>();
+        let nota = format!(
+            "Artefato unico{}: sem tamanho por fonte; linhas de codigo usadas como estimativa.",
+            principal
+                .filter(|p| tamanhos.contains_key(*p))
+                .map(|p| format!(" ({})", p))
+                .unwrap_or_default()
+        );
+        (ModoAnalise::Linhas, total, linhas, Some(nota))
+    };
+
+    linhas.sort_by(|a, b| {
+        b.bytes
+            .cmp(&a.bytes)
+            .then_with(|| b.linhas.cmp(&a.linhas))
+            .then_with(|| a.fonte.cmp(&b.fonte))
+    });
+    RelatorioTamanho {
+        modo,
+        total_bytes,
+        fontes: linhas,
+        crescimento: Vec::new(),
+        nota,
+    }
+}
+
+fn percentual(parte: u64, total: u64) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        parte as f64 * 100.0 / total as f64
+    }
+}
+
+/// Artefatos que mais cresceram entre dois registros do historico, maior crescimento primeiro.
+pub fn maiores_crescimentos(
+    anterior: &BTreeMap<String, u64>,
+    atual: &BTreeMap<String, u64>,
+    fontes: &[FonteAnalisada],
+) -> Vec<Crescimento> {
+    let mut out: Vec<Crescimento> = atual
+        .iter()
+        .filter_map(|(artefato, &bytes)| {
+            let antes = *anterior.get(artefato)?;
+            let delta = bytes as i64 - antes as i64;
+            (delta > 0).then(|| Crescimento {
+                artefato: artefato.clone(),
+                fonte: fontes
+                    .iter()
+                    .find(|f| &f.artefato == artefato)
+                    .map(|f| f.caminho.clone()),
+                anterior: antes,
+                atual: bytes,
+                delta,
+            })
+        })
+        .collect();
+    out.sort_by(|a, b| {
+        b.delta
+            .cmp(&a.delta)
+            .then_with(|| a.artefato.cmp(&b.artefato))
+    });
+    out.truncate(MAX_CRESCIMENTO);
+    out
+}
+
+/// Fontes do projeto com o artefato `<stem>.pbc` correspondente e a contagem de linhas.
+fn fontes_analisadas(raiz: &Path) -> Result<Vec<FonteAnalisada>> {
+    listar_prs(raiz)
+        .iter()
+        .map(|arq| {
+            let texto = fs::read(arq).with_context(|| format!("Falha ao ler {}", arq.display()))?;
+            let rel = arq.strip_prefix(raiz).unwrap_or(arq);
+            Ok(FonteAnalisada {
+                caminho: rel.to_string_lossy().replace('\\', "/"),
+                artefato: format!(
+                    "{}.pbc",
+                    arq.file_stem().unwrap_or_default().to_string_lossy()
+                ),
+                linhas: String::from_utf8_lossy(&texto).lines().count(),
+            })
+        })
+        .collect()
+}
+
+/// Nome do artefato nomeado do build (`<nome do projeto>.pbc`).
+fn artefato_principal(raiz: &Path, fontes: &[FonteAnalisada]) -> Option<String> {
+    let stem = fontes
+        .first()?
+        .artefato
+        .trim_end_matches(".pbc")
+        .to_string();
+    resolver_nome_saida(raiz, None, &stem, false)
+        .ok()
+        .map(|n| format!("{}.pbc", n))
+}
+
+/// Acrescenta os tamanhos atuais dos artefatos de bytecode ao historico do projeto.
+///
+/// Chamado ao fim do build; falha so gera aviso.
+pub fn registrar_tamanhos(raiz: &Path, saida_dir: &Path, arquivos: &[PathBuf]) {
+    let mut nomes: Vec<String> = arquivos
+        .iter()
+        .map(|a| {
+            format!(
+                "{}.pbc",
+                a.file_stem().unwrap_or_default().to_string_lossy()
+            )
+        })
+        .collect();
+    if let Ok(fontes) = fontes_analisadas(raiz) {
+        nomes.extend(artefato_principal(raiz, &fontes));
+    }
+    let tamanhos = tamanhos_artefatos(saida_dir, nomes.iter().map(String::as_str));
+    if tamanhos.is_empty() {
+        return;
+    }
+    if let Err(e) = gravar_registro(&pasta_saida_projeto(raiz), &tamanhos) {
+        eprintln!("Aviso: nao foi possivel registrar os tamanhos: {:#}", e);
+    }
+}
+
+fn gravar_registro(pasta_base: &Path, tamanhos: &BTreeMap<String, u64>) -> Result<()> {
+    let registro = serde_json::json!({
+        "timestamp": SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0),
+        "artefatos": tamanhos,
+    });
+    let caminho = pasta_base.join(ARQUIVO_TAMANHOS);
+    let mut arquivo = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&caminho)
+        .with_context(|| format!("Falha ao abrir {}", caminho.display()))?;
+    writeln!(arquivo, "{}", registro)
+        .with_context(|| format!("Falha ao escrever {}", caminho.display()))
+}
+
+/// Registros do historico de tamanhos, do mais antigo ao mais recente.
+fn ler_historico(pasta_base: &Path) -> Vec<BTreeMap<String, u64>> {
+    fs::read_to_string(pasta_base.join(ARQUIVO_TAMANHOS))
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|l| serde_json::from_str::<serde_json::Value>(l).ok())
+        .filter_map(|v| serde_json::from_value(v.get("artefatos")?.clone()).ok())
+        .collect()
+}
+
+/// `analisar tamanho`: quais fontes mais pesam no bytecode e o que cresceu desde o build anterior.
+pub fn tamanho_cmd(caminho: &Path, json: bool) -> Result<()> {
+    let raiz = localizar_raiz(caminho);
+    let fontes = fontes_analisadas(&raiz)?;
+    if fontes.is_empty() {
+        bail!("Nenhum arquivo .pr encontrado em {}/src", raiz.display());
+    }
+    let saida_dir = Perfil::Dev.saida_dir(&raiz);
+    let principal = artefato_principal(&raiz, &fontes);
+    let nomes = fontes
+        .iter()
+        .map(|f| f.artefato.as_str())
+        .chain(principal.as_deref());
+    let tamanhos = tamanhos_artefatos(&saida_dir, nomes);
+    if tamanhos.is_empty() {
+        bail!(
+            "Nenhum artefato de bytecode em {}. Rode `pordosol build` antes.",
+            saida_dir.display()
+        );
+    }
+
+    let mut relatorio = correlacionar(&fontes, &tamanhos, principal.as_deref());
+    // O ultimo registro e o do build atual; o anterior serve de base
+    let historico = ler_historico(&pasta_saida_projeto(&raiz));
+    if let [.., anterior, _] = historico.as_slice() {
+        relatorio.crescimento = maiores_crescimentos(anterior, &tamanhos, &fontes);
+    }
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&relatorio)?);
+        return Ok(());
+    }
+
+    let largura = relatorio
+        .fontes
+        .iter()
+        .map(|l| l.fonte.len())
+        .chain(std::iter::once("fonte".len()))
+        .max()
+        .unwrap_or(0);
+    match relatorio.modo {
+        ModoAnalise::PorArquivo => {
+            println!(
+                "{:<largura$}  {:>10}  {:>6}",
+                "fonte",
+                "bytes",
+                "%",
+                largura = largura
+            );
+            for l in &relatorio.fontes {
+                println!(
+                    "{:<largura$}  {:>10}  {:>5.1}%",
+                    l.fonte,
+                    l.bytes.unwrap_or(0),
+                    l.percentual,
+                    largura = largura
+                );
+            }
+            println!(
+                "{:<largura$}  {:>10}",
+                "total",
+                relatorio.total_bytes,
+                largura = largura
+            );
+        }
+        ModoAnalise::Linhas => {
+            println!(
+                "{:<largura$}  {:>10}  {:>6}",
+                "fonte",
+                "linhas",
+                "%",
+                largura = largura
+            );
+            for l in &relatorio.fontes {
+                println!(
+                    "{:<largura$}  {:>10}  {:>5.1}%",
+                    l.fonte,
+                    l.linhas,
+                    l.percentual,
+                    largura = largura
+                );
+            }
+            println!("Artefato: {} bytes", relatorio.total_bytes);
+        }
+    }
+    if let Some(nota) = &relatorio.nota {
+        println!("Nota: {}", nota);
+    }
+    if !relatorio.crescimento.is_empty() {
+        println!("Maior crescimento desde o build anterior:");
+        for c in &relatorio.crescimento {
+            println!(
+                "  {}{}: {} -> {} bytes (+{})",
+                c.artefato,
+                c.fonte
+                    .as_deref()
+                    .map(|f| format!(" ({})", f))
+                    .unwrap_or_default(),
+                c.anterior,
+                c.atual,
+                c.delta
+            );
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fonte(caminho: &str, artefato: &str, linhas: usize) -> FonteAnalisada {
+        FonteAnalisada {
+            caminho: caminho.to_string(),
+            artefato: artefato.to_string(),
+            linhas,
+        }
+    }
+
+    #[test]
+    fn correlaciona_artefatos_pelo_manifesto() {
+        let dir = tempfile::tempdir().unwrap();
+        let saida = dir.path();
+        fs::create_dir_all(saida.join("bc")).unwrap();
+        fs::write(saida.join("bc").join("util.pbc"), vec![0u8; 300]).unwrap();
+        fs::write(saida.join("programa.pbc"), vec![0u8; 100]).unwrap();
+        fs::write(
+            saida.join(".pordosol-build.json"),
+            r#"{"artefatos": {"util.pbc": "bc/util.pbc"}}"#,
+        )
+        .unwrap();
+        let fontes = [
+            fonte("src/programa.pr", "programa.pbc", 10),
+            fonte("src/util.pr", "util.pbc", 5),
+        ];
+
+        let tamanhos = tamanhos_artefatos(saida, fontes.iter().map(|f| f.artefato.as_str()));
+        let r = correlacionar(&fontes, &tamanhos, Some("app.pbc"));
+        assert_eq!(r.modo, ModoAnalise::PorArquivo);
+        assert_eq!(r.total_bytes, 400);
+        assert_eq!(r.fontes[0].fonte, "src/util.pr");
+        assert_eq!(r.fontes[0].bytes, Some(300));
+        assert_eq!(r.fontes[0].percentual, 75.0);
+        assert_eq!(r.fontes[1].percentual, 25.0);
+        assert!(r.nota.is_none());
+    }
+
+    #[test]
+    fn artefato_unico_usa_linhas_como_estimativa() {
+        let tamanhos = BTreeMap::from([("app.pbc".to_string(), 900)]);
+        let fontes = [
+            fonte("src/programa.pr", "programa.pbc", 30),
+            fonte("src/util.pr", "util.pbc", 90),
+        ];
+        let r = correlacionar(&fontes, &tamanhos, Some("app.pbc"));
+        assert_eq!(r.modo, ModoAnalise::Linhas);
+        assert_eq!(r.total_bytes, 900);
+        assert_eq!(r.fontes[0].fonte, "src/util.pr");
+        assert_eq!(r.fontes[0].percentual, 75.0);
+        assert!(r.nota.unwrap().contains("app.pbc"));
+    }
+
+    #[test]
+    fn crescimento_ordena_pelo_maior_delta() {
+        let anterior = BTreeMap::from([
+            ("a.pbc".to_string(), 100),
+            ("b.pbc".to_string(), 100),
+            ("c.pbc".to_string(), 100),
+        ]);
+        let atual = BTreeMap::from([
+            ("a.pbc".to_string(), 110),
+            ("b.pbc".to_string(), 180),
+            ("c.pbc".to_string(), 90),
+            ("novo.pbc".to_string(), 50),
+        ]);
+        let fontes = [fonte("src/b.pr", "b.pbc", 1)];
+        let c = maiores_crescimentos(&anterior, &atual, &fontes);
+        assert_eq!(c.len(), 2);
+        assert_eq!(c[0].artefato, "b.pbc");
+        assert_eq!(c[0].fonte.as_deref(), Some("src/b.pr"));
+        assert_eq!(c[0].delta, 80);
+        assert_eq!(c[1].artefato, "a.pbc");
+    }
+}
@@ -0,0 +1,237 @@
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{bail, Context, Result};
+
+use crate::git;
+use crate::toolchain::{carregar_configuracao_projeto, localizar_raiz};
+
+const ARQUIVO_CHANGELOG: &str = "CHANGELOG.md";
+const CABECALHO: &str = "# Changelog";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Grupo {
+    Novidades,
+    Correcoes,
+    Outros,
+}
+
+impl Grupo {
+    fn titulo(&self) -> &'static str {
+        match self {
+            Grupo::Novidades => "Novidades",
+            Grupo::Correcoes => "Correcoes",
+            Grupo::Outros => "Outros",
+        }
+    }
+}
+
+/// Opcoes do comando `changelog`.
+pub struct OpcoesChangelog<'a> {
+    pub desde: Option<&'a str>,
+    pub versao: Option<&'a str>,
+    pub dry_run: bool,
+}
+
+pub fn changelog_cmd(caminho: &Path, opcoes: &OpcoesChangelog) -> Result<()> {
+    let raiz = localizar_raiz(caminho);
+    git::localizar().context("O git e necessario para gerar o changelog")?;
+    if !git::eh_repositorio(&raiz) {
+        bail!("{} nao esta em um repositorio git", raiz.display());
+    }
+
+    let base = match opcoes.desde {
+        Some(rev) if git::ref_existe(&raiz, rev) => Some(rev.to_string()),
+        Some(rev) => {
+            eprintln!(
+                "Aviso: referencia '{}' nao encontrada; usando a ultima mudanca de versao do pordosol.proj.",
+                rev
+            );
+            ultimo_bump_de_versao(&raiz)
+        }
+        None => ultimo_bump_de_versao(&raiz),
+    };
+
+    let intervalo = match &base {
+        Some(b) => format!("{}..HEAD", b),
+        None => "HEAD".to_string(),
+    };
+    let log = git::executar(
+        Some(&raiz),
+        ["log", "--no-merges", "--format=%s", intervalo.as_str()],
+    )
+    .context("Falha ao ler o historico git (o repositorio tem commits?)")?;
+    let assuntos: Vec<&str> = log.lines().filter(|l| !l.trim().is_empty()).collect();
+
+    let versao = opcoes
+        .versao
+        .map(str::to_string)
+        .or_else(|| {
+            carregar_configuracao_projeto(&raiz)
+                .and_then(|c| c.get("versao").and_then(|v| v.as_str()).map(str::to_string))
+        })
+        .unwrap_or_else(|| "nao lancado".to_string());
+    let secao = formatar_secao(&versao, &data_hoje(), &assuntos);
+
+    if opcoes.dry_run {
+        print!("{}", secao);
+        return Ok(());
+    }
+
+    let arquivo = raiz.join(ARQUIVO_CHANGELOG);
+    let existente = fs::read_to_string(&arquivo).unwrap_or_default();
+    fs::write(&arquivo, inserir_secao(&existente, &secao))
+        .with_context(|| format!("Falha ao escrever {}", arquivo.display()))?;
+    println!(
+        "{} atualizado com {} commit(s) desde {}.",
+        arquivo.display(),
+        assuntos.len(),
+        base.as_deref().unwrap_or("o inicio do historico")
+    );
+    Ok(())
+}
+
+/// Commit mais recente que alterou o campo `versao` do pordosol.proj.
+fn ultimo_bump_de_versao(raiz: &Path) -> Option<String> {
+    let log = git::executar(
+        Some(raiz),
+        ["log", "--format=%x00%H", "-p", "--", "pordosol.proj"],
+    )
+    .ok()?;
+    log.split('\0').find_map(|bloco| {
+        let mut linhas = bloco.lines();
+        let hash = linhas.next()?.trim();
+        linhas
+            .any(|l| l.starts_with('+') && !l.starts_with("+++") && l.contains("\"versao\""))
+            .then(|| hash.to_string())
+    })
+}
+
+/// Separa `tipo(escopo)!: descricao` em grupo e descricao; assuntos fora do padrao vao para Outros.
+fn classificar(assunto: &str) -> (Grupo, String) {
+    if let Some((prefixo, resto)) = assunto.split_once(':') {
+        let tipo = prefixo
+            .trim_end_matches('!')
+            .split('(')
+            .next()
+            .unwrap_or("")
+            .trim()
+            .to_ascii_lowercase();
+        let convencional = !tipo.is_empty()
+            && tipo.chars().all(|c| c.is_ascii_alphabetic())
+            && !prefixo.contains(' ');
+        if convencional && !resto.trim().is_empty() {
+            let grupo = match tipo.as_str() {
+                "feat" => Grupo::Novidades,
+                "fix" => Grupo::Correcoes,
+                _ => Grupo::Outros,
+            };
+            return (grupo, resto.trim().to_string());
+        }
+    }
+    (Grupo::Outros, assunto.trim().to_string())
+}
+
+fn formatar_secao(versao: &str, data: &str, assuntos: &[&str]) -> String {
+    let mut secao = format!("## [{}] - {}\n", versao, data);
+    if assuntos.is_empty() {
+        secao.push_str("\nNenhuma mudanca registrada.\n");
+    }
+    let classificados: Vec<(Grupo, String)> = assuntos.iter().map(|a| classificar(a)).collect();
+    for grupo in [Grupo::Novidades, Grupo::Correcoes, Grupo::Outros] {
+        let itens: Vec<&String> = classificados
+            .iter()
+            .filter(|(g, _)| *g == grupo)
+            .map(|(_, texto)| texto)
+            .collect();
+        if itens.is_empty() {
+            continue;
+        }
+        secao.push_str(&format!("\n### {}\n\n", grupo.titulo()));
+        for item in itens {
+            secao.push_str(&format!("- {}\n", item));
+        }
+    }
+    secao
+}
+
+/// Insere a secao logo apos o cabecalho `# Changelog` (criando-o se necessario).
+fn inserir_secao(existente: &str, secao: &str) -> String {
+    let corpo = existente
+        .strip_prefix(CABECALHO)
+        .map(|r| r.trim_start_matches(['\r', '\n']))
+        .unwrap_or(existente);
+    if corpo.trim().is_empty() {
+        format!("{}\n\n{}", CABECALHO, secao)
+    } else {
+        format!("{}\n\n{}\n{}", CABECALHO, secao, corpo)
+    }
+}
+
+/// Data UTC atual em `AAAA-MM-DD`.
+fn data_hoje() -> String {
+    let segundos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let (a, m, d) = data_civil((segundos / 86_400) as i64);
+    format!("{:04}-{:02}-{:02}", a, m, d)
+}
+
+/// Converte dias desde 1970-01-01 em (ano, mes, dia) no calendario gregoriano.
+pub(crate) fn data_civil(dias: i64) -> (i64, u32, u32) {
+    let z = dias + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let dia = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let mes = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let ano = yoe + era * 400 + i64::from(mes <= 2);
+    (ano, mes, dia)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifica_prefixos_convencionais() {
+        assert_eq!(
+            classificar("feat(cli): novo comando"),
+            (Grupo::Novidades, "novo comando".to_string())
+        );
+        assert_eq!(
+            classificar("fix!: corrige saida"),
+            (Grupo::Correcoes, "corrige saida".to_string())
+        );
+        assert_eq!(
+            classificar("docs: readme"),
+            (Grupo::Outros, "readme".to_string())
+        );
+        assert_eq!(
+            classificar("Ajusta algo: detalhe"),
+            (Grupo::Outros, "Ajusta algo: detalhe".to_string())
+        );
+    }
+
+    #[test]
+    fn data_civil_conhecida() {
+        assert_eq!(data_civil(0), (1970, 1, 1));
+        assert_eq!(data_civil(11_016), (2000, 2, 29));
+        assert_eq!(data_civil(20_376), (2025, 10, 15));
+    }
+
+    #[test]
+    fn secao_entra_apos_cabecalho() {
+        let novo = inserir_secao("", "## [1.0.0] - 2025-01-01\n");
+        assert_eq!(novo, "# Changelog\n\n## [1.0.0] - 2025-01-01\n");
+        let depois = inserir_secao(&novo, "## [1.1.0] - 2025-02-01\n");
+        assert_eq!(
+            depois,
+            "# Changelog\n\n## [1.1.0] - 2025-02-01\n\n## [1.0.0] - 2025-01-01\n"
+        );
+    }
+}
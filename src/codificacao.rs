@@ -0,0 +1,114 @@
+/// Codificacao usada para decodificar a saida capturada de processos filhos.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum Codificacao {
+    /// UTF-8 quando valido, senao Windows-1252
+    #[default]
+    Auto,
+    #[value(name = "utf-8", alias = "utf8")]
+    Utf8,
+    /// Windows-1252 (ANSI do Windows ocidental)
+    #[value(name = "cp1252", alias = "windows-1252")]
+    Cp1252,
+    /// CP850 (OEM do console Windows em pt-BR)
+    #[value(name = "cp850", alias = "ibm850")]
+    Cp850,
+}
+
+/// Decodifica bytes capturados para UTF-8.
+///
+/// Bytes invalidos viram U+FFFD ("substituir caracteres invalidos") em vez de falhar.
+pub fn decodificar(bytes: &[u8], codificacao: Codificacao) -> String {
+    match codificacao {
+        Codificacao::Auto => match std::str::from_utf8(bytes) {
+            Ok(s) => s.to_string(),
+            Err(_) => decodificar_tabela(bytes, &CP1252_ALTO),
+        },
+        Codificacao::Utf8 => String::from_utf8_lossy(bytes).to_string(),
+        Codificacao::Cp1252 => decodificar_tabela(bytes, &CP1252_ALTO),
+        Codificacao::Cp850 => decodificar_tabela(bytes, &CP850_ALTO),
+    }
+}
+
+fn decodificar_tabela(bytes: &[u8], alto: &[char; 128]) -> String {
+    bytes
+        .iter()
+        .map(|&b| {
+            if b < 0x80 {
+                b as char
+            } else {
+                alto[(b - 0x80) as usize]
+            }
+        })
+        .collect()
+}
+
+const INV: char = '\u{FFFD}';
+
+/// Bytes 0x80..=0xFF da Windows-1252; 0xA0..=0xFF coincidem com Latin-1.
+const CP1252_ALTO: [char; 128] = {
+    let mut t = [INV; 128];
+    let controle = [
+        '\u{20AC}', INV, '\u{201A}', '\u{0192}', '\u{201E}', '\u{2026}', '\u{2020}', '\u{2021}',
+        '\u{02C6}', '\u{2030}', '\u{0160}', '\u{2039}', '\u{0152}', INV, '\u{017D}', INV, INV,
+        '\u{2018}', '\u{2019}', '\u{201C}', '\u{201D}', '\u{2022}', '\u{2013}', '\u{2014}',
+        '\u{02DC}', '\u{2122}', '\u{0161}', '\u{203A}', '\u{0153}', INV, '\u{017E}', '\u{0178}',
+    ];
+    let mut i = 0;
+    while i < 32 {
+        t[i] = controle[i];
+        i += 1;
+    }
+    while i < 128 {
+        t[i] = match char::from_u32(0x80 + i as u32) {
+            Some(c) => c,
+            None => INV,
+        };
+        i += 1;
+    }
+    t
+};
+
+const CP850_ALTO: [char; 128] = [
+    'Ç', 'ü', 'é', 'â', 'ä', 'à', 'å', 'ç', 'ê', 'ë', 'è', 'ï', 'î', 'ì', 'Ä', 'Å', //
+    'É', 'æ', 'Æ', 'ô', 'ö', 'ò', 'û', 'ù', 'ÿ', 'Ö', 'Ü', 'ø', '£', 'Ø', '×', 'ƒ', //
+    'á', 'í', 'ó', 'ú', 'ñ', 'Ñ', 'ª', 'º', '¿', '®', '¬', '½', '¼', '¡', '«', '»', //
+    '░', '▒', '▓', '│', '┤', 'Á', 'Â', 'À', '©', '╣', '║', '╗', '╝', '¢', '¥', '┐', //
+    '└', '┴', '┬', '├', '─', '┼', 'ã', 'Ã', '╚', '╔', '╩', '╦', '╠', '═', '╬', '¤', //
+    'ð', 'Ð', 'Ê', 'Ë', 'È', 'ı', 'Í', 'Î', 'Ï', '┘', '┌', '█', '▄', '¦', 'Ì', '▀', //
+    'Ó', 'ß', 'Ô', 'Ò', 'õ', 'Õ', 'µ', 'þ', 'Þ', 'Ú', 'Û', 'Ù', 'ý', 'Ý', '¯', '´', //
+    '\u{00AD}', '±', '‗', '¾', '¶', '§', '÷', '¸', '°', '¨', '·', '¹', '³', '²', '■', '\u{00A0}',
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // "Ação é ótima €" em Windows-1252
+    const CP1252: &[u8] = b"A\xe7\xe3o \xe9 \xf3tima \x80";
+    // "Ação é ótima" em CP850
+    const CP850: &[u8] = b"A\x87\xc6o \x82 \xa2tima";
+
+    #[test]
+    fn utf8_valido_passa_direto() {
+        let bytes = "Ação é ótima €".as_bytes();
+        assert_eq!(decodificar(bytes, Codificacao::Auto), "Ação é ótima €");
+        assert_eq!(decodificar(bytes, Codificacao::Utf8), "Ação é ótima €");
+    }
+
+    #[test]
+    fn auto_cai_para_cp1252() {
+        assert_eq!(decodificar(CP1252, Codificacao::Auto), "Ação é ótima €");
+        assert_eq!(decodificar(CP1252, Codificacao::Cp1252), "Ação é ótima €");
+    }
+
+    #[test]
+    fn cp850_forcado() {
+        assert_eq!(decodificar(CP850, Codificacao::Cp850), "Ação é ótima");
+    }
+
+    #[test]
+    fn bytes_invalidos_sao_substituidos() {
+        assert_eq!(decodificar(b"a\x81b", Codificacao::Cp1252), "a\u{FFFD}b");
+        assert!(decodificar(CP1252, Codificacao::Utf8).contains('\u{FFFD}'));
+    }
+}
@@ -0,0 +1,248 @@
+use anyhow::Result;
+use serde::Serialize;
+
+/// Registro de um subcomando: nome principal, aliases e descricao curta.
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct ComandoInfo {
+    pub nome: &'static str,
+    /// Aliases aceitos mas omitidos da ajuda
+    pub aliases: &'static [&'static str],
+    /// Aliases exibidos na ajuda
+    pub aliases_visiveis: &'static [&'static str],
+    pub descricao_pt: &'static str,
+    pub descricao_en: &'static str,
+}
+
+pub const NEW: ComandoInfo = ComandoInfo {
+    nome: "new",
+    aliases: &["novo"],
+    aliases_visiveis: &["Novo", "criar", "Criar"],
+    descricao_pt:
+        "Cria um projeto (estilo dotnet new), mantendo compatibilidade com `novo <caminho>`",
+    descricao_en: "Creates a project (dotnet new style), keeping `novo <path>` compatibility",
+};
+
+pub const BUILD: ComandoInfo = ComandoInfo {
+    nome: "build",
+    aliases: &["compilar"],
+    aliases_visiveis: &["Build", "Compilar"],
+    descricao_pt: "Compila arquivos .pr para bytecode (.pbc) por padrao",
+    descricao_en: "Compiles .pr files to bytecode (.pbc) by default",
+};
+
+pub const RUN: ComandoInfo = ComandoInfo {
+    nome: "run",
+    aliases: &["rodar"],
+    aliases_visiveis: &["Rodar"],
+    descricao_pt: "Compila e executa o programa (equivalente a dotnet run)",
+    descricao_en: "Builds and runs the program (like dotnet run)",
+};
+
+pub const CHECK: ComandoInfo = ComandoInfo {
+    nome: "check",
+    aliases: &["verificar"],
+    aliases_visiveis: &["Check", "Verificar"],
+    descricao_pt: "Verifica os arquivos .pr com o compilador sem gerar artefatos",
+    descricao_en: "Checks .pr files with the compiler without emitting artifacts",
+};
+
+pub const PRODUCAO: ComandoInfo = ComandoInfo {
+    nome: "producao",
+    aliases: &["release"],
+    aliases_visiveis: &["Release", "Producao"],
+    descricao_pt: "E o compilar com o perfil de producao preselecionado (--perfil producao: target llvm-ir, saida <pasta_saida>/producao); aceita as mesmas flags",
+    descricao_en: "The build command with the production profile preselected (--perfil producao: llvm-ir target, <output>/producao); takes the same flags",
+};
+
+pub const CLEAN: ComandoInfo = ComandoInfo {
+    nome: "clean",
+    aliases: &["limpar"],
+    aliases_visiveis: &["Limpar"],
+    descricao_pt: "Limpa os artefatos de build (pasta build/)",
+    descricao_en: "Removes build artifacts (build/ folder)",
+};
+
+pub const INFO: ComandoInfo = ComandoInfo {
+    nome: "info",
+    aliases: &[],
+    aliases_visiveis: &["Info"],
+    descricao_pt: "Mostra informacoes sobre o projeto",
+    descricao_en: "Shows project information",
+};
+
+pub const DOCTOR: ComandoInfo = ComandoInfo {
+    nome: "doctor",
+    aliases: &["diagnostico"],
+    aliases_visiveis: &["Doctor", "Diagnostico"],
+    descricao_pt: "Diagnostica toolchain global (compilador, interpretador e stdlib)",
+    descricao_en: "Diagnoses the global toolchain (compiler, interpreter and stdlib)",
+};
+
+pub const ENV: ComandoInfo = ComandoInfo {
+    nome: "env",
+    aliases: &[],
+    aliases_visiveis: &["Env"],
+    descricao_pt: "Imprime as variaveis da toolchain resolvida para `eval \"$(pordosol env)\"` (`--shell powershell`)",
+    descricao_en: "Prints the resolved toolchain variables for `eval \"$(pordosol env)\"` (`--shell powershell`)",
+};
+
+pub const LISTAR: ComandoInfo = ComandoInfo {
+    nome: "listar",
+    aliases: &[],
+    aliases_visiveis: &["Listar"],
+    descricao_pt: "Lista os arquivos .pr do projeto",
+    descricao_en: "Lists the project's .pr files",
+};
+
+pub const DEP: ComandoInfo = ComandoInfo {
+    nome: "dep",
+    aliases: &[],
+    aliases_visiveis: &["Dep"],
+    descricao_pt: "Gerencia dependencias do projeto (add, remove, list, resolve, tree, install)",
+    descricao_en: "Manages project dependencies (add, remove, list, resolve, tree, install)",
+};
+
+pub const ANALISAR: ComandoInfo = ComandoInfo {
+    nome: "analisar",
+    aliases: &["analyze"],
+    aliases_visiveis: &["Analisar"],
+    descricao_pt: "Analisa os artefatos do build (ex.: `analisar tamanho`)",
+    descricao_en: "Analyzes build artifacts (e.g. `analisar tamanho`)",
+};
+
+pub const TOOLCHAIN: ComandoInfo = ComandoInfo {
+    nome: "toolchain",
+    aliases: &[],
+    aliases_visiveis: &["Toolchain"],
+    descricao_pt: "Consulta a toolchain resolvida (which) para uso em scripts",
+    descricao_en: "Queries the resolved toolchain (which) for use in scripts",
+};
+
+pub const MANUTENCAO: ComandoInfo = ComandoInfo {
+    nome: "manutencao",
+    aliases: &["limpar-logs"],
+    aliases_visiveis: &["Manutencao"],
+    descricao_pt: "Mostra tamanho e idade dos arquivos auxiliares e aplica a retencao (--aplicar)",
+    descricao_en: "Reports size and age of sidecar files and applies retention (--aplicar)",
+};
+
+pub const COMANDOS: ComandoInfo = ComandoInfo {
+    nome: "comandos",
+    aliases: &["commands"],
+    aliases_visiveis: &["Comandos"],
+    descricao_pt: "Lista os comandos registrados, seus aliases e descricoes",
+    descricao_en: "Lists registered commands, their aliases and descriptions",
+};
+
+pub const STATS: ComandoInfo = ComandoInfo {
+    nome: "stats",
+    aliases: &["estatisticas"],
+    aliases_visiveis: &["Stats", "Estatisticas"],
+    descricao_pt: "Mostra estatisticas de codigo dos arquivos .pr do projeto",
+    descricao_en: "Shows code statistics for the project's .pr files",
+};
+
+pub const RENAME: ComandoInfo = ComandoInfo {
+    nome: "rename",
+    aliases: &["renomear"],
+    aliases_visiveis: &["Rename", "Renomear"],
+    descricao_pt: "Renomeia o projeto (nome no pordosol.proj e namespace)",
+    descricao_en: "Renames the project (pordosol.proj name and namespace)",
+};
+
+pub const SCRIPT: ComandoInfo = ComandoInfo {
+    nome: "script",
+    aliases: &["rodar-arquivo"],
+    aliases_visiveis: &["Script"],
+    descricao_pt: "Compila e executa um unico arquivo .pr fora de um projeto",
+    descricao_en: "Builds and runs a single .pr file outside of a project",
+};
+
+pub const TEST: ComandoInfo = ComandoInfo {
+    nome: "test",
+    aliases: &["testar"],
+    aliases_visiveis: &["Test", "Teste"],
+    descricao_pt: "Executa os testes em testes/*.pr (com .entrada e .saida opcionais)",
+    descricao_en: "Runs the tests in testes/*.pr (with optional .entrada and .saida files)",
+};
+
+pub const CHANGELOG: ComandoInfo = ComandoInfo {
+    nome: "changelog",
+    aliases: &[],
+    aliases_visiveis: &["Changelog"],
+    descricao_pt: "Gera uma secao do CHANGELOG.md a partir dos commits git",
+    descricao_en: "Generates a CHANGELOG.md section from git commits",
+};
+
+pub const CONFIG: ComandoInfo = ComandoInfo {
+    nome: "config",
+    aliases: &["configuracao"],
+    aliases_visiveis: &["Config"],
+    descricao_pt: "Mostra o valor efetivo de uma configuracao e de qual camada ele veio",
+    descricao_en: "Shows a setting's effective value and which layer it came from",
+};
+
+pub const VERSAO: ComandoInfo = ComandoInfo {
+    nome: "versao",
+    aliases: &["version"],
+    aliases_visiveis: &["Versao"],
+    descricao_pt: "Mostra a versao da CLI e das ferramentas (`--all` inclui a stdlib e as origens)",
+    descricao_en: "Shows the CLI and tool versions (`--all` adds the stdlib and origins)",
+};
+
+pub const EXPLAIN: ComandoInfo = ComandoInfo {
+    nome: "explain",
+    aliases: &["explicar"],
+    aliases_visiveis: &["Explain", "Explicar"],
+    descricao_pt: "Explica um codigo de erro do compilador (sem codigo, lista os conhecidos)",
+    descricao_en: "Explains a compiler error code (without a code, lists the known ones)",
+};
+
+pub const TEMPLATES: ComandoInfo = ComandoInfo {
+    nome: "templates",
+    aliases: &["template"],
+    aliases_visiveis: &["Templates"],
+    descricao_pt:
+        "Instala templates do usuario em PORDOSOL_HOME/templates (`templates add <caminho>`)",
+    descricao_en: "Installs user templates into PORDOSOL_HOME/templates (`templates add <path>`)",
+};
+
+/// Tabela completa usada para ajuda, documentacao e validacao dos aliases.
+pub const REGISTRO: &[ComandoInfo] = &[
+    NEW, TEMPLATES, BUILD, CHECK, RUN, SCRIPT, TEST, PRODUCAO, CLEAN, INFO, DOCTOR, ENV, LISTAR,
+    DEP, STATS, ANALISAR, TOOLCHAIN, RENAME, CHANGELOG, CONFIG, VERSAO, MANUTENCAO, EXPLAIN,
+    COMANDOS,
+];
+
+pub fn comandos_cmd(json: bool) -> Result<()> {
+    if json {
+        println!("{}", serde_json::to_string_pretty(REGISTRO)?);
+        return Ok(());
+    }
+
+    let largura = REGISTRO.iter().map(|c| c.nome.len()).max().unwrap_or(0);
+    println!("Comandos disponiveis:");
+    for c in REGISTRO {
+        let aliases: Vec<&str> = c
+            .aliases
+            .iter()
+            .chain(c.aliases_visiveis.iter())
+            .copied()
+            .collect();
+        println!(
+            "  {:<largura$}  {}",
+            c.nome,
+            c.descricao_pt,
+            largura = largura
+        );
+        if !aliases.is_empty() {
+            println!(
+                "  {:<largura$}  aliases: {}",
+                "",
+                aliases.join(", "),
+                largura = largura
+            );
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,462 @@
+//! Captura e comparacao de saidas de programas: base dos testes golden (`pordosol test`)
+//! e de `pordosol run --comparar-com`.
+
+use std::io::Write;
+use std::process::{Command, ExitStatus, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Context, Result};
+
+/// Linhas de contexto em volta de cada trecho do diff.
+const CONTEXTO_DIFF: usize = 3;
+
+/// Acima deste numero de celulas (linhas de um lado x do outro) o diff nao procura a maior
+/// subsequencia comum e marca o miolo inteiro como trocado.
+const LIMITE_CELULAS_DIFF: usize = 4_000_000;
+
+/// Texto que substitui os trechos mascarados por `--ignorar-regex`.
+pub const MASCARA: &str = "<ignorado>";
+
+/// Resultado de um programa executado com a saida capturada.
+#[derive(Debug)]
+pub struct Captura {
+    pub status: ExitStatus,
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+    pub duracao: Duration,
+}
+
+/// Executa `cmd` capturando stdout e stderr; `entrada` vai para o stdin (sem ela, stdin vazio).
+pub fn capturar(mut cmd: Command, entrada: Option<&[u8]>) -> Result<Captura> {
+    cmd.stdin(if entrada.is_some() {
+        Stdio::piped()
+    } else {
+        Stdio::null()
+    })
+    .stdout(Stdio::piped())
+    .stderr(Stdio::piped());
+    let inicio = Instant::now();
+    let mut filho = cmd.spawn().context("Falha ao executar o interpretador")?;
+    // Escrita em thread separada: o programa pode encher o pipe de saida antes de ler o stdin
+    let escritor = match (entrada, filho.stdin.take()) {
+        (Some(bytes), Some(mut stdin)) => {
+            let bytes = bytes.to_vec();
+            Some(thread::spawn(move || stdin.write_all(&bytes)))
+        }
+        _ => None,
+    };
+    let out = filho
+        .wait_with_output()
+        .context("Falha ao executar o interpretador")?;
+    if let Some(escritor) = escritor {
+        // Programas que nao leem o stdin fecham o pipe; isso nao e erro
+        escritor.join().ok();
+    }
+    Ok(Captura {
+        status: out.status,
+        stdout: out.stdout,
+        stderr: out.stderr,
+        duracao: inicio.elapsed(),
+    })
+}
+
+/// Ignora diferencas de fim de linha e espacos no final.
+pub fn normalizar_saida(texto: &str) -> String {
+    texto
+        .lines()
+        .map(str::trim_end)
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim_end()
+        .to_string()
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum ItemClasse {
+    Intervalo(char, char),
+    Digito,
+    Palavra,
+    Espaco,
+}
+
+impl ItemClasse {
+    fn aceita(&self, c: char) -> bool {
+        match self {
+            ItemClasse::Intervalo(a, b) => (*a..=*b).contains(&c),
+            ItemClasse::Digito => c.is_ascii_digit(),
+            ItemClasse::Palavra => c.is_alphanumeric() || c == '_',
+            ItemClasse::Espaco => c.is_whitespace(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Atomo {
+    Qualquer,
+    Classe {
+        itens: Vec<ItemClasse>,
+        negada: bool,
+    },
+}
+
+impl Atomo {
+    fn literal(c: char) -> Self {
+        Atomo::Classe {
+            itens: vec![ItemClasse::Intervalo(c, c)],
+            negada: false,
+        }
+    }
+
+    fn aceita(&self, c: char) -> bool {
+        match self {
+            Atomo::Qualquer => true,
+            Atomo::Classe { itens, negada } => itens.iter().any(|i| i.aceita(c)) != *negada,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Termo {
+    atomo: Atomo,
+    min: usize,
+    max: usize,
+}
+
+/// Expressao regular simples para mascarar trechos de uma linha.
+///
+/// Suporta literais, `.`, classes `[a-z]`/`[^...]`, `\d \w \s` (e `\D \W \S`), os
+/// quantificadores `* + ?` e as ancoras `^`/`$`. Grupos, `|` e `{n,m}` sao rejeitados.
+#[derive(Clone, Debug)]
+pub struct Padrao {
+    termos: Vec<Termo>,
+    inicio: bool,
+    fim: bool,
+}
+
+fn escape(c: char) -> Atomo {
+    let classe = |item, negada| Atomo::Classe {
+        itens: vec![item],
+        negada,
+    };
+    match c {
+        'd' => classe(ItemClasse::Digito, false),
+        'D' => classe(ItemClasse::Digito, true),
+        'w' => classe(ItemClasse::Palavra, false),
+        'W' => classe(ItemClasse::Palavra, true),
+        's' => classe(ItemClasse::Espaco, false),
+        'S' => classe(ItemClasse::Espaco, true),
+        't' => Atomo::literal('\t'),
+        outro => Atomo::literal(outro),
+    }
+}
+
+impl Padrao {
+    pub fn compilar(fonte: &str) -> Result<Self> {
+        let mut chars = fonte.chars().peekable();
+        let mut padrao = Padrao {
+            termos: Vec::new(),
+            inicio: chars.next_if_eq(&'^').is_some(),
+            fim: false,
+        };
+        while let Some(c) = chars.next() {
+            let atomo = match c {
+                '$' if chars.peek().is_none() => {
+                    padrao.fim = true;
+                    break;
+                }
+                '.' => Atomo::Qualquer,
+                '\\' => match chars.next() {
+                    Some(e) => escape(e),
+                    None => bail!("padrao '{}' termina com `\\`", fonte),
+                },
+                '[' => {
+                    let negada = chars.next_if_eq(&'^').is_some();
+                    let mut itens = Vec::new();
+                    let mut primeiro = true;
+                    loop {
+                        let Some(c) = chars.next() else {
+                            bail!("padrao '{}': `[` sem `]`", fonte);
+                        };
+                        if c == ']' && !primeiro {
+                            break;
+                        }
+                        primeiro = false;
+                        let item = if c == '\\' {
+                            match chars.next().map(escape) {
+                                Some(Atomo::Classe { mut itens, negada: false })
+                                    if itens.len() == 1 =>
+                                {
+                                    itens.remove(0)
+                                }
+                                _ => bail!(
+                                    "padrao '{}': escape negado ou incompleto dentro de `[]`",
+                                    fonte
+                                ),
+                            }
+                        } else if chars.peek() == Some(&'-') {
+                            chars.next();
+                            match chars.next() {
+                                Some(']') => {
+                                    itens.push(ItemClasse::Intervalo(c, c));
+                                    itens.push(ItemClasse::Intervalo('-', '-'));
+                                    break;
+                                }
+                                Some(fim) if fim >= c => ItemClasse::Intervalo(c, fim),
+                                _ => bail!("padrao '{}': intervalo invalido em `[]`", fonte),
+                            }
+                        } else {
+                            ItemClasse::Intervalo(c, c)
+                        };
+                        itens.push(item);
+                    }
+                    Atomo::Classe { itens, negada }
+                }
+                '*' | '+' | '?' => bail!("padrao '{}': `{}` sem nada antes", fonte, c),
+                '(' | ')' | '|' | '{' | '}' => bail!(
+                    "padrao '{}': `{}` nao e suportado (sem grupos, alternativas ou {{n,m}}); use `\\{}` para o caractere",
+                    fonte,
+                    c,
+                    c
+                ),
+                literal => Atomo::literal(literal),
+            };
+            let (min, max) = match chars.next_if(|c| matches!(c, '*' | '+' | '?')) {
+                Some('*') => (0, usize::MAX),
+                Some('+') => (1, usize::MAX),
+                Some('?') => (0, 1),
+                _ => (1, 1),
+            };
+            padrao.termos.push(Termo { atomo, min, max });
+        }
+        Ok(padrao)
+    }
+
+    /// Fim do casamento mais longo (guloso, com retrocesso) comecando em `i`.
+    fn casar(&self, termos: &[Termo], s: &[char], i: usize) -> Option<usize> {
+        let Some((termo, resto)) = termos.split_first() else {
+            return (!self.fim || i == s.len()).then_some(i);
+        };
+        let mut n = 0;
+        while n < termo.max && i + n < s.len() && termo.atomo.aceita(s[i + n]) {
+            n += 1;
+        }
+        while n >= termo.min {
+            if let Some(fim) = self.casar(resto, s, i + n) {
+                return Some(fim);
+            }
+            if n == 0 {
+                break;
+            }
+            n -= 1;
+        }
+        None
+    }
+
+    /// Troca cada casamento em `linha` por `por`.
+    pub fn substituir(&self, linha: &str, por: &str) -> String {
+        let s: Vec<char> = linha.chars().collect();
+        let mut resultado = String::with_capacity(linha.len());
+        let mut i = 0;
+        while i <= s.len() {
+            let casou = if self.inicio && i > 0 {
+                None
+            } else {
+                self.casar(&self.termos, &s, i)
+            };
+            match casou {
+                Some(fim) if fim > i => {
+                    resultado.push_str(por);
+                    i = fim;
+                }
+                _ => {
+                    if let Some(c) = s.get(i) {
+                        resultado.push(*c);
+                    }
+                    i += 1;
+                }
+            }
+        }
+        resultado
+    }
+}
+
+/// Aplica os padroes linha a linha, na ordem dada.
+pub fn mascarar(texto: &str, padroes: &[Padrao]) -> String {
+    if padroes.is_empty() {
+        return texto.to_string();
+    }
+    texto
+        .lines()
+        .map(|l| {
+            padroes
+                .iter()
+                .fold(l.to_string(), |l, p| p.substituir(&l, MASCARA))
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Op {
+    Igual,
+    Remove,
+    Adiciona,
+}
+
+/// Sequencia de operacoes que transforma `a` em `b` pela maior subsequencia comum.
+fn operacoes(a: &[&str], b: &[&str]) -> Vec<Op> {
+    let prefixo = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let sufixo = a[prefixo..]
+        .iter()
+        .rev()
+        .zip(b[prefixo..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let (meio_a, meio_b) = (&a[prefixo..a.len() - sufixo], &b[prefixo..b.len() - sufixo]);
+
+    let mut ops = vec![Op::Igual; prefixo];
+    let (n, m) = (meio_a.len(), meio_b.len());
+    if n.saturating_mul(m) > LIMITE_CELULAS_DIFF {
+        ops.extend(std::iter::repeat_n(Op::Remove, n));
+        ops.extend(std::iter::repeat_n(Op::Adiciona, m));
+    } else {
+        // lcs[i][j]: maior subsequencia comum de meio_a[i..] e meio_b[j..]
+        let mut lcs = vec![vec![0u32; m + 1]; n + 1];
+        for i in (0..n).rev() {
+            for j in (0..m).rev() {
+                lcs[i][j] = if meio_a[i] == meio_b[j] {
+                    lcs[i + 1][j + 1] + 1
+                } else {
+                    lcs[i + 1][j].max(lcs[i][j + 1])
+                };
+            }
+        }
+        let (mut i, mut j) = (0, 0);
+        while i < n || j < m {
+            if i < n && j < m && meio_a[i] == meio_b[j] {
+                ops.push(Op::Igual);
+                i += 1;
+                j += 1;
+            } else if i < n && (j == m || lcs[i + 1][j] >= lcs[i][j + 1]) {
+                ops.push(Op::Remove);
+                i += 1;
+            } else {
+                ops.push(Op::Adiciona);
+                j += 1;
+            }
+        }
+    }
+    ops.extend(std::iter::repeat_n(Op::Igual, sufixo));
+    ops
+}
+
+/// Diff unificado linha a linha de `a` para `b`; `None` quando os textos sao iguais.
+pub fn diff_unificado(a: &str, b: &str, rotulo_a: &str, rotulo_b: &str) -> Option<String> {
+    let linhas_a: Vec<&str> = a.lines().collect();
+    let linhas_b: Vec<&str> = b.lines().collect();
+    let ops = operacoes(&linhas_a, &linhas_b);
+    let mudancas: Vec<usize> = (0..ops.len()).filter(|&k| ops[k] != Op::Igual).collect();
+    if mudancas.is_empty() {
+        return None;
+    }
+
+    // Trechos [ini, fim) de `ops`, unindo mudancas cujo contexto se sobrepoe
+    let mut trechos: Vec<(usize, usize)> = Vec::new();
+    for &k in &mudancas {
+        let ini = k.saturating_sub(CONTEXTO_DIFF);
+        let fim = (k + 1 + CONTEXTO_DIFF).min(ops.len());
+        match trechos.last_mut() {
+            Some(ultimo) if ini <= ultimo.1 => ultimo.1 = fim,
+            _ => trechos.push((ini, fim)),
+        }
+    }
+
+    let mut saida = format!("--- {}\n+++ {}\n", rotulo_a, rotulo_b);
+    let (mut ia, mut ib, mut k) = (0usize, 0usize, 0usize);
+    for (ini, fim) in trechos {
+        while k < ini {
+            match ops[k] {
+                Op::Igual => {
+                    ia += 1;
+                    ib += 1;
+                }
+                Op::Remove => ia += 1,
+                Op::Adiciona => ib += 1,
+            }
+            k += 1;
+        }
+        let conta = |alvo: Op| ops[ini..fim].iter().filter(|o| **o != alvo).count();
+        let (len_a, len_b) = (conta(Op::Adiciona), conta(Op::Remove));
+        saida.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            ia + usize::from(len_a > 0),
+            len_a,
+            ib + usize::from(len_b > 0),
+            len_b
+        ));
+        while k < fim {
+            match ops[k] {
+                Op::Igual => {
+                    saida.push_str(&format!(" {}\n", linhas_a[ia]));
+                    ia += 1;
+                    ib += 1;
+                }
+                Op::Remove => {
+                    saida.push_str(&format!("-{}\n", linhas_a[ia]));
+                    ia += 1;
+                }
+                Op::Adiciona => {
+                    saida.push_str(&format!("+{}\n", linhas_b[ib]));
+                    ib += 1;
+                }
+            }
+            k += 1;
+        }
+    }
+    Some(saida)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn padrao_mascara_trechos_variaveis() {
+        let hora = Padrao::compilar(r"\d\d:\d\d(:\d\d)?").unwrap_err();
+        assert!(hora.to_string().contains("nao e suportado"), "{}", hora);
+
+        let hora = Padrao::compilar(r"\d+:\d+").unwrap();
+        assert_eq!(
+            hora.substituir("inicio 12:30, fim 9:05.", MASCARA),
+            "inicio <ignorado>, fim <ignorado>."
+        );
+        let id = Padrao::compilar("id=[0-9a-f-]+").unwrap();
+        assert_eq!(id.substituir("id=3f2a-91 ok", "X"), "X ok");
+        let ancorado = Padrao::compilar("^t.*s$").unwrap();
+        assert_eq!(ancorado.substituir("tempo: 12ms", "X"), "X");
+        assert_eq!(ancorado.substituir("o tempo: 12ms", "X"), "o tempo: 12ms");
+        let opcional = Padrao::compilar(r"colou?r\.").unwrap();
+        assert_eq!(opcional.substituir("color. colour.", "X"), "X X");
+        assert!(Padrao::compilar("[abc").is_err());
+        assert!(Padrao::compilar("*x").is_err());
+    }
+
+    #[test]
+    fn diff_unificado_agrupa_trechos_com_contexto() {
+        assert_eq!(diff_unificado("a\nb", "a\nb", "x", "y"), None);
+
+        let a = "1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n11\n12";
+        let b = "1\n2\n3\n4\nquatro e meio\n5\n6\n7\n8\n9\n10\n11\ndoze";
+        let diff = diff_unificado(a, b, "antigo", "atual").unwrap();
+        assert_eq!(
+            diff,
+            "--- antigo\n+++ atual\n\
+             @@ -2,6 +2,7 @@\n 2\n 3\n 4\n+quatro e meio\n 5\n 6\n 7\n\
+             @@ -9,4 +10,4 @@\n 9\n 10\n 11\n-12\n+doze\n"
+        );
+
+        let diff = diff_unificado("", "novo", "a", "b").unwrap();
+        assert_eq!(diff, "--- a\n+++ b\n@@ -0,0 +1,1 @@\n+novo\n");
+    }
+}
@@ -0,0 +1,379 @@
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use anyhow::{bail, Result};
+use path_absolutize::Absolutize;
+use serde::Serialize;
+
+use crate::erros::ErroCli;
+use crate::toolchain::{carregar_configuracao_base, localizar_raiz, Ferramenta};
+
+/// Chaves aceitas por `config origem`, na ordem de `--todas`.
+pub const CHAVES: &[&str] = &[
+    "target",
+    "saida",
+    "otimizacao",
+    "compilador",
+    "interpretador",
+    "stdlib",
+];
+
+/// Uma camada consultada na resolucao de uma chave; `valor` e `None` quando ela nao define nada.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Camada {
+    pub fonte: String,
+    pub valor: Option<String>,
+}
+
+/// Valor de configuracao com todas as camadas consultadas, da maior para a menor precedencia.
+///
+/// A primeira camada com valor vence; as demais ficam registradas para `config origem`.
+#[derive(Clone, Debug, Serialize)]
+pub struct ValorRastreado {
+    pub chave: &'static str,
+    pub camadas: Vec<Camada>,
+}
+
+impl ValorRastreado {
+    fn novo(chave: &'static str) -> Self {
+        Self {
+            chave,
+            camadas: Vec::new(),
+        }
+    }
+
+    fn camada(mut self, fonte: impl Into<String>, valor: Option<String>) -> Self {
+        self.camadas.push(Camada {
+            fonte: fonte.into(),
+            valor,
+        });
+        self
+    }
+
+    /// Indice da camada vencedora em `camadas`.
+    pub fn vencedora(&self) -> Option<usize> {
+        self.camadas.iter().position(|c| c.valor.is_some())
+    }
+
+    pub fn valor(&self) -> Option<&str> {
+        self.vencedora()
+            .and_then(|i| self.camadas[i].valor.as_deref())
+    }
+}
+
+fn valor_campo(config: &serde_json::Value, campo: &str) -> Option<String> {
+    match config.get("configuracao")?.get(campo)? {
+        serde_json::Value::String(s) if !s.trim().is_empty() => Some(s.trim().to_string()),
+        serde_json::Value::Bool(b) => Some(b.to_string()),
+        serde_json::Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+/// Arquivos do projeto que podem definir um campo, do ambiente ativo para a base.
+fn arquivos_projeto(raiz: &Path) -> Vec<(String, Option<serde_json::Value>)> {
+    let mut arquivos = Vec::new();
+    if let Some(arquivo) = crate::ambiente::arquivo_ambiente(raiz) {
+        let camada = crate::ambiente::carregar_ambiente(raiz).map(|(_, v)| v);
+        arquivos.push((crate::ambiente::nome_arquivo(&arquivo), camada));
+    }
+    arquivos.push((
+        "pordosol.proj".to_string(),
+        carregar_configuracao_base(raiz),
+    ));
+    arquivos
+}
+
+impl ValorRastreado {
+    /// Uma camada `<arquivo> configuracao.<campo>` por arquivo do projeto.
+    fn camadas_projeto(
+        self,
+        arquivos: &[(String, Option<serde_json::Value>)],
+        campo: &str,
+    ) -> Self {
+        arquivos.iter().fold(self, |v, (nome, config)| {
+            v.camada(
+                format!("{} configuracao.{}", nome, campo),
+                config.as_ref().and_then(|c| valor_campo(c, campo)),
+            )
+        })
+    }
+}
+
+/// Target de `build`: `--target`, `configuracao.target_padrao`, `bytecode`.
+pub fn resolver_target(raiz: &Path, flag: Option<&str>) -> ValorRastreado {
+    ValorRastreado::novo("target")
+        .camada("flag --target", flag.map(str::to_string))
+        .camadas_projeto(&arquivos_projeto(raiz), "target_padrao")
+        .camada("padrao", Some("bytecode".to_string()))
+}
+
+/// `--target-dir`: pasta de saida desta invocacao, ja absoluta (relativa ao cwd).
+static TARGET_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+pub fn definir_target_dir(pasta: Option<&Path>) {
+    if let Some(abs) = pasta.and_then(|p| p.absolutize().ok()) {
+        TARGET_DIR.set(abs.to_path_buf()).ok();
+    }
+}
+
+/// `PORDOSOL_TARGET_DIR`, absoluta: como `--target-dir`, relativa ao cwd e nao a raiz.
+fn target_dir_env() -> Option<String> {
+    let valor = std::env::var("PORDOSOL_TARGET_DIR").ok()?;
+    let valor = valor.trim();
+    if valor.is_empty() {
+        return None;
+    }
+    let abs = Path::new(valor).absolutize().ok()?;
+    Some(abs.display().to_string())
+}
+
+/// Pasta de saida: `--saida`, `--target-dir`, `PORDOSOL_TARGET_DIR`,
+/// `configuracao.pasta_saida`, `build`.
+///
+/// Relativos sao resolvidos contra a raiz por quem consome o valor.
+pub fn resolver_saida(raiz: &Path, flag: Option<&Path>) -> ValorRastreado {
+    ValorRastreado::novo("saida")
+        .camada("flag --saida", flag.map(|p| p.display().to_string()))
+        .camada(
+            "flag --target-dir",
+            TARGET_DIR.get().map(|p| p.display().to_string()),
+        )
+        .camada("env:PORDOSOL_TARGET_DIR", target_dir_env())
+        .camadas_projeto(&arquivos_projeto(raiz), "pasta_saida")
+        .camada("padrao", Some("build".to_string()))
+}
+
+/// `--arg-compilador`: flags repassadas ao compilador nesta invocacao.
+static ARGS_COMPILADOR: OnceLock<Vec<String>> = OnceLock::new();
+
+pub fn definir_args_compilador(args: &[String]) {
+    if !args.is_empty() {
+        ARGS_COMPILADOR.set(args.to_vec()).ok();
+    }
+}
+
+/// Flags extras do compilador, agrupadas pela origem: `configuracao.flags_compilador` (o arquivo
+/// do ambiente ativo substitui o do pordosol.proj) e depois `--arg-compilador`.
+///
+/// A CLI nao interpreta nenhuma delas; vao para o compilador logo apos o `--target`.
+pub fn flags_compilador_por_origem(raiz: &Path) -> Result<Vec<(String, Vec<String>)>> {
+    let mut grupos = Vec::new();
+    for (nome, config) in arquivos_projeto(raiz) {
+        let Some(valor) = config
+            .as_ref()
+            .and_then(|c| c.get("configuracao"))
+            .and_then(|c| c.get("flags_compilador"))
+            .filter(|v| !v.is_null())
+        else {
+            continue;
+        };
+        let flags = valor
+            .as_array()
+            .and_then(|lista| {
+                lista
+                    .iter()
+                    .map(|f| f.as_str().map(str::to_string))
+                    .collect::<Option<Vec<_>>>()
+            })
+            .ok_or_else(|| {
+                ErroCli::ProjetoInvalido(format!(
+                    "{}: configuracao.flags_compilador deve ser uma lista de textos",
+                    nome
+                ))
+            })?;
+        grupos.push((format!("{} configuracao.flags_compilador", nome), flags));
+        break;
+    }
+    if let Some(args) = ARGS_COMPILADOR.get() {
+        grupos.push(("flag --arg-compilador".to_string(), args.clone()));
+    }
+    Ok(grupos)
+}
+
+/// Todas as flags extras, na ordem em que o compilador as recebe.
+pub fn flags_compilador(raiz: &Path) -> Result<Vec<String>> {
+    Ok(flags_compilador_por_origem(raiz)?
+        .into_iter()
+        .flat_map(|(_, flags)| flags)
+        .collect())
+}
+
+pub fn resolver_otimizacao(raiz: &Path) -> ValorRastreado {
+    ValorRastreado::novo("otimizacao")
+        .camadas_projeto(&arquivos_projeto(raiz), "otimizacao")
+        .camada("padrao", Some("false".to_string()))
+}
+
+/// Ferramentas da toolchain: os mesmos candidatos do build, na ordem em que sao testados.
+fn resolver_ferramenta(raiz: &Path, chave: &'static str, ferramenta: Ferramenta) -> ValorRastreado {
+    ferramenta
+        .candidatos(raiz)
+        .into_iter()
+        .fold(ValorRastreado::novo(chave), |v, c| {
+            let valor = c.encontrado.then(|| c.caminho.display().to_string());
+            v.camada(c.origem, valor)
+        })
+}
+
+/// Flags da invocacao de `config origem`, para simular o que o build veria.
+#[derive(Default)]
+pub struct FlagsConfig<'a> {
+    pub target: Option<&'a str>,
+    pub saida: Option<&'a Path>,
+}
+
+pub fn resolver_chave(raiz: &Path, chave: &str, flags: &FlagsConfig) -> Result<ValorRastreado> {
+    Ok(match chave {
+        "target" => resolver_target(raiz, flags.target),
+        "saida" | "pasta_saida" => resolver_saida(raiz, flags.saida),
+        "otimizacao" => resolver_otimizacao(raiz),
+        "compilador" => resolver_ferramenta(raiz, "compilador", Ferramenta::Compilador),
+        "interpretador" => resolver_ferramenta(raiz, "interpretador", Ferramenta::Interpretador),
+        "stdlib" => resolver_ferramenta(raiz, "stdlib", Ferramenta::Stdlib),
+        outra => bail!(
+            "Chave de configuracao desconhecida: '{}'. Chaves: {}",
+            outra,
+            CHAVES.join(", ")
+        ),
+    })
+}
+
+pub fn origem_cmd(
+    caminho: &Path,
+    chave: Option<&str>,
+    todas: bool,
+    flags: &FlagsConfig,
+    json: bool,
+) -> Result<()> {
+    let raiz = localizar_raiz(caminho);
+    let valores = if todas {
+        CHAVES
+            .iter()
+            .map(|c| resolver_chave(&raiz, c, flags))
+            .collect::<Result<Vec<_>>>()?
+    } else {
+        let Some(chave) = chave else {
+            bail!("Informe a chave ou use --todas");
+        };
+        vec![resolver_chave(&raiz, chave, flags)?]
+    };
+
+    if json {
+        let saida: Vec<serde_json::Value> = valores
+            .iter()
+            .map(|v| {
+                serde_json::json!({
+                    "chave": v.chave,
+                    "valor": v.valor(),
+                    "origem": v.vencedora().map(|i| &v.camadas[i].fonte),
+                    "camadas": v.camadas,
+                })
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&saida)?);
+        return Ok(());
+    }
+
+    if todas {
+        let largura = valores.iter().map(|v| v.chave.len()).max().unwrap_or(0);
+        for v in &valores {
+            println!(
+                "{:<largura$} = {} ({})",
+                v.chave,
+                v.valor().unwrap_or("-"),
+                v.vencedora()
+                    .map(|i| v.camadas[i].fonte.as_str())
+                    .unwrap_or("nenhuma camada"),
+                largura = largura
+            );
+        }
+        return Ok(());
+    }
+
+    let v = &valores[0];
+    println!("{} = {}", v.chave, v.valor().unwrap_or("-"));
+    println!("Precedencia (maior primeiro):");
+    let vencedora = v.vencedora();
+    for (i, camada) in v.camadas.iter().enumerate() {
+        let marca = match vencedora {
+            Some(w) if w == i => "  <- vence",
+            Some(w) if w < i && camada.valor.is_some() => "  (sobrescrito)",
+            _ => "",
+        };
+        println!(
+            "  {}. {}: {}{}",
+            i + 1,
+            camada.fonte,
+            camada.valor.as_deref().unwrap_or("(nao definido)"),
+            marca
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn projeto(configuracao: &str) -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("pordosol.proj"),
+            format!(r#"{{"nome": "app", "configuracao": {}}}"#, configuracao),
+        )
+        .unwrap();
+        dir
+    }
+
+    #[test]
+    fn flag_vence_projeto_que_vence_padrao() {
+        let dir = projeto(r#"{"target_padrao": "llvm-ir"}"#);
+        let v = resolver_target(dir.path(), None);
+        assert_eq!(v.valor(), Some("llvm-ir"));
+        assert_eq!(v.vencedora(), Some(1));
+        assert_eq!(v.camadas[2].valor.as_deref(), Some("bytecode"));
+
+        let v = resolver_target(dir.path(), Some("console"));
+        assert_eq!(v.valor(), Some("console"));
+        assert_eq!(v.vencedora(), Some(0));
+    }
+
+    #[test]
+    fn campo_vazio_ou_ausente_cai_no_padrao() {
+        let dir = projeto(r#"{"pasta_saida": "  ", "otimizacao": true}"#);
+        let v = resolver_saida(dir.path(), None);
+        assert_eq!(v.valor(), Some("build"));
+        assert_eq!(v.vencedora(), Some(v.camadas.len() - 1));
+        assert_eq!(resolver_otimizacao(dir.path()).valor(), Some("true"));
+        assert!(resolver_chave(dir.path(), "jobs", &FlagsConfig::default()).is_err());
+    }
+
+    #[test]
+    fn camada_do_ambiente_vem_antes_da_base() {
+        let arquivos = vec![
+            (
+                "pordosol.ci.proj".to_string(),
+                Some(serde_json::json!({"configuracao": {"otimizacao": true}})),
+            ),
+            (
+                "pordosol.proj".to_string(),
+                Some(
+                    serde_json::json!({"configuracao": {"otimizacao": false, "pasta_saida": "out"}}),
+                ),
+            ),
+        ];
+        let v = ValorRastreado::novo("otimizacao").camadas_projeto(&arquivos, "otimizacao");
+        assert_eq!(v.valor(), Some("true"));
+        assert_eq!(
+            v.camadas[0].fonte,
+            "pordosol.ci.proj configuracao.otimizacao"
+        );
+        assert_eq!(v.camadas[1].valor.as_deref(), Some("false"));
+
+        let v = ValorRastreado::novo("saida").camadas_projeto(&arquivos, "pasta_saida");
+        assert_eq!(v.vencedora(), Some(1));
+        assert_eq!(v.valor(), Some("out"));
+    }
+}
@@ -2,164 +2,1763 @@ use std::ffi::OsStr;
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use anyhow::{bail, Context, Result};
 use path_absolutize::Absolutize;
+use walkdir::WalkDir;
 
+use crate::configuracao::{
+    flags_compilador, flags_compilador_por_origem, resolver_chave, resolver_saida, resolver_target,
+    FlagsConfig, ValorRastreado,
+};
+use crate::dependencias::{resolver_plano, DependenciaResolvida, PlanoInclusao};
+use crate::erros::ErroCli;
+use crate::explicar::voce_quis_dizer;
+use crate::impressoes::{impressao_stdlib, Fnv1a, Impressao};
+use crate::relatorio::{RelatorioExecucao, SituacaoCache};
+use crate::saida::{descrever_comando, detalhe, progresso};
+use crate::tempos::Cronometro;
+use crate::titulo::{Estado, Titulo};
 use crate::toolchain::{
-    carregar_configuracao_projeto, listar_prs, localizar_binarios, localizar_raiz,
+    carregar_configuracao_projeto, detectar_versao_binario, fontes_projeto, listar_prs,
+    localizar_compilador, localizar_raiz, localizar_raiz_com_marcador,
 };
+use crate::versao::{verificar_compatibilidade, Requisito, Versao};
+use crate::workspace::CacheDependencias;
+
+/// Perfil de build: define a pasta de saida e o pipeline usado.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum Perfil {
+    /// Bytecode de desenvolvimento em `build/`
+    Dev,
+    /// Artefatos de `pordosol producao` em `build/producao/`
+    Producao,
+}
+
+impl Perfil {
+    pub fn nome(&self) -> &'static str {
+        match self {
+            Perfil::Dev => "dev",
+            Perfil::Producao => "producao",
+        }
+    }
+
+    pub fn saida_dir(&self, raiz: &Path) -> PathBuf {
+        let base = pasta_saida_projeto(raiz);
+        match self {
+            Perfil::Dev => base,
+            Perfil::Producao => base.join("producao"),
+        }
+    }
+}
+
+/// Pasta de saida base: `configuracao.pasta_saida` do pordosol.proj ou `build/`.
+///
+/// Caminhos relativos sao resolvidos contra a raiz do projeto.
+pub fn pasta_saida_projeto(raiz: &Path) -> PathBuf {
+    let pasta = PathBuf::from(resolver_saida(raiz, None).valor().unwrap_or("build"));
+    if pasta.is_absolute() {
+        pasta
+    } else {
+        raiz.join(pasta)
+    }
+}
+
+/// `--permitir-saida-perigosa`: desliga `validar_pasta_saida` nesta invocacao.
+static PERMITIR_SAIDA_PERIGOSA: AtomicBool = AtomicBool::new(false);
+
+pub fn definir_permitir_saida_perigosa(permitir: bool) {
+    PERMITIR_SAIDA_PERIGOSA.store(permitir, Ordering::Relaxed);
+}
+
+/// Caminho canonico mesmo que ainda nao exista: canoniza o ancestral existente mais
+/// proximo (resolvendo symlinks) e reacrescenta o resto.
+fn canonico_parcial(caminho: &Path) -> PathBuf {
+    let abs = caminho
+        .absolutize()
+        .map(|p| p.to_path_buf())
+        .unwrap_or_else(|_| caminho.to_path_buf());
+    let mut resto = Vec::new();
+    let mut atual = abs.as_path();
+    loop {
+        if let Ok(c) = atual.canonicalize() {
+            return resto.iter().rev().fold(c, |acc, parte| acc.join(parte));
+        }
+        match (atual.parent(), atual.file_name()) {
+            (Some(pai), Some(nome)) => {
+                resto.push(nome.to_os_string());
+                atual = pai;
+            }
+            _ => return abs,
+        }
+    }
+}
+
+/// Como a pasta de saida se sobrepoe a uma pasta de fontes, se se sobrepoe.
+pub fn sobreposicao_saida(saida: &Path, fontes: &Path) -> Option<&'static str> {
+    let (saida, fontes) = (canonico_parcial(saida), canonico_parcial(fontes));
+    if saida == fontes {
+        Some("e a mesma que")
+    } else if fontes.starts_with(&saida) {
+        Some("contem")
+    } else if saida.starts_with(&fontes) {
+        Some("fica dentro de")
+    } else {
+        None
+    }
+}
 
-pub fn compilar_cmd(caminho: &Path, target: &str, saida: Option<&Path>) -> Result<()> {
+/// Recusa uma pasta de saida que coincide com, contem ou fica dentro de `src/`.
+///
+/// `clean` apaga a pasta de saida e o compilador grava nela: sobreposta aos fontes,
+/// qualquer um dos dois destroi codigo.
+pub fn validar_pasta_saida(raiz: &Path, saida: &Path) -> Result<()> {
+    if PERMITIR_SAIDA_PERIGOSA.load(Ordering::Relaxed) {
+        return Ok(());
+    }
+    let fontes = raiz.join("src");
+    if let Some(relacao) = sobreposicao_saida(saida, &fontes) {
+        return Err(ErroCli::ProjetoInvalido(format!(
+            "A pasta de saida {} {} a pasta de fontes {}. Ajuste `configuracao.pasta_saida`/--saida ou use --permitir-saida-perigosa.",
+            canonico_parcial(saida).display(),
+            relacao,
+            canonico_parcial(&fontes).display()
+        ))
+        .into());
+    }
+    Ok(())
+}
+
+/// Quando `caminho` e uma pasta dentro da pasta de saida do projeto (ex.: cwd em `build/`),
+/// devolve a raiz do projeto, avisando; senao devolve `caminho` intacto.
+pub fn sair_da_pasta_saida(caminho: &Path) -> PathBuf {
+    if !caminho.is_dir() {
+        return caminho.to_path_buf();
+    }
+    let Ok(abs) = caminho.absolutize().map(|p| p.to_path_buf()) else {
+        return caminho.to_path_buf();
+    };
     let raiz = localizar_raiz(caminho);
-    let config = carregar_configuracao_projeto(&raiz);
-
-    let target_final = if target == "bytecode" && config.is_some() {
-        config
-            .as_ref()
-            .and_then(|c| c.get("configuracao"))
-            .and_then(|c| c.get("target_padrao"))
-            .and_then(|t| t.as_str())
-            .unwrap_or(target)
+    let saida = pasta_saida_projeto(&raiz);
+    if abs == raiz || saida == raiz || !abs.starts_with(&saida) {
+        return caminho.to_path_buf();
+    }
+    eprintln!(
+        "Aviso: {} esta dentro da pasta de saida {}; usando a raiz do projeto {}.",
+        abs.display(),
+        saida.display(),
+        raiz.display()
+    );
+    raiz
+}
+
+/// Executa os comandos de `configuracao.pre_checks`, abortando no primeiro que falhar.
+///
+/// Sao gates de validacao (nao geram artefatos); rodam na raiz do projeto via shell.
+pub fn executar_pre_checks(raiz: &Path) -> Result<()> {
+    let Some(config) = carregar_configuracao_projeto(raiz) else {
+        return Ok(());
+    };
+    let checks = match config.get("configuracao").and_then(|c| c.get("pre_checks")) {
+        None | Some(serde_json::Value::Null) => return Ok(()),
+        Some(serde_json::Value::Array(itens)) => itens,
+        Some(_) => {
+            return Err(ErroCli::ProjetoInvalido(
+                "Campo 'configuracao.pre_checks' invalido: esperado uma lista de comandos"
+                    .to_string(),
+            )
+            .into())
+        }
+    };
+
+    for item in checks {
+        let Some(comando) = item.as_str() else {
+            return Err(ErroCli::ProjetoInvalido(format!(
+                "Pre-check invalido em 'configuracao.pre_checks': {} (esperado texto)",
+                item
+            ))
+            .into());
+        };
+        progresso!("Pre-check: {}", comando);
+        let status = comando_shell(comando)
+            .current_dir(raiz)
+            .stdin(Stdio::null())
+            .status()
+            .with_context(|| format!("Falha ao executar pre-check `{}`", comando))?;
+        if !status.success() {
+            bail!(
+                "Pre-check falhou: `{}` (status {}). Use --skip-checks para ignorar.",
+                comando,
+                status
+            );
+        }
+    }
+    Ok(())
+}
+
+/// `comando` interpretado pelo shell do sistema (`sh -c` ou `cmd /C`).
+fn comando_shell(comando: &str) -> Command {
+    if cfg!(windows) {
+        let mut c = Command::new("cmd");
+        c.arg("/C").arg(comando);
+        c
     } else {
-        target
+        let mut c = Command::new("sh");
+        c.arg("-c").arg(comando);
+        c
+    }
+}
+
+/// `--sem-hooks`: nao executa a secao `hooks` do pordosol.proj.
+static SEM_HOOKS: AtomicBool = AtomicBool::new(false);
+
+pub fn definir_sem_hooks(sem_hooks: bool) {
+    SEM_HOOKS.store(sem_hooks, Ordering::Relaxed);
+}
+
+/// Momento de um build em que `hooks.<fase>` do pordosol.proj roda.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FaseHook {
+    /// Antes do compilador (ex.: gerar um arquivo de versao)
+    PreBuild,
+    /// Depois de um build bem-sucedido (ex.: copiar assets)
+    PostBuild,
+}
+
+impl FaseHook {
+    pub fn chave(self) -> &'static str {
+        match self {
+            FaseHook::PreBuild => "pre_build",
+            FaseHook::PostBuild => "post_build",
+        }
+    }
+}
+
+/// Executa os comandos de `hooks.<fase>`, abortando o build no primeiro que falhar.
+///
+/// Ao contrario dos pre-checks, hooks podem gerar arquivos: recebem `PORDOSOL_RAIZ`,
+/// `PORDOSOL_TARGET` e `PORDOSOL_SAIDA` e rodam na raiz do projeto via shell. Devolve se
+/// algum hook rodou, para o cronometro so abrir a etapa quando houver.
+pub fn executar_hooks(raiz: &Path, fase: FaseHook, target: &str, saida_dir: &Path) -> Result<bool> {
+    if SEM_HOOKS.load(Ordering::Relaxed) {
+        return Ok(false);
+    }
+    let Some(config) = carregar_configuracao_projeto(raiz) else {
+        return Ok(false);
+    };
+    let chave = fase.chave();
+    let comandos = match config.get("hooks").and_then(|h| h.get(chave)) {
+        None | Some(serde_json::Value::Null) => return Ok(false),
+        Some(serde_json::Value::Array(itens)) => itens,
+        Some(_) => {
+            return Err(ErroCli::ProjetoInvalido(format!(
+                "Campo 'hooks.{}' invalido: esperado uma lista de comandos",
+                chave
+            ))
+            .into())
+        }
     };
 
-    let arquivos: Vec<PathBuf> =
-        if caminho.is_file() && caminho.extension() == Some(OsStr::new("pr")) {
-            match caminho.absolutize() {
-                Ok(abs) => vec![abs.to_path_buf()],
-                Err(_) => vec![caminho.to_path_buf()],
-            }
-        } else {
-            let list = listar_prs(&raiz);
-            if list.is_empty() {
-                bail!("Nenhum arquivo .pr encontrado em {}/src", raiz.display());
-            }
-            list
+    let absoluto = |p: &Path| {
+        p.absolutize()
+            .map(|a| a.to_path_buf())
+            .unwrap_or_else(|_| p.to_path_buf())
+    };
+    for item in comandos {
+        let Some(comando) = item.as_str() else {
+            return Err(ErroCli::ProjetoInvalido(format!(
+                "Hook invalido em 'hooks.{}': {} (esperado texto)",
+                chave, item
+            ))
+            .into());
         };
+        progresso!("Hook {}: {}", chave, comando);
+        let status = comando_shell(comando)
+            .current_dir(raiz)
+            .env("PORDOSOL_RAIZ", absoluto(raiz))
+            .env("PORDOSOL_TARGET", target)
+            .env("PORDOSOL_SAIDA", absoluto(saida_dir))
+            .stdin(Stdio::null())
+            .status()
+            .with_context(|| format!("Falha ao executar hook {} `{}`", chave, comando))?;
+        if !status.success() {
+            bail!(
+                "Hook {} falhou: `{}` (codigo {}). Use --sem-hooks para ignorar.",
+                chave,
+                comando,
+                crate::erros::codigo_do_status(status)
+            );
+        }
+    }
+    Ok(!comandos.is_empty())
+}
 
-    let (compilador, _interp) = localizar_binarios(&raiz);
-    if !compilador.exists() {
-        bail!(
-            "Compilador nao encontrado em {}. Rode `pordosol doctor` e configure PORDOSOL_COMPILADOR_PATH/PORDOSOL_HOME.",
-            compilador.display()
-        );
+/// Avisa (ou falha, com `estrito`) se a versao do compilador estiver fora da faixa suportada.
+///
+/// Compiladores que nao reportam versao nao sao bloqueados.
+pub fn verificar_versao_compilador(compilador: &Path, estrito: bool) -> Result<()> {
+    let Some(versao) = detectar_versao_binario(compilador) else {
+        return Ok(());
+    };
+    let compat = verificar_compatibilidade(&versao);
+    if compat.ok() {
+        return Ok(());
+    }
+    let msg = format!(
+        "Compilador {}: {}",
+        compilador.display(),
+        compat.descricao(&versao)
+    );
+    if estrito {
+        bail!("{} (--strict-versao ativo)", msg);
+    }
+    eprintln!("Aviso: {}. Use --strict-versao para tratar como erro.", msg);
+    Ok(())
+}
+
+/// `--ignorar-toolchain`: nao confere o compilador contra `toolchain.compilador` do projeto.
+static IGNORAR_TOOLCHAIN: AtomicBool = AtomicBool::new(false);
+
+pub fn definir_ignorar_toolchain(ignorar: bool) {
+    IGNORAR_TOOLCHAIN.store(ignorar, Ordering::Relaxed);
+}
+
+/// Requisito de `"toolchain": {"compilador": ">=0.5.0"}` no pordosol.proj, se houver.
+pub fn requisito_compilador(raiz: &Path) -> Result<Option<Requisito>> {
+    let Some(valor) = carregar_configuracao_projeto(raiz)
+        .and_then(|c| c.get("toolchain")?.get("compilador").cloned())
+    else {
+        return Ok(None);
+    };
+    let texto = valor.as_str().ok_or_else(|| {
+        ErroCli::ProjetoInvalido(format!(
+            "`toolchain.compilador` deve ser texto (ex.: \">=0.5.0\"), encontrado {}",
+            valor
+        ))
+    })?;
+    Requisito::parse(texto)
+        .map(Some)
+        .map_err(|e| ErroCli::ProjetoInvalido(format!("`toolchain.compilador`: {:#}", e)).into())
+}
+
+/// Mensagem de quando o compilador nao satisfaz o requisito do projeto.
+pub fn mensagem_toolchain_incompativel(
+    versao: Option<&str>,
+    requisito: &Requisito,
+    caminho: &Path,
+    origem: &str,
+) -> String {
+    let encontrado = match versao {
+        Some(v) => format!("compilador {} encontrado", v),
+        None => "compilador sem versao reconhecivel".to_string(),
+    };
+    format!(
+        "{}, requerido {} (pordosol.proj), caminho {}, origem {}. Instale uma versao compativel ou use --ignorar-toolchain.",
+        encontrado,
+        requisito,
+        caminho.display(),
+        origem
+    )
+}
+
+/// Confere o compilador resolvido contra `toolchain.compilador` do projeto.
+pub fn verificar_toolchain_projeto(raiz: &Path) -> Result<()> {
+    if IGNORAR_TOOLCHAIN.load(Ordering::Relaxed) {
+        return Ok(());
+    }
+    let Some(requisito) = requisito_compilador(raiz)? else {
+        return Ok(());
+    };
+    let Ok(compilador) = localizar_compilador(raiz) else {
+        // O erro de compilador ausente vem de quem for usa-lo
+        return Ok(());
+    };
+    let versao = detectar_versao_binario(&compilador.caminho);
+    let aceito = versao
+        .as_deref()
+        .and_then(|v| Versao::parse(v).ok())
+        .is_some_and(|v| requisito.aceita(&v));
+    if aceito {
+        return Ok(());
+    }
+    Err(ErroCli::ToolchainAusente(mensagem_toolchain_incompativel(
+        versao.as_deref(),
+        &requisito,
+        &compilador.caminho,
+        &compilador.origem,
+    ))
+    .into())
+}
+
+/// `build --emit-deps`: arquivo .d gravado a cada build de bytecode bem-sucedido.
+static EMIT_DEPS: OnceLock<PathBuf> = OnceLock::new();
+
+pub fn definir_emit_deps(arquivo: Option<PathBuf>) {
+    if let Some(a) = arquivo {
+        EMIT_DEPS.set(a).ok();
+    }
+}
+
+/// Escapa um caminho para regra de Makefile: espacos e `#` com `\`, `$` dobrado.
+fn escapar_make(caminho: &Path) -> String {
+    let mut out = String::new();
+    for c in caminho.to_string_lossy().replace('\\', "/").chars() {
+        match c {
+            ' ' | '#' => {
+                out.push('\\');
+                out.push(c);
+            }
+            '$' => out.push_str("$$"),
+            _ => out.push(c),
+        }
     }
+    out
+}
+
+/// Regra `alvo: fontes` no formato .d, um pre-requisito por linha.
+pub fn regra_dependencias(alvo: &Path, fontes: &[PathBuf]) -> String {
+    let mut regra = format!("{}:", escapar_make(alvo));
+    for f in fontes {
+        regra.push_str(" \\\n  ");
+        regra.push_str(&escapar_make(f));
+    }
+    regra.push('\n');
+    regra
+}
+
+fn gravar_dependencias(destino: &Path, alvo: &Path, fontes: &[PathBuf]) -> Result<()> {
+    let absoluto = |p: &Path| {
+        p.absolutize()
+            .map(|a| a.to_path_buf())
+            .unwrap_or_else(|_| p.to_path_buf())
+    };
+    let fontes: Vec<PathBuf> = fontes.iter().map(|f| absoluto(f)).collect();
+    if let Some(pai) = destino.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(pai)?;
+    }
+    fs::write(destino, regra_dependencias(&absoluto(alvo), &fontes))
+        .with_context(|| format!("Falha ao gravar {}", destino.display()))?;
+    progresso!("Dependencias gravadas em {}", destino.display());
+    Ok(())
+}
+
+/// Contagens de um build, para a linha de `build --oneline`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ResumoBuild {
+    pub arquivos: usize,
+    pub artefatos: usize,
+}
+
+impl ResumoBuild {
+    pub fn linha_ok(&self, duracao: Duration) -> String {
+        format!(
+            "OK: {} arquivo(s), {} artefato(s), {:.1}s",
+            self.arquivos,
+            self.artefatos,
+            duracao.as_secs_f64()
+        )
+    }
+}
+
+/// Linha de `build --oneline` para uma falha: o primeiro `.pr` citado no erro, se houver.
+pub fn linha_falha(raiz: &Path, erro: &anyhow::Error) -> String {
+    let texto = format!("{:#}", erro);
+    let arquivo = texto
+        .split(|c: char| c.is_whitespace() || matches!(c, '\'' | '"' | '(' | ')'))
+        .map(|t| t.split(':').next().unwrap_or(t))
+        .find(|t| t.ends_with(".pr"));
+    match arquivo {
+        Some(a) => {
+            let caminho = Path::new(a);
+            let rel = caminho.strip_prefix(raiz).unwrap_or(caminho);
+            format!("FALHOU: erro em {}", rel.display())
+        }
+        None => format!("FALHOU: {}", texto.lines().next().unwrap_or("erro")),
+    }
+}
+
+/// Compila o projeto (ou um `.pr`) para um target.
+///
+/// Com `capturar_saida`, a saida do compilador nao vai para o terminal: em caso de
+/// falha ela entra na mensagem de erro (usado por `--oneline`).
+pub fn compilar_cmd(
+    caminho: &Path,
+    target: &str,
+    saida: Option<&Path>,
+    nome_saida: Option<&str>,
+    timings: bool,
+    capturar_saida: bool,
+) -> Result<ResumoBuild> {
+    let mut cronometro = Cronometro::iniciar(timings);
+    let (raiz, marcador) = localizar_raiz_com_marcador(caminho);
+    detalhe!(
+        "Raiz do projeto: {} (marcador: {})",
+        raiz.display(),
+        marcador
+    );
+
+    // `bytecode` e o default do clap: so conta como flag quando outro target foi pedido
+    let rastreio = resolver_target(&raiz, (target != "bytecode").then_some(target));
+    let target_final = rastreio.valor().unwrap_or(target);
+    let alvo_flag = resolver_flag_target(target_final, TARGETS_BUILD)?;
 
     let saida_dir = saida
         .map(Path::to_path_buf)
-        .unwrap_or_else(|| raiz.join("build"));
-    fs::create_dir_all(&saida_dir).ok();
+        .unwrap_or_else(|| Perfil::Dev.saida_dir(&raiz));
+    validar_pasta_saida(&raiz, &saida_dir)?;
 
-    let tnorm = target_final.trim().to_ascii_lowercase();
-    let alvo_flag = match tnorm.as_str() {
-        "bytecode" | "bc" => "--target=bytecode",
-        "llvm" | "llvm-ir" => "--target=llvm-ir",
-        "cil-bytecode" => "--target=cil-bytecode",
-        "console" => "--target=console",
-        "universal" => "--target=universal",
-        other => {
-            eprintln!("Alvo desconhecido: {}. Usando bytecode.", other);
-            "--target=bytecode"
+    // Antes da descoberta: um hook pode gerar fontes
+    if !dry_run() && executar_hooks(&raiz, FaseHook::PreBuild, target_final, &saida_dir)? {
+        cronometro.etapa("hooks pre_build");
+    }
+
+    let arquivo_unico = caminho.is_file() && caminho.extension() == Some(OsStr::new("pr"));
+    let arquivos: Vec<PathBuf> = if arquivo_unico {
+        match caminho.absolutize() {
+            Ok(abs) => vec![abs.to_path_buf()],
+            Err(_) => vec![caminho.to_path_buf()],
         }
+    } else {
+        fontes_com_info_build(&raiz, Perfil::Dev)?
     };
 
-    println!(
-        "Compilando para {} com {} arquivo(s)...",
-        target_final,
-        arquivos.len()
-    );
+    let plano = resolver_plano(&raiz)?;
+    cronometro.etapa("descoberta de fontes");
+
+    let compilador = localizar_compilador(&raiz).map_err(ErroCli::from)?.caminho;
+    if !dry_run() {
+        fs::create_dir_all(&saida_dir).ok();
+    }
+
+    let incremental = if arquivo_unico {
+        None
+    } else {
+        plano_incremental(&compilador, &arquivos)
+    };
+    match &incremental {
+        Some((flag, enviados)) => progresso!(
+            "Compilando para {} com {} de {} arquivo(s), o principal e os alterados ({})...",
+            target_final,
+            enviados.len(),
+            arquivos.len(),
+            flag
+        ),
+        None => progresso!(
+            "Compilando para {} com {} arquivo(s)...",
+            target_final,
+            arquivos.len()
+        ),
+    }
 
     let mut cmd = Command::new(&compilador);
     cmd.current_dir(&saida_dir)
-        .arg(alvo_flag)
+        .arg(&alvo_flag)
+        .args(incremental.as_ref().map(|(flag, _)| *flag))
+        .args(flags_compilador(&raiz)?)
         .stdin(Stdio::null());
-    for arq in &arquivos {
+    let enviados = incremental
+        .as_ref()
+        .map_or(&arquivos, |(_, enviados)| enviados);
+    for arq in enviados {
         cmd.arg(arq);
     }
+    plano.aplicar(&mut cmd);
+    if mostrar_dry_run(&cmd) {
+        return Ok(ResumoBuild {
+            arquivos: arquivos.len(),
+            artefatos: 0,
+        });
+    }
 
-    let status = cmd.status().context("Falha ao executar o compilador")?;
-    if !status.success() {
-        bail!("Compilacao falhou (status {})", status);
+    let inicio = SystemTime::now();
+    cronometro.descartar();
+    executar_compilador(
+        &mut cmd,
+        Perfil::Dev,
+        target_final,
+        arquivos.len(),
+        capturar_saida,
+        "Compilacao falhou",
+    )?;
+    cronometro.etapa("compilador");
+    if !arquivo_unico {
+        crate::impressoes::registrar_impressoes(&raiz, &arquivos);
     }
 
-    println!("Compilado com sucesso. Saida em {}", saida_dir.display());
+    let stem_principal = arquivos[0]
+        .file_stem()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+    let nome = resolver_nome_saida(&raiz, nome_saida, &stem_principal, arquivo_unico)?;
+    match extensao_artefato(&alvo_flag) {
+        // O bytecode e o que `run` executa: precisa ser localizado sem ambiguidade
+        Some("pbc") => {
+            finalizar_artefato(&saida_dir, &stem_principal, &nome, "pbc", inicio)?;
+            crate::analise::registrar_tamanhos(&raiz, &saida_dir, &arquivos);
+            if let Some(destino) = EMIT_DEPS.get() {
+                let mut fontes = arquivos.clone();
+                fontes.extend(plano.arquivos().cloned());
+                gravar_dependencias(destino, &saida_dir.join(format!("{}.pbc", nome)), &fontes)?;
+            }
+        }
+        Some(ext) => copiar_artefato_nomeado(
+            &saida_dir.join(format!("{}.{}", stem_principal, ext)),
+            &saida_dir.join(format!("{}.{}", nome, ext)),
+        )?,
+        None => {}
+    }
+    let manifesto = crate::manifesto::registrar_build(
+        &raiz,
+        &saida_dir,
+        "build",
+        target_final,
+        &compilador,
+        &arquivos,
+        inicio,
+    );
+    gravar_checksums(&saida_dir, manifesto.as_ref());
+
+    progresso!("Compilado com sucesso. Saida em {}", saida_dir.display());
 
+    let mut artefatos = 0;
     if let Ok(entries) = fs::read_dir(&saida_dir) {
+        // Arquivos ocultos sao metadados da CLI (historico de tempos), como o manifesto e os checksums
         let arquivos_build: Vec<_> = entries
             .filter_map(|e| e.ok())
-            .filter(|e| e.path().is_file())
+            .filter(|e| {
+                let nome = e.file_name();
+                e.path().is_file()
+                    && !nome.to_string_lossy().starts_with('.')
+                    && !crate::manifesto::metadado_da_cli(&nome)
+            })
             .collect();
+        artefatos = arquivos_build.len();
 
         if !arquivos_build.is_empty() {
-            println!("Arquivos gerados:");
+            progresso!("Arquivos gerados:");
             for entry in arquivos_build {
                 let path = entry.path();
                 let rel_path = path.strip_prefix(&saida_dir).unwrap_or(&path);
                 if let Ok(metadata) = entry.metadata() {
-                    println!("  {} ({} bytes)", rel_path.display(), metadata.len());
+                    progresso!("  {} ({} bytes)", rel_path.display(), metadata.len());
                 } else {
-                    println!("  {}", rel_path.display());
+                    progresso!("  {}", rel_path.display());
                 }
             }
         }
     }
+    cronometro.etapa("listagem de artefatos");
+    if executar_hooks(&raiz, FaseHook::PostBuild, target_final, &saida_dir)? {
+        cronometro.etapa("hooks post_build");
+    }
+    cronometro.concluir(&pasta_saida_projeto(&raiz), "build", target_final);
+
+    Ok(ResumoBuild {
+        arquivos: arquivos.len(),
+        artefatos,
+    })
+}
+
+/// Flag `--target=` do compilador para um alvo da CLI; `None` para alvos desconhecidos.
+fn flag_target(target: &str) -> Option<&'static str> {
+    Some(match target.trim().to_ascii_lowercase().as_str() {
+        "bytecode" | "bc" => "--target=bytecode",
+        "llvm" | "llvm-ir" => "--target=llvm-ir",
+        "cil-bytecode" => "--target=cil-bytecode",
+        "console" => "--target=console",
+        "universal" => "--target=universal",
+        _ => return None,
+    })
+}
+
+/// Alvos aceitos por `build` (os de [`flag_target`]), para sugestoes.
+const TARGETS_BUILD: &[&str] = &[
+    "bytecode",
+    "bc",
+    "llvm",
+    "llvm-ir",
+    "cil-bytecode",
+    "console",
+    "universal",
+];
+
+/// Alvos aceitos por `producao`.
+const TARGETS_PRODUCAO: &[&str] = &["llvm", "llvm-ir", "console"];
+
+/// `--target-livre`: alvos desconhecidos vao ao compilador como vieram.
+static TARGET_LIVRE: AtomicBool = AtomicBool::new(false);
+
+pub fn definir_target_livre(livre: bool) {
+    TARGET_LIVRE.store(livre, Ordering::Relaxed);
+}
+
+/// `--dry-run` de `build`/`producao`: mostra o comando do compilador sem executar nada.
+static DRY_RUN: AtomicBool = AtomicBool::new(false);
+
+pub fn definir_dry_run(ativo: bool) {
+    DRY_RUN.store(ativo, Ordering::Relaxed);
+}
+
+pub fn dry_run() -> bool {
+    DRY_RUN.load(Ordering::Relaxed)
+}
+
+/// `--checksums` (ligado por padrao): grava o hash dos artefatos depois de cada build.
+static CHECKSUMS: AtomicBool = AtomicBool::new(true);
+
+pub fn definir_checksums(ativo: bool) {
+    CHECKSUMS.store(ativo, Ordering::Relaxed);
+}
+
+/// `--message-format` de `build`/`producao`.
+static FORMATO_MENSAGENS: OnceLock<FormatoMensagens> = OnceLock::new();
+
+pub fn definir_formato_mensagens(formato: FormatoMensagens) {
+    FORMATO_MENSAGENS.set(formato).ok();
+}
+
+/// `--jobs`: compilacoes simultaneas que o compilador pode usar, em `PORDOSOL_JOBS`.
+static JOBS: OnceLock<u16> = OnceLock::new();
+
+pub fn definir_jobs(jobs: Option<u16>) {
+    if let Some(j) = jobs {
+        JOBS.set(j).ok();
+    }
+}
+
+/// Flag `--target=` para `target`, se ele for um dos `aceitos`; senao erro com sugestao, a
+/// menos que `--target-livre` (alvos que o compilador conhece e a CLI ainda nao).
+fn resolver_flag_target(target: &str, aceitos: &[&str]) -> Result<String> {
+    let normalizado = target.trim().to_ascii_lowercase();
+    if aceitos.contains(&normalizado.as_str()) {
+        if let Some(flag) = flag_target(&normalizado) {
+            return Ok(flag.to_string());
+        }
+    }
+    if TARGET_LIVRE.load(Ordering::Relaxed) {
+        return Ok(format!("--target={}", target.trim()));
+    }
+    bail!(
+        "Target desconhecido: '{}'.{} Aceitos: {} (--target-livre repassa qualquer valor ao compilador)",
+        normalizado,
+        voce_quis_dizer(&normalizado, aceitos),
+        aceitos.join("|")
+    )
+}
+
+/// Origem do valor vencedor e as camadas que ele sobrescreveu, para `--explain-flags`.
+fn imprimir_rastreio(rastreio: &ValorRastreado) {
+    let vencedora = rastreio.vencedora();
+    for (i, camada) in rastreio.camadas.iter().enumerate() {
+        let Some(valor) = camada.valor.as_deref() else {
+            continue;
+        };
+        let papel = match vencedora {
+            Some(w) if w == i => "origem",
+            _ => "sobrescrito",
+        };
+        println!("  {}: {} = {}", papel, camada.fonte, valor);
+    }
+}
+
+/// `build --explain-flags`: de onde vem cada flag gerenciada pela CLI, sem compilar.
+pub fn explicar_flags(caminho: &Path, target: &str, saida: Option<&Path>) -> Result<()> {
+    let raiz = localizar_raiz(caminho);
+    let flags = FlagsConfig {
+        target: (target != "bytecode").then_some(target),
+        saida,
+    };
+
+    println!(
+        "Flags do compilador para `build` (perfil {}); nada foi compilado.",
+        Perfil::Dev.nome()
+    );
+    println!();
+
+    let rastreio = resolver_chave(&raiz, "target", &flags)?;
+    let alvo = rastreio.valor().unwrap_or(target);
+    match resolver_flag_target(alvo, TARGETS_BUILD) {
+        Ok(flag) => println!("target: {}", flag),
+        Err(e) => println!("target: invalido ({})", e),
+    }
+    imprimir_rastreio(&rastreio);
+
+    let rastreio = resolver_chave(&raiz, "saida", &flags)?;
+    let pasta = saida
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| Perfil::Dev.saida_dir(&raiz));
+    println!(
+        "saida: diretorio de trabalho do compilador {}",
+        pasta.display()
+    );
+    imprimir_rastreio(&rastreio);
+
+    let rastreio = resolver_chave(&raiz, "otimizacao", &flags)?;
+    println!("otimizacao: nenhuma flag (o valor e lido do pordosol.proj, mas nao e repassado ao compilador)");
+    imprimir_rastreio(&rastreio);
+
+    let rastreio = resolver_chave(&raiz, "stdlib", &flags)?;
+    println!("stdlib: nenhuma flag (o compilador localiza a propria stdlib)");
+    match rastreio.vencedora() {
+        Some(i) => println!(
+            "  detectada: {} ({})",
+            rastreio.camadas[i].valor.as_deref().unwrap_or("-"),
+            rastreio.camadas[i].fonte
+        ),
+        None => println!("  detectada: nenhuma"),
+    }
+
+    println!("defines: nenhum (a CLI nao repassa defines ao compilador)");
 
+    let extras = flags_compilador_por_origem(&raiz)?;
+    if extras.is_empty() {
+        println!("extras: nenhuma (configuracao.flags_compilador ou --arg-compilador)");
+    }
+    for (origem, flags) in &extras {
+        println!("extras: {}", flags.join(" "));
+        println!("  origem: {}", origem);
+    }
+
+    let plano = resolver_plano(&raiz)?;
+    if plano.dependencias.is_empty() {
+        println!("dependencias: nenhuma flag (pordosol.proj sem dependencias)");
+    }
+    for dep in &plano.dependencias {
+        let mut args: Vec<String> = dep
+            .includes
+            .iter()
+            .map(|inc| format!("--incluir={}", inc.display()))
+            .collect();
+        args.extend(dep.arquivos.iter().map(|a| a.display().to_string()));
+        println!(
+            "dependencia {}: {}",
+            dep.nome,
+            if args.is_empty() {
+                "nenhuma flag (sem fontes locais)".to_string()
+            } else {
+                args.join(" ")
+            }
+        );
+        println!("  origem: pordosol.proj dependencias.{}", dep.nome);
+    }
     Ok(())
 }
 
-pub fn producao_cmd(caminho: &Path, target: &str) -> Result<()> {
+/// Fontes do projeto mais o `.pr` de `configuracao.gerar_info_build`, regenerado para `perfil`.
+///
+/// O arquivo gerado entra mesmo fora de `src/`.
+pub fn fontes_com_info_build(raiz: &Path, perfil: Perfil) -> Result<Vec<PathBuf>> {
+    let gerado = crate::info_build::preparar(raiz, perfil)?;
+    let mut fontes = fontes_projeto(raiz)?;
+    if let Some(gerado) = gerado {
+        if !fontes.contains(&gerado) {
+            fontes.push(gerado);
+        }
+    }
+    Ok(fontes)
+}
+
+/// Intervalo entre varreduras de `src/` nos modos `--watch`.
+pub const INTERVALO_WATCH: Duration = Duration::from_millis(300);
+
+/// Assinatura de um fonte: caminho, tamanho e mtime.
+pub type AssinaturaFonte = (PathBuf, u64, Option<SystemTime>);
+
+/// Caminho, tamanho e mtime de cada `.pr`: muda quando um fonte e criado, removido ou editado.
+pub fn assinatura_fontes(raiz: &Path) -> Vec<AssinaturaFonte> {
+    listar_prs(raiz)
+        .into_iter()
+        .map(|p| {
+            let meta = p.metadata().ok();
+            let tamanho = meta.as_ref().map(|m| m.len()).unwrap_or(0);
+            let modificado = meta.and_then(|m| m.modified().ok());
+            (p, tamanho, modificado)
+        })
+        .collect()
+}
+
+/// Fontes criados, removidos ou editados entre duas assinaturas.
+fn fontes_alterados(antes: &[AssinaturaFonte], depois: &[AssinaturaFonte]) -> Vec<PathBuf> {
+    let mut alterados: Vec<PathBuf> = depois
+        .iter()
+        .filter(|a| !antes.contains(a))
+        .map(|(p, _, _)| p.clone())
+        .collect();
+    alterados.extend(
+        antes
+            .iter()
+            .filter(|(p, _, _)| !depois.iter().any(|(q, _, _)| q == p))
+            .map(|(p, _, _)| p.clone()),
+    );
+    alterados
+}
+
+/// Flags com que o compilador anuncia, na ajuda, aceitar so os fontes alterados e reaproveitar
+/// o resto do build anterior na mesma pasta de saida, em ordem de preferencia.
+const FLAGS_INCREMENTAIS: &[&str] = &["--incremental"];
+
+/// Estado de uma sessao de `build --watch` entre um rebuild e outro.
+struct SessaoWatch {
+    /// Fontes alterados desde o ultimo build bem-sucedido; `None` pede o build completo
+    alterados: Option<Vec<PathBuf>>,
+    /// Resultado da sondagem das [`FLAGS_INCREMENTAIS`], feita uma vez por sessao
+    flag_incremental: Option<Option<&'static str>>,
+}
+
+/// Sessao do `build --watch` em andamento; `None` fora dele.
+static SESSAO_WATCH: Mutex<Option<SessaoWatch>> = Mutex::new(None);
+
+/// Flag incremental e fontes a enviar quando o rebuild do watch pode ser parcial: so
+/// edicoes e criacoes entre os `arquivos` do projeto, e um compilador que anuncia a flag.
+///
+/// O compilador nomeia o artefato pelo primeiro fonte recebido, entao o principal
+/// (`arquivos[0]`) vai sempre na frente, mesmo sem ter sido alterado.
+fn plano_incremental(
+    compilador: &Path,
+    arquivos: &[PathBuf],
+) -> Option<(&'static str, Vec<PathBuf>)> {
+    let mut sessao = SESSAO_WATCH.lock().unwrap_or_else(|e| e.into_inner());
+    let sessao = sessao.as_mut()?;
+    let alterados = sessao.alterados.clone()?;
+    // Um fonte removido (ou excluido) muda o conjunto do programa: so o build completo resolve
+    if alterados.is_empty() || alterados.iter().any(|a| !arquivos.contains(a)) {
+        return None;
+    }
+    let flag = (*sessao
+        .flag_incremental
+        .get_or_insert_with(|| procurar_na_ajuda(compilador, FLAGS_INCREMENTAIS)))?;
+    let principal = arquivos.first()?;
+    let mut enviados = vec![principal.clone()];
+    enviados.extend(alterados.into_iter().filter(|a| a != principal));
+    Some((flag, enviados))
+}
+
+fn definir_alterados_watch(alterados: Option<Vec<PathBuf>>) {
+    if let Some(sessao) = SESSAO_WATCH
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .as_mut()
+    {
+        sessao.alterados = alterados;
+    }
+}
+
+/// `build --watch`: recompila a cada mudanca em `src/**/*.pr`, com o tempo de cada rebuild.
+///
+/// Se o compilador anuncia uma das [`FLAGS_INCREMENTAIS`] (sondado uma vez por sessao), os
+/// rebuilds enviam so o fonte principal e os editados ou criados; o primeiro build, o seguinte
+/// a uma falha e os que removem fontes recebem a lista completa. Falhas nao encerram o watcher; Ctrl+C encerra (o compilador
+/// recebe o mesmo sinal).
+pub fn build_watch<F>(raiz: &Path, mut compilar: F) -> Result<()>
+where
+    F: FnMut() -> Result<()>,
+{
+    let mut builds = 0u32;
+    let mut alterados: Vec<PathBuf> = Vec::new();
+    let mut anterior_ok = false;
+    let mut titulo = Titulo::iniciar(&nome_membro(raiz), "build --watch");
+    *SESSAO_WATCH.lock().unwrap_or_else(|e| e.into_inner()) = Some(SessaoWatch {
+        alterados: None,
+        flag_incremental: None,
+    });
+    loop {
+        builds += 1;
+        if builds > 1 {
+            progresso!();
+        }
+        progresso!("===== pordosol build --watch: build {} =====", builds);
+        for arquivo in &alterados {
+            let rel = arquivo.strip_prefix(raiz).unwrap_or(arquivo);
+            progresso!("Alterado: {}", rel.display());
+        }
+        let assinatura = assinatura_fontes(raiz);
+
+        let inicio = Instant::now();
+        titulo.estado(Estado::Compilando);
+        definir_alterados_watch(anterior_ok.then(|| alterados.clone()));
+        let resultado = compilar();
+        definir_alterados_watch(None);
+        anterior_ok = resultado.is_ok();
+        match resultado {
+            Ok(()) => {
+                titulo.estado(Estado::AguardandoMudancas);
+                progresso!(
+                    "Build {} concluido em {} ms.",
+                    builds,
+                    inicio.elapsed().as_millis()
+                )
+            }
+            Err(e) => {
+                titulo.estado(Estado::Falhou);
+                eprintln!(
+                    "Error: build {} falhou em {} ms: {:#}",
+                    builds,
+                    inicio.elapsed().as_millis(),
+                    e
+                )
+            }
+        }
+
+        loop {
+            thread::sleep(INTERVALO_WATCH);
+            let atual = assinatura_fontes(raiz);
+            if atual != assinatura {
+                alterados = fontes_alterados(&assinatura, &atual);
+                break;
+            }
+        }
+    }
+}
+
+/// Compila o projeto para varios targets, registrando cada um no relatorio de execucao.
+pub fn compilar_multi_target(
+    caminho: &Path,
+    targets: &[String],
+    saida: Option<&Path>,
+    nome_saida: Option<&str>,
+    json: bool,
+    fail_fast: bool,
+    timings: bool,
+) -> Result<()> {
     let raiz = localizar_raiz(caminho);
-    let arquivos: Vec<PathBuf> =
-        if caminho.is_file() && caminho.extension() == Some(OsStr::new("pr")) {
-            vec![caminho.to_path_buf()]
-        } else {
-            let list = listar_prs(&raiz);
-            if list.is_empty() {
-                bail!("Nenhum arquivo .pr encontrado em {}/src", raiz.display());
+    let membro = nome_membro(&raiz);
+    let mut relatorio = RelatorioExecucao::new();
+
+    let mut interromper = false;
+    for target in targets {
+        if interromper {
+            relatorio.pular(&membro, target);
+            continue;
+        }
+        let resultado = relatorio.executar(&membro, target, || {
+            compilar_cmd(caminho, target, saida, nome_saida, timings, false).map(|_| ())
+        });
+        if resultado.is_err() {
+            interromper = fail_fast;
+        }
+    }
+
+    relatorio.finalizar(json)
+}
+
+/// Nome exibido para o projeto em relatorios: `nome` do pordosol.proj ou a pasta.
+pub fn nome_membro(raiz: &Path) -> String {
+    carregar_configuracao_projeto(raiz)
+        .and_then(|c| c.get("nome").and_then(|n| n.as_str()).map(str::to_string))
+        .unwrap_or_else(|| {
+            raiz.file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string()
+        })
+}
+
+/// Nome base dos artefatos: `--nome-saida`, depois `nome` do pordosol.proj, depois o stem da fonte.
+///
+/// Compilando um unico arquivo `.pr`, o nome do projeto e ignorado.
+pub fn resolver_nome_saida(
+    raiz: &Path,
+    explicito: Option<&str>,
+    stem_principal: &str,
+    arquivo_unico: bool,
+) -> Result<String> {
+    if let Some(nome) = explicito {
+        validar_nome_saida(nome)?;
+        return Ok(nome.to_string());
+    }
+
+    if !arquivo_unico {
+        let nome_proj = carregar_configuracao_projeto(raiz)
+            .and_then(|c| c.get("nome").and_then(|n| n.as_str()).map(str::to_string));
+        if let Some(nome) = nome_proj.filter(|n| !n.trim().is_empty()) {
+            if validar_nome_saida(&nome).is_ok() {
+                return Ok(nome);
             }
-            list
-        };
+            eprintln!(
+                "Aviso: nome do projeto '{}' nao serve como nome de artefato; usando '{}'.",
+                nome, stem_principal
+            );
+        }
+    }
+
+    Ok(stem_principal.to_string())
+}
 
-    let (compilador, _interp) = localizar_binarios(&raiz);
-    if !compilador.exists() {
+fn validar_nome_saida(nome: &str) -> Result<()> {
+    if nome.trim().is_empty() {
+        bail!("--nome-saida nao pode ser vazio");
+    }
+    if let Some(c) = nome
+        .chars()
+        .find(|c| c.is_whitespace() || matches!(c, '/' | '\\' | ':'))
+    {
         bail!(
-            "Compilador nao encontrado em {}. Rode `pordosol doctor` e configure PORDOSOL_COMPILADOR_PATH/PORDOSOL_HOME.",
-            compilador.display()
+            "Nome de saida invalido '{}': caractere {:?} nao permitido (sem espacos ou separadores de caminho)",
+            nome,
+            c
         );
     }
+    if nome == "." || nome == ".." {
+        bail!("Nome de saida invalido '{}'", nome);
+    }
+    Ok(())
+}
 
-    let saida_dir = raiz.join("build");
-    fs::create_dir_all(&saida_dir).ok();
+/// Copia o artefato gerado pelo compilador para o nome solicitado, se forem diferentes.
+pub fn copiar_artefato_nomeado(gerado: &Path, destino: &Path) -> Result<()> {
+    if gerado == destino || !gerado.is_file() {
+        return Ok(());
+    }
+    fs::copy(gerado, destino).with_context(|| {
+        format!(
+            "Falha ao copiar {} para {}",
+            gerado.display(),
+            destino.display()
+        )
+    })?;
+    Ok(())
+}
 
-    let tnorm = target.trim().to_ascii_lowercase();
-    let alvo_flag = match tnorm.as_str() {
-        "llvm" | "llvm-ir" => "--target=llvm-ir",
-        other => {
-            eprintln!(
-                "Target de producao desconhecido: {}. Usando llvm-ir.",
-                other
+/// Manifesto da pasta de saida com o local de artefatos que o compilador gravou em subpastas.
+const MANIFESTO_BUILD: &str = ".pordosol-build.json";
+/// Profundidade maxima da busca por artefatos dentro da pasta de saida.
+const PROFUNDIDADE_BUSCA_ARTEFATO: usize = 4;
+
+fn ler_manifesto_build(saida_dir: &Path) -> serde_json::Map<String, serde_json::Value> {
+    fs::read_to_string(saida_dir.join(MANIFESTO_BUILD))
+        .ok()
+        .and_then(|t| serde_json::from_str::<serde_json::Value>(&t).ok())
+        .and_then(|v| v.get("artefatos").and_then(|a| a.as_object()).cloned())
+        .unwrap_or_default()
+}
+
+fn registrar_no_manifesto(
+    saida_dir: &Path,
+    nome_arquivo: &str,
+    relativo: Option<&Path>,
+) -> Result<()> {
+    let mut artefatos = ler_manifesto_build(saida_dir);
+    match relativo {
+        Some(r) => {
+            let r = r
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            artefatos.insert(nome_arquivo.to_string(), serde_json::Value::String(r));
+        }
+        None => {
+            if artefatos.remove(nome_arquivo).is_none() {
+                return Ok(());
+            }
+        }
+    }
+    let caminho = saida_dir.join(MANIFESTO_BUILD);
+    let json = serde_json::json!({ "artefatos": artefatos });
+    fs::write(&caminho, serde_json::to_string_pretty(&json)? + "\n")
+        .with_context(|| format!("Falha ao gravar {}", caminho.display()))
+}
+
+/// Caminho esperado de `nome_arquivo`: o local registrado no manifesto (se ainda existir),
+/// senao a raiz da pasta de saida.
+pub fn artefato_registrado(saida_dir: &Path, nome_arquivo: &str) -> PathBuf {
+    ler_manifesto_build(saida_dir)
+        .get(nome_arquivo)
+        .and_then(|r| r.as_str())
+        .map(|r| saida_dir.join(r))
+        .filter(|p| p.is_file())
+        .unwrap_or_else(|| saida_dir.join(nome_arquivo))
+}
+
+/// Localiza o artefato recem-gerado `nome_arquivo` (compiladores que gravam em subpastas).
+///
+/// Fora da raiz da pasta de saida, busca na arvore (profundidade limitada) os arquivos
+/// com esse nome escritos desde `inicio`; com exatamente um, registra o local no manifesto.
+pub fn localizar_artefato_gerado(
+    saida_dir: &Path,
+    nome_arquivo: &str,
+    inicio: SystemTime,
+) -> Result<PathBuf> {
+    let direto = saida_dir.join(nome_arquivo);
+    if direto.is_file() {
+        registrar_no_manifesto(saida_dir, nome_arquivo, None)?;
+        return Ok(direto);
+    }
+
+    let encontrados: Vec<PathBuf> = WalkDir::new(saida_dir)
+        .max_depth(PROFUNDIDADE_BUSCA_ARTEFATO)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file() && e.file_name() == OsStr::new(nome_arquivo))
+        .map(|e| e.into_path())
+        .collect();
+    // Trunca para o segundo: sistemas de arquivos com mtime de baixa resolucao
+    let limite = inicio
+        .duration_since(UNIX_EPOCH)
+        .map(|d| UNIX_EPOCH + Duration::from_secs(d.as_secs()))
+        .unwrap_or(inicio);
+    let recentes: Vec<&PathBuf> = encontrados
+        .iter()
+        .filter(|p| {
+            p.metadata()
+                .and_then(|m| m.modified())
+                .is_ok_and(|t| t >= limite)
+        })
+        .collect();
+
+    match recentes.as_slice() {
+        [unico] => {
+            let relativo = unico.strip_prefix(saida_dir).unwrap_or(unico);
+            registrar_no_manifesto(saida_dir, nome_arquivo, Some(relativo))?;
+            progresso!("Artefato encontrado em {}", unico.display());
+            Ok((*unico).clone())
+        }
+        outros => {
+            let lista = if encontrados.is_empty() {
+                "nenhum arquivo com esse nome".to_string()
+            } else {
+                encontrados
+                    .iter()
+                    .map(|p| {
+                        let antigo = if recentes.contains(&p) {
+                            ""
+                        } else {
+                            " (antigo)"
+                        };
+                        format!("{}{}", p.display(), antigo)
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            };
+            bail!(
+                "{} {} gerado(s) pela compilacao em {}; encontrados: {}",
+                if outros.is_empty() {
+                    "Nenhum artefato"
+                } else {
+                    "Mais de um artefato"
+                },
+                nome_arquivo,
+                saida_dir.display(),
+                lista
+            )
+        }
+    }
+}
+
+/// Localiza o artefato `<stem>.<ext>` recem-compilado e, com `--nome-saida`, copia para `<nome>.<ext>`.
+pub fn finalizar_artefato(
+    saida_dir: &Path,
+    stem: &str,
+    nome: &str,
+    ext: &str,
+    inicio: SystemTime,
+) -> Result<PathBuf> {
+    let gerado = localizar_artefato_gerado(saida_dir, &format!("{}.{}", stem, ext), inicio)?;
+    if nome == stem {
+        return Ok(gerado);
+    }
+    let destino = saida_dir.join(format!("{}.{}", nome, ext));
+    copiar_artefato_nomeado(&gerado, &destino)?;
+    Ok(destino)
+}
+
+fn extensao_artefato(alvo_flag: &str) -> Option<&'static str> {
+    match alvo_flag {
+        "--target=bytecode" => Some("pbc"),
+        "--target=llvm-ir" => Some("ll"),
+        _ => None,
+    }
+}
+
+/// `--dry-run`: mostra o comando que o build executaria (com a pasta de saida, onde ele
+/// roda); `true` se o build deve parar aqui.
+fn mostrar_dry_run(cmd: &Command) -> bool {
+    if !dry_run() {
+        return false;
+    }
+    println!("Executaria: {}", descrever_comando(cmd));
+    true
+}
+
+/// Resultado de `build`/`producao` com `--message-format json`.
+#[derive(Debug, serde::Serialize)]
+struct ResultadoCompilacao<'a> {
+    ok: bool,
+    perfil: &'a str,
+    target: &'a str,
+    arquivos: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    status: Option<i32>,
+    duracao_ms: u128,
+    diagnosticos: Vec<String>,
+}
+
+/// Linhas nao vazias do stdout e do stderr do compilador.
+fn diagnosticos(out: &std::process::Output) -> Vec<String> {
+    String::from_utf8_lossy(&out.stdout)
+        .lines()
+        .chain(String::from_utf8_lossy(&out.stderr).lines())
+        .filter(|l| !l.trim().is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Roda o compilador de `build`/`producao` (`cmd` ja montado) para `arquivos` fontes.
+///
+/// Com `--message-format json`, a saida dele vira um [`ResultadoCompilacao`] no stdout; com
+/// `capturar_saida`, so aparece na mensagem de erro. `falha` abre essa mensagem.
+fn executar_compilador(
+    cmd: &mut Command,
+    perfil: Perfil,
+    target: &str,
+    arquivos: usize,
+    capturar_saida: bool,
+    falha: &str,
+) -> Result<()> {
+    if let Some(jobs) = JOBS.get() {
+        cmd.env("PORDOSOL_JOBS", jobs.to_string());
+    }
+    detalhe!("Executando: {}", descrever_comando(cmd));
+    let formato = FORMATO_MENSAGENS.get().copied().unwrap_or_default();
+    if formato == FormatoMensagens::Json {
+        let inicio = Instant::now();
+        let out = cmd.output().context("Falha ao executar o compilador")?;
+        let resultado = ResultadoCompilacao {
+            ok: out.status.success(),
+            perfil: perfil.nome(),
+            target,
+            arquivos,
+            status: out.status.code(),
+            duracao_ms: inicio.elapsed().as_millis(),
+            diagnosticos: diagnosticos(&out),
+        };
+        println!("{}", serde_json::to_string_pretty(&resultado)?);
+        if !out.status.success() {
+            bail!("{} (status {})", falha, out.status);
+        }
+    } else if capturar_saida {
+        let out = cmd.output().context("Falha ao executar o compilador")?;
+        if !out.status.success() {
+            bail!(
+                "{} (status {})\n{}{}",
+                falha,
+                out.status,
+                String::from_utf8_lossy(&out.stdout),
+                String::from_utf8_lossy(&out.stderr)
             );
-            "--target=llvm-ir"
         }
+    } else {
+        let status = cmd.status().context("Falha ao executar o compilador")?;
+        if !status.success() {
+            bail!("{} (status {})", falha, status);
+        }
+    }
+    Ok(())
+}
+
+/// `--checksums`: grava [`crate::manifesto::ARQUIVO_CHECKSUMS`] com os artefatos do build;
+/// desligado, remove o de um build anterior, que nao valeria mais.
+fn gravar_checksums(saida_dir: &Path, manifesto: Option<&crate::manifesto::Manifesto>) {
+    let arquivo = saida_dir.join(crate::manifesto::ARQUIVO_CHECKSUMS);
+    if !CHECKSUMS.load(Ordering::Relaxed) {
+        fs::remove_file(arquivo).ok();
+        return;
+    }
+    let Some(manifesto) = manifesto else {
+        return;
+    };
+    if let Err(e) = crate::manifesto::gravar_checksums(saida_dir, &manifesto.artefatos) {
+        eprintln!("Aviso: nao foi possivel gravar os checksums: {:#}", e);
+    }
+}
+
+/// Flags do compilador que validam os fontes sem emitir artefatos, em ordem de preferencia.
+const FLAGS_SEM_EMISSAO: &[&str] = &["--apenas-verificar", "--no-emit"];
+
+/// Primeira das `flags` que aparece na ajuda (`--ajuda` ou `--help`) do compilador.
+fn procurar_na_ajuda(compilador: &Path, flags: &[&'static str]) -> Option<&'static str> {
+    let mut ajuda = String::new();
+    for flag in ["--ajuda", "--help"] {
+        if let Ok(out) = Command::new(compilador)
+            .arg(flag)
+            .stdin(Stdio::null())
+            .output()
+        {
+            ajuda.push_str(&String::from_utf8_lossy(&out.stdout));
+            ajuda.push_str(&String::from_utf8_lossy(&out.stderr));
+        }
+    }
+    flags.iter().copied().find(|f| ajuda.contains(f))
+}
+
+/// Procura na ajuda do compilador uma flag de verificacao sem emissao.
+fn detectar_flag_verificacao(compilador: &Path) -> Option<&'static str> {
+    procurar_na_ajuda(compilador, FLAGS_SEM_EMISSAO)
+}
+
+/// `check --formato-mensagens`: como os diagnosticos chegam ao usuario.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum FormatoMensagens {
+    /// Saida do compilador repassada como veio
+    #[default]
+    Texto,
+    /// Um objeto JSON com resultado e diagnosticos, para hooks e editores
+    Json,
+}
+
+/// Resultado de `check --formato-mensagens json`.
+#[derive(Debug, serde::Serialize)]
+struct ResultadoCheck {
+    ok: bool,
+    arquivos: usize,
+    /// `sem-emissao` (flag do backend) ou `bytecode-descartado`
+    modo: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    status: Option<i32>,
+    duracao_ms: u128,
+    diagnosticos: Vec<String>,
+}
+
+/// `check`: valida os fontes com o compilador sem gerar artefatos nem tocar na pasta de saida.
+///
+/// Usa a flag de verificacao do backend quando ela existe; senao compila para bytecode numa
+/// pasta temporaria que e descartada, entao `build/` (e o estado usado pelo `run`) fica intacta.
+pub fn check_cmd(caminho: &Path, formato: FormatoMensagens) -> Result<()> {
+    let raiz = localizar_raiz(caminho);
+    let arquivos = fontes_com_info_build(&raiz, Perfil::Dev)?;
+    let plano = resolver_plano(&raiz)?;
+    let compilador = localizar_compilador(&raiz).map_err(ErroCli::from)?.caminho;
+    let (flag, modo) = match detectar_flag_verificacao(&compilador) {
+        Some(flag) => (flag, "sem-emissao"),
+        None => ("--target=bytecode", "bytecode-descartado"),
     };
 
+    // Removida ao sair do escopo, inclusive em panico
+    let temporaria = tempfile::Builder::new()
+        .prefix("pordosol-check-")
+        .tempdir()
+        .context("Falha ao criar a pasta temporaria do check")?;
+    let mut cmd = Command::new(&compilador);
+    cmd.current_dir(temporaria.path())
+        .arg(flag)
+        .args(flags_compilador(&raiz)?)
+        .args(&arquivos)
+        .stdin(Stdio::null());
+    plano.aplicar(&mut cmd);
+    let inicio = Instant::now();
+    detalhe!("Executando: {}", descrever_comando(&cmd));
+    let resultado = match formato {
+        FormatoMensagens::Texto => cmd.status().map(|s| (s, Vec::new())),
+        FormatoMensagens::Json => cmd.output().map(|out| (out.status, diagnosticos(&out))),
+    };
+    drop(temporaria);
+    let (status, diagnosticos) = resultado.context("Falha ao executar o compilador")?;
+    let duracao_ms = inicio.elapsed().as_millis();
+
+    if formato == FormatoMensagens::Json {
+        let resultado = ResultadoCheck {
+            ok: status.success(),
+            arquivos: arquivos.len(),
+            modo,
+            status: status.code(),
+            duracao_ms,
+            diagnosticos,
+        };
+        println!("{}", serde_json::to_string_pretty(&resultado)?);
+    }
+    if !status.success() {
+        bail!(
+            "Verificacao falhou (status {}) em {} arquivo(s)",
+            status,
+            arquivos.len()
+        );
+    }
+    if formato == FormatoMensagens::Texto {
+        progresso!(
+            "Verificacao concluida: {} arquivo(s) sem erros em {} ms.",
+            arquivos.len(),
+            duracao_ms
+        );
+    }
+    Ok(())
+}
+
+/// Compila com o perfil de producao: target llvm-ir e saida em `<pasta_saida>/producao`.
+///
+/// `saida` substitui a pasta, como em `compilar --saida`.
+pub fn producao_cmd(
+    caminho: &Path,
+    target: &str,
+    saida: Option<&Path>,
+    timings: bool,
+    cache: Option<&CacheDependencias>,
+) -> Result<Option<SituacaoCache>> {
+    let mut cronometro = Cronometro::iniciar(timings);
+    let (raiz, marcador) = localizar_raiz_com_marcador(caminho);
+    detalhe!(
+        "Raiz do projeto: {} (marcador: {})",
+        raiz.display(),
+        marcador
+    );
+    let alvo_flag = resolver_flag_target(target, TARGETS_PRODUCAO)?;
+    let target_final = alvo_flag.trim_start_matches("--target=");
+
+    let saida_dir = saida
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| Perfil::Producao.saida_dir(&raiz));
+    validar_pasta_saida(&raiz, &saida_dir)?;
+
+    // Antes da descoberta: um hook pode gerar fontes
+    if !dry_run() && executar_hooks(&raiz, FaseHook::PreBuild, target_final, &saida_dir)? {
+        cronometro.etapa("hooks pre_build");
+    }
+
+    let arquivos: Vec<PathBuf> =
+        if caminho.is_file() && caminho.extension() == Some(OsStr::new("pr")) {
+            vec![caminho.to_path_buf()]
+        } else {
+            fontes_com_info_build(&raiz, Perfil::Producao)?
+        };
+
+    let mut plano = resolver_plano(&raiz)?;
+    cronometro.etapa("descoberta de fontes");
+
+    let compilador = localizar_compilador(&raiz).map_err(ErroCli::from)?.caminho;
+    let mut situacao = None;
+    if let Some(cache) = cache.filter(|_| !dry_run()) {
+        situacao = precompilar_dependencias(&raiz, &mut plano, &alvo_flag, &compilador, cache)?;
+        cronometro.etapa("dependencias");
+    }
+    if !dry_run() {
+        fs::create_dir_all(&saida_dir).ok();
+    }
+
     let mut cmd = Command::new(&compilador);
     cmd.current_dir(&saida_dir)
-        .arg(alvo_flag)
+        .arg(&alvo_flag)
+        .args(flags_compilador(&raiz)?)
         .stdin(Stdio::null());
     for arq in &arquivos {
         cmd.arg(arq);
     }
+    plano.aplicar(&mut cmd);
+    if mostrar_dry_run(&cmd) {
+        return Ok(situacao);
+    }
 
-    let status = cmd
-        .status()
-        .context("Falha ao executar o compilador (producao)")?;
-    if !status.success() {
-        bail!("Compilacao de producao falhou (status {})", status);
+    let inicio = SystemTime::now();
+    cronometro.descartar();
+    executar_compilador(
+        &mut cmd,
+        Perfil::Producao,
+        target_final,
+        arquivos.len(),
+        false,
+        "Compilacao de producao falhou",
+    )?;
+    cronometro.etapa("compilador");
+    let manifesto = crate::manifesto::registrar_build(
+        &raiz,
+        &saida_dir,
+        "producao",
+        target_final,
+        &compilador,
+        &arquivos,
+        inicio,
+    );
+    gravar_checksums(&saida_dir, manifesto.as_ref());
+
+    progresso!("Producao concluida. Artefatos em {}", saida_dir.display());
+    if executar_hooks(&raiz, FaseHook::PostBuild, target_final, &saida_dir)? {
+        cronometro.etapa("hooks post_build");
     }
+    cronometro.concluir(&pasta_saida_projeto(&raiz), "producao", target_final);
+    Ok(situacao)
+}
 
-    println!("Producao concluida. Artefatos em {}", saida_dir.display());
-    Ok(())
+/// Chave de `dep` no cache de dependencias do workspace: target, compilador (caminho e versao),
+/// flags do membro, includes, fontes da dependencia (caminho e conteudo) e stdlib.
+fn chave_dependencia(
+    raiz: &Path,
+    dep: &DependenciaResolvida,
+    alvo_flag: &str,
+    compilador: &Path,
+) -> Result<String> {
+    let mut hash = Fnv1a::novo();
+    let mut campo = |bytes: &[u8]| {
+        hash.escrever(bytes);
+        hash.escrever(&[0]);
+    };
+    campo(alvo_flag.as_bytes());
+    campo(compilador.as_os_str().as_encoded_bytes());
+    campo(
+        detectar_versao_binario(compilador)
+            .unwrap_or_default()
+            .as_bytes(),
+    );
+    for flag in flags_compilador(raiz)? {
+        campo(flag.as_bytes());
+    }
+    // Normalizados: cada membro chega a mesma dependencia por outro `../`
+    for inc in &dep.includes {
+        campo(inc.absolutize()?.as_os_str().as_encoded_bytes());
+    }
+    for arquivo in &dep.arquivos {
+        campo(arquivo.absolutize()?.as_os_str().as_encoded_bytes());
+        campo(Impressao::de_arquivo(arquivo)?.hash.as_bytes());
+    }
+    if let Some(stdlib) = impressao_stdlib(raiz) {
+        campo(stdlib.hash().as_bytes());
+    }
+    Ok(hash.hex())
+}
+
+/// Troca as fontes de cada dependencia do `plano` pelos artefatos dela no `cache`, compilando
+/// (uma vez por chave) as que ainda nao estao la. Recompilado se alguma precisou do compilador.
+fn precompilar_dependencias(
+    raiz: &Path,
+    plano: &mut PlanoInclusao,
+    alvo_flag: &str,
+    compilador: &Path,
+    cache: &CacheDependencias,
+) -> Result<Option<SituacaoCache>> {
+    let target = alvo_flag.trim_start_matches("--target=");
+    let mut situacao = None;
+    for dep in plano.dependencias.iter_mut() {
+        if dep.arquivos.is_empty() {
+            continue;
+        }
+        let chave = chave_dependencia(raiz, dep, alvo_flag, compilador)?;
+        let (artefatos, situacao_dep) = cache.obter(&chave, &dep.nome, |pasta| {
+            progresso!("Compilando a dependencia {} para o cache...", dep.nome);
+            let mut cmd = Command::new(compilador);
+            cmd.current_dir(pasta)
+                .arg(alvo_flag)
+                .args(flags_compilador(raiz)?)
+                .args(dep.argumentos_incluir())
+                .args(&dep.arquivos)
+                .stdin(Stdio::null());
+            executar_compilador(
+                &mut cmd,
+                Perfil::Producao,
+                target,
+                dep.arquivos.len(),
+                true,
+                &format!("Compilacao da dependencia {} falhou", dep.nome),
+            )
+        })?;
+        if situacao_dep == SituacaoCache::Reaproveitado {
+            progresso!("Dependencia {} reaproveitada do cache", dep.nome);
+        }
+        if situacao != Some(SituacaoCache::Recompilado) {
+            situacao = Some(situacao_dep);
+        }
+        dep.arquivos = artefatos;
+    }
+    Ok(situacao)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn saida_sobreposta_aos_fontes() {
+        let temp = tempfile::tempdir().unwrap();
+        let raiz = temp.path();
+        let src = raiz.join("src");
+        fs::create_dir_all(&src).unwrap();
+
+        assert_eq!(sobreposicao_saida(&src, &src), Some("e a mesma que"));
+        assert_eq!(
+            sobreposicao_saida(&raiz.join("src/../src"), &src),
+            Some("e a mesma que")
+        );
+        assert_eq!(sobreposicao_saida(raiz, &src), Some("contem"));
+        assert_eq!(
+            sobreposicao_saida(&src.join("build"), &src),
+            Some("fica dentro de")
+        );
+        assert_eq!(sobreposicao_saida(&raiz.join("build"), &src), None);
+        assert_eq!(sobreposicao_saida(&raiz.join("srcs"), &src), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn saida_por_symlink_para_os_fontes() {
+        let temp = tempfile::tempdir().unwrap();
+        let src = temp.path().join("src");
+        fs::create_dir_all(&src).unwrap();
+        let atalho = temp.path().join("build");
+        std::os::unix::fs::symlink(&src, &atalho).unwrap();
+
+        assert_eq!(sobreposicao_saida(&atalho, &src), Some("e a mesma que"));
+        assert_eq!(
+            sobreposicao_saida(&atalho.join("dev"), &src),
+            Some("fica dentro de")
+        );
+    }
+
+    #[test]
+    fn mensagem_de_toolchain_incompativel() {
+        let req = Requisito::parse(">=0.5.0").unwrap();
+        assert_eq!(
+            mensagem_toolchain_incompativel(
+                Some("0.4.2"),
+                &req,
+                Path::new("/opt/pds/compilador"),
+                "PATH"
+            ),
+            "compilador 0.4.2 encontrado, requerido >=0.5.0 (pordosol.proj), caminho /opt/pds/compilador, origem PATH. Instale uma versao compativel ou use --ignorar-toolchain."
+        );
+        assert!(
+            mensagem_toolchain_incompativel(None, &req, Path::new("c"), "env:X")
+                .starts_with("compilador sem versao reconhecivel, requerido >=0.5.0")
+        );
+    }
+
+    #[test]
+    fn requisito_lido_do_projeto() {
+        let temp = tempfile::tempdir().unwrap();
+        let raiz = temp.path();
+        assert_eq!(requisito_compilador(raiz).unwrap(), None);
+
+        fs::write(
+            raiz.join("pordosol.proj"),
+            r#"{"toolchain": {"compilador": "^0.5"}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            requisito_compilador(raiz).unwrap().unwrap().to_string(),
+            "^0.5.0"
+        );
+
+        fs::write(
+            raiz.join("pordosol.proj"),
+            r#"{"toolchain": {"compilador": "cinco"}}"#,
+        )
+        .unwrap();
+        assert!(requisito_compilador(raiz).is_err());
+    }
+
+    #[test]
+    fn regra_de_dependencias_escapa_caminhos() {
+        let regra = regra_dependencias(
+            Path::new("/p/build/programa.pbc"),
+            &[
+                PathBuf::from("/p/src/programa.pr"),
+                PathBuf::from("/p/src/meu modulo.pr"),
+                PathBuf::from("/p/src/a#$b.pr"),
+            ],
+        );
+        assert_eq!(
+            regra,
+            "/p/build/programa.pbc: \\\n  /p/src/programa.pr \\\n  /p/src/meu\\ modulo.pr \\\n  /p/src/a\\#$$b.pr\n"
+        );
+    }
 }
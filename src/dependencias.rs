@@ -0,0 +1,708 @@
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::erros::ErroCli;
+use crate::git;
+use crate::impressoes::Fnv1a;
+use crate::projeto::DocumentoProjeto;
+use crate::toolchain::listar_prs;
+
+/// Pasta, na raiz do projeto, onde `dep install` copia as fontes das dependencias `path`.
+pub const PASTA_VENDOR: &str = "deps";
+
+/// Registro do que `dep install` copiou para `deps/`, na raiz do projeto.
+pub const ARQUIVO_LOCK: &str = "pordosol.lock";
+
+/// `build --com-deps`: dependencias `path` instaladas vem de `deps/<nome>`.
+static COM_DEPS: AtomicBool = AtomicBool::new(false);
+
+pub fn definir_com_deps(com_deps: bool) {
+    COM_DEPS.store(com_deps, Ordering::Relaxed);
+}
+
+/// Como uma dependencia foi resolvida.
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "tipo", rename_all = "lowercase")]
+pub enum OrigemDependencia {
+    /// `{"path": "..."}`, relativo a raiz do projeto
+    Path { caminho: PathBuf },
+    /// `{"git": "...", "rev": "..."}`, com checkout em `.pordosol/deps/<nome>`
+    Git {
+        url: String,
+        commit: Option<String>,
+        checkout: PathBuf,
+    },
+    /// Versao de registro (`"1.0"`), ainda sem fontes locais
+    Registro { versao: String },
+}
+
+impl OrigemDependencia {
+    fn descricao(&self) -> String {
+        match self {
+            OrigemDependencia::Path { caminho } => format!("path {}", caminho.display()),
+            OrigemDependencia::Git { url, commit, .. } => format!(
+                "git {} no commit {}",
+                url,
+                commit.as_deref().unwrap_or("desconhecido")
+            ),
+            OrigemDependencia::Registro { versao } => format!("registro (versao {})", versao),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct DependenciaResolvida {
+    pub nome: String,
+    pub origem: OrigemDependencia,
+    pub arquivos: Vec<PathBuf>,
+    pub includes: Vec<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub aviso: Option<String>,
+}
+
+/// Duas ou mais dependencias fornecendo fontes com o mesmo stem.
+#[derive(Clone, Debug, Serialize)]
+pub struct ConflitoStem {
+    pub stem: String,
+    pub dependencias: Vec<String>,
+}
+
+/// Fontes e pastas de include que as dependencias acrescentam a chamada do compilador.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct PlanoInclusao {
+    pub dependencias: Vec<DependenciaResolvida>,
+    pub conflitos: Vec<ConflitoStem>,
+}
+
+impl DependenciaResolvida {
+    /// `--incluir=<pasta>` de cada include da dependencia.
+    pub fn argumentos_incluir(&self) -> Vec<OsString> {
+        self.includes
+            .iter()
+            .map(|inc| {
+                let mut a = OsString::from("--incluir=");
+                a.push(inc);
+                a
+            })
+            .collect()
+    }
+}
+
+impl PlanoInclusao {
+    /// Argumentos extras do compilador, na ordem em que sao acrescentados.
+    pub fn argumentos(&self) -> Vec<OsString> {
+        let mut args = Vec::new();
+        for dep in &self.dependencias {
+            args.extend(dep.argumentos_incluir());
+        }
+        for dep in &self.dependencias {
+            args.extend(dep.arquivos.iter().map(|a| a.as_os_str().to_owned()));
+        }
+        args
+    }
+
+    /// Acrescenta o plano ao comando do compilador, avisando sobre conflitos.
+    pub fn aplicar(&self, cmd: &mut Command) {
+        for c in &self.conflitos {
+            eprintln!(
+                "Aviso: '{}' e fornecido por mais de uma dependencia ({}).",
+                c.stem,
+                c.dependencias.join(", ")
+            );
+        }
+        cmd.args(self.argumentos());
+    }
+
+    /// Todas as fontes das dependencias (usado na checagem de artefato desatualizado).
+    pub fn arquivos(&self) -> impl Iterator<Item = &PathBuf> {
+        self.dependencias.iter().flat_map(|d| d.arquivos.iter())
+    }
+}
+
+/// Resolve as `dependencias` do pordosol.proj em um plano de inclusao.
+///
+/// Sem pordosol.proj o plano e vazio. Esta e a mesma funcao usada pelo build,
+/// entao `dep resolve` mostra exatamente o que o compilador recebe.
+pub fn resolver_plano(raiz: &Path) -> Result<PlanoInclusao> {
+    let proj = raiz.join("pordosol.proj");
+    if !proj.exists() {
+        return Ok(PlanoInclusao::default());
+    }
+    let mut plano = PlanoInclusao::default();
+    for (nome, valor) in declaradas(raiz)? {
+        plano
+            .dependencias
+            .push(resolver_dependencia(raiz, &nome, &valor)?);
+    }
+
+    let mut por_stem: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for dep in &plano.dependencias {
+        for arq in &dep.arquivos {
+            let stem = arq
+                .file_stem()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string();
+            let donos = por_stem.entry(stem).or_default();
+            if !donos.contains(&dep.nome) {
+                donos.push(dep.nome.clone());
+            }
+        }
+    }
+    plano.conflitos = por_stem
+        .into_iter()
+        .filter(|(_, donos)| donos.len() > 1)
+        .map(|(stem, dependencias)| ConflitoStem { stem, dependencias })
+        .collect();
+
+    Ok(plano)
+}
+
+fn resolver_dependencia(
+    raiz: &Path,
+    nome: &str,
+    valor: &serde_json::Value,
+) -> Result<DependenciaResolvida> {
+    let invalida = |msg: String| -> anyhow::Error { ErroCli::ProjetoInvalido(msg).into() };
+
+    if let Some(versao) = valor.as_str() {
+        return Ok(DependenciaResolvida {
+            nome: nome.to_string(),
+            origem: OrigemDependencia::Registro {
+                versao: versao.to_string(),
+            },
+            arquivos: Vec::new(),
+            includes: Vec::new(),
+            aviso: Some("dependencias de registro ainda nao fornecem fontes locais".to_string()),
+        });
+    }
+
+    let obj = valor.as_object().ok_or_else(|| {
+        invalida(format!(
+            "Dependencia '{}' invalida: esperado texto de versao ou objeto",
+            nome
+        ))
+    })?;
+
+    if let Some(p) = obj.get("path") {
+        let p = p
+            .as_str()
+            .ok_or_else(|| invalida(format!("Dependencia '{}': 'path' deve ser texto", nome)))?;
+        let caminho = raiz.join(p);
+        if !caminho.is_dir() {
+            return Err(invalida(format!(
+                "Dependencia '{}': pasta {} nao encontrada",
+                nome,
+                caminho.display()
+            )));
+        }
+        let mut aviso = None;
+        let (arquivos, includes) = if COM_DEPS.load(Ordering::Relaxed) {
+            let vendor = raiz.join(PASTA_VENDOR).join(nome);
+            if vendor.is_dir() {
+                (fontes_vendorizadas(&vendor), vec![vendor])
+            } else {
+                aviso = Some(format!(
+                    "nao instalada em {}/; usando a pasta original (rode `pordosol dep install`)",
+                    PASTA_VENDOR
+                ));
+                fontes_da_pasta(&caminho)
+            }
+        } else {
+            fontes_da_pasta(&caminho)
+        };
+        return Ok(DependenciaResolvida {
+            nome: nome.to_string(),
+            origem: OrigemDependencia::Path { caminho },
+            arquivos,
+            includes,
+            aviso,
+        });
+    }
+
+    if let Some(url) = obj.get("git") {
+        let url = url
+            .as_str()
+            .ok_or_else(|| invalida(format!("Dependencia '{}': 'git' deve ser texto", nome)))?;
+        let rev = obj.get("rev").and_then(|r| r.as_str());
+        let checkout = raiz.join(".pordosol").join("deps").join(nome);
+        if !checkout.is_dir() {
+            return Ok(DependenciaResolvida {
+                nome: nome.to_string(),
+                origem: OrigemDependencia::Git {
+                    url: url.to_string(),
+                    commit: rev.map(str::to_string),
+                    checkout: checkout.clone(),
+                },
+                arquivos: Vec::new(),
+                includes: Vec::new(),
+                aviso: Some(format!("checkout ausente em {}", checkout.display())),
+            });
+        }
+        let commit = git::commit_atual(&checkout).or_else(|| rev.map(str::to_string));
+        let (arquivos, includes) = fontes_da_pasta(&checkout);
+        return Ok(DependenciaResolvida {
+            nome: nome.to_string(),
+            origem: OrigemDependencia::Git {
+                url: url.to_string(),
+                commit,
+                checkout,
+            },
+            arquivos,
+            includes,
+            aviso: None,
+        });
+    }
+
+    Err(invalida(format!(
+        "Dependencia '{}' sem 'path' ou 'git'",
+        nome
+    )))
+}
+
+/// Operadores aceitos num requisito de versao, os de dois caracteres primeiro.
+const OPERADORES_VERSAO: &[&str] = &[">=", "<=", "^", "~", ">", "<", "="];
+
+/// Valida a versao de `dep add`: semver (`1.2.3`, `1.0.0-beta.1`) ou requisito simples.
+///
+/// Requisitos aceitos: `*`, versoes parciais (`1.2`, `1.*`) e comparadores
+/// (`^1.2`, `~1.2.3`, `>=1.0`, `<2.0`) separados por virgula.
+pub fn validar_requisito_versao(requisito: &str) -> Result<()> {
+    let requisito = requisito.trim();
+    let invalida = || {
+        anyhow::anyhow!(
+            "Versao invalida '{}': esperado semver (ex.: 1.2.3) ou requisito (^1.2, ~1.2.3, >=1.0, <2.0, *)",
+            requisito
+        )
+    };
+    if requisito == "*" {
+        return Ok(());
+    }
+    if requisito.is_empty() {
+        return Err(invalida());
+    }
+    for comparador in requisito.split(',') {
+        let comparador = comparador.trim();
+        let (operador, versao) = OPERADORES_VERSAO
+            .iter()
+            .find_map(|op| comparador.strip_prefix(op).map(|resto| (*op, resto)))
+            .unwrap_or(("", comparador));
+        let versao = versao.trim_start();
+        // `>=1.*` nao tem significado; coringas so sem operador ou com `=`
+        let aceita_coringa = operador.is_empty() || operador == "=";
+        if !versao_parcial_valida(versao, aceita_coringa) {
+            return Err(invalida());
+        }
+    }
+    Ok(())
+}
+
+/// `MAJOR[.MINOR[.PATCH[-pre][+build]]]`, com `*`/`x` no lugar dos componentes finais.
+fn versao_parcial_valida(versao: &str, aceita_coringa: bool) -> bool {
+    let (nucleo, build) = match versao.split_once('+') {
+        Some((n, b)) => (n, Some(b)),
+        None => (versao, None),
+    };
+    let (nucleo, pre) = match nucleo.split_once('-') {
+        Some((n, p)) => (n, Some(p)),
+        None => (nucleo, None),
+    };
+    let componentes: Vec<&str> = nucleo.split('.').collect();
+    if componentes.len() > 3 {
+        return false;
+    }
+    let mut coringa = false;
+    for c in &componentes {
+        if matches!(*c, "*" | "x" | "X") {
+            if !aceita_coringa {
+                return false;
+            }
+            coringa = true;
+        } else if coringa || !numero_semver(c) {
+            return false;
+        }
+    }
+    // Pre-release e build so fazem sentido numa versao completa
+    if (pre.is_some() || build.is_some()) && (componentes.len() != 3 || coringa) {
+        return false;
+    }
+    let identificadores_ok = |texto: &str, numericos: bool| {
+        texto.split('.').all(|id| {
+            !id.is_empty()
+                && id.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-')
+                && (!numericos || !id.bytes().all(|b| b.is_ascii_digit()) || numero_semver(id))
+        })
+    };
+    pre.is_none_or(|p| identificadores_ok(p, true))
+        && build.is_none_or(|b| identificadores_ok(b, false))
+}
+
+/// Inteiro sem zeros a esquerda, como o semver exige.
+fn numero_semver(texto: &str) -> bool {
+    !texto.is_empty()
+        && texto.bytes().all(|b| b.is_ascii_digit())
+        && (texto == "0" || !texto.starts_with('0'))
+}
+
+/// Valida `dep add --caminho`: pasta de outro projeto pordosol, relativa a raiz.
+///
+/// Caminho que ainda nao existe so gera aviso (a dependencia pode ser criada depois).
+pub fn validar_caminho_dependencia(raiz: &Path, caminho: &Path) -> Result<()> {
+    let alvo = raiz.join(caminho);
+    if !alvo.exists() {
+        eprintln!(
+            "Aviso: {} ainda nao existe; `dep resolve` falhara ate a pasta ser criada.",
+            alvo.display()
+        );
+        return Ok(());
+    }
+    if !alvo.is_dir() {
+        bail!("--caminho deve apontar uma pasta: {}", alvo.display());
+    }
+    if !alvo.join("pordosol.proj").is_file() {
+        bail!(
+            "{} nao parece um projeto pordosol (pordosol.proj ausente)",
+            alvo.display()
+        );
+    }
+    Ok(())
+}
+
+fn fontes_da_pasta(pasta: &Path) -> (Vec<PathBuf>, Vec<PathBuf>) {
+    let src = pasta.join("src");
+    let includes = if src.is_dir() { vec![src] } else { Vec::new() };
+    (listar_prs(pasta), includes)
+}
+
+/// `.pr` de `deps/<nome>`, que ja espelha o `src/` da dependencia.
+fn fontes_vendorizadas(pasta: &Path) -> Vec<PathBuf> {
+    let mut arquivos: Vec<PathBuf> = walkdir::WalkDir::new(pasta)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .map(|e| e.into_path())
+        .filter(|p| p.is_file() && p.extension().is_some_and(|e| e == "pr"))
+        .collect();
+    arquivos.sort();
+    arquivos
+}
+
+/// Conteudo de `pordosol.lock`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ArquivoLock {
+    #[serde(default)]
+    pub dependencias: BTreeMap<String, EntradaLock>,
+}
+
+/// Uma dependencia `path` copiada para `deps/<nome>`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EntradaLock {
+    /// Valor de `path` no pordosol.proj
+    pub path: String,
+    /// FNV-1a dos caminhos e conteudos copiados; igual = nada a copiar
+    pub hash: String,
+    /// Arquivos copiados, relativos a `deps/<nome>`
+    pub arquivos: Vec<String>,
+}
+
+fn ler_lock(raiz: &Path) -> Result<ArquivoLock> {
+    let caminho = raiz.join(ARQUIVO_LOCK);
+    if !caminho.exists() {
+        return Ok(ArquivoLock::default());
+    }
+    let texto = fs::read_to_string(&caminho)
+        .with_context(|| format!("Falha ao ler {}", caminho.display()))?;
+    serde_json::from_str(&texto).with_context(|| format!("{} invalido", caminho.display()))
+}
+
+/// Hash dos caminhos e conteudos copiados, estavel entre execucoes.
+fn hash_fontes(fontes: &[(String, Vec<u8>)]) -> String {
+    let mut h = Fnv1a::novo();
+    for (rel, conteudo) in fontes {
+        h.escrever(rel.as_bytes());
+        h.escrever(&[0]);
+        h.escrever(conteudo);
+        h.escrever(&[0]);
+    }
+    h.hex()
+}
+
+/// Nome usado como pasta em `deps/`: nao pode sair dela.
+fn nome_pasta_valido(nome: &str) -> bool {
+    !nome.is_empty() && nome != "." && nome != ".." && !nome.contains(['/', '\\'])
+}
+
+/// `pordosol dep install`: copia o `src/` de cada dependencia `path` para `deps/<nome>`.
+///
+/// Dependencias cujo conteudo nao mudou desde o ultimo install sao puladas; as que
+/// sairam do pordosol.proj tem a pasta removida. O resultado fica em `pordosol.lock`.
+pub fn instalar_cmd(raiz: &Path) -> Result<()> {
+    let anterior = ler_lock(raiz)?;
+    let mut novo = ArquivoLock::default();
+    let vendor = raiz.join(PASTA_VENDOR);
+
+    for (nome, valor) in declaradas(raiz)? {
+        let Some(path) = valor.get("path").and_then(|p| p.as_str()) else {
+            println!(
+                "  {}: ignorada (apenas dependencias path sao instaladas)",
+                nome
+            );
+            continue;
+        };
+        if !nome_pasta_valido(&nome) {
+            return Err(ErroCli::ProjetoInvalido(format!(
+                "Dependencia '{}': nome invalido para uma pasta em {}/",
+                nome, PASTA_VENDOR
+            ))
+            .into());
+        }
+        let dep = resolver_dependencia(raiz, &nome, &valor)?;
+        let OrigemDependencia::Path { caminho } = &dep.origem else {
+            continue;
+        };
+        let src = caminho.join("src");
+        let mut fontes = Vec::new();
+        for arq in listar_prs(caminho) {
+            let rel = arq.strip_prefix(&src).unwrap_or(&arq);
+            let conteudo =
+                fs::read(&arq).with_context(|| format!("Falha ao ler {}", arq.display()))?;
+            fontes.push((rel.to_string_lossy().replace('\\', "/"), conteudo));
+        }
+        fontes.sort();
+
+        let destino = vendor.join(&nome);
+        let entrada = EntradaLock {
+            path: path.to_string(),
+            hash: hash_fontes(&fontes),
+            arquivos: fontes.iter().map(|(rel, _)| rel.clone()).collect(),
+        };
+        if anterior.dependencias.get(&nome) == Some(&entrada) && destino.is_dir() {
+            println!("  {}: sem mudancas", nome);
+        } else {
+            if destino.exists() {
+                fs::remove_dir_all(&destino)
+                    .with_context(|| format!("Falha ao limpar {}", destino.display()))?;
+            }
+            for (rel, conteudo) in &fontes {
+                let alvo = destino.join(rel);
+                if let Some(pai) = alvo.parent() {
+                    fs::create_dir_all(pai)?;
+                }
+                fs::write(&alvo, conteudo)
+                    .with_context(|| format!("Falha ao gravar {}", alvo.display()))?;
+            }
+            fs::create_dir_all(&destino)?;
+            println!("  {}: {} arquivo(s) copiado(s)", nome, fontes.len());
+        }
+        novo.dependencias.insert(nome, entrada);
+    }
+
+    for nome in anterior.dependencias.keys() {
+        if novo.dependencias.contains_key(nome) || !nome_pasta_valido(nome) {
+            continue;
+        }
+        let pasta = vendor.join(nome);
+        if pasta.exists() {
+            fs::remove_dir_all(&pasta)
+                .with_context(|| format!("Falha ao remover {}", pasta.display()))?;
+        }
+        println!("  {}: removida de {}/", nome, PASTA_VENDOR);
+    }
+
+    let lock = raiz.join(ARQUIVO_LOCK);
+    if novo.dependencias.is_empty() && !lock.exists() {
+        println!("Nenhuma dependencia path para instalar.");
+        return Ok(());
+    }
+    fs::write(&lock, serde_json::to_string_pretty(&novo)? + "\n")
+        .with_context(|| format!("Falha ao gravar {}", lock.display()))?;
+    println!(
+        "{} dependencia(s) em {}/ (registro em {}).",
+        novo.dependencias.len(),
+        PASTA_VENDOR,
+        ARQUIVO_LOCK
+    );
+    Ok(())
+}
+
+/// `pordosol dep resolve`: imprime o plano de inclusao do projeto atual.
+pub fn resolve_cmd(raiz: &Path, json: bool) -> Result<()> {
+    let plano = resolver_plano(raiz)?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&plano)?);
+        return Ok(());
+    }
+
+    if plano.dependencias.is_empty() {
+        println!("Nenhuma dependencia declarada.");
+        return Ok(());
+    }
+
+    println!("Plano de inclusao:");
+    for dep in &plano.dependencias {
+        println!("  {} ({})", dep.nome, dep.origem.descricao());
+        for inc in &dep.includes {
+            println!("    include: {}", inc.display());
+        }
+        for arq in &dep.arquivos {
+            println!("    fonte:   {}", arq.display());
+        }
+        if let Some(aviso) = &dep.aviso {
+            println!("    aviso:   {}", aviso);
+        }
+    }
+
+    if !plano.conflitos.is_empty() {
+        println!();
+        println!("Conflitos de stem:");
+        for c in &plano.conflitos {
+            println!("  {}: {}", c.stem, c.dependencias.join(", "));
+        }
+    }
+    Ok(())
+}
+
+/// No de `dep tree`: uma dependencia e, para dependencias `path`, as dela.
+#[derive(Clone, Debug, Serialize)]
+pub struct NoDependencia {
+    pub nome: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub origem: Option<OrigemDependencia>,
+    /// A pasta ja aparece entre os ancestrais; nao ha recursao
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub ciclo: bool,
+    /// Ha dependencias abaixo deste no, omitidas por `--profundidade`
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub truncado: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub aviso: Option<String>,
+    pub dependencias: Vec<NoDependencia>,
+}
+
+/// Monta a arvore de dependencias de `raiz`, seguindo recursivamente as dependencias `path`.
+///
+/// `profundidade` limita quantos niveis abaixo da raiz sao expandidos (`None` = sem limite).
+pub fn arvore_dependencias(raiz: &Path, profundidade: Option<usize>) -> Result<Vec<NoDependencia>> {
+    let mut ancestrais = vec![canonico(raiz)];
+    expandir(raiz, 1, profundidade, &mut ancestrais)
+}
+
+fn canonico(p: &Path) -> PathBuf {
+    p.canonicalize().unwrap_or_else(|_| p.to_path_buf())
+}
+
+/// `dependencias` na ordem do pordosol.proj, com as do ambiente ativo mescladas.
+fn declaradas(raiz: &Path) -> Result<Vec<(String, serde_json::Value)>> {
+    let proj = raiz.join("pordosol.proj");
+    if !proj.exists() {
+        return Ok(Vec::new());
+    }
+    let mut entradas = DocumentoProjeto::ler(&proj)?
+        .objeto("dependencias")?
+        .into_iter()
+        .map(|(nome, bruto)| Ok((nome, serde_json::from_str(bruto.get())?)))
+        .collect::<Result<Vec<_>>>()?;
+    if let Some((_, camada)) = crate::ambiente::carregar_ambiente(raiz) {
+        crate::ambiente::mesclar_entradas(&mut entradas, camada.get("dependencias"));
+    }
+    Ok(entradas)
+}
+
+fn expandir(
+    raiz: &Path,
+    nivel: usize,
+    profundidade: Option<usize>,
+    ancestrais: &mut Vec<PathBuf>,
+) -> Result<Vec<NoDependencia>> {
+    let mut nos = Vec::new();
+    for (nome, valor) in declaradas(raiz)? {
+        let mut no = NoDependencia {
+            nome: nome.clone(),
+            origem: None,
+            ciclo: false,
+            truncado: false,
+            aviso: None,
+            dependencias: Vec::new(),
+        };
+        match resolver_dependencia(raiz, &nome, &valor) {
+            Ok(dep) => {
+                no.aviso = dep.aviso;
+                if let OrigemDependencia::Path { caminho } = &dep.origem {
+                    let alvo = canonico(caminho);
+                    if ancestrais.contains(&alvo) {
+                        no.ciclo = true;
+                    } else if profundidade.is_some_and(|max| nivel >= max) {
+                        no.truncado = !declaradas(caminho).unwrap_or_default().is_empty();
+                    } else {
+                        ancestrais.push(alvo);
+                        let filhos = expandir(caminho, nivel + 1, profundidade, ancestrais);
+                        ancestrais.pop();
+                        no.dependencias = filhos?;
+                    }
+                }
+                no.origem = Some(dep.origem);
+            }
+            Err(e) => no.aviso = Some(e.to_string()),
+        }
+        nos.push(no);
+    }
+    Ok(nos)
+}
+
+/// `pordosol dep tree`: imprime a arvore de dependencias (transitiva para dependencias path).
+pub fn tree_cmd(raiz: &Path, profundidade: Option<usize>, json: bool) -> Result<()> {
+    let arvore = arvore_dependencias(raiz, profundidade)?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&arvore)?);
+        return Ok(());
+    }
+
+    let nome = DocumentoProjeto::ler(&raiz.join("pordosol.proj"))
+        .ok()
+        .and_then(|d| {
+            d.obter("nome")
+                .and_then(|v| serde_json::from_str::<String>(v.get()).ok())
+        })
+        .unwrap_or_else(|| {
+            raiz.file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string()
+        });
+    println!("{}", nome);
+    if arvore.is_empty() {
+        println!("  (nenhuma dependencia declarada)");
+    }
+    imprimir_nos(&arvore, 1);
+    Ok(())
+}
+
+fn imprimir_nos(nos: &[NoDependencia], nivel: usize) {
+    for no in nos {
+        let origem = no
+            .origem
+            .as_ref()
+            .map(|o| format!(" ({})", o.descricao()))
+            .unwrap_or_default();
+        let marca = if no.ciclo {
+            " [ciclo]"
+        } else if no.truncado {
+            " [...]"
+        } else {
+            ""
+        };
+        println!("{}- {}{}{}", "  ".repeat(nivel), no.nome, origem, marca);
+        if let Some(aviso) = &no.aviso {
+            println!("{}  aviso: {}", "  ".repeat(nivel), aviso);
+        }
+        imprimir_nos(&no.dependencias, nivel + 1);
+    }
+}
@@ -0,0 +1,423 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::construir::Perfil;
+use crate::erros::ErroCli;
+use crate::impressoes::ImpressaoStdlib;
+use crate::saida::{pintar, Cor};
+use crate::templates::{self, DiagnosticoTemplates};
+use crate::toolchain;
+use crate::versao::Versao;
+
+#[derive(Serialize)]
+struct DoctorJson<'a> {
+    #[serde(flatten)]
+    diagnostico: &'a toolchain::DiagnosticoToolchain,
+    ambiente: &'a [toolchain::VerificacaoAmbiente],
+    templates: &'a DiagnosticoTemplates,
+    pronto: bool,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    versoes_minimas: &'a [ChecagemVersao],
+}
+
+/// Opcoes do comando `doctor`.
+pub struct OpcoesDoctor<'a> {
+    pub json: bool,
+    /// `Some(None)` exporta para stdout, `Some(Some(arquivo))` grava no arquivo
+    pub export: Option<Option<&'a Path>>,
+    pub compare: Option<&'a Path>,
+    pub ignore_paths: bool,
+    /// Pares `ferramenta=versao` de `--min-version`
+    pub min_versoes: &'a [String],
+    /// `--nao-falhar`: ambiente com pendencias nao muda o codigo de saida
+    pub nao_falhar: bool,
+    /// `--estrito`: avisos (ex.: templates com problemas) contam como pendencias
+    pub estrito: bool,
+}
+
+/// Resultado de um par `--min-version` contra a versao detectada.
+#[derive(Debug, Serialize)]
+struct ChecagemVersao {
+    ferramenta: &'static str,
+    minima: String,
+    detectada: Option<String>,
+    ok: bool,
+}
+
+/// Retrato da toolchain usado por `doctor --export` e `doctor --compare`.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct SnapshotFerramenta {
+    encontrado: bool,
+    origem: String,
+    caminho: PathBuf,
+    versao: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct SnapshotToolchain {
+    compilador: SnapshotFerramenta,
+    interpretador: SnapshotFerramenta,
+    stdlib: SnapshotFerramenta,
+}
+
+pub fn doctor_cmd(caminho: &Path, opcoes: &OpcoesDoctor) -> Result<()> {
+    let raiz = toolchain::localizar_raiz(caminho);
+    let diag = toolchain::diagnosticar_toolchain(&raiz);
+    let ambiente = toolchain::diagnosticar_ambiente(&diag, &Perfil::Dev.saida_dir(&raiz));
+    let diag_templates = templates::diagnosticar_templates();
+    let avisos = diag_templates.todos_os_avisos();
+    let pronto =
+        diag.pronto() && ambiente.iter().all(|v| v.ok) && (!opcoes.estrito || avisos.is_empty());
+    let json = opcoes.json;
+    let requisitos = opcoes
+        .min_versoes
+        .iter()
+        .map(|par| parse_versao_minima(par))
+        .collect::<Result<Vec<_>>>()?;
+    let versoes_minimas = checar_versoes_minimas(&diag, &requisitos);
+
+    if let Some(destino) = opcoes.export {
+        let texto = serde_json::to_string_pretty(&snapshot(&diag))?;
+        match destino {
+            Some(arquivo) => {
+                fs::write(arquivo, format!("{}\n", texto))
+                    .with_context(|| format!("Falha ao escrever {}", arquivo.display()))?;
+                println!("Snapshot da toolchain gravado em {}", arquivo.display());
+            }
+            None => println!("{}", texto),
+        }
+        return Ok(());
+    }
+
+    if let Some(arquivo) = opcoes.compare {
+        return comparar_snapshot(&diag, arquivo, opcoes.ignore_paths);
+    }
+
+    if json {
+        let saida = DoctorJson {
+            diagnostico: &diag,
+            ambiente: &ambiente,
+            templates: &diag_templates,
+            pronto,
+            versoes_minimas: &versoes_minimas,
+        };
+        println!("{}", serde_json::to_string_pretty(&saida)?);
+        exigir_pronto(pronto, opcoes.nao_falhar)?;
+        return exigir_versoes_minimas(&versoes_minimas);
+    }
+
+    println!("=== Diagnostico do ambiente Por do Sol ===");
+    println!("Caminho de referencia: {}", raiz.display());
+    println!();
+
+    let mut pendencias: Vec<String> = Vec::new();
+
+    imprimir_item_doctor(
+        &diag.compilador,
+        true,
+        "Defina PORDOSOL_COMPILADOR_PATH ou coloque o compilador em <instalacao>/tools.",
+        &mut pendencias,
+    );
+    imprimir_item_doctor(
+        &diag.interpretador,
+        true,
+        "Defina PORDOSOL_INTERPRETADOR_PATH ou coloque o interpretador em <instalacao>/tools.",
+        &mut pendencias,
+    );
+    imprimir_item_doctor(
+        &diag.stdlib,
+        false,
+        "Defina PORDOSOL_STDLIB_PATH ou instale a stdlib em <instalacao>/tools/stdlib.",
+        &mut pendencias,
+    );
+    if diag.stdlib.encontrado {
+        println!(
+            "  versao: {}",
+            toolchain::versao_stdlib(&diag.stdlib.caminho)
+                .as_deref()
+                .unwrap_or("versao desconhecida")
+        );
+        if let Some(imp) = ImpressaoStdlib::de_pasta(&diag.stdlib.caminho) {
+            println!("  impressao: {}", imp.hash());
+        }
+    }
+
+    println!();
+    println!("Ambiente:");
+    for v in &ambiente {
+        println!("{}: {}", v.nome, estado(v.ok));
+        if !v.caminho.as_os_str().is_empty() {
+            println!("  caminho: {}", v.caminho.display());
+        }
+        println!("  origem: {}", v.origem);
+        if !v.ok {
+            pendencias.push(v.dica.clone());
+        }
+    }
+
+    println!();
+    println!("Templates:");
+    match &diag_templates.diretorio {
+        Some(dir) => {
+            println!("diretorio: {}", dir.display());
+            println!("  origem: {}", diag_templates.origem.unwrap_or("-"));
+            println!("  templates: {}", diag_templates.templates.len());
+        }
+        None => println!("diretorio: {}", estado(false)),
+    }
+    for t in &diag_templates.templates {
+        println!("  {}: {}", t.nome, aviso(t.problemas.is_empty()));
+        for p in &t.problemas {
+            println!("    - {}", p);
+        }
+    }
+    for a in &diag_templates.avisos {
+        println!("  Aviso: {}", a);
+    }
+    if opcoes.estrito {
+        pendencias.extend(avisos.iter().map(|a| format!("Corrija: {}.", a)));
+    }
+
+    println!();
+    if pronto {
+        println!("Resultado: ambiente pronto para `pordosol build` e `pordosol run`.");
+    } else {
+        println!("Resultado: ambiente com pendencias.");
+        println!("Acoes corretivas sugeridas:");
+        for (idx, item) in pendencias.iter().enumerate() {
+            println!("{}. {}", idx + 1, item);
+        }
+        println!("Dica: configure `PORDOSOL_HOME` para centralizar tools e templates.");
+    }
+
+    if !versoes_minimas.is_empty() {
+        println!();
+        println!("Versoes minimas:");
+        for c in &versoes_minimas {
+            println!(
+                "{} >= {}: {} ({})",
+                c.ferramenta,
+                c.minima,
+                estado(c.ok),
+                c.detectada.as_deref().unwrap_or("versao nao detectada")
+            );
+        }
+    }
+
+    exigir_pronto(pronto, opcoes.nao_falhar)?;
+    exigir_versoes_minimas(&versoes_minimas)
+}
+
+/// Ambiente com pendencias sai com o codigo de toolchain ausente (2), para gates de CI.
+fn exigir_pronto(pronto: bool, nao_falhar: bool) -> Result<()> {
+    if pronto || nao_falhar {
+        return Ok(());
+    }
+    Err(ErroCli::ToolchainAusente(
+        "Ambiente nao esta pronto (veja o diagnostico acima). Use --nao-falhar para apenas informar."
+            .to_string(),
+    )
+    .into())
+}
+
+/// Le `ferramenta=versao`; ferramenta aceita o nome em portugues ou em ingles.
+fn parse_versao_minima(par: &str) -> Result<(&'static str, Versao)> {
+    let Some((ferramenta, versao)) = par.split_once('=') else {
+        bail!(
+            "--min-version espera FERRAMENTA=VERSAO (ex.: compilador=1.2.0), recebido '{}'",
+            par
+        );
+    };
+    let ferramenta = match ferramenta.trim().to_ascii_lowercase().as_str() {
+        "compilador" | "compiler" => "compilador",
+        "interpretador" | "interpreter" => "interpretador",
+        outra => bail!(
+            "Ferramenta desconhecida em --min-version: '{}' (use compilador ou interpretador)",
+            outra
+        ),
+    };
+    Ok((ferramenta, Versao::parse(versao)?))
+}
+
+fn checar_versoes_minimas(
+    diag: &toolchain::DiagnosticoToolchain,
+    requisitos: &[(&'static str, Versao)],
+) -> Vec<ChecagemVersao> {
+    requisitos
+        .iter()
+        .map(|(ferramenta, minima)| {
+            let item = match *ferramenta {
+                "compilador" => &diag.compilador,
+                _ => &diag.interpretador,
+            };
+            let detectada = item.versao.clone().filter(|_| item.encontrado);
+            // Versao ausente ou fora do semver nao comprova o minimo
+            let ok = detectada
+                .as_deref()
+                .and_then(|v| Versao::parse(v).ok())
+                .is_some_and(|v| v >= *minima);
+            ChecagemVersao {
+                ferramenta,
+                minima: minima.to_string(),
+                detectada,
+                ok,
+            }
+        })
+        .collect()
+}
+
+fn exigir_versoes_minimas(checagens: &[ChecagemVersao]) -> Result<()> {
+    let abaixo: Vec<String> = checagens
+        .iter()
+        .filter(|c| !c.ok)
+        .map(|c| {
+            format!(
+                "{} {} (minimo {})",
+                c.ferramenta,
+                c.detectada.as_deref().unwrap_or("sem versao detectada"),
+                c.minima
+            )
+        })
+        .collect();
+    if !abaixo.is_empty() {
+        bail!("Toolchain abaixo da versao minima: {}", abaixo.join("; "));
+    }
+    Ok(())
+}
+
+/// `OK` verde ou `FALHA` vermelho, conforme `--cor`.
+fn estado(ok: bool) -> String {
+    if ok {
+        pintar(Cor::Verde, "OK")
+    } else {
+        pintar(Cor::Vermelho, "FALHA")
+    }
+}
+
+/// Como `estado`, mas problema que nao bloqueia o ambiente sai como aviso.
+fn aviso(ok: bool) -> String {
+    if ok {
+        pintar(Cor::Verde, "OK")
+    } else {
+        pintar(Cor::Amarelo, "AVISO")
+    }
+}
+
+fn imprimir_item_doctor(
+    item: &toolchain::DiagnosticoFerramenta,
+    mostrar_versao: bool,
+    acao: &str,
+    pendencias: &mut Vec<String>,
+) {
+    if item.encontrado {
+        let versao = if mostrar_versao {
+            format!(
+                " ({})",
+                item.versao.as_deref().unwrap_or("versao desconhecida")
+            )
+        } else {
+            String::new()
+        };
+        println!(
+            "{}: {}{} via {}",
+            item.nome,
+            estado(true),
+            versao,
+            item.origem
+        );
+    } else {
+        println!("{}: {}", item.nome, estado(false));
+    }
+    println!("  caminho: {}", item.caminho.display());
+    println!("  origem: {}", item.origem);
+    if !item.encontrado {
+        println!("  tentativas:");
+        for linha in toolchain::trilha_busca(&item.tentativas) {
+            println!("    {}", linha);
+        }
+        pendencias.push(acao.to_string());
+    }
+}
+
+fn snapshot(diag: &toolchain::DiagnosticoToolchain) -> SnapshotToolchain {
+    let item = |f: &toolchain::DiagnosticoFerramenta| SnapshotFerramenta {
+        encontrado: f.encontrado,
+        origem: f.origem.clone(),
+        caminho: f.caminho.clone(),
+        versao: f.versao.clone(),
+    };
+    SnapshotToolchain {
+        compilador: item(&diag.compilador),
+        interpretador: item(&diag.interpretador),
+        stdlib: item(&diag.stdlib),
+    }
+}
+
+fn comparar_snapshot(
+    diag: &toolchain::DiagnosticoToolchain,
+    arquivo: &Path,
+    ignorar_caminhos: bool,
+) -> Result<()> {
+    let texto = fs::read_to_string(arquivo)
+        .with_context(|| format!("Falha ao ler snapshot {}", arquivo.display()))?;
+    let esperado: SnapshotToolchain = serde_json::from_str(&texto)
+        .with_context(|| format!("Snapshot invalido em {}", arquivo.display()))?;
+    let atual = snapshot(diag);
+
+    let mut divergencias = Vec::new();
+    for (nome, e, a) in [
+        ("compilador", &esperado.compilador, &atual.compilador),
+        (
+            "interpretador",
+            &esperado.interpretador,
+            &atual.interpretador,
+        ),
+        ("stdlib", &esperado.stdlib, &atual.stdlib),
+    ] {
+        if e.encontrado != a.encontrado {
+            divergencias.push(format!(
+                "{}.encontrado: esperado {}, detectado {}",
+                nome, e.encontrado, a.encontrado
+            ));
+        }
+        if e.origem != a.origem {
+            divergencias.push(format!(
+                "{}.origem: esperado {:?}, detectado {:?}",
+                nome, e.origem, a.origem
+            ));
+        }
+        if e.versao != a.versao {
+            divergencias.push(format!(
+                "{}.versao: esperado {}, detectado {}",
+                nome,
+                e.versao.as_deref().unwrap_or("-"),
+                a.versao.as_deref().unwrap_or("-")
+            ));
+        }
+        if !ignorar_caminhos && e.caminho != a.caminho {
+            divergencias.push(format!(
+                "{}.caminho: esperado {}, detectado {}",
+                nome,
+                e.caminho.display(),
+                a.caminho.display()
+            ));
+        }
+    }
+
+    if divergencias.is_empty() {
+        println!("Toolchain confere com o snapshot {}.", arquivo.display());
+        return Ok(());
+    }
+
+    println!("Divergencias em relacao a {}:", arquivo.display());
+    for d in &divergencias {
+        println!("  - {}", d);
+    }
+    bail!(
+        "Toolchain diverge do snapshot em {} campo(s)",
+        divergencias.len()
+    );
+}
@@ -0,0 +1,138 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// `--permitir-duplicados`: links fisicos do mesmo fonte vao todos para o compilador.
+static PERMITIR: AtomicBool = AtomicBool::new(false);
+
+/// Evita repetir o aviso (a descoberta de fontes roda varias vezes por comando e no `--watch`).
+static AVISADO: AtomicBool = AtomicBool::new(false);
+
+pub fn definir_permitir_duplicados(permitir: bool) {
+    PERMITIR.store(permitir, Ordering::Relaxed);
+}
+
+/// Identidade do arquivo no sistema de arquivos: (dispositivo, inode) no Unix.
+#[cfg(unix)]
+fn identidade(caminho: &Path) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+
+    let meta = caminho.metadata().ok()?;
+    Some((meta.dev(), meta.ino()))
+}
+
+/// Identidade do arquivo no sistema de arquivos: (serie do volume, indice do arquivo) no Windows.
+#[cfg(windows)]
+fn identidade(caminho: &Path) -> Option<(u64, u64)> {
+    use std::os::windows::io::AsRawHandle;
+
+    /// BY_HANDLE_FILE_INFORMATION
+    #[repr(C)]
+    #[derive(Default)]
+    struct InformacaoArquivo {
+        atributos: u32,
+        criacao: [u32; 2],
+        acesso: [u32; 2],
+        escrita: [u32; 2],
+        serie_volume: u32,
+        tamanho_alto: u32,
+        tamanho_baixo: u32,
+        links: u32,
+        indice_alto: u32,
+        indice_baixo: u32,
+    }
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetFileInformationByHandle(
+            arquivo: std::os::windows::io::RawHandle,
+            informacao: *mut InformacaoArquivo,
+        ) -> i32;
+    }
+
+    let arquivo = std::fs::File::open(caminho).ok()?;
+    let mut info = InformacaoArquivo::default();
+    // SAFETY: o handle e valido enquanto `arquivo` vive e `info` tem o layout esperado.
+    let ok = unsafe { GetFileInformationByHandle(arquivo.as_raw_handle(), &mut info) };
+    (ok != 0).then(|| {
+        (
+            info.serie_volume as u64,
+            ((info.indice_alto as u64) << 32) | info.indice_baixo as u64,
+        )
+    })
+}
+
+#[cfg(not(any(unix, windows)))]
+fn identidade(_caminho: &Path) -> Option<(u64, u64)> {
+    None
+}
+
+/// Separa os fontes que sao o mesmo arquivo (links fisicos), mantendo a primeira ocorrencia.
+///
+/// Devolve os mantidos e os pares (ignorado, mantido). Sem identidade (arquivo sumiu no meio da
+/// varredura), o fonte fica.
+fn separar(arquivos: Vec<PathBuf>) -> (Vec<PathBuf>, Vec<(PathBuf, PathBuf)>) {
+    let mut vistos: HashMap<(u64, u64), usize> = HashMap::new();
+    let mut mantidos: Vec<PathBuf> = Vec::with_capacity(arquivos.len());
+    let mut ignorados = Vec::new();
+    for arquivo in arquivos {
+        match identidade(&arquivo) {
+            Some(id) => match vistos.get(&id) {
+                Some(&i) => ignorados.push((arquivo, mantidos[i].clone())),
+                None => {
+                    vistos.insert(id, mantidos.len());
+                    mantidos.push(arquivo);
+                }
+            },
+            None => mantidos.push(arquivo),
+        }
+    }
+    (mantidos, ignorados)
+}
+
+/// Remove links fisicos repetidos da lista de fontes de `raiz`, avisando uma vez.
+///
+/// Roda na descoberta, antes das impressoes digitais, para que elas vejam a mesma lista.
+pub fn remover_links_duplicados(raiz: &Path, arquivos: Vec<PathBuf>) -> Vec<PathBuf> {
+    if PERMITIR.load(Ordering::Relaxed) {
+        return arquivos;
+    }
+    let (mantidos, ignorados) = separar(arquivos);
+    if !ignorados.is_empty() && !AVISADO.swap(true, Ordering::Relaxed) {
+        let relativo = |p: &Path| p.strip_prefix(raiz).unwrap_or(p).display().to_string();
+        eprintln!("Aviso: fontes que sao o mesmo arquivo (link fisico) foram ignorados:");
+        for (ignorado, mantido) in &ignorados {
+            eprintln!(
+                "  {} (mesmo arquivo que {})",
+                relativo(ignorado),
+                relativo(mantido)
+            );
+        }
+        eprintln!("  Use --permitir-duplicados para compilar todos.");
+    }
+    mantidos
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn link_fisico_mantem_so_a_primeira_ocorrencia() {
+        let temp = tempfile::tempdir().unwrap();
+        let src = temp.path().join("src");
+        fs::create_dir_all(src.join("backup")).unwrap();
+        let programa = src.join("programa.pr");
+        let copia = src.join("backup").join("programa.pr");
+        let outro = src.join("util.pr");
+        fs::write(&programa, "// programa\n").unwrap();
+        fs::hard_link(&programa, &copia).unwrap();
+        // Mesmo conteudo, arquivo diferente: nao e duplicado
+        fs::write(&outro, "// programa\n").unwrap();
+
+        let (mantidos, ignorados) = separar(vec![programa.clone(), copia.clone(), outro.clone()]);
+        assert_eq!(mantidos, [programa.clone(), outro]);
+        assert_eq!(ignorados, [(copia, programa)]);
+    }
+}
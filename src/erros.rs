@@ -0,0 +1,59 @@
+use std::fmt;
+
+/// Erros com codigo de saida proprio, para que scripts de CI consigam
+/// distinguir ambiente mal configurado de falha de compilacao.
+#[derive(Debug)]
+pub enum ErroCli {
+    /// Compilador ou interpretador nao encontrados (codigo 2)
+    ToolchainAusente(String),
+    /// `pordosol.proj` ausente ou invalido (codigo 3)
+    ProjetoInvalido(String),
+    /// O programa executado por `run`/`script` falhou: sai com o mesmo codigo que ele
+    ProgramaFalhou { codigo: i32, mensagem: String },
+}
+
+impl ErroCli {
+    pub fn codigo_saida(&self) -> i32 {
+        match self {
+            ErroCli::ToolchainAusente(_) => 2,
+            ErroCli::ProjetoInvalido(_) => 3,
+            ErroCli::ProgramaFalhou { codigo, .. } => *codigo,
+        }
+    }
+}
+
+impl fmt::Display for ErroCli {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ErroCli::ToolchainAusente(msg)
+            | ErroCli::ProjetoInvalido(msg)
+            | ErroCli::ProgramaFalhou { mensagem: msg, .. } => f.write_str(msg),
+        }
+    }
+}
+
+/// Codigo de saida de um processo filho: o proprio codigo ou, morto por sinal no Unix,
+/// 128 + sinal (como os shells). Sem nenhum dos dois, 1.
+pub fn codigo_do_status(status: std::process::ExitStatus) -> i32 {
+    if let Some(codigo) = status.code() {
+        return codigo;
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        if let Some(sinal) = status.signal() {
+            return 128 + sinal;
+        }
+    }
+    1
+}
+
+impl std::error::Error for ErroCli {}
+
+/// Codigo de saida do processo para um erro: o do primeiro `ErroCli` da cadeia, ou 1.
+pub fn codigo_saida(erro: &anyhow::Error) -> i32 {
+    erro.chain()
+        .find_map(|e| e.downcast_ref::<ErroCli>())
+        .map(ErroCli::codigo_saida)
+        .unwrap_or(1)
+}
@@ -0,0 +1,269 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+
+use crate::toolchain::{listar_prs, localizar_raiz};
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct Estatisticas {
+    pub linhas: usize,
+    pub linhas_nao_vazias: usize,
+    pub linhas_comentario: usize,
+    pub classes: usize,
+    pub funcoes: usize,
+    pub interfaces: usize,
+}
+
+impl Estatisticas {
+    fn somar(&mut self, outra: &Estatisticas) {
+        self.linhas += outra.linhas;
+        self.linhas_nao_vazias += outra.linhas_nao_vazias;
+        self.linhas_comentario += outra.linhas_comentario;
+        self.classes += outra.classes;
+        self.funcoes += outra.funcoes;
+        self.interfaces += outra.interfaces;
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct EstatisticasArquivo {
+    caminho: String,
+    #[serde(flatten)]
+    estatisticas: Estatisticas,
+}
+
+#[derive(Debug, Serialize)]
+struct RelatorioEstatisticas {
+    arquivos: usize,
+    total: Estatisticas,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    por_arquivo: Option<Vec<EstatisticasArquivo>>,
+}
+
+pub fn stats_cmd(caminho: &Path, json: bool, por_arquivo: bool) -> Result<()> {
+    let raiz = localizar_raiz(caminho);
+    let fontes = listar_prs(&raiz);
+
+    let mut linhas_arquivos = Vec::new();
+    let mut total = Estatisticas::default();
+    for arq in &fontes {
+        let bytes = fs::read(arq).with_context(|| format!("Falha ao ler {}", arq.display()))?;
+        let est = contar(&String::from_utf8_lossy(&bytes));
+        total.somar(&est);
+        let rel = arq.strip_prefix(&raiz).unwrap_or(arq);
+        linhas_arquivos.push(EstatisticasArquivo {
+            caminho: rel.to_string_lossy().replace('\\', "/"),
+            estatisticas: est,
+        });
+    }
+    linhas_arquivos.sort_by(|a, b| {
+        b.estatisticas
+            .linhas
+            .cmp(&a.estatisticas.linhas)
+            .then_with(|| a.caminho.cmp(&b.caminho))
+    });
+
+    let relatorio = RelatorioEstatisticas {
+        arquivos: fontes.len(),
+        total,
+        por_arquivo: por_arquivo.then_some(linhas_arquivos),
+    };
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&relatorio)?);
+        return Ok(());
+    }
+
+    if relatorio.arquivos == 0 {
+        println!("Nenhum arquivo .pr encontrado em {}/src", raiz.display());
+        return Ok(());
+    }
+
+    let rotulo_total = format!("Total ({} arquivo(s))", relatorio.arquivos);
+    let largura = relatorio
+        .por_arquivo
+        .iter()
+        .flatten()
+        .map(|l| l.caminho.chars().count())
+        .chain(std::iter::once(rotulo_total.chars().count()))
+        .max()
+        .unwrap_or(0);
+
+    println!(
+        "{:<largura$}  {:>7}  {:>10}  {:>11}  {:>7}  {:>7}  {:>10}",
+        "Arquivo",
+        "Linhas",
+        "Nao vazias",
+        "Comentarios",
+        "Classes",
+        "Funcoes",
+        "Interfaces",
+        largura = largura
+    );
+    for linha in relatorio.por_arquivo.iter().flatten() {
+        imprimir_linha(&linha.caminho, &linha.estatisticas, largura);
+    }
+    imprimir_linha(&rotulo_total, &relatorio.total, largura);
+    Ok(())
+}
+
+fn imprimir_linha(rotulo: &str, est: &Estatisticas, largura: usize) {
+    println!(
+        "{:<largura$}  {:>7}  {:>10}  {:>11}  {:>7}  {:>7}  {:>10}",
+        rotulo,
+        est.linhas,
+        est.linhas_nao_vazias,
+        est.linhas_comentario,
+        est.classes,
+        est.funcoes,
+        est.interfaces,
+        largura = largura
+    );
+}
+
+/// Conta linhas, comentarios e declaracoes de um fonte `.pr`.
+///
+/// Uma linha e de comentario quando todo o seu conteudo nao vazio esta dentro de
+/// `//` ou `/* */`. Palavras-chave so contam fora de comentarios e textos.
+pub fn contar(fonte: &str) -> Estatisticas {
+    let mut est = Estatisticas::default();
+    let mut em_bloco = false;
+
+    for linha in fonte.split('\n') {
+        let linha = linha.strip_suffix('\r').unwrap_or(linha);
+        est.linhas += 1;
+        if linha.trim().is_empty() {
+            continue;
+        }
+        est.linhas_nao_vazias += 1;
+
+        let mut codigo = String::new();
+        let mut tem_comentario = false;
+        let mut em_texto = false;
+        let mut chars = linha.chars().peekable();
+        while let Some(c) = chars.next() {
+            if em_bloco {
+                tem_comentario = true;
+                if c == '*' && chars.peek() == Some(&'/') {
+                    chars.next();
+                    em_bloco = false;
+                }
+                continue;
+            }
+            if em_texto {
+                codigo.push(c);
+                if c == '\\' {
+                    if let Some(escapado) = chars.next() {
+                        codigo.push(escapado);
+                    }
+                } else if c == '"' {
+                    em_texto = false;
+                }
+                continue;
+            }
+            match (c, chars.peek()) {
+                ('/', Some('/')) => {
+                    tem_comentario = true;
+                    break;
+                }
+                ('/', Some('*')) => {
+                    chars.next();
+                    tem_comentario = true;
+                    em_bloco = true;
+                }
+                ('"', _) => {
+                    em_texto = true;
+                    codigo.push(c);
+                }
+                _ => codigo.push(c),
+            }
+        }
+
+        if tem_comentario && codigo.trim().is_empty() {
+            est.linhas_comentario += 1;
+        }
+        contar_palavras_chave(&codigo, &mut est);
+    }
+
+    // Arquivo terminado em newline nao tem uma linha extra
+    if fonte.ends_with('\n') {
+        est.linhas -= 1;
+    }
+    if fonte.is_empty() {
+        est.linhas = 0;
+    }
+    est
+}
+
+fn contar_palavras_chave(codigo: &str, est: &mut Estatisticas) {
+    let mut em_texto = false;
+    let mut palavra = String::new();
+    for c in codigo.chars().chain(std::iter::once(' ')) {
+        if c == '"' {
+            em_texto = !em_texto;
+        }
+        if !em_texto && (c.is_alphanumeric() || c == '_') {
+            palavra.push(c);
+            continue;
+        }
+        match palavra.as_str() {
+            "classe" => est.classes += 1,
+            "funcao" | "função" => est.funcoes += 1,
+            "interface" => est.interfaces += 1,
+            _ => {}
+        }
+        palavra.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FONTE: &str = "// cabecalho\n\
+usando Sistema.IO;\n\
+\n\
+/* bloco\n\
+   de comentario */\n\
+classe publica Pessoa\n\
+{\n\
+    publico vazio Falar() { imprima(\"classe funcao\"); } // fim\n\
+}\n\
+\n\
+interface IFalante { }\n\
+função vazio Principal()\n\
+{\n\
+}\n";
+
+    #[test]
+    fn conta_linhas_e_declaracoes() {
+        let est = contar(FONTE);
+        assert_eq!(est.linhas, 14);
+        assert_eq!(est.linhas_nao_vazias, 12);
+        assert_eq!(est.linhas_comentario, 3);
+        assert_eq!(est.classes, 1);
+        assert_eq!(est.funcoes, 1);
+        assert_eq!(est.interfaces, 1);
+    }
+
+    #[test]
+    fn crlf_produz_mesmo_resultado() {
+        let crlf = FONTE.replace('\n', "\r\n");
+        assert_eq!(contar(&crlf), contar(FONTE));
+    }
+
+    #[test]
+    fn palavras_acentuadas_nao_casam_parcialmente() {
+        let est = contar("funcao a() {}\nfunçãoX b\nvar classes = 1;\n");
+        assert_eq!(est.funcoes, 1);
+        assert_eq!(est.classes, 0);
+    }
+
+    #[test]
+    fn arquivo_vazio_e_sem_newline_final() {
+        assert_eq!(contar("").linhas, 0);
+        assert_eq!(contar("funcao f()").linhas, 1);
+    }
+}
@@ -0,0 +1,141 @@
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+
+use crate::toolchain::carregar_configuracao_projeto;
+
+/// Padroes de `--excluir` desta invocacao, somados aos de `"excluir"` do pordosol.proj.
+static EXCLUSOES_CLI: OnceLock<Vec<String>> = OnceLock::new();
+
+/// Evita repetir o aviso de campo invalido (a descoberta roda a cada ciclo do `--watch`).
+static AVISO_CAMPO_INVALIDO: AtomicBool = AtomicBool::new(false);
+
+pub fn definir_exclusoes_cli(padroes: Vec<String>) {
+    EXCLUSOES_CLI.set(padroes).ok();
+}
+
+/// Padroes de exclusao do projeto (`"excluir"` no pordosol.proj) seguidos dos de `--excluir`.
+pub fn padroes_exclusao(raiz: &Path) -> Vec<String> {
+    let mut padroes = Vec::new();
+    if let Some(config) = carregar_configuracao_projeto(raiz) {
+        match config.get("excluir") {
+            None | Some(serde_json::Value::Null) => {}
+            Some(serde_json::Value::Array(itens)) if itens.iter().all(|i| i.is_string()) => {
+                padroes.extend(itens.iter().filter_map(|i| i.as_str()).map(str::to_string));
+            }
+            Some(_) => {
+                if !AVISO_CAMPO_INVALIDO.swap(true, Ordering::Relaxed) {
+                    eprintln!(
+                        "Aviso: campo 'excluir' do pordosol.proj ignorado: esperado uma lista de padroes"
+                    );
+                }
+            }
+        }
+    }
+    if let Some(cli) = EXCLUSOES_CLI.get() {
+        padroes.extend(cli.iter().cloned());
+    }
+    padroes
+}
+
+/// `relativo` e o caminho a partir de `src/`; casa com qualquer um dos padroes.
+pub fn esta_excluido(padroes: &[String], relativo: &Path) -> bool {
+    let texto = relativo.to_string_lossy().replace('\\', "/");
+    padroes.iter().any(|p| casa_padrao(p, &texto))
+}
+
+/// Glob com `*` e `?` dentro de um segmento e `**` para zero ou mais segmentos.
+///
+/// Sensivel a maiusculas no Unix e insensivel no Windows, como o sistema de arquivos.
+pub fn casa_padrao(padrao: &str, caminho: &str) -> bool {
+    let (padrao, caminho) = if cfg!(windows) {
+        (padrao.to_lowercase(), caminho.to_lowercase())
+    } else {
+        (padrao.to_string(), caminho.to_string())
+    };
+    let padrao = padrao.replace('\\', "/");
+    let segmentos_padrao: Vec<&str> = padrao
+        .trim_start_matches("./")
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .collect();
+    let segmentos: Vec<&str> = caminho.split('/').filter(|s| !s.is_empty()).collect();
+    casa_segmentos(&segmentos_padrao, &segmentos)
+}
+
+fn casa_segmentos(padrao: &[&str], caminho: &[&str]) -> bool {
+    match padrao.split_first() {
+        None => caminho.is_empty(),
+        Some((&"**", resto)) => (0..=caminho.len()).any(|i| casa_segmentos(resto, &caminho[i..])),
+        Some((seg, resto)) => match caminho.split_first() {
+            Some((nome, resto_caminho)) => {
+                casa_segmento(seg.as_bytes(), nome.as_bytes())
+                    && casa_segmentos(resto, resto_caminho)
+            }
+            None => false,
+        },
+    }
+}
+
+fn casa_segmento(padrao: &[u8], nome: &[u8]) -> bool {
+    match padrao.split_first() {
+        None => nome.is_empty(),
+        Some((b'*', resto)) => (0..=nome.len()).any(|i| casa_segmento(resto, &nome[i..])),
+        Some((b'?', resto)) => !nome.is_empty() && casa_segmento(resto, &nome[1..]),
+        Some((c, resto)) => nome.first() == Some(c) && casa_segmento(resto, &nome[1..]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn glob_por_segmentos() {
+        assert!(casa_padrao("gerado/**", "gerado/a.pr"));
+        assert!(casa_padrao("gerado/**", "gerado/sub/b.pr"));
+        assert!(!casa_padrao("gerado/**", "outro/gerado/a.pr"));
+        assert!(casa_padrao("**/*_old.pr", "x_old.pr"));
+        assert!(casa_padrao("**/*_old.pr", "a/b/x_old.pr"));
+        assert!(!casa_padrao("*.pr", "a/b.pr"));
+        assert!(casa_padrao("modulo?.pr", "modulo1.pr"));
+        assert!(!casa_padrao("modulo?.pr", "modulo10.pr"));
+        assert_eq!(casa_padrao("Gerado/**", "gerado/a.pr"), cfg!(windows));
+    }
+
+    #[test]
+    fn listar_prs_aplica_exclusoes_do_proj() {
+        let dir = tempfile::tempdir().unwrap();
+        let raiz = dir.path();
+        for rel in [
+            "programa.pr",
+            "util.pr",
+            "util_old.pr",
+            "gerado/tabela.pr",
+            "gerado/sub/mais.pr",
+            "modulos/rede_old.pr",
+        ] {
+            let arquivo = raiz.join("src").join(rel);
+            fs::create_dir_all(arquivo.parent().unwrap()).unwrap();
+            fs::write(arquivo, "").unwrap();
+        }
+        fs::write(
+            raiz.join("pordosol.proj"),
+            r#"{"nome": "app", "excluir": ["gerado/**", "**/*_old.pr"]}"#,
+        )
+        .unwrap();
+
+        let mut nomes: Vec<String> = crate::toolchain::listar_prs(raiz)
+            .iter()
+            .map(|p| {
+                p.strip_prefix(raiz.join("src"))
+                    .unwrap()
+                    .to_string_lossy()
+                    .replace('\\', "/")
+            })
+            .collect();
+        nomes.sort();
+        assert_eq!(nomes, ["programa.pr", "util.pr"]);
+    }
+}
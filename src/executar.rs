@@ -1,25 +1,404 @@
-use std::ffi::OsStr;
+use std::ffi::{OsStr, OsString};
 use std::fs;
+use std::io::{self, Read, Write};
 use std::path::{Path, PathBuf};
-use std::process::{Command, Stdio};
+use std::process::{Child, Command, Stdio};
+use std::thread;
+use std::time::{Duration, SystemTime};
 
 use anyhow::{bail, Context, Result};
 use path_absolutize::Absolutize;
 
-use crate::toolchain::{listar_prs, localizar_binarios, localizar_raiz};
+use crate::codificacao::{decodificar, Codificacao};
+use crate::comparacao::{capturar, diff_unificado, mascarar, normalizar_saida, Padrao};
+use crate::construir::{
+    artefato_registrado, assinatura_fontes, executar_hooks, executar_pre_checks,
+    finalizar_artefato, fontes_com_info_build, nome_membro, producao_cmd, resolver_nome_saida,
+    validar_pasta_saida, verificar_toolchain_projeto, verificar_versao_compilador, FaseHook,
+    Perfil, INTERVALO_WATCH,
+};
+use crate::dependencias::resolver_plano;
+use crate::erros::ErroCli;
+use crate::impressoes::{self, MOTIVO_STDLIB};
+use crate::ligar;
+use crate::saida::{descrever_comando, detalhe, progresso};
+use crate::titulo::{Estado, Titulo};
+use crate::toolchain::{
+    carregar_configuracao_projeto, fontes_projeto, listar_prs, localizar_binarios,
+    localizar_compilador, localizar_interpretador, localizar_raiz, localizar_raiz_com_marcador,
+    nome_executavel,
+};
+use crate::trava;
 
-pub fn run_cmd(caminho: &Path, force: bool, arquivo: Option<&Path>, no_build: bool) -> Result<()> {
-    run_unificado(caminho, force, arquivo, no_build)
+/// Opcoes do comando `run`.
+pub struct OpcoesRun<'a> {
+    pub force: bool,
+    pub arquivo: Option<&'a Path>,
+    pub no_build: bool,
+    /// `--no-build --strict`: bytecode desatualizado vira erro
+    pub strict: bool,
+    pub assert_exit: Option<i32>,
+    pub assert_output: Option<&'a str>,
+    pub perfil: Perfil,
+    /// `run --release`: compila com target llvm-ir, liga o `.ll` e so aceita binario nativo
+    pub release: bool,
+    pub nome_saida: Option<&'a str>,
+    pub codificacao: Codificacao,
+    pub skip_checks: bool,
+    pub strict_versao: bool,
+    pub watch: bool,
+    /// `--input-json`: JSON literal ou caminho de arquivo, enviado ao stdin do programa
+    pub input_json: Option<&'a str>,
+    /// `--env-clear`: o programa roda sem herdar o ambiente da CLI
+    pub env_clear: bool,
+    /// `--env-inherit`: variaveis repassadas mesmo com `--env-clear`
+    pub env_inherit: &'a [String],
+    /// `--env CHAVE=VALOR`, na ordem da linha de comando
+    pub env: &'a [String],
+    /// `--env-file`: pares `CHAVE=VALOR` por linha, `#` comenta
+    pub env_file: Option<&'a Path>,
+    /// `--comparar-com`: outro artefato executado em seguida com os mesmos args e stdin
+    pub comparar_com: Option<&'a Path>,
+    /// `--ignorar-regex`: padroes mascarados nas duas saidas antes da comparacao
+    pub ignorar_regex: &'a [String],
+    /// `--somente-relatar`: diferencas na comparacao nao fazem o comando falhar
+    pub somente_relatar: bool,
+    /// Argumentos repassados ao programa
+    pub args: &'a [String],
 }
 
-fn run_unificado(
-    caminho: &Path,
-    force: bool,
-    arquivo: Option<&Path>,
-    no_build: bool,
+/// `run -`: o programa vem do stdin em vez de um projeto ou arquivo.
+pub const MARCADOR_STDIN: &str = "-";
+
+pub fn run_cmd(caminho: &Path, opcoes: &OpcoesRun) -> Result<()> {
+    if caminho == Path::new(MARCADOR_STDIN) {
+        return run_stdin(opcoes);
+    }
+    let perfil_run = secao_run(&localizar_raiz(caminho))?;
+    let args: Vec<String> = perfil_run.args.iter().chain(opcoes.args).cloned().collect();
+    let opcoes = &OpcoesRun {
+        args: &args,
+        ..*opcoes
+    };
+    if opcoes.watch {
+        return run_watch(caminho, opcoes, &perfil_run);
+    }
+    // Valida a entrada antes de compilar para falhar cedo
+    let entrada = opcoes.input_json.map(carregar_input_json).transpose()?;
+    let padroes = opcoes
+        .ignorar_regex
+        .iter()
+        .map(|p| Padrao::compilar(p))
+        .collect::<Result<Vec<_>>>()
+        .context("--ignorar-regex invalido")?;
+    let ambiente = ambiente_do_programa(opcoes, &perfil_run)?;
+    let mut titulo = Titulo::iniciar(&nome_membro(&localizar_raiz(caminho)), "run");
+    titulo.estado(Estado::Compilando);
+    let mut cmd = preparar_execucao(caminho, opcoes)?;
+    titulo.estado(Estado::Executando);
+    aplicar_ambiente(&mut cmd, &ambiente);
+    if let Some(outro) = opcoes.comparar_com {
+        let mut cmd_outro = comando_comparado(&localizar_raiz(caminho), outro, opcoes.args)?;
+        aplicar_ambiente(&mut cmd_outro, &ambiente);
+        return comparar_execucoes(cmd, cmd_outro, outro, entrada.as_deref(), &padroes, opcoes);
+    }
+    executar_programa(
+        cmd,
+        opcoes.assert_exit,
+        opcoes.assert_output,
+        opcoes.codificacao,
+        entrada.as_deref(),
+    )
+}
+
+/// Comando do artefato de `--comparar-com`: `.pbc` pelo interpretador do projeto, qualquer
+/// outro arquivo executado direto (binario nativo de um build antigo).
+fn comando_comparado(raiz: &Path, artefato: &Path, args: &[String]) -> Result<Command> {
+    if !artefato.is_file() {
+        bail!(
+            "Artefato de --comparar-com nao encontrado: {}",
+            artefato.display()
+        );
+    }
+    let artefato = artefato.absolutize()?.to_path_buf();
+    let mut cmd = if artefato.extension() == Some(OsStr::new("pbc")) {
+        let interpretador = localizar_interpretador(raiz)
+            .map_err(ErroCli::from)?
+            .caminho;
+        let mut cmd = Command::new(interpretador);
+        cmd.arg(&artefato);
+        cmd
+    } else {
+        Command::new(&artefato)
+    };
+    cmd.args(args);
+    Ok(cmd)
+}
+
+/// `run --comparar-com`: executa o artefato atual e o indicado com o mesmo stdin e relata o
+/// diff das saidas (mascaradas por `--ignorar-regex`), os codigos de saida e os tempos.
+fn comparar_execucoes(
+    atual: Command,
+    outro: Command,
+    rotulo_outro: &Path,
+    entrada: Option<&str>,
+    padroes: &[Padrao],
+    opcoes: &OpcoesRun,
 ) -> Result<()> {
-    let raiz = localizar_raiz(caminho);
-    let arquivo_path = arquivo.map(|p| p.to_path_buf());
+    let rotulo_outro = rotulo_outro.display().to_string();
+    let mut saidas = Vec::new();
+    for (rotulo, cmd) in [("atual", atual), (rotulo_outro.as_str(), outro)] {
+        detalhe!("Executando: {}", descrever_comando(&cmd));
+        let captura = capturar(cmd, entrada.map(str::as_bytes))?;
+        io::stderr()
+            .write_all(decodificar(&captura.stderr, opcoes.codificacao).as_bytes())
+            .ok();
+        progresso!(
+            "{}: status {}, {} ms",
+            rotulo,
+            captura
+                .status
+                .code()
+                .map(|c| c.to_string())
+                .unwrap_or_else(|| captura.status.to_string()),
+            captura.duracao.as_millis()
+        );
+        let texto = normalizar_saida(&decodificar(&captura.stdout, opcoes.codificacao));
+        saidas.push((mascarar(&texto, padroes), captura.status));
+    }
+
+    let (atual, outro) = (&saidas[0], &saidas[1]);
+    let diff = diff_unificado(&outro.0, &atual.0, &rotulo_outro, "atual");
+    match &diff {
+        Some(diff) => print!("{}", diff),
+        None => println!("Saidas identicas."),
+    }
+    let mesmo_status = atual.1.code() == outro.1.code();
+    if !mesmo_status {
+        println!(
+            "Codigos de saida diferentes: atual {}, {} {}.",
+            atual.1, rotulo_outro, outro.1
+        );
+    }
+    if (diff.is_some() || !mesmo_status) && !opcoes.somente_relatar {
+        bail!(
+            "A execucao atual difere de {} (use --somente-relatar para nao falhar).",
+            rotulo_outro
+        );
+    }
+    Ok(())
+}
+
+/// Secao `run` do pordosol.proj: argumentos e variaveis aplicados a todo `pordosol run`.
+///
+/// `{"run": {"args": ["--porta", "8080"], "env": {"MODO": "dev"}}}`; os argumentos apos `--`
+/// vem depois dos configurados e `--env`/`--env-file` vencem em conflito.
+#[derive(Debug, Default)]
+struct SecaoRun {
+    args: Vec<String>,
+    env: Vec<(String, OsString)>,
+}
+
+fn secao_run(raiz: &Path) -> Result<SecaoRun> {
+    let mut secao = SecaoRun::default();
+    let Some(run) = carregar_configuracao_projeto(raiz).and_then(|c| c.get("run").cloned()) else {
+        return Ok(secao);
+    };
+    let invalido = |detalhe: &str| -> anyhow::Error {
+        ErroCli::ProjetoInvalido(format!("pordosol.proj: campo `run` invalido: {}", detalhe)).into()
+    };
+    let serde_json::Value::Object(run) = run else {
+        return Err(invalido("esperado um objeto"));
+    };
+    if let Some(args) = run.get("args") {
+        let lista = args
+            .as_array()
+            .ok_or_else(|| invalido("`args` deve ser uma lista de textos"))?;
+        for arg in lista {
+            let arg = arg
+                .as_str()
+                .ok_or_else(|| invalido("`args` deve ser uma lista de textos"))?;
+            secao.args.push(arg.to_string());
+        }
+    }
+    if let Some(env) = run.get("env") {
+        let mapa = env
+            .as_object()
+            .ok_or_else(|| invalido("`env` deve ser um objeto CHAVE: valor"))?;
+        for (chave, valor) in mapa {
+            let valor = match valor {
+                serde_json::Value::String(s) => s.clone(),
+                serde_json::Value::Number(n) => n.to_string(),
+                serde_json::Value::Bool(b) => b.to_string(),
+                _ => {
+                    return Err(invalido(&format!(
+                        "valor de `env.{}` deve ser texto",
+                        chave
+                    )))
+                }
+            };
+            let par = par_env(&format!("{}={}", chave, valor))
+                .map_err(|e| invalido(&format!("`env`: {}", e)))?;
+            secao.env.push(par);
+        }
+    }
+    Ok(secao)
+}
+
+/// Ambiente do programa montado a partir de `--env-clear`, `--env-inherit`, `--env-file` e `--env`.
+#[derive(Debug, Default)]
+struct AmbientePrograma {
+    /// `--env-clear`: parte de um ambiente vazio
+    limpar: bool,
+    variaveis: Vec<(String, OsString)>,
+}
+
+/// Monta o ambiente do programa: `run.env` do projeto, depois `--env-file` e por ultimo `--env`,
+/// que tem a palavra final.
+///
+/// Com `--env-clear`, variaveis de `--env-inherit` ausentes geram aviso e sao ignoradas.
+fn ambiente_do_programa(opcoes: &OpcoesRun, secao: &SecaoRun) -> Result<AmbientePrograma> {
+    let mut ambiente = AmbientePrograma {
+        limpar: opcoes.env_clear,
+        variaveis: Vec::new(),
+    };
+    if opcoes.env_clear {
+        for nome in opcoes.env_inherit {
+            match std::env::var_os(nome) {
+                Some(valor) => ambiente.variaveis.push((nome.clone(), valor)),
+                None => eprintln!(
+                    "Aviso: variavel {} nao esta definida; nao sera repassada ao programa.",
+                    nome
+                ),
+            }
+        }
+    }
+    ambiente.variaveis.extend(secao.env.iter().cloned());
+    if let Some(arquivo) = opcoes.env_file {
+        let texto = fs::read_to_string(arquivo)
+            .with_context(|| format!("Falha ao ler --env-file {}", arquivo.display()))?;
+        for (i, linha) in texto.lines().enumerate() {
+            let linha = linha.trim();
+            if linha.is_empty() || linha.starts_with('#') {
+                continue;
+            }
+            let par = par_env(linha).with_context(|| format!("{}:{}", arquivo.display(), i + 1))?;
+            ambiente.variaveis.push(par);
+        }
+    }
+    for item in opcoes.env {
+        ambiente
+            .variaveis
+            .push(par_env(item).context("--env invalido")?);
+    }
+    Ok(ambiente)
+}
+
+/// `CHAVE=VALOR`, separado so no primeiro `=` (o valor pode conter outros).
+fn par_env(texto: &str) -> Result<(String, OsString)> {
+    let Some((chave, valor)) = texto.split_once('=') else {
+        bail!("esperado CHAVE=VALOR, recebido '{}'", texto);
+    };
+    let chave = chave.trim();
+    if chave.is_empty() || chave.chars().any(char::is_whitespace) {
+        bail!("nome de variavel invalido em '{}'", texto);
+    }
+    Ok((chave.to_string(), OsString::from(valor)))
+}
+
+fn aplicar_ambiente(cmd: &mut Command, ambiente: &AmbientePrograma) {
+    if ambiente.limpar {
+        cmd.env_clear();
+    }
+    cmd.envs(ambiente.variaveis.iter().map(|(k, v)| (k, v)));
+    if ambiente.limpar || !ambiente.variaveis.is_empty() {
+        // Valor final de cada variavel, na ordem em que apareceu pela primeira vez
+        let mut finais: Vec<(&str, &OsString)> = Vec::new();
+        for (k, v) in &ambiente.variaveis {
+            match finais.iter_mut().find(|(c, _)| c == k) {
+                Some(par) => par.1 = v,
+                None => finais.push((k, v)),
+            }
+        }
+        detalhe!(
+            "Ambiente do programa{}: {}",
+            if ambiente.limpar { " (limpo)" } else { "" },
+            if finais.is_empty() {
+                "(vazio)".to_string()
+            } else {
+                finais
+                    .iter()
+                    .map(|(k, v)| format!("{}={}", k, v.to_string_lossy()))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            }
+        );
+    }
+}
+
+/// Le o valor de `--input-json` (arquivo existente ou JSON literal) e valida a sintaxe.
+fn carregar_input_json(valor: &str) -> Result<String> {
+    let arquivo = Path::new(valor);
+    let (texto, origem) = if arquivo.is_file() {
+        let texto = fs::read_to_string(arquivo)
+            .with_context(|| format!("Falha ao ler {}", arquivo.display()))?;
+        (texto, arquivo.display().to_string())
+    } else {
+        (valor.to_string(), "valor literal".to_string())
+    };
+    if let Err(e) = serde_json::from_str::<serde::de::IgnoredAny>(&texto) {
+        bail!("--input-json invalido ({}): {}", origem, e);
+    }
+    Ok(texto)
+}
+
+/// Resolve `--arquivo` contra o cwd da invocacao e, se nao existir ali, contra a raiz do projeto.
+///
+/// O interpretador roda com o cwd herdado, entao um caminho relativo repassado cru
+/// quebraria quando o `run` e chamado de uma subpasta.
+fn resolver_arquivo_run(arquivo: &Path, raiz: &Path) -> Result<PathBuf> {
+    let no_cwd = arquivo.absolutize()?.to_path_buf();
+    if no_cwd.is_file() {
+        return Ok(no_cwd);
+    }
+    if arquivo.is_absolute() {
+        bail!("--arquivo nao encontrado: {}", arquivo.display());
+    }
+    let na_raiz = raiz.join(arquivo).absolutize()?.to_path_buf();
+    if na_raiz.is_file() {
+        return Ok(na_raiz);
+    }
+    bail!(
+        "--arquivo {} nao encontrado. Procurado em:\n  {} (relativo ao diretorio atual)\n  {} (relativo a raiz do projeto)",
+        arquivo.display(),
+        no_cwd.display(),
+        na_raiz.display()
+    )
+}
+
+/// Compila se necessario e devolve o comando que executa o programa.
+fn preparar_execucao(caminho: &Path, opcoes: &OpcoesRun) -> Result<Command> {
+    let OpcoesRun {
+        force,
+        arquivo,
+        no_build,
+        perfil,
+        nome_saida,
+        skip_checks,
+        strict_versao,
+        args,
+        ..
+    } = *opcoes;
+    let (raiz, marcador) = localizar_raiz_com_marcador(caminho);
+    detalhe!(
+        "Raiz do projeto: {} (marcador: {})",
+        raiz.display(),
+        marcador
+    );
+    verificar_toolchain_projeto(&raiz)?;
+    let arquivo_path = arquivo
+        .map(|p| resolver_arquivo_run(p, &raiz))
+        .transpose()?;
 
     let somente_pbc = arquivo_path
         .as_ref()
@@ -30,10 +409,7 @@ fn run_unificado(
         listar_prs(&raiz)
     } else if let Some(ap) = arquivo_path.as_ref() {
         if ap.extension() == Some(OsStr::new("pr")) {
-            match ap.absolutize() {
-                Ok(abs) => vec![abs.to_path_buf()],
-                Err(_) => vec![ap.clone()],
-            }
+            vec![ap.clone()]
         } else {
             listar_prs(&raiz)
         }
@@ -42,105 +418,759 @@ fn run_unificado(
             Ok(abs) => vec![abs.to_path_buf()],
             Err(_) => vec![caminho.to_path_buf()],
         }
+    } else if perfil == Perfil::Producao {
+        // producao_cmd gera a info de build com o perfil certo
+        fontes_projeto(&raiz)?
     } else {
-        let list = listar_prs(&raiz);
-        if list.is_empty() {
-            bail!("Nenhum arquivo .pr encontrado em {}/src", raiz.display());
-        }
-        list
+        fontes_com_info_build(&raiz, Perfil::Dev)?
     };
 
-    let (compilador, interpretador) = localizar_binarios(&raiz);
-
-    if !compilador.exists() {
-        bail!(
-            "Compilador nao encontrado em {}. Rode `pordosol doctor` e configure PORDOSOL_COMPILADOR_PATH/PORDOSOL_HOME.",
-            compilador.display()
-        );
-    }
-    if !interpretador.exists() {
-        bail!(
-            "Interpretador nao encontrado em {}. Rode `pordosol doctor` e configure PORDOSOL_INTERPRETADOR_PATH/PORDOSOL_HOME.",
-            interpretador.display()
-        );
+    if perfil == Perfil::Producao && !somente_pbc {
+        let alvo_build = arquivo_path
+            .as_deref()
+            .filter(|p| p.extension() == Some(OsStr::new("pr")))
+            .unwrap_or(caminho);
+        return preparar_perfil_producao(alvo_build, &raiz, &arquivos_fontes, opcoes);
     }
 
-    let saida_dir = raiz.join("build");
+    let plano = resolver_plano(&raiz)?;
+    let (compilador, interpretador) = localizar_binarios(&raiz).map_err(ErroCli::from)?;
+
+    let saida_dir = Perfil::Dev.saida_dir(&raiz);
+    validar_pasta_saida(&raiz, &saida_dir)?;
     fs::create_dir_all(&saida_dir).ok();
 
-    let pbc = if somente_pbc {
+    let arquivo_unico = arquivo_path
+        .as_ref()
+        .map(|ap| ap.extension() == Some(OsStr::new("pr")))
+        .unwrap_or(false)
+        || (caminho.is_file() && caminho.extension() == Some(OsStr::new("pr")));
+
+    let mut pbc = if somente_pbc {
         arquivo_path.clone().unwrap()
-    } else if let Some(ap) = arquivo_path.as_ref() {
-        if ap.extension() == Some(OsStr::new("pr")) {
-            let nome = ap
-                .file_stem()
-                .unwrap_or_default()
-                .to_string_lossy()
-                .to_string();
-            saida_dir.join(format!("{}.pbc", nome))
-        } else {
-            ap.clone()
-        }
+    } else if let Some(ap) = arquivo_path
+        .as_ref()
+        .filter(|ap| ap.extension() != Some(OsStr::new("pr")))
+    {
+        ap.clone()
     } else {
-        let nome = arquivos_fontes[0]
-            .file_stem()
-            .unwrap_or_default()
-            .to_string_lossy()
-            .to_string();
-        saida_dir.join(format!("{}.pbc", nome))
+        let stem_principal = stem_fonte_principal(&raiz, &arquivos_fontes)?;
+        let nome = resolver_nome_saida(&raiz, nome_saida, &stem_principal, arquivo_unico)?;
+        artefato_registrado(&saida_dir, &format!("{}.pbc", nome))
     };
 
+    let mut fontes_monitoradas = arquivos_fontes.clone();
+    fontes_monitoradas.extend(plano.arquivos().cloned());
+    let stdlib_mudou = !somente_pbc && !arquivo_unico && stdlib_mudou_desde_o_build(&raiz);
     let precisa_compilar = (!somente_pbc)
         && !no_build
-        && (force || !pbc.exists() || {
-            let pbc_modified = pbc.metadata().ok().and_then(|m| m.modified().ok());
-            arquivos_fontes.iter().any(|pr| {
-                let pr_modified = pr.metadata().ok().and_then(|m| m.modified().ok());
-                match (pbc_modified, pr_modified) {
-                    (Some(pbc_time), Some(pr_time)) => pr_time > pbc_time,
-                    _ => true,
-                }
-            })
-        });
+        && (force || stdlib_mudou || artefato_desatualizado(&pbc, &fontes_monitoradas));
 
     if precisa_compilar {
-        println!("Compilando...");
+        let _trava = trava::adquirir(&raiz)?;
+        if !skip_checks {
+            executar_pre_checks(&raiz)?;
+        }
+        verificar_versao_compilador(&compilador, strict_versao)?;
+        if stdlib_mudou {
+            progresso!("Artefatos desatualizados: {}.", MOTIVO_STDLIB);
+        }
+        executar_hooks(&raiz, FaseHook::PreBuild, "bytecode", &saida_dir)?;
+        let stem_principal = stem_fonte_principal(&raiz, &arquivos_fontes)?;
+        progresso!("Compilando...");
 
         let mut cmd = Command::new(&compilador);
         cmd.current_dir(&saida_dir)
             .arg("--target=bytecode")
+            .args(crate::configuracao::flags_compilador(&raiz)?)
             .stdin(Stdio::null());
         for arq in &arquivos_fontes {
             cmd.arg(arq);
         }
+        plano.aplicar(&mut cmd);
+        let inicio = SystemTime::now();
+        detalhe!("Executando: {}", descrever_comando(&cmd));
         let status = cmd.status().context("Falha ao executar o compilador")?;
 
         if !status.success() {
             bail!("Compilacao falhou (status {})", status);
         }
-        println!("Compilacao concluida.");
+        let nome = pbc
+            .file_stem()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+        pbc = finalizar_artefato(&saida_dir, &stem_principal, &nome, "pbc", inicio)?;
+        if !arquivo_unico {
+            impressoes::registrar_impressoes(&raiz, &arquivos_fontes);
+        }
+        executar_hooks(&raiz, FaseHook::PostBuild, "bytecode", &saida_dir)?;
+        progresso!("Compilacao concluida.");
     } else if no_build {
-        println!("--no-build ativo, pulando compilacao.");
+        progresso!("--no-build ativo, pulando compilacao.");
     } else {
-        println!("Bytecode esta atualizado, pulando compilacao...");
+        progresso!("Bytecode esta atualizado, pulando compilacao...");
     }
 
+    crate::resultado::registrar("bytecode", &pbc);
+    crate::resultado::registrar("compilado", precisa_compilar);
+
     if no_build && !pbc.exists() {
         bail!(
             "Bytecode nao encontrado em {}. Rode `pordosol build` ou remova --no-build.",
             pbc.display()
         );
     }
+    if no_build && !somente_pbc {
+        if stdlib_mudou {
+            eprintln!(
+                "Aviso: {} desde o ultimo build; o bytecode {} pode estar desatualizado.",
+                MOTIVO_STDLIB,
+                pbc.display()
+            );
+        }
+        verificar_frescor_no_build(&pbc, &fontes_monitoradas, opcoes.strict)?;
+    }
 
-    println!("Executando bytecode {}...", pbc.display());
-    let status = Command::new(&interpretador)
-        .arg(&pbc)
-        .stdin(Stdio::null())
-        .status()
-        .context("Falha ao executar o interpretador")?;
+    progresso!(
+        "Executando bytecode {} (perfil {})...",
+        pbc.display(),
+        Perfil::Dev.nome()
+    );
+    let mut cmd = Command::new(&interpretador);
+    cmd.arg(&pbc).args(args);
+    detalhe!("Executando: {}", descrever_comando(&cmd));
+    Ok(cmd)
+}
 
-    if !status.success() {
-        bail!("Execucao falhou (status {})", status);
+/// Stem do primeiro fonte, que o compilador usa para nomear o artefato.
+fn stem_fonte_principal(raiz: &Path, arquivos_fontes: &[PathBuf]) -> Result<String> {
+    let Some(principal) = arquivos_fontes.first() else {
+        bail!("Nenhum arquivo .pr encontrado em {}/src", raiz.display());
+    };
+    Ok(principal
+        .file_stem()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string())
+}
+
+enum ArtefatoExecutavel {
+    Nativo(PathBuf),
+    Bytecode(PathBuf),
+}
+
+impl ArtefatoExecutavel {
+    fn caminho(&self) -> &Path {
+        match self {
+            ArtefatoExecutavel::Nativo(p) | ArtefatoExecutavel::Bytecode(p) => p,
+        }
+    }
+}
+
+fn preparar_perfil_producao(
+    alvo_build: &Path,
+    raiz: &Path,
+    fontes: &[PathBuf],
+    opcoes: &OpcoesRun,
+) -> Result<Command> {
+    let OpcoesRun {
+        force,
+        no_build,
+        release,
+        skip_checks,
+        strict_versao,
+        args,
+        ..
+    } = *opcoes;
+    let perfil = Perfil::Producao;
+    let target = if release { "llvm-ir" } else { "console" };
+    let saida_dir = perfil.saida_dir(raiz);
+    let stem = stem_fonte_principal(raiz, fontes)?;
+
+    let existente = if release {
+        nativo_release(raiz, &saida_dir, &stem)
+    } else {
+        localizar_artefato_executavel(&saida_dir, &stem, false)
+    };
+    let precisa_compilar = !no_build
+        && (force
+            || existente
+                .map(|a| artefato_desatualizado(a.caminho(), fontes))
+                .unwrap_or(true));
+
+    if precisa_compilar {
+        let _trava = trava::adquirir(raiz)?;
+        if !skip_checks {
+            executar_pre_checks(raiz)?;
+        }
+        let compilador = localizar_compilador(raiz).map_err(ErroCli::from)?;
+        verificar_versao_compilador(&compilador.caminho, strict_versao)?;
+        progresso!("Compilando perfil {}...", perfil.nome());
+        producao_cmd(alvo_build, target, None, false, None)?;
+    } else if no_build {
+        progresso!("--no-build ativo, pulando compilacao.");
+    } else {
+        progresso!(
+            "Artefato de {} esta atualizado, pulando compilacao...",
+            perfil.nome()
+        );
+    }
+
+    let artefato = if release && !no_build {
+        ligar_release(raiz, &saida_dir, &stem, precisa_compilar)?
+    } else if release {
+        nativo_release(raiz, &saida_dir, &stem)
+    } else {
+        localizar_artefato_executavel(&saida_dir, &stem, false)
+    };
+    let Some(artefato) = artefato else {
+        if no_build {
+            bail!(
+                "Artefato de {} nao encontrado em {}. Rode `pordosol producao --target {}` ou remova --no-build.",
+                perfil.nome(),
+                saida_dir.display(),
+                target
+            );
+        }
+        if release {
+            bail!(
+                "O target llvm-ir nao gerou {}.ll nem um binario executavel ({}) em {}; sem eles nao ha o que ligar, e outros artefatos nao sao executaveis via `run`. Use `--perfil producao` (target console).",
+                stem,
+                nome_executavel(&stem),
+                saida_dir.display()
+            );
+        }
+        bail!(
+            "Nenhum artefato executavel ({} ou {}.pbc) em {}. Targets como llvm-ir nao sao executaveis via `run`.",
+            nome_executavel(&stem),
+            stem,
+            saida_dir.display()
+        );
+    };
+    if no_build {
+        verificar_frescor_no_build(artefato.caminho(), fontes, opcoes.strict)?;
+    }
+
+    let mut cmd = match &artefato {
+        ArtefatoExecutavel::Nativo(p) => {
+            progresso!(
+                "Executando binario nativo {} (perfil {})...",
+                p.display(),
+                perfil.nome()
+            );
+            Command::new(p)
+        }
+        ArtefatoExecutavel::Bytecode(p) => {
+            let interpretador = localizar_interpretador(raiz)
+                .map_err(ErroCli::from)?
+                .caminho;
+            progresso!(
+                "Executando bytecode {} (perfil {})...",
+                p.display(),
+                perfil.nome()
+            );
+            let mut cmd = Command::new(&interpretador);
+            cmd.arg(p);
+            cmd
+        }
+    };
+    cmd.args(args);
+    detalhe!("Executando: {}", descrever_comando(&cmd));
+    Ok(cmd)
+}
+
+/// Binario nativo de `run --release`: o que o compilador gerou ou o ligado do `.ll` antes.
+fn nativo_release(raiz: &Path, saida_dir: &Path, stem: &str) -> Option<ArtefatoExecutavel> {
+    localizar_artefato_executavel(saida_dir, stem, true).or_else(|| {
+        let ligado = ligar::destino(raiz, stem);
+        ligado
+            .is_file()
+            .then_some(ArtefatoExecutavel::Nativo(ligado))
+    })
+}
+
+/// Depois do build de `run --release`: sem binario do compilador, liga o `<stem>.ll` em
+/// `<pasta_saida>/release/` quando ele e novo (ou o executavel ligado esta desatualizado).
+fn ligar_release(
+    raiz: &Path,
+    saida_dir: &Path,
+    stem: &str,
+    compilou: bool,
+) -> Result<Option<ArtefatoExecutavel>> {
+    if let Some(nativo) = localizar_artefato_executavel(saida_dir, stem, true) {
+        return Ok(Some(nativo));
+    }
+    let ll = artefato_registrado(saida_dir, &format!("{}.ll", stem));
+    let destino = ligar::destino(raiz, stem);
+    if !ll.is_file() {
+        return Ok(destino
+            .is_file()
+            .then_some(ArtefatoExecutavel::Nativo(destino)));
+    }
+    if compilou || artefato_desatualizado(&destino, std::slice::from_ref(&ll)) {
+        ligar::ligar(&ll, &destino)?;
+    }
+    Ok(Some(ArtefatoExecutavel::Nativo(destino)))
+}
+
+/// Procura o artefato executavel de um perfil: binario nativo primeiro, depois bytecode.
+///
+/// Com `somente_nativo` (`run --release`) um `.pbc` antigo nao serve.
+fn localizar_artefato_executavel(
+    saida_dir: &Path,
+    stem: &str,
+    somente_nativo: bool,
+) -> Option<ArtefatoExecutavel> {
+    let nativo = artefato_registrado(saida_dir, &nome_executavel(stem));
+    if nativo.is_file() {
+        return Some(ArtefatoExecutavel::Nativo(nativo));
+    }
+    if somente_nativo {
+        return None;
+    }
+    let pbc = artefato_registrado(saida_dir, &format!("{}.pbc", stem));
+    if pbc.is_file() {
+        return Some(ArtefatoExecutavel::Bytecode(pbc));
+    }
+    None
+}
+
+/// A stdlib registrada no ultimo build difere da atual (ver `impressoes::Registro`).
+fn stdlib_mudou_desde_o_build(raiz: &Path) -> bool {
+    impressoes::ler(raiz)
+        .ok()
+        .flatten()
+        .is_some_and(|r| r.stdlib_mudou(impressoes::impressao_stdlib(raiz).as_ref()))
+}
+
+/// Verdadeiro se o artefato nao existe ou se alguma fonte e mais nova que ele.
+fn artefato_desatualizado(artefato: &Path, fontes: &[PathBuf]) -> bool {
+    !artefato.exists() || !bytecode_desatualizado(artefato, fontes).is_empty()
+}
+
+/// Fontes mais novas que o artefato (ou sem mtime legivel); todas, se o artefato nao existir.
+fn bytecode_desatualizado(pbc: &Path, fontes: &[PathBuf]) -> Vec<PathBuf> {
+    let Some(pbc_modified) = pbc.metadata().ok().and_then(|m| m.modified().ok()) else {
+        return fontes.to_vec();
+    };
+    fontes
+        .iter()
+        .filter(
+            |pr| match pr.metadata().ok().and_then(|m| m.modified().ok()) {
+                Some(pr_time) => pr_time > pbc_modified,
+                None => true,
+            },
+        )
+        .cloned()
+        .collect()
+}
+
+/// `--no-build`: avisa (ou falha, com `--strict`) se ha fontes mais novas que o artefato.
+fn verificar_frescor_no_build(artefato: &Path, fontes: &[PathBuf], strict: bool) -> Result<()> {
+    let novas = bytecode_desatualizado(artefato, fontes);
+    if novas.is_empty() {
+        return Ok(());
+    }
+    let mut msg = format!(
+        "{} arquivo(s) .pr mais novos que o bytecode {}:",
+        novas.len(),
+        artefato.display()
+    );
+    for f in &novas {
+        msg.push_str(&format!("\n  - {}", f.display()));
+    }
+    if strict {
+        bail!(
+            "{}\nRode `pordosol build` ou remova --strict para executar mesmo assim.",
+            msg
+        );
+    }
+    eprintln!("aviso: {}", msg);
+    eprintln!("aviso: executando bytecode desatualizado (--no-build).");
+    Ok(())
+}
+
+/// `run --watch`: recompila e reinicia o programa a cada mudanca em `src/**/*.pr`.
+///
+/// Falhas de compilacao ou execucao nao encerram o watcher; Ctrl+C encerra o
+/// watcher e o programa (ambos recebem o sinal do terminal).
+fn run_watch(caminho: &Path, opcoes: &OpcoesRun, secao: &SecaoRun) -> Result<()> {
+    let raiz = localizar_raiz(caminho);
+    let ambiente = ambiente_do_programa(opcoes, secao)?;
+    let mut titulo = Titulo::iniciar(&nome_membro(&raiz), "run --watch");
+    let mut ciclo = 0u32;
+
+    loop {
+        ciclo += 1;
+        if ciclo > 1 {
+            progresso!();
+        }
+        progresso!("===== pordosol run --watch: ciclo {} =====", ciclo);
+        let assinatura = assinatura_fontes(&raiz);
+
+        titulo.estado(Estado::Compilando);
+        let mut filho = match preparar_execucao(caminho, opcoes) {
+            Ok(mut cmd) => {
+                aplicar_ambiente(&mut cmd, &ambiente);
+                match cmd.stdin(Stdio::null()).spawn() {
+                    Ok(filho) => Some(filho),
+                    Err(e) => {
+                        eprintln!("Error: Falha ao executar o programa: {}", e);
+                        None
+                    }
+                }
+            }
+            Err(e) => {
+                eprintln!("Error: {:#}", e);
+                None
+            }
+        };
+        titulo.estado(if filho.is_some() {
+            Estado::Executando
+        } else {
+            Estado::Falhou
+        });
+
+        loop {
+            thread::sleep(INTERVALO_WATCH);
+            if let Some(f) = filho.as_mut() {
+                if let Ok(Some(status)) = f.try_wait() {
+                    progresso!("Programa encerrado (status {}).", status);
+                    titulo.estado(if status.success() {
+                        Estado::AguardandoMudancas
+                    } else {
+                        Estado::Falhou
+                    });
+                    filho = None;
+                }
+            }
+            if assinatura_fontes(&raiz) != assinatura {
+                break;
+            }
+        }
+
+        progresso!("Mudanca detectada em src/, reiniciando...");
+        if let Some(mut f) = filho {
+            encerrar_filho(&mut f);
+        }
+    }
+}
+
+/// Encerra o programa: SIGTERM com prazo e depois kill no Unix; TerminateProcess no Windows.
+fn encerrar_filho(filho: &mut Child) {
+    #[cfg(unix)]
+    {
+        use std::time::Instant;
+
+        // SAFETY: `kill` apenas envia um sinal ao pid do filho que ainda nao foi coletado.
+        unsafe {
+            libc::kill(filho.id() as libc::pid_t, libc::SIGTERM);
+        }
+        let limite = Instant::now() + Duration::from_secs(2);
+        while Instant::now() < limite {
+            if let Ok(Some(_)) = filho.try_wait() {
+                return;
+            }
+            thread::sleep(Duration::from_millis(50));
+        }
+    }
+    filho.kill().ok();
+    filho.wait().ok();
+}
+
+fn executar_programa(
+    mut cmd: Command,
+    assert_exit: Option<i32>,
+    assert_output: Option<&str>,
+    codificacao: Codificacao,
+    entrada: Option<&str>,
+) -> Result<()> {
+    cmd.stdin(if entrada.is_some() {
+        Stdio::piped()
+    } else {
+        Stdio::null()
+    });
+    if assert_output.is_some() {
+        cmd.stdout(Stdio::piped()).stderr(Stdio::piped());
+    }
+    let mut filho = cmd.spawn().context("Falha ao executar o interpretador")?;
+    // Escrita em thread separada: o programa pode encher o pipe de saida antes de ler o stdin
+    let escritor = match (entrada, filho.stdin.take()) {
+        (Some(texto), Some(mut stdin)) => {
+            let texto = texto.to_string();
+            Some(thread::spawn(move || stdin.write_all(texto.as_bytes())))
+        }
+        _ => None,
+    };
+
+    let (status, saida_capturada) = if assert_output.is_some() {
+        let out = filho
+            .wait_with_output()
+            .context("Falha ao executar o interpretador")?;
+        let stdout = decodificar(&out.stdout, codificacao);
+        io::stdout().write_all(stdout.as_bytes()).ok();
+        io::stderr()
+            .write_all(decodificar(&out.stderr, codificacao).as_bytes())
+            .ok();
+        (out.status, Some(stdout))
+    } else {
+        let status = filho.wait().context("Falha ao executar o interpretador")?;
+        (status, None)
+    };
+    if let Some(escritor) = escritor {
+        // Programas que nao leem o stdin fecham o pipe; isso nao e erro
+        escritor.join().ok();
+    }
+    crate::resultado::registrar("codigo_programa", crate::erros::codigo_do_status(status));
+
+    if let Some(esperado) = assert_exit {
+        if status.code() != Some(esperado) {
+            bail!(
+                "Codigo de saida inesperado: esperado {}, obtido {}",
+                esperado,
+                status
+                    .code()
+                    .map(|c| c.to_string())
+                    .unwrap_or_else(|| status.to_string())
+            );
+        }
+    } else if !status.success() {
+        return Err(ErroCli::ProgramaFalhou {
+            codigo: crate::erros::codigo_do_status(status),
+            mensagem: format!("Execucao falhou (status {})", status),
+        }
+        .into());
+    }
+
+    if let (Some(trecho), Some(saida)) = (assert_output, saida_capturada.as_deref()) {
+        if !saida.contains(trecho) {
+            bail!(
+                "Saida do programa nao contem o trecho esperado: {:?}",
+                trecho
+            );
+        }
     }
     Ok(())
 }
+
+/// Opcoes do comando `script`.
+pub struct OpcoesScript<'a> {
+    /// Mantem a pasta de compilacao em vez de remove-la ao final
+    pub manter: bool,
+    /// Argumentos repassados ao programa
+    pub args: &'a [String],
+}
+
+/// Pasta de compilacao de um script: `$TMPDIR/pordosol-cache/<hash do caminho absoluto>`.
+fn pasta_cache_script(arquivo: &Path) -> PathBuf {
+    use std::hash::{Hash, Hasher};
+
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    arquivo.hash(&mut hasher);
+    crate::manutencao::pasta_cache_scripts().join(format!("{:016x}", hasher.finish()))
+}
+
+/// `script`: compila um unico .pr fora de qualquer projeto e executa.
+///
+/// Nada e gravado ao lado do arquivo; a toolchain vem so de overrides, ambiente e PATH.
+pub fn script_cmd(arquivo: &Path, opcoes: &OpcoesScript) -> Result<()> {
+    if !arquivo.is_file() {
+        bail!("Arquivo nao encontrado: {}", arquivo.display());
+    }
+    if arquivo.extension() != Some(OsStr::new("pr")) {
+        bail!("Esperado um arquivo .pr: {}", arquivo.display());
+    }
+    let arquivo = arquivo
+        .absolutize()
+        .context("Falha ao resolver caminho do script")?
+        .to_path_buf();
+
+    let (compilador, interpretador) = binarios_script()?;
+    let pasta = pasta_cache_script(&arquivo);
+    // Restos de uma execucao anterior nao podem ser confundidos com o artefato novo
+    fs::remove_dir_all(&pasta).ok();
+    fs::create_dir_all(&pasta).with_context(|| format!("Falha ao criar {}", pasta.display()))?;
+
+    let resultado =
+        compilar_e_executar_script(&arquivo, &pasta, &compilador, &interpretador, opcoes);
+    if opcoes.manter {
+        progresso!("Artefatos mantidos em {}", pasta.display());
+    } else {
+        fs::remove_dir_all(&pasta).ok();
+    }
+    resultado
+}
+
+/// Compilador e interpretador para codigo fora de projeto (`script` e `run -`).
+fn binarios_script() -> Result<(PathBuf, PathBuf)> {
+    let (compilador, interpretador) = crate::toolchain::localizar_binarios_globais();
+    for (nome, caminho, variavel) in [
+        ("Compilador", &compilador, "PORDOSOL_COMPILADOR_PATH"),
+        (
+            "Interpretador",
+            &interpretador,
+            "PORDOSOL_INTERPRETADOR_PATH",
+        ),
+    ] {
+        if !caminho.exists() {
+            return Err(ErroCli::ToolchainAusente(format!(
+                "{} nao encontrado em {}. Sem projeto, configure {}, PORDOSOL_HOME ou o PATH.",
+                nome,
+                caminho.display(),
+                variavel
+            ))
+            .into());
+        }
+    }
+    Ok((compilador, interpretador))
+}
+
+fn compilar_e_executar_script(
+    arquivo: &Path,
+    pasta: &Path,
+    compilador: &Path,
+    interpretador: &Path,
+    opcoes: &OpcoesScript,
+) -> Result<()> {
+    let pbc = compilar_script(arquivo, pasta, compilador)?;
+    let mut cmd = Command::new(interpretador);
+    cmd.arg(&pbc).args(opcoes.args);
+    detalhe!("Executando: {}", descrever_comando(&cmd));
+    executar_programa(cmd, None, None, Codificacao::Auto, None)
+}
+
+/// Compila um unico .pr para bytecode em `pasta` e devolve o `.pbc` gerado.
+fn compilar_script(arquivo: &Path, pasta: &Path, compilador: &Path) -> Result<PathBuf> {
+    progresso!("Compilando {}...", arquivo.display());
+    let mut cmd = Command::new(compilador);
+    cmd.current_dir(pasta)
+        .arg("--target=bytecode")
+        .arg(arquivo)
+        .stdin(Stdio::null());
+    detalhe!("Executando: {}", descrever_comando(&cmd));
+    let status = cmd.status().context("Falha ao executar o compilador")?;
+    if !status.success() {
+        bail!("Compilacao falhou (status {})", status);
+    }
+
+    let stem = arquivo.file_stem().unwrap_or_default().to_string_lossy();
+    let pbc = pasta.join(format!("{}.pbc", stem));
+    if !pbc.is_file() {
+        bail!(
+            "Compilacao nao gerou {} em {}",
+            pbc.file_name().unwrap_or_default().to_string_lossy(),
+            pasta.display()
+        );
+    }
+    Ok(pbc)
+}
+
+/// `run -`: le o programa do stdin, compila como um `script` em uma pasta temporaria e executa.
+///
+/// O stdin ja foi consumido, entao o programa roda com stdin nulo; a secao `run` do
+/// pordosol.proj nao se aplica (nao ha projeto), mas `--env*` e os `--assert-*` sim.
+fn run_stdin(opcoes: &OpcoesRun) -> Result<()> {
+    if opcoes.watch
+        || opcoes.no_build
+        || opcoes.release
+        || opcoes.arquivo.is_some()
+        || opcoes.input_json.is_some()
+        || opcoes.comparar_com.is_some()
+    {
+        bail!("`run -` nao combina com --watch, --no-build, --release, --arquivo, --input-json nem --comparar-com.");
+    }
+    let mut codigo = Vec::new();
+    io::stdin()
+        .read_to_end(&mut codigo)
+        .context("Falha ao ler o programa do stdin")?;
+    if codigo.iter().all(u8::is_ascii_whitespace) {
+        bail!("Nenhum codigo recebido no stdin (uso: echo '...' | pordosol run -).");
+    }
+    let ambiente = ambiente_do_programa(opcoes, &SecaoRun::default())?;
+    let (compilador, interpretador) = binarios_script()?;
+
+    let pasta =
+        crate::manutencao::pasta_cache_scripts().join(format!("stdin-{}", std::process::id()));
+    fs::remove_dir_all(&pasta).ok();
+    fs::create_dir_all(&pasta).with_context(|| format!("Falha ao criar {}", pasta.display()))?;
+    let arquivo = pasta.join("stdin.pr");
+    let resultado = fs::write(&arquivo, &codigo)
+        .with_context(|| format!("Falha ao gravar {}", arquivo.display()))
+        .and_then(|_| compilar_script(&arquivo, &pasta, &compilador))
+        .and_then(|pbc| {
+            let mut cmd = Command::new(&interpretador);
+            cmd.arg(&pbc).args(opcoes.args);
+            aplicar_ambiente(&mut cmd, &ambiente);
+            detalhe!("Executando: {}", descrever_comando(&cmd));
+            executar_programa(
+                cmd,
+                opcoes.assert_exit,
+                opcoes.assert_output,
+                opcoes.codificacao,
+                None,
+            )
+        });
+    fs::remove_dir_all(&pasta).ok();
+    resultado
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+
+    #[test]
+    fn par_env_separa_no_primeiro_igual() {
+        assert_eq!(
+            par_env("URL=http://x?a=1").unwrap(),
+            ("URL".to_string(), OsString::from("http://x?a=1"))
+        );
+        assert_eq!(
+            par_env("VAZIA=").unwrap(),
+            ("VAZIA".to_string(), OsString::new())
+        );
+        assert!(par_env("SEM_IGUAL").is_err());
+        assert!(par_env("=valor").is_err());
+        assert!(par_env("COM ESPACO=x").is_err());
+    }
+
+    fn tocar(caminho: &Path, segundos: u64) {
+        fs::write(caminho, "// x\n").unwrap();
+        File::options()
+            .write(true)
+            .open(caminho)
+            .unwrap()
+            .set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(segundos))
+            .unwrap();
+    }
+
+    #[test]
+    fn bytecode_desatualizado_lista_fontes_mais_novas() {
+        let dir = tempfile::tempdir().unwrap();
+        let pbc = dir.path().join("programa.pbc");
+        let antiga = dir.path().join("antiga.pr");
+        let nova = dir.path().join("nova.pr");
+        tocar(&antiga, 1_000);
+        tocar(&pbc, 2_000);
+        tocar(&nova, 3_000);
+
+        let fontes = vec![antiga.clone(), nova.clone()];
+        assert_eq!(bytecode_desatualizado(&pbc, &fontes), vec![nova.clone()]);
+        assert!(artefato_desatualizado(&pbc, &fontes));
+        let so_antiga = [antiga];
+        assert!(bytecode_desatualizado(&pbc, &so_antiga).is_empty());
+        assert!(!artefato_desatualizado(&pbc, &so_antiga));
+    }
+
+    #[test]
+    fn bytecode_ausente_considera_todas_as_fontes() {
+        let dir = tempfile::tempdir().unwrap();
+        let fonte = dir.path().join("programa.pr");
+        tocar(&fonte, 1_000);
+        let pbc = dir.path().join("programa.pbc");
+        let fontes = vec![fonte];
+        assert_eq!(bytecode_desatualizado(&pbc, &fontes), fontes);
+        assert!(artefato_desatualizado(&pbc, &[]));
+    }
+}
@@ -0,0 +1,230 @@
+//! `pordosol explain <codigo>`: explicacao detalhada dos codigos de erro do compilador.
+//!
+//! Os textos embutidos cobrem os codigos basicos; `PORDOSOL_HOME/erros/<CODIGO>.md` (ou `.txt`)
+//! acrescenta codigos novos e substitui o texto embutido de um codigo existente.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::{bail, Result};
+
+/// Textos embutidos: primeira linha e o titulo, o resto e a explicacao.
+const CATALOGO: &[(&str, &str)] = &[
+    (
+        "E0001",
+        "Erro de sintaxe\n\nO compilador encontrou um simbolo inesperado. Confira parenteses, \
+         chaves e ponto e virgula perto da linha indicada; o erro real costuma estar um pouco \
+         antes do ponto apontado.",
+    ),
+    (
+        "E0002",
+        "Identificador nao declarado\n\nUma variavel, funcao ou classe foi usada sem ter sido \
+         declarada no escopo. Verifique a grafia (maiusculas contam), o `usando` do espaco de \
+         nomes e se o arquivo que declara o simbolo esta entre os fontes do projeto.",
+    ),
+    (
+        "E0003",
+        "Tipos incompativeis\n\nO valor tem um tipo diferente do esperado pela variavel, \
+         parametro ou retorno. Converta o valor explicitamente ou ajuste a declaracao.",
+    ),
+    (
+        "E0004",
+        "Numero de argumentos incorreto\n\nA funcao foi chamada com mais ou menos argumentos do \
+         que declara. Compare a chamada com a assinatura da funcao.",
+    ),
+    (
+        "E0005",
+        "Simbolo declarado mais de uma vez\n\nDois simbolos com o mesmo nome no mesmo escopo. \
+         Renomeie um deles ou mova-o para outro espaco de nomes.",
+    ),
+    (
+        "E0006",
+        "Retorno ausente\n\nUma funcao com tipo de retorno tem um caminho que termina sem \
+         `retorne`. Acrescente o retorno no fim ou em cada ramo do `se`/`senao`.",
+    ),
+    (
+        "E0007",
+        "Arquivo ou modulo nao encontrado\n\nUm `usando` ou inclusao aponta para algo que o \
+         compilador nao achou. Confira o caminho e as dependencias do pordosol.proj \
+         (`pordosol dep listar`).",
+    ),
+];
+
+/// Extensoes aceitas em `PORDOSOL_HOME/erros/`, em ordem de preferencia.
+const EXTENSOES: &[&str] = &["md", "txt"];
+
+/// Maximo de sugestoes para um codigo desconhecido.
+const MAX_SUGESTOES: usize = 3;
+
+/// Normaliza a grafia de um codigo: `e12` e `E0012` viram `E0012`.
+fn normalizar(codigo: &str) -> String {
+    let codigo = codigo.trim().to_ascii_uppercase();
+    match codigo.strip_prefix('E') {
+        Some(num)
+            if !num.is_empty() && num.len() < 4 && num.chars().all(|c| c.is_ascii_digit()) =>
+        {
+            format!("E{:0>4}", num)
+        }
+        _ => codigo,
+    }
+}
+
+fn pasta_erros_home() -> Option<PathBuf> {
+    let home = std::env::var("PORDOSOL_HOME").ok()?;
+    let home = home.trim();
+    (!home.is_empty()).then(|| PathBuf::from(home).join("erros"))
+}
+
+/// Catalogo completo: embutido mais `PORDOSOL_HOME/erros/`, que tem precedencia.
+fn carregar_catalogo() -> BTreeMap<String, String> {
+    let mut catalogo: BTreeMap<String, String> = CATALOGO
+        .iter()
+        .map(|(c, t)| (c.to_string(), t.to_string()))
+        .collect();
+    let Some(pasta) = pasta_erros_home() else {
+        return catalogo;
+    };
+    let Ok(entradas) = fs::read_dir(&pasta) else {
+        return catalogo;
+    };
+    let mut arquivos: Vec<PathBuf> = entradas
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.is_file())
+        .collect();
+    // `.md` vence `.txt` do mesmo codigo: ordena para que ele seja lido por ultimo
+    arquivos.sort_by_key(|p| {
+        let ext = p.extension().and_then(|e| e.to_str()).unwrap_or("");
+        std::cmp::Reverse(EXTENSOES.iter().position(|x| *x == ext))
+    });
+    for arquivo in arquivos {
+        let ext = arquivo.extension().and_then(|e| e.to_str()).unwrap_or("");
+        if !EXTENSOES.contains(&ext) {
+            continue;
+        }
+        let Some(stem) = arquivo.file_stem().map(|s| s.to_string_lossy().to_string()) else {
+            continue;
+        };
+        match fs::read_to_string(&arquivo) {
+            Ok(texto) => {
+                catalogo.insert(normalizar(&stem), texto.trim_end().to_string());
+            }
+            Err(e) => eprintln!("Aviso: ignorando {}: {}", arquivo.display(), e),
+        }
+    }
+    catalogo
+}
+
+/// Distancia de edicao (insercao, remocao, troca) entre duas sequencias de caracteres.
+pub(crate) fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut anterior: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut atual = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let troca = anterior[j] + usize::from(ca != *cb);
+            atual[j + 1] = troca.min(anterior[j + 1] + 1).min(atual[j] + 1);
+        }
+        anterior = atual;
+    }
+    anterior[b.len()]
+}
+
+/// Candidato mais parecido com `valor`, se estiver a no maximo 2 edicoes.
+pub(crate) fn sugestao<'a>(valor: &str, candidatos: &[&'a str]) -> Option<&'a str> {
+    candidatos
+        .iter()
+        .map(|c| (levenshtein(valor, c), *c))
+        .filter(|(d, _)| *d <= 2)
+        .min()
+        .map(|(_, c)| c)
+}
+
+/// ` Você quis dizer 'x'?` para acrescentar a uma mensagem de erro, ou vazio sem sugestao.
+pub(crate) fn voce_quis_dizer(valor: &str, candidatos: &[&str]) -> String {
+    sugestao(valor, candidatos)
+        .map(|c| format!(" Você quis dizer '{}'?", c))
+        .unwrap_or_default()
+}
+
+/// Codigos mais proximos de `codigo`, do mais parecido ao menos (empate: ordem do codigo).
+fn sugestoes<'a>(codigo: &str, conhecidos: impl IntoIterator<Item = &'a String>) -> Vec<&'a str> {
+    let mut por_distancia: Vec<(usize, &str)> = conhecidos
+        .into_iter()
+        .map(|c| (levenshtein(codigo, c), c.as_str()))
+        .collect();
+    por_distancia.sort();
+    por_distancia
+        .into_iter()
+        .take(MAX_SUGESTOES)
+        .map(|(_, c)| c)
+        .collect()
+}
+
+/// `pordosol explain [codigo]`: sem codigo, lista os codigos conhecidos com o titulo.
+pub fn explicar_cmd(codigo: Option<&str>) -> Result<()> {
+    let catalogo = carregar_catalogo();
+    let Some(codigo) = codigo else {
+        for (c, texto) in &catalogo {
+            println!("{}  {}", c, texto.lines().next().unwrap_or(""));
+        }
+        return Ok(());
+    };
+
+    let codigo = normalizar(codigo);
+    if let Some(texto) = catalogo.get(&codigo) {
+        println!("{}: {}", codigo, texto);
+        return Ok(());
+    }
+
+    let parecidos = sugestoes(&codigo, catalogo.keys());
+    if parecidos.is_empty() {
+        bail!("Codigo de erro '{}' nao reconhecido.", codigo);
+    }
+    bail!(
+        "Codigo de erro '{}' nao reconhecido. Codigos parecidos: {}. Use `pordosol explain` para listar todos.",
+        codigo,
+        parecidos.join(", ")
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sugestao_so_ate_duas_edicoes() {
+        let acoes = ["add", "remove", "list", "tree"];
+        assert_eq!(sugestao("lst", &acoes), Some("list"));
+        assert_eq!(sugestao("ad", &acoes), Some("add"));
+        assert_eq!(sugestao("instalar", &acoes), None);
+        assert_eq!(voce_quis_dizer("tre", &acoes), " Você quis dizer 'tree'?");
+        assert_eq!(voce_quis_dizer("xyzw", &acoes), "");
+    }
+
+    #[test]
+    fn distancia_de_edicao() {
+        assert_eq!(levenshtein("E0012", "E0012"), 0);
+        assert_eq!(levenshtein("E0012", "E0002"), 1);
+        assert_eq!(levenshtein("E0012", "E012"), 1);
+        assert_eq!(levenshtein("", "E01"), 3);
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+    }
+
+    #[test]
+    fn normaliza_e_sugere_os_mais_proximos() {
+        assert_eq!(normalizar(" e12 "), "E0012");
+        assert_eq!(normalizar("E0012"), "E0012");
+        assert_eq!(normalizar("W100"), "W100");
+
+        let conhecidos: Vec<String> = ["E0001", "E0002", "E0120", "W0012"]
+            .iter()
+            .map(|c| c.to_string())
+            .collect();
+        assert_eq!(
+            sugestoes("E0012", &conhecidos),
+            vec!["E0002", "W0012", "E0001"]
+        );
+    }
+}
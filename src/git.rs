@@ -0,0 +1,74 @@
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use anyhow::{anyhow, bail, Context, Result};
+
+/// Localiza o executavel do git no PATH.
+pub fn localizar() -> Result<PathBuf> {
+    which::which("git").map_err(|_| anyhow!("`git` nao encontrado no PATH"))
+}
+
+/// Executa `git <args>` (em `dir`, quando informado) e devolve o stdout sem o newline final.
+///
+/// Falhas trazem o comando e o stderr do git na mensagem.
+pub fn executar<I, S>(dir: Option<&Path>, args: I) -> Result<String>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    let git = localizar()?;
+    let args: Vec<S> = args.into_iter().collect();
+    let descricao = args
+        .iter()
+        .map(|a| a.as_ref().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let mut cmd = Command::new(git);
+    if let Some(dir) = dir {
+        cmd.arg("-C").arg(dir);
+    }
+    let out = cmd
+        .args(&args)
+        .stdin(Stdio::null())
+        .output()
+        .with_context(|| format!("Falha ao executar `git {}`", descricao))?;
+    if !out.status.success() {
+        bail!(
+            "`git {}` falhou (status {}): {}",
+            descricao,
+            out.status,
+            String::from_utf8_lossy(&out.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&out.stdout).trim_end().to_string())
+}
+
+/// Verdadeiro se `dir` esta dentro de um repositorio git (falso tambem sem git instalado).
+pub fn eh_repositorio(dir: &Path) -> bool {
+    executar(Some(dir), ["rev-parse", "--is-inside-work-tree"])
+        .map(|s| s == "true")
+        .unwrap_or(false)
+}
+
+/// Verdadeiro se `rev` resolve para um commit em `dir`.
+pub fn ref_existe(dir: &Path, rev: &str) -> bool {
+    executar(
+        Some(dir),
+        [
+            "rev-parse",
+            "--verify",
+            "--quiet",
+            &format!("{}^{{commit}}", rev),
+        ],
+    )
+    .is_ok()
+}
+
+/// Hash do commit atual de `dir`, se for um checkout git.
+pub fn commit_atual(dir: &Path) -> Option<String> {
+    executar(Some(dir), ["rev-parse", "HEAD"])
+        .ok()
+        .filter(|c| !c.is_empty())
+}
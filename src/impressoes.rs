@@ -0,0 +1,396 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::UNIX_EPOCH;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::construir::pasta_saida_projeto;
+use crate::toolchain::{listar_prs, localizar_raiz, localizar_stdlib};
+
+/// Impressoes digitais dos fontes do ultimo build bem-sucedido, na pasta de saida base.
+pub const ARQUIVO_IMPRESSOES: &str = ".impressoes.json";
+
+/// Hash FNV-1a de 64 bits: estavel entre versoes do Rust, ao contrario do `DefaultHasher`.
+pub struct Fnv1a(u64);
+
+impl Fnv1a {
+    pub fn novo() -> Self {
+        Fnv1a(0xcbf2_9ce4_8422_2325)
+    }
+
+    pub fn escrever(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.0 ^= u64::from(*b);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    pub fn hex(&self) -> String {
+        format!("{:016x}", self.0)
+    }
+}
+
+/// Impressao de um fonte: tamanho e hash do conteudo.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Impressao {
+    pub bytes: u64,
+    pub hash: String,
+}
+
+impl Impressao {
+    pub fn de_arquivo(caminho: &Path) -> Result<Self> {
+        let conteudo =
+            fs::read(caminho).with_context(|| format!("Falha ao ler {}", caminho.display()))?;
+        let mut h = Fnv1a::novo();
+        h.escrever(&conteudo);
+        Ok(Impressao {
+            bytes: conteudo.len() as u64,
+            hash: h.hex(),
+        })
+    }
+}
+
+/// `--ignorar-stdlib-na-impressao-digital`: a stdlib fica fora do registro e da comparacao.
+static IGNORAR_STDLIB: AtomicBool = AtomicBool::new(false);
+
+pub fn definir_ignorar_stdlib(ignorar: bool) {
+    IGNORAR_STDLIB.store(ignorar, Ordering::Relaxed);
+}
+
+/// Impressao barata da stdlib: caminho mais tamanho e mtime do `Sistema.toml`
+/// (sem ele, da pasta `src/` da stdlib). Atualizar a stdlib muda pelo menos o mtime.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImpressaoStdlib {
+    pub caminho: String,
+    pub bytes: u64,
+    /// mtime em nanossegundos desde a epoca
+    pub modificado: u128,
+}
+
+impl ImpressaoStdlib {
+    pub fn de_pasta(pasta: &Path) -> Option<Self> {
+        let marcador = [pasta.join("Sistema.toml"), pasta.join("src")]
+            .into_iter()
+            .find(|p| p.exists())?;
+        let meta = marcador.metadata().ok()?;
+        let modificado = meta
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_nanos())
+            .unwrap_or(0);
+        Some(ImpressaoStdlib {
+            caminho: pasta.display().to_string(),
+            bytes: if meta.is_file() { meta.len() } else { 0 },
+            modificado,
+        })
+    }
+
+    /// Resumo de 16 hex para exibir (`doctor`).
+    pub fn hash(&self) -> String {
+        let mut h = Fnv1a::novo();
+        h.escrever(self.caminho.as_bytes());
+        h.escrever(&self.bytes.to_le_bytes());
+        h.escrever(&self.modificado.to_le_bytes());
+        h.hex()
+    }
+}
+
+/// Impressao da stdlib que o projeto usaria agora; `None` sem stdlib ou com a flag de ignorar.
+pub fn impressao_stdlib(raiz: &Path) -> Option<ImpressaoStdlib> {
+    if IGNORAR_STDLIB.load(Ordering::Relaxed) {
+        return None;
+    }
+    let stdlib = localizar_stdlib(raiz);
+    if !stdlib.encontrado {
+        return None;
+    }
+    ImpressaoStdlib::de_pasta(&stdlib.caminho)
+}
+
+/// Motivo mostrado quando a stdlib mudou desde o ultimo build.
+pub const MOTIVO_STDLIB: &str = "biblioteca padrao mudou";
+
+/// Registro do ultimo build: impressao de cada fonte e da stdlib usada.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Registro {
+    pub fontes: BTreeMap<String, Impressao>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stdlib: Option<ImpressaoStdlib>,
+}
+
+impl Registro {
+    /// A stdlib atual difere da registrada. Registro sem stdlib (formato antigo ou gravado
+    /// com a flag de ignorar) nao conta como mudanca.
+    pub fn stdlib_mudou(&self, atual: Option<&ImpressaoStdlib>) -> bool {
+        match (&self.stdlib, atual) {
+            (Some(antes), Some(agora)) => antes != agora,
+            (Some(_), None) => !IGNORAR_STDLIB.load(Ordering::Relaxed),
+            (None, _) => false,
+        }
+    }
+}
+
+/// Fontes do projeto, relativos a raiz com `/`, como chaves do registro.
+fn chave(raiz: &Path, arquivo: &Path) -> String {
+    arquivo
+        .strip_prefix(raiz)
+        .unwrap_or(arquivo)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+/// Le o registro; aceita tambem o formato antigo (so o mapa de fontes).
+pub fn ler(raiz: &Path) -> Result<Option<Registro>> {
+    let caminho = pasta_saida_projeto(raiz).join(ARQUIVO_IMPRESSOES);
+    if !caminho.exists() {
+        return Ok(None);
+    }
+    let texto = fs::read_to_string(&caminho)
+        .with_context(|| format!("Falha ao ler {}", caminho.display()))?;
+    let registro = serde_json::from_str::<Registro>(&texto)
+        .or_else(|_| {
+            serde_json::from_str::<BTreeMap<String, Impressao>>(&texto).map(|fontes| Registro {
+                fontes,
+                stdlib: None,
+            })
+        })
+        .with_context(|| format!("{} invalido", caminho.display()))?;
+    Ok(Some(registro))
+}
+
+/// Registra os fontes compilados pelo build que acabou de terminar.
+///
+/// Falhas so geram aviso: o registro nao pode quebrar um build que deu certo.
+pub fn registrar_impressoes(raiz: &Path, arquivos: &[PathBuf]) {
+    let resultado = (|| -> Result<()> {
+        let mut registro = Registro {
+            fontes: BTreeMap::new(),
+            stdlib: impressao_stdlib(raiz),
+        };
+        for arq in arquivos {
+            registro
+                .fontes
+                .insert(chave(raiz, arq), Impressao::de_arquivo(arq)?);
+        }
+        let pasta = pasta_saida_projeto(raiz);
+        fs::create_dir_all(&pasta)?;
+        fs::write(
+            pasta.join(ARQUIVO_IMPRESSOES),
+            serde_json::to_string_pretty(&registro)? + "\n",
+        )?;
+        Ok(())
+    })();
+    if let Err(e) = resultado {
+        eprintln!(
+            "Aviso: nao foi possivel registrar as impressoes dos fontes: {:#}",
+            e
+        );
+    }
+}
+
+/// Situacao de um fonte em relacao ao ultimo build.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum SituacaoFonte {
+    #[value(alias = "novos")]
+    Novo,
+    #[value(alias = "modificados")]
+    Modificado,
+    #[value(alias = "inalterados")]
+    Inalterado,
+    #[value(alias = "removidos")]
+    Removido,
+}
+
+impl SituacaoFonte {
+    pub fn rotulo(&self) -> &'static str {
+        match self {
+            SituacaoFonte::Novo => "novo",
+            SituacaoFonte::Modificado => "modificado",
+            SituacaoFonte::Inalterado => "inalterado",
+            SituacaoFonte::Removido => "removido",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct MudancaFonte {
+    pub arquivo: String,
+    pub situacao: SituacaoFonte,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct RelatorioMudancas {
+    /// Ha registro de um build anterior
+    pub registro: bool,
+    /// Por que todos os artefatos estao desatualizados, alem dos fontes (ex.: stdlib)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub motivo: Option<&'static str>,
+    pub contagem: BTreeMap<&'static str, usize>,
+    pub arquivos: Vec<MudancaFonte>,
+}
+
+/// Compara os fontes atuais com o registro; sem registro, tudo e novo.
+pub fn comparar(
+    atuais: &BTreeMap<String, Impressao>,
+    registro: Option<&BTreeMap<String, Impressao>>,
+) -> Vec<MudancaFonte> {
+    let vazio = BTreeMap::new();
+    let anteriores = registro.unwrap_or(&vazio);
+    let mut mudancas: Vec<MudancaFonte> = atuais
+        .iter()
+        .map(|(arquivo, imp)| MudancaFonte {
+            arquivo: arquivo.clone(),
+            situacao: match anteriores.get(arquivo) {
+                None => SituacaoFonte::Novo,
+                Some(ant) if ant != imp => SituacaoFonte::Modificado,
+                Some(_) => SituacaoFonte::Inalterado,
+            },
+        })
+        .collect();
+    mudancas.extend(
+        anteriores
+            .keys()
+            .filter(|k| !atuais.contains_key(*k))
+            .map(|arquivo| MudancaFonte {
+                arquivo: arquivo.clone(),
+                situacao: SituacaoFonte::Removido,
+            }),
+    );
+    mudancas.sort_by(|a, b| a.arquivo.cmp(&b.arquivo));
+    mudancas
+}
+
+/// `pordosol listar --mudancas`: o que mudou nos fontes desde o ultimo build (somente leitura).
+pub fn mudancas_cmd(caminho: &Path, somente: Option<SituacaoFonte>, json: bool) -> Result<()> {
+    let raiz = localizar_raiz(caminho);
+    let registro = ler(&raiz)?;
+    let mut atuais = BTreeMap::new();
+    for arq in listar_prs(&raiz) {
+        atuais.insert(chave(&raiz, &arq), Impressao::de_arquivo(&arq)?);
+    }
+
+    let mut arquivos = comparar(&atuais, registro.as_ref().map(|r| &r.fontes));
+    let motivo = registro
+        .as_ref()
+        .filter(|r| r.stdlib_mudou(impressao_stdlib(&raiz).as_ref()))
+        .map(|_| MOTIVO_STDLIB);
+    let mut contagem: BTreeMap<&'static str, usize> = [
+        SituacaoFonte::Novo,
+        SituacaoFonte::Modificado,
+        SituacaoFonte::Inalterado,
+        SituacaoFonte::Removido,
+    ]
+    .iter()
+    .map(|s| (s.rotulo(), 0))
+    .collect();
+    for m in &arquivos {
+        *contagem.entry(m.situacao.rotulo()).or_default() += 1;
+    }
+    if let Some(s) = somente {
+        arquivos.retain(|m| m.situacao == s);
+    }
+
+    let relatorio = RelatorioMudancas {
+        registro: registro.is_some(),
+        motivo,
+        contagem,
+        arquivos,
+    };
+    if json {
+        println!("{}", serde_json::to_string_pretty(&relatorio)?);
+        return Ok(());
+    }
+
+    if !relatorio.registro {
+        println!(
+            "Nenhum build anterior registrado ({} ausente); todos os fontes contam como novos.",
+            ARQUIVO_IMPRESSOES
+        );
+    }
+    if let Some(motivo) = relatorio.motivo {
+        println!("Todos os artefatos estao desatualizados: {}.", motivo);
+    }
+    for m in &relatorio.arquivos {
+        println!("  {:<10} {}", m.situacao.rotulo(), m.arquivo);
+    }
+    println!(
+        "{} novo(s), {} modificado(s), {} inalterado(s), {} removido(s)",
+        relatorio.contagem["novo"],
+        relatorio.contagem["modificado"],
+        relatorio.contagem["inalterado"],
+        relatorio.contagem["removido"]
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn imp(hash: &str) -> Impressao {
+        Impressao {
+            bytes: 1,
+            hash: hash.to_string(),
+        }
+    }
+
+    #[test]
+    fn compara_com_registro_anterior() {
+        let atuais = BTreeMap::from([
+            ("src/a.pr".to_string(), imp("1")),
+            ("src/b.pr".to_string(), imp("2")),
+            ("src/c.pr".to_string(), imp("3")),
+        ]);
+        let anteriores = BTreeMap::from([
+            ("src/a.pr".to_string(), imp("1")),
+            ("src/b.pr".to_string(), imp("x")),
+            ("src/d.pr".to_string(), imp("4")),
+        ]);
+        let situacoes: Vec<(String, SituacaoFonte)> = comparar(&atuais, Some(&anteriores))
+            .into_iter()
+            .map(|m| (m.arquivo, m.situacao))
+            .collect();
+        assert_eq!(
+            situacoes,
+            vec![
+                ("src/a.pr".to_string(), SituacaoFonte::Inalterado),
+                ("src/b.pr".to_string(), SituacaoFonte::Modificado),
+                ("src/c.pr".to_string(), SituacaoFonte::Novo),
+                ("src/d.pr".to_string(), SituacaoFonte::Removido),
+            ]
+        );
+
+        assert!(comparar(&atuais, None)
+            .iter()
+            .all(|m| m.situacao == SituacaoFonte::Novo));
+    }
+
+    #[test]
+    fn stdlib_so_conta_quando_registrada() {
+        let stdlib = |modificado| ImpressaoStdlib {
+            caminho: "/opt/pds/stdlib".to_string(),
+            bytes: 20,
+            modificado,
+        };
+        let registro = Registro {
+            fontes: BTreeMap::new(),
+            stdlib: Some(stdlib(1)),
+        };
+        assert!(!registro.stdlib_mudou(Some(&stdlib(1))));
+        assert!(registro.stdlib_mudou(Some(&stdlib(2))));
+        assert!(registro.stdlib_mudou(None));
+        assert!(!Registro::default().stdlib_mudou(Some(&stdlib(2))));
+    }
+
+    #[test]
+    fn fnv1a_conhecido() {
+        let mut h = Fnv1a::novo();
+        h.escrever(b"a");
+        assert_eq!(h.hex(), "af63dc4c8601ec8c");
+    }
+}
@@ -0,0 +1,186 @@
+//! `configuracao.gerar_info_build`: fonte `.pr` com metadados do build (nome, versao, commit,
+//! data e perfil), regenerado antes de cada compilacao e incluido nos fontes.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+
+use crate::changelog::data_civil;
+use crate::construir::Perfil;
+use crate::erros::ErroCli;
+use crate::saida::detalhe;
+use crate::toolchain::carregar_configuracao_projeto;
+
+/// Campo de `configuracao` com o caminho do arquivo gerado, relativo a raiz.
+pub const CAMPO: &str = "gerar_info_build";
+
+/// Caminho sugerido por `pordosol new --info-build`.
+pub const CAMINHO_PADRAO: &str = "src/gerado/info_build.pr";
+
+/// A data muda a cada build; fora dela, o conteudo so muda quando os metadados mudam.
+const LINHA_DATA: &str = "constante texto DATA_BUILD";
+
+const TEMPLATE: &str = "\
+// Gerado pelo pordosol a partir de configuracao.gerar_info_build; nao edite.
+constante texto NOME = {{NOME}};
+constante texto VERSAO = {{VERSAO}};
+constante texto COMMIT = {{COMMIT}};
+constante texto PERFIL = {{PERFIL}};
+constante texto DATA_BUILD = {{DATA_BUILD}};
+";
+
+/// Caminho do arquivo gerado, se a opcao estiver ligada no pordosol.proj.
+pub fn arquivo_configurado(raiz: &Path) -> Result<Option<PathBuf>> {
+    let Some(config) = carregar_configuracao_projeto(raiz) else {
+        return Ok(None);
+    };
+    match config.get("configuracao").and_then(|c| c.get(CAMPO)) {
+        None | Some(serde_json::Value::Null) => Ok(None),
+        Some(serde_json::Value::String(s)) if !s.trim().is_empty() => Ok(Some(raiz.join(s.trim()))),
+        Some(_) => Err(ErroCli::ProjetoInvalido(format!(
+            "configuracao.{} deve ser o caminho do .pr a gerar (ex.: \"{}\")",
+            CAMPO, CAMINHO_PADRAO
+        ))
+        .into()),
+    }
+}
+
+/// Literal de texto da linguagem, com `\` e `"` escapados.
+fn literal(valor: &str) -> String {
+    format!("\"{}\"", valor.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Instante atual em UTC, `AAAA-MM-DDTHH:MM:SSZ`.
+fn data_hora_utc() -> String {
+    formatar_utc(SystemTime::now())
+}
+
+/// `instante` em UTC, `AAAA-MM-DDTHH:MM:SSZ`.
+pub(crate) fn formatar_utc(instante: SystemTime) -> String {
+    let segundos = instante
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let (a, m, d) = data_civil((segundos / 86_400) as i64);
+    let resto = segundos % 86_400;
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        a,
+        m,
+        d,
+        resto / 3_600,
+        resto % 3_600 / 60,
+        resto % 60
+    )
+}
+
+fn renderizar(raiz: &Path, perfil: Perfil, data: &str) -> String {
+    let config = carregar_configuracao_projeto(raiz);
+    let campo = |nome: &str| {
+        config
+            .as_ref()
+            .and_then(|c| c.get(nome))
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .to_string()
+    };
+    let commit = crate::git::commit_atual(raiz).unwrap_or_default();
+    TEMPLATE
+        .replace("{{NOME}}", &literal(&campo("nome")))
+        .replace("{{VERSAO}}", &literal(&campo("versao")))
+        .replace("{{COMMIT}}", &literal(&commit))
+        .replace("{{PERFIL}}", &literal(perfil.nome()))
+        .replace("{{DATA_BUILD}}", &literal(data))
+}
+
+/// Conteudo sem a linha da data, para decidir se o arquivo precisa ser regravado.
+fn sem_data(texto: &str) -> String {
+    texto
+        .lines()
+        .filter(|l| !l.trim_start().starts_with(LINHA_DATA))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Gera o arquivo configurado antes de compilar e devolve o caminho dele.
+///
+/// So grava quando nome, versao, commit ou perfil mudaram: regravar a cada build deixaria
+/// o bytecode sempre desatualizado. A data fica a do ultimo build que gravou o arquivo.
+pub fn preparar(raiz: &Path, perfil: Perfil) -> Result<Option<PathBuf>> {
+    let Some(arquivo) = arquivo_configurado(raiz)? else {
+        return Ok(None);
+    };
+    let novo = renderizar(raiz, perfil, &data_hora_utc());
+    let atual = fs::read_to_string(&arquivo).ok();
+    if atual.as_deref().map(sem_data) == Some(sem_data(&novo)) {
+        detalhe!("Info de build inalterada: {}", arquivo.display());
+        return Ok(Some(arquivo));
+    }
+    if let Some(pasta) = arquivo.parent() {
+        fs::create_dir_all(pasta).with_context(|| format!("Falha ao criar {}", pasta.display()))?;
+    }
+    fs::write(&arquivo, novo).with_context(|| format!("Falha ao gravar {}", arquivo.display()))?;
+    detalhe!("Info de build gerada: {}", arquivo.display());
+    Ok(Some(arquivo))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn regenera_so_quando_os_metadados_mudam() {
+        let temp = tempfile::tempdir().unwrap();
+        let raiz = temp.path();
+        let proj = |versao: &str| {
+            format!(
+                r#"{{"nome": "app \"x\"", "versao": "{}", "configuracao": {{"{}": "gerado/info.pr"}}}}"#,
+                versao, CAMPO
+            )
+        };
+        fs::write(raiz.join("pordosol.proj"), proj("1.0.0")).unwrap();
+
+        let arquivo = preparar(raiz, Perfil::Dev).unwrap().unwrap();
+        assert_eq!(arquivo, raiz.join("gerado/info.pr"));
+        let primeiro = fs::read_to_string(&arquivo).unwrap();
+        assert!(primeiro.contains(r#"NOME = "app \"x\"";"#), "{}", primeiro);
+        assert!(primeiro.contains(r#"VERSAO = "1.0.0";"#), "{}", primeiro);
+        assert!(primeiro.contains(r#"PERFIL = "dev";"#), "{}", primeiro);
+
+        // Mesmos metadados: o arquivo (e a data dentro dele) nao e regravado
+        fs::write(
+            &arquivo,
+            primeiro.replace("DATA_BUILD = \"", "DATA_BUILD = \"antiga "),
+        )
+        .unwrap();
+        preparar(raiz, Perfil::Dev).unwrap();
+        assert!(fs::read_to_string(&arquivo).unwrap().contains("antiga"));
+
+        fs::write(raiz.join("pordosol.proj"), proj("1.1.0")).unwrap();
+        preparar(raiz, Perfil::Dev).unwrap();
+        let novo = fs::read_to_string(&arquivo).unwrap();
+        assert!(novo.contains(r#"VERSAO = "1.1.0";"#), "{}", novo);
+        assert!(!novo.contains("antiga"), "{}", novo);
+
+        preparar(raiz, Perfil::Producao).unwrap();
+        assert!(fs::read_to_string(&arquivo)
+            .unwrap()
+            .contains(r#"PERFIL = "producao";"#));
+    }
+
+    #[test]
+    fn desligado_sem_o_campo_e_invalido_com_outro_tipo() {
+        let temp = tempfile::tempdir().unwrap();
+        fs::write(temp.path().join("pordosol.proj"), r#"{"nome": "app"}"#).unwrap();
+        assert_eq!(preparar(temp.path(), Perfil::Dev).unwrap(), None);
+
+        fs::write(
+            temp.path().join("pordosol.proj"),
+            format!(r#"{{"configuracao": {{"{}": true}}}}"#, CAMPO),
+        )
+        .unwrap();
+        assert!(preparar(temp.path(), Perfil::Dev).is_err());
+    }
+}
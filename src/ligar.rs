@@ -0,0 +1,213 @@
+//! Ligacao do `.ll` gerado por `run --release` em um executavel nativo, com as ferramentas
+//! LLVM do PATH: `clang` direto ou, sem ele, `llc` (gera o objeto) mais `cc` (liga).
+
+use std::ffi::OsString;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use anyhow::{bail, Context, Result};
+
+use crate::construir::pasta_saida_projeto;
+use crate::erros::ErroCli;
+use crate::saida::{descrever_comando, detalhe, progresso};
+use crate::toolchain::nome_executavel;
+
+/// Subpasta da pasta de saida com os executaveis ligados por `run --release`.
+pub const PASTA_RELEASE: &str = "release";
+
+/// Ferramentas procuradas no PATH, na ordem de preferencia.
+const PROCURADOS: &[&str] = &["clang", "llc", "cc"];
+
+/// Executavel nativo ligado a partir de `<stem>.ll`: `<pasta_saida>/release/<stem>`.
+pub fn destino(raiz: &Path, stem: &str) -> PathBuf {
+    pasta_saida_projeto(raiz)
+        .join(PASTA_RELEASE)
+        .join(nome_executavel(stem))
+}
+
+/// Como o `.ll` vira executavel.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Ligador {
+    Clang(PathBuf),
+    LlcCc { llc: PathBuf, cc: PathBuf },
+}
+
+impl fmt::Display for Ligador {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Ligador::Clang(clang) => write!(f, "clang ({})", clang.display()),
+            Ligador::LlcCc { llc, cc } => {
+                write!(f, "llc ({}) + cc ({})", llc.display(), cc.display())
+            }
+        }
+    }
+}
+
+/// Procura o ligador em `path` (o valor de uma variavel PATH).
+///
+/// Sem nenhuma combinacao utilizavel, o erro lista cada ferramenta procurada e se foi achada.
+fn localizar_em(path: Option<OsString>) -> Result<Ligador> {
+    let cwd = std::env::current_dir().unwrap_or_default();
+    let achados: Vec<(&str, Option<PathBuf>)> = PROCURADOS
+        .iter()
+        .map(|nome| (*nome, which::which_in(nome, path.as_ref(), &cwd).ok()))
+        .collect();
+    let achado = |nome: &str| {
+        achados
+            .iter()
+            .find(|(n, _)| *n == nome)
+            .and_then(|(_, c)| c.clone())
+    };
+
+    if let Some(clang) = achado("clang") {
+        return Ok(Ligador::Clang(clang));
+    }
+    if let (Some(llc), Some(cc)) = (achado("llc"), achado("cc")) {
+        return Ok(Ligador::LlcCc { llc, cc });
+    }
+
+    let mut msg = String::from(
+        "Nenhum ligador LLVM encontrado para `run --release` (precisa de clang, ou de llc e cc). Procurados no PATH:",
+    );
+    for (nome, caminho) in &achados {
+        match caminho {
+            Some(c) => msg.push_str(&format!("\n  - {} (encontrado: {})", nome, c.display())),
+            None => msg.push_str(&format!("\n  - {} (ausente)", nome)),
+        }
+    }
+    msg.push_str("\nInstale o clang ou use `--perfil producao` para executar sem ligar.");
+    Err(ErroCli::ToolchainAusente(msg).into())
+}
+
+/// Liga `ll` em `destino` com o ligador do PATH.
+pub fn ligar(ll: &Path, destino: &Path) -> Result<()> {
+    let ligador = localizar_em(std::env::var_os("PATH"))?;
+    ligar_com(&ligador, ll, destino)
+}
+
+fn executar(mut cmd: Command, etapa: &str) -> Result<()> {
+    cmd.stdin(Stdio::null());
+    detalhe!("Executando: {}", descrever_comando(&cmd));
+    let status = cmd
+        .status()
+        .with_context(|| format!("Falha ao executar {}", etapa))?;
+    if !status.success() {
+        bail!("{} falhou (status {})", etapa, status);
+    }
+    Ok(())
+}
+
+fn ligar_com(ligador: &Ligador, ll: &Path, destino: &Path) -> Result<()> {
+    if let Some(pasta) = destino.parent() {
+        fs::create_dir_all(pasta).with_context(|| format!("Falha ao criar {}", pasta.display()))?;
+    }
+    progresso!("Ligando {} com {}...", ll.display(), ligador);
+    match ligador {
+        Ligador::Clang(clang) => {
+            let mut cmd = Command::new(clang);
+            cmd.arg("-O2").arg("-o").arg(destino).arg(ll);
+            executar(cmd, "clang")?;
+        }
+        Ligador::LlcCc { llc, cc } => {
+            let objeto = destino.with_extension("o");
+            let mut cmd = Command::new(llc);
+            cmd.args(["-O2", "-filetype=obj", "-o"])
+                .arg(&objeto)
+                .arg(ll);
+            executar(cmd, "llc")?;
+            let mut cmd = Command::new(cc);
+            cmd.arg("-o").arg(destino).arg(&objeto);
+            let resultado = executar(cmd, "cc");
+            fs::remove_file(&objeto).ok();
+            resultado?;
+        }
+    }
+    if !destino.is_file() {
+        bail!("{} terminou sem gerar {}", ligador, destino.display());
+    }
+    Ok(())
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+
+    /// Ferramenta falsa que registra a chamada e grava o arquivo de `-o`.
+    fn stub(dir: &Path, nome: &str) -> PathBuf {
+        let caminho = dir.join(nome);
+        fs::write(
+            &caminho,
+            format!(
+                "#!/usr/bin/env bash\necho \"{} $*\" >> \"$(dirname \"$0\")/chamadas\"\nwhile [[ $# -gt 0 ]]; do\n  if [[ \"$1\" == -o ]]; then printf 'nativo' > \"$2\"; fi\n  shift\ndone\n",
+                nome
+            ),
+        )
+        .unwrap();
+        fs::set_permissions(&caminho, fs::Permissions::from_mode(0o755)).unwrap();
+        caminho
+    }
+
+    #[test]
+    fn prefere_clang_e_liga_o_ll() {
+        let temp = tempfile::tempdir().unwrap();
+        let bin = temp.path().join("bin");
+        fs::create_dir_all(&bin).unwrap();
+        let clang = stub(&bin, "clang");
+        stub(&bin, "llc");
+        stub(&bin, "cc");
+
+        let ligador = localizar_em(Some(bin.clone().into_os_string())).unwrap();
+        assert_eq!(ligador, Ligador::Clang(clang));
+
+        let ll = temp.path().join("app.ll");
+        fs::write(&ll, "; modulo\n").unwrap();
+        let destino = temp.path().join("build/release/app");
+        ligar_com(&ligador, &ll, &destino).unwrap();
+        assert!(destino.is_file());
+        let chamadas = fs::read_to_string(bin.join("chamadas")).unwrap();
+        assert_eq!(
+            chamadas,
+            format!("clang -O2 -o {} {}\n", destino.display(), ll.display())
+        );
+    }
+
+    #[test]
+    fn sem_clang_usa_llc_e_cc_e_remove_o_objeto() {
+        let temp = tempfile::tempdir().unwrap();
+        let bin = temp.path().join("bin");
+        fs::create_dir_all(&bin).unwrap();
+        stub(&bin, "llc");
+        stub(&bin, "cc");
+
+        let ligador = localizar_em(Some(bin.clone().into_os_string())).unwrap();
+        assert!(matches!(ligador, Ligador::LlcCc { .. }));
+        let ll = temp.path().join("app.ll");
+        fs::write(&ll, "; modulo\n").unwrap();
+        let destino = temp.path().join("release/app");
+        ligar_com(&ligador, &ll, &destino).unwrap();
+        assert!(destino.is_file());
+        assert!(!destino.with_extension("o").exists());
+        let chamadas = fs::read_to_string(bin.join("chamadas")).unwrap();
+        assert!(
+            chamadas.starts_with("llc -O2 -filetype=obj"),
+            "{}",
+            chamadas
+        );
+        assert!(chamadas.contains("\ncc -o "), "{}", chamadas);
+    }
+
+    #[test]
+    fn sem_ligador_lista_o_que_foi_procurado() {
+        let temp = tempfile::tempdir().unwrap();
+        stub(temp.path(), "cc");
+
+        let erro = localizar_em(Some(temp.path().as_os_str().to_owned())).unwrap_err();
+        let msg = format!("{:#}", erro);
+        assert!(msg.contains("- clang (ausente)"), "{}", msg);
+        assert!(msg.contains("- llc (ausente)"), "{}", msg);
+        assert!(msg.contains("- cc (encontrado: "), "{}", msg);
+    }
+}
@@ -3,11 +3,49 @@ use std::path::{Path, PathBuf};
 
 use anyhow::{anyhow, bail, Context, Result};
 use clap::{CommandFactory, Parser, Subcommand};
+use serde_json::value::RawValue;
 
+use crate::saida::progresso;
+
+mod ambiente;
+mod analise;
+mod changelog;
+mod codificacao;
+mod comandos;
+mod comparacao;
+mod configuracao;
 mod construir;
+mod dependencias;
+mod doctor;
+mod duplicados;
+mod erros;
+mod estatisticas;
+mod exclusao;
 mod executar;
+mod explicar;
+mod git;
+mod impressoes;
+mod info_build;
+mod ligar;
+mod manifesto;
+mod manutencao;
+mod nomes;
 mod novo;
+mod permissoes;
+mod projeto;
+mod rede;
+mod relatorio;
+mod resultado;
+mod saida;
+mod templates;
+mod tempos;
+mod testes;
+mod titulo;
 mod toolchain;
+mod trava;
+mod variaveis;
+mod versao;
+mod workspace;
 
 #[derive(Parser, Debug)]
 #[command(name = "pordosol", version, about = "Ferramenta CLI do Por do Sol", long_about = None)]
@@ -18,6 +56,73 @@ struct Cli {
     /// Mostra versao da CLI e tenta detectar a versao do compilador
     #[arg(long = "versao", action = clap::ArgAction::SetTrue)]
     versao: bool,
+    /// Suprime mensagens de progresso (erros e a saida do programa continuam aparecendo)
+    #[arg(short = 'q', long, alias = "silencioso", global = true, action = clap::ArgAction::SetTrue)]
+    quiet: bool,
+    /// Mostra linhas de comando, origens da toolchain e tempo de cada etapa (no stderr)
+    #[arg(
+        short = 'v',
+        long = "verboso",
+        alias = "verbose",
+        global = true,
+        action = clap::ArgAction::SetTrue,
+        conflicts_with = "quiet"
+    )]
+    verboso: bool,
+    /// Compilador a usar nesta execucao (precedencia sobre PORDOSOL_COMPILADOR_PATH)
+    #[arg(long, global = true, value_name = "CAMINHO")]
+    compiler: Option<PathBuf>,
+    /// Interpretador a usar nesta execucao (precedencia sobre PORDOSOL_INTERPRETADOR_PATH)
+    #[arg(long, global = true, value_name = "CAMINHO")]
+    interpreter: Option<PathBuf>,
+    /// Nao usa a trava build/.lock (permite builds simultaneos do mesmo projeto)
+    #[arg(long = "sem-lock", global = true, action = clap::ArgAction::SetTrue)]
+    sem_lock: bool,
+    /// Aceita pasta de saida sobreposta a src/ (clean e o compilador podem apagar ou misturar fontes)
+    #[arg(long = "permitir-saida-perigosa", global = true, action = clap::ArgAction::SetTrue)]
+    permitir_saida_perigosa: bool,
+    /// Falha em vez de acessar a rede (clone de template remoto, download de dependencia)
+    #[arg(long, global = true, action = clap::ArgAction::SetTrue)]
+    offline: bool,
+    /// Mescla pordosol.<AMBIENTE>.proj sobre o pordosol.proj (padrao: PORDOSOL_AMBIENTE)
+    #[arg(long, global = true, value_name = "AMBIENTE")]
+    ambiente: Option<String>,
+    /// Nao confere o compilador contra `toolchain.compilador` do pordosol.proj
+    #[arg(long = "ignorar-toolchain", global = true, action = clap::ArgAction::SetTrue)]
+    ignorar_toolchain: bool,
+    /// Deixa a stdlib fora da impressao digital do build (mudar a stdlib nao invalida artefatos)
+    #[arg(
+        long = "ignorar-stdlib-na-impressao-digital",
+        global = true,
+        action = clap::ArgAction::SetTrue
+    )]
+    ignorar_stdlib_na_impressao_digital: bool,
+    /// Compila tambem os fontes que sao links fisicos de outro fonte (por padrao, so o primeiro)
+    #[arg(long = "permitir-duplicados", global = true, action = clap::ArgAction::SetTrue)]
+    permitir_duplicados: bool,
+    /// Nao executa os `hooks` (pre_build/post_build) do pordosol.proj
+    #[arg(long = "sem-hooks", global = true, action = clap::ArgAction::SetTrue)]
+    sem_hooks: bool,
+    /// Pasta de saida no lugar de build/ (precedencia sobre PORDOSOL_TARGET_DIR e pasta_saida)
+    #[arg(long = "target-dir", global = true, value_name = "PASTA")]
+    target_dir: Option<PathBuf>,
+    /// Flag repassada ao compilador sem interpretacao (apos `configuracao.flags_compilador`); pode ser repetida
+    #[arg(
+        long = "arg-compilador",
+        global = true,
+        value_name = "FLAG",
+        allow_hyphen_values = true
+    )]
+    arg_compilador: Vec<String>,
+    /// Quando colorir a saida (auto respeita NO_COLOR e so colore em terminal)
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        value_name = "QUANDO",
+        default_value = "auto"
+    )]
+    cor: saida::ModoCor,
 
     #[command(subcommand)]
     command: Option<CommandEnum>,
@@ -25,8 +130,12 @@ struct Cli {
 
 #[derive(Subcommand, Debug)]
 enum CommandEnum {
-    /// Cria um projeto (estilo dotnet new), mantendo compatibilidade com `novo <caminho>`
-    #[command(name = "new", alias = "novo", visible_aliases = ["Novo", "criar", "Criar"])]
+    #[command(
+        name = comandos::NEW.nome,
+        aliases = comandos::NEW.aliases,
+        visible_aliases = comandos::NEW.aliases_visiveis,
+        about = comandos::NEW.descricao_pt
+    )]
     New {
         /// Tipo do projeto (console|web|biblioteca|classe), `list` ou caminho legado
         #[arg(value_name = "TIPO_OU_CAMINHO")]
@@ -34,8 +143,8 @@ enum CommandEnum {
         /// Nome do projeto
         #[arg(short = 'n', long = "nome", value_name = "NOME")]
         nome: Option<String>,
-        /// Pasta base de saida
-        #[arg(short = 'o', long = "output", value_name = "PASTA")]
+        /// Pasta base de saida; o projeto fica em `<PASTA>/<NOME>`
+        #[arg(short = 'o', long = "saida", alias = "output", value_name = "PASTA")]
         output: Option<PathBuf>,
         /// Tipo do projeto (compatibilidade)
         #[arg(long, value_name = "TIPO")]
@@ -46,29 +155,90 @@ enum CommandEnum {
         /// Nao sobrescrever arquivos existentes
         #[arg(long, action = clap::ArgAction::SetTrue)]
         nao_sobrescrever: bool,
+        /// Permite criar o projeto numa pasta que ja existe e nao esta vazia
+        #[arg(long, action = clap::ArgAction::SetTrue)]
+        forcar: bool,
+        /// Usa um .pr existente como programa do projeto (copiado; o original nao muda)
+        #[arg(
+            long = "de-arquivo",
+            value_name = "ARQUIVO",
+            conflicts_with = "from_git"
+        )]
+        de_arquivo: Option<PathBuf>,
+        /// Com --de-arquivo, mantem o nome do arquivo e o declara como `entrada`
+        #[arg(long = "manter-nome", action = clap::ArgAction::SetTrue, requires = "de_arquivo")]
+        manter_nome: bool,
+        /// Liga `configuracao.gerar_info_build` (metadados do build num .pr gerado e ignorado pelo git)
+        #[arg(long = "info-build", action = clap::ArgAction::SetTrue, conflicts_with = "from_git")]
+        info_build: bool,
+        /// Cria o projeto clonando um repositorio git (em vez de usar um template)
+        #[arg(long = "from-git", value_name = "URL")]
+        from_git: Option<String>,
+        /// Commit, tag ou branch a usar com --from-git
+        #[arg(long, value_name = "REV", requires = "from_git")]
+        rev: Option<String>,
+        /// Variavel do template (`{{CHAVE}}`); pode ser repetida
+        #[arg(long = "var", value_name = "CHAVE=VALOR", value_parser = novo::parse_var)]
+        vars: Vec<(String, String)>,
+        /// Com `new list`, mostra descricao, versao e autor de cada template
+        #[arg(long, action = clap::ArgAction::SetTrue)]
+        detalhado: bool,
+        /// Mostra os arquivos que seriam criados ou sobrescritos, sem gravar nada
+        #[arg(long = "dry-run", action = clap::ArgAction::SetTrue, conflicts_with = "from_git")]
+        dry_run: bool,
+        /// Sob sudo, devolve os arquivos criados ao usuario que chamou o sudo (`=false` desativa)
+        #[arg(
+            long = "manter-usuario",
+            value_name = "BOOL",
+            num_args = 0..=1,
+            default_value_t = true,
+            default_missing_value = "true",
+            action = clap::ArgAction::Set
+        )]
+        manter_usuario: bool,
     },
 
-    /// Compila arquivos .pr para bytecode (.pbc) por padrao
-    #[command(name = "build", alias = "compilar", visible_aliases = ["Build", "Compilar"])]
+    #[command(
+        name = comandos::BUILD.nome,
+        aliases = comandos::BUILD.aliases,
+        visible_aliases = comandos::BUILD.aliases_visiveis,
+        about = comandos::BUILD.descricao_pt
+    )]
     Build {
-        /// Caminho do projeto ou arquivo .pr (compatibilidade legada)
-        #[arg(value_name = "CAMINHO")]
-        caminho: Option<PathBuf>,
-        /// Caminho do projeto ou arquivo .pr
-        #[arg(long = "project", alias = "projeto", value_name = "CAMINHO")]
-        project: Option<PathBuf>,
-        /// Target de compilacao (bytecode|llvm-ir|cil-bytecode|console|universal)
-        #[arg(long, value_name = "ALVO", default_value = "bytecode")]
-        target: String,
-        /// Caminho de saida (pasta build/ por padrao)
-        #[arg(long, alias = "output")]
-        saida: Option<PathBuf>,
+        #[command(flatten)]
+        args: ArgsBuild,
+        #[command(flatten)]
+        flags: FlagsCompilacao,
     },
 
-    /// Compila e executa o programa (equivalente a dotnet run)
-    #[command(name = "run", alias = "rodar", visible_aliases = ["Rodar"])]
+    #[command(
+        name = comandos::CHECK.nome,
+        aliases = comandos::CHECK.aliases,
+        visible_aliases = comandos::CHECK.aliases_visiveis,
+        about = comandos::CHECK.descricao_pt
+    )]
+    Check {
+        /// Caminho do projeto (padrao: cwd)
+        #[arg(default_value = ".")]
+        caminho: PathBuf,
+        /// Formato dos diagnosticos: texto (saida do compilador) ou json
+        #[arg(
+            long = "formato-mensagens",
+            value_enum,
+            value_name = "FORMATO",
+            default_value = "texto"
+        )]
+        formato_mensagens: construir::FormatoMensagens,
+    },
+
+    #[command(
+        name = comandos::RUN.nome,
+        aliases = comandos::RUN.aliases,
+        visible_aliases = comandos::RUN.aliases_visiveis,
+        about = comandos::RUN.descricao_pt
+    )]
     Run {
-        /// Caminho do projeto ou arquivo .pr (compatibilidade legada)
+        /// Caminho do projeto ou arquivo .pr (compatibilidade legada); `-` le o programa do stdin
         #[arg(value_name = "CAMINHO")]
         caminho: Option<PathBuf>,
         /// Caminho do projeto ou arquivo .pr
@@ -77,51 +247,275 @@ enum CommandEnum {
         /// Nao compilar antes de executar
         #[arg(long = "no-build", action = clap::ArgAction::SetTrue)]
         no_build: bool,
+        /// Com --no-build, falha (em vez de avisar) se houver fontes mais novas que o bytecode
+        #[arg(long, action = clap::ArgAction::SetTrue, requires = "no_build")]
+        strict: bool,
         /// Forca recompilacao mesmo se bytecode estiver atualizado
         #[arg(long, action = clap::ArgAction::SetTrue)]
         force: bool,
         /// Arquivo .pbc especifico para executar (pula deducao)
         #[arg(long)]
         arquivo: Option<PathBuf>,
+        /// Falha se o programa nao sair exatamente com este codigo
+        #[arg(
+            long = "assert-exit",
+            value_name = "CODIGO",
+            allow_negative_numbers = true
+        )]
+        assert_exit: Option<i32>,
+        /// Falha se a saida do programa nao contiver este trecho
+        #[arg(long = "assert-output", value_name = "TEXTO")]
+        assert_output: Option<String>,
+        /// Perfil de build a executar (dev usa build/, producao usa build/producao/)
+        #[arg(long, value_enum, default_value = "dev")]
+        perfil: construir::Perfil,
+        /// Compila via LLVM (fluxo de `producao`, target llvm-ir), liga o .ll com clang (ou
+        /// llc + cc) em <pasta_saida>/release/ e executa o binario nativo
+        #[arg(long, action = clap::ArgAction::SetTrue, conflicts_with = "perfil")]
+        release: bool,
+        /// Nome base do .pbc a gerar/procurar; padrao: `nome` do pordosol.proj
+        #[arg(long = "nome-saida", value_name = "NOME")]
+        nome_saida: Option<String>,
+        /// Codificacao da saida capturada do programa (auto: UTF-8, senao Windows-1252)
+        #[arg(long, value_enum, default_value = "auto")]
+        codificacao: codificacao::Codificacao,
+        /// Nao executa os `configuracao.pre_checks` antes de compilar
+        #[arg(long = "skip-checks", action = clap::ArgAction::SetTrue)]
+        skip_checks: bool,
+        /// Falha (em vez de avisar) se a versao do compilador for incompativel com a CLI
+        #[arg(long = "strict-versao", action = clap::ArgAction::SetTrue)]
+        strict_versao: bool,
+        /// Observa src/**/*.pr e recompila/reinicia o programa a cada mudanca
+        #[arg(
+            long,
+            action = clap::ArgAction::SetTrue,
+            conflicts_with_all = ["assert_exit", "assert_output", "arquivo", "input_json"]
+        )]
+        watch: bool,
+        /// JSON (literal ou caminho de arquivo .json) validado e enviado ao stdin do programa
+        #[arg(long = "input-json", value_name = "JSON|ARQUIVO")]
+        input_json: Option<String>,
+        /// Executa o programa com o ambiente vazio (a compilacao mantem o ambiente)
+        #[arg(long = "env-clear", action = clap::ArgAction::SetTrue)]
+        env_clear: bool,
+        /// Com --env-clear, repassa estas variaveis com os valores atuais (ex.: HOME,PATH)
+        #[arg(
+            long = "env-inherit",
+            value_name = "VARS",
+            value_delimiter = ',',
+            requires = "env_clear"
+        )]
+        env_inherit: Vec<String>,
+        /// Define uma variavel no ambiente do programa (CHAVE=VALOR); repetivel
+        #[arg(long = "env", value_name = "CHAVE=VALOR")]
+        env: Vec<String>,
+        /// Le variaveis CHAVE=VALOR de um arquivo (uma por linha, # comenta); --env tem precedencia
+        #[arg(long = "env-file", value_name = "ARQUIVO")]
+        env_file: Option<PathBuf>,
+        /// Executa tambem este artefato (.pbc ou binario) e compara as saidas com a atual
+        #[arg(
+            long = "comparar-com",
+            value_name = "ARTEFATO",
+            conflicts_with_all = ["watch", "assert_exit", "assert_output"]
+        )]
+        comparar_com: Option<PathBuf>,
+        /// Mascara trechos que casam com o padrao nas duas saidas antes do diff; repetivel
+        #[arg(
+            long = "ignorar-regex",
+            value_name = "PADRAO",
+            requires = "comparar_com"
+        )]
+        ignorar_regex: Vec<String>,
+        /// Com --comparar-com, relata as diferencas sem falhar
+        #[arg(
+            long = "somente-relatar",
+            action = clap::ArgAction::SetTrue,
+            requires = "comparar_com"
+        )]
+        somente_relatar: bool,
+        /// Exclui fontes que casam com o glob (relativo a src/); repetivel
+        #[arg(long, value_name = "GLOB")]
+        excluir: Vec<String>,
+        /// Grava um resumo JSON do comando em ARQUIVO ao terminar, com sucesso ou falha
+        #[arg(long = "resultado", value_name = "ARQUIVO")]
+        arquivo_resultado: Option<PathBuf>,
+        /// Argumentos repassados ao programa (apos `--`)
+        #[arg(last = true, value_name = "ARGS")]
+        args: Vec<String>,
+    },
+
+    #[command(
+        name = comandos::SCRIPT.nome,
+        aliases = comandos::SCRIPT.aliases,
+        visible_aliases = comandos::SCRIPT.aliases_visiveis,
+        about = comandos::SCRIPT.descricao_pt
+    )]
+    Script {
+        /// Arquivo .pr a compilar e executar
+        #[arg(value_name = "ARQUIVO")]
+        arquivo: PathBuf,
+        /// Mantem o bytecode em $TMPDIR/pordosol-cache em vez de remove-lo ao final
+        #[arg(long, action = clap::ArgAction::SetTrue)]
+        manter: bool,
+        /// Argumentos repassados ao programa (apos `--`)
+        #[arg(last = true, value_name = "ARGS")]
+        args: Vec<String>,
+    },
+
+    #[command(
+        name = comandos::TEST.nome,
+        aliases = comandos::TEST.aliases,
+        visible_aliases = comandos::TEST.aliases_visiveis,
+        about = comandos::TEST.descricao_pt
+    )]
+    Test {
+        /// Executa apenas o teste com este nome (stem exato ou prefixo unico)
+        #[arg(value_name = "NOME", conflicts_with = "indice")]
+        nome: Option<String>,
+        /// Executa apenas o teste com este numero (ver --lista)
+        #[arg(long, value_name = "N")]
+        indice: Option<usize>,
+        /// Lista os testes descobertos sem compilar
+        #[arg(long, action = clap::ArgAction::SetTrue)]
+        lista: bool,
+        /// Caminho do projeto
+        #[arg(long = "project", alias = "projeto", default_value = ".")]
+        project: PathBuf,
+        /// Grava um resumo JSON do comando em ARQUIVO ao terminar, com sucesso ou falha
+        #[arg(long = "resultado", value_name = "ARQUIVO")]
+        arquivo_resultado: Option<PathBuf>,
     },
 
-    /// Compila para producao (LLVM), podendo especificar target
-    #[command(name = "producao", alias = "release", visible_aliases = ["Release", "Producao"])]
+    #[command(
+        name = comandos::PRODUCAO.nome,
+        aliases = comandos::PRODUCAO.aliases,
+        visible_aliases = comandos::PRODUCAO.aliases_visiveis,
+        about = comandos::PRODUCAO.descricao_pt
+    )]
     ReleaseInterno {
         /// Caminho do projeto ou arquivo .pr (padrao: cwd)
         #[arg(default_value = ".")]
         caminho: PathBuf,
-        /// Target de producao (ex.: llvm-ir)
-        #[arg(long, default_value = "llvm-ir")]
-        target: String,
+        /// Target (padrao do perfil: llvm-ir em producao, bytecode em dev)
+        #[arg(long)]
+        target: Option<String>,
+        /// Mede as etapas do build e registra em .timings.jsonl na pasta de saida
+        #[arg(long, action = clap::ArgAction::SetTrue)]
+        timings: bool,
+        /// Compila todos os membros do workspace (padrao na raiz de um pordosol.workspace)
+        #[arg(long, action = clap::ArgAction::SetTrue, conflicts_with = "saida")]
+        todos: bool,
+        #[command(flatten)]
+        flags: FlagsCompilacao,
     },
 
-    /// Limpa os artefatos de build (pasta build/)
-    #[command(alias = "limpar", visible_alias = "Limpar")]
+    #[command(
+        name = comandos::CLEAN.nome,
+        aliases = comandos::CLEAN.aliases,
+        visible_aliases = comandos::CLEAN.aliases_visiveis,
+        about = comandos::CLEAN.descricao_pt
+    )]
     Clean {
         /// Caminho do projeto (padrao: cwd)
         #[arg(default_value = ".")]
         caminho: PathBuf,
+        /// Apenas lista o que seria removido, sem apagar nada
+        #[arg(long = "dry-run", action = clap::ArgAction::SetTrue)]
+        dry_run: bool,
+        /// Emite a lista do dry-run em JSON (caminhos e tamanho total)
+        #[arg(long, action = clap::ArgAction::SetTrue, requires = "dry_run")]
+        json: bool,
+        /// Limpa todos os membros do workspace (padrao na raiz de um pordosol.workspace)
+        #[arg(long, action = clap::ArgAction::SetTrue, conflicts_with = "json")]
+        todos: bool,
+        /// Como --todos, e remove tambem o cache do workspace (.pordosol/cache na raiz dele)
+        #[arg(long, action = clap::ArgAction::SetTrue, conflicts_with = "json")]
+        tudo: bool,
+        /// Num workspace, segue para os proximos membros apos uma falha
+        #[arg(long, action = clap::ArgAction::SetTrue)]
+        continuar: bool,
+        /// Grava um resumo JSON do comando em ARQUIVO ao terminar, com sucesso ou falha
+        #[arg(long = "resultado", value_name = "ARQUIVO")]
+        arquivo_resultado: Option<PathBuf>,
     },
 
-    /// Mostra informacoes sobre o projeto
-    #[command(visible_alias = "Info")]
+    #[command(
+        name = comandos::INFO.nome,
+        aliases = comandos::INFO.aliases,
+        visible_aliases = comandos::INFO.aliases_visiveis,
+        about = comandos::INFO.descricao_pt
+    )]
     Info {
         /// Caminho do projeto (padrao: cwd)
         #[arg(default_value = ".")]
         caminho: PathBuf,
+        /// Mostra como cada dependencia e resolvida (versao ou path, e se o path e valido)
+        #[arg(long, action = clap::ArgAction::SetTrue)]
+        deps: bool,
     },
 
-    /// Diagnostica toolchain global (compilador, interpretador e stdlib)
-    #[command(name = "doctor", alias = "diagnostico", visible_aliases = ["Doctor", "Diagnostico"])]
+    #[command(
+        name = comandos::DOCTOR.nome,
+        aliases = comandos::DOCTOR.aliases,
+        visible_aliases = comandos::DOCTOR.aliases_visiveis,
+        about = comandos::DOCTOR.descricao_pt
+    )]
     Doctor {
         /// Caminho de referencia para detectar fallback local
         #[arg(default_value = ".")]
         caminho: PathBuf,
+        /// Emite o diagnostico em JSON (para IDEs e extensoes de editor)
+        #[arg(long, action = clap::ArgAction::SetTrue)]
+        json: bool,
+        /// Exporta um snapshot da toolchain (stdout ou ARQUIVO) para uso com --compare
+        #[arg(long, value_name = "ARQUIVO", num_args = 0..=1)]
+        export: Option<Option<PathBuf>>,
+        /// Compara a toolchain detectada com um snapshot gerado por --export
+        #[arg(long, value_name = "ARQUIVO", conflicts_with = "export")]
+        compare: Option<PathBuf>,
+        /// Ignora caminhos na comparacao (apenas origem, versao e presenca)
+        #[arg(long = "ignore-paths", action = clap::ArgAction::SetTrue, requires = "compare")]
+        ignore_paths: bool,
+        /// Falha se a ferramenta estiver abaixo da versao (ex.: compilador=1.2.0); repetivel
+        #[arg(
+            long = "min-version",
+            value_name = "FERRAMENTA=VERSAO",
+            value_delimiter = ',',
+            conflicts_with_all = ["export", "compare"]
+        )]
+        min_version: Vec<String>,
+        /// Sai com 0 mesmo com o ambiente incompleto (por padrao, pendencias saem com codigo 2)
+        #[arg(long = "nao-falhar", alias = "no-fail", action = clap::ArgAction::SetTrue)]
+        nao_falhar: bool,
+        /// Trata avisos (ex.: templates com placeholders desconhecidos) como pendencias
+        #[arg(long, alias = "strict", action = clap::ArgAction::SetTrue)]
+        estrito: bool,
+    },
+
+    #[command(
+        name = comandos::ENV.nome,
+        aliases = comandos::ENV.aliases,
+        visible_aliases = comandos::ENV.aliases_visiveis,
+        about = comandos::ENV.descricao_pt
+    )]
+    Env {
+        /// Caminho de referencia para detectar fallback local
+        #[arg(default_value = ".")]
+        caminho: PathBuf,
+        /// Sintaxe das linhas: sh (export) ou powershell ($env:)
+        #[arg(long, value_enum, default_value = "sh")]
+        shell: variaveis::Shell,
+        /// Emite as variaveis em JSON, com a origem de cada uma
+        #[arg(long, action = clap::ArgAction::SetTrue, conflicts_with = "shell")]
+        json: bool,
     },
 
-    /// Lista os arquivos .pr do projeto
-    #[command(visible_alias = "Listar")]
+    #[command(
+        name = comandos::LISTAR.nome,
+        aliases = comandos::LISTAR.aliases,
+        visible_aliases = comandos::LISTAR.aliases_visiveis,
+        about = comandos::LISTAR.descricao_pt
+    )]
     Listar {
         /// Caminho do projeto (padrao: cwd)
         #[arg(default_value = ".")]
@@ -129,12 +523,50 @@ enum CommandEnum {
         /// Mostrar apenas arquivos modificados recentemente
         #[arg(long, action = clap::ArgAction::SetTrue)]
         recentes: bool,
+        /// Exclui fontes que casam com o glob (relativo a src/); repetivel
+        #[arg(long, value_name = "GLOB")]
+        excluir: Vec<String>,
+        /// Lista tambem os fontes excluidos, marcados com (excluido)
+        #[arg(long = "mostrar-excluidos", action = clap::ArgAction::SetTrue)]
+        mostrar_excluidos: bool,
+        /// Compara os fontes com o ultimo build: novo, modificado, inalterado ou removido
+        #[arg(
+            long,
+            action = clap::ArgAction::SetTrue,
+            conflicts_with_all = ["recentes", "mostrar_excluidos"]
+        )]
+        mudancas: bool,
+        /// Com --mudancas, mostra so os fontes nessa situacao
+        #[arg(long, value_enum, value_name = "SITUACAO", requires = "mudancas")]
+        somente: Option<impressoes::SituacaoFonte>,
+        /// Emite a lista (ou, com --mudancas, o relatorio) em JSON
+        #[arg(long, action = clap::ArgAction::SetTrue)]
+        json: bool,
+        /// Ordena a lista (crescente: nome, menor primeiro, mais antigo primeiro)
+        #[arg(long, value_enum, value_name = "CRITERIO", conflicts_with = "mudancas")]
+        ordenar: Option<OrdemListagem>,
+        /// Inverte a ordem de --ordenar
+        #[arg(long, action = clap::ArgAction::SetTrue, requires = "ordenar")]
+        reverso: bool,
+        /// Falha (em vez de avisar) com nomes de fonte problematicos (espacos, nao-ASCII, colisoes)
+        #[arg(long, action = clap::ArgAction::SetTrue)]
+        strict: bool,
+        /// Lista os fontes de todos os membros do workspace (padrao na raiz de um pordosol.workspace)
+        #[arg(long, action = clap::ArgAction::SetTrue, conflicts_with = "json")]
+        todos: bool,
+        /// Num workspace, segue para os proximos membros apos uma falha
+        #[arg(long, action = clap::ArgAction::SetTrue)]
+        continuar: bool,
     },
 
-    /// Gerencia dependencias do projeto (add, remove, list)
-    #[command(visible_alias = "Dep")]
+    #[command(
+        name = comandos::DEP.nome,
+        aliases = comandos::DEP.aliases,
+        visible_aliases = comandos::DEP.aliases_visiveis,
+        about = comandos::DEP.descricao_pt
+    )]
     Dep {
-        /// Acao: add|remove|list
+        /// Acao: add|remove|list|resolve|tree|install
         #[arg(value_name = "ACAO", default_value = "list")]
         acao: String,
         /// Nome da dependencia (para add/remove)
@@ -149,11 +581,410 @@ enum CommandEnum {
         /// Caminho do projeto (padrao: cwd)
         #[arg(long, default_value = ".")]
         caminho_projeto: PathBuf,
+        /// Saida em JSON (para resolve e tree)
+        #[arg(long)]
+        json: bool,
+        /// Limita os niveis expandidos pelo tree (1 = apenas dependencias diretas)
+        #[arg(long, value_name = "N", value_parser = clap::value_parser!(usize))]
+        profundidade: Option<usize>,
+    },
+
+    #[command(
+        name = comandos::STATS.nome,
+        aliases = comandos::STATS.aliases,
+        visible_aliases = comandos::STATS.aliases_visiveis,
+        about = comandos::STATS.descricao_pt
+    )]
+    Stats {
+        /// Caminho do projeto (padrao: cwd)
+        #[arg(default_value = ".")]
+        caminho: PathBuf,
+        /// Emite as estatisticas em JSON
+        #[arg(long, action = clap::ArgAction::SetTrue)]
+        json: bool,
+        /// Lista uma linha por arquivo, ordenada por numero de linhas
+        #[arg(long = "por-arquivo", action = clap::ArgAction::SetTrue)]
+        por_arquivo: bool,
+    },
+
+    #[command(
+        name = comandos::ANALISAR.nome,
+        aliases = comandos::ANALISAR.aliases,
+        visible_aliases = comandos::ANALISAR.aliases_visiveis,
+        about = comandos::ANALISAR.descricao_pt
+    )]
+    Analisar {
+        #[command(subcommand)]
+        acao: AcaoAnalisar,
+    },
+
+    #[command(
+        name = comandos::TEMPLATES.nome,
+        aliases = comandos::TEMPLATES.aliases,
+        visible_aliases = comandos::TEMPLATES.aliases_visiveis,
+        about = comandos::TEMPLATES.descricao_pt
+    )]
+    Templates {
+        #[command(subcommand)]
+        acao: AcaoTemplates,
+    },
+
+    #[command(
+        name = comandos::TOOLCHAIN.nome,
+        aliases = comandos::TOOLCHAIN.aliases,
+        visible_aliases = comandos::TOOLCHAIN.aliases_visiveis,
+        about = comandos::TOOLCHAIN.descricao_pt
+    )]
+    Toolchain {
+        #[command(subcommand)]
+        acao: AcaoToolchain,
+    },
+
+    #[command(
+        name = comandos::RENAME.nome,
+        aliases = comandos::RENAME.aliases,
+        visible_aliases = comandos::RENAME.aliases_visiveis,
+        about = comandos::RENAME.descricao_pt
+    )]
+    Rename {
+        /// Novo nome do projeto
+        #[arg(value_name = "NOVO_NOME")]
+        novo_nome: String,
+        /// Tambem substitui o nome e o namespace antigos no README.md
+        #[arg(long, action = clap::ArgAction::SetTrue)]
+        readme: bool,
+        /// Caminho do projeto
+        #[arg(long = "project", alias = "projeto", default_value = ".")]
+        project: PathBuf,
+    },
+
+    #[command(
+        name = comandos::CHANGELOG.nome,
+        aliases = comandos::CHANGELOG.aliases,
+        visible_aliases = comandos::CHANGELOG.aliases_visiveis,
+        about = comandos::CHANGELOG.descricao_pt
+    )]
+    Changelog {
+        /// Caminho do projeto (padrao: cwd)
+        #[arg(default_value = ".")]
+        caminho: PathBuf,
+        /// Tag ou commit base (padrao: ultima mudanca de versao do pordosol.proj)
+        #[arg(long)]
+        desde: Option<String>,
+        /// Versao da nova secao (padrao: campo `versao` do pordosol.proj)
+        #[arg(long)]
+        versao: Option<String>,
+        /// Imprime a secao em vez de gravar o CHANGELOG.md
+        #[arg(long = "dry-run", action = clap::ArgAction::SetTrue)]
+        dry_run: bool,
+    },
+
+    #[command(
+        name = comandos::CONFIG.nome,
+        aliases = comandos::CONFIG.aliases,
+        visible_aliases = comandos::CONFIG.aliases_visiveis,
+        about = comandos::CONFIG.descricao_pt
+    )]
+    Config {
+        #[command(subcommand)]
+        acao: AcaoConfig,
+    },
+
+    #[command(
+        name = comandos::VERSAO.nome,
+        aliases = comandos::VERSAO.aliases,
+        visible_aliases = comandos::VERSAO.aliases_visiveis,
+        about = comandos::VERSAO.descricao_pt
+    )]
+    Versao {
+        /// Inclui a stdlib e mostra, para cada ferramenta, se foi encontrada e de onde veio
+        #[arg(long = "all", alias = "todas", action = clap::ArgAction::SetTrue)]
+        all: bool,
+    },
+
+    #[command(
+        name = comandos::MANUTENCAO.nome,
+        aliases = comandos::MANUTENCAO.aliases,
+        visible_aliases = comandos::MANUTENCAO.aliases_visiveis,
+        about = comandos::MANUTENCAO.descricao_pt
+    )]
+    Manutencao {
+        /// Caminho do projeto (padrao: cwd)
+        #[arg(default_value = ".")]
+        caminho: PathBuf,
+        /// Apaga e apara o que passou da retencao (sem a flag, so relata)
+        #[arg(long, action = clap::ArgAction::SetTrue)]
+        aplicar: bool,
+    },
+
+    #[command(
+        name = comandos::EXPLAIN.nome,
+        aliases = comandos::EXPLAIN.aliases,
+        visible_aliases = comandos::EXPLAIN.aliases_visiveis,
+        about = comandos::EXPLAIN.descricao_pt
+    )]
+    Explain {
+        /// Codigo do erro (ex.: E0012); omitido, lista os codigos conhecidos
+        #[arg(value_name = "CODIGO")]
+        codigo: Option<String>,
+    },
+
+    #[command(
+        name = comandos::COMANDOS.nome,
+        aliases = comandos::COMANDOS.aliases,
+        visible_aliases = comandos::COMANDOS.aliases_visiveis,
+        about = comandos::COMANDOS.descricao_pt
+    )]
+    Comandos {
+        /// Emite a tabela de comandos em JSON (para geracao de documentacao)
+        #[arg(long, action = clap::ArgAction::SetTrue)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum AcaoAnalisar {
+    /// Quais fontes mais contribuem para o bytecode e o que cresceu desde o build anterior
+    #[command(alias = "size")]
+    Tamanho {
+        /// Emite a tabela e o crescimento em JSON
+        #[arg(long, action = clap::ArgAction::SetTrue)]
+        json: bool,
+        /// Caminho do projeto (padrao: cwd)
+        #[arg(long = "project", alias = "projeto", default_value = ".")]
+        project: PathBuf,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum AcaoConfig {
+    /// Valor efetivo de uma chave e a precedencia entre flag, ambiente, pordosol.proj e padrao
+    Origem {
+        /// Chave (target, saida, otimizacao, compilador, interpretador, stdlib)
+        #[arg(required_unless_present = "todas")]
+        chave: Option<String>,
+        /// Lista todas as chaves com o valor efetivo e a camada de origem
+        #[arg(long, action = clap::ArgAction::SetTrue, conflicts_with = "chave")]
+        todas: bool,
+        /// Target que seria passado ao build (entra como camada de flag)
+        #[arg(long, value_name = "ALVO")]
+        target: Option<String>,
+        /// Pasta de saida que seria passada ao build (entra como camada de flag)
+        #[arg(long, alias = "output")]
+        saida: Option<PathBuf>,
+        /// Emite valores e camadas em JSON
+        #[arg(long, action = clap::ArgAction::SetTrue)]
+        json: bool,
+        /// Caminho do projeto (padrao: cwd)
+        #[arg(long = "project", alias = "projeto", default_value = ".")]
+        project: PathBuf,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum AcaoTemplates {
+    /// Instala template(s) de uma pasta local ou de um repositorio git (clone raso)
+    #[command(aliases = ["add", "adicionar"])]
+    Instalar {
+        /// Pasta do template ou URL git; uma pasta sem arquivos na raiz instala cada subpasta
+        #[arg(value_name = "ORIGEM")]
+        origem: String,
+        /// Substitui um template ja instalado com o mesmo nome
+        #[arg(long, alias = "force", action = clap::ArgAction::SetTrue)]
+        forcar: bool,
+    },
+    /// Remove um template instalado na pasta do usuario
+    #[command(alias = "remove")]
+    Remover {
+        #[arg(value_name = "NOME")]
+        nome: String,
+    },
+    /// Procura problemas nos templates (manifesto, pasta vazia, placeholders desconhecidos)
+    #[command(alias = "check")]
+    Verificar {
+        /// Template a verificar (padrao: todos)
+        #[arg(value_name = "NOME")]
+        nome: Option<String>,
+    },
+    /// Lista os templates disponiveis (como `new list`)
+    #[command(alias = "list")]
+    Listar {
+        /// Mostra descricao, versao e autor de cada template
+        #[arg(long, action = clap::ArgAction::SetTrue)]
+        detalhado: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum AcaoToolchain {
+    /// Imprime apenas o caminho absoluto da ferramenta resolvida (sai com 1 se ausente)
+    Which {
+        #[arg(value_enum)]
+        ferramenta: toolchain::Ferramenta,
+        /// Imprime o rotulo de origem em vez do caminho
+        #[arg(long, action = clap::ArgAction::SetTrue, conflicts_with = "todos")]
+        origem: bool,
+        /// Lista todos os candidatos considerados, em ordem, marcando os encontrados
+        #[arg(long, action = clap::ArgAction::SetTrue)]
+        todos: bool,
+        /// Caminho do projeto (padrao: cwd)
+        #[arg(long = "project", alias = "projeto", default_value = ".")]
+        project: PathBuf,
     },
 }
 
-fn main() -> Result<()> {
+/// Argumentos proprios do `build` (perfil dev); os comuns com `producao` ficam em
+/// [`FlagsCompilacao`].
+#[derive(clap::Args, Debug, Default)]
+struct ArgsBuild {
+    /// Caminho do projeto ou arquivo .pr (compatibilidade legada)
+    #[arg(value_name = "CAMINHO")]
+    caminho: Option<PathBuf>,
+    /// Caminho do projeto ou arquivo .pr
+    #[arg(long = "project", alias = "projeto", value_name = "CAMINHO")]
+    project: Option<PathBuf>,
+    /// Target de compilacao (bytecode|llvm-ir|cil-bytecode|console|universal); aceita lista separada por virgula (padrao: bytecode)
+    #[arg(long, value_name = "ALVO", value_delimiter = ',')]
+    target: Vec<String>,
+    /// Nome base do artefato (.pbc/.ll); padrao: `nome` do pordosol.proj
+    #[arg(long = "nome-saida", value_name = "NOME")]
+    nome_saida: Option<String>,
+    /// Emite o relatorio de execucao por target (num workspace, por membro) em JSON
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    json: bool,
+    /// Para no primeiro target que falhar (os demais aparecem como nao executados)
+    #[arg(long = "fail-fast", action = clap::ArgAction::SetTrue)]
+    fail_fast: bool,
+    /// Mede as etapas do build e registra em .timings.jsonl na pasta de saida
+    #[arg(long, action = clap::ArgAction::SetTrue, conflicts_with = "json")]
+    timings: bool,
+    /// Observa src/**/*.pr e recompila a cada mudanca (sem executar); so os alterados se o compilador anunciar --incremental
+    #[arg(long, action = clap::ArgAction::SetTrue, conflicts_with = "json")]
+    watch: bool,
+    /// Imprime apenas uma linha de resumo (OK: N arquivos, M artefatos, T s | FALHOU: ...)
+    #[arg(
+        long,
+        action = clap::ArgAction::SetTrue,
+        conflicts_with_all = ["json", "watch", "timings"]
+    )]
+    oneline: bool,
+    /// Mostra de onde vem cada flag passada ao compilador (CLI, pordosol.proj, padrao), sem compilar
+    #[arg(
+        long = "explain-flags",
+        action = clap::ArgAction::SetTrue,
+        conflicts_with_all = ["json", "watch", "timings", "oneline"]
+    )]
+    explain_flags: bool,
+    /// Grava as dependencias do .pbc no formato .d do Make (alvo: fontes)
+    #[arg(long = "emit-deps", value_name = "ARQUIVO")]
+    emit_deps: Option<PathBuf>,
+    /// Compila todos os membros do workspace (padrao na raiz de um pordosol.workspace)
+    #[arg(
+        long,
+        action = clap::ArgAction::SetTrue,
+        conflicts_with_all = ["watch", "oneline", "explain_flags", "saida"]
+    )]
+    todos: bool,
+}
+
+/// Flags comuns a `build` e `producao`: `producao` e o `build` com o perfil de producao
+/// preselecionado, entao as duas superficies andam juntas.
+#[derive(clap::Args, Debug)]
+struct FlagsCompilacao {
+    /// Perfil: dev (build/) ou producao (llvm-ir em <pasta_saida>/producao); padrao: o do comando
+    #[arg(long, value_enum, value_name = "PERFIL")]
+    perfil: Option<construir::Perfil>,
+    /// Repassa um --target desconhecido ao compilador em vez de falhar
+    #[arg(long = "target-livre", action = clap::ArgAction::SetTrue)]
+    target_livre: bool,
+    /// Caminho de saida (padrao: a pasta do perfil, configuracao.pasta_saida ou build/)
+    #[arg(long, alias = "output")]
+    saida: Option<PathBuf>,
+    /// Nao executa os `configuracao.pre_checks` do pordosol.proj
+    #[arg(long = "skip-checks", action = clap::ArgAction::SetTrue)]
+    skip_checks: bool,
+    /// Falha (em vez de avisar) se a versao do compilador for incompativel com a CLI
+    #[arg(long = "strict-versao", action = clap::ArgAction::SetTrue)]
+    strict_versao: bool,
+    /// Exclui fontes que casam com o glob (relativo a src/); repetivel
+    #[arg(long, value_name = "GLOB")]
+    excluir: Vec<String>,
+    /// Usa as copias de `dep install` em deps/ para as dependencias path
+    #[arg(long = "com-deps", action = clap::ArgAction::SetTrue)]
+    com_deps: bool,
+    /// Falha (em vez de avisar) com nomes de fonte problematicos (espacos, nao-ASCII, colisoes)
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    strict: bool,
+    /// Grava checksums.txt com o hash de cada artefato na pasta de saida; `--checksums=false` desliga
+    #[arg(
+        long,
+        value_name = "BOOL",
+        default_value_t = true,
+        num_args = 0..=1,
+        default_missing_value = "true",
+        action = clap::ArgAction::Set
+    )]
+    checksums: bool,
+    /// Mostra o comando do compilador sem executar nada (hooks, pre_checks e compilador)
+    #[arg(long = "dry-run", action = clap::ArgAction::SetTrue)]
+    dry_run: bool,
+    /// Formato dos diagnosticos do compilador: texto (como vieram) ou json (um objeto por compilacao)
+    #[arg(
+        long = "message-format",
+        alias = "formato-mensagens",
+        value_enum,
+        value_name = "FORMATO",
+        default_value = "texto"
+    )]
+    message_format: construir::FormatoMensagens,
+    /// Compilacoes simultaneas: membros de um workspace (padrao: 1 no build, numero de CPUs em
+    /// producao); o compilador recebe o valor em PORDOSOL_JOBS
+    #[arg(long, short = 'j', value_name = "N", value_parser = clap::value_parser!(u16).range(1..))]
+    jobs: Option<u16>,
+    /// Num workspace, segue para os proximos membros apos uma falha
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    continuar: bool,
+    /// Grava um resumo JSON do comando em ARQUIVO ao terminar, com sucesso ou falha
+    #[arg(long = "resultado", value_name = "ARQUIVO")]
+    arquivo_resultado: Option<PathBuf>,
+}
+
+fn main() {
+    let _resultado = resultado::Guarda;
+    if let Err(erro) = executar_cli() {
+        eprintln!(
+            "{} {:?}",
+            saida::pintar_erro(saida::Cor::Vermelho, "Error:"),
+            erro
+        );
+        resultado::concluir(Some(&erro));
+        titulo::restaurar();
+        std::process::exit(erros::codigo_saida(&erro));
+    }
+    resultado::concluir(None);
+}
+
+fn executar_cli() -> Result<()> {
     let cli = Cli::parse();
+    saida::definir_nivel(if cli.quiet {
+        saida::Nivel::Silencioso
+    } else if cli.verboso {
+        saida::Nivel::Verboso
+    } else {
+        saida::Nivel::Normal
+    });
+    saida::definir_cor(cli.cor);
+    trava::definir_sem_trava(cli.sem_lock);
+    construir::definir_permitir_saida_perigosa(cli.permitir_saida_perigosa);
+    construir::definir_ignorar_toolchain(cli.ignorar_toolchain);
+    construir::definir_sem_hooks(cli.sem_hooks);
+    impressoes::definir_ignorar_stdlib(cli.ignorar_stdlib_na_impressao_digital);
+    duplicados::definir_permitir_duplicados(cli.permitir_duplicados);
+    configuracao::definir_target_dir(cli.target_dir.as_deref());
+    configuracao::definir_args_compilador(&cli.arg_compilador);
+    ambiente::definir_ambiente(cli.ambiente.as_deref())?;
+    rede::definir_offline(cli.offline);
+    toolchain::definir_overrides(cli.compiler.as_deref(), cli.interpreter.as_deref())?;
 
     if cli.ajuda {
         let mut cmd = Cli::command();
@@ -176,7 +1007,29 @@ fn main() -> Result<()> {
             tipo,
             template,
             nao_sobrescrever,
+            forcar,
+            de_arquivo,
+            manter_nome,
+            info_build,
+            from_git,
+            rev,
+            vars,
+            detalhado,
+            dry_run,
+            manter_usuario,
         }) => {
+            if let Some(url) = from_git.as_deref() {
+                let (destino, _) = resolver_new_params(
+                    tipo_ou_caminho.as_deref(),
+                    nome.as_deref(),
+                    output.as_deref(),
+                    tipo.as_deref(),
+                    template.as_deref(),
+                )?;
+                novo::novo_de_git(&destino, url, rev.as_deref())?;
+                permissoes::ajustar_dono_apos_sudo(&destino, None, manter_usuario);
+                return Ok(());
+            }
             if eh_new_list_request(
                 tipo_ou_caminho.as_deref(),
                 nome.as_deref(),
@@ -184,7 +1037,7 @@ fn main() -> Result<()> {
                 tipo.as_deref(),
                 template.as_deref(),
             ) {
-                return novo::listar_templates_cmd();
+                return novo::listar_templates_cmd(detalhado);
             }
             let (destino, template_final) = resolver_new_params(
                 tipo_ou_caminho.as_deref(),
@@ -193,47 +1046,374 @@ fn main() -> Result<()> {
                 tipo.as_deref(),
                 template.as_deref(),
             )?;
-            novo::novo_cmd(&destino, nao_sobrescrever, &template_final)
+            // Pasta ja existente pode ter arquivos de outros donos: so o que for gravado agora muda
+            let desde = destino.exists().then(std::time::SystemTime::now);
+            novo::novo_cmd(
+                &destino,
+                &template_final,
+                &novo::OpcoesNovo {
+                    nome: nome.as_deref(),
+                    nao_sobrescrever,
+                    forcar,
+                    extras: &vars,
+                    dry_run,
+                    de_arquivo: de_arquivo.as_deref(),
+                    manter_nome,
+                    info_build,
+                },
+            )?;
+            if !dry_run {
+                permissoes::ajustar_dono_apos_sudo(&destino, desde, manter_usuario);
+            }
+            Ok(())
         }
-        Some(CommandEnum::Build {
-            caminho,
-            project,
-            target,
-            saida,
-        }) => {
-            let caminho_final = resolver_project_path(project.as_deref(), caminho.as_deref());
-            construir::compilar_cmd(&caminho_final, &target, saida.as_deref())
+        Some(CommandEnum::Build { args, flags }) => {
+            aplicar_flags_compilacao(&flags, comandos::BUILD.nome);
+            let caminho_final =
+                resolver_project_path(args.project.as_deref(), args.caminho.as_deref());
+            match flags.perfil.unwrap_or(construir::Perfil::Dev) {
+                construir::Perfil::Dev => build_cli(&caminho_final, args, &flags),
+                construir::Perfil::Producao => {
+                    if args.target.len() > 1
+                        || args.nome_saida.is_some()
+                        || args.json
+                        || args.fail_fast
+                        || args.watch
+                        || args.oneline
+                        || args.explain_flags
+                        || args.emit_deps.is_some()
+                    {
+                        bail!(
+                            "Varios targets, --nome-saida, --json, --fail-fast, --watch, --oneline, --explain-flags e --emit-deps valem para o perfil dev"
+                        );
+                    }
+                    producao_cli(
+                        &caminho_final,
+                        args.target.first().map(String::as_str),
+                        args.timings,
+                        args.todos,
+                        &flags,
+                    )
+                }
+            }
         }
+        Some(CommandEnum::Check {
+            caminho,
+            formato_mensagens,
+        }) => construir::check_cmd(&caminho, formato_mensagens),
+        Some(CommandEnum::Script {
+            arquivo,
+            manter,
+            args,
+        }) => executar::script_cmd(
+            &arquivo,
+            &executar::OpcoesScript {
+                manter,
+                args: &args,
+            },
+        ),
         Some(CommandEnum::Run {
             caminho,
             project,
             no_build,
+            strict,
             force,
             arquivo,
+            assert_exit,
+            assert_output,
+            perfil,
+            release,
+            nome_saida,
+            codificacao,
+            skip_checks,
+            strict_versao,
+            watch,
+            input_json,
+            env_clear,
+            env_inherit,
+            env,
+            env_file,
+            comparar_com,
+            ignorar_regex,
+            somente_relatar,
+            excluir,
+            arquivo_resultado,
+            args,
         }) => {
-            let caminho_final = resolver_project_path(project.as_deref(), caminho.as_deref());
-            executar::run_cmd(&caminho_final, force, arquivo.as_deref(), no_build)
+            resultado::definir_resultado(arquivo_resultado.as_deref(), comandos::RUN.nome);
+            exclusao::definir_exclusoes_cli(excluir);
+            let caminho_final = resolver_caminho_run(project.as_deref(), caminho.as_deref())?;
+            executar::run_cmd(
+                &caminho_final,
+                &executar::OpcoesRun {
+                    force,
+                    arquivo: arquivo.as_deref(),
+                    no_build,
+                    strict,
+                    assert_exit,
+                    assert_output: assert_output.as_deref(),
+                    perfil: if release {
+                        construir::Perfil::Producao
+                    } else {
+                        perfil
+                    },
+                    release,
+                    nome_saida: nome_saida.as_deref(),
+                    codificacao,
+                    skip_checks,
+                    strict_versao,
+                    watch,
+                    input_json: input_json.as_deref(),
+                    env_clear,
+                    env_inherit: &env_inherit,
+                    env: &env,
+                    env_file: env_file.as_deref(),
+                    comparar_com: comparar_com.as_deref(),
+                    ignorar_regex: &ignorar_regex,
+                    somente_relatar,
+                    args: &args,
+                },
+            )
         }
-        Some(CommandEnum::ReleaseInterno { caminho, target }) => {
-            construir::producao_cmd(&caminho, &target)
+        Some(CommandEnum::ReleaseInterno {
+            caminho,
+            target,
+            timings,
+            todos,
+            flags,
+        }) => {
+            aplicar_flags_compilacao(&flags, comandos::PRODUCAO.nome);
+            match flags.perfil.unwrap_or(construir::Perfil::Producao) {
+                construir::Perfil::Producao => {
+                    producao_cli(&caminho, target.as_deref(), timings, todos, &flags)
+                }
+                construir::Perfil::Dev => build_cli(
+                    &caminho,
+                    ArgsBuild {
+                        target: target.into_iter().collect(),
+                        timings,
+                        todos,
+                        ..ArgsBuild::default()
+                    },
+                    &flags,
+                ),
+            }
+        }
+        Some(CommandEnum::Clean {
+            caminho,
+            dry_run,
+            json,
+            todos,
+            tudo,
+            continuar,
+            arquivo_resultado,
+        }) => {
+            resultado::definir_resultado(arquivo_resultado.as_deref(), comandos::CLEAN.nome);
+            // Presente mesmo quando nada e removido (um item por arquivo ou pasta apagado)
+            resultado::registrar("removidos", Vec::<PathBuf>::new());
+            resultado::registrar("dry_run", dry_run);
+            if usar_workspace(
+                &caminho,
+                &toolchain::localizar_raiz(&caminho),
+                todos || tudo,
+            ) {
+                if json {
+                    bail!("--json vale para um projeto: use o caminho do membro");
+                }
+                let (raiz_ws, membros) = workspace::membros_de(&caminho)?;
+                let limpeza = workspace::executar_em_membros(
+                    &membros,
+                    &workspace::OpcoesMembros::em_ordem("-", continuar),
+                    |m| clean_cmd(&m.raiz, dry_run, false).map(|()| None),
+                );
+                if tudo {
+                    // O cache sai mesmo que algum membro falhe: ele nao vale sem os artefatos
+                    workspace::limpar_cache(&raiz_ws, dry_run)?;
+                }
+                limpeza
+            } else {
+                clean_cmd(&caminho, dry_run, json)
+            }
+        }
+        Some(CommandEnum::Info { caminho, deps }) => info_cmd(&caminho, deps),
+        Some(CommandEnum::Doctor {
+            caminho,
+            json,
+            export,
+            compare,
+            ignore_paths,
+            min_version,
+            nao_falhar,
+            estrito,
+        }) => doctor::doctor_cmd(
+            &caminho,
+            &doctor::OpcoesDoctor {
+                json,
+                export: export.as_ref().map(|e| e.as_deref()),
+                compare: compare.as_deref(),
+                ignore_paths,
+                min_versoes: &min_version,
+                nao_falhar,
+                estrito,
+            },
+        ),
+        Some(CommandEnum::Env {
+            caminho,
+            shell,
+            json,
+        }) => variaveis::env_cmd(&caminho, shell, json),
+        Some(CommandEnum::Listar {
+            caminho,
+            recentes,
+            excluir,
+            mostrar_excluidos,
+            mudancas,
+            somente,
+            json,
+            ordenar,
+            reverso,
+            strict,
+            todos,
+            continuar,
+        }) => {
+            exclusao::definir_exclusoes_cli(excluir);
+            let listar = |caminho: &Path| {
+                if mudancas {
+                    impressoes::mudancas_cmd(caminho, somente, json)
+                } else {
+                    listar_cmd(
+                        caminho,
+                        recentes,
+                        mostrar_excluidos,
+                        json,
+                        strict,
+                        ordenar.map(|o| (o, reverso)),
+                    )
+                }
+            };
+            if usar_workspace(&caminho, &toolchain::localizar_raiz(&caminho), todos) {
+                if json {
+                    bail!("--json vale para um projeto: use o caminho do membro");
+                }
+                let (_, membros) = workspace::membros_de(&caminho)?;
+                workspace::executar_em_membros(
+                    &membros,
+                    &workspace::OpcoesMembros::em_ordem("-", continuar),
+                    |m| listar(&m.raiz).map(|()| None),
+                )
+            } else {
+                listar(&caminho)
+            }
         }
-        Some(CommandEnum::Clean { caminho }) => clean_cmd(&caminho),
-        Some(CommandEnum::Info { caminho }) => info_cmd(&caminho),
-        Some(CommandEnum::Doctor { caminho }) => doctor_cmd(&caminho),
-        Some(CommandEnum::Listar { caminho, recentes }) => listar_cmd(&caminho, recentes),
         Some(CommandEnum::Dep {
             acao,
             nome,
             versao,
             caminho: caminho_local,
             caminho_projeto,
+            json,
+            profundidade,
         }) => dep_cmd(
             &acao,
             nome.as_deref(),
             versao.as_deref(),
             caminho_local.as_deref(),
             &caminho_projeto,
+            json,
+            profundidade,
+        ),
+        Some(CommandEnum::Stats {
+            caminho,
+            json,
+            por_arquivo,
+        }) => estatisticas::stats_cmd(&caminho, json, por_arquivo),
+        Some(CommandEnum::Templates { acao }) => match acao {
+            AcaoTemplates::Instalar { origem, forcar } => templates::instalar_cmd(&origem, forcar),
+            AcaoTemplates::Remover { nome } => templates::remover_cmd(&nome),
+            AcaoTemplates::Verificar { nome } => templates::verificar_cmd(nome.as_deref()),
+            AcaoTemplates::Listar { detalhado } => novo::listar_templates_cmd(detalhado),
+        },
+        Some(CommandEnum::Toolchain {
+            acao:
+                AcaoToolchain::Which {
+                    ferramenta,
+                    origem,
+                    todos,
+                    project,
+                },
+        }) => toolchain::which_cmd(&project, ferramenta, origem, todos),
+        Some(CommandEnum::Analisar {
+            acao: AcaoAnalisar::Tamanho { json, project },
+        }) => analise::tamanho_cmd(&project, json),
+        Some(CommandEnum::Config {
+            acao:
+                AcaoConfig::Origem {
+                    chave,
+                    todas,
+                    target,
+                    saida,
+                    json,
+                    project,
+                },
+        }) => configuracao::origem_cmd(
+            &project,
+            chave.as_deref(),
+            todas,
+            &configuracao::FlagsConfig {
+                target: target.as_deref(),
+                saida: saida.as_deref(),
+            },
+            json,
+        ),
+        Some(CommandEnum::Test {
+            nome,
+            indice,
+            lista,
+            project,
+            arquivo_resultado,
+        }) => {
+            resultado::definir_resultado(arquivo_resultado.as_deref(), comandos::TEST.nome);
+            testes::test_cmd(
+                &project,
+                &testes::OpcoesTeste {
+                    nome: nome.as_deref(),
+                    indice,
+                    lista,
+                },
+            )
+        }
+        Some(CommandEnum::Rename {
+            novo_nome,
+            readme,
+            project,
+        }) => novo::renomear_cmd(&project, &novo_nome, readme),
+        Some(CommandEnum::Changelog {
+            caminho,
+            desde,
+            versao,
+            dry_run,
+        }) => changelog::changelog_cmd(
+            &caminho,
+            &changelog::OpcoesChangelog {
+                desde: desde.as_deref(),
+                versao: versao.as_deref(),
+                dry_run,
+            },
         ),
+        Some(CommandEnum::Versao { all }) => {
+            let cwd = std::env::current_dir()?;
+            if all {
+                imprimir_versoes_completas(&cwd);
+            } else {
+                imprimir_versoes(&cwd);
+            }
+            Ok(())
+        }
+        Some(CommandEnum::Manutencao { caminho, aplicar }) => {
+            manutencao::manutencao_cmd(&caminho, aplicar)
+        }
+        Some(CommandEnum::Explain { codigo }) => explicar::explicar_cmd(codigo.as_deref()),
+        Some(CommandEnum::Comandos { json }) => comandos::comandos_cmd(json),
         None => {
             let mut cmd = Cli::command();
             cmd.print_long_help().ok();
@@ -270,11 +1450,269 @@ fn eh_new_list_request(
             .unwrap_or(false)
 }
 
+/// Estado global das [`FlagsCompilacao`], antes de escolher o perfil.
+fn aplicar_flags_compilacao(flags: &FlagsCompilacao, comando: &'static str) {
+    resultado::definir_resultado(flags.arquivo_resultado.as_deref(), comando);
+    exclusao::definir_exclusoes_cli(flags.excluir.clone());
+    dependencias::definir_com_deps(flags.com_deps);
+    construir::definir_target_livre(flags.target_livre);
+    construir::definir_checksums(flags.checksums);
+    construir::definir_dry_run(flags.dry_run);
+    construir::definir_formato_mensagens(flags.message_format);
+    construir::definir_jobs(flags.jobs);
+    if flags.message_format == construir::FormatoMensagens::Json {
+        // No stdout, so os objetos com os diagnosticos
+        saida::definir_nivel(saida::Nivel::Silencioso);
+    }
+}
+
+/// `build` com o perfil dev (tambem `producao --perfil dev`).
+fn build_cli(caminho_final: &Path, args: ArgsBuild, flags: &FlagsCompilacao) -> Result<()> {
+    let ArgsBuild {
+        target,
+        nome_saida,
+        json,
+        fail_fast,
+        timings,
+        watch,
+        oneline,
+        explain_flags,
+        emit_deps,
+        todos,
+        ..
+    } = args;
+    let FlagsCompilacao {
+        ref saida,
+        skip_checks,
+        strict_versao,
+        strict,
+        dry_run,
+        jobs,
+        continuar,
+        ..
+    } = *flags;
+    let target = if target.is_empty() {
+        vec!["bytecode".to_string()]
+    } else {
+        target
+    };
+    construir::definir_emit_deps(emit_deps);
+    if explain_flags {
+        for (i, t) in target.iter().enumerate() {
+            if i > 0 {
+                println!();
+            }
+            construir::explicar_flags(caminho_final, t, saida.as_deref())?;
+        }
+        return Ok(());
+    }
+    let raiz = toolchain::localizar_raiz(caminho_final);
+    let em_workspace = usar_workspace(caminho_final, &raiz, todos);
+    if em_workspace && (watch || oneline || saida.is_some()) {
+        bail!("--watch, --oneline e --saida valem para um projeto: use --project <membro>");
+    }
+    if !caminho_final.is_file() && !em_workspace {
+        nomes::verificar_nomes(&raiz, &toolchain::listar_prs(&raiz), strict)?;
+    }
+    // `capturar`: saida do compilador so na falha (--oneline e o --json do workspace)
+    let compilar = |caminho_final: &Path, capturar: bool| {
+        let raiz = toolchain::localizar_raiz(caminho_final);
+        let _trava = (!dry_run).then(|| trava::adquirir(&raiz)).transpose()?;
+        if !skip_checks && !dry_run {
+            construir::executar_pre_checks(&raiz)?;
+        }
+        construir::verificar_toolchain_projeto(&raiz)?;
+        // Sem compilador, o erro com a trilha de busca vem da compilacao
+        if let Ok(compilador) = toolchain::localizar_compilador(&raiz) {
+            construir::verificar_versao_compilador(&compilador.caminho, strict_versao)?;
+        }
+        let resultado = if capturar {
+            // Todos os targets somados num unico resumo; para no primeiro erro
+            target
+                .iter()
+                .try_fold(construir::ResumoBuild::default(), |total, t| {
+                    let r = construir::compilar_cmd(
+                        caminho_final,
+                        t,
+                        saida.as_deref(),
+                        nome_saida.as_deref(),
+                        false,
+                        true,
+                    )?;
+                    Ok(construir::ResumoBuild {
+                        arquivos: r.arquivos,
+                        artefatos: total.artefatos + r.artefatos,
+                    })
+                })
+        } else if target.len() == 1 && !json {
+            construir::compilar_cmd(
+                caminho_final,
+                &target[0],
+                saida.as_deref(),
+                nome_saida.as_deref(),
+                timings,
+                false,
+            )
+        } else {
+            construir::compilar_multi_target(
+                caminho_final,
+                &target,
+                saida.as_deref(),
+                nome_saida.as_deref(),
+                json,
+                fail_fast,
+                timings,
+            )
+            .map(|_| construir::ResumoBuild::default())
+        };
+        if resultado.is_ok() && !dry_run {
+            manutencao::manutencao_automatica(&raiz);
+        }
+        resultado
+    };
+    if em_workspace {
+        if json {
+            // No stdout, so o relatorio
+            saida::definir_nivel(saida::Nivel::Silencioso);
+        }
+        let (_, membros) = workspace::membros_de(caminho_final)?;
+        let alvo = target.join(",");
+        let opcoes = workspace::OpcoesMembros {
+            json,
+            paralelos: jobs.map_or(1, usize::from),
+            ..workspace::OpcoesMembros::em_ordem(&alvo, continuar)
+        };
+        workspace::executar_em_membros(&membros, &opcoes, |m| {
+            nomes::verificar_nomes(&m.raiz, &toolchain::listar_prs(&m.raiz), strict)?;
+            compilar(&m.raiz, json).map(|_| None)
+        })
+    } else if watch {
+        construir::build_watch(&raiz, || compilar(caminho_final, false).map(|_| ()))
+    } else if oneline {
+        saida::definir_nivel(saida::Nivel::Silencioso);
+        let inicio = std::time::Instant::now();
+        match compilar(caminho_final, true) {
+            Ok(resumo) => {
+                println!("{}", resumo.linha_ok(inicio.elapsed()));
+                Ok(())
+            }
+            Err(erro) => {
+                // A linha de resumo substitui a mensagem de erro habitual
+                println!("{}", construir::linha_falha(&raiz, &erro));
+                resultado::concluir(Some(&erro));
+                std::process::exit(erros::codigo_saida(&erro));
+            }
+        }
+    } else {
+        compilar(caminho_final, false).map(|_| ())
+    }
+}
+
+/// `producao` (tambem `build --perfil producao`): `target` ausente vira llvm-ir.
+fn producao_cli(
+    caminho: &Path,
+    target: Option<&str>,
+    timings: bool,
+    todos: bool,
+    flags: &FlagsCompilacao,
+) -> Result<()> {
+    let FlagsCompilacao {
+        ref saida,
+        skip_checks,
+        strict_versao,
+        strict,
+        dry_run,
+        jobs,
+        continuar,
+        ..
+    } = *flags;
+    let target = target.unwrap_or("llvm-ir");
+    // Mesma sequencia do `build`: so o perfil (target e pasta) muda
+    let producao = |caminho: &Path, cache: Option<&workspace::CacheDependencias>| {
+        let raiz = toolchain::localizar_raiz(caminho);
+        if !caminho.is_file() {
+            nomes::verificar_nomes(&raiz, &toolchain::listar_prs(&raiz), strict)?;
+        }
+        let _trava = (!dry_run).then(|| trava::adquirir(&raiz)).transpose()?;
+        if !skip_checks && !dry_run {
+            construir::executar_pre_checks(&raiz)?;
+        }
+        construir::verificar_toolchain_projeto(&raiz)?;
+        // Sem compilador, o erro com a trilha de busca vem da compilacao
+        if let Ok(compilador) = toolchain::localizar_compilador(&raiz) {
+            construir::verificar_versao_compilador(&compilador.caminho, strict_versao)?;
+        }
+        let situacao = construir::producao_cmd(caminho, target, saida.as_deref(), timings, cache)?;
+        if !dry_run {
+            manutencao::manutencao_automatica(&raiz);
+        }
+        Ok(situacao)
+    };
+    if !usar_workspace(caminho, &toolchain::localizar_raiz(caminho), todos) {
+        return producao(caminho, None).map(|_| ());
+    }
+    if saida.is_some() {
+        bail!("--saida vale para um projeto: use o caminho do membro");
+    }
+    let (raiz_ws, membros) = workspace::membros_de(caminho)?;
+    let paralelos = jobs
+        .map(usize::from)
+        .unwrap_or_else(|| std::thread::available_parallelism().map_or(1, |n| n.get()));
+    let opcoes = workspace::OpcoesMembros {
+        paralelos,
+        ..workspace::OpcoesMembros::em_ordem(target, continuar)
+    };
+    // Com o cache, cada dependencia compartilhada e compilada uma vez; sem ele, cada membro
+    // compila as fontes das proprias dependencias. Nos dois casos a ordem entre eles nao importa.
+    let cache = workspace::cache_dependencias(&raiz_ws)?
+        .then(|| workspace::CacheDependencias::abrir(&raiz_ws));
+    workspace::executar_em_membros(&membros, &opcoes, |m| producao(&m.raiz, cache.as_ref()))
+}
+
 fn resolver_project_path(project: Option<&Path>, caminho_legacy: Option<&Path>) -> PathBuf {
-    project
+    let caminho = project
         .map(Path::to_path_buf)
         .or_else(|| caminho_legacy.map(Path::to_path_buf))
-        .unwrap_or_else(|| PathBuf::from("."))
+        .unwrap_or_else(|| PathBuf::from("."));
+    construir::sair_da_pasta_saida(&caminho)
+}
+
+/// Caminho do `run`; num workspace, `--projeto <nome>` tambem aceita o nome de um membro.
+///
+/// Na raiz de um workspace sem projeto proprio nao ha o que executar sem escolher um membro.
+fn resolver_caminho_run(project: Option<&Path>, caminho_legacy: Option<&Path>) -> Result<PathBuf> {
+    if let Some(nome) = project.filter(|p| !p.exists()).and_then(Path::to_str) {
+        let cwd = std::env::current_dir().context("Falha ao obter diretorio atual")?;
+        if workspace::localizar_workspace(&cwd).is_some() {
+            let (_, membros) = workspace::membros_de(&cwd)?;
+            if let Some(m) = membros.iter().find(|m| m.nome == nome) {
+                return Ok(m.raiz.clone());
+            }
+        }
+    }
+    let caminho = resolver_project_path(project, caminho_legacy);
+    // `run -` le o programa do stdin: nao ha raiz a conferir
+    if caminho != Path::new(executar::MARCADOR_STDIN) && !caminho.is_file() {
+        let raiz = toolchain::localizar_raiz(&caminho);
+        if workspace::eh_raiz_workspace(&raiz) {
+            let nomes: Vec<String> = workspace::membros(&raiz)?
+                .into_iter()
+                .map(|m| m.nome)
+                .collect();
+            return Err(erros::ErroCli::ProjetoInvalido(format!(
+                "{} e a raiz de um workspace: escolha o membro com --projeto <nome> ({})",
+                raiz.display(),
+                nomes.join(", ")
+            ))
+            .into());
+        }
+    }
+    Ok(caminho)
+}
+
+/// `--todos`, ou um caminho (nao arquivo) cuja raiz e a de um workspace sem projeto proprio.
+fn usar_workspace(caminho: &Path, raiz: &Path, todos: bool) -> bool {
+    todos || (!caminho.is_file() && workspace::eh_raiz_workspace(raiz))
 }
 
 fn resolver_new_params(
@@ -338,6 +1776,43 @@ fn imprimir_versoes(cwd: &Path) {
     imprimir_versao_ferramenta(&diag.interpretador);
 }
 
+/// `versao --all`: CLI, compilador, interpretador e stdlib, com origem de cada um.
+fn imprimir_versoes_completas(cwd: &Path) {
+    println!("pordosol CLI v{}", env!("CARGO_PKG_VERSION"));
+
+    let raiz = toolchain::localizar_raiz(cwd);
+    let diag = toolchain::diagnosticar_toolchain(&raiz);
+    let itens = [
+        (
+            &diag.compilador,
+            toolchain::detectar_versao_binario(&diag.compilador.caminho),
+        ),
+        (
+            &diag.interpretador,
+            toolchain::detectar_versao_binario(&diag.interpretador.caminho),
+        ),
+        (&diag.stdlib, toolchain::versao_stdlib(&diag.stdlib.caminho)),
+    ];
+    for (item, versao) in itens {
+        if !item.encontrado {
+            println!(
+                "{}: nao encontrado (origem {}, caminho {})",
+                item.nome,
+                item.origem,
+                item.caminho.display()
+            );
+            continue;
+        }
+        println!(
+            "{}: {} (encontrado em {}, origem {})",
+            item.nome,
+            versao.as_deref().unwrap_or("versao nao detectada"),
+            item.caminho.display(),
+            item.origem
+        );
+    }
+}
+
 fn imprimir_versao_ferramenta(item: &toolchain::DiagnosticoFerramenta) {
     if !item.encontrado {
         println!(
@@ -360,11 +1835,15 @@ fn imprimir_versao_ferramenta(item: &toolchain::DiagnosticoFerramenta) {
     }
 }
 
-fn info_cmd(caminho: &Path) -> Result<()> {
-    let raiz = toolchain::localizar_raiz(caminho);
+fn info_cmd(caminho: &Path, deps: bool) -> Result<()> {
+    let (raiz, marcador) = toolchain::localizar_raiz_com_marcador(caminho);
 
     println!("=== Informacoes do Projeto ===");
-    println!("Raiz do projeto: {}", raiz.display());
+    println!(
+        "Raiz do projeto: {} (marcador: {})",
+        raiz.display(),
+        marcador
+    );
 
     if let Some(config) = toolchain::carregar_configuracao_projeto(&raiz) {
         if let Some(nome) = config.get("nome").and_then(|v| v.as_str()) {
@@ -390,143 +1869,257 @@ fn info_cmd(caminho: &Path) -> Result<()> {
         println!("  - {}", rel_path.display());
     }
 
+    if deps {
+        imprimir_resolucao_dependencias(&raiz);
+    }
+
     let diag = toolchain::diagnosticar_toolchain(&raiz);
     println!("\n=== Ferramentas ===");
-    println!(
-        "Compilador: {} ({}) [{}]",
-        diag.compilador.caminho.display(),
-        if diag.compilador.encontrado {
-            "ok"
-        } else {
-            "nao encontrado"
-        },
-        diag.compilador.origem
-    );
-    println!(
-        "Interpretador: {} ({}) [{}]",
-        diag.interpretador.caminho.display(),
-        if diag.interpretador.encontrado {
-            "ok"
-        } else {
-            "nao encontrado"
-        },
-        diag.interpretador.origem
-    );
-    println!(
-        "Biblioteca padrao: {} ({}) [{}]",
-        diag.stdlib.caminho.display(),
-        if diag.stdlib.encontrado {
-            "ok"
-        } else {
-            "nao encontrada"
-        },
-        diag.stdlib.origem
-    );
+    for (rotulo, item) in [
+        ("Compilador", &diag.compilador),
+        ("Interpretador", &diag.interpretador),
+        ("Biblioteca padrao", &diag.stdlib),
+    ] {
+        println!(
+            "{}: {} {} [{}]",
+            rotulo,
+            saida::marca(item.encontrado),
+            item.caminho.display(),
+            item.origem
+        );
+        if !item.encontrado {
+            println!("  Locais tentados:");
+            for linha in toolchain::trilha_busca(&item.tentativas) {
+                println!("    {}", linha);
+            }
+        }
+    }
 
-    let build_dir = raiz.join("build");
+    let build_dir = construir::pasta_saida_projeto(&raiz);
+    if let Some(m) = manifesto::ler(&build_dir) {
+        println!("\n{}", manifesto::resumo(&m, std::time::SystemTime::now()));
+        println!("  Compilador: {}", m.compilador.caminho);
+        if let Some(versao) = &m.compilador.versao {
+            println!("  Versao do compilador: {}", versao);
+        }
+        println!("  Fontes: {}", m.fontes.len());
+        for artefato in &m.artefatos {
+            println!(
+                "  - {} ({} bytes)",
+                artefato.caminho, artefato.impressao.bytes
+            );
+        }
+    }
     if build_dir.exists() {
         let entries: Vec<_> = fs::read_dir(&build_dir)
             .unwrap_or_else(|_| fs::read_dir(".").unwrap())
             .filter_map(|e| e.ok())
             .collect();
-        println!("\nPasta build/: {} arquivo(s)", entries.len());
+        println!(
+            "\nPasta de saida {}: {} arquivo(s)",
+            build_dir.display(),
+            entries.len()
+        );
     } else {
-        println!("\nPasta build/: nao existe");
+        println!("\nPasta de saida {}: nao existe", build_dir.display());
     }
 
     Ok(())
 }
 
-fn listar_cmd(caminho: &Path, recentes: bool) -> Result<()> {
+/// Item de `listar --json`.
+#[derive(serde::Serialize)]
+struct FonteListada {
+    caminho: String,
+    bytes: Option<u64>,
+    /// Segundos desde a epoch Unix
+    modificado_epoch: Option<u64>,
+    /// Arquivo priorizado como principal (`entrada` do pordosol.proj ou src/programa.pr)
+    preferido: bool,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    excluido: bool,
+}
+
+/// Criterio de `listar --ordenar`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+enum OrdemListagem {
+    /// Caminho relativo, em ordem alfabetica
+    Nome,
+    /// Tamanho em bytes
+    Tamanho,
+    /// Data de modificacao
+    Modificado,
+}
+
+/// Ordena por `ordem` (empates pelo caminho); `reverso` inverte o resultado.
+///
+/// Sem metadados, o arquivo conta como o menor e o mais antigo.
+fn ordenar_fontes<T>(fontes: &mut [(FonteListada, T)], ordem: OrdemListagem, reverso: bool) {
+    fontes.sort_by(|(a, _), (b, _)| {
+        let criterio = match ordem {
+            OrdemListagem::Nome => std::cmp::Ordering::Equal,
+            OrdemListagem::Tamanho => a.bytes.cmp(&b.bytes),
+            OrdemListagem::Modificado => a.modificado_epoch.cmp(&b.modificado_epoch),
+        };
+        criterio.then_with(|| a.caminho.cmp(&b.caminho))
+    });
+    if reverso {
+        fontes.reverse();
+    }
+}
+
+fn listar_cmd(
+    caminho: &Path,
+    recentes: bool,
+    mostrar_excluidos: bool,
+    json: bool,
+    strict: bool,
+    ordem: Option<(OrdemListagem, bool)>,
+) -> Result<()> {
     let raiz = toolchain::localizar_raiz(caminho);
-    let arquivos = toolchain::listar_prs(&raiz);
+    let (incluidos, excluidos) = toolchain::listar_prs_com_excluidos(&raiz);
+    nomes::verificar_nomes(&raiz, &incluidos, strict)?;
+    let mut arquivos: Vec<(PathBuf, bool)> = incluidos.into_iter().map(|a| (a, false)).collect();
+    if mostrar_excluidos {
+        arquivos.extend(excluidos.into_iter().map(|a| (a, true)));
+    }
+    let preferido = toolchain::arquivo_preferido(&raiz);
+
+    let agora = std::time::SystemTime::now();
+    let mut fontes = Vec::new();
+    for (arq, excluido) in &arquivos {
+        let meta = arq.metadata().ok();
+        let modificado = meta.as_ref().and_then(|m| m.modified().ok());
+        let idade = modificado.map(|m| agora.duration_since(m).unwrap_or_default().as_secs());
+        // Sem metadados nao da para saber a idade: o arquivo continua na lista
+        if recentes && idade.is_some_and(|i| i > 86400) {
+            continue;
+        }
+        fontes.push((
+            FonteListada {
+                caminho: arq
+                    .strip_prefix(&raiz)
+                    .unwrap_or(arq)
+                    .to_string_lossy()
+                    .replace('\\', "/"),
+                bytes: meta.as_ref().map(|m| m.len()),
+                modificado_epoch: modificado
+                    .and_then(|m| m.duration_since(std::time::UNIX_EPOCH).ok())
+                    .map(|d| d.as_secs()),
+                preferido: arq == &preferido,
+                excluido: *excluido,
+            },
+            idade,
+        ));
+    }
+    if let Some((ordem, reverso)) = ordem {
+        ordenar_fontes(&mut fontes, ordem, reverso);
+    }
 
-    if arquivos.is_empty() {
+    if json {
+        let itens: Vec<&FonteListada> = fontes.iter().map(|(f, _)| f).collect();
+        println!("{}", serde_json::to_string_pretty(&itens)?);
+        return Ok(());
+    }
+
+    if fontes.is_empty() {
         println!("Nenhum arquivo .pr encontrado em {}/src", raiz.display());
         return Ok(());
     }
 
     println!("Arquivos .pr no projeto:");
 
-    for arq in &arquivos {
-        let rel_path = arq.strip_prefix(&raiz).unwrap_or(arq);
-
-        if recentes {
-            if let Ok(metadata) = arq.metadata() {
-                if let Ok(modified) = metadata.modified() {
-                    let duration = std::time::SystemTime::now()
-                        .duration_since(modified)
-                        .unwrap_or_default();
-
-                    if duration.as_secs() > 86400 {
-                        continue;
-                    }
+    for (fonte, idade) in &fontes {
+        let rel_path = Path::new(&fonte.caminho);
+        let linha = match (fonte.bytes, idade) {
+            (Some(size), Some(idade)) if recentes => format!(
+                "  {} ({} bytes, modificado ha {}s)",
+                rel_path.display(),
+                size,
+                idade
+            ),
+            (Some(size), _) if !recentes => format!("  {} ({} bytes)", rel_path.display(), size),
+            _ => format!("  {}", rel_path.display()),
+        };
 
-                    let size = metadata.len();
-                    println!(
-                        "  {} ({} bytes, modificado ha {}s)",
-                        rel_path.display(),
-                        size,
-                        duration.as_secs()
-                    );
-                } else {
-                    println!("  {}", rel_path.display());
-                }
-            } else {
-                println!("  {}", rel_path.display());
-            }
-        } else if let Ok(metadata) = arq.metadata() {
-            let size = metadata.len();
-            println!("  {} ({} bytes)", rel_path.display(), size);
+        if fonte.excluido {
+            println!(
+                "{}",
+                saida::pintar(saida::Cor::Esmaecido, &format!("{} (excluido)", linha))
+            );
         } else {
-            println!("  {}", rel_path.display());
+            println!("{}", linha);
         }
     }
 
     Ok(())
 }
 
+/// Acoes de `dep` (com os apelidos), para sugestoes.
+const ACOES_DEP: &[&str] = &[
+    "add",
+    "remove",
+    "rm",
+    "list",
+    "ls",
+    "listar",
+    "resolve",
+    "resolver",
+    "tree",
+    "arvore",
+    "install",
+    "restaurar",
+];
+
 fn dep_cmd(
     acao: &str,
     nome: Option<&str>,
     versao: Option<&str>,
     caminho_local: Option<&Path>,
     caminho_projeto: &Path,
+    json: bool,
+    profundidade: Option<usize>,
 ) -> Result<()> {
     let raiz = toolchain::localizar_raiz(caminho_projeto);
     let proj_path = raiz.join("pordosol.proj");
     if !proj_path.exists() {
-        bail!(
+        return Err(erros::ErroCli::ProjetoInvalido(format!(
             "Arquivo de projeto nao encontrado em {}",
             proj_path.display()
-        );
+        ))
+        .into());
     }
-    let mut json: serde_json::Value = serde_json::from_str(&fs::read_to_string(&proj_path)?)?;
-    let deps = json
-        .get_mut("dependencias")
-        .and_then(|d| d.as_object_mut())
-        .ok_or_else(|| anyhow!("Campo 'dependencias' ausente ou invalido"))?;
+    let mut doc = projeto::DocumentoProjeto::ler(&proj_path)?;
+    let mut deps = doc.objeto("dependencias")?;
 
     match acao.to_ascii_lowercase().as_str() {
         "add" => {
             let nome = nome.ok_or_else(|| anyhow!("Informe o nome da dependencia"))?;
-            if deps.contains_key(nome) {
-                println!("Dependencia '{}' ja existe. Atualizando...", nome);
-            }
             let valor = if let Some(c) = caminho_local {
+                dependencias::validar_caminho_dependencia(&raiz, c)?;
                 serde_json::json!({"path": c.to_string_lossy()})
             } else {
                 let ver = versao.unwrap_or("*");
+                dependencias::validar_requisito_versao(ver)?;
                 serde_json::json!(ver)
             };
-            deps.insert(nome.to_string(), valor);
-            fs::write(&proj_path, serde_json::to_string_pretty(&json)?)?;
+            let valor = RawValue::from_string(serde_json::to_string(&valor)?)?;
+            if let Some((_, v)) = deps.iter_mut().find(|(k, _)| k == nome) {
+                println!("Dependencia '{}' ja existe. Atualizando...", nome);
+                *v = valor;
+            } else {
+                deps.push((nome.to_string(), valor));
+            }
+            doc.definir_objeto("dependencias", &deps)?;
+            doc.salvar()?;
             println!("Dependencia '{}' adicionada/atualizada.", nome);
         }
         "remove" | "rm" => {
             let nome = nome.ok_or_else(|| anyhow!("Informe o nome da dependencia"))?;
-            if deps.remove(nome).is_some() {
-                fs::write(&proj_path, serde_json::to_string_pretty(&json)?)?;
+            if let Some(pos) = deps.iter().position(|(k, _)| k == nome) {
+                deps.remove(pos);
+                doc.definir_objeto("dependencias", &deps)?;
+                doc.salvar()?;
                 println!("Dependencia '{}' removida.", nome);
             } else {
                 println!("Dependencia '{}' nao encontrada.", nome);
@@ -537,8 +2130,9 @@ fn dep_cmd(
                 println!("Nenhuma dependencia declarada.");
             } else {
                 println!("Dependencias:");
-                for (k, v) in deps.iter() {
-                    match v {
+                for (k, raw) in deps.iter() {
+                    let v: serde_json::Value = serde_json::from_str(raw.get())?;
+                    match &v {
                         serde_json::Value::String(s) => println!("  - {} = {}", k, s),
                         serde_json::Value::Object(o) => {
                             if let Some(p) = o.get("path") {
@@ -552,113 +2146,229 @@ fn dep_cmd(
                 }
             }
         }
+        "resolve" | "resolver" => dependencias::resolve_cmd(&raiz, json)?,
+        "tree" | "arvore" => dependencias::tree_cmd(&raiz, profundidade, json)?,
+        "install" | "restaurar" => dependencias::instalar_cmd(&raiz)?,
         outra => {
-            bail!("Acao desconhecida: {} (use add|remove|list)", outra);
+            bail!(
+                "Ação desconhecida: '{}'.{} (use add|remove|list|resolve|tree|install)",
+                outra,
+                explicar::voce_quis_dizer(outra, ACOES_DEP)
+            );
         }
     }
     Ok(())
 }
 
-fn doctor_cmd(caminho: &Path) -> Result<()> {
-    let raiz = toolchain::localizar_raiz(caminho);
-    let diag = toolchain::diagnosticar_toolchain(&raiz);
-
-    println!("=== Diagnostico do ambiente Por do Sol ===");
-    println!("Caminho de referencia: {}", raiz.display());
-    println!();
-
-    let mut pendencias: Vec<&'static str> = Vec::new();
-
-    imprimir_item_doctor(
-        &diag.compilador,
-        true,
-        toolchain::detectar_versao_binario(&diag.compilador.caminho),
-        "Defina PORDOSOL_COMPILADOR_PATH ou coloque o compilador em <instalacao>/tools.",
-        &mut pendencias,
-    );
-    imprimir_item_doctor(
-        &diag.interpretador,
-        true,
-        toolchain::detectar_versao_binario(&diag.interpretador.caminho),
-        "Defina PORDOSOL_INTERPRETADOR_PATH ou coloque o interpretador em <instalacao>/tools.",
-        &mut pendencias,
-    );
-    imprimir_item_doctor(
-        &diag.stdlib,
-        false,
-        None,
-        "Defina PORDOSOL_STDLIB_PATH ou instale a stdlib em <instalacao>/tools/stdlib.",
-        &mut pendencias,
-    );
-
-    println!();
-    if diag.pronto() {
-        println!("Resultado: ambiente pronto para `pordosol build` e `pordosol run`.");
-    } else {
-        println!("Resultado: ambiente com pendencias.");
-        println!("Acoes corretivas sugeridas:");
-        for (idx, item) in pendencias.iter().enumerate() {
-            println!("{}. {}", idx + 1, item);
+/// `info --deps`: para cada item de `dependencias`, se vem de versao ou de `path` e, para
+/// `path`, se a pasta existe e tem pordosol.proj (senao marcada com ✗).
+fn imprimir_resolucao_dependencias(raiz: &Path) {
+    println!("\n=== Dependencias ===");
+    let declaradas = toolchain::carregar_configuracao_projeto(raiz)
+        .and_then(|c| c.get("dependencias").and_then(|d| d.as_object()).cloned())
+        .unwrap_or_default();
+    if declaradas.is_empty() {
+        println!("Nenhuma dependencia declarada.");
+        return;
+    }
+    for (nome, valor) in &declaradas {
+        match valor {
+            serde_json::Value::String(versao) => println!("  - {}: versao {}", nome, versao),
+            serde_json::Value::Object(o) if o.contains_key("path") => {
+                let relativo = o.get("path").and_then(|p| p.as_str()).unwrap_or_default();
+                let pasta = raiz.join(relativo);
+                let problema = if !pasta.is_dir() {
+                    Some("pasta nao existe")
+                } else if !pasta.join("pordosol.proj").is_file() {
+                    Some("sem pordosol.proj")
+                } else {
+                    None
+                };
+                println!(
+                    "  {} {}: path {}{}",
+                    saida::marca(problema.is_none()),
+                    nome,
+                    relativo,
+                    problema.map(|p| format!(" ({})", p)).unwrap_or_default()
+                );
+            }
+            serde_json::Value::Object(o) if o.contains_key("git") => println!(
+                "  - {}: git {}",
+                nome,
+                o.get("git").and_then(|g| g.as_str()).unwrap_or_default()
+            ),
+            outro => println!(
+                "  {} {}: formato nao reconhecido ({})",
+                saida::marca(false),
+                nome,
+                outro
+            ),
         }
-        println!("Dica: configure `PORDOSOL_HOME` para centralizar tools e templates.");
     }
+}
 
-    Ok(())
+/// Item de primeiro nivel da pasta de saida, com o tamanho somado dos arquivos.
+struct ItemLimpeza {
+    caminho: PathBuf,
+    bytes: u64,
 }
 
-fn imprimir_item_doctor(
-    item: &toolchain::DiagnosticoFerramenta,
-    mostrar_versao: bool,
-    versao: Option<String>,
-    acao: &'static str,
-    pendencias: &mut Vec<&'static str>,
-) {
-    let status = if item.encontrado { "OK" } else { "FALHA" };
-    println!("{}: {}", item.nome, status);
-    println!("  caminho: {}", item.caminho.display());
-    println!("  origem: {}", item.origem);
-    if mostrar_versao {
-        if let Some(v) = versao {
-            println!("  versao: {}", v);
-        } else if item.encontrado {
-            println!("  versao: nao detectada");
+fn coletar_itens_limpeza(build_dir: &Path) -> Result<Vec<ItemLimpeza>> {
+    let mut itens = Vec::new();
+    for entry in fs::read_dir(build_dir)? {
+        let path = entry?.path();
+        // A trava pertence ao proprio clean e sai quando ele termina
+        if path.file_name() == Some(std::ffi::OsStr::new(trava::ARQUIVO_TRAVA)) {
+            continue;
         }
+        let bytes = walkdir::WalkDir::new(&path)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter_map(|e| e.metadata().ok())
+            .filter(|m| m.is_file())
+            .map(|m| m.len())
+            .sum();
+        itens.push(ItemLimpeza {
+            caminho: path,
+            bytes,
+        });
     }
-    if !item.encontrado {
-        pendencias.push(acao);
-    }
+    itens.sort_by(|a, b| a.caminho.cmp(&b.caminho));
+    Ok(itens)
 }
 
-fn clean_cmd(caminho: &Path) -> Result<()> {
+fn clean_cmd(caminho: &Path, dry_run: bool, json: bool) -> Result<()> {
     let raiz = toolchain::localizar_raiz(caminho);
-    let build_dir = raiz.join("build");
+    let build_dir = construir::pasta_saida_projeto(&raiz);
+    construir::validar_pasta_saida(&raiz, &build_dir)?;
 
-    if !build_dir.exists() {
-        println!("Pasta build/ nao existe em {}", raiz.display());
+    let _trava = if !dry_run && build_dir.exists() {
+        trava::adquirir(&raiz)?
+    } else {
+        None
+    };
+    let itens = if build_dir.exists() {
+        coletar_itens_limpeza(&build_dir)?
+    } else {
+        Vec::new()
+    };
+    let total: u64 = itens.iter().map(|i| i.bytes).sum();
+    resultado::acrescentar("pastas_saida", &build_dir);
+
+    if json {
+        let relatorio = serde_json::json!({
+            "pasta_saida": build_dir.display().to_string(),
+            "dry_run": true,
+            "itens": itens
+                .iter()
+                .map(|i| serde_json::json!({
+                    "caminho": i.caminho.display().to_string(),
+                    "bytes": i.bytes,
+                }))
+                .collect::<Vec<_>>(),
+            "total_bytes": total,
+        });
+        println!("{}", serde_json::to_string_pretty(&relatorio)?);
         return Ok(());
     }
 
-    let entries = fs::read_dir(&build_dir)?;
-    let mut count = 0;
+    if !build_dir.exists() {
+        progresso!("Pasta de saida {} nao existe", build_dir.display());
+        return Ok(());
+    }
 
-    for entry in entries {
-        let entry = entry?;
-        let path = entry.path();
+    if dry_run {
+        for item in &itens {
+            println!("  {} ({} bytes)", item.caminho.display(), item.bytes);
+        }
+        println!(
+            "Dry-run: {} item(s) ({} bytes) seriam removidos de {}",
+            itens.len(),
+            total,
+            build_dir.display()
+        );
+        return Ok(());
+    }
 
-        if path.is_file() {
-            fs::remove_file(&path).context(format!("Falha ao remover {}", path.display()))?;
-            count += 1;
-        } else if path.is_dir() {
-            fs::remove_dir_all(&path)
+    for item in &itens {
+        let path = &item.caminho;
+        if path.is_dir() {
+            fs::remove_dir_all(path)
                 .context(format!("Falha ao remover diretorio {}", path.display()))?;
-            count += 1;
+        } else {
+            fs::remove_file(path).context(format!("Falha ao remover {}", path.display()))?;
         }
+        resultado::acrescentar(
+            "removidos",
+            serde_json::json!({ "caminho": path, "bytes": item.bytes }),
+        );
     }
 
-    println!(
+    progresso!(
         "Limpeza concluida: {} item(s) removido(s) de {}",
-        count,
+        itens.len(),
         build_dir.display()
     );
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subcomandos_e_aliases_estao_no_registro() {
+        let cmd = Cli::command();
+        for sub in cmd.get_subcommands() {
+            let nome = sub.get_name();
+            if nome == "help" {
+                continue;
+            }
+            let info = comandos::REGISTRO
+                .iter()
+                .find(|c| c.nome == nome)
+                .unwrap_or_else(|| panic!("comando '{}' ausente do registro", nome));
+
+            let mut aliases: Vec<&str> = sub.get_all_aliases().collect();
+            aliases.sort();
+            let mut esperados: Vec<&str> = info
+                .aliases
+                .iter()
+                .chain(info.aliases_visiveis.iter())
+                .copied()
+                .collect();
+            esperados.sort();
+            assert_eq!(aliases, esperados, "aliases divergentes em '{}'", nome);
+
+            let mut visiveis: Vec<&str> = sub.get_visible_aliases().collect();
+            visiveis.sort();
+            let mut visiveis_esperados = info.aliases_visiveis.to_vec();
+            visiveis_esperados.sort();
+            assert_eq!(
+                visiveis, visiveis_esperados,
+                "aliases visiveis divergentes em '{}'",
+                nome
+            );
+
+            assert_eq!(
+                sub.get_about().map(|a| a.to_string()).as_deref(),
+                Some(info.descricao_pt),
+                "descricao divergente em '{}'",
+                nome
+            );
+        }
+    }
+
+    #[test]
+    fn registro_nao_tem_comandos_orfaos() {
+        let cmd = Cli::command();
+        for info in comandos::REGISTRO {
+            assert!(
+                cmd.find_subcommand(info.nome).is_some(),
+                "comando '{}' registrado mas nao definido no Cli",
+                info.nome
+            );
+            assert!(!info.descricao_en.is_empty());
+        }
+    }
+}
@@ -0,0 +1,273 @@
+use std::ffi::OsStr;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use walkdir::WalkDir;
+
+use crate::construir::pasta_saida_projeto;
+use crate::impressoes::Impressao;
+use crate::toolchain::detectar_versao_binario;
+
+/// Registro do ultimo build bem-sucedido (`build` ou `release`), na pasta de saida base.
+pub const ARQUIVO_MANIFESTO: &str = "manifest.json";
+
+/// `--checksums`: hash de cada artefato do build, na pasta de saida do perfil.
+pub const ARQUIVO_CHECKSUMS: &str = "checksums.txt";
+
+/// Arquivos que a CLI grava junto dos artefatos e que nao contam como artefato.
+pub fn metadado_da_cli(nome: &OsStr) -> bool {
+    nome == ARQUIVO_MANIFESTO || nome == ARQUIVO_CHECKSUMS
+}
+
+/// Profundidade da varredura por artefatos (compiladores que gravam em subpastas).
+const PROFUNDIDADE_ARTEFATOS: usize = 4;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompiladorManifesto {
+    pub caminho: String,
+    pub versao: Option<String>,
+}
+
+/// Arquivo do manifesto: caminho relativo (com `/`), tamanho e hash FNV-1a.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArquivoManifesto {
+    pub caminho: String,
+    #[serde(flatten)]
+    pub impressao: Impressao,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Manifesto {
+    /// `build` ou `producao`
+    pub perfil: String,
+    pub target: String,
+    pub timestamp: u64,
+    pub compilador: CompiladorManifesto,
+    pub pasta_saida: String,
+    /// `.pr` passados ao compilador, relativos a raiz do projeto
+    pub fontes: Vec<ArquivoManifesto>,
+    /// Arquivos gravados na pasta de saida durante o build, relativos a ela
+    pub artefatos: Vec<ArquivoManifesto>,
+}
+
+fn relativo(base: &Path, caminho: &Path) -> String {
+    caminho
+        .strip_prefix(base)
+        .unwrap_or(caminho)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+fn entrada(base: &Path, caminho: &Path) -> Result<ArquivoManifesto> {
+    Ok(ArquivoManifesto {
+        caminho: relativo(base, caminho),
+        impressao: Impressao::de_arquivo(caminho)?,
+    })
+}
+
+/// Arquivos da pasta de saida escritos desde `inicio`, sem os metadados da CLI
+/// (ocultos e o proprio manifesto).
+fn artefatos_gerados(saida_dir: &Path, inicio: SystemTime) -> Vec<PathBuf> {
+    // Trunca para o segundo: sistemas de arquivos com mtime de baixa resolucao
+    let limite = inicio
+        .duration_since(UNIX_EPOCH)
+        .map(|d| UNIX_EPOCH + Duration::from_secs(d.as_secs()))
+        .unwrap_or(inicio);
+    let mut gerados: Vec<PathBuf> = WalkDir::new(saida_dir)
+        .max_depth(PROFUNDIDADE_ARTEFATOS)
+        .into_iter()
+        .filter_entry(|e| e.depth() == 0 || !e.file_name().to_string_lossy().starts_with('.'))
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file() && !metadado_da_cli(e.file_name()))
+        .filter(|e| {
+            e.metadata()
+                .ok()
+                .and_then(|m| m.modified().ok())
+                .is_some_and(|t| t >= limite)
+        })
+        .map(|e| e.into_path())
+        .collect();
+    gerados.sort();
+    gerados
+}
+
+/// Monta e grava `manifest.json` na pasta de saida base depois de um build bem-sucedido.
+///
+/// Falha ao gravar so gera aviso: o build ja terminou. Devolve o manifesto, se foi montado.
+pub fn registrar_build(
+    raiz: &Path,
+    saida_dir: &Path,
+    perfil: &str,
+    target: &str,
+    compilador: &Path,
+    fontes: &[PathBuf],
+    inicio: SystemTime,
+) -> Option<Manifesto> {
+    let resultado =
+        montar(raiz, saida_dir, perfil, target, compilador, fontes, inicio).and_then(|m| {
+            crate::resultado::acrescentar("builds", &m);
+            gravar(&pasta_saida_projeto(raiz), &m).map(|()| m)
+        });
+    match resultado {
+        Ok(m) => Some(m),
+        Err(e) => {
+            eprintln!(
+                "Aviso: nao foi possivel gravar o manifesto do build: {:#}",
+                e
+            );
+            None
+        }
+    }
+}
+
+/// Grava [`ARQUIVO_CHECKSUMS`] em `saida_dir`: uma linha `<hash>  <artefato>` por artefato.
+pub fn gravar_checksums(saida_dir: &Path, artefatos: &[ArquivoManifesto]) -> Result<()> {
+    let linhas: String = artefatos
+        .iter()
+        .map(|a| format!("{}  {}\n", a.impressao.hash, a.caminho))
+        .collect();
+    let caminho = saida_dir.join(ARQUIVO_CHECKSUMS);
+    fs::write(&caminho, linhas).with_context(|| format!("Falha ao gravar {}", caminho.display()))
+}
+
+fn montar(
+    raiz: &Path,
+    saida_dir: &Path,
+    perfil: &str,
+    target: &str,
+    compilador: &Path,
+    fontes: &[PathBuf],
+    inicio: SystemTime,
+) -> Result<Manifesto> {
+    Ok(Manifesto {
+        perfil: perfil.to_string(),
+        target: target.to_string(),
+        timestamp: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0),
+        compilador: CompiladorManifesto {
+            caminho: compilador.display().to_string(),
+            versao: detectar_versao_binario(compilador),
+        },
+        pasta_saida: saida_dir.display().to_string(),
+        fontes: fontes
+            .iter()
+            .map(|f| entrada(raiz, f))
+            .collect::<Result<_>>()?,
+        artefatos: artefatos_gerados(saida_dir, inicio)
+            .iter()
+            .map(|a| entrada(saida_dir, a))
+            .collect::<Result<_>>()?,
+    })
+}
+
+fn gravar(pasta_base: &Path, manifesto: &Manifesto) -> Result<()> {
+    fs::create_dir_all(pasta_base).ok();
+    let caminho = pasta_base.join(ARQUIVO_MANIFESTO);
+    fs::write(&caminho, serde_json::to_string_pretty(manifesto)? + "\n")
+        .with_context(|| format!("Falha ao gravar {}", caminho.display()))
+}
+
+/// Manifesto do ultimo build, se existir e for legivel.
+pub fn ler(pasta_base: &Path) -> Option<Manifesto> {
+    let texto = fs::read_to_string(pasta_base.join(ARQUIVO_MANIFESTO)).ok()?;
+    serde_json::from_str(&texto).ok()
+}
+
+/// Idade aproximada para humanos: `40s`, `5min`, `2h`, `3d`.
+pub fn descrever_idade(segundos: u64) -> String {
+    match segundos {
+        s if s < 60 => format!("{}s", s),
+        s if s < 60 * 60 => format!("{}min", s / 60),
+        s if s < 24 * 60 * 60 => format!("{}h", s / (60 * 60)),
+        s => format!("{}d", s / (24 * 60 * 60)),
+    }
+}
+
+/// Resumo de uma linha para `info`: "Ultimo build: llvm-ir ha 2h, 3 artefato(s)".
+pub fn resumo(manifesto: &Manifesto, agora: SystemTime) -> String {
+    let agora = agora
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let perfil = if manifesto.perfil == "build" {
+        String::new()
+    } else {
+        format!(" ({})", manifesto.perfil)
+    };
+    format!(
+        "Ultimo build: {}{} ha {}, {} artefato(s)",
+        manifesto.target,
+        perfil,
+        descrever_idade(agora.saturating_sub(manifesto.timestamp)),
+        manifesto.artefatos.len()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn idade_e_resumo() {
+        assert_eq!(descrever_idade(42), "42s");
+        assert_eq!(descrever_idade(5 * 60 + 3), "5min");
+        assert_eq!(descrever_idade(2 * 60 * 60 + 59), "2h");
+        assert_eq!(descrever_idade(3 * 24 * 60 * 60), "3d");
+
+        let m = Manifesto {
+            perfil: "build".to_string(),
+            target: "llvm-ir".to_string(),
+            timestamp: 1_000,
+            compilador: CompiladorManifesto {
+                caminho: "/opt/pds/compilador".to_string(),
+                versao: Some("0.5.0".to_string()),
+            },
+            pasta_saida: "/app/build".to_string(),
+            fontes: Vec::new(),
+            artefatos: ["a.ll", "b.ll", "c.ll"]
+                .iter()
+                .map(|c| ArquivoManifesto {
+                    caminho: c.to_string(),
+                    impressao: Impressao {
+                        bytes: 1,
+                        hash: "0".repeat(16),
+                    },
+                })
+                .collect(),
+        };
+        let agora = UNIX_EPOCH + Duration::from_secs(1_000 + 2 * 60 * 60);
+        assert_eq!(
+            resumo(&m, agora),
+            "Ultimo build: llvm-ir ha 2h, 3 artefato(s)"
+        );
+    }
+
+    #[test]
+    fn artefatos_ignoram_metadados_da_cli() {
+        let temp = tempfile::tempdir().unwrap();
+        let saida = temp.path();
+        fs::create_dir_all(saida.join("sub")).unwrap();
+        fs::create_dir_all(saida.join(".cache")).unwrap();
+        for arq in [
+            "programa.pbc",
+            "sub/modulo.pbc",
+            ".timings.jsonl",
+            ".cache/x",
+            ARQUIVO_MANIFESTO,
+            ARQUIVO_CHECKSUMS,
+        ] {
+            fs::write(saida.join(arq), "x").unwrap();
+        }
+        let antes = SystemTime::now() - Duration::from_secs(5);
+        let gerados: Vec<String> = artefatos_gerados(saida, antes)
+            .iter()
+            .map(|p| relativo(saida, p))
+            .collect();
+        assert_eq!(gerados, vec!["programa.pbc", "sub/modulo.pbc"]);
+    }
+}
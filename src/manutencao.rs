@@ -0,0 +1,366 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use anyhow::{Context, Result};
+use walkdir::WalkDir;
+
+use crate::analise::ARQUIVO_TAMANHOS;
+use crate::construir::pasta_saida_projeto;
+use crate::saida::detalhe;
+use crate::tempos::ARQUIVO_TIMINGS;
+use crate::toolchain::{carregar_configuracao_projeto, localizar_raiz};
+
+/// Marca da ultima manutencao automatica, na pasta de saida base.
+pub const ARQUIVO_MARCA: &str = ".manutencao";
+
+/// Intervalo minimo entre manutencoes automaticas no fim do build.
+const INTERVALO_AUTOMATICO: Duration = Duration::from_secs(24 * 60 * 60);
+
+const DIA: u64 = 24 * 60 * 60;
+
+/// Limites de retencao; `None` e ilimitado.
+///
+/// Vem do objeto `manutencao` do pordosol.proj (`logs_dias`, `historico_entradas`,
+/// `cache_scripts_dias`); `null` desliga o limite.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Retencao {
+    pub logs_dias: Option<u64>,
+    pub historico_entradas: Option<usize>,
+    pub cache_scripts_dias: Option<u64>,
+}
+
+impl Default for Retencao {
+    fn default() -> Self {
+        Retencao {
+            logs_dias: Some(14),
+            historico_entradas: Some(500),
+            cache_scripts_dias: Some(30),
+        }
+    }
+}
+
+impl Retencao {
+    pub fn do_projeto(raiz: &Path) -> Self {
+        let mut r = Retencao::default();
+        let Some(config) = carregar_configuracao_projeto(raiz) else {
+            return r;
+        };
+        let Some(m) = config.get("manutencao") else {
+            return r;
+        };
+        let limite = |chave: &str, padrao: Option<u64>| match m.get(chave) {
+            Some(serde_json::Value::Null) => None,
+            Some(v) => v.as_u64().or(padrao),
+            None => padrao,
+        };
+        r.logs_dias = limite("logs_dias", r.logs_dias);
+        r.historico_entradas = limite("historico_entradas", r.historico_entradas.map(|n| n as u64))
+            .map(|n| n as usize);
+        r.cache_scripts_dias = limite("cache_scripts_dias", r.cache_scripts_dias);
+        r
+    }
+}
+
+/// Um arquivo ou pasta auxiliar e o que a politica faria com ele.
+#[derive(Clone, Debug)]
+pub struct ItemAuxiliar {
+    pub caminho: PathBuf,
+    pub bytes: u64,
+    pub idade_dias: u64,
+    /// Linhas de historico que sairiam (historicos sao aparados, nao apagados)
+    pub linhas_excedentes: usize,
+    pub remover: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct Categoria {
+    pub nome: &'static str,
+    pub limite: String,
+    pub itens: Vec<ItemAuxiliar>,
+}
+
+fn idade_dias(caminho: &Path, agora: SystemTime) -> u64 {
+    caminho
+        .metadata()
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|m| agora.duration_since(m).ok())
+        .map(|d| d.as_secs() / DIA)
+        .unwrap_or(0)
+}
+
+fn tamanho(caminho: &Path) -> u64 {
+    WalkDir::new(caminho)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter_map(|e| e.metadata().ok())
+        .filter(|m| m.is_file())
+        .map(|m| m.len())
+        .sum()
+}
+
+fn descrever_dias(limite: Option<u64>) -> String {
+    limite.map_or("ilimitado".to_string(), |d| format!("{} dias", d))
+}
+
+/// Pasta dos caches de `pordosol script` (uma subpasta por arquivo executado).
+pub fn pasta_cache_scripts() -> PathBuf {
+    std::env::temp_dir().join("pordosol-cache")
+}
+
+/// Levanta as categorias de arquivos auxiliares do projeto e do usuario.
+pub fn levantar(raiz: &Path, retencao: &Retencao, agora: SystemTime) -> Vec<Categoria> {
+    let base = pasta_saida_projeto(raiz);
+
+    let historico = [ARQUIVO_TIMINGS, ARQUIVO_TAMANHOS]
+        .iter()
+        .map(|nome| base.join(nome))
+        .filter(|c| c.is_file())
+        .map(|caminho| {
+            let linhas = fs::read_to_string(&caminho)
+                .map(|t| t.lines().count())
+                .unwrap_or(0);
+            let excedentes = retencao
+                .historico_entradas
+                .map_or(0, |max| linhas.saturating_sub(max));
+            ItemAuxiliar {
+                bytes: tamanho(&caminho),
+                idade_dias: idade_dias(&caminho, agora),
+                linhas_excedentes: excedentes,
+                remover: false,
+                caminho,
+            }
+        })
+        .collect();
+
+    let logs = WalkDir::new(&base)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file() && e.path().extension().is_some_and(|x| x == "log"))
+        .map(|e| {
+            let idade = idade_dias(e.path(), agora);
+            ItemAuxiliar {
+                caminho: e.path().to_path_buf(),
+                bytes: tamanho(e.path()),
+                idade_dias: idade,
+                linhas_excedentes: 0,
+                remover: retencao.logs_dias.is_some_and(|max| idade > max),
+            }
+        })
+        .collect();
+
+    let caches = fs::read_dir(pasta_cache_scripts())
+        .into_iter()
+        .flatten()
+        .filter_map(|e| e.ok())
+        .map(|e| {
+            let idade = idade_dias(&e.path(), agora);
+            ItemAuxiliar {
+                bytes: tamanho(&e.path()),
+                idade_dias: idade,
+                linhas_excedentes: 0,
+                remover: retencao.cache_scripts_dias.is_some_and(|max| idade > max),
+                caminho: e.path(),
+            }
+        })
+        .collect();
+
+    vec![
+        Categoria {
+            nome: "historico",
+            limite: retencao
+                .historico_entradas
+                .map_or("ilimitado".to_string(), |n| format!("{} entradas", n)),
+            itens: historico,
+        },
+        Categoria {
+            nome: "logs",
+            limite: descrever_dias(retencao.logs_dias),
+            itens: logs,
+        },
+        Categoria {
+            nome: "cache de scripts",
+            limite: descrever_dias(retencao.cache_scripts_dias),
+            itens: caches,
+        },
+    ]
+}
+
+/// Mantem so as ultimas `manter` linhas de um historico `.jsonl`.
+fn aparar_historico(caminho: &Path, manter: usize) -> Result<()> {
+    let texto = fs::read_to_string(caminho)
+        .with_context(|| format!("Falha ao ler {}", caminho.display()))?;
+    let linhas: Vec<&str> = texto.lines().collect();
+    let inicio = linhas.len().saturating_sub(manter);
+    let mut novo = linhas[inicio..].join("\n");
+    if !novo.is_empty() {
+        novo.push('\n');
+    }
+    fs::write(caminho, novo).with_context(|| format!("Falha ao gravar {}", caminho.display()))
+}
+
+/// Aplica a politica; devolve quantos itens foram apagados ou aparados.
+pub fn aplicar(categorias: &[Categoria], retencao: &Retencao) -> Result<usize> {
+    let mut alterados = 0;
+    for item in categorias.iter().flat_map(|c| &c.itens) {
+        if item.linhas_excedentes > 0 {
+            if let Some(max) = retencao.historico_entradas {
+                aparar_historico(&item.caminho, max)?;
+                alterados += 1;
+            }
+        } else if item.remover {
+            if item.caminho.is_dir() {
+                fs::remove_dir_all(&item.caminho)
+            } else {
+                fs::remove_file(&item.caminho)
+            }
+            .with_context(|| format!("Falha ao remover {}", item.caminho.display()))?;
+            alterados += 1;
+        }
+    }
+    Ok(alterados)
+}
+
+/// `pordosol manutencao`: tamanho e idade dos arquivos auxiliares; com `aplicar`, aplica a retencao.
+pub fn manutencao_cmd(caminho: &Path, aplicar_politica: bool) -> Result<()> {
+    let raiz = localizar_raiz(caminho);
+    let retencao = Retencao::do_projeto(&raiz);
+    let categorias = levantar(&raiz, &retencao, SystemTime::now());
+
+    for c in &categorias {
+        let bytes: u64 = c.itens.iter().map(|i| i.bytes).sum();
+        let mais_antigo = c.itens.iter().map(|i| i.idade_dias).max();
+        println!(
+            "{}: {} item(ns), {} bytes{} (retencao: {})",
+            c.nome,
+            c.itens.len(),
+            bytes,
+            mais_antigo.map_or(String::new(), |d| format!(", mais antigo com {} dia(s)", d)),
+            c.limite
+        );
+        for i in &c.itens {
+            if i.linhas_excedentes > 0 {
+                println!(
+                    "  {} {}: {} entrada(s) alem do limite",
+                    if aplicar_politica {
+                        "aparado"
+                    } else {
+                        "aparar"
+                    },
+                    i.caminho.display(),
+                    i.linhas_excedentes
+                );
+            } else if i.remover {
+                println!(
+                    "  {} {} ({} dia(s), {} bytes)",
+                    if aplicar_politica {
+                        "removido"
+                    } else {
+                        "remover"
+                    },
+                    i.caminho.display(),
+                    i.idade_dias,
+                    i.bytes
+                );
+            }
+        }
+    }
+
+    if aplicar_politica {
+        let n = aplicar(&categorias, &retencao)?;
+        println!("{} item(ns) alterado(s).", n);
+        marcar(&raiz);
+    } else if categorias
+        .iter()
+        .flat_map(|c| &c.itens)
+        .any(|i| i.remover || i.linhas_excedentes > 0)
+    {
+        println!("Nada foi alterado. Use --aplicar para aplicar a retencao.");
+    }
+    Ok(())
+}
+
+fn marcar(raiz: &Path) {
+    let base = pasta_saida_projeto(raiz);
+    if fs::create_dir_all(&base).is_ok() {
+        fs::write(base.join(ARQUIVO_MARCA), "").ok();
+    }
+}
+
+/// Manutencao oportunista no fim do build: no maximo uma vez por dia, em silencio.
+///
+/// Falhas nunca afetam o build; so aparecem em `--verboso`.
+pub fn manutencao_automatica(raiz: &Path) {
+    let marca = pasta_saida_projeto(raiz).join(ARQUIVO_MARCA);
+    let recente = marca
+        .metadata()
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|m| SystemTime::now().duration_since(m).ok())
+        .is_some_and(|d| d < INTERVALO_AUTOMATICO);
+    if recente {
+        return;
+    }
+    let retencao = Retencao::do_projeto(raiz);
+    let categorias = levantar(raiz, &retencao, SystemTime::now());
+    match aplicar(&categorias, &retencao) {
+        Ok(n) => detalhe!("Manutencao automatica: {} item(ns) alterado(s)", n),
+        Err(e) => detalhe!("Manutencao automatica falhou: {:#}", e),
+    }
+    marcar(raiz);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retencao_do_projeto_com_padroes_e_null() {
+        let temp = tempfile::tempdir().unwrap();
+        assert_eq!(Retencao::do_projeto(temp.path()), Retencao::default());
+
+        fs::write(
+            temp.path().join("pordosol.proj"),
+            r#"{"manutencao": {"logs_dias": 3, "historico_entradas": null}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            Retencao::do_projeto(temp.path()),
+            Retencao {
+                logs_dias: Some(3),
+                historico_entradas: None,
+                cache_scripts_dias: Some(30),
+            }
+        );
+    }
+
+    #[test]
+    fn historico_e_aparado_e_logs_antigos_removidos() {
+        let temp = tempfile::tempdir().unwrap();
+        let raiz = temp.path();
+        let build = raiz.join("build");
+        fs::create_dir_all(&build).unwrap();
+        let linhas: String = (0..5).map(|i| format!("{{\"n\": {}}}\n", i)).collect();
+        fs::write(build.join(ARQUIVO_TIMINGS), linhas).unwrap();
+        fs::write(build.join("compilador.log"), "log").unwrap();
+
+        let retencao = Retencao {
+            logs_dias: Some(1),
+            historico_entradas: Some(2),
+            cache_scripts_dias: None,
+        };
+        // Dez dias depois, o log passa do limite
+        let depois = SystemTime::now() + Duration::from_secs(10 * DIA);
+        let categorias = levantar(raiz, &retencao, depois);
+        assert_eq!(categorias[0].itens[0].linhas_excedentes, 3);
+        assert!(categorias[1].itens[0].remover);
+
+        assert_eq!(aplicar(&categorias, &retencao).unwrap(), 2);
+        assert_eq!(
+            fs::read_to_string(build.join(ARQUIVO_TIMINGS)).unwrap(),
+            "{\"n\": 3}\n{\"n\": 4}\n"
+        );
+        assert!(!build.join("compilador.log").exists());
+    }
+}
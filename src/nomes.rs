@@ -0,0 +1,118 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+
+use crate::erros::ErroCli;
+use crate::saida::{pintar_erro, Cor};
+
+/// Stem como ficaria depois de sanitizado para nome de artefato: ASCII minusculo, resto vira `_`.
+///
+/// Minusculo porque `a.pbc` e `A.pbc` sao o mesmo arquivo em sistemas sem distincao de caixa.
+pub fn stem_sanitizado(stem: &str) -> String {
+    stem.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Problemas nos nomes dos fontes: espacos, nao-ASCII, outros caracteres especiais e stems
+/// que colidem depois de sanitizados (o `.pbc` sai do stem).
+pub fn problemas_nomes(raiz: &Path, arquivos: &[PathBuf]) -> Vec<String> {
+    let mut problemas = Vec::new();
+    let mut por_stem: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for arq in arquivos {
+        let rel = arq.strip_prefix(raiz).unwrap_or(arq).display().to_string();
+        let nome = arq
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+        if nome.chars().any(char::is_whitespace) {
+            problemas.push(format!("{}: nome com espacos", rel));
+        }
+        if !nome.is_ascii() {
+            problemas.push(format!("{}: nome com caracteres nao-ASCII", rel));
+        } else if nome
+            .chars()
+            .any(|c| !(c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | ' ')))
+        {
+            problemas.push(format!("{}: nome com caracteres especiais", rel));
+        }
+        let stem = arq.file_stem().unwrap_or_default().to_string_lossy();
+        por_stem
+            .entry(stem_sanitizado(&stem))
+            .or_default()
+            .push(rel);
+    }
+    for (stem, donos) in por_stem {
+        if donos.len() > 1 {
+            problemas.push(format!(
+                "{} geram o mesmo artefato '{}.pbc'",
+                donos.join(", "),
+                stem
+            ));
+        }
+    }
+    problemas
+}
+
+/// Avisa (no stderr) sobre nomes problematicos; com `strict`, falha listando todos.
+pub fn verificar_nomes(raiz: &Path, arquivos: &[PathBuf], strict: bool) -> Result<()> {
+    let problemas = problemas_nomes(raiz, arquivos);
+    if problemas.is_empty() {
+        return Ok(());
+    }
+    if strict {
+        return Err(ErroCli::ProjetoInvalido(format!(
+            "Nomes de fonte problematicos (--strict):\n  {}",
+            problemas.join("\n  ")
+        ))
+        .into());
+    }
+    for p in &problemas {
+        eprintln!("{}", pintar_erro(Cor::Amarelo, &format!("Aviso: {}", p)));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sanitiza_para_ascii_minusculo() {
+        assert_eq!(stem_sanitizado("Meu Modulo"), "meu_modulo");
+        assert_eq!(stem_sanitizado("ação"), "a__o");
+        assert_eq!(stem_sanitizado("util-v2"), "util-v2");
+    }
+
+    #[test]
+    fn aponta_espacos_acentos_especiais_e_colisoes() {
+        let raiz = Path::new("/p");
+        let arquivos: Vec<PathBuf> = [
+            "src/programa.pr",
+            "src/meu modulo.pr",
+            "src/ação.pr",
+            "src/a$b.pr",
+            "src/Util.pr",
+            "src/lib/util.pr",
+        ]
+        .iter()
+        .map(|r| raiz.join(r))
+        .collect();
+        let problemas = problemas_nomes(raiz, &arquivos);
+        assert!(problemas.contains(&"src/meu modulo.pr: nome com espacos".to_string()));
+        assert!(problemas.contains(&"src/ação.pr: nome com caracteres nao-ASCII".to_string()));
+        assert!(problemas.contains(&"src/a$b.pr: nome com caracteres especiais".to_string()));
+        assert!(problemas.contains(
+            &"src/Util.pr, src/lib/util.pr geram o mesmo artefato 'util.pbc'".to_string()
+        ));
+        assert!(!problemas.iter().any(|p| p.contains("programa")));
+    }
+}
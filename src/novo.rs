@@ -2,17 +2,48 @@ use std::ffi::OsStr;
 use std::fs;
 use std::path::{Component, Path, PathBuf};
 
-use anyhow::{bail, Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use path_absolutize::Absolutize;
 use walkdir::WalkDir;
 
+use crate::erros::ErroCli;
+use crate::explicar::voce_quis_dizer;
+use crate::git;
+use crate::info_build;
+use crate::projeto::DocumentoProjeto;
+use crate::saida::progresso;
+use crate::toolchain::{listar_prs, localizar_raiz};
+
 struct TemplateVars {
     project_name: String,
     namespace: String,
     target: String,
+    /// Variaveis extras de `--var CHAVE=VALOR`
+    extras: Vec<(String, String)>,
+}
+
+/// Placeholders que `pordosol new` sempre preenche, sem `--var`.
+pub(crate) const PLACEHOLDERS_EMBUTIDOS: &[&str] = &["PROJECT_NAME", "NAMESPACE", "TARGET"];
+
+impl TemplateVars {
+    fn definida(&self, nome: &str) -> bool {
+        PLACEHOLDERS_EMBUTIDOS.contains(&nome) || self.extras.iter().any(|(k, _)| k == nome)
+    }
+}
+
+/// Manifesto opcional `template.toml` na raiz de um template.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct ManifestoTemplate {
+    descricao: Option<String>,
+    autor: Option<String>,
+    versao: Option<String>,
+    /// Variaveis que precisam vir de `--var` ou dos built-ins
+    pub(crate) variaveis: Vec<String>,
 }
 
-pub fn listar_templates_cmd() -> Result<()> {
+pub(crate) const MANIFESTO_TEMPLATE: &str = "template.toml";
+
+pub fn listar_templates_cmd(detalhado: bool) -> Result<()> {
     let templates = listar_templates_disponiveis()?;
     if templates.is_empty() {
         println!("Nenhum template encontrado.");
@@ -20,43 +51,195 @@ pub fn listar_templates_cmd() -> Result<()> {
     }
 
     println!("Templates disponiveis:");
-    for template in templates {
-        println!("  {}", template);
+    if !detalhado {
+        for template in templates {
+            println!("  {}", template);
+        }
+        return Ok(());
+    }
+
+    let raiz = localizar_diretorio_templates();
+    let linhas: Vec<(String, ManifestoTemplate)> = templates
+        .into_iter()
+        .map(|t| {
+            let manifesto = raiz
+                .as_ref()
+                .and_then(|r| ler_manifesto(&r.join(&t)).ok().flatten())
+                .unwrap_or_default();
+            (t, manifesto)
+        })
+        .collect();
+    let largura_nome = linhas
+        .iter()
+        .map(|(t, _)| t.chars().count())
+        .chain(std::iter::once("Nome".len()))
+        .max()
+        .unwrap_or(0);
+    let largura_versao = linhas
+        .iter()
+        .map(|(_, m)| m.versao.as_deref().unwrap_or("-").chars().count())
+        .chain(std::iter::once("Versao".len()))
+        .max()
+        .unwrap_or(0);
+
+    println!(
+        "  {:<ln$}  {:<lv$}  Descricao",
+        "Nome",
+        "Versao",
+        ln = largura_nome,
+        lv = largura_versao
+    );
+    for (nome, m) in &linhas {
+        let mut descricao = m.descricao.clone().unwrap_or_else(|| "-".to_string());
+        if let Some(autor) = &m.autor {
+            descricao.push_str(&format!(" (por {})", autor));
+        }
+        if !m.variaveis.is_empty() {
+            descricao.push_str(&format!(" [vars: {}]", m.variaveis.join(", ")));
+        }
+        println!(
+            "  {:<ln$}  {:<lv$}  {}",
+            nome,
+            m.versao.as_deref().unwrap_or("-"),
+            descricao,
+            ln = largura_nome,
+            lv = largura_versao
+        );
     }
     Ok(())
 }
 
-pub fn novo_cmd(destino: &Path, nao_sobrescrever: bool, template: &str) -> Result<()> {
+/// Interpreta `--var CHAVE=VALOR`.
+pub fn parse_var(texto: &str) -> std::result::Result<(String, String), String> {
+    let (chave, valor) = texto
+        .split_once('=')
+        .ok_or_else(|| format!("esperado CHAVE=VALOR, recebido '{}'", texto))?;
+    let chave = chave.trim();
+    if chave.is_empty() {
+        return Err("CHAVE vazia em --var".to_string());
+    }
+    Ok((chave.to_string(), valor.to_string()))
+}
+
+/// Opcoes de `pordosol new` alem do destino e do template.
+pub struct OpcoesNovo<'a> {
+    /// `--nome`: vira o `PROJECT_NAME` e a base do namespace (sem ele, o nome da pasta)
+    pub nome: Option<&'a str>,
+    pub nao_sobrescrever: bool,
+    /// Permite pasta existente com conteudo
+    pub forcar: bool,
+    /// Variaveis extras de `--var CHAVE=VALOR`
+    pub extras: &'a [(String, String)],
+    pub dry_run: bool,
+    /// `--de-arquivo`: fonte existente que substitui o programa de exemplo
+    pub de_arquivo: Option<&'a Path>,
+    /// `--manter-nome`: copia o fonte com o nome original e o declara como `entrada`
+    pub manter_nome: bool,
+    /// `--info-build`: liga `configuracao.gerar_info_build` no projeto gerado
+    pub info_build: bool,
+}
+
+/// Com `dry_run`, percorre o template inteiro imprimindo o que seria criado ou sobrescrito,
+/// sem criar nenhum arquivo ou diretorio.
+///
+/// Pasta existente com conteudo exige `forcar` (ou `nao_sobrescrever`, que nunca toca no
+/// que ja existe).
+pub fn novo_cmd(destino: &Path, template: &str, opcoes: &OpcoesNovo) -> Result<()> {
+    if let Some(nome) = opcoes.nome {
+        validar_nome_projeto(nome.trim())?;
+    }
+    if let Some(arquivo) = opcoes.de_arquivo {
+        if !arquivo.is_file() || arquivo.extension() != Some(OsStr::new("pr")) {
+            bail!(
+                "--de-arquivo espera um arquivo .pr existente: {}",
+                arquivo.display()
+            );
+        }
+    }
     let raiz = destino
         .absolutize()
         .context("Falha ao resolver caminho do projeto")?
         .to_path_buf();
-    fs::create_dir_all(&raiz).context("Falha ao criar pasta do projeto")?;
-    fs::create_dir_all(raiz.join("build")).ok();
+    if !opcoes.dry_run && !opcoes.forcar && !opcoes.nao_sobrescrever && pasta_com_conteudo(&raiz) {
+        bail!(
+            "Pasta de destino {} ja existe e nao esta vazia. Use --forcar para criar o projeto nela.",
+            raiz.display()
+        );
+    }
+    let ja_existia = raiz.exists();
+    if !opcoes.dry_run {
+        fs::create_dir_all(&raiz).context("Falha ao criar pasta do projeto")?;
+        fs::create_dir_all(raiz.join("build")).ok();
+    }
+
+    let resultado = gerar_projeto(&raiz, template, opcoes);
+    // Falha (template ausente, variavel faltando) nao deixa pasta vazia para o --forcar
+    if resultado.is_err() && !opcoes.dry_run && !ja_existia {
+        fs::remove_dir_all(&raiz).ok();
+    }
+    resultado
+}
 
+fn gerar_projeto(raiz: &Path, template: &str, opcoes: &OpcoesNovo) -> Result<()> {
+    let OpcoesNovo {
+        nome,
+        nao_sobrescrever,
+        extras,
+        dry_run,
+        de_arquivo,
+        ..
+    } = *opcoes;
     let template_final = template.trim().to_ascii_lowercase();
     if template_final.is_empty() {
         bail!("Template invalido. Informe um tipo com `pordosol new list`.");
     }
 
-    let nome_projeto = raiz
-        .file_name()
-        .unwrap_or_default()
-        .to_string_lossy()
-        .to_string();
+    let nome_projeto = nome
+        .map(str::trim)
+        .filter(|n| !n.is_empty())
+        .map(str::to_string)
+        .unwrap_or_else(|| {
+            raiz.file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string()
+        });
     let vars = TemplateVars {
+        namespace: gerar_namespace(&nome_projeto),
         project_name: nome_projeto,
-        namespace: gerar_namespace(&raiz),
         target: target_padrao(&template_final).to_string(),
+        extras: extras.to_vec(),
     };
 
-    if aplicar_template_em_arquivos(&raiz, nao_sobrescrever, &template_final, &vars)? {
-        println!("Projeto {} pronto em {}", template_final, raiz.display());
-        return Ok(());
-    }
-
-    if aplicar_template_legado(&raiz, nao_sobrescrever, &template_final)? {
-        println!("Projeto {} pronto em {}", template_final, raiz.display());
+    let sem_exemplo = de_arquivo.is_some();
+    let aplicado = aplicar_template_em_arquivos(
+        raiz,
+        nao_sobrescrever,
+        &template_final,
+        &vars,
+        sem_exemplo,
+        dry_run,
+    )? || aplicar_template_legado(
+        raiz,
+        nao_sobrescrever,
+        &template_final,
+        sem_exemplo,
+        dry_run,
+    )?;
+    if aplicado {
+        if let Some(arquivo) = de_arquivo {
+            adotar_arquivo(raiz, arquivo, opcoes.manter_nome, dry_run)?;
+        }
+        configurar_info_build(raiz, opcoes.info_build, dry_run)?;
+        if dry_run {
+            println!(
+                "Dry-run: nada foi gravado; o projeto {} seria criado em {}",
+                template_final,
+                raiz.display()
+            );
+        } else {
+            progresso!("Projeto {} pronto em {}", template_final, raiz.display());
+        }
         return Ok(());
     }
 
@@ -67,17 +250,229 @@ pub fn novo_cmd(destino: &Path, nao_sobrescrever: bool, template: &str) -> Resul
             template_final
         );
     }
+    let nomes: Vec<&str> = disponiveis.iter().map(String::as_str).collect();
     bail!(
-        "Template '{}' nao encontrado. Use `pordosol new list` para ver os disponiveis.",
-        template_final
+        "Template '{}' nao encontrado.{} Use `pordosol new list` para ver os disponiveis.",
+        template_final,
+        voce_quis_dizer(&template_final, &nomes)
     );
 }
 
+/// Verdadeiro se o fonte declara uma funcao `Principal` (com ou sem acento em `funcao`).
+fn declara_principal(fonte: &str) -> bool {
+    fonte.lines().any(|linha| {
+        let linha = linha.trim_start();
+        (linha.starts_with("funcao ") || linha.starts_with("função "))
+            && linha
+                .split(|c: char| !c.is_alphanumeric() && c != '_')
+                .any(|t| t == "Principal")
+    })
+}
+
+/// Copia o fonte de `--de-arquivo` para `src/`: como `programa.pr` ou, com `manter_nome`,
+/// com o nome original e declarado como `entrada` no pordosol.proj. O original nao e tocado.
+fn adotar_arquivo(raiz: &Path, arquivo: &Path, manter_nome: bool, dry_run: bool) -> Result<()> {
+    let conteudo =
+        fs::read(arquivo).with_context(|| format!("Falha ao ler {}", arquivo.display()))?;
+    if !declara_principal(&String::from_utf8_lossy(&conteudo)) {
+        eprintln!(
+            "Aviso: {} nao declara uma funcao Principal; o projeto compila, mas `pordosol run` nao tera o que executar.",
+            arquivo.display()
+        );
+    }
+
+    let nome = if manter_nome {
+        arquivo
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string()
+    } else {
+        "programa.pr".to_string()
+    };
+    gravar_arquivo_projeto(&raiz.join("src").join(&nome), &conteudo, dry_run)?;
+
+    if manter_nome && nome != "programa.pr" {
+        let entrada = format!("src/{}", nome);
+        if dry_run {
+            println!("Definiria `entrada` = {} no pordosol.proj", entrada);
+        } else {
+            let mut doc = DocumentoProjeto::ler(&raiz.join("pordosol.proj"))?;
+            doc.definir_bruto("entrada", serde_json::to_string(&entrada)?)?;
+            doc.salvar()?;
+            progresso!("Entrada do projeto: {}", entrada);
+        }
+    }
+    Ok(())
+}
+
+/// Com `ligar`, define `configuracao.gerar_info_build`; com a opcao ligada (pela flag ou ja
+/// pelo template), o arquivo gerado vai para o `.gitignore`.
+fn configurar_info_build(raiz: &Path, ligar: bool, dry_run: bool) -> Result<()> {
+    if dry_run {
+        if ligar {
+            println!(
+                "Definiria configuracao.{} = {} e o acrescentaria ao .gitignore",
+                info_build::CAMPO,
+                info_build::CAMINHO_PADRAO
+            );
+        }
+        return Ok(());
+    }
+    if ligar {
+        let mut doc = DocumentoProjeto::ler(&raiz.join("pordosol.proj"))?;
+        let mut config = doc.objeto("configuracao")?;
+        let valor = serde_json::value::RawValue::from_string(serde_json::to_string(
+            info_build::CAMINHO_PADRAO,
+        )?)?;
+        match config.iter_mut().find(|(k, _)| k == info_build::CAMPO) {
+            Some((_, v)) => *v = valor,
+            None => config.push((info_build::CAMPO.to_string(), valor)),
+        }
+        doc.definir_objeto("configuracao", &config)?;
+        doc.salvar()?;
+    }
+    let Some(arquivo) = info_build::arquivo_configurado(raiz)? else {
+        return Ok(());
+    };
+    let rel = arquivo
+        .strip_prefix(raiz)
+        .unwrap_or(&arquivo)
+        .to_string_lossy()
+        .replace('\\', "/");
+    ignorar_no_git(raiz, &format!("/{}", rel))
+}
+
+/// Acrescenta `padrao` ao `.gitignore` da raiz (criando-o), se ainda nao estiver la.
+fn ignorar_no_git(raiz: &Path, padrao: &str) -> Result<()> {
+    let caminho = raiz.join(".gitignore");
+    let mut texto = fs::read_to_string(&caminho).unwrap_or_default();
+    let sem_barra = padrao.trim_start_matches('/');
+    if texto
+        .lines()
+        .any(|l| l.trim().trim_start_matches('/') == sem_barra)
+    {
+        return Ok(());
+    }
+    if !texto.is_empty() && !texto.ends_with('\n') {
+        texto.push('\n');
+    }
+    texto.push_str(padrao);
+    texto.push('\n');
+    fs::write(&caminho, texto).with_context(|| format!("Falha ao gravar {}", caminho.display()))
+}
+
+/// Cria um projeto clonando um repositorio git existente (`new --from-git`).
+///
+/// O historico (`.git`) e removido e o `nome` do pordosol.proj passa a ser o da pasta
+/// de destino. Em caso de falha, a pasta criada parcialmente e apagada.
+pub fn novo_de_git(destino: &Path, url: &str, rev: Option<&str>) -> Result<()> {
+    let raiz = destino
+        .absolutize()
+        .context("Falha ao resolver caminho do projeto")?
+        .to_path_buf();
+
+    let ja_existia = raiz.exists();
+    if ja_existia && pasta_com_conteudo(&raiz) {
+        bail!(
+            "Pasta de destino {} ja existe e nao esta vazia.",
+            raiz.display()
+        );
+    }
+
+    if crate::rede::eh_remoto(url) {
+        crate::rede::exigir_rede("new --from-git", url)?;
+    }
+    git::localizar().context("O git e necessario para --from-git")?;
+
+    let resultado = clonar_repositorio(url, rev, &raiz);
+    if let Err(erro) = resultado {
+        if ja_existia {
+            limpar_conteudo(&raiz);
+        } else {
+            fs::remove_dir_all(&raiz).ok();
+        }
+        return Err(erro);
+    }
+
+    fs::remove_dir_all(raiz.join(".git"))
+        .with_context(|| format!("Falha ao remover {}", raiz.join(".git").display()))?;
+
+    let nome_projeto = raiz
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+    let proj = raiz.join("pordosol.proj");
+    if proj.is_file() {
+        let mut doc = DocumentoProjeto::ler(&proj)?;
+        doc.definir_bruto("nome", serde_json::to_string(&nome_projeto)?)?;
+        doc.salvar()?;
+        progresso!("Atualizado nome em {}", proj.display());
+    } else {
+        progresso!("Aviso: repositorio clonado nao contem pordosol.proj.");
+    }
+
+    progresso!("Projeto clonado de {} pronto em {}", url, raiz.display());
+    Ok(())
+}
+
+pub(crate) fn clonar_repositorio(url: &str, rev: Option<&str>, destino: &Path) -> Result<()> {
+    let mut args: Vec<&OsStr> = vec![OsStr::new("clone"), OsStr::new("--quiet")];
+    if rev.is_none() {
+        args.extend([OsStr::new("--depth"), OsStr::new("1")]);
+    }
+    args.extend([OsStr::new(url), destino.as_os_str()]);
+    git::executar(None, args)?;
+
+    if let Some(rev) = rev {
+        git::executar(Some(destino), ["checkout", "--quiet", rev])?;
+    }
+    Ok(())
+}
+
+fn limpar_conteudo(dir: &Path) {
+    if let Ok(entries) = fs::read_dir(dir) {
+        for entry in entries.filter_map(|e| e.ok()) {
+            let path = entry.path();
+            if path.is_dir() {
+                fs::remove_dir_all(&path).ok();
+            } else {
+                fs::remove_file(&path).ok();
+            }
+        }
+    }
+}
+
+/// Grava um arquivo do projeto novo (criando as pastas) ou, em dry-run, so descreve a acao.
+fn gravar_arquivo_projeto(destino: &Path, conteudo: &[u8], dry_run: bool) -> Result<()> {
+    if dry_run {
+        let acao = if destino.exists() {
+            "Sobrescreveria"
+        } else {
+            "Criaria"
+        };
+        println!("{} {} ({} bytes)", acao, destino.display(), conteudo.len());
+        return Ok(());
+    }
+    if let Some(parent) = destino.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Falha ao criar diretorio de destino {}", parent.display()))?;
+    }
+    fs::write(destino, conteudo)
+        .with_context(|| format!("Falha ao escrever arquivo {}", destino.display()))?;
+    progresso!("Criado {}", destino.display());
+    Ok(())
+}
+
+/// Com `sem_exemplo`, os `.pr` de `src/` do template nao sao gerados (`--de-arquivo`).
 fn aplicar_template_em_arquivos(
     destino: &Path,
     nao_sobrescrever: bool,
     template: &str,
     vars: &TemplateVars,
+    sem_exemplo: bool,
+    dry_run: bool,
 ) -> Result<bool> {
     let Some(templates_root) = localizar_diretorio_templates() else {
         return Ok(false);
@@ -88,6 +483,22 @@ fn aplicar_template_em_arquivos(
         return Ok(false);
     }
 
+    if let Some(manifesto) = ler_manifesto(&template_dir)? {
+        let faltando: Vec<&str> = manifesto
+            .variaveis
+            .iter()
+            .map(String::as_str)
+            .filter(|v| !vars.definida(v))
+            .collect();
+        if !faltando.is_empty() {
+            bail!(
+                "Template '{}' requer variaveis nao informadas: {}. Use --var CHAVE=VALOR.",
+                template,
+                faltando.join(", ")
+            );
+        }
+    }
+
     for entry in WalkDir::new(&template_dir)
         .into_iter()
         .filter_map(|entry| entry.ok())
@@ -97,47 +508,49 @@ fn aplicar_template_em_arquivos(
         let rel = origem
             .strip_prefix(&template_dir)
             .context("Falha ao resolver caminho relativo do template")?;
+        if rel == Path::new(MANIFESTO_TEMPLATE) {
+            continue;
+        }
         let destino_rel = renderizar_caminho_relativo(rel, vars);
+        if sem_exemplo
+            && destino_rel.starts_with("src")
+            && destino_rel.extension() == Some(OsStr::new("pr"))
+        {
+            continue;
+        }
         let arquivo_destino = destino.join(destino_rel);
 
         if arquivo_destino.exists() && nao_sobrescrever {
-            println!(
+            progresso!(
                 "Arquivo {} ja existe (nao sobrescrito).",
                 arquivo_destino.display()
             );
             continue;
         }
 
-        if let Some(parent) = arquivo_destino.parent() {
-            fs::create_dir_all(parent).with_context(|| {
-                format!("Falha ao criar diretorio de destino {}", parent.display())
-            })?;
-        }
-
-        copiar_ou_renderizar_arquivo(origem, &arquivo_destino, vars)?;
-        println!("Criado {}", arquivo_destino.display());
+        let conteudo = renderizar_arquivo(origem, vars)?;
+        gravar_arquivo_projeto(&arquivo_destino, &conteudo, dry_run)?;
     }
 
     Ok(true)
 }
 
-fn copiar_ou_renderizar_arquivo(origem: &Path, destino: &Path, vars: &TemplateVars) -> Result<()> {
+/// BOM que editores do Windows gravam no inicio de arquivos UTF-8.
+const BOM_UTF8: &[u8] = b"\xEF\xBB\xBF";
+
+/// Conteudo final de um arquivo do template: texto com placeholders substituidos, binario intacto.
+///
+/// Um BOM UTF-8 inicial e descartado antes da substituicao (senao `{{PROJECT_NAME}}` na
+/// primeira linha nao casa) e o arquivo gerado sai sem ele; as quebras de linha sao mantidas.
+fn renderizar_arquivo(origem: &Path, vars: &TemplateVars) -> Result<Vec<u8>> {
     let bytes = fs::read(origem)
         .with_context(|| format!("Falha ao ler arquivo de template {}", origem.display()))?;
 
-    match String::from_utf8(bytes.clone()) {
-        Ok(texto) => {
-            let renderizado = substituir_placeholders(&texto, vars);
-            fs::write(destino, renderizado)
-                .with_context(|| format!("Falha ao escrever arquivo {}", destino.display()))?;
-        }
-        Err(_) => {
-            fs::write(destino, bytes)
-                .with_context(|| format!("Falha ao copiar arquivo {}", destino.display()))?;
-        }
-    }
-
-    Ok(())
+    let texto = bytes.strip_prefix(BOM_UTF8).unwrap_or(&bytes);
+    Ok(match std::str::from_utf8(texto) {
+        Ok(texto) => substituir_placeholders(texto, vars).into_bytes(),
+        Err(_) => bytes,
+    })
 }
 
 fn renderizar_caminho_relativo(rel: &Path, vars: &TemplateVars) -> PathBuf {
@@ -158,10 +571,98 @@ fn renderizar_caminho_relativo(rel: &Path, vars: &TemplateVars) -> PathBuf {
 }
 
 fn substituir_placeholders(valor: &str, vars: &TemplateVars) -> String {
-    valor
+    let mut out = valor
         .replace("{{PROJECT_NAME}}", &vars.project_name)
         .replace("{{NAMESPACE}}", &vars.namespace)
-        .replace("{{TARGET}}", &vars.target)
+        .replace("{{TARGET}}", &vars.target);
+    for (chave, v) in &vars.extras {
+        out = out.replace(&format!("{{{{{}}}}}", chave), v);
+    }
+    out
+}
+
+/// Le `template.toml` (subconjunto de TOML: `chave = "texto"` e listas de textos).
+pub(crate) fn ler_manifesto(template_dir: &Path) -> Result<Option<ManifestoTemplate>> {
+    let caminho = template_dir.join(MANIFESTO_TEMPLATE);
+    if !caminho.is_file() {
+        return Ok(None);
+    }
+    let texto = fs::read_to_string(&caminho)
+        .with_context(|| format!("Falha ao ler {}", caminho.display()))?;
+    parse_manifesto(&texto)
+        .with_context(|| format!("Manifesto invalido em {}", caminho.display()))
+        .map(Some)
+}
+
+fn parse_manifesto(texto: &str) -> Result<ManifestoTemplate> {
+    let mut manifesto = ManifestoTemplate::default();
+    let mut linhas = texto.lines().enumerate();
+    while let Some((idx, linha)) = linhas.next() {
+        let linha = remover_comentario_toml(linha).trim();
+        if linha.is_empty() {
+            continue;
+        }
+        let Some((chave, valor)) = linha.split_once('=') else {
+            bail!("linha {}: esperado `chave = valor`", idx + 1);
+        };
+        let chave = chave.trim();
+        let mut valor = valor.trim().to_string();
+
+        // Listas podem se estender por varias linhas ate o `]`
+        if valor.starts_with('[') {
+            while !valor.trim_end().ends_with(']') {
+                let Some((_, prox)) = linhas.next() else {
+                    bail!("linha {}: lista sem `]`", idx + 1);
+                };
+                valor.push(' ');
+                valor.push_str(remover_comentario_toml(prox).trim());
+            }
+        }
+
+        match chave {
+            "descricao" => manifesto.descricao = Some(parse_texto_toml(&valor, idx)?),
+            "autor" => manifesto.autor = Some(parse_texto_toml(&valor, idx)?),
+            "versao" => manifesto.versao = Some(parse_texto_toml(&valor, idx)?),
+            "variaveis" => {
+                let interno = valor
+                    .trim()
+                    .strip_prefix('[')
+                    .and_then(|v| v.strip_suffix(']'))
+                    .ok_or_else(|| anyhow!("linha {}: `variaveis` deve ser uma lista", idx + 1))?;
+                manifesto.variaveis = interno
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|v| !v.is_empty())
+                    .map(|v| parse_texto_toml(v, idx))
+                    .collect::<Result<_>>()?;
+            }
+            // Chaves desconhecidas sao ignoradas para compatibilidade futura
+            _ => {}
+        }
+    }
+    Ok(manifesto)
+}
+
+fn parse_texto_toml(valor: &str, idx: usize) -> Result<String> {
+    let valor = valor.trim();
+    let interno = valor
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .ok_or_else(|| anyhow!("linha {}: esperado texto entre aspas", idx + 1))?;
+    Ok(interno.replace("\\\"", "\"").replace("\\\\", "\\"))
+}
+
+/// Remove `# comentario` fora de aspas.
+fn remover_comentario_toml(linha: &str) -> &str {
+    let mut em_texto = false;
+    for (i, c) in linha.char_indices() {
+        match c {
+            '"' => em_texto = !em_texto,
+            '#' if !em_texto => return &linha[..i],
+            _ => {}
+        }
+    }
+    linha
 }
 
 fn listar_templates_disponiveis() -> Result<Vec<String>> {
@@ -183,18 +684,32 @@ fn listar_templates_disponiveis() -> Result<Vec<String>> {
     ])
 }
 
+/// Pasta de templates do usuario (`PORDOSOL_TEMPLATES_PATH` ou `PORDOSOL_HOME/templates`),
+/// mesmo que ainda nao exista; e onde `pordosol templates instalar` grava.
+pub(crate) fn diretorio_templates_usuario() -> Option<PathBuf> {
+    let env = |var: &str| std::env::var_os(var).filter(|v| !v.is_empty());
+    env("PORDOSOL_TEMPLATES_PATH")
+        .map(PathBuf::from)
+        .or_else(|| env("PORDOSOL_HOME").map(|h| PathBuf::from(h).join("templates")))
+}
+
 fn localizar_diretorio_templates() -> Option<PathBuf> {
+    localizar_diretorio_templates_com_origem().map(|(p, _)| p)
+}
+
+/// Pasta de templates usada por `new`, com o rotulo de onde ela veio (para o `doctor`).
+pub(crate) fn localizar_diretorio_templates_com_origem() -> Option<(PathBuf, &'static str)> {
     if let Ok(path) = std::env::var("PORDOSOL_TEMPLATES_PATH") {
         let p = PathBuf::from(path);
         if p.is_dir() {
-            return Some(p);
+            return Some((p, "env:PORDOSOL_TEMPLATES_PATH"));
         }
     }
 
     if let Ok(home) = std::env::var("PORDOSOL_HOME") {
         let p = PathBuf::from(home).join("templates");
         if p.is_dir() {
-            return Some(p);
+            return Some((p, "env:PORDOSOL_HOME/templates"));
         }
     }
 
@@ -202,12 +717,12 @@ fn localizar_diretorio_templates() -> Option<PathBuf> {
         if let Some(exe_dir) = exe_path.parent() {
             let templates = exe_dir.join("templates");
             if templates.is_dir() {
-                return Some(templates);
+                return Some((templates, "exe:templates"));
             }
             if let Some(parent) = exe_dir.parent() {
                 let templates = parent.join("templates");
                 if templates.is_dir() {
-                    return Some(templates);
+                    return Some((templates, "exe:../templates"));
                 }
             }
         }
@@ -215,7 +730,7 @@ fn localizar_diretorio_templates() -> Option<PathBuf> {
 
     let templates_local = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("templates");
     if templates_local.is_dir() {
-        return Some(templates_local);
+        return Some((templates_local, "fonte:templates"));
     }
 
     None
@@ -228,12 +743,64 @@ fn target_padrao(template: &str) -> &'static str {
     }
 }
 
-fn gerar_namespace(destino: &Path) -> String {
-    let nome = destino
-        .file_name()
-        .unwrap_or_else(|| OsStr::new("Projeto"))
-        .to_string_lossy()
-        .to_string();
+/// Nome aceito em `new --nome` e `rename`: vira nome de pasta, valor JSON e namespace.
+fn validar_nome_projeto(nome: &str) -> Result<()> {
+    if nome.is_empty()
+        || nome
+            .chars()
+            .any(|c| matches!(c, '/' | '\\' | '"') || c.is_control())
+    {
+        bail!(
+            "Nome invalido '{}': use um nome sem separadores de caminho ou aspas",
+            nome
+        );
+    }
+    if !nome
+        .chars()
+        .map(sem_acento)
+        .any(|c| c.is_ascii_alphanumeric())
+    {
+        bail!(
+            "Nome '{}' nao gera um namespace valido: inclua letras ou numeros",
+            nome
+        );
+    }
+    Ok(())
+}
+
+/// Pasta existente com pelo menos uma entrada (ilegivel conta como ocupada).
+fn pasta_com_conteudo(pasta: &Path) -> bool {
+    pasta.exists()
+        && fs::read_dir(pasta)
+            .map(|mut d| d.next().is_some())
+            .unwrap_or(true)
+}
+
+/// Letra sem acento (`ç` -> `c`, `Ã` -> `A`); outros caracteres voltam iguais.
+fn sem_acento(c: char) -> char {
+    match c {
+        'á' | 'à' | 'â' | 'ã' | 'ä' => 'a',
+        'Á' | 'À' | 'Â' | 'Ã' | 'Ä' => 'A',
+        'é' | 'è' | 'ê' | 'ë' => 'e',
+        'É' | 'È' | 'Ê' | 'Ë' => 'E',
+        'í' | 'ì' | 'î' | 'ï' => 'i',
+        'Í' | 'Ì' | 'Î' | 'Ï' => 'I',
+        'ó' | 'ò' | 'ô' | 'õ' | 'ö' => 'o',
+        'Ó' | 'Ò' | 'Ô' | 'Õ' | 'Ö' => 'O',
+        'ú' | 'ù' | 'û' | 'ü' => 'u',
+        'Ú' | 'Ù' | 'Û' | 'Ü' => 'U',
+        'ç' => 'c',
+        'Ç' => 'C',
+        'ñ' => 'n',
+        'Ñ' => 'N',
+        _ => c,
+    }
+}
+
+/// Namespace derivado do nome do projeto: acentos caem (`Pão` -> `Pao`) e o resto que
+/// nao for ASCII alfanumerico separa segmentos (`meu-app` -> `Meu.App`).
+fn gerar_namespace(nome: &str) -> String {
+    let nome: String = nome.chars().map(sem_acento).collect();
 
     let partes = nome
         .split(|c: char| !c.is_ascii_alphanumeric())
@@ -248,6 +815,50 @@ fn gerar_namespace(destino: &Path) -> String {
     partes.join(".")
 }
 
+/// Palavras reservadas de Por do Sol que nao podem ser segmento de namespace.
+///
+/// So as grafias ASCII: os acentos ja foram removidos antes desta checagem.
+const PALAVRAS_RESERVADAS: &[&str] = &[
+    "abstrato",
+    "base",
+    "booleano",
+    "caso",
+    "classe",
+    "constante",
+    "continue",
+    "decimal",
+    "enquanto",
+    "espaco",
+    "estatico",
+    "este",
+    "falso",
+    "faca",
+    "funcao",
+    "inteiro",
+    "interface",
+    "nulo",
+    "novo",
+    "para",
+    "pare",
+    "privado",
+    "privada",
+    "protegido",
+    "publico",
+    "publica",
+    "real",
+    "retorne",
+    "se",
+    "senao",
+    "texto",
+    "usando",
+    "var",
+    "vazio",
+    "verdadeiro",
+];
+
+/// Segmento de namespace a partir de um pedaco do nome da pasta.
+///
+/// Palavras reservadas ganham `_` no fim (`classe` -> `Classe_`) para continuar validas.
 fn formatar_token_namespace(token: &str) -> String {
     let mut chars = token.chars().filter(|c| c.is_ascii_alphanumeric());
     let Some(first) = chars.next() else {
@@ -266,17 +877,114 @@ fn formatar_token_namespace(token: &str) -> String {
         out.push(c.to_ascii_lowercase());
     }
 
+    if PALAVRAS_RESERVADAS.contains(&out.to_ascii_lowercase().as_str()) {
+        out.push('_');
+    }
     out
 }
 
-fn aplicar_template_legado(destino: &Path, nao_sobrescrever: bool, template: &str) -> Result<bool> {
+/// Prefixo do comentario de namespace gerado pelos templates em `src/*.pr`.
+const COMENTARIO_NAMESPACE: &str = "// Namespace:";
+
+/// `pordosol rename`: atualiza o `nome` do pordosol.proj e o namespace derivado dele.
+///
+/// A pasta do projeto no disco nao e renomeada.
+pub fn renomear_cmd(caminho: &Path, novo_nome: &str, atualizar_readme: bool) -> Result<()> {
+    let novo_nome = novo_nome.trim();
+    validar_nome_projeto(novo_nome)?;
+
+    let raiz = localizar_raiz(caminho);
+    let proj = raiz.join("pordosol.proj");
+    if !proj.exists() {
+        return Err(ErroCli::ProjetoInvalido(format!(
+            "Arquivo de projeto nao encontrado em {}",
+            proj.display()
+        ))
+        .into());
+    }
+    let mut doc = DocumentoProjeto::ler(&proj)?;
+    let nome_antigo = doc
+        .obter("nome")
+        .and_then(|v| serde_json::from_str::<String>(v.get()).ok())
+        .unwrap_or_else(|| {
+            raiz.file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string()
+        });
+    let namespace_antigo = gerar_namespace(&nome_antigo);
+    let namespace = gerar_namespace(novo_nome);
+
+    doc.definir_bruto("nome", serde_json::to_string(novo_nome)?)?;
+    doc.salvar()?;
+    progresso!(
+        "Nome do projeto: {} -> {} (namespace {})",
+        nome_antigo,
+        novo_nome,
+        namespace
+    );
+
+    for arquivo in listar_prs(&raiz) {
+        let texto = fs::read_to_string(&arquivo)
+            .with_context(|| format!("Falha ao ler {}", arquivo.display()))?;
+        let mut alterado = false;
+        let linhas: Vec<String> = texto
+            .split('\n')
+            .map(|linha| {
+                if linha.trim_start().starts_with(COMENTARIO_NAMESPACE) {
+                    alterado = true;
+                    let fim_cr = if linha.ends_with('\r') { "\r" } else { "" };
+                    format!("{} {}{}", COMENTARIO_NAMESPACE, namespace, fim_cr)
+                } else {
+                    linha.to_string()
+                }
+            })
+            .collect();
+        if alterado {
+            fs::write(&arquivo, linhas.join("\n"))
+                .with_context(|| format!("Falha ao escrever {}", arquivo.display()))?;
+            progresso!("Namespace atualizado em {}", arquivo.display());
+        }
+    }
+
+    let readme = raiz.join("README.md");
+    if atualizar_readme && readme.is_file() {
+        let texto = fs::read_to_string(&readme)
+            .with_context(|| format!("Falha ao ler {}", readme.display()))?;
+        // Namespace primeiro: o nome antigo pode ser parte dele
+        let novo = texto
+            .replace(&namespace_antigo, &namespace)
+            .replace(&nome_antigo, novo_nome);
+        if novo != texto {
+            fs::write(&readme, novo)
+                .with_context(|| format!("Falha ao escrever {}", readme.display()))?;
+            progresso!("Atualizado {}", readme.display());
+        }
+    }
+
+    eprintln!(
+        "Aviso: a pasta {} nao foi renomeada; renomeie-a manualmente se desejar.",
+        raiz.display()
+    );
+    Ok(())
+}
+
+fn aplicar_template_legado(
+    destino: &Path,
+    nao_sobrescrever: bool,
+    template: &str,
+    sem_exemplo: bool,
+    dry_run: bool,
+) -> Result<bool> {
     match template {
         "console" | "web" | "biblioteca" | "classe" => {}
         _ => return Ok(false),
     }
 
-    fs::create_dir_all(destino.join("src")).ok();
-    fs::create_dir_all(destino.join("build")).ok();
+    if !dry_run {
+        fs::create_dir_all(destino.join("src")).ok();
+        fs::create_dir_all(destino.join("build")).ok();
+    }
 
     let nome_projeto = destino
         .file_name()
@@ -349,14 +1057,14 @@ fn aplicar_template_legado(destino: &Path, nao_sobrescrever: bool, template: &st
             ),
         };
 
-        fs::write(&projeto_file, conteudo_projeto)?;
-        println!("Criado {}", projeto_file.display());
+        gravar_arquivo_projeto(&projeto_file, conteudo_projeto.as_bytes(), dry_run)?;
     }
 
     let prog = destino.join("src").join("programa.pr");
-    if prog.exists() && nao_sobrescrever {
-        println!("Projeto ja contem src/programa.pr (nao sobrescrito).");
-    } else if !prog.exists() || !nao_sobrescrever {
+    // Com --de-arquivo o programa vem do arquivo do usuario
+    if !sem_exemplo && prog.exists() && nao_sobrescrever {
+        progresso!("Projeto ja contem src/programa.pr (nao sobrescrito).");
+    } else if !sem_exemplo && (!prog.exists() || !nao_sobrescrever) {
         let exemplo = match template {
             "biblioteca" => {
                 r#"// biblioteca.pr - template de biblioteca
@@ -430,8 +1138,7 @@ funcao vazio Principal()
             }
         };
 
-        fs::write(&prog, exemplo)?;
-        println!("Criado {}", prog.display());
+        gravar_arquivo_projeto(&prog, exemplo.as_bytes(), dry_run)?;
     }
 
     let readme = destino.join("README.md");
@@ -472,8 +1179,7 @@ pordosol clean
             nome_projeto
         );
 
-        fs::write(&readme, conteudo_readme)?;
-        println!("Criado {}", readme.display());
+        gravar_arquivo_projeto(&readme, conteudo_readme.as_bytes(), dry_run)?;
     }
 
     Ok(true)
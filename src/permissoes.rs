@@ -0,0 +1,153 @@
+use std::path::Path;
+use std::time::SystemTime;
+
+use anyhow::Result;
+
+/// Usuario que chamou o `sudo`, dono legitimo do que a CLI criar.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UsuarioOriginal {
+    pub uid: u32,
+    pub gid: u32,
+}
+
+/// Decide se o processo roda como root via sudo: `euid` 0 com `SUDO_UID`/`SUDO_GID` de outro usuario.
+///
+/// Root de verdade (sem sudo, ou `sudo` a partir do proprio root) nao tem a quem devolver os arquivos.
+pub fn usuario_sudo(
+    euid: u32,
+    sudo_uid: Option<&str>,
+    sudo_gid: Option<&str>,
+) -> Option<UsuarioOriginal> {
+    if euid != 0 {
+        return None;
+    }
+    let uid = sudo_uid?.trim().parse::<u32>().ok()?;
+    let gid = sudo_gid?.trim().parse::<u32>().ok()?;
+    (uid != 0).then_some(UsuarioOriginal { uid, gid })
+}
+
+#[cfg(unix)]
+pub fn detectar_sudo() -> Option<UsuarioOriginal> {
+    let euid = unsafe { libc::geteuid() };
+    usuario_sudo(
+        euid,
+        std::env::var("SUDO_UID").ok().as_deref(),
+        std::env::var("SUDO_GID").ok().as_deref(),
+    )
+}
+
+#[cfg(windows)]
+pub fn detectar_sudo() -> Option<UsuarioOriginal> {
+    None
+}
+
+/// Comando que o usuario pode rodar para corrigir o dono manualmente.
+pub fn comando_chown(raiz: &Path, usuario: UsuarioOriginal) -> String {
+    format!(
+        "sudo chown -R {}:{} '{}'",
+        usuario.uid,
+        usuario.gid,
+        raiz.display()
+    )
+}
+
+/// Devolve ao usuario do sudo o que foi criado em `raiz`.
+///
+/// Com `desde`, so entradas modificadas a partir desse instante (a pasta ja existia e
+/// pode ter arquivos de outros donos); sem, a arvore inteira.
+#[cfg(unix)]
+pub fn devolver_propriedade(
+    raiz: &Path,
+    usuario: UsuarioOriginal,
+    desde: Option<SystemTime>,
+) -> Result<usize> {
+    use anyhow::Context;
+
+    let mut alterados = 0;
+    for entrada in walkdir::WalkDir::new(raiz) {
+        let entrada = entrada?;
+        let meta = entrada.path().symlink_metadata()?;
+        let recente = desde.is_none_or(|d| meta.modified().is_ok_and(|m| m >= d));
+        if !recente {
+            continue;
+        }
+        std::os::unix::fs::lchown(entrada.path(), Some(usuario.uid), Some(usuario.gid))
+            .with_context(|| format!("Falha ao alterar o dono de {}", entrada.path().display()))?;
+        alterados += 1;
+    }
+    Ok(alterados)
+}
+
+#[cfg(windows)]
+pub fn devolver_propriedade(
+    _raiz: &Path,
+    _usuario: UsuarioOriginal,
+    _desde: Option<SystemTime>,
+) -> Result<usize> {
+    Ok(0)
+}
+
+/// Pos-processamento de quem grava arquivos do usuario (projeto novo, PORDOSOL_HOME) sob sudo.
+///
+/// Avisa sempre; com `manter_usuario`, devolve os arquivos ao usuario original e,
+/// se nao conseguir, imprime o `chown` exato para corrigir.
+pub fn ajustar_dono_apos_sudo(raiz: &Path, desde: Option<SystemTime>, manter_usuario: bool) {
+    let Some(usuario) = detectar_sudo() else {
+        return;
+    };
+    eprintln!(
+        "Aviso: pordosol rodando como root via sudo; os arquivos criados em {} ficariam com o root.",
+        raiz.display()
+    );
+    if !manter_usuario {
+        eprintln!(
+            "Para editar sem sudo, rode: {}",
+            comando_chown(raiz, usuario)
+        );
+        return;
+    }
+    match devolver_propriedade(raiz, usuario, desde) {
+        Ok(n) => eprintln!(
+            "Dono de {} item(ns) devolvido a {}:{}.",
+            n, usuario.uid, usuario.gid
+        ),
+        Err(e) => eprintln!(
+            "Aviso: {:#}. Corrija com: {}",
+            e,
+            comando_chown(raiz, usuario)
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn so_considera_sudo_de_outro_usuario_com_euid_root() {
+        assert_eq!(
+            usuario_sudo(0, Some("1000"), Some("1001")),
+            Some(UsuarioOriginal {
+                uid: 1000,
+                gid: 1001
+            })
+        );
+        assert_eq!(usuario_sudo(1000, Some("1000"), Some("1000")), None);
+        assert_eq!(usuario_sudo(0, None, None), None);
+        assert_eq!(usuario_sudo(0, Some("0"), Some("0")), None);
+        assert_eq!(usuario_sudo(0, Some("abc"), Some("1000")), None);
+        assert_eq!(usuario_sudo(0, Some("1000"), None), None);
+    }
+
+    #[test]
+    fn comando_chown_cita_uid_gid_e_caminho() {
+        let usuario = UsuarioOriginal {
+            uid: 1000,
+            gid: 100,
+        };
+        assert_eq!(
+            comando_chown(Path::new("/opt/app"), usuario),
+            "sudo chown -R 1000:100 '/opt/app'"
+        );
+    }
+}
@@ -0,0 +1,178 @@
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::de::{Deserialize, Deserializer, MapAccess, Visitor};
+use serde_json::value::RawValue;
+
+use crate::erros::ErroCli;
+
+/// `pordosol.proj` carregado preservando a ordem e a formatacao original das chaves.
+///
+/// Cada valor de topo e mantido como texto bruto, entao reescrever o arquivo so
+/// altera as chaves efetivamente modificadas.
+pub struct DocumentoProjeto {
+    caminho: PathBuf,
+    entradas: Vec<(String, Box<RawValue>)>,
+    indentacao: String,
+    newline_final: bool,
+}
+
+impl DocumentoProjeto {
+    pub fn ler(caminho: &Path) -> Result<Self> {
+        let conteudo = fs::read_to_string(caminho)
+            .with_context(|| format!("Falha ao ler {}", caminho.display()))?;
+        let entradas = ler_entradas(&conteudo).map_err(|e| {
+            ErroCli::ProjetoInvalido(format!(
+                "{} deve conter um objeto JSON valido: {}",
+                caminho.display(),
+                e
+            ))
+        })?;
+
+        Ok(Self {
+            caminho: caminho.to_path_buf(),
+            entradas,
+            indentacao: detectar_indentacao(&conteudo),
+            newline_final: conteudo.ends_with('\n'),
+        })
+    }
+
+    pub fn obter(&self, chave: &str) -> Option<&RawValue> {
+        self.entradas
+            .iter()
+            .find(|(k, _)| k == chave)
+            .map(|(_, v)| v.as_ref())
+    }
+
+    /// Define o texto JSON de uma chave de topo, mantendo sua posicao se ja existir.
+    pub fn definir_bruto(&mut self, chave: &str, json: String) -> Result<()> {
+        let valor = RawValue::from_string(json).context("Valor JSON invalido")?;
+        if let Some((_, v)) = self.entradas.iter_mut().find(|(k, _)| k == chave) {
+            *v = valor;
+        } else {
+            self.entradas.push((chave.to_string(), valor));
+        }
+        Ok(())
+    }
+
+    /// Entradas de um objeto de topo (ex.: `dependencias`) em ordem.
+    ///
+    /// Chave ausente ou `null` resulta em objeto vazio; qualquer outro tipo e erro.
+    pub fn objeto(&self, chave: &str) -> Result<Vec<(String, Box<RawValue>)>> {
+        match self.obter(chave) {
+            None => Ok(Vec::new()),
+            Some(raw) if raw.get().trim() == "null" => Ok(Vec::new()),
+            Some(raw) => match ler_entradas(raw.get()) {
+                Ok(entradas) => Ok(entradas),
+                Err(_) => Err(ErroCli::ProjetoInvalido(format!(
+                    "Campo '{}' invalido em {}: esperado um objeto, encontrado {}",
+                    chave,
+                    self.caminho.display(),
+                    descrever_tipo(raw.get())
+                ))
+                .into()),
+            },
+        }
+    }
+
+    pub fn definir_objeto(
+        &mut self,
+        chave: &str,
+        entradas: &[(String, Box<RawValue>)],
+    ) -> Result<()> {
+        let texto = renderizar_objeto(entradas, &self.indentacao, 1)?;
+        self.definir_bruto(chave, texto)
+    }
+
+    pub fn salvar(&self) -> Result<()> {
+        let mut texto = renderizar_objeto(&self.entradas, &self.indentacao, 0)?;
+        if self.newline_final {
+            texto.push('\n');
+        }
+        fs::write(&self.caminho, texto)
+            .with_context(|| format!("Falha ao escrever {}", self.caminho.display()))
+    }
+}
+
+struct EntradasOrdenadas(Vec<(String, Box<RawValue>)>);
+
+impl<'de> Deserialize<'de> for EntradasOrdenadas {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct VisitanteOrdenado;
+
+        impl<'de> Visitor<'de> for VisitanteOrdenado {
+            type Value = EntradasOrdenadas;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("um objeto JSON")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let mut out = Vec::new();
+                while let Some((k, v)) = map.next_entry::<String, Box<RawValue>>()? {
+                    out.push((k, v));
+                }
+                Ok(EntradasOrdenadas(out))
+            }
+        }
+
+        deserializer.deserialize_map(VisitanteOrdenado)
+    }
+}
+
+fn ler_entradas(texto: &str) -> Result<Vec<(String, Box<RawValue>)>> {
+    let EntradasOrdenadas(entradas) = serde_json::from_str(texto)?;
+    Ok(entradas)
+}
+
+fn renderizar_objeto(
+    entradas: &[(String, Box<RawValue>)],
+    indentacao: &str,
+    nivel: usize,
+) -> Result<String> {
+    if entradas.is_empty() {
+        return Ok("{}".to_string());
+    }
+
+    let interno = indentacao.repeat(nivel + 1);
+    let mut out = String::from("{\n");
+    for (idx, (chave, valor)) in entradas.iter().enumerate() {
+        out.push_str(&interno);
+        out.push_str(&serde_json::to_string(chave)?);
+        out.push_str(": ");
+        out.push_str(valor.get());
+        if idx + 1 < entradas.len() {
+            out.push(',');
+        }
+        out.push('\n');
+    }
+    out.push_str(&indentacao.repeat(nivel));
+    out.push('}');
+    Ok(out)
+}
+
+fn detectar_indentacao(conteudo: &str) -> String {
+    conteudo
+        .lines()
+        .skip(1)
+        .find(|l| l.trim_start().starts_with('"'))
+        .map(|l| {
+            l.chars()
+                .take_while(|c| *c == ' ' || *c == '\t')
+                .collect::<String>()
+        })
+        .filter(|i| !i.is_empty())
+        .unwrap_or_else(|| "    ".to_string())
+}
+
+fn descrever_tipo(texto: &str) -> &'static str {
+    match texto.trim_start().chars().next() {
+        Some('[') => "um array",
+        Some('"') => "um texto",
+        Some('t') | Some('f') => "um booleano",
+        Some('n') => "null",
+        _ => "um numero",
+    }
+}
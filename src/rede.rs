@@ -0,0 +1,52 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use anyhow::{bail, Result};
+
+/// `--offline`: todo caminho que usaria a rede falha antes de tentar.
+static OFFLINE: AtomicBool = AtomicBool::new(false);
+
+pub fn definir_offline(offline: bool) {
+    OFFLINE.store(offline, Ordering::Relaxed);
+}
+
+/// Verdadeiro para URLs que exigem rede: `esquema://` (menos `file://`) e o formato
+/// `usuario@host:caminho` do ssh. Caminhos locais, inclusive `C:\...`, nao contam.
+pub fn eh_remoto(url: &str) -> bool {
+    if let Some((esquema, _)) = url.split_once("://") {
+        return !esquema.eq_ignore_ascii_case("file");
+    }
+    match url.split_once(':') {
+        Some((antes, _)) => antes.len() > 1 && !antes.contains(['/', '\\']),
+        None => false,
+    }
+}
+
+/// Porta de entrada de qualquer acesso a rede: todo codigo que baixa algo passa por aqui
+/// antes de comecar, para que `--offline` seja respeitado.
+pub fn exigir_rede(operacao: &str, alvo: &str) -> Result<()> {
+    if OFFLINE.load(Ordering::Relaxed) {
+        bail!(
+            "{} precisa de rede ({}), mas --offline esta ativo. Use uma copia local ou remova --offline.",
+            operacao,
+            alvo
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn distingue_urls_remotas_de_caminhos_locais() {
+        assert!(eh_remoto("https://github.com/org/modelo.git"));
+        assert!(eh_remoto("ssh://git@host/org/modelo"));
+        assert!(eh_remoto("git@github.com:org/modelo.git"));
+        assert!(!eh_remoto("file:///srv/modelos/app"));
+        assert!(!eh_remoto("/srv/modelos/app"));
+        assert!(!eh_remoto("../modelos/app"));
+        assert!(!eh_remoto(r"C:\modelos\app"));
+        assert!(!eh_remoto("./a:b"));
+    }
+}
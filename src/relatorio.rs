@@ -0,0 +1,271 @@
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Result};
+use serde::Serialize;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum StatusUnidade {
+    #[serde(rename = "sucesso")]
+    Sucesso,
+    #[serde(rename = "falha")]
+    Falha,
+    #[serde(rename = "nao executado")]
+    NaoExecutado,
+}
+
+impl StatusUnidade {
+    fn rotulo(&self) -> &'static str {
+        match self {
+            StatusUnidade::Sucesso => "sucesso",
+            StatusUnidade::Falha => "FALHA",
+            StatusUnidade::NaoExecutado => "nao executado",
+        }
+    }
+
+    fn ordem(&self) -> u8 {
+        match self {
+            StatusUnidade::Falha => 0,
+            StatusUnidade::NaoExecutado => 1,
+            StatusUnidade::Sucesso => 2,
+        }
+    }
+}
+
+/// O que o cache fez com uma unidade que passa por ele (ex.: as dependencias de um membro no
+/// `producao` de um workspace com `cache_dependencias`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SituacaoCache {
+    /// Todas as dependencias vieram do cache, com os artefatos intactos
+    Reaproveitado,
+    /// Ao menos uma dependencia passou pelo compilador
+    Recompilado,
+}
+
+impl SituacaoCache {
+    fn rotulo(&self) -> &'static str {
+        match self {
+            SituacaoCache::Reaproveitado => "reaproveitado",
+            SituacaoCache::Recompilado => "recompilado",
+        }
+    }
+}
+
+/// Resultado de uma unidade de trabalho (membro x target).
+#[derive(Clone, Debug, Serialize)]
+pub struct UnidadeExecucao {
+    pub membro: String,
+    pub target: String,
+    pub status: StatusUnidade,
+    pub duracao_ms: u128,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cache: Option<SituacaoCache>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub erro: Option<String>,
+}
+
+/// Acumula o resultado de cada unidade executada por drivers multi-membro/multi-target.
+#[derive(Debug, Default, Serialize)]
+pub struct RelatorioExecucao {
+    pub unidades: Vec<UnidadeExecucao>,
+}
+
+impl RelatorioExecucao {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Executa uma unidade registrando status, duracao e resumo do erro; o erro segue para
+    /// quem chamou, que decide se interrompe e de onde vem o codigo de saida.
+    pub fn executar<F>(&mut self, membro: &str, target: &str, f: F) -> Result<()>
+    where
+        F: FnOnce() -> Result<()>,
+    {
+        let inicio = Instant::now();
+        let resultado = f();
+        self.registrar(
+            membro,
+            target,
+            inicio.elapsed(),
+            resultado.as_ref().map(|()| None),
+        );
+        resultado
+    }
+
+    /// Registra uma unidade executada fora de [`Self::executar`] (ex.: numa thread).
+    pub fn registrar(
+        &mut self,
+        membro: &str,
+        target: &str,
+        duracao: Duration,
+        resultado: Result<Option<SituacaoCache>, &anyhow::Error>,
+    ) {
+        let (status, cache, erro) = match resultado {
+            Ok(cache) => (StatusUnidade::Sucesso, cache, None),
+            Err(e) => (StatusUnidade::Falha, None, Some(format!("{:#}", e))),
+        };
+        self.unidades.push(UnidadeExecucao {
+            membro: membro.to_string(),
+            target: target.to_string(),
+            status,
+            duracao_ms: duracao.as_millis(),
+            cache,
+            erro,
+        });
+    }
+
+    /// Registra uma unidade pulada (ex.: apos `--fail-fast`).
+    pub fn pular(&mut self, membro: &str, target: &str) {
+        self.unidades.push(UnidadeExecucao {
+            membro: membro.to_string(),
+            target: target.to_string(),
+            status: StatusUnidade::NaoExecutado,
+            duracao_ms: 0,
+            cache: None,
+            erro: None,
+        });
+    }
+
+    pub fn falhas(&self) -> usize {
+        self.unidades
+            .iter()
+            .filter(|u| u.status == StatusUnidade::Falha)
+            .count()
+    }
+
+    /// Unidades ordenadas com falhas primeiro, preservando a ordem de execucao dentro de cada grupo.
+    pub fn ordenadas(&self) -> Vec<&UnidadeExecucao> {
+        let mut out: Vec<&UnidadeExecucao> = self.unidades.iter().collect();
+        out.sort_by_key(|u| u.status.ordem());
+        out
+    }
+
+    pub fn imprimir_tabela(&self) {
+        let largura_membro = self
+            .unidades
+            .iter()
+            .map(|u| u.membro.chars().count())
+            .chain(std::iter::once("Membro".len()))
+            .max()
+            .unwrap_or(0);
+        let largura_target = self
+            .unidades
+            .iter()
+            .map(|u| u.target.chars().count())
+            .chain(std::iter::once("Target".len()))
+            .max()
+            .unwrap_or(0);
+        // A coluna so aparece quando alguma unidade passou por um cache
+        let largura_cache = if self.unidades.iter().any(|u| u.cache.is_some()) {
+            "reaproveitado".len() + 2
+        } else {
+            0
+        };
+        let cache = |u: Option<&UnidadeExecucao>| match u {
+            _ if largura_cache == 0 => String::new(),
+            None => "Cache".to_string(),
+            Some(u) => u.cache.map(|c| c.rotulo()).unwrap_or("-").to_string(),
+        };
+
+        println!();
+        println!("=== Resumo da execucao ===");
+        println!(
+            "{:<lm$}  {:<lt$}  {:<13}  {:<lc$}{:>9}  Erro",
+            "Membro",
+            "Target",
+            "Status",
+            cache(None),
+            "Tempo",
+            lm = largura_membro,
+            lt = largura_target,
+            lc = largura_cache
+        );
+        for u in self.ordenadas() {
+            let erro = u
+                .erro
+                .as_deref()
+                .and_then(|e| e.lines().next())
+                .unwrap_or("");
+            println!(
+                "{:<lm$}  {:<lt$}  {:<13}  {:<lc$}{:>7}ms  {}",
+                u.membro,
+                u.target,
+                u.status.rotulo(),
+                cache(Some(u)),
+                u.duracao_ms,
+                erro,
+                lm = largura_membro,
+                lt = largura_target,
+                lc = largura_cache
+            );
+        }
+    }
+
+    /// Imprime o relatorio como tabela ou JSON.
+    pub fn imprimir(&self, json: bool) -> Result<()> {
+        if json {
+            println!("{}", serde_json::to_string_pretty(self)?);
+        } else {
+            self.imprimir_tabela();
+        }
+        Ok(())
+    }
+
+    /// Imprime o relatorio (tabela ou JSON) e falha se alguma unidade falhou.
+    pub fn finalizar(&self, json: bool) -> Result<()> {
+        self.imprimir(json)?;
+
+        let falhas = self.falhas();
+        if falhas > 0 {
+            bail!("{} de {} unidade(s) falharam", falhas, self.unidades.len());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+
+    #[test]
+    fn falhas_primeiro_e_puladas_marcadas() {
+        let mut rel = RelatorioExecucao::new();
+        assert!(rel.executar("app", "bytecode", || Ok(())).is_ok());
+        let erro = rel
+            .executar("app", "llvm-ir", || Err(anyhow!("quebrou")))
+            .unwrap_err();
+        assert_eq!(erro.to_string(), "quebrou");
+        rel.pular("app", "console");
+
+        let ordem: Vec<(&str, StatusUnidade)> = rel
+            .ordenadas()
+            .iter()
+            .map(|u| (u.target.as_str(), u.status))
+            .collect();
+        assert_eq!(
+            ordem,
+            vec![
+                ("llvm-ir", StatusUnidade::Falha),
+                ("console", StatusUnidade::NaoExecutado),
+                ("bytecode", StatusUnidade::Sucesso),
+            ]
+        );
+        assert_eq!(rel.falhas(), 1);
+        assert_eq!(rel.unidades[1].erro.as_deref(), Some("quebrou"));
+
+        let json = serde_json::to_value(&rel).unwrap();
+        assert_eq!(json["unidades"][2]["status"], "nao executado");
+        assert!(json["unidades"][0].get("cache").is_none());
+
+        rel.registrar(
+            "lib",
+            "llvm-ir",
+            Duration::from_millis(5),
+            Ok(Some(SituacaoCache::Reaproveitado)),
+        );
+        let json = serde_json::to_value(&rel).unwrap();
+        assert_eq!(json["unidades"][3]["cache"], "reaproveitado");
+        assert_eq!(json["unidades"][3]["duracao_ms"], 5);
+    }
+}
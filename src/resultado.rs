@@ -0,0 +1,238 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, Once};
+use std::time::SystemTime;
+
+use anyhow::{Context, Result};
+use path_absolutize::Absolutize;
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::info_build::formatar_utc;
+
+/// Versao do documento de `--resultado`; so muda quando um campo existente muda de sentido.
+pub const VERSAO_ESQUEMA: u32 = 1;
+
+/// Documento pedido por `--resultado`, preenchido ao longo do comando.
+struct Pendente {
+    arquivo: PathBuf,
+    comando: &'static str,
+    argumentos: Vec<String>,
+    diretorio: PathBuf,
+    inicio: SystemTime,
+    dados: Map<String, Value>,
+}
+
+static PENDENTE: Mutex<Option<Pendente>> = Mutex::new(None);
+/// Mensagem do ultimo panico, guardada pelo gancho para o [`Guarda`].
+static PANICO: Mutex<Option<String>> = Mutex::new(None);
+static GANCHO: Once = Once::new();
+
+/// Formato gravado por `--resultado` (ver RESULTADO.md).
+#[derive(Serialize)]
+struct Documento<'a> {
+    versao_esquema: u32,
+    /// Nome canonico do subcomando (`build`, `producao`, `run`, `test`, `clean`)
+    comando: &'a str,
+    /// Argumentos como recebidos, sem o executavel
+    argumentos: &'a [String],
+    /// Diretorio de trabalho, base dos caminhos relativos dos argumentos
+    diretorio: String,
+    /// `AAAA-MM-DDTHH:MM:SSZ`
+    inicio: String,
+    fim: String,
+    duracao_ms: u128,
+    codigo_saida: i32,
+    sucesso: bool,
+    /// Cadeia do erro, do contexto mais externo a causa; vazia no sucesso
+    erros: Vec<String>,
+    /// Dados proprios do comando (artefatos, resumo de testes, itens removidos)
+    dados: &'a Map<String, Value>,
+}
+
+fn pendente() -> std::sync::MutexGuard<'static, Option<Pendente>> {
+    // Um panico com o lock tomado nao invalida o documento
+    PENDENTE.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// `--resultado <arquivo>`: passa a montar o documento de `comando`, gravado ao terminar.
+pub fn definir_resultado(arquivo: Option<&Path>, comando: &'static str) {
+    let Some(arquivo) = arquivo else {
+        return;
+    };
+    GANCHO.call_once(|| {
+        let anterior = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            *PANICO.lock().unwrap_or_else(|e| e.into_inner()) = Some(info.to_string());
+            anterior(info);
+        }));
+    });
+    *pendente() = Some(Pendente {
+        arquivo: arquivo
+            .absolutize()
+            .map(|a| a.to_path_buf())
+            .unwrap_or_else(|_| arquivo.to_path_buf()),
+        comando,
+        argumentos: std::env::args().skip(1).collect(),
+        diretorio: std::env::current_dir().unwrap_or_default(),
+        inicio: SystemTime::now(),
+        dados: Map::new(),
+    });
+}
+
+/// Define `dados.<chave>`; sem `--resultado`, nao faz nada.
+pub fn registrar(chave: &str, valor: impl Serialize) {
+    if let Some(p) = pendente().as_mut() {
+        let valor = serde_json::to_value(valor).unwrap_or(Value::Null);
+        p.dados.insert(chave.to_string(), valor);
+    }
+}
+
+/// Acrescenta `valor` a lista `dados.<chave>` (um item por target, arquivo removido etc.).
+pub fn acrescentar(chave: &str, valor: impl Serialize) {
+    if let Some(p) = pendente().as_mut() {
+        let valor = serde_json::to_value(valor).unwrap_or(Value::Null);
+        match p
+            .dados
+            .entry(chave)
+            .or_insert_with(|| Value::Array(Vec::new()))
+        {
+            Value::Array(itens) => itens.push(valor),
+            outro => *outro = Value::Array(vec![valor]),
+        }
+    }
+}
+
+/// Grava o documento pendente com o desfecho do comando. Chamado antes de sair do processo.
+pub fn concluir(erro: Option<&anyhow::Error>) {
+    let codigo = erro.map(crate::erros::codigo_saida).unwrap_or(0);
+    let erros = erro
+        .map(|e| e.chain().map(|c| c.to_string()).collect())
+        .unwrap_or_default();
+    finalizar(codigo, erros);
+}
+
+fn finalizar(codigo_saida: i32, erros: Vec<String>) {
+    let Some(p) = pendente().take() else {
+        return;
+    };
+    let fim = SystemTime::now();
+    let documento = Documento {
+        versao_esquema: VERSAO_ESQUEMA,
+        comando: p.comando,
+        argumentos: &p.argumentos,
+        diretorio: p.diretorio.display().to_string(),
+        inicio: formatar_utc(p.inicio),
+        fim: formatar_utc(fim),
+        duracao_ms: fim.duration_since(p.inicio).unwrap_or_default().as_millis(),
+        codigo_saida,
+        sucesso: codigo_saida == 0,
+        erros,
+        dados: &p.dados,
+    };
+    if let Err(e) = gravar_atomico(&p.arquivo, &documento) {
+        eprintln!(
+            "Aviso: nao foi possivel gravar o resultado em {}: {:#}",
+            p.arquivo.display(),
+            e
+        );
+    }
+}
+
+/// Grava num temporario da mesma pasta e renomeia: quem le nunca ve um JSON pela metade.
+fn gravar_atomico(arquivo: &Path, documento: &Documento) -> Result<()> {
+    let pasta = arquivo.parent().unwrap_or(Path::new("."));
+    fs::create_dir_all(pasta).with_context(|| format!("Falha ao criar {}", pasta.display()))?;
+    let temporario = pasta.join(format!(
+        ".{}.{}.tmp",
+        arquivo
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        std::process::id()
+    ));
+    fs::write(&temporario, serde_json::to_string_pretty(documento)? + "\n")
+        .with_context(|| format!("Falha ao gravar {}", temporario.display()))?;
+    fs::rename(&temporario, arquivo).map_err(|e| {
+        fs::remove_file(&temporario).ok();
+        anyhow::Error::new(e).context(format!("Falha ao renomear para {}", arquivo.display()))
+    })
+}
+
+/// Vive em `main`: se o comando entrar em panico, grava o documento durante o unwind
+/// com codigo 101 (o mesmo do runtime) e a mensagem do panico.
+pub struct Guarda;
+
+impl Drop for Guarda {
+    fn drop(&mut self) {
+        if !std::thread::panicking() {
+            return;
+        }
+        let mensagem = PANICO
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .take()
+            .unwrap_or_else(|| "panico".to_string());
+        finalizar(101, vec![mensagem]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gravacao_atomica_substitui_sem_deixar_temporario() {
+        let temp = tempfile::tempdir().unwrap();
+        let arquivo = temp.path().join("saida").join("resultado.json");
+        let dados = Map::new();
+        let argumentos = vec!["build".to_string()];
+        let documento = |codigo_saida| Documento {
+            versao_esquema: VERSAO_ESQUEMA,
+            comando: "build",
+            argumentos: &argumentos,
+            diretorio: String::new(),
+            inicio: formatar_utc(SystemTime::UNIX_EPOCH),
+            fim: formatar_utc(SystemTime::UNIX_EPOCH),
+            duracao_ms: 0,
+            codigo_saida,
+            sucesso: codigo_saida == 0,
+            erros: Vec::new(),
+            dados: &dados,
+        };
+
+        gravar_atomico(&arquivo, &documento(0)).unwrap();
+        gravar_atomico(&arquivo, &documento(3)).unwrap();
+        let json: Value = serde_json::from_str(&fs::read_to_string(&arquivo).unwrap()).unwrap();
+        assert_eq!(json["codigo_saida"], 3);
+        assert_eq!(json["inicio"], "1970-01-01T00:00:00Z");
+        let nomes: Vec<_> = fs::read_dir(arquivo.parent().unwrap())
+            .unwrap()
+            .map(|e| e.unwrap().file_name())
+            .collect();
+        assert_eq!(nomes, ["resultado.json"]);
+    }
+
+    #[test]
+    fn panico_grava_o_documento_durante_o_unwind() {
+        let temp = tempfile::tempdir().unwrap();
+        let arquivo = temp.path().join("resultado.json");
+        definir_resultado(Some(&arquivo), "run");
+        registrar("compilado", true);
+
+        let r = std::panic::catch_unwind(|| {
+            let _guarda = Guarda;
+            panic!("falha interna");
+        });
+        assert!(r.is_err());
+        let json: Value = serde_json::from_str(&fs::read_to_string(&arquivo).unwrap()).unwrap();
+        assert_eq!(json["comando"], "run");
+        assert_eq!(json["codigo_saida"], 101);
+        assert_eq!(json["dados"]["compilado"], true);
+        assert!(
+            json["erros"][0].as_str().unwrap().contains("falha interna"),
+            "{}",
+            json
+        );
+    }
+}
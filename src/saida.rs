@@ -0,0 +1,181 @@
+use std::io::IsTerminal;
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+
+/// Nivel global de mensagens, definido por `--quiet`/`--verboso`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Nivel {
+    /// So erros e a saida do programa aparecem
+    Silencioso = 0,
+    Normal = 1,
+    /// Tambem linhas de comando, origens da toolchain e tempo de cada etapa (no stderr)
+    Verboso = 2,
+}
+
+static NIVEL: AtomicU8 = AtomicU8::new(Nivel::Normal as u8);
+
+pub fn definir_nivel(nivel: Nivel) {
+    NIVEL.store(nivel as u8, Ordering::Relaxed);
+}
+
+pub fn nivel() -> Nivel {
+    match NIVEL.load(Ordering::Relaxed) {
+        0 => Nivel::Silencioso,
+        1 => Nivel::Normal,
+        _ => Nivel::Verboso,
+    }
+}
+
+pub fn silencioso() -> bool {
+    nivel() == Nivel::Silencioso
+}
+
+pub fn verboso() -> bool {
+    nivel() == Nivel::Verboso
+}
+
+/// `--cor`: quando colorir a saida.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ModoCor {
+    /// Cor so em terminal e sem `NO_COLOR`
+    #[default]
+    Auto,
+    #[value(alias = "always")]
+    Sempre,
+    #[value(alias = "never")]
+    Nunca,
+}
+
+/// Decide se um fluxo recebe cor: a flag vence `NO_COLOR`, que vence a deteccao de terminal.
+pub fn decidir_cor(modo: ModoCor, no_color: bool, terminal: bool) -> bool {
+    match modo {
+        ModoCor::Sempre => true,
+        ModoCor::Nunca => false,
+        ModoCor::Auto => !no_color && terminal,
+    }
+}
+
+static COR_STDOUT: AtomicBool = AtomicBool::new(false);
+static COR_STDERR: AtomicBool = AtomicBool::new(false);
+
+pub fn definir_cor(modo: ModoCor) {
+    // Segue no-color.org: so conta quando definida e nao vazia
+    let no_color = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+    COR_STDOUT.store(
+        decidir_cor(modo, no_color, std::io::stdout().is_terminal()),
+        Ordering::Relaxed,
+    );
+    COR_STDERR.store(
+        decidir_cor(modo, no_color, std::io::stderr().is_terminal()),
+        Ordering::Relaxed,
+    );
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Cor {
+    Verde,
+    Amarelo,
+    Vermelho,
+    Esmaecido,
+}
+
+impl Cor {
+    fn codigo(&self) -> &'static str {
+        match self {
+            Cor::Verde => "32",
+            Cor::Amarelo => "33",
+            Cor::Vermelho => "31",
+            Cor::Esmaecido => "2",
+        }
+    }
+}
+
+fn aplicar(cor: Cor, texto: &str, ativa: bool) -> String {
+    if ativa {
+        format!("\x1b[{}m{}\x1b[0m", cor.codigo(), texto)
+    } else {
+        texto.to_string()
+    }
+}
+
+/// Texto para o stdout, colorido se a decisao de `--cor` permitir.
+pub fn pintar(cor: Cor, texto: &str) -> String {
+    aplicar(cor, texto, COR_STDOUT.load(Ordering::Relaxed))
+}
+
+/// Como `pintar`, para mensagens no stderr (avisos e erros).
+pub fn pintar_erro(cor: Cor, texto: &str) -> String {
+    aplicar(cor, texto, COR_STDERR.load(Ordering::Relaxed))
+}
+
+/// Terminais do Windows fora do Windows Terminal costumam nao ter UTF-8 no console.
+pub(crate) fn unicode_suportado() -> bool {
+    !cfg!(windows) || std::env::var_os("WT_SESSION").is_some()
+}
+
+/// Marca de presenca (`✓`/`✗`, ou `[ok]`/`[faltando]` sem unicode), verde ou vermelha.
+pub fn marca(ok: bool) -> String {
+    let texto = match (ok, unicode_suportado()) {
+        (true, true) => "✓",
+        (false, true) => "✗",
+        (true, false) => "[ok]",
+        (false, false) => "[faltando]",
+    };
+    pintar(if ok { Cor::Verde } else { Cor::Vermelho }, texto)
+}
+
+/// Linha de comando de um processo filho, para `detalhe!`.
+pub fn descrever_comando(cmd: &Command) -> String {
+    format!("{:?}", cmd)
+}
+
+/// `println!` para mensagens informativas ("Compilando...", "Criado ..."), suprimidas por `--quiet`.
+macro_rules! progresso {
+    ($($arg:tt)*) => {
+        if !$crate::saida::silencioso() {
+            println!($($arg)*);
+        }
+    };
+}
+
+/// Diagnostico extra de `--verboso`, no stderr para nao misturar com a saida do programa.
+macro_rules! detalhe {
+    ($($arg:tt)*) => {
+        if $crate::saida::verboso() {
+            eprintln!("[verboso] {}", format_args!($($arg)*));
+        }
+    };
+}
+
+pub(crate) use detalhe;
+pub(crate) use progresso;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flag_vence_no_color_e_terminal() {
+        for (no_color, terminal) in [(false, false), (false, true), (true, false), (true, true)] {
+            assert!(decidir_cor(ModoCor::Sempre, no_color, terminal));
+            assert!(!decidir_cor(ModoCor::Nunca, no_color, terminal));
+        }
+    }
+
+    #[test]
+    fn auto_exige_terminal_sem_no_color() {
+        assert!(decidir_cor(ModoCor::Auto, false, true));
+        assert!(!decidir_cor(ModoCor::Auto, true, true));
+        assert!(!decidir_cor(ModoCor::Auto, false, false));
+        assert!(!decidir_cor(ModoCor::Auto, true, false));
+    }
+
+    #[test]
+    fn aplicar_so_envolve_quando_ativa() {
+        assert_eq!(aplicar(Cor::Verde, "OK", false), "OK");
+        assert_eq!(
+            aplicar(Cor::Vermelho, "FALHA", true),
+            "\x1b[31mFALHA\x1b[0m"
+        );
+    }
+}
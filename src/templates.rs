@@ -0,0 +1,574 @@
+//! `pordosol templates`: instala (de uma pasta local ou de um repositorio git), lista e remove
+//! templates do usuario em `PORDOSOL_TEMPLATES_PATH` ou `PORDOSOL_HOME/templates`, de onde
+//! `pordosol new` e `new list` ja os leem.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::Serialize;
+use walkdir::WalkDir;
+
+use crate::explicar::sugestao;
+use crate::novo::{
+    clonar_repositorio, diretorio_templates_usuario, ler_manifesto,
+    localizar_diretorio_templates_com_origem, MANIFESTO_TEMPLATE, PLACEHOLDERS_EMBUTIDOS,
+};
+use crate::saida::{detalhe, progresso};
+
+/// Pastas da origem que nao fazem parte do template.
+const IGNORADOS: &[&str] = &[".git"];
+
+/// Pasta onde os templates do usuario sao instalados (pode ainda nao existir).
+fn diretorio_usuario() -> Result<PathBuf> {
+    diretorio_templates_usuario().ok_or_else(|| {
+        anyhow!(
+            "Nem PORDOSOL_TEMPLATES_PATH nem PORDOSOL_HOME definidos. Aponte um deles para onde instalar templates."
+        )
+    })
+}
+
+/// Normaliza e valida um nome de template: minusculas, como `pordosol new <tipo>` o procura.
+fn validar_nome(nome: &str) -> Result<String> {
+    let nome = nome.trim().to_ascii_lowercase();
+    if nome.is_empty() || nome == "list" || nome.starts_with('.') || nome.contains(['/', '\\']) {
+        bail!("Nome de template invalido: '{}'.", nome);
+    }
+    Ok(nome)
+}
+
+/// Nome do template a partir da pasta de origem.
+fn nome_template(origem: &Path) -> Result<String> {
+    let nome = origem
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    validar_nome(&nome).with_context(|| format!("Origem {}", origem.display()))
+}
+
+/// Verdadeiro se `origem` deve ser clonada em vez de lida como pasta local.
+fn eh_url_git(origem: &str) -> bool {
+    ["https://", "http://", "ssh://", "git://", "file://", "git@"]
+        .iter()
+        .any(|p| origem.starts_with(p))
+        || (origem.ends_with(".git") && !Path::new(origem).is_dir())
+}
+
+/// Nome padrao de um template clonado: ultimo segmento da URL, sem `.git`.
+fn nome_da_url(url: &str) -> String {
+    let ultimo = url
+        .trim_end_matches('/')
+        .rsplit(['/', ':'])
+        .next()
+        .unwrap_or(url);
+    ultimo.strip_suffix(".git").unwrap_or(ultimo).to_string()
+}
+
+/// Arquivos de documentacao que nao fazem de uma pasta um template por si so.
+fn eh_documentacao(nome: &str) -> bool {
+    let nome = nome.to_ascii_uppercase();
+    nome.starts_with("README") || nome.starts_with("LICEN") || nome.starts_with('.')
+}
+
+/// Templates contidos em `origem`: ela propria, quando tem `src/` ou arquivos alem de
+/// README/LICENSE na raiz, ou cada subpasta (um repositorio compartilhado com varios templates).
+fn templates_da_origem(origem: &Path, nome: &str) -> Result<Vec<(String, PathBuf)>> {
+    let entradas: Vec<fs::DirEntry> = fs::read_dir(origem)
+        .with_context(|| format!("Falha ao ler {}", origem.display()))?
+        .filter_map(|e| e.ok())
+        .collect();
+    let unico = origem.join("src").is_dir()
+        || entradas
+            .iter()
+            .any(|e| e.path().is_file() && !eh_documentacao(&e.file_name().to_string_lossy()));
+    if unico {
+        return Ok(vec![(validar_nome(nome)?, origem.to_path_buf())]);
+    }
+
+    let mut templates = entradas
+        .iter()
+        .filter(|e| e.path().is_dir())
+        .filter(|e| !e.file_name().to_string_lossy().starts_with('.'))
+        .map(|e| Ok((nome_template(&e.path())?, e.path())))
+        .collect::<Result<Vec<_>>>()?;
+    templates.sort();
+    if templates.is_empty() {
+        bail!(
+            "{} nao contem nenhum arquivo; nada a instalar.",
+            origem.display()
+        );
+    }
+    Ok(templates)
+}
+
+/// Arquivos da origem (relativos a ela), sem o que esta em `IGNORADOS`.
+fn arquivos_template(origem: &Path) -> Vec<PathBuf> {
+    WalkDir::new(origem)
+        .into_iter()
+        .filter_entry(|e| {
+            e.depth() == 0 || !IGNORADOS.contains(&e.file_name().to_string_lossy().as_ref())
+        })
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().is_file())
+        .filter_map(|e| e.path().strip_prefix(origem).ok().map(Path::to_path_buf))
+        .collect()
+}
+
+/// Copia `origem` para `destino` passando por uma pasta temporaria ao lado dele, para que uma
+/// falha no meio nao deixe um template pela metade (nem apague o anterior com `--forcar`).
+fn copiar_template(origem: &Path, destino: &Path, arquivos: &[PathBuf]) -> Result<()> {
+    let mut temporario = destino.as_os_str().to_owned();
+    temporario.push(".instalando");
+    let temporario = PathBuf::from(temporario);
+    if temporario.exists() {
+        fs::remove_dir_all(&temporario)
+            .with_context(|| format!("Falha ao remover {}", temporario.display()))?;
+    }
+
+    for rel in arquivos {
+        let alvo = temporario.join(rel);
+        if let Some(pasta) = alvo.parent() {
+            fs::create_dir_all(pasta)
+                .with_context(|| format!("Falha ao criar {}", pasta.display()))?;
+        }
+        fs::copy(origem.join(rel), &alvo)
+            .with_context(|| format!("Falha ao copiar {}", origem.join(rel).display()))?;
+        detalhe!("  {}", rel.display());
+    }
+
+    if destino.exists() {
+        fs::remove_dir_all(destino)
+            .with_context(|| format!("Falha ao remover {}", destino.display()))?;
+    }
+    fs::rename(&temporario, destino)
+        .with_context(|| format!("Falha ao instalar em {}", destino.display()))
+}
+
+/// Confere um template antes de copiar: ao menos um arquivo alem do `template.toml`, que,
+/// se presente, precisa ser valido. Devolve os arquivos a copiar.
+fn validar_template(nome: &str, dir: &Path) -> Result<Vec<PathBuf>> {
+    let arquivos = arquivos_template(dir);
+    if !arquivos.iter().any(|a| a != Path::new(MANIFESTO_TEMPLATE)) {
+        bail!(
+            "Template '{}' ({}) nao contem nenhum arquivo; nada a instalar.",
+            nome,
+            dir.display()
+        );
+    }
+    ler_manifesto(dir).with_context(|| format!("Template '{}'", nome))?;
+    Ok(arquivos)
+}
+
+/// Instala os templates de uma pasta local ja resolvida.
+fn instalar_de(origem: &Path, nome: &str, forcar: bool) -> Result<()> {
+    let templates = templates_da_origem(origem, nome)?;
+    let validados = templates
+        .iter()
+        .map(|(nome, dir)| Ok((nome, dir, validar_template(nome, dir)?)))
+        .collect::<Result<Vec<_>>>()?;
+
+    let raiz = diretorio_usuario()?;
+    // Conflitos sao checados antes de copiar qualquer coisa, para nao instalar pela metade
+    if !forcar {
+        if let Some((nome, _, _)) = validados.iter().find(|(n, _, _)| raiz.join(n).exists()) {
+            bail!(
+                "Template '{}' ja existe em {}. Use --forcar para substitui-lo.",
+                nome,
+                raiz.join(nome).display()
+            );
+        }
+    }
+    fs::create_dir_all(&raiz).with_context(|| format!("Falha ao criar {}", raiz.display()))?;
+
+    for (nome, dir, arquivos) in &validados {
+        let destino = raiz.join(nome);
+        copiar_template(dir, &destino, arquivos)?;
+        progresso!(
+            "Template '{}' instalado em {} ({} arquivo(s)).",
+            nome,
+            destino.display(),
+            arquivos.len()
+        );
+    }
+    Ok(())
+}
+
+/// `pordosol templates instalar <origem>`: pasta local ou URL git (`git clone --depth 1`).
+pub fn instalar_cmd(origem: &str, forcar: bool) -> Result<()> {
+    if !eh_url_git(origem) {
+        let caminho = Path::new(origem);
+        if !caminho.is_dir() {
+            bail!("{} nao e um diretorio de template.", caminho.display());
+        }
+        let caminho = caminho
+            .canonicalize()
+            .with_context(|| format!("Falha ao resolver {}", caminho.display()))?;
+        let nome = nome_template(&caminho)?;
+        return instalar_de(&caminho, &nome, forcar);
+    }
+
+    let nome = nome_da_url(origem);
+    let clone =
+        std::env::temp_dir().join(format!("pordosol-template-{}-{}", std::process::id(), nome));
+    if clone.exists() {
+        fs::remove_dir_all(&clone)
+            .with_context(|| format!("Falha ao remover {}", clone.display()))?;
+    }
+    progresso!("Clonando {}...", origem);
+    let resultado =
+        clonar_repositorio(origem, None, &clone).and_then(|_| instalar_de(&clone, &nome, forcar));
+    let _ = fs::remove_dir_all(&clone);
+    resultado
+}
+
+/// `pordosol templates remover <nome>`: so remove da pasta do usuario.
+pub fn remover_cmd(nome: &str) -> Result<()> {
+    let nome = validar_nome(nome)?;
+    let destino = diretorio_usuario()?.join(&nome);
+    if !destino.is_dir() {
+        bail!(
+            "Template '{}' nao esta instalado em {}.",
+            nome,
+            destino.parent().unwrap_or(&destino).display()
+        );
+    }
+    fs::remove_dir_all(&destino)
+        .with_context(|| format!("Falha ao remover {}", destino.display()))?;
+    progresso!("Template '{}' removido de {}.", nome, destino.display());
+    Ok(())
+}
+
+/// Placeholder `{{NOME}}` que nem e embutido nem esta em `variaveis` do manifesto.
+#[derive(Debug, PartialEq, Eq)]
+pub struct PlaceholderOrfao {
+    pub nome: String,
+    /// Placeholder conhecido mais parecido, quando parece erro de digitacao
+    pub sugestao: Option<String>,
+}
+
+/// Procura `{{NOME}}` desconhecidos em `texto`, sem repetir nomes.
+///
+/// So considera nomes no formato de identificador, para nao acusar `{{ }}` de outras
+/// linguagens que o template carregue como texto.
+pub fn placeholders_orfaos(texto: &str, declaradas: &[String]) -> Vec<PlaceholderOrfao> {
+    let conhecidos: Vec<&str> = PLACEHOLDERS_EMBUTIDOS
+        .iter()
+        .copied()
+        .chain(declaradas.iter().map(String::as_str))
+        .collect();
+    let mut orfaos: Vec<PlaceholderOrfao> = Vec::new();
+    let mut resto = texto;
+    while let Some(inicio) = resto.find("{{") {
+        resto = &resto[inicio + 2..];
+        let Some(fim) = resto.find("}}") else {
+            break;
+        };
+        let nome = &resto[..fim];
+        let identificador =
+            !nome.is_empty() && nome.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if identificador && !conhecidos.contains(&nome) && !orfaos.iter().any(|o| o.nome == nome) {
+            let sugestao = sugestao(nome, &conhecidos).map(str::to_string);
+            orfaos.push(PlaceholderOrfao {
+                nome: nome.to_string(),
+                sugestao,
+            });
+        }
+        if identificador {
+            resto = &resto[fim + 2..];
+        }
+    }
+    orfaos
+}
+
+/// Problemas encontrados em um template instalado; vazio quando esta tudo certo.
+///
+/// Confere se a pasta e legivel, se tem arquivos alem do manifesto, se o manifesto e valido
+/// e se nomes e conteudos so usam placeholders embutidos ou declarados em `variaveis`.
+pub fn verificar_template(dir: &Path) -> Vec<String> {
+    if let Err(e) = fs::read_dir(dir) {
+        return vec![format!("pasta ilegivel: {}", e)];
+    }
+    let mut problemas = Vec::new();
+    let declaradas = match ler_manifesto(dir) {
+        Ok(manifesto) => manifesto.map(|m| m.variaveis).unwrap_or_default(),
+        Err(e) => {
+            problemas.push(format!("{} invalido: {:#}", MANIFESTO_TEMPLATE, e));
+            Vec::new()
+        }
+    };
+
+    let arquivos: Vec<PathBuf> = arquivos_template(dir)
+        .into_iter()
+        .filter(|a| a != Path::new(MANIFESTO_TEMPLATE))
+        .collect();
+    if arquivos.is_empty() {
+        problemas.push("nenhum arquivo alem do manifesto".to_string());
+    }
+
+    for rel in &arquivos {
+        let mut orfaos = placeholders_orfaos(&rel.to_string_lossy(), &declaradas);
+        match fs::read(dir.join(rel)) {
+            Ok(bytes) => {
+                if let Ok(texto) = std::str::from_utf8(&bytes) {
+                    for orfao in placeholders_orfaos(texto, &declaradas) {
+                        if !orfaos.contains(&orfao) {
+                            orfaos.push(orfao);
+                        }
+                    }
+                }
+            }
+            Err(e) => problemas.push(format!("{}: ilegivel: {}", rel.display(), e)),
+        }
+        for orfao in orfaos {
+            problemas.push(match orfao.sugestao {
+                Some(s) => format!(
+                    "{}: placeholder desconhecido {{{{{}}}}} (quis dizer {{{{{}}}}}?)",
+                    rel.display(),
+                    orfao.nome,
+                    s
+                ),
+                None => format!(
+                    "{}: placeholder desconhecido {{{{{}}}}} (declare-o em `variaveis` do {})",
+                    rel.display(),
+                    orfao.nome,
+                    MANIFESTO_TEMPLATE
+                ),
+            });
+        }
+    }
+    problemas
+}
+
+#[derive(Debug, Serialize)]
+pub struct TemplateVerificado {
+    pub nome: String,
+    pub problemas: Vec<String>,
+}
+
+/// Retrato da pasta de templates para o `doctor`.
+#[derive(Debug, Serialize)]
+pub struct DiagnosticoTemplates {
+    pub diretorio: Option<PathBuf>,
+    pub origem: Option<&'static str>,
+    /// Problemas da resolucao da pasta em si (ex.: `PORDOSOL_TEMPLATES_PATH` quebrado)
+    pub avisos: Vec<String>,
+    pub templates: Vec<TemplateVerificado>,
+}
+
+impl DiagnosticoTemplates {
+    /// Todos os avisos, gerais e por template, em uma linha cada.
+    pub fn todos_os_avisos(&self) -> Vec<String> {
+        self.avisos
+            .iter()
+            .cloned()
+            .chain(self.templates.iter().flat_map(|t| {
+                t.problemas
+                    .iter()
+                    .map(move |p| format!("template '{}': {}", t.nome, p))
+            }))
+            .collect()
+    }
+}
+
+/// Verifica cada template de `raiz`, em ordem alfabetica.
+fn verificar_pasta(raiz: &Path) -> Result<Vec<TemplateVerificado>> {
+    let mut templates = fs::read_dir(raiz)
+        .with_context(|| format!("Falha ao ler {}", raiz.display()))?
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().is_dir())
+        .map(|e| TemplateVerificado {
+            nome: e.file_name().to_string_lossy().to_string(),
+            problemas: verificar_template(&e.path()),
+        })
+        .collect::<Vec<_>>();
+    templates.sort_by(|a, b| a.nome.cmp(&b.nome));
+    Ok(templates)
+}
+
+/// Qual pasta `new` usaria, de onde ela veio e o estado de cada template nela.
+pub fn diagnosticar_templates() -> DiagnosticoTemplates {
+    let resolvida = localizar_diretorio_templates_com_origem();
+    let mut avisos = Vec::new();
+    if let Some(configurada) = std::env::var_os("PORDOSOL_TEMPLATES_PATH") {
+        let configurada = PathBuf::from(configurada);
+        if !configurada.is_dir() {
+            avisos.push(format!(
+                "PORDOSOL_TEMPLATES_PATH={} nao e um diretorio; {}",
+                configurada.display(),
+                match &resolvida {
+                    Some((_, origem)) => format!("usando {} no lugar", origem),
+                    None => "usando os templates embutidos".to_string(),
+                }
+            ));
+        }
+    }
+
+    let Some((diretorio, origem)) = resolvida else {
+        avisos.push(
+            "nenhuma pasta de templates encontrada; `new` usa os templates embutidos".to_string(),
+        );
+        return DiagnosticoTemplates {
+            diretorio: None,
+            origem: None,
+            avisos,
+            templates: Vec::new(),
+        };
+    };
+    let templates = match verificar_pasta(&diretorio) {
+        Ok(t) => t,
+        Err(e) => {
+            avisos.push(format!("{:#}", e));
+            Vec::new()
+        }
+    };
+    if templates.is_empty() && avisos.is_empty() {
+        avisos.push(format!("{} nao tem nenhum template", diretorio.display()));
+    }
+    DiagnosticoTemplates {
+        diretorio: Some(diretorio),
+        origem: Some(origem),
+        avisos,
+        templates,
+    }
+}
+
+/// `pordosol templates verificar [nome]`: falha se algum template tiver problemas.
+pub fn verificar_cmd(nome: Option<&str>) -> Result<()> {
+    let Some((raiz, origem)) = localizar_diretorio_templates_com_origem() else {
+        bail!("Nenhuma pasta de templates encontrada.");
+    };
+    println!("Templates em {} ({})", raiz.display(), origem);
+    let templates = match nome {
+        Some(nome) => {
+            let nome = validar_nome(nome)?;
+            let dir = raiz.join(&nome);
+            if !dir.is_dir() {
+                bail!("Template '{}' nao encontrado em {}.", nome, raiz.display());
+            }
+            vec![TemplateVerificado {
+                problemas: verificar_template(&dir),
+                nome,
+            }]
+        }
+        None => verificar_pasta(&raiz)?,
+    };
+
+    let mut com_problemas = 0;
+    for t in &templates {
+        if t.problemas.is_empty() {
+            println!("  {}: ok", t.nome);
+            continue;
+        }
+        com_problemas += 1;
+        println!("  {}:", t.nome);
+        for p in &t.problemas {
+            println!("    - {}", p);
+        }
+    }
+    if com_problemas > 0 {
+        bail!(
+            "{} de {} template(s) com problemas.",
+            com_problemas,
+            templates.len()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arquivos_ignoram_git_e_nome_vem_da_pasta() {
+        let temp = tempfile::tempdir().unwrap();
+        let origem = temp.path().join("MeuApp");
+        fs::create_dir_all(origem.join("src")).unwrap();
+        fs::create_dir_all(origem.join(".git/objects")).unwrap();
+        fs::write(origem.join("src/programa.pr"), "// {{PROJECT_NAME}}\n").unwrap();
+        fs::write(origem.join(".git/HEAD"), "ref: refs/heads/main\n").unwrap();
+
+        assert_eq!(
+            arquivos_template(&origem),
+            vec![PathBuf::from("src/programa.pr")]
+        );
+        assert_eq!(nome_template(&origem).unwrap(), "meuapp");
+        assert!(nome_template(&temp.path().join("list")).is_err());
+    }
+
+    #[test]
+    fn origem_com_varias_pastas_vira_varios_templates() {
+        let temp = tempfile::tempdir().unwrap();
+        let repo = temp.path().join("compartilhados");
+        fs::create_dir_all(repo.join("Api/src")).unwrap();
+        fs::create_dir_all(repo.join("cli")).unwrap();
+        fs::create_dir_all(repo.join(".git")).unwrap();
+        fs::write(repo.join("README.md"), "# templates do time\n").unwrap();
+        let nomes = |dir: &Path, nome: &str| -> Vec<String> {
+            templates_da_origem(dir, nome)
+                .unwrap()
+                .into_iter()
+                .map(|(n, _)| n)
+                .collect()
+        };
+        assert_eq!(nomes(&repo, "compartilhados"), ["api", "cli"]);
+
+        // Um arquivo do template na raiz faz dela o proprio template
+        fs::write(repo.join("pordosol.proj.tpl"), "{}\n").unwrap();
+        assert_eq!(nomes(&repo, "compartilhados"), ["compartilhados"]);
+    }
+
+    #[test]
+    fn reconhece_urls_git_e_deriva_o_nome() {
+        assert!(eh_url_git("https://example.com/time/templates.git"));
+        assert!(eh_url_git("git@example.com:time/api.git"));
+        assert!(!eh_url_git("./templates/api"));
+        assert_eq!(nome_da_url("https://example.com/time/api.git"), "api");
+        assert_eq!(nome_da_url("git@example.com:api.git"), "api");
+        assert_eq!(nome_da_url("file:///srv/templates/"), "templates");
+    }
+
+    #[test]
+    fn placeholders_orfaos_sugerem_o_mais_parecido() {
+        let declaradas = vec!["LICENCA".to_string()];
+        let orfaos = placeholders_orfaos(
+            "// {{PROJETC_NAME}} {{LICENCA}} {{NAMESPACE}} {{AUTOR}} {{PROJETC_NAME}} {{ x.y }}",
+            &declaradas,
+        );
+        assert_eq!(
+            orfaos,
+            vec![
+                PlaceholderOrfao {
+                    nome: "PROJETC_NAME".to_string(),
+                    sugestao: Some("PROJECT_NAME".to_string()),
+                },
+                PlaceholderOrfao {
+                    nome: "AUTOR".to_string(),
+                    sugestao: None,
+                },
+            ]
+        );
+        // `{{` solto nao engole o placeholder seguinte
+        assert_eq!(
+            placeholders_orfaos("a {{ b {{TARGTE}}", &[])[0].nome,
+            "TARGTE"
+        );
+    }
+
+    #[test]
+    fn verificar_template_aponta_manifesto_e_placeholders_nos_nomes() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path().join("api");
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::write(dir.join("src/{{NAMESPCE}}.pr.tpl"), "// {{PROJECT_NAME}}\n").unwrap();
+        fs::write(dir.join(MANIFESTO_TEMPLATE), "descricao\n").unwrap();
+
+        let problemas = verificar_template(&dir);
+        assert_eq!(problemas.len(), 2, "{:?}", problemas);
+        assert!(problemas[0].starts_with("template.toml invalido"));
+        assert!(problemas[1].contains("{{NAMESPCE}} (quis dizer {{NAMESPACE}}?)"));
+
+        fs::write(dir.join(MANIFESTO_TEMPLATE), "variaveis = [\"NAMESPCE\"]\n").unwrap();
+        assert!(verificar_template(&dir).is_empty());
+    }
+}
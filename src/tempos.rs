@@ -0,0 +1,118 @@
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+
+use crate::saida::detalhe;
+
+/// Historico de `--timings`, uma linha JSON por build, na pasta de saida base.
+pub const ARQUIVO_TIMINGS: &str = ".timings.jsonl";
+
+/// Mede etapas consecutivas de um build para `--timings`; inativo, nao registra nada.
+pub struct Cronometro {
+    ativo: bool,
+    marca: Instant,
+    etapas: Vec<(String, Duration)>,
+}
+
+impl Cronometro {
+    pub fn iniciar(ativo: bool) -> Self {
+        Self {
+            ativo,
+            marca: Instant::now(),
+            etapas: Vec::new(),
+        }
+    }
+
+    /// Registra o tempo desde a etapa anterior (ou do inicio) sob `nome`.
+    pub fn etapa(&mut self, nome: &str) {
+        let agora = Instant::now();
+        detalhe!("Etapa {}: {} ms", nome, (agora - self.marca).as_millis());
+        if self.ativo {
+            self.etapas.push((nome.to_string(), agora - self.marca));
+        }
+        self.marca = agora;
+    }
+
+    /// Descarta o tempo desde a ultima marca (trabalho que nao pertence a nenhuma etapa).
+    pub fn descartar(&mut self) {
+        self.marca = Instant::now();
+    }
+
+    /// Imprime a tabela de etapas e acrescenta o registro em `<pasta_base>/.timings.jsonl`.
+    ///
+    /// Falha ao gravar o historico so gera aviso: o build ja terminou.
+    pub fn concluir(&self, pasta_base: &Path, comando: &str, target: &str) {
+        if !self.ativo {
+            return;
+        }
+        let total: Duration = self.etapas.iter().map(|(_, d)| *d).sum();
+        let largura = self
+            .etapas
+            .iter()
+            .map(|(n, _)| n.len())
+            .chain(std::iter::once("total".len()))
+            .max()
+            .unwrap_or(0);
+
+        println!("Tempos ({} {}):", comando, target);
+        for (nome, duracao) in &self.etapas {
+            let pct = if total.is_zero() {
+                0.0
+            } else {
+                duracao.as_secs_f64() * 100.0 / total.as_secs_f64()
+            };
+            println!(
+                "  {:<largura$}  {:>8} ms  {:>5.1}%",
+                nome,
+                duracao.as_millis(),
+                pct,
+                largura = largura
+            );
+        }
+        println!(
+            "  {:<largura$}  {:>8} ms",
+            "total",
+            total.as_millis(),
+            largura = largura
+        );
+
+        if let Err(e) = self.gravar(pasta_base, comando, target, total) {
+            eprintln!("Aviso: nao foi possivel registrar os tempos: {:#}", e);
+        }
+    }
+
+    fn gravar(
+        &self,
+        pasta_base: &Path,
+        comando: &str,
+        target: &str,
+        total: Duration,
+    ) -> Result<()> {
+        let registro = serde_json::json!({
+            "timestamp": SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            "comando": comando,
+            "target": target,
+            "etapas": self
+                .etapas
+                .iter()
+                .map(|(nome, d)| serde_json::json!({ "etapa": nome, "ms": d.as_millis() as u64 }))
+                .collect::<Vec<_>>(),
+            "total_ms": total.as_millis() as u64,
+        });
+        std::fs::create_dir_all(pasta_base).ok();
+        let caminho = pasta_base.join(ARQUIVO_TIMINGS);
+        let mut arquivo = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&caminho)
+            .with_context(|| format!("Falha ao abrir {}", caminho.display()))?;
+        writeln!(arquivo, "{}", registro)
+            .with_context(|| format!("Falha ao escrever {}", caminho.display()))
+    }
+}
@@ -0,0 +1,304 @@
+use std::ffi::OsStr;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use anyhow::{anyhow, bail, Context, Result};
+use walkdir::WalkDir;
+
+use crate::codificacao::{decodificar, Codificacao};
+use crate::comparacao::{capturar, diff_unificado, normalizar_saida};
+use crate::construir::{nome_membro, Perfil};
+use crate::erros::ErroCli;
+use crate::saida::{descrever_comando, detalhe, progresso};
+use crate::titulo::{Estado, Titulo};
+use crate::toolchain::{localizar_binarios, localizar_raiz};
+use crate::trava;
+
+/// Pasta dos testes, relativa a raiz do projeto.
+const PASTA_TESTES: &str = "testes";
+
+/// Um arquivo `testes/**/*.pr`, com os arquivos auxiliares ao lado dele.
+///
+/// `<stem>.saida` e a saida esperada (golden) e `<stem>.entrada` vai para o stdin.
+/// Tags vem de um comentario `// tags: a, b` nas primeiras linhas.
+#[derive(Clone, Debug)]
+pub struct CasoTeste {
+    pub nome: String,
+    pub arquivo: PathBuf,
+    pub tags: Vec<String>,
+    pub golden: Option<PathBuf>,
+    pub entrada: Option<PathBuf>,
+}
+
+/// Descobre os testes em ordem estavel; o indice de `--lista`/`--indice` e a posicao + 1.
+///
+/// E a mesma funcao usada pela execucao, entao a numeracao nao diverge do que roda.
+pub fn descobrir_testes(raiz: &Path) -> Vec<CasoTeste> {
+    let pasta = raiz.join(PASTA_TESTES);
+    let mut arquivos: Vec<PathBuf> = WalkDir::new(&pasta)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .map(|e| e.path().to_path_buf())
+        .filter(|p| p.is_file() && p.extension() == Some(OsStr::new("pr")))
+        .collect();
+    arquivos.sort();
+
+    arquivos
+        .into_iter()
+        .map(|arquivo| {
+            let nome = arquivo
+                .file_stem()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string();
+            let irmao = |ext: &str| Some(arquivo.with_extension(ext)).filter(|p| p.is_file());
+            CasoTeste {
+                tags: ler_tags(&arquivo),
+                golden: irmao("saida"),
+                entrada: irmao("entrada"),
+                nome,
+                arquivo,
+            }
+        })
+        .collect()
+}
+
+fn ler_tags(arquivo: &Path) -> Vec<String> {
+    let Ok(texto) = fs::read_to_string(arquivo) else {
+        return Vec::new();
+    };
+    texto
+        .lines()
+        .take(5)
+        .find_map(|l| l.trim().strip_prefix("//")?.trim().strip_prefix("tags:"))
+        .map(|t| {
+            t.split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Escolhe um teste pelo nome: stem exato primeiro, depois prefixo unico.
+fn selecionar_por_nome<'a>(casos: &'a [CasoTeste], nome: &str) -> Result<&'a CasoTeste> {
+    if let Some(caso) = casos.iter().find(|c| c.nome == nome) {
+        return Ok(caso);
+    }
+    let candidatos: Vec<&CasoTeste> = casos.iter().filter(|c| c.nome.starts_with(nome)).collect();
+    match candidatos.as_slice() {
+        [unico] => Ok(unico),
+        [] => bail!(
+            "Nenhum teste corresponde a '{}'. Disponiveis: {}",
+            nome,
+            listar_nomes(casos.iter())
+        ),
+        varios => bail!(
+            "'{}' e ambiguo; candidatos: {}",
+            nome,
+            listar_nomes(varios.iter().copied())
+        ),
+    }
+}
+
+fn listar_nomes<'a>(casos: impl Iterator<Item = &'a CasoTeste>) -> String {
+    let nomes: Vec<&str> = casos.map(|c| c.nome.as_str()).collect();
+    if nomes.is_empty() {
+        "(nenhum)".to_string()
+    } else {
+        nomes.join(", ")
+    }
+}
+
+/// Opcoes do comando `test`.
+pub struct OpcoesTeste<'a> {
+    pub nome: Option<&'a str>,
+    pub indice: Option<usize>,
+    pub lista: bool,
+}
+
+pub fn test_cmd(caminho: &Path, opcoes: &OpcoesTeste) -> Result<()> {
+    let raiz = localizar_raiz(caminho);
+    let casos = descobrir_testes(&raiz);
+
+    if opcoes.lista {
+        imprimir_lista(&raiz, &casos);
+        return Ok(());
+    }
+
+    if casos.is_empty() {
+        bail!(
+            "Nenhum teste encontrado em {}",
+            raiz.join(PASTA_TESTES).display()
+        );
+    }
+    let selecionados: Vec<&CasoTeste> = match (opcoes.nome, opcoes.indice) {
+        (Some(nome), _) => vec![selecionar_por_nome(&casos, nome)?],
+        (None, Some(indice)) => vec![indice
+            .checked_sub(1)
+            .and_then(|i| casos.get(i))
+            .ok_or_else(|| {
+                anyhow!(
+                    "Indice {} fora do intervalo (1..={}). Use --lista para ver os testes.",
+                    indice,
+                    casos.len()
+                )
+            })?],
+        (None, None) => casos.iter().collect(),
+    };
+
+    let (compilador, interpretador) = localizar_binarios(&raiz).map_err(ErroCli::from)?;
+    let _trava = trava::adquirir(&raiz)?;
+    let saida_dir = Perfil::Dev.saida_dir(&raiz).join(PASTA_TESTES);
+    fs::create_dir_all(&saida_dir)
+        .with_context(|| format!("Falha ao criar {}", saida_dir.display()))?;
+
+    let mut titulo = Titulo::iniciar(&nome_membro(&raiz), "test");
+    titulo.estado(Estado::Executando);
+    let mut falhas = Vec::new();
+    for caso in &selecionados {
+        match executar_caso(caso, &compilador, &interpretador, &saida_dir) {
+            Ok(()) => progresso!("ok    {}", caso.nome),
+            Err(e) => {
+                println!("FALHA {}: {:#}", caso.nome, e);
+                falhas.push(caso.nome.as_str());
+            }
+        }
+    }
+
+    progresso!(
+        "{} teste(s): {} ok, {} falha(s)",
+        selecionados.len(),
+        selecionados.len() - falhas.len(),
+        falhas.len()
+    );
+    crate::resultado::registrar(
+        "testes",
+        serde_json::json!({
+            "total": selecionados.len(),
+            "ok": selecionados.len() - falhas.len(),
+            "falhas": falhas,
+        }),
+    );
+    if !falhas.is_empty() {
+        bail!("Testes com falha: {}", falhas.join(", "));
+    }
+    Ok(())
+}
+
+fn imprimir_lista(raiz: &Path, casos: &[CasoTeste]) {
+    if casos.is_empty() {
+        println!(
+            "Nenhum teste encontrado em {}",
+            raiz.join(PASTA_TESTES).display()
+        );
+        return;
+    }
+    for (i, caso) in casos.iter().enumerate() {
+        let tags = if caso.tags.is_empty() {
+            "-".to_string()
+        } else {
+            caso.tags.join(",")
+        };
+        println!(
+            "{:>3}. {}  tags: {}  golden: {}  entrada: {}",
+            i + 1,
+            caso.nome,
+            tags,
+            if caso.golden.is_some() { "sim" } else { "nao" },
+            if caso.entrada.is_some() { "sim" } else { "nao" }
+        );
+    }
+}
+
+fn executar_caso(
+    caso: &CasoTeste,
+    compilador: &Path,
+    interpretador: &Path,
+    saida_dir: &Path,
+) -> Result<()> {
+    let mut cmd = Command::new(compilador);
+    cmd.current_dir(saida_dir)
+        .arg("--target=bytecode")
+        .arg(&caso.arquivo)
+        .stdin(Stdio::null());
+    detalhe!("Executando: {}", descrever_comando(&cmd));
+    let status = cmd.status().context("Falha ao executar o compilador")?;
+    if !status.success() {
+        bail!("compilacao falhou (status {})", status);
+    }
+
+    let pbc = saida_dir.join(format!("{}.pbc", caso.nome));
+    let entrada = caso
+        .entrada
+        .as_ref()
+        .map(|e| fs::read(e).with_context(|| format!("Falha ao ler {}", e.display())))
+        .transpose()?;
+    let mut cmd = Command::new(interpretador);
+    cmd.arg(&pbc);
+    detalhe!("Executando: {}", descrever_comando(&cmd));
+    let out = capturar(cmd, entrada.as_deref())?;
+    if !out.status.success() {
+        bail!("execucao falhou (status {})", out.status);
+    }
+
+    if let Some(golden) = &caso.golden {
+        let esperado =
+            fs::read(golden).with_context(|| format!("Falha ao ler {}", golden.display()))?;
+        let esperado = normalizar_saida(&decodificar(&esperado, Codificacao::Auto));
+        let obtido = normalizar_saida(&decodificar(&out.stdout, Codificacao::Auto));
+        if let Some(diff) =
+            diff_unificado(&esperado, &obtido, &golden.display().to_string(), "obtido")
+        {
+            bail!("saida difere de {}\n{}", golden.display(), diff.trim_end());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn caso(nome: &str) -> CasoTeste {
+        CasoTeste {
+            nome: nome.to_string(),
+            arquivo: PathBuf::from(format!("testes/{}.pr", nome)),
+            tags: Vec::new(),
+            golden: None,
+            entrada: None,
+        }
+    }
+
+    #[test]
+    fn selecao_prefere_nome_exato_depois_prefixo_unico() {
+        let casos = vec![caso("soma"), caso("soma_grande"), caso("subtracao")];
+        assert_eq!(selecionar_por_nome(&casos, "soma").unwrap().nome, "soma");
+        assert_eq!(
+            selecionar_por_nome(&casos, "sub").unwrap().nome,
+            "subtracao"
+        );
+        let erro = selecionar_por_nome(&casos, "so").unwrap_err().to_string();
+        assert!(erro.contains("ambiguo"), "{}", erro);
+        assert!(erro.contains("soma, soma_grande"), "{}", erro);
+        let erro = selecionar_por_nome(&casos, "div").unwrap_err().to_string();
+        assert!(
+            erro.contains("Disponiveis: soma, soma_grande, subtracao"),
+            "{}",
+            erro
+        );
+    }
+
+    #[test]
+    fn tags_vem_do_comentario_inicial() {
+        let dir = tempfile::tempdir().unwrap();
+        let arquivo = dir.path().join("t.pr");
+        fs::write(
+            &arquivo,
+            "// teste de io\n// tags: rapido, io\nfuncao f() {}\n",
+        )
+        .unwrap();
+        assert_eq!(ler_tags(&arquivo), ["rapido", "io"]);
+    }
+}
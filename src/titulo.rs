@@ -0,0 +1,284 @@
+use std::io::{self, IsTerminal, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Once;
+
+use crate::saida;
+
+/// Empilha o titulo atual do terminal (XTWINOPS 22): e o unico jeito portavel de devolve-lo.
+const SALVAR: &str = "\x1b[22;0t";
+/// Desempilha o titulo salvo por [`SALVAR`].
+const RESTAURAR: &str = "\x1b[23;0t";
+
+/// Ha um titulo empilhado por [`Titulo::iniciar`] ainda nao restaurado.
+///
+/// Os ganchos de sinal e de panico consultam isto em vez do guard, que pode nao chegar a ser
+/// destruido.
+static ATIVO: AtomicBool = AtomicBool::new(false);
+static GANCHOS: Once = Once::new();
+
+/// Fase de um comando longo mostrada no titulo.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Estado {
+    Compilando,
+    Executando,
+    Falhou,
+    AguardandoMudancas,
+}
+
+impl Estado {
+    fn texto(self) -> &'static str {
+        match self {
+            Estado::Compilando => "compilando",
+            Estado::Executando => "executando",
+            Estado::Falhou => "falhou",
+            Estado::AguardandoMudancas => "aguardando mudancas",
+        }
+    }
+}
+
+/// `pordosol: <nome> [<comando>] — <estado>`.
+pub fn texto_titulo(nome: &str, comando: &str, estado: Estado) -> String {
+    let separador = if saida::unicode_suportado() {
+        "—"
+    } else {
+        "-"
+    };
+    format!(
+        "pordosol: {} [{}] {} {}",
+        nome,
+        comando,
+        separador,
+        estado.texto()
+    )
+}
+
+/// OSC 0 com o texto; caracteres de controle sairiam da sequencia e sao descartados.
+pub fn sequencia_titulo(texto: &str) -> String {
+    let limpo: String = texto.chars().filter(|c| !c.is_control()).collect();
+    format!("\x1b]0;{}\x07", limpo)
+}
+
+/// Terminais que ignoram ou imprimem a sequencia em vez de aplica-la.
+fn terminal_suportado(term: Option<&str>, dentro_do_emacs: bool) -> bool {
+    if dentro_do_emacs {
+        return false;
+    }
+    if cfg!(windows) {
+        // O console classico so entende sequencias VT dentro do Windows Terminal
+        return std::env::var_os("WT_SESSION").is_some();
+    }
+    !matches!(
+        term,
+        None | Some("" | "dumb" | "linux" | "cons25" | "emacs")
+    )
+}
+
+/// Decide se o titulo e usado: so em terminal, fora do `--quiet`, sem `PORDOSOL_SEM_TITULO`.
+fn decidir(terminal: bool, silencioso: bool, sem_titulo: bool, suportado: bool) -> bool {
+    terminal && !silencioso && !sem_titulo && suportado
+}
+
+fn habilitado() -> bool {
+    let term = std::env::var("TERM").ok();
+    decidir(
+        io::stderr().is_terminal(),
+        saida::silencioso(),
+        // Como NO_COLOR: so conta quando definida e nao vazia
+        std::env::var_os("PORDOSOL_SEM_TITULO").is_some_and(|v| !v.is_empty()),
+        terminal_suportado(term.as_deref(), std::env::var_os("INSIDE_EMACS").is_some()),
+    )
+}
+
+/// Titulo do terminal durante `run`, `--watch` e `test`; o original volta ao destruir o guard.
+///
+/// Todo comando longo deve passar por aqui: a restauracao tambem acontece em panico,
+/// Ctrl+C e `process::exit` (via [`restaurar`]), o que so funciona com um unico escritor.
+pub struct Titulo<W: Write = io::Stderr> {
+    /// `None` quando desativado: as chamadas viram no-op
+    saida: Option<W>,
+    nome: String,
+    comando: &'static str,
+    /// Guard do processo, coordenado com os ganchos por [`ATIVO`]
+    global: bool,
+}
+
+impl Titulo {
+    /// Empilha o titulo atual e mostra o do comando; desativado fora das condicoes de [`decidir`].
+    pub fn iniciar(nome: &str, comando: &'static str) -> Titulo {
+        if !habilitado() || ATIVO.load(Ordering::SeqCst) {
+            return Titulo {
+                saida: None,
+                nome: nome.to_string(),
+                comando,
+                global: false,
+            };
+        }
+        instalar_ganchos();
+        ATIVO.store(true, Ordering::SeqCst);
+        let mut titulo = Titulo::com_saida(io::stderr(), nome, comando);
+        titulo.global = true;
+        titulo
+    }
+}
+
+impl<W: Write> Titulo<W> {
+    fn com_saida(mut saida: W, nome: &str, comando: &'static str) -> Titulo<W> {
+        saida.write_all(SALVAR.as_bytes()).ok();
+        saida.flush().ok();
+        Titulo {
+            saida: Some(saida),
+            nome: nome.to_string(),
+            comando,
+            global: false,
+        }
+    }
+
+    pub fn estado(&mut self, estado: Estado) {
+        if let Some(saida) = self.saida.as_mut() {
+            let texto = texto_titulo(&self.nome, self.comando, estado);
+            saida.write_all(sequencia_titulo(&texto).as_bytes()).ok();
+            saida.flush().ok();
+        }
+    }
+}
+
+impl<W: Write> Drop for Titulo<W> {
+    fn drop(&mut self) {
+        let Some(saida) = self.saida.as_mut() else {
+            return;
+        };
+        // Um gancho de sinal ou de panico pode ter restaurado antes
+        if !self.global || ATIVO.swap(false, Ordering::SeqCst) {
+            saida.write_all(RESTAURAR.as_bytes()).ok();
+            saida.flush().ok();
+        }
+    }
+}
+
+/// Devolve o titulo original se ainda houver um empilhado (antes de `process::exit`).
+pub fn restaurar() {
+    if ATIVO.swap(false, Ordering::SeqCst) {
+        let mut stderr = io::stderr();
+        stderr.write_all(RESTAURAR.as_bytes()).ok();
+        stderr.flush().ok();
+    }
+}
+
+fn instalar_ganchos() {
+    GANCHOS.call_once(|| {
+        let anterior = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            restaurar();
+            anterior(info);
+        }));
+        instalar_ganchos_sinal();
+    });
+}
+
+#[cfg(unix)]
+fn instalar_ganchos_sinal() {
+    extern "C" fn ao_receber(sinal: libc::c_int) {
+        // So chamadas seguras em tratador de sinal: swap atomico, write, signal e raise
+        if ATIVO.swap(false, Ordering::SeqCst) {
+            // SAFETY: escreve um buffer estatico no fd 2, que continua aberto.
+            unsafe {
+                libc::write(2, RESTAURAR.as_ptr().cast(), RESTAURAR.len());
+            }
+        }
+        // SAFETY: volta ao tratamento padrao e reenvia o sinal, para o processo terminar como
+        // terminaria sem o gancho (status 128 + sinal para o shell).
+        unsafe {
+            libc::signal(sinal, libc::SIG_DFL);
+            libc::raise(sinal);
+        }
+    }
+    for sinal in [libc::SIGINT, libc::SIGTERM, libc::SIGHUP] {
+        // SAFETY: `ao_receber` so usa funcoes seguras em tratador de sinal.
+        unsafe {
+            libc::signal(
+                sinal,
+                ao_receber as extern "C" fn(libc::c_int) as libc::sighandler_t,
+            );
+        }
+    }
+}
+
+#[cfg(windows)]
+fn instalar_ganchos_sinal() {
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn SetConsoleCtrlHandler(
+            rotina: Option<unsafe extern "system" fn(u32) -> i32>,
+            adicionar: i32,
+        ) -> i32;
+    }
+    unsafe extern "system" fn ao_receber(_evento: u32) -> i32 {
+        restaurar();
+        // Nao tratado: o Windows segue para o tratador padrao, que encerra o processo
+        0
+    }
+    // SAFETY: registra uma rotina com a assinatura de HandlerRoutine.
+    unsafe {
+        SetConsoleCtrlHandler(Some(ao_receber), 1);
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+fn instalar_ganchos_sinal() {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sequencia_usa_osc_0_e_descarta_controles() {
+        assert_eq!(
+            sequencia_titulo("pordosol: app"),
+            "\x1b]0;pordosol: app\x07"
+        );
+        assert_eq!(sequencia_titulo("a\x07b\x1b]0;c\nd"), "\x1b]0;ab]0;cd\x07");
+        let texto = texto_titulo("app", "run --watch", Estado::AguardandoMudancas);
+        assert!(
+            texto.starts_with("pordosol: app [run --watch] "),
+            "{}",
+            texto
+        );
+        assert!(texto.ends_with(" aguardando mudancas"), "{}", texto);
+    }
+
+    #[test]
+    fn guard_empilha_atualiza_e_restaura_ao_destruir() {
+        let mut saida = Vec::new();
+        {
+            let mut titulo = Titulo::com_saida(&mut saida, "app", "test");
+            titulo.estado(Estado::Compilando);
+            titulo.estado(Estado::Falhou);
+        }
+        let escrito = String::from_utf8(saida).unwrap();
+        assert!(escrito.starts_with(SALVAR), "{:?}", escrito);
+        assert!(escrito.ends_with(RESTAURAR), "{:?}", escrito);
+        let compilando = escrito.find("compilando").unwrap();
+        let falhou = escrito.find("falhou").unwrap();
+        assert!(compilando < falhou, "{:?}", escrito);
+        assert_eq!(escrito.matches(RESTAURAR).count(), 1);
+    }
+
+    #[test]
+    fn decisao_exige_terminal_sem_quiet_nem_opt_out() {
+        assert!(decidir(true, false, false, true));
+        assert!(!decidir(false, false, false, true));
+        assert!(!decidir(true, true, false, true));
+        assert!(!decidir(true, false, true, true));
+        assert!(!decidir(true, false, false, false));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn terminais_sem_suporte_a_titulo() {
+        assert!(terminal_suportado(Some("xterm-256color"), false));
+        assert!(!terminal_suportado(Some("xterm-256color"), true));
+        for term in [None, Some(""), Some("dumb"), Some("linux")] {
+            assert!(!terminal_suportado(term, false), "{:?}", term);
+        }
+    }
+}
@@ -2,19 +2,141 @@ use std::ffi::OsStr;
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
 
+use anyhow::{bail, Result};
 use path_absolutize::Absolutize;
+use serde::Serialize;
 use walkdir::WalkDir;
 
-#[derive(Clone, Debug)]
+use crate::duplicados::remover_links_duplicados;
+use crate::erros::ErroCli;
+use crate::exclusao::{esta_excluido, padroes_exclusao};
+use crate::saida::{detalhe, verboso};
+use crate::versao::{verificar_compatibilidade, COMPILADOR_MAX, COMPILADOR_MIN};
+
+/// Caminhos de `--compiler`/`--interpreter`: valem apenas para esta invocacao
+/// e tem precedencia sobre variaveis de ambiente e demais candidatos.
+#[derive(Debug, Default)]
+struct OverridesToolchain {
+    compilador: Option<PathBuf>,
+    interpretador: Option<PathBuf>,
+}
+
+static OVERRIDES: OnceLock<OverridesToolchain> = OnceLock::new();
+
+/// Registra os overrides da linha de comando, validando que existem e sao executaveis.
+pub fn definir_overrides(compilador: Option<&Path>, interpretador: Option<&Path>) -> Result<()> {
+    let validar = |flag: &str, caminho: Option<&Path>| -> Result<Option<PathBuf>> {
+        let Some(caminho) = caminho else {
+            return Ok(None);
+        };
+        let abs = caminho.absolutize()?.to_path_buf();
+        if !abs.is_file() {
+            return Err(ErroCli::ToolchainAusente(format!(
+                "{} {}: arquivo nao encontrado",
+                flag,
+                abs.display()
+            ))
+            .into());
+        }
+        if !eh_executavel(&abs) {
+            return Err(ErroCli::ToolchainAusente(format!(
+                "{} {}: arquivo nao e executavel",
+                flag,
+                abs.display()
+            ))
+            .into());
+        }
+        Ok(Some(abs))
+    };
+    let overrides = OverridesToolchain {
+        compilador: validar("--compiler", compilador)?,
+        interpretador: validar("--interpreter", interpretador)?,
+    };
+    OVERRIDES.set(overrides).ok();
+    Ok(())
+}
+
+fn override_para(nome_base: &str) -> Option<(PathBuf, &'static str)> {
+    let overrides = OVERRIDES.get()?;
+    match nome_base {
+        "compilador" => overrides.compilador.clone().map(|p| (p, "flag:--compiler")),
+        "interpretador" => overrides
+            .interpretador
+            .clone()
+            .map(|p| (p, "flag:--interpreter")),
+        _ => None,
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
 pub struct DiagnosticoFerramenta {
     pub nome: String,
     pub caminho: PathBuf,
     pub origem: String,
     pub encontrado: bool,
+    /// Versao reportada pelo binario (apenas compilador/interpretador encontrados)
+    pub versao: Option<String>,
+    /// Locais tentados, na ordem (apenas quando nao encontrado)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tentativas: Vec<CandidatoFerramenta>,
 }
 
+/// Ferramenta da toolchain que nenhum candidato resolveu, com todos os locais tentados.
 #[derive(Clone, Debug)]
+pub struct FerramentaNaoEncontrada {
+    pub nome: String,
+    pub variavel_env: &'static str,
+    pub tentativas: Vec<CandidatoFerramenta>,
+}
+
+impl FerramentaNaoEncontrada {
+    fn de(item: DiagnosticoFerramenta, variavel_env: &'static str) -> Self {
+        FerramentaNaoEncontrada {
+            nome: item.nome,
+            variavel_env,
+            tentativas: item.tentativas,
+        }
+    }
+}
+
+/// Linhas `- caminho (origem)` de cada local tentado.
+pub fn trilha_busca(tentativas: &[CandidatoFerramenta]) -> Vec<String> {
+    tentativas
+        .iter()
+        .map(|c| format!("- {} ({})", c.caminho.display(), c.origem))
+        .collect()
+}
+
+impl std::fmt::Display for FerramentaNaoEncontrada {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut nome = self.nome.clone();
+        if let Some(c) = nome.get_mut(0..1) {
+            c.make_ascii_uppercase();
+        }
+        writeln!(f, "{} nao encontrado. Locais tentados:", nome)?;
+        for linha in trilha_busca(&self.tentativas) {
+            writeln!(f, "  {}", linha)?;
+        }
+        write!(
+            f,
+            "Rode `pordosol doctor` e configure {}/PORDOSOL_HOME.",
+            self.variavel_env
+        )
+    }
+}
+
+impl std::error::Error for FerramentaNaoEncontrada {}
+
+impl From<FerramentaNaoEncontrada> for ErroCli {
+    fn from(e: FerramentaNaoEncontrada) -> Self {
+        ErroCli::ToolchainAusente(e.to_string())
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
 pub struct DiagnosticoToolchain {
     pub compilador: DiagnosticoFerramenta,
     pub interpretador: DiagnosticoFerramenta,
@@ -27,7 +149,187 @@ impl DiagnosticoToolchain {
     }
 }
 
+/// Verificacao complementar do ambiente (permissoes, pastas e variaveis) exibida no doctor.
+#[derive(Clone, Debug, Serialize)]
+pub struct VerificacaoAmbiente {
+    pub nome: String,
+    pub caminho: PathBuf,
+    pub origem: String,
+    pub ok: bool,
+    pub dica: String,
+}
+
+/// Checa se os binarios resolvidos sao executaveis, se a pasta de saida e gravavel
+/// e se `PORDOSOL_HOME` (quando definido) aponta para um diretorio existente.
+pub fn diagnosticar_ambiente(
+    diag: &DiagnosticoToolchain,
+    saida_dir: &Path,
+) -> Vec<VerificacaoAmbiente> {
+    let mut out = Vec::new();
+
+    for (item, var) in [
+        (&diag.compilador, "PORDOSOL_COMPILADOR_PATH"),
+        (&diag.interpretador, "PORDOSOL_INTERPRETADOR_PATH"),
+    ] {
+        if !item.encontrado {
+            continue;
+        }
+        let dica = if cfg!(windows) {
+            format!(
+                "Use um executavel .exe/.cmd/.bat para o {} ou ajuste {}.",
+                item.nome, var
+            )
+        } else {
+            format!(
+                "Rode `chmod +x {}` ou ajuste {}.",
+                item.caminho.display(),
+                var
+            )
+        };
+        out.push(VerificacaoAmbiente {
+            nome: format!("{} executavel", item.nome),
+            caminho: item.caminho.clone(),
+            origem: item.origem.clone(),
+            ok: eh_executavel(&item.caminho),
+            dica,
+        });
+    }
+
+    if let (true, Some(versao)) = (diag.compilador.encontrado, &diag.compilador.versao) {
+        let compat = verificar_compatibilidade(versao);
+        out.push(VerificacaoAmbiente {
+            nome: "compilador compativel".to_string(),
+            caminho: diag.compilador.caminho.clone(),
+            origem: compat.descricao(versao),
+            ok: compat.ok(),
+            dica: format!(
+                "Instale um compilador na faixa >={}, <{} ou atualize a CLI.",
+                COMPILADOR_MIN, COMPILADOR_MAX
+            ),
+        });
+    }
+
+    let (ok_saida, origem_saida) = pasta_gravavel(saida_dir);
+    out.push(VerificacaoAmbiente {
+        nome: "pasta de saida gravavel".to_string(),
+        caminho: saida_dir.to_path_buf(),
+        origem: origem_saida,
+        ok: ok_saida,
+        dica: format!(
+            "Verifique as permissoes de {} ou use `--saida` para outra pasta.",
+            saida_dir.display()
+        ),
+    });
+
+    let home = ler_env_path("PORDOSOL_HOME");
+    out.push(VerificacaoAmbiente {
+        nome: "PORDOSOL_HOME".to_string(),
+        caminho: home.clone().unwrap_or_default(),
+        origem: if home.is_some() {
+            "env:PORDOSOL_HOME".to_string()
+        } else {
+            "env:PORDOSOL_HOME (nao definido)".to_string()
+        },
+        ok: home.as_ref().map(|h| h.is_dir()).unwrap_or(true),
+        dica: "Aponte PORDOSOL_HOME para a pasta de instalacao existente ou remova a variavel."
+            .to_string(),
+    });
+
+    out
+}
+
+#[cfg(unix)]
+pub fn eh_executavel(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    path.metadata()
+        .map(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
+
+#[cfg(not(unix))]
+pub fn eh_executavel(path: &Path) -> bool {
+    path.is_file()
+        && path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| {
+                matches!(
+                    e.to_ascii_lowercase().as_str(),
+                    "exe" | "cmd" | "bat" | "com"
+                )
+            })
+            .unwrap_or(false)
+}
+
+/// Testa escrita criando e removendo um arquivo na pasta (ou no primeiro ancestral existente).
+fn pasta_gravavel(dir: &Path) -> (bool, String) {
+    let mut alvo = dir.to_path_buf();
+    let mut origem = "existente".to_string();
+    while !alvo.exists() {
+        origem = "sera criada".to_string();
+        match alvo.parent() {
+            Some(p) => alvo = p.to_path_buf(),
+            None => return (false, "sem ancestral existente".to_string()),
+        }
+    }
+    if !alvo.is_dir() {
+        return (false, format!("{} nao e diretorio", alvo.display()));
+    }
+
+    let sonda = alvo.join(format!(".pordosol-sonda-{}", std::process::id()));
+    let ok = fs::write(&sonda, b"").is_ok();
+    fs::remove_file(&sonda).ok();
+    (ok, origem)
+}
+
+/// Arquivos que marcam a raiz de um projeto, em ordem de preferencia.
+const ARQUIVOS_PROJETO: &[&str] = &["pordosol.proj", "pordosol.toml"];
+
+/// Niveis subidos pela heuristica de `src/` quando `PORDOSOL_NIVEIS_RAIZ` nao e definido.
+const NIVEIS_RAIZ_PADRAO: usize = 8;
+
+/// O que identificou a raiz encontrada por [`localizar_raiz_com_marcador`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MarcadorRaiz {
+    /// `pordosol.proj`, `pordosol.toml` ou `pordosol.workspace`
+    Arquivo(&'static str),
+    /// Nenhum arquivo de projeto: pasta mais proxima com `src/`
+    PastaSrc,
+    /// Nada encontrado: a propria pasta pedida
+    Nenhum,
+}
+
+impl std::fmt::Display for MarcadorRaiz {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MarcadorRaiz::Arquivo(nome) => write!(f, "{}", nome),
+            MarcadorRaiz::PastaSrc => write!(f, "pasta src/"),
+            MarcadorRaiz::Nenhum => write!(f, "nenhum (pasta informada)"),
+        }
+    }
+}
+
+/// Limite de niveis da heuristica de `src/`: `PORDOSOL_NIVEIS_RAIZ` ou o padrao.
+fn niveis_raiz() -> usize {
+    std::env::var("PORDOSOL_NIVEIS_RAIZ")
+        .ok()
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(NIVEIS_RAIZ_PADRAO)
+}
+
+/// Raiz do projeto de `caminho`; ver [`localizar_raiz_com_marcador`].
 pub fn localizar_raiz(caminho: &Path) -> PathBuf {
+    localizar_raiz_com_marcador(caminho).0
+}
+
+/// Raiz do projeto de `caminho` e o marcador que a identificou: a pasta mais proxima com
+/// `pordosol.proj` (ou `pordosol.toml`) ou `pordosol.workspace` ou, sem nenhuma, a mais
+/// proxima com `src/` (ate `PORDOSOL_NIVEIS_RAIZ` niveis).
+///
+/// O arquivo de projeto vem primeiro para que um `src/` aninhado, ou o de um projeto de
+/// outra linguagem acima (workspace cargo, monorepo node), nao seja tomado pela raiz.
+/// Para um arquivo, a busca comeca na pasta dele.
+pub fn localizar_raiz_com_marcador(caminho: &Path) -> (PathBuf, MarcadorRaiz) {
     let mut p = caminho.absolutize().unwrap().to_path_buf();
     if p.is_file() {
         if let Some(parent) = p.parent() {
@@ -35,49 +337,285 @@ pub fn localizar_raiz(caminho: &Path) -> PathBuf {
         }
     }
 
-    for _ in 0..5 {
-        if p.join("src").is_dir() {
-            return p;
+    for a in p.ancestors() {
+        if let Some(nome) = ARQUIVOS_PROJETO.iter().find(|n| a.join(n).is_file()) {
+            return (a.to_path_buf(), MarcadorRaiz::Arquivo(nome));
         }
-        if let Some(par) = p.parent() {
-            p = par.to_path_buf();
-        } else {
-            break;
+        // Dentro de um membro, o pordosol.proj dele ja respondeu acima
+        if a.join(crate::workspace::ARQUIVO_WORKSPACE).is_file() {
+            return (
+                a.to_path_buf(),
+                MarcadorRaiz::Arquivo(crate::workspace::ARQUIVO_WORKSPACE),
+            );
         }
     }
-    caminho.absolutize().unwrap().to_path_buf()
+
+    if let Some(raiz) = p
+        .ancestors()
+        .take(niveis_raiz())
+        .find(|a| a.join("src").is_dir())
+    {
+        return (raiz.to_path_buf(), MarcadorRaiz::PastaSrc);
+    }
+    (p, MarcadorRaiz::Nenhum)
 }
 
 pub fn listar_prs(raiz: &Path) -> Vec<PathBuf> {
+    listar_prs_com_excluidos(raiz).0
+}
+
+/// Campos do pordosol.proj que apontam o arquivo principal, em ordem de preferencia.
+const CAMPOS_ENTRADA: &[&str] = &["entrada", "main"];
+
+/// Arquivo principal declarado no pordosol.proj (`entrada` ou `main`).
+///
+/// O caminho e relativo a raiz do projeto; se nao existir ali, tenta dentro de `src/`.
+pub fn arquivo_entrada(raiz: &Path) -> Result<Option<PathBuf>> {
+    let Some(config) = carregar_configuracao_projeto(raiz) else {
+        return Ok(None);
+    };
+    let Some((campo, valor)) = CAMPOS_ENTRADA.iter().find_map(|c| {
+        config
+            .get(*c)
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(|v| (*c, v))
+    }) else {
+        return Ok(None);
+    };
+    let candidatos = [raiz.join(valor), raiz.join("src").join(valor)];
+    match candidatos.iter().find(|p| p.is_file()) {
+        Some(p) => Ok(Some(p.absolutize()?.to_path_buf())),
+        None => Err(ErroCli::ProjetoInvalido(format!(
+            "Arquivo de entrada '{}' (campo `{}` do pordosol.proj) nao encontrado em {}",
+            valor,
+            campo,
+            raiz.display()
+        ))
+        .into()),
+    }
+}
+
+/// Fontes do projeto para compilar: valida o arquivo de entrada e exige ao menos um .pr.
+pub fn fontes_projeto(raiz: &Path) -> Result<Vec<PathBuf>> {
+    arquivo_entrada(raiz)?;
+    let list = listar_prs(raiz);
+    if list.is_empty() {
+        bail!("Nenhum arquivo .pr encontrado em {}/src", raiz.display());
+    }
+    Ok(list)
+}
+
+/// Fonte priorizado como principal: a `entrada` do pordosol.proj ou `src/programa.pr`.
+///
+/// Entrada invalida e reportada por `fontes_projeto`; aqui so cai no padrao.
+pub fn arquivo_preferido(raiz: &Path) -> PathBuf {
+    arquivo_entrada(raiz)
+        .ok()
+        .flatten()
+        .unwrap_or_else(|| raiz.join("src").join("programa.pr"))
+}
+
+/// Fontes de `src/` separados em (incluidos, excluidos) pelos padroes de exclusao.
+///
+/// Links fisicos do mesmo arquivo entram uma vez so (ver `--permitir-duplicados`).
+pub fn listar_prs_com_excluidos(raiz: &Path) -> (Vec<PathBuf>, Vec<PathBuf>) {
     let src = raiz.join("src");
-    let mut arquivos: Vec<PathBuf> = WalkDir::new(&src)
+    let padroes = padroes_exclusao(raiz);
+    let (mut arquivos, excluidos): (Vec<PathBuf>, Vec<PathBuf>) = WalkDir::new(&src)
         .into_iter()
         .filter_map(|e| e.ok())
         .map(|e| e.path().to_path_buf())
         .filter(|p| p.is_file() && p.extension() == Some(OsStr::new("pr")))
-        .collect();
+        .partition(|p| !esta_excluido(&padroes, p.strip_prefix(&src).unwrap_or(p)));
 
-    let preferido = src.join("programa.pr");
+    let preferido = arquivo_preferido(raiz);
     if let Some(pos) = arquivos.iter().position(|p| p == &preferido) {
         let pref = arquivos.remove(pos);
         arquivos.insert(0, pref);
     }
-    arquivos
+    // Depois do preferido ir para a frente: e ele que fica se tiver um link fisico
+    let arquivos = remover_links_duplicados(raiz, arquivos);
+    (arquivos, excluidos)
+}
+
+/// Origens ja mostradas em `--verboso` (a resolucao roda mais de uma vez por comando).
+static COMPILADOR_DETALHADO: AtomicBool = AtomicBool::new(false);
+static INTERPRETADOR_DETALHADO: AtomicBool = AtomicBool::new(false);
+
+fn detalhar_origem(item: &DiagnosticoFerramenta) {
+    detalhe!(
+        "{}: {} (origem {})",
+        item.nome,
+        item.caminho.display(),
+        item.origem
+    );
+}
+
+fn localizar_obrigatorio(
+    nome_base: &str,
+    variavel_env: &'static str,
+    raiz: &Path,
+    detalhado: &AtomicBool,
+) -> std::result::Result<DiagnosticoFerramenta, FerramentaNaoEncontrada> {
+    let item = localizar_executavel(nome_base, variavel_env, raiz);
+    if !item.encontrado {
+        return Err(FerramentaNaoEncontrada::de(item, variavel_env));
+    }
+    if verboso() && !detalhado.swap(true, Ordering::Relaxed) {
+        detalhar_origem(&item);
+    }
+    Ok(item)
+}
+
+/// Compilador do projeto com a origem; sem candidato valido, devolve a trilha de busca.
+pub fn localizar_compilador(
+    raiz: &Path,
+) -> std::result::Result<DiagnosticoFerramenta, FerramentaNaoEncontrada> {
+    localizar_obrigatorio(
+        "compilador",
+        "PORDOSOL_COMPILADOR_PATH",
+        raiz,
+        &COMPILADOR_DETALHADO,
+    )
+}
+
+/// Interpretador do projeto com a origem; sem candidato valido, devolve a trilha de busca.
+pub fn localizar_interpretador(
+    raiz: &Path,
+) -> std::result::Result<DiagnosticoFerramenta, FerramentaNaoEncontrada> {
+    localizar_obrigatorio(
+        "interpretador",
+        "PORDOSOL_INTERPRETADOR_PATH",
+        raiz,
+        &INTERPRETADOR_DETALHADO,
+    )
+}
+
+/// Compilador e interpretador do projeto; falha no primeiro que nao for encontrado.
+pub fn localizar_binarios(
+    raiz: &Path,
+) -> std::result::Result<(PathBuf, PathBuf), FerramentaNaoEncontrada> {
+    let compilador = localizar_compilador(raiz)?;
+    let interpretador = localizar_interpretador(raiz)?;
+    Ok((compilador.caminho, interpretador.caminho))
 }
 
-pub fn localizar_binarios(raiz: &Path) -> (PathBuf, PathBuf) {
-    let diag = diagnosticar_toolchain(raiz);
-    (diag.compilador.caminho, diag.interpretador.caminho)
+/// Compilador e interpretador sem projeto: so overrides, variaveis de ambiente, instalacao e PATH.
+///
+/// Usado por `script`, que nao tem raiz para procurar `lib/`.
+pub fn localizar_binarios_globais() -> (PathBuf, PathBuf) {
+    let resolver = |nome_base: &str, variavel_env: &str| {
+        let candidatos = candidatos_executavel(nome_base, variavel_env, Path::new(""))
+            .into_iter()
+            .filter(|c| !c.origem.starts_with("fallback:"))
+            .collect();
+        resolver_candidatos(
+            nome_base,
+            candidatos,
+            PathBuf::from(nome_executavel(nome_base)),
+        )
+    };
+    let compilador = resolver("compilador", "PORDOSOL_COMPILADOR_PATH");
+    let interpretador = resolver("interpretador", "PORDOSOL_INTERPRETADOR_PATH");
+    detalhar_origem(&compilador);
+    detalhar_origem(&interpretador);
+    (compilador.caminho, interpretador.caminho)
 }
 
 pub fn diagnosticar_toolchain(raiz: &Path) -> DiagnosticoToolchain {
+    let com_versao = |mut item: DiagnosticoFerramenta| {
+        if item.encontrado {
+            item.versao = detectar_versao_binario(&item.caminho);
+        }
+        item
+    };
     DiagnosticoToolchain {
-        compilador: localizar_executavel("compilador", "PORDOSOL_COMPILADOR_PATH", raiz),
-        interpretador: localizar_executavel("interpretador", "PORDOSOL_INTERPRETADOR_PATH", raiz),
-        stdlib: localizar_stdlib_diagnostico(raiz),
+        compilador: com_versao(localizar_executavel(
+            "compilador",
+            "PORDOSOL_COMPILADOR_PATH",
+            raiz,
+        )),
+        interpretador: com_versao(localizar_executavel(
+            "interpretador",
+            "PORDOSOL_INTERPRETADOR_PATH",
+            raiz,
+        )),
+        stdlib: localizar_stdlib(raiz),
+    }
+}
+
+/// Ferramentas resolviveis por `pordosol toolchain which`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum Ferramenta {
+    Compilador,
+    Interpretador,
+    Stdlib,
+    Formatador,
+}
+
+impl Ferramenta {
+    pub fn nome(&self) -> &'static str {
+        match self {
+            Ferramenta::Compilador => "compilador",
+            Ferramenta::Interpretador => "interpretador",
+            Ferramenta::Stdlib => "stdlib",
+            Ferramenta::Formatador => "formatador",
+        }
+    }
+
+    pub fn candidatos(&self, raiz: &Path) -> Vec<CandidatoFerramenta> {
+        match self {
+            Ferramenta::Compilador => {
+                candidatos_executavel("compilador", "PORDOSOL_COMPILADOR_PATH", raiz)
+            }
+            Ferramenta::Interpretador => {
+                candidatos_executavel("interpretador", "PORDOSOL_INTERPRETADOR_PATH", raiz)
+            }
+            Ferramenta::Stdlib => candidatos_stdlib(raiz),
+            Ferramenta::Formatador => {
+                candidatos_executavel("formatador", "PORDOSOL_FORMATADOR_PATH", raiz)
+            }
+        }
     }
 }
 
+/// `pordosol toolchain which`: imprime so o caminho absoluto (ou a origem) da ferramenta.
+///
+/// Usa os mesmos candidatos do build, entao a resposta e o binario que seria executado.
+pub fn which_cmd(caminho: &Path, ferramenta: Ferramenta, origem: bool, todos: bool) -> Result<()> {
+    let raiz = localizar_raiz(caminho);
+    let candidatos = ferramenta.candidatos(&raiz);
+    let absoluto = |p: &Path| {
+        p.absolutize()
+            .map(|a| a.to_path_buf())
+            .unwrap_or_else(|_| p.to_path_buf())
+    };
+
+    if todos {
+        for c in &candidatos {
+            if c.encontrado {
+                println!("[x] {} ({})", absoluto(&c.caminho).display(), c.origem);
+            } else {
+                println!("[ ] {} ({})", c.caminho.display(), c.origem);
+            }
+        }
+    }
+
+    let Some(escolhido) = candidatos.iter().find(|c| c.encontrado) else {
+        bail!("Ferramenta '{}' nao encontrada", ferramenta.nome());
+    };
+    if !todos {
+        if origem {
+            println!("{}", escolhido.origem);
+        } else {
+            println!("{}", absoluto(&escolhido.caminho).display());
+        }
+    }
+    Ok(())
+}
+
 pub fn detectar_versao_binario(caminho: &Path) -> Option<String> {
     if !caminho.is_file() {
         return None;
@@ -92,7 +630,8 @@ pub fn detectar_versao_binario(caminho: &Path) -> Option<String> {
                 }
                 texto.push_str(&String::from_utf8_lossy(&out.stderr));
             }
-            if let Some(v) = extrair_versao(&texto) {
+            let programa = caminho.file_stem().map(|s| s.to_string_lossy());
+            if let Some(v) = extrair_versao(&texto, programa.as_deref()) {
                 return Some(v);
             }
         }
@@ -101,7 +640,17 @@ pub fn detectar_versao_binario(caminho: &Path) -> Option<String> {
     None
 }
 
+/// `pordosol.proj` com o arquivo do ambiente ativo (`--ambiente`) mesclado por cima.
 pub fn carregar_configuracao_projeto(raiz: &Path) -> Option<serde_json::Value> {
+    let mut config = carregar_configuracao_base(raiz)?;
+    if let Some((_, camada)) = crate::ambiente::carregar_ambiente(raiz) {
+        crate::ambiente::mesclar(&mut config, camada);
+    }
+    Some(config)
+}
+
+/// So o `pordosol.proj`, sem o arquivo do ambiente.
+pub fn carregar_configuracao_base(raiz: &Path) -> Option<serde_json::Value> {
     let projeto_file = raiz.join("pordosol.proj");
     if projeto_file.exists() {
         let conteudo = fs::read_to_string(&projeto_file).ok()?;
@@ -111,151 +660,150 @@ pub fn carregar_configuracao_projeto(raiz: &Path) -> Option<serde_json::Value> {
     }
 }
 
-fn localizar_executavel(nome_base: &str, variavel_env: &str, raiz: &Path) -> DiagnosticoFerramenta {
+/// Local considerado na resolucao de uma ferramenta, em ordem de prioridade.
+///
+/// `origem` ja traz o sufixo `(ausente)`/`(invalido)` quando o candidato nao serve.
+#[derive(Clone, Debug, Serialize)]
+pub struct CandidatoFerramenta {
+    pub caminho: PathBuf,
+    pub origem: String,
+    pub encontrado: bool,
+}
+
+fn candidato(
+    caminho: PathBuf,
+    origem: &str,
+    encontrado: bool,
+    sufixo_falha: &str,
+) -> CandidatoFerramenta {
+    CandidatoFerramenta {
+        caminho,
+        origem: if encontrado || sufixo_falha.is_empty() {
+            origem.to_string()
+        } else {
+            format!("{} {}", origem, sufixo_falha)
+        },
+        encontrado,
+    }
+}
+
+/// Todos os candidatos para um executavel da toolchain, na ordem em que sao testados.
+pub fn candidatos_executavel(
+    nome_base: &str,
+    variavel_env: &str,
+    raiz: &Path,
+) -> Vec<CandidatoFerramenta> {
     let nome_exec = nome_executavel(nome_base);
-    let mut primeira_falha: Option<DiagnosticoFerramenta> = None;
+    let mut out = Vec::new();
 
+    if let Some((path, origem)) = override_para(nome_base) {
+        out.push(candidato(path, origem, true, ""));
+    }
     if let Some(path) = ler_env_path(variavel_env) {
-        if path.is_file() {
-            return ok(nome_base, path, format!("env:{}", variavel_env));
-        }
-        primeira_falha.get_or_insert_with(|| {
-            falha(nome_base, path, format!("env:{} (invalido)", variavel_env))
-        });
+        let ok = path.is_file();
+        out.push(candidato(
+            path,
+            &format!("env:{}", variavel_env),
+            ok,
+            "(invalido)",
+        ));
     }
-
     for path in caminhos_tools_instalacao(&nome_exec) {
-        if path.is_file() {
-            return ok(nome_base, path, "instalacao-cli/tools".to_string());
-        }
-        primeira_falha.get_or_insert_with(|| {
-            falha(
-                nome_base,
-                path,
-                "instalacao-cli/tools (ausente)".to_string(),
-            )
-        });
+        let ok = path.is_file();
+        out.push(candidato(path, "instalacao-cli/tools", ok, "(ausente)"));
     }
-
     if let Some(path) = caminho_pordosol_home_tools(&nome_exec) {
-        if path.is_file() {
-            return ok(nome_base, path, "env:PORDOSOL_HOME/tools".to_string());
-        }
-        primeira_falha.get_or_insert_with(|| {
-            falha(
-                nome_base,
-                path,
-                "env:PORDOSOL_HOME/tools (ausente)".to_string(),
-            )
-        });
+        let ok = path.is_file();
+        out.push(candidato(path, "env:PORDOSOL_HOME/tools", ok, "(ausente)"));
     }
-
-    if let Ok(path) = which::which(&nome_exec) {
-        return ok(nome_base, path, "PATH".to_string());
+    match which::which(&nome_exec) {
+        Ok(path) => out.push(candidato(path, "PATH", true, "")),
+        Err(_) => out.push(candidato(PathBuf::from(&nome_exec), "PATH", false, "")),
     }
-    primeira_falha
-        .get_or_insert_with(|| falha(nome_base, PathBuf::from(&nome_exec), "PATH".to_string()));
-
     for path in candidatos_lib_local(raiz, &nome_exec) {
-        if path.is_file() {
-            return ok(nome_base, path, "fallback:./lib".to_string());
-        }
-        primeira_falha
-            .get_or_insert_with(|| falha(nome_base, path, "fallback:./lib (ausente)".to_string()));
+        let ok = path.is_file();
+        out.push(candidato(path, "fallback:./lib", ok, "(ausente)"));
     }
-
-    primeira_falha.unwrap_or_else(|| {
-        falha(
-            nome_base,
-            PathBuf::from(&nome_exec),
-            "nao resolvido".to_string(),
-        )
-    })
+    out
 }
 
-fn localizar_stdlib_diagnostico(raiz: &Path) -> DiagnosticoFerramenta {
-    let mut primeira_falha: Option<DiagnosticoFerramenta> = None;
+/// Todos os candidatos para a biblioteca padrao, na ordem em que sao testados.
+pub fn candidatos_stdlib(raiz: &Path) -> Vec<CandidatoFerramenta> {
+    let mut out = Vec::new();
 
     for var in ["PORDOSOL_STDLIB_PATH", "PORDOSOL_BIBLIOTECA_PADRAO_PATH"] {
         if let Some(path) = ler_env_path(var) {
-            if eh_stdlib_valida(&path) {
-                return ok("biblioteca padrao", path, format!("env:{}", var));
-            }
-            primeira_falha.get_or_insert_with(|| {
-                falha("biblioteca padrao", path, format!("env:{} (invalido)", var))
-            });
-        }
-    }
-
-    for candidato in ["stdlib", "sistema-padrao"] {
-        for path in caminhos_tools_instalacao(candidato) {
-            if eh_stdlib_valida(&path) {
-                return ok(
-                    "biblioteca padrao",
-                    path,
-                    format!("instalacao-cli/tools/{}", candidato),
-                );
-            }
-            primeira_falha.get_or_insert_with(|| {
-                falha(
-                    "biblioteca padrao",
-                    path,
-                    format!("instalacao-cli/tools/{} (ausente)", candidato),
-                )
-            });
+            let ok = eh_stdlib_valida(&path);
+            out.push(candidato(path, &format!("env:{}", var), ok, "(invalido)"));
         }
     }
-
-    if let Some(path) = caminho_pordosol_home_tools("stdlib") {
-        if eh_stdlib_valida(&path) {
-            return ok(
-                "biblioteca padrao",
+    for nome in ["stdlib", "sistema-padrao"] {
+        for path in caminhos_tools_instalacao(nome) {
+            let ok = eh_stdlib_valida(&path);
+            out.push(candidato(
                 path,
-                "env:PORDOSOL_HOME/tools/stdlib".to_string(),
-            );
+                &format!("instalacao-cli/tools/{}", nome),
+                ok,
+                "(ausente)",
+            ));
         }
-        primeira_falha.get_or_insert_with(|| {
-            falha(
-                "biblioteca padrao",
-                path,
-                "env:PORDOSOL_HOME/tools/stdlib (ausente)".to_string(),
-            )
-        });
     }
-
-    if let Some(path) = caminho_pordosol_home_tools("sistema-padrao") {
-        if eh_stdlib_valida(&path) {
-            return ok(
-                "biblioteca padrao",
+    for nome in ["stdlib", "sistema-padrao"] {
+        if let Some(path) = caminho_pordosol_home_tools(nome) {
+            let ok = eh_stdlib_valida(&path);
+            out.push(candidato(
                 path,
-                "env:PORDOSOL_HOME/tools/sistema-padrao".to_string(),
-            );
+                &format!("env:PORDOSOL_HOME/tools/{}", nome),
+                ok,
+                "(ausente)",
+            ));
         }
     }
-
     for path in candidatos_stdlib_local(raiz) {
-        if eh_stdlib_valida(&path) {
-            return ok("biblioteca padrao", path, "fallback:local".to_string());
-        }
-        primeira_falha.get_or_insert_with(|| {
-            falha(
-                "biblioteca padrao",
-                path,
-                "fallback:local (ausente)".to_string(),
-            )
-        });
+        let ok = eh_stdlib_valida(&path);
+        out.push(candidato(path, "fallback:local", ok, "(ausente)"));
     }
+    out
+}
 
-    primeira_falha.unwrap_or_else(|| {
-        falha(
-            "biblioteca padrao",
-            raiz.join("sistema-padrao"),
-            "nao resolvido".to_string(),
-        )
-    })
+/// Primeiro candidato encontrado; senao o primeiro que falhou, com todos em `tentativas`.
+fn resolver_candidatos(
+    nome: &str,
+    candidatos: Vec<CandidatoFerramenta>,
+    padrao: PathBuf,
+) -> DiagnosticoFerramenta {
+    if let Some(c) = candidatos.iter().find(|c| c.encontrado) {
+        return ok(nome, c.caminho.clone(), c.origem.clone());
+    }
+    let mut item = match candidatos.first() {
+        Some(c) => falha(nome, c.caminho.clone(), c.origem.clone()),
+        None => falha(nome, padrao, "nao resolvido".to_string()),
+    };
+    item.tentativas = candidatos;
+    item
 }
 
-fn extrair_versao(texto: &str) -> Option<String> {
+fn localizar_executavel(nome_base: &str, variavel_env: &str, raiz: &Path) -> DiagnosticoFerramenta {
+    resolver_candidatos(
+        nome_base,
+        candidatos_executavel(nome_base, variavel_env, raiz),
+        PathBuf::from(nome_executavel(nome_base)),
+    )
+}
+
+pub fn localizar_stdlib(raiz: &Path) -> DiagnosticoFerramenta {
+    resolver_candidatos(
+        "biblioteca padrao",
+        candidatos_stdlib(raiz),
+        raiz.join("sistema-padrao"),
+    )
+}
+
+/// Versao na saida de `--versao`: `(v1.2.3)`, depois `v1.2.3` e, por fim, um semver puro.
+///
+/// O semver puro (`compilador 1.4.0`) prefere a primeira ocorrencia depois do nome do
+/// programa, para nao pegar a versao de outra ferramenta citada antes.
+fn extrair_versao(texto: &str, programa: Option<&str>) -> Option<String> {
     if let Some(pos) = texto.find("(v") {
         let sub = &texto[pos + 1..];
         if let Some(end) = sub.find(')') {
@@ -283,7 +831,79 @@ fn extrair_versao(texto: &str) -> Option<String> {
         }
     }
 
-    None
+    let semvers = semvers_puros(texto);
+    let depois_do_nome = programa
+        .filter(|p| !p.is_empty())
+        .and_then(|p| {
+            texto
+                .to_ascii_lowercase()
+                .find(&p.to_ascii_lowercase())
+                .map(|i| i + p.len())
+        })
+        .and_then(|fim| semvers.iter().find(|(inicio, _)| *inicio >= fim));
+    depois_do_nome
+        .or(semvers.first())
+        .map(|(_, versao)| versao.clone())
+}
+
+/// Ocorrencias de `N.N.N` (com pre-release opcional) e a posicao de cada uma.
+///
+/// Ficam de fora numeros colados a letras ou pontos (`x1.2.3`, `1.2.3.4`, enderecos IP) e
+/// componentes com zero a esquerda (datas como `2024.01.15`).
+fn semvers_puros(texto: &str) -> Vec<(usize, String)> {
+    let bytes = texto.as_bytes();
+    let colado = |b: u8| b.is_ascii_alphanumeric() || b == b'.' || b == b'_';
+    let mut achados = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        if !bytes[i].is_ascii_digit() || (i > 0 && colado(bytes[i - 1])) {
+            i += 1;
+            continue;
+        }
+        let mut j = i;
+        let mut componentes = Vec::new();
+        loop {
+            let inicio = j;
+            while j < bytes.len() && bytes[j].is_ascii_digit() {
+                j += 1;
+            }
+            componentes.push(&texto[inicio..j]);
+            if componentes.len() == 3 || j + 1 >= bytes.len() || bytes[j] != b'.' {
+                break;
+            }
+            if !bytes[j + 1].is_ascii_digit() {
+                break;
+            }
+            j += 1;
+        }
+        let valido = componentes.len() == 3
+            && componentes
+                .iter()
+                .all(|c| !c.is_empty() && (c.len() == 1 || !c.starts_with('0')));
+        if !valido {
+            i = j.max(i + 1);
+            continue;
+        }
+        // Pre-release: `-beta.1`, `-rc1`
+        let mut fim = j;
+        if fim < bytes.len() && bytes[fim] == b'-' {
+            let mut k = fim + 1;
+            while k < bytes.len() && (bytes[k].is_ascii_alphanumeric() || bytes[k] == b'.') {
+                k += 1;
+            }
+            if k > fim + 1 {
+                fim = k;
+            }
+        }
+        let seguinte_colado = fim < bytes.len()
+            && (bytes[fim].is_ascii_alphanumeric()
+                || (bytes[fim] == b'.' && bytes.get(fim + 1).is_some_and(u8::is_ascii_digit)));
+        if !seguinte_colado {
+            achados.push((i, texto[i..fim].trim_end_matches('.').to_string()));
+        }
+        i = fim.max(i + 1);
+    }
+    achados
 }
 
 fn ok(nome: &str, caminho: PathBuf, origem: String) -> DiagnosticoFerramenta {
@@ -292,6 +912,8 @@ fn ok(nome: &str, caminho: PathBuf, origem: String) -> DiagnosticoFerramenta {
         caminho,
         origem,
         encontrado: true,
+        versao: None,
+        tentativas: Vec::new(),
     }
 }
 
@@ -301,10 +923,12 @@ fn falha(nome: &str, caminho: PathBuf, origem: String) -> DiagnosticoFerramenta
         caminho,
         origem,
         encontrado: false,
+        versao: None,
+        tentativas: Vec::new(),
     }
 }
 
-fn ler_env_path(nome: &str) -> Option<PathBuf> {
+pub(crate) fn ler_env_path(nome: &str) -> Option<PathBuf> {
     let valor = std::env::var(nome).ok()?;
     let valor = valor.trim();
     if valor.is_empty() {
@@ -362,14 +986,181 @@ fn candidatos_stdlib_local(raiz: &Path) -> Vec<PathBuf> {
     out
 }
 
+/// Campo `versao` (ou `version`) do `Sistema.toml` da stdlib.
+///
+/// Leitura linha a linha, sem parser TOML: so chaves fora de tabelas, no formato `versao = "x"`.
+pub fn versao_stdlib(pasta: &Path) -> Option<String> {
+    let texto = fs::read_to_string(pasta.join("Sistema.toml")).ok()?;
+    for linha in texto.lines() {
+        let linha = linha.trim();
+        if linha.starts_with('[') {
+            break;
+        }
+        let Some((chave, valor)) = linha.split_once('=') else {
+            continue;
+        };
+        if matches!(chave.trim(), "versao" | "version") {
+            let valor = valor
+                .split('#')
+                .next()
+                .unwrap_or("")
+                .trim()
+                .trim_matches('"');
+            return (!valor.is_empty()).then(|| valor.to_string());
+        }
+    }
+    None
+}
+
 fn eh_stdlib_valida(path: &Path) -> bool {
     path.is_dir() && (path.join("Sistema.toml").is_file() || path.join("src").is_dir())
 }
 
-fn nome_executavel(nome: &str) -> String {
+pub fn nome_executavel(nome: &str) -> String {
     if cfg!(windows) {
         format!("{}.exe", nome)
     } else {
         nome.to_string()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extrair_versao_aceita_semver_sem_prefixo() {
+        assert_eq!(
+            extrair_versao("compilador 1.4.0\n", Some("compilador")).as_deref(),
+            Some("1.4.0")
+        );
+        // Os formatos com `v` continuam tendo prioridade
+        assert_eq!(
+            extrair_versao("compilador 2.0.0 (v1.2.3)", None).as_deref(),
+            Some("v1.2.3")
+        );
+        assert_eq!(
+            extrair_versao("build 2024.01.15 em 10.0.0.1", None),
+            None,
+            "datas e enderecos nao sao versoes"
+        );
+        assert_eq!(
+            extrair_versao(
+                "usa libfoo 3.1.0\ncompilador 1.4.0-beta.2",
+                Some("compilador")
+            )
+            .as_deref(),
+            Some("1.4.0-beta.2")
+        );
+        // Sem o nome do programa na saida, vale a primeira ocorrencia
+        assert_eq!(
+            extrair_versao("usa libfoo 3.1.0; pordosolc 1.4.0", Some("compilador")).as_deref(),
+            Some("3.1.0")
+        );
+    }
+
+    #[test]
+    fn versao_stdlib_le_chave_de_topo_do_sistema_toml() {
+        let temp = tempfile::tempdir().unwrap();
+        fs::write(
+            temp.path().join("Sistema.toml"),
+            "# stdlib\nnome = \"sistema\"\nversao = \"0.4.1\" # estavel\n\n[modulos]\nversao = \"9\"\n",
+        )
+        .unwrap();
+        assert_eq!(versao_stdlib(temp.path()).as_deref(), Some("0.4.1"));
+
+        fs::write(
+            temp.path().join("Sistema.toml"),
+            "[pacote]\nversao = \"9\"\n",
+        )
+        .unwrap();
+        assert_eq!(versao_stdlib(temp.path()), None);
+    }
+
+    #[test]
+    fn localizar_raiz_prefere_pordosol_proj_a_src_aninhado() {
+        let temp = tempfile::tempdir().unwrap();
+        let raiz = temp.path().join("app");
+        let modulo = raiz.join("src").join("modulo");
+        fs::create_dir_all(modulo.join("src")).unwrap();
+        fs::write(raiz.join("pordosol.proj"), "{}").unwrap();
+
+        assert_eq!(localizar_raiz(&modulo), raiz);
+        assert_eq!(localizar_raiz(&modulo.join("src")), raiz);
+
+        // Sem pordosol.proj, continua valendo a pasta mais proxima com src/
+        fs::remove_file(raiz.join("pordosol.proj")).unwrap();
+        assert_eq!(localizar_raiz(&modulo), modulo);
+    }
+
+    #[test]
+    fn localizar_raiz_dentro_de_workspace_cargo() {
+        let temp = tempfile::tempdir().unwrap();
+        let ws = temp.path().join("ws");
+        fs::create_dir_all(ws.join("src")).unwrap();
+        fs::write(ws.join("Cargo.toml"), "[workspace]\n").unwrap();
+        let app = ws.join("ferramentas").join("app");
+        fs::create_dir_all(app.join("src").join("util")).unwrap();
+        fs::write(app.join("pordosol.toml"), "").unwrap();
+
+        assert_eq!(
+            localizar_raiz_com_marcador(&app.join("src").join("util")),
+            (app.clone(), MarcadorRaiz::Arquivo("pordosol.toml"))
+        );
+
+        // pordosol.proj vence pordosol.toml na mesma pasta
+        fs::write(app.join("pordosol.proj"), "{}").unwrap();
+        assert_eq!(
+            localizar_raiz_com_marcador(&app),
+            (app.clone(), MarcadorRaiz::Arquivo("pordosol.proj"))
+        );
+
+        // Fora do projeto, so resta o src/ do workspace
+        assert_eq!(
+            localizar_raiz_com_marcador(&ws.join("ferramentas")),
+            (ws.clone(), MarcadorRaiz::PastaSrc)
+        );
+    }
+
+    #[test]
+    fn localizar_raiz_de_arquivo_pr_em_pasta_irma() {
+        let temp = tempfile::tempdir().unwrap();
+        let app = temp.path().join("app");
+        fs::create_dir_all(app.join("src")).unwrap();
+        fs::write(app.join("pordosol.proj"), "{}").unwrap();
+        let rascunho = temp.path().join("rascunho");
+        fs::create_dir_all(&rascunho).unwrap();
+        let arquivo = rascunho.join("teste.pr");
+        fs::write(&arquivo, "").unwrap();
+
+        assert_eq!(
+            localizar_raiz_com_marcador(&arquivo),
+            (rascunho.clone(), MarcadorRaiz::Nenhum)
+        );
+        assert_eq!(
+            localizar_raiz_com_marcador(&app.join("src")),
+            (app, MarcadorRaiz::Arquivo("pordosol.proj"))
+        );
+    }
+
+    #[test]
+    fn localizar_raiz_reconhece_pordosol_workspace() {
+        let temp = tempfile::tempdir().unwrap();
+        let ws = temp.path().join("ws");
+        let api = ws.join("servicos").join("api");
+        fs::create_dir_all(api.join("src")).unwrap();
+        fs::write(
+            ws.join("pordosol.workspace"),
+            r#"{"membros": ["servicos/*"]}"#,
+        )
+        .unwrap();
+        fs::write(api.join("pordosol.proj"), "{}").unwrap();
+
+        assert_eq!(
+            localizar_raiz_com_marcador(&ws.join("servicos")),
+            (ws.clone(), MarcadorRaiz::Arquivo("pordosol.workspace"))
+        );
+        // O projeto do membro vem antes do workspace acima dele
+        assert_eq!(localizar_raiz(&api.join("src")), api);
+    }
+}
@@ -0,0 +1,198 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Context, Result};
+
+use crate::construir::pasta_saida_projeto;
+
+/// Arquivo de trava, na pasta de saida base do projeto.
+pub const ARQUIVO_TRAVA: &str = ".lock";
+
+/// Espera maxima pela trava; `PORDOSOL_LOCK_TIMEOUT` (segundos) substitui.
+const ESPERA_PADRAO: Duration = Duration::from_secs(300);
+const INTERVALO_TENTATIVA: Duration = Duration::from_millis(200);
+
+/// `--sem-lock`: desativa a trava nesta invocacao.
+static SEM_TRAVA: AtomicBool = AtomicBool::new(false);
+
+pub fn definir_sem_trava(sem_trava: bool) {
+    SEM_TRAVA.store(sem_trava, Ordering::Relaxed);
+}
+
+/// Trava exclusiva de build de um projeto; liberada (e o arquivo removido) no drop.
+///
+/// Usa trava consultiva do sistema (`flock` no Unix, abertura exclusiva no Windows),
+/// entao um processo que morre nunca deixa o projeto bloqueado: o arquivo que sobra
+/// so guarda o PID antigo, que e detectado e reaproveitado.
+pub struct TravaBuild {
+    arquivo: Option<File>,
+    caminho: PathBuf,
+}
+
+impl Drop for TravaBuild {
+    fn drop(&mut self) {
+        // Remover antes de soltar: quem estiver esperando no arquivo antigo percebe
+        // que ele foi trocado (ver `mesmo_arquivo`) e tenta de novo.
+        if cfg!(unix) {
+            fs::remove_file(&self.caminho).ok();
+        }
+        drop(self.arquivo.take());
+        if !cfg!(unix) {
+            fs::remove_file(&self.caminho).ok();
+        }
+    }
+}
+
+/// Adquire a trava de build de `raiz`, esperando outro processo pordosol terminar.
+///
+/// Devolve `None` com `--sem-lock`.
+pub fn adquirir(raiz: &Path) -> Result<Option<TravaBuild>> {
+    if SEM_TRAVA.load(Ordering::Relaxed) {
+        return Ok(None);
+    }
+    let pasta = pasta_saida_projeto(raiz);
+    fs::create_dir_all(&pasta).with_context(|| format!("Falha ao criar {}", pasta.display()))?;
+    let caminho = pasta.join(ARQUIVO_TRAVA);
+    let espera = espera_maxima();
+    let inicio = Instant::now();
+    let mut avisou = false;
+
+    loop {
+        if let Some(mut arquivo) = tentar_travar(&caminho)? {
+            if let Some(pid) = ler_pid(&mut arquivo).filter(|p| *p != std::process::id()) {
+                if !processo_vivo(pid) {
+                    eprintln!(
+                        "Aviso: trava abandonada pelo PID {} (processo encerrado) reaproveitada.",
+                        pid
+                    );
+                }
+            }
+            gravar_pid(&mut arquivo)
+                .with_context(|| format!("Falha ao escrever {}", caminho.display()))?;
+            return Ok(Some(TravaBuild {
+                arquivo: Some(arquivo),
+                caminho,
+            }));
+        }
+
+        if inicio.elapsed() >= espera {
+            bail!(
+                "Tempo esgotado ({}s) aguardando a trava {}{}. Use --sem-lock para ignorar.",
+                espera.as_secs(),
+                caminho.display(),
+                descrever_dono(&caminho)
+            );
+        }
+        if !avisou {
+            eprintln!(
+                "Outro processo pordosol esta construindo este projeto{}; aguardando ate {}s...",
+                descrever_dono(&caminho),
+                espera.as_secs()
+            );
+            avisou = true;
+        }
+        thread::sleep(INTERVALO_TENTATIVA);
+    }
+}
+
+fn espera_maxima() -> Duration {
+    std::env::var("PORDOSOL_LOCK_TIMEOUT")
+        .ok()
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .map(Duration::from_secs)
+        .unwrap_or(ESPERA_PADRAO)
+}
+
+fn descrever_dono(caminho: &Path) -> String {
+    File::open(caminho)
+        .ok()
+        .and_then(|mut f| ler_pid(&mut f))
+        .map(|pid| format!(" (PID {})", pid))
+        .unwrap_or_default()
+}
+
+fn ler_pid(arquivo: &mut File) -> Option<u32> {
+    let mut texto = String::new();
+    arquivo.seek(SeekFrom::Start(0)).ok()?;
+    arquivo.read_to_string(&mut texto).ok()?;
+    texto.trim().parse().ok()
+}
+
+fn gravar_pid(arquivo: &mut File) -> std::io::Result<()> {
+    arquivo.set_len(0)?;
+    arquivo.seek(SeekFrom::Start(0))?;
+    writeln!(arquivo, "{}", std::process::id())?;
+    arquivo.flush()
+}
+
+#[cfg(unix)]
+fn tentar_travar(caminho: &Path) -> Result<Option<File>> {
+    use std::os::unix::io::AsRawFd;
+
+    let arquivo = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(caminho)
+        .with_context(|| format!("Falha ao abrir {}", caminho.display()))?;
+    let r = unsafe { libc::flock(arquivo.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) };
+    if r != 0 {
+        let erro = std::io::Error::last_os_error();
+        if erro.kind() == std::io::ErrorKind::WouldBlock {
+            return Ok(None);
+        }
+        return Err(erro).with_context(|| format!("Falha ao travar {}", caminho.display()));
+    }
+    // O dono anterior pode ter removido o arquivo entre o open e o flock
+    if !mesmo_arquivo(&arquivo, caminho) {
+        return Ok(None);
+    }
+    Ok(Some(arquivo))
+}
+
+#[cfg(unix)]
+fn mesmo_arquivo(arquivo: &File, caminho: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+
+    match (arquivo.metadata(), fs::metadata(caminho)) {
+        (Ok(a), Ok(b)) => a.dev() == b.dev() && a.ino() == b.ino(),
+        _ => false,
+    }
+}
+
+#[cfg(unix)]
+fn processo_vivo(pid: u32) -> bool {
+    let r = unsafe { libc::kill(pid as libc::pid_t, 0) };
+    r == 0 || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+#[cfg(windows)]
+fn tentar_travar(caminho: &Path) -> Result<Option<File>> {
+    use std::os::windows::fs::OpenOptionsExt;
+
+    // ERROR_SHARING_VIOLATION: outro processo esta com o arquivo aberto
+    const VIOLACAO_COMPARTILHAMENTO: i32 = 32;
+    match OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .share_mode(0)
+        .open(caminho)
+    {
+        Ok(arquivo) => Ok(Some(arquivo)),
+        Err(e) if e.raw_os_error() == Some(VIOLACAO_COMPARTILHAMENTO) => Ok(None),
+        Err(e) => Err(e).with_context(|| format!("Falha ao travar {}", caminho.display())),
+    }
+}
+
+#[cfg(windows)]
+fn processo_vivo(_pid: u32) -> bool {
+    // Com abertura exclusiva, conseguir o arquivo ja prova que o dono anterior saiu
+    false
+}
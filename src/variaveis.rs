@@ -0,0 +1,172 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use path_absolutize::Absolutize;
+use serde::Serialize;
+
+use crate::toolchain::{self, DiagnosticoFerramenta};
+
+/// Sintaxe das linhas emitidas por `pordosol env`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum Shell {
+    /// `export NOME='valor'` (sh, bash, zsh)
+    Sh,
+    /// `$env:NOME = 'valor'`
+    #[value(alias = "pwsh")]
+    Powershell,
+}
+
+/// Variavel exportada por `pordosol env`; `valor` ausente quando a ferramenta nao foi resolvida.
+#[derive(Clone, Debug, Serialize)]
+pub struct VariavelEnv {
+    pub nome: &'static str,
+    pub valor: Option<PathBuf>,
+    pub origem: String,
+    /// Como resolver, quando nao ha valor
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dica: Option<String>,
+}
+
+fn absoluto(caminho: &Path) -> PathBuf {
+    caminho
+        .absolutize()
+        .map(|a| a.to_path_buf())
+        .unwrap_or_else(|_| caminho.to_path_buf())
+}
+
+fn da_ferramenta(nome: &'static str, item: &DiagnosticoFerramenta) -> VariavelEnv {
+    if item.encontrado {
+        return VariavelEnv {
+            nome,
+            valor: Some(absoluto(&item.caminho)),
+            origem: item.origem.clone(),
+            dica: None,
+        };
+    }
+    VariavelEnv {
+        nome,
+        valor: None,
+        origem: item.origem.clone(),
+        dica: Some("rode `pordosol doctor` para ver os locais tentados".to_string()),
+    }
+}
+
+/// `PORDOSOL_HOME` do ambiente ou, sem ele, a instalacao que contem este executavel
+/// (`<home>/bin/pordosol` com `<home>/tools` ao lado).
+fn pordosol_home() -> VariavelEnv {
+    const NOME: &str = "PORDOSOL_HOME";
+    if let Some(home) = toolchain::ler_env_path(NOME) {
+        return VariavelEnv {
+            nome: NOME,
+            valor: Some(absoluto(&home)),
+            origem: format!("env:{}", NOME),
+            dica: None,
+        };
+    }
+    let instalacao = std::env::current_exe()
+        .ok()
+        .and_then(|exe| Some(exe.parent()?.parent()?.to_path_buf()))
+        .filter(|home| home.join("tools").is_dir());
+    match instalacao {
+        Some(home) => VariavelEnv {
+            nome: NOME,
+            valor: Some(home),
+            origem: "instalacao-cli".to_string(),
+            dica: None,
+        },
+        None => VariavelEnv {
+            nome: NOME,
+            valor: None,
+            origem: format!("env:{} (nao definido)", NOME),
+            dica: Some("aponte para a pasta de instalacao (a que contem tools/)".to_string()),
+        },
+    }
+}
+
+/// Variaveis resolvidas para o projeto em `raiz`, na ordem em que sao impressas.
+pub fn resolver(raiz: &Path) -> Vec<VariavelEnv> {
+    let diag = toolchain::diagnosticar_toolchain(raiz);
+    vec![
+        pordosol_home(),
+        da_ferramenta("PORDOSOL_COMPILADOR_PATH", &diag.compilador),
+        da_ferramenta("PORDOSOL_INTERPRETADOR_PATH", &diag.interpretador),
+        da_ferramenta("PORDOSOL_STDLIB_PATH", &diag.stdlib),
+    ]
+}
+
+/// Aspas simples nas duas sintaxes: nada dentro e expandido pelo shell.
+fn citar(valor: &str, shell: Shell) -> String {
+    match shell {
+        Shell::Sh => format!("'{}'", valor.replace('\'', r"'\''")),
+        Shell::Powershell => format!("'{}'", valor.replace('\'', "''")),
+    }
+}
+
+/// Linha de export; variaveis sem valor viram comentario para nao quebrar o `eval`.
+pub fn linha(variavel: &VariavelEnv, shell: Shell) -> String {
+    let Some(valor) = &variavel.valor else {
+        return format!(
+            "# {} nao resolvido: {}",
+            variavel.nome,
+            variavel.dica.as_deref().unwrap_or_default()
+        );
+    };
+    let valor = citar(&valor.display().to_string(), shell);
+    match shell {
+        Shell::Sh => format!("export {}={}", variavel.nome, valor),
+        Shell::Powershell => format!("$env:{} = {}", variavel.nome, valor),
+    }
+}
+
+pub fn env_cmd(caminho: &Path, shell: Shell, json: bool) -> Result<()> {
+    let raiz = toolchain::localizar_raiz(caminho);
+    let variaveis = resolver(&raiz);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&variaveis)?);
+        return Ok(());
+    }
+    for variavel in &variaveis {
+        println!("{}", linha(variavel, shell));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn variavel(valor: Option<&str>) -> VariavelEnv {
+        VariavelEnv {
+            nome: "PORDOSOL_COMPILADOR_PATH",
+            valor: valor.map(PathBuf::from),
+            origem: "PATH".to_string(),
+            dica: valor
+                .is_none()
+                .then(|| "rode `pordosol doctor`".to_string()),
+        }
+    }
+
+    #[test]
+    fn linhas_sh_e_powershell_escapam_aspas() {
+        let v = variavel(Some("/opt/it's/compilador"));
+        assert_eq!(
+            linha(&v, Shell::Sh),
+            r"export PORDOSOL_COMPILADOR_PATH='/opt/it'\''s/compilador'"
+        );
+        assert_eq!(
+            linha(&v, Shell::Powershell),
+            "$env:PORDOSOL_COMPILADOR_PATH = '/opt/it''s/compilador'"
+        );
+    }
+
+    #[test]
+    fn sem_valor_vira_comentario() {
+        let v = variavel(None);
+        for shell in [Shell::Sh, Shell::Powershell] {
+            assert_eq!(
+                linha(&v, shell),
+                "# PORDOSOL_COMPILADOR_PATH nao resolvido: rode `pordosol doctor`"
+            );
+        }
+    }
+}
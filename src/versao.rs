@@ -0,0 +1,310 @@
+use std::cmp::Ordering;
+use std::fmt;
+
+use anyhow::{bail, Result};
+
+/// Faixa de versoes do compilador suportada por esta CLI: `[COMPILADOR_MIN, COMPILADOR_MAX)`.
+pub const COMPILADOR_MIN: &str = "0.1.0";
+pub const COMPILADOR_MAX: &str = "2.0.0";
+
+/// Versao no formato semver (`MAJOR.MINOR.PATCH[-pre][+build]`).
+///
+/// Aceita prefixo `v` e componentes omitidos (`1.2` = `1.2.0`); metadados de build sao ignorados.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Versao {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+    pub pre: Option<String>,
+}
+
+impl Versao {
+    pub fn parse(texto: &str) -> Result<Versao> {
+        let t = texto.trim();
+        let t = t
+            .strip_prefix('v')
+            .or_else(|| t.strip_prefix('V'))
+            .unwrap_or(t);
+        let t = t.split('+').next().unwrap_or(t);
+        let (nucleo, pre) = match t.split_once('-') {
+            Some((n, p)) if !p.is_empty() => (n, Some(p.to_string())),
+            Some(_) => bail!("Versao invalida '{}': pre-release vazio", texto),
+            None => (t, None),
+        };
+
+        let partes: Vec<&str> = nucleo.split('.').collect();
+        if partes.is_empty() || partes.len() > 3 {
+            bail!("Versao invalida '{}': esperado MAJOR.MINOR.PATCH", texto);
+        }
+        let mut numeros = [0u64; 3];
+        for (i, parte) in partes.iter().enumerate() {
+            if parte.is_empty() || !parte.chars().all(|c| c.is_ascii_digit()) {
+                bail!(
+                    "Versao invalida '{}': componente '{}' nao numerico",
+                    texto,
+                    parte
+                );
+            }
+            numeros[i] = parte.parse()?;
+        }
+
+        Ok(Versao {
+            major: numeros[0],
+            minor: numeros[1],
+            patch: numeros[2],
+            pre,
+        })
+    }
+}
+
+impl fmt::Display for Versao {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)?;
+        if let Some(pre) = &self.pre {
+            write!(f, "-{}", pre)?;
+        }
+        Ok(())
+    }
+}
+
+impl Ord for Versao {
+    fn cmp(&self, outra: &Self) -> Ordering {
+        (self.major, self.minor, self.patch)
+            .cmp(&(outra.major, outra.minor, outra.patch))
+            .then_with(|| match (&self.pre, &outra.pre) {
+                (None, None) => Ordering::Equal,
+                // Pre-release vem antes da versao final
+                (None, Some(_)) => Ordering::Greater,
+                (Some(_), None) => Ordering::Less,
+                (Some(a), Some(b)) => comparar_pre(a, b),
+            })
+    }
+}
+
+impl PartialOrd for Versao {
+    fn partial_cmp(&self, outra: &Self) -> Option<Ordering> {
+        Some(self.cmp(outra))
+    }
+}
+
+/// Compara identificadores de pre-release: numericos por valor, demais lexicograficamente.
+fn comparar_pre(a: &str, b: &str) -> Ordering {
+    let mut ia = a.split('.');
+    let mut ib = b.split('.');
+    loop {
+        match (ia.next(), ib.next()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) => {
+                let ord = match (x.parse::<u64>(), y.parse::<u64>()) {
+                    (Ok(nx), Ok(ny)) => nx.cmp(&ny),
+                    (Ok(_), Err(_)) => Ordering::Less,
+                    (Err(_), Ok(_)) => Ordering::Greater,
+                    (Err(_), Err(_)) => x.cmp(y),
+                };
+                if ord != Ordering::Equal {
+                    return ord;
+                }
+            }
+        }
+    }
+}
+
+/// Resultado da checagem da versao do compilador contra a faixa suportada pela CLI.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Compatibilidade {
+    Compativel,
+    AbaixoDoMinimo,
+    AcimaDoMaximo,
+    /// A versao reportada nao e semver valido
+    Indeterminada,
+}
+
+impl Compatibilidade {
+    pub fn ok(&self) -> bool {
+        matches!(self, Compatibilidade::Compativel)
+    }
+
+    pub fn descricao(&self, versao: &str) -> String {
+        match self {
+            Compatibilidade::Compativel => format!(
+                "{} compativel (suportado: >={}, <{})",
+                versao, COMPILADOR_MIN, COMPILADOR_MAX
+            ),
+            Compatibilidade::AbaixoDoMinimo => {
+                format!("{} abaixo do minimo suportado {}", versao, COMPILADOR_MIN)
+            }
+            Compatibilidade::AcimaDoMaximo => {
+                format!("{} acima do maximo suportado (<{})", versao, COMPILADOR_MAX)
+            }
+            Compatibilidade::Indeterminada => {
+                format!("{} nao e uma versao semver reconhecida", versao)
+            }
+        }
+    }
+}
+
+/// Compara a versao do compilador com `COMPILADOR_MIN`/`COMPILADOR_MAX`.
+pub fn verificar_compatibilidade(versao: &str) -> Compatibilidade {
+    let Ok(v) = Versao::parse(versao) else {
+        return Compatibilidade::Indeterminada;
+    };
+    let min = Versao::parse(COMPILADOR_MIN).expect("COMPILADOR_MIN valido");
+    let max = Versao::parse(COMPILADOR_MAX).expect("COMPILADOR_MAX valido");
+    if v < min {
+        Compatibilidade::AbaixoDoMinimo
+    } else if v >= max {
+        Compatibilidade::AcimaDoMaximo
+    } else {
+        Compatibilidade::Compativel
+    }
+}
+
+/// Operador de um requisito de versao declarado no projeto.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Operador {
+    /// `=1.2.3` ou so `1.2.3`: exatamente essa versao
+    Exato,
+    Maior,
+    MaiorOuIgual,
+    Menor,
+    MenorOuIgual,
+    /// `^1.2.3`: mesma versao "compativel" (mesmo MAJOR; com MAJOR 0, mesmo MINOR)
+    Compativel,
+}
+
+/// Requisito como `>=0.5.0`, `=1.2.3`, `^0.4` ou `1.2.3` (exato).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Requisito {
+    pub operador: Operador,
+    pub versao: Versao,
+}
+
+impl Requisito {
+    pub fn parse(texto: &str) -> Result<Requisito> {
+        let t = texto.trim();
+        let (operador, resto) = [
+            (">=", Operador::MaiorOuIgual),
+            ("<=", Operador::MenorOuIgual),
+            (">", Operador::Maior),
+            ("<", Operador::Menor),
+            ("=", Operador::Exato),
+            ("^", Operador::Compativel),
+        ]
+        .iter()
+        .find_map(|(prefixo, op)| t.strip_prefix(prefixo).map(|r| (*op, r)))
+        .unwrap_or((Operador::Exato, t));
+        if resto.trim().is_empty() {
+            bail!("Requisito de versao invalido '{}': falta a versao", texto);
+        }
+        Ok(Requisito {
+            operador,
+            versao: Versao::parse(resto)?,
+        })
+    }
+
+    pub fn aceita(&self, v: &Versao) -> bool {
+        let r = &self.versao;
+        match self.operador {
+            Operador::Exato => v == r,
+            Operador::Maior => v > r,
+            Operador::MaiorOuIgual => v >= r,
+            Operador::Menor => v < r,
+            Operador::MenorOuIgual => v <= r,
+            Operador::Compativel => {
+                let mesma_serie = if r.major > 0 {
+                    v.major == r.major
+                } else {
+                    v.major == 0 && v.minor == r.minor
+                };
+                v >= r && mesma_serie
+            }
+        }
+    }
+}
+
+impl fmt::Display for Requisito {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let op = match self.operador {
+            Operador::Exato => "=",
+            Operador::Maior => ">",
+            Operador::MaiorOuIgual => ">=",
+            Operador::Menor => "<",
+            Operador::MenorOuIgual => "<=",
+            Operador::Compativel => "^",
+        };
+        write!(f, "{}{}", op, self.versao)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_aceita_prefixo_e_componentes_omitidos() {
+        let v = Versao::parse("v1.2").unwrap();
+        assert_eq!((v.major, v.minor, v.patch), (1, 2, 0));
+        assert_eq!(
+            Versao::parse("1.4.0-beta.2+abc").unwrap().to_string(),
+            "1.4.0-beta.2"
+        );
+        assert!(Versao::parse("1.x").is_err());
+        assert!(Versao::parse("1.2.3.4").is_err());
+    }
+
+    #[test]
+    fn ordem_semver() {
+        let v = |s| Versao::parse(s).unwrap();
+        assert!(v("1.0.0-alpha") < v("1.0.0-alpha.1"));
+        assert!(v("1.0.0-alpha.1") < v("1.0.0-beta"));
+        assert!(v("1.0.0-beta.2") < v("1.0.0-beta.11"));
+        assert!(v("1.0.0-rc.1") < v("1.0.0"));
+        assert!(v("1.9.0") < v("1.10.0"));
+    }
+
+    #[test]
+    fn compatibilidade_com_faixa_embutida() {
+        assert_eq!(
+            verificar_compatibilidade("v1.2.3"),
+            Compatibilidade::Compativel
+        );
+        assert_eq!(
+            verificar_compatibilidade("0.0.9"),
+            Compatibilidade::AbaixoDoMinimo
+        );
+        assert_eq!(
+            verificar_compatibilidade("v2.0.0"),
+            Compatibilidade::AcimaDoMaximo
+        );
+        assert_eq!(
+            verificar_compatibilidade("abc"),
+            Compatibilidade::Indeterminada
+        );
+    }
+
+    #[test]
+    fn requisito_aceita_operadores_e_versao_pura() {
+        let v = |s| Versao::parse(s).unwrap();
+        let r = |s| Requisito::parse(s).unwrap();
+
+        assert!(r(">=0.5.0").aceita(&v("0.5.0")));
+        assert!(r(">=0.5.0").aceita(&v("1.0.0")));
+        assert!(!r(">=0.5.0").aceita(&v("0.4.2")));
+
+        assert!(r("=1.2.3").aceita(&v("1.2.3")));
+        assert!(!r("=1.2.3").aceita(&v("1.2.4")));
+        assert_eq!(r("1.2.3"), r("=1.2.3"));
+
+        assert!(r("^1.2").aceita(&v("1.9.0")));
+        assert!(!r("^1.2").aceita(&v("2.0.0")));
+        assert!(!r("^1.2").aceita(&v("1.1.9")));
+        assert!(r("^0.4.1").aceita(&v("0.4.7")));
+        assert!(!r("^0.4.1").aceita(&v("0.5.0")));
+
+        assert_eq!(r(" >= v0.5 ").to_string(), ">=0.5.0");
+        assert!(Requisito::parse(">=").is_err());
+        assert!(Requisito::parse("~>1.0").is_err());
+    }
+}
@@ -0,0 +1,650 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use path_absolutize::Absolutize;
+use serde::{Deserialize, Serialize};
+use walkdir::WalkDir;
+
+use crate::erros::ErroCli;
+use crate::exclusao::casa_padrao;
+use crate::impressoes::Impressao;
+use crate::manifesto::ArquivoManifesto;
+use crate::relatorio::{RelatorioExecucao, SituacaoCache, StatusUnidade};
+use crate::saida::{pintar_erro, progresso, Cor};
+use crate::toolchain::carregar_configuracao_projeto;
+
+/// Arquivo, na raiz do workspace, com os globs dos membros: `{"membros": ["servicos/*"]}`.
+pub const ARQUIVO_WORKSPACE: &str = "pordosol.workspace";
+
+/// Um projeto do workspace.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Membro {
+    /// `nome` do pordosol.proj ou, sem ele, o nome da pasta
+    pub nome: String,
+    pub raiz: PathBuf,
+    /// Caminho relativo a raiz do workspace, com `/`
+    pub relativo: String,
+}
+
+/// Workspace mais proximo que contem `caminho` (a propria pasta ou uma acima).
+pub fn localizar_workspace(caminho: &Path) -> Option<PathBuf> {
+    let p = caminho.absolutize().ok()?.to_path_buf();
+    p.ancestors()
+        .find(|a| a.join(ARQUIVO_WORKSPACE).is_file())
+        .map(Path::to_path_buf)
+}
+
+/// `raiz` e a raiz de um workspace sem projeto proprio (so o `pordosol.workspace`).
+pub fn eh_raiz_workspace(raiz: &Path) -> bool {
+    raiz.join(ARQUIVO_WORKSPACE).is_file() && !raiz.join("pordosol.proj").is_file()
+}
+
+fn invalido(raiz_ws: &Path, detalhe: &str) -> anyhow::Error {
+    ErroCli::ProjetoInvalido(format!(
+        "{}: {}",
+        raiz_ws.join(ARQUIVO_WORKSPACE).display(),
+        detalhe
+    ))
+    .into()
+}
+
+fn ler_workspace(raiz_ws: &Path) -> Result<serde_json::Value> {
+    let arquivo = raiz_ws.join(ARQUIVO_WORKSPACE);
+    let texto = fs::read_to_string(&arquivo)
+        .with_context(|| format!("Falha ao ler {}", arquivo.display()))?;
+    serde_json::from_str(&texto).map_err(|e| invalido(raiz_ws, &format!("JSON invalido: {}", e)))
+}
+
+/// `cache_dependencias` do `pordosol.workspace` (padrao: desligado). So vale para compiladores
+/// que aceitam, entre as entradas, os artefatos ja compilados de uma dependencia.
+pub fn cache_dependencias(raiz_ws: &Path) -> Result<bool> {
+    match ler_workspace(raiz_ws)?.get("cache_dependencias") {
+        None => Ok(false),
+        Some(valor) => valor
+            .as_bool()
+            .ok_or_else(|| invalido(raiz_ws, "`cache_dependencias` deve ser true ou false")),
+    }
+}
+
+/// Globs de `membros` do `pordosol.workspace`.
+fn ler_padroes(raiz_ws: &Path) -> Result<Vec<String>> {
+    ler_workspace(raiz_ws)?
+        .get("membros")
+        .and_then(|m| m.as_array())
+        .and_then(|lista| {
+            lista
+                .iter()
+                .map(|p| p.as_str().map(str::to_string))
+                .collect::<Option<Vec<_>>>()
+        })
+        .ok_or_else(|| invalido(raiz_ws, "`membros` deve ser uma lista de globs"))
+}
+
+/// Membros do workspace, dependencias `path` entre irmaos primeiro.
+///
+/// Cada glob casa com pastas relativas a raiz do workspace; so as que tem `pordosol.proj`
+/// entram. Sem dependencias entre eles, a ordem e a dos caminhos.
+pub fn membros(raiz_ws: &Path) -> Result<Vec<Membro>> {
+    let padroes = ler_padroes(raiz_ws)?;
+    // Sem `**`, nenhum membro fica mais fundo que o glob mais longo
+    let profundidade = if padroes.iter().any(|p| p.contains("**")) {
+        usize::MAX
+    } else {
+        padroes
+            .iter()
+            .map(|p| p.split('/').filter(|s| !s.is_empty()).count())
+            .max()
+            .unwrap_or(0)
+    };
+    let mut encontrados: BTreeMap<String, PathBuf> = BTreeMap::new();
+    for entrada in WalkDir::new(raiz_ws)
+        .min_depth(1)
+        .max_depth(profundidade)
+        .into_iter()
+        .filter_entry(|e| !e.file_name().to_string_lossy().starts_with('.'))
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_dir())
+    {
+        let relativo = entrada
+            .path()
+            .strip_prefix(raiz_ws)
+            .unwrap_or(entrada.path())
+            .to_string_lossy()
+            .replace('\\', "/");
+        if entrada.path().join("pordosol.proj").is_file()
+            && padroes.iter().any(|p| casa_padrao(p, &relativo))
+        {
+            encontrados.insert(relativo, entrada.into_path());
+        }
+    }
+
+    let mut lista = Vec::new();
+    let mut nomes: BTreeMap<String, String> = BTreeMap::new();
+    for (relativo, raiz) in encontrados {
+        let nome = carregar_configuracao_projeto(&raiz)
+            .and_then(|c| c.get("nome").and_then(|n| n.as_str()).map(str::to_string))
+            .unwrap_or_else(|| {
+                raiz.file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .to_string()
+            });
+        if let Some(outro) = nomes.insert(nome.clone(), relativo.clone()) {
+            return Err(ErroCli::ProjetoInvalido(format!(
+                "Membros do workspace com o mesmo nome '{}': {} e {}",
+                nome, outro, relativo
+            ))
+            .into());
+        }
+        lista.push(Membro {
+            nome,
+            raiz,
+            relativo,
+        });
+    }
+    ordenar_por_dependencias(lista)
+}
+
+fn canonico(p: &Path) -> PathBuf {
+    p.canonicalize().unwrap_or_else(|_| p.to_path_buf())
+}
+
+/// Indices dos membros que `membro` declara como dependencia `path`.
+fn dependencias_irmas(membro: &Membro, raizes: &[PathBuf]) -> Vec<usize> {
+    let Some(deps) = carregar_configuracao_projeto(&membro.raiz)
+        .and_then(|c| c.get("dependencias").and_then(|d| d.as_object()).cloned())
+    else {
+        return Vec::new();
+    };
+    deps.values()
+        .filter_map(|v| v.get("path").and_then(|p| p.as_str()))
+        .filter_map(|p| {
+            let alvo = canonico(&membro.raiz.join(p));
+            raizes.iter().position(|r| *r == alvo)
+        })
+        .collect()
+}
+
+/// Ordem topologica estavel: entre os membros prontos, vence o que veio antes.
+fn ordenar_por_dependencias(lista: Vec<Membro>) -> Result<Vec<Membro>> {
+    let raizes: Vec<PathBuf> = lista.iter().map(|m| canonico(&m.raiz)).collect();
+    let deps: Vec<Vec<usize>> = lista
+        .iter()
+        .map(|m| dependencias_irmas(m, &raizes))
+        .collect();
+    let mut feitos = vec![false; lista.len()];
+    let mut ordem = Vec::with_capacity(lista.len());
+    while ordem.len() < lista.len() {
+        let Some(proximo) =
+            (0..lista.len()).find(|&i| !feitos[i] && deps[i].iter().all(|&d| d == i || feitos[d]))
+        else {
+            let ciclo: Vec<&str> = (0..lista.len())
+                .filter(|&i| !feitos[i])
+                .map(|i| lista[i].nome.as_str())
+                .collect();
+            return Err(ErroCli::ProjetoInvalido(format!(
+                "Ciclo de dependencias entre membros do workspace: {}",
+                ciclo.join(", ")
+            ))
+            .into());
+        };
+        feitos[proximo] = true;
+        ordem.push(proximo);
+    }
+    let mut lista: Vec<Option<Membro>> = lista.into_iter().map(Some).collect();
+    Ok(ordem.into_iter().filter_map(|i| lista[i].take()).collect())
+}
+
+/// Membros do workspace de `caminho`, ou erro se ele nao estiver em nenhum.
+pub fn membros_de(caminho: &Path) -> Result<(PathBuf, Vec<Membro>)> {
+    let Some(raiz_ws) = localizar_workspace(caminho) else {
+        return Err(ErroCli::ProjetoInvalido(format!(
+            "{} nao esta em um workspace ({} nao encontrado)",
+            caminho.display(),
+            ARQUIVO_WORKSPACE
+        ))
+        .into());
+    };
+    let lista = membros(&raiz_ws)?;
+    Ok((raiz_ws, lista))
+}
+
+/// Como [`executar_em_membros`] percorre o workspace.
+#[derive(Clone, Copy, Debug)]
+pub struct OpcoesMembros<'a> {
+    /// Coluna `Target` do relatorio
+    pub target: &'a str,
+    /// Segue para os proximos membros apos uma falha
+    pub continuar: bool,
+    /// Relatorio em JSON, sem os cabecalhos por membro
+    pub json: bool,
+    /// Membros executados ao mesmo tempo; com 1, um por vez na ordem de dependencias
+    pub paralelos: usize,
+}
+
+impl<'a> OpcoesMembros<'a> {
+    /// Um membro por vez, relatorio em tabela.
+    pub fn em_ordem(target: &'a str, continuar: bool) -> Self {
+        OpcoesMembros {
+            target,
+            continuar,
+            json: false,
+            paralelos: 1,
+        }
+    }
+}
+
+/// Desfecho de um membro: `None` se nao chegou a rodar.
+type Desfecho = Option<(Result<Option<SituacaoCache>>, Duration)>;
+
+/// Roda `acao` em cada membro com um cabecalho por membro e o relatorio no fim (uma
+/// unidade por membro, com a situacao do cache quando a acao informa).
+///
+/// Com `paralelos` > 1, ate esse numero de membros roda ao mesmo tempo; so serve quando
+/// cada membro compila as proprias dependencias, como em `producao`. Depois de um erro,
+/// nenhum membro novo comeca, a menos que `continuar`; o erro devolvido carrega o do
+/// primeiro membro que falhou, para que o codigo de saida seja o dele.
+pub fn executar_em_membros(
+    membros: &[Membro],
+    opcoes: &OpcoesMembros,
+    acao: impl Fn(&Membro) -> Result<Option<SituacaoCache>> + Sync,
+) -> Result<()> {
+    let desfechos: Vec<Mutex<Desfecho>> = membros.iter().map(|_| Mutex::new(None)).collect();
+    let proximo = AtomicUsize::new(0);
+    let falhou = AtomicBool::new(false);
+    let trabalhador = || loop {
+        if falhou.load(Ordering::SeqCst) && !opcoes.continuar {
+            break;
+        }
+        let i = proximo.fetch_add(1, Ordering::SeqCst);
+        let Some(membro) = membros.get(i) else {
+            break;
+        };
+        if !opcoes.json {
+            if i > 0 {
+                progresso!();
+            }
+            progresso!("== {} ({}) ==", membro.nome, membro.relativo);
+        }
+        let inicio = Instant::now();
+        let resultado = acao(membro);
+        if let Err(erro) = &resultado {
+            eprintln!(
+                "{} {}: {:#}",
+                pintar_erro(Cor::Vermelho, "Error:"),
+                membro.nome,
+                erro
+            );
+            falhou.store(true, Ordering::SeqCst);
+        }
+        *desfechos[i].lock().unwrap_or_else(|e| e.into_inner()) =
+            Some((resultado, inicio.elapsed()));
+    };
+    let paralelos = opcoes.paralelos.clamp(1, membros.len().max(1));
+    if paralelos == 1 {
+        trabalhador();
+    } else {
+        thread::scope(|escopo| {
+            for _ in 0..paralelos {
+                escopo.spawn(trabalhador);
+            }
+        });
+    }
+
+    let mut relatorio = RelatorioExecucao::new();
+    let mut primeiro_erro = None;
+    for (membro, desfecho) in membros.iter().zip(desfechos) {
+        match desfecho.into_inner().unwrap_or_else(|e| e.into_inner()) {
+            None => relatorio.pular(&membro.nome, opcoes.target),
+            Some((resultado, duracao)) => {
+                relatorio.registrar(
+                    &membro.nome,
+                    opcoes.target,
+                    duracao,
+                    resultado.as_ref().copied(),
+                );
+                if let Err(erro) = resultado {
+                    primeiro_erro.get_or_insert(erro);
+                }
+            }
+        }
+    }
+    relatorio.imprimir(opcoes.json)?;
+    match primeiro_erro {
+        None => Ok(()),
+        Some(erro) => {
+            let falharam: Vec<&str> = relatorio
+                .unidades
+                .iter()
+                .filter(|u| u.status == StatusUnidade::Falha)
+                .map(|u| u.membro.as_str())
+                .collect();
+            Err(erro.context(format!(
+                "{} membro(s) do workspace falharam: {}",
+                falharam.len(),
+                falharam.join(", ")
+            )))
+        }
+    }
+}
+
+/// Pasta de cache do workspace, relativa a raiz dele; `clean --tudo` a remove.
+pub const PASTA_CACHE: &str = ".pordosol/cache";
+
+/// Subpasta de [`PASTA_CACHE`] com uma pasta por dependencia compilada, nomeada pela chave.
+const PASTA_CACHE_DEPENDENCIAS: &str = "deps";
+
+/// Registro de uma dependencia compilada, dentro da pasta da chave dela.
+const ARQUIVO_ENTRADA: &str = "entrada.json";
+
+/// Artefatos que a compilacao de uma dependencia deixou na pasta da chave dela.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct EntradaDependencia {
+    dependencia: String,
+    /// Relativos a pasta da chave, como no manifest.json
+    artefatos: Vec<ArquivoManifesto>,
+}
+
+/// `cache_dependencias` do `pordosol.workspace`: cada dependencia dos membros e compilada uma
+/// vez por chave (impressao da raiz dela, target, compilador) e os artefatos entram nas chamadas
+/// dos membros no lugar das fontes. Fica em [`PASTA_CACHE`], entre invocacoes.
+pub struct CacheDependencias {
+    pasta: PathBuf,
+    /// Uma trava por chave: membros em paralelo esperam a primeira compilacao em vez de repeti-la
+    travas: Mutex<BTreeMap<String, Arc<Mutex<()>>>>,
+}
+
+impl CacheDependencias {
+    pub fn abrir(raiz_ws: &Path) -> Self {
+        CacheDependencias {
+            pasta: raiz_ws.join(PASTA_CACHE).join(PASTA_CACHE_DEPENDENCIAS),
+            travas: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    fn trava(&self, chave: &str) -> Arc<Mutex<()>> {
+        self.travas
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(chave.to_string())
+            .or_default()
+            .clone()
+    }
+
+    /// Artefatos da `dependencia` com esta `chave`: os do cache, se estiverem intactos, ou os que
+    /// `compilar` gravar na pasta recebida (vazia). A situacao diz qual dos dois aconteceu.
+    pub fn obter<F>(
+        &self,
+        chave: &str,
+        dependencia: &str,
+        compilar: F,
+    ) -> Result<(Vec<PathBuf>, SituacaoCache)>
+    where
+        F: FnOnce(&Path) -> Result<()>,
+    {
+        let trava = self.trava(chave);
+        let _guarda = trava.lock().unwrap_or_else(|e| e.into_inner());
+        let pasta = self.pasta.join(chave);
+        let registro = pasta.join(ARQUIVO_ENTRADA);
+        let entrada: Option<EntradaDependencia> = fs::read_to_string(&registro)
+            .ok()
+            .and_then(|texto| serde_json::from_str(&texto).ok());
+        if let Some(entrada) = entrada.filter(|e| {
+            !e.artefatos.is_empty()
+                && e.artefatos.iter().all(|a| {
+                    Impressao::de_arquivo(&pasta.join(&a.caminho))
+                        .is_ok_and(|atual| atual == a.impressao)
+                })
+        }) {
+            let artefatos = entrada
+                .artefatos
+                .iter()
+                .map(|a| pasta.join(&a.caminho))
+                .collect();
+            return Ok((artefatos, SituacaoCache::Reaproveitado));
+        }
+
+        if pasta.exists() {
+            fs::remove_dir_all(&pasta)
+                .with_context(|| format!("Falha ao remover diretorio {}", pasta.display()))?;
+        }
+        fs::create_dir_all(&pasta)
+            .with_context(|| format!("Falha ao criar {}", pasta.display()))?;
+        let compilado = compilar(&pasta);
+        let mut artefatos: Vec<PathBuf> = match compilado {
+            Ok(()) => WalkDir::new(&pasta)
+                .min_depth(1)
+                .into_iter()
+                .filter_map(|e| e.ok())
+                .filter(|e| e.file_type().is_file())
+                .map(|e| e.into_path())
+                .collect(),
+            Err(e) => {
+                // Sem registro, a pasta nao serve para a proxima invocacao
+                fs::remove_dir_all(&pasta).ok();
+                return Err(e);
+            }
+        };
+        artefatos.sort();
+        if artefatos.is_empty() {
+            fs::remove_dir_all(&pasta).ok();
+            anyhow::bail!(
+                "A compilacao da dependencia {} nao gerou artefatos em {}",
+                dependencia,
+                pasta.display()
+            );
+        }
+        let entrada = EntradaDependencia {
+            dependencia: dependencia.to_string(),
+            artefatos: artefatos
+                .iter()
+                .map(|a| {
+                    Ok(ArquivoManifesto {
+                        caminho: a
+                            .strip_prefix(&pasta)
+                            .unwrap_or(a)
+                            .to_string_lossy()
+                            .replace('\\', "/"),
+                        impressao: Impressao::de_arquivo(a)?,
+                    })
+                })
+                .collect::<Result<_>>()?,
+        };
+        fs::write(&registro, serde_json::to_string_pretty(&entrada)? + "\n")
+            .with_context(|| format!("Falha ao gravar {}", registro.display()))?;
+        Ok((artefatos, SituacaoCache::Recompilado))
+    }
+}
+
+/// `clean --tudo`: remove [`PASTA_CACHE`] da raiz do workspace (ou so a mostra, com `dry_run`).
+pub fn limpar_cache(raiz_ws: &Path, dry_run: bool) -> Result<()> {
+    let pasta = raiz_ws.join(PASTA_CACHE);
+    if !pasta.exists() {
+        progresso!("Cache do workspace {} nao existe", pasta.display());
+        return Ok(());
+    }
+    if dry_run {
+        println!(
+            "Dry-run: o cache do workspace {} seria removido",
+            pasta.display()
+        );
+        return Ok(());
+    }
+    let bytes: u64 = WalkDir::new(&pasta)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter_map(|e| e.metadata().ok())
+        .filter(|m| m.is_file())
+        .map(|m| m.len())
+        .sum();
+    fs::remove_dir_all(&pasta)
+        .with_context(|| format!("Falha ao remover diretorio {}", pasta.display()))?;
+    crate::resultado::acrescentar(
+        "removidos",
+        serde_json::json!({ "caminho": pasta, "bytes": bytes }),
+    );
+    progresso!("Cache do workspace removido: {}", pasta.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn membro(ws: &Path, relativo: &str, proj: &str) {
+        let pasta = ws.join(relativo);
+        fs::create_dir_all(pasta.join("src")).unwrap();
+        fs::write(pasta.join("pordosol.proj"), proj).unwrap();
+    }
+
+    fn workspace_exemplo() -> tempfile::TempDir {
+        let temp = tempfile::tempdir().unwrap();
+        let ws = temp.path();
+        fs::write(
+            ws.join(ARQUIVO_WORKSPACE),
+            r#"{"membros": ["servicos/*", "lib/comum"]}"#,
+        )
+        .unwrap();
+        membro(
+            ws,
+            "servicos/api",
+            r#"{"nome": "api", "dependencias": {"comum": {"path": "../../lib/comum"}}}"#,
+        );
+        membro(ws, "servicos/worker", r#"{"nome": "worker"}"#);
+        membro(ws, "lib/comum", r#"{"nome": "comum"}"#);
+        // Fora dos globs e sem pordosol.proj: nao sao membros
+        membro(ws, "ferramentas/script", r#"{"nome": "script"}"#);
+        fs::create_dir_all(ws.join("servicos").join("docs")).unwrap();
+        temp
+    }
+
+    fn nomes(lista: &[Membro]) -> Vec<&str> {
+        lista.iter().map(|m| m.nome.as_str()).collect()
+    }
+
+    #[test]
+    fn membros_casam_globs_e_dependencias_vem_primeiro() {
+        let temp = workspace_exemplo();
+        let lista = membros(temp.path()).unwrap();
+        assert_eq!(nomes(&lista), ["comum", "api", "worker"]);
+        assert_eq!(lista[1].relativo, "servicos/api");
+
+        assert_eq!(
+            localizar_workspace(&temp.path().join("servicos").join("api").join("src")),
+            Some(temp.path().to_path_buf())
+        );
+        assert!(eh_raiz_workspace(temp.path()));
+        assert!(!eh_raiz_workspace(&temp.path().join("lib").join("comum")));
+    }
+
+    #[test]
+    fn membros_rejeita_ciclo_e_nome_repetido() {
+        let temp = workspace_exemplo();
+        membro(
+            temp.path(),
+            "lib/comum",
+            r#"{"nome": "comum", "dependencias": {"api": {"path": "../../servicos/api"}}}"#,
+        );
+        let erro = membros(temp.path()).unwrap_err().to_string();
+        assert!(erro.contains("Ciclo"), "{}", erro);
+        assert!(erro.contains("api") && erro.contains("comum"), "{}", erro);
+
+        membro(temp.path(), "lib/comum", r#"{"nome": "worker"}"#);
+        let erro = membros(temp.path()).unwrap_err().to_string();
+        assert!(erro.contains("mesmo nome 'worker'"), "{}", erro);
+    }
+
+    #[test]
+    fn executar_em_membros_para_no_primeiro_erro_ou_continua() {
+        let temp = workspace_exemplo();
+        let lista = membros(temp.path()).unwrap();
+        let visitados = Mutex::new(Vec::new());
+        let falha_em_api = |m: &Membro| {
+            visitados.lock().unwrap().push(m.nome.clone());
+            if m.nome == "api" {
+                Err(ErroCli::ToolchainAusente("sem compilador".to_string()).into())
+            } else {
+                Ok(None)
+            }
+        };
+
+        let opcoes = OpcoesMembros::em_ordem("bytecode", false);
+        let erro = executar_em_membros(&lista, &opcoes, falha_em_api).unwrap_err();
+        assert_eq!(*visitados.lock().unwrap(), ["comum", "api"]);
+        assert_eq!(crate::erros::codigo_saida(&erro), 2);
+        assert!(erro.to_string().contains("1 membro(s)"), "{}", erro);
+
+        visitados.lock().unwrap().clear();
+        let opcoes = OpcoesMembros::em_ordem("bytecode", true);
+        let erro = executar_em_membros(&lista, &opcoes, falha_em_api).unwrap_err();
+        assert_eq!(*visitados.lock().unwrap(), ["comum", "api", "worker"]);
+        assert!(
+            erro.to_string()
+                .starts_with("1 membro(s) do workspace falharam: api"),
+            "{}",
+            erro
+        );
+    }
+
+    #[test]
+    fn executar_em_membros_em_paralelo_roda_todos() {
+        let temp = workspace_exemplo();
+        let lista = membros(temp.path()).unwrap();
+        let visitados = Mutex::new(Vec::new());
+        let opcoes = OpcoesMembros {
+            paralelos: 3,
+            ..OpcoesMembros::em_ordem("llvm-ir", false)
+        };
+        executar_em_membros(&lista, &opcoes, |m| {
+            visitados.lock().unwrap().push(m.nome.clone());
+            Ok(Some(SituacaoCache::Recompilado))
+        })
+        .unwrap();
+        let mut visitados = visitados.into_inner().unwrap();
+        visitados.sort();
+        assert_eq!(visitados, ["api", "comum", "worker"]);
+    }
+
+    #[test]
+    fn cache_de_dependencias_compila_uma_vez_por_chave() {
+        let temp = workspace_exemplo();
+        let compilacoes = AtomicUsize::new(0);
+        let compilar = |pasta: &Path| {
+            compilacoes.fetch_add(1, Ordering::SeqCst);
+            fs::write(pasta.join("comum.ll"), "; ir").map_err(Into::into)
+        };
+
+        let cache = CacheDependencias::abrir(temp.path());
+        let (artefatos, situacao) = cache.obter("abc", "comum", compilar).unwrap();
+        assert_eq!(situacao, SituacaoCache::Recompilado);
+        assert_eq!(artefatos.len(), 1);
+        assert!(artefatos[0].ends_with("abc/comum.ll"));
+
+        let cache = CacheDependencias::abrir(temp.path());
+        let (reusados, situacao) = cache.obter("abc", "comum", compilar).unwrap();
+        assert_eq!(situacao, SituacaoCache::Reaproveitado);
+        assert_eq!(reusados, artefatos);
+        assert_eq!(compilacoes.load(Ordering::SeqCst), 1);
+
+        // Outra chave ou artefato adulterado: compila de novo
+        cache.obter("def", "comum", compilar).unwrap();
+        fs::write(&artefatos[0], "; alterado").unwrap();
+        let (_, situacao) = cache.obter("abc", "comum", compilar).unwrap();
+        assert_eq!(situacao, SituacaoCache::Recompilado);
+        assert_eq!(compilacoes.load(Ordering::SeqCst), 3);
+
+        let erro = cache.obter("vazia", "comum", |_| Ok(())).unwrap_err();
+        assert!(erro.to_string().contains("nao gerou artefatos"), "{}", erro);
+
+        limpar_cache(temp.path(), false).unwrap();
+        assert!(!temp.path().join(PASTA_CACHE).exists());
+        let (_, situacao) = cache.obter("abc", "comum", compilar).unwrap();
+        assert_eq!(situacao, SituacaoCache::Recompilado);
+    }
+}
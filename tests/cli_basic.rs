@@ -51,6 +51,45 @@ fn cria_projeto_novo_modo_legado() {
     assert!(programa.exists(), "programa.pr deve existir");
 }
 
+#[test]
+fn new_dry_run_nao_grava_nada() {
+    let bin = bin_path();
+    let temp = tempfile::tempdir().unwrap();
+    let dir = temp.path().join("app");
+
+    let out = Command::new(&bin)
+        .args(["new", "console", "-o"])
+        .arg(&dir)
+        .arg("--dry-run")
+        .output()
+        .expect("run new --dry-run");
+    assert!(out.status.success());
+    let s = String::from_utf8_lossy(&out.stdout);
+    assert!(s.contains("Criaria"), "saida: {}", s);
+    assert!(s.contains("programa.pr"), "saida: {}", s);
+    assert!(!dir.exists(), "dry-run nao deve criar a pasta do projeto");
+
+    let status = Command::new(&bin)
+        .args(["new", "console", "-o"])
+        .arg(&dir)
+        .status()
+        .expect("run new");
+    assert!(status.success());
+    let programa = dir.join("src").join("programa.pr");
+    fs::write(&programa, "// editado").unwrap();
+
+    let out = Command::new(&bin)
+        .args(["new", "console", "-o"])
+        .arg(&dir)
+        .arg("--dry-run")
+        .output()
+        .expect("run new --dry-run sobre projeto existente");
+    assert!(out.status.success());
+    let s = String::from_utf8_lossy(&out.stdout);
+    assert!(s.contains("Sobrescreveria"), "saida: {}", s);
+    assert_eq!(fs::read_to_string(&programa).unwrap(), "// editado");
+}
+
 #[test]
 fn cria_projeto_new_estilo_dotnet() {
     let bin = bin_path();
@@ -117,6 +156,233 @@ fn new_list_exibe_templates_disponiveis() {
     assert!(s.contains("web"));
 }
 
+#[test]
+fn templates_add_instala_em_pordosol_home_e_exige_force() {
+    let bin = bin_path();
+    let temp = tempfile::tempdir().unwrap();
+    let home = temp.path().join("home");
+    let origem = temp.path().join("meuapp");
+    fs::create_dir_all(&home).unwrap();
+    fs::create_dir_all(origem.join("src")).unwrap();
+
+    let add = |args: &[&str]| {
+        Command::new(&bin)
+            .args(["templates", "add"])
+            .arg(&origem)
+            .args(args)
+            .env("PORDOSOL_HOME", &home)
+            .env_remove("PORDOSOL_TEMPLATES_PATH")
+            .output()
+            .expect("run templates add")
+    };
+
+    let out = add(&[]);
+    assert!(!out.status.success());
+    assert!(
+        String::from_utf8_lossy(&out.stderr).contains("nao contem nenhum arquivo"),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+
+    fs::write(
+        origem.join("src").join("programa.pr"),
+        "// v1 {{PROJECT_NAME}}\n",
+    )
+    .unwrap();
+    let out = add(&[]);
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    let instalado = home.join("templates").join("meuapp");
+    assert!(instalado.join("src").join("programa.pr").is_file());
+
+    let out = Command::new(&bin)
+        .args(["new", "list"])
+        .env("PORDOSOL_HOME", &home)
+        .env_remove("PORDOSOL_TEMPLATES_PATH")
+        .output()
+        .expect("run new list");
+    assert!(String::from_utf8_lossy(&out.stdout).contains("  meuapp"));
+
+    fs::write(origem.join("src").join("programa.pr"), "// v2\n").unwrap();
+    let out = add(&[]);
+    assert!(!out.status.success());
+    assert!(String::from_utf8_lossy(&out.stderr).contains("--forcar"));
+    assert!(
+        fs::read_to_string(instalado.join("src").join("programa.pr"))
+            .unwrap()
+            .contains("v1")
+    );
+
+    let out = add(&["--force"]);
+    assert!(out.status.success());
+    assert_eq!(
+        fs::read_to_string(instalado.join("src").join("programa.pr")).unwrap(),
+        "// v2\n"
+    );
+}
+
+#[test]
+fn templates_instalar_de_git_lista_e_remove() {
+    if Command::new("git").arg("--version").output().is_err() {
+        eprintln!("git ausente, pulando teste");
+        return;
+    }
+    let bin = bin_path();
+    let temp = tempfile::tempdir().unwrap();
+    let destino = temp.path().join("meus-templates");
+    let repo = temp.path().join("templates-do-time");
+    for nome in ["api", "worker"] {
+        fs::create_dir_all(repo.join(nome).join("src")).unwrap();
+        fs::write(
+            repo.join(nome).join("src").join("programa.pr.tpl"),
+            "// {{PROJECT_NAME}}\n",
+        )
+        .unwrap();
+    }
+    fs::write(
+        repo.join("worker").join("template.toml"),
+        "descricao = \"Worker em segundo plano\"\n",
+    )
+    .unwrap();
+    fs::write(repo.join("README.md"), "# templates do time\n").unwrap();
+    git(&repo, &["init", "-q"]);
+    git(&repo, &["add", "."]);
+    git(&repo, &["commit", "-q", "-m", "templates"]);
+
+    let pordosol = |args: &[&str]| {
+        Command::new(&bin)
+            .arg("template")
+            .args(args)
+            .env("PORDOSOL_TEMPLATES_PATH", &destino)
+            .output()
+            .expect("run template")
+    };
+
+    let url = format!("file://{}", repo.display());
+    let out = pordosol(&["instalar", &url]);
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    assert!(destino.join("api/src/programa.pr.tpl").is_file());
+    assert!(destino.join("worker/template.toml").is_file());
+    assert!(!destino.join("README.md").exists());
+
+    let out = pordosol(&["listar", "--detalhado"]);
+    let s = String::from_utf8_lossy(&out.stdout);
+    assert!(s.contains("api"), "{}", s);
+    assert!(s.contains("Worker em segundo plano"), "{}", s);
+
+    // Um template ja instalado bloqueia o lote inteiro sem --forcar
+    let out = pordosol(&["instalar", &url]);
+    assert!(!out.status.success());
+    assert!(String::from_utf8_lossy(&out.stderr).contains("--forcar"));
+    assert!(pordosol(&["instalar", &url, "--forcar"]).status.success());
+
+    assert!(pordosol(&["remover", "api"]).status.success());
+    assert!(!destino.join("api").exists());
+    assert!(destino.join("worker").is_dir());
+    let out = pordosol(&["remover", "api"]);
+    assert!(!out.status.success());
+    assert!(String::from_utf8_lossy(&out.stderr).contains("nao esta instalado"));
+}
+
+#[test]
+fn new_template_com_manifesto_exige_variaveis() {
+    let bin = bin_path();
+    let temp = tempfile::tempdir().unwrap();
+    let templates = temp.path().join("templates");
+    let tpl = templates.join("lib");
+    fs::create_dir_all(tpl.join("src")).unwrap();
+    fs::write(
+        tpl.join("template.toml"),
+        "# manifesto\ndescricao = \"Biblioteca com licenca\"\nautor = \"Ana\"\nversao = \"0.2.0\"\nvariaveis = [\n  \"LICENCA\",\n  \"PROJECT_NAME\",\n]\n",
+    )
+    .unwrap();
+    fs::write(
+        tpl.join("src").join("lib.pr.tpl"),
+        "// {{PROJECT_NAME}} - {{LICENCA}}\n",
+    )
+    .unwrap();
+
+    let out = Command::new(&bin)
+        .args(["new", "list", "--detalhado"])
+        .env("PORDOSOL_TEMPLATES_PATH", &templates)
+        .output()
+        .expect("run new list --detalhado");
+    let s = String::from_utf8_lossy(&out.stdout);
+    assert!(
+        s.contains("lib   0.2.0   Biblioteca com licenca (por Ana)"),
+        "{}",
+        s
+    );
+
+    let novo = |args: &[&str]| {
+        Command::new(&bin)
+            .args(["new", "lib", "-n", "minha", "-o"])
+            .arg(temp.path())
+            .args(args)
+            .env("PORDOSOL_TEMPLATES_PATH", &templates)
+            .output()
+            .expect("run new lib")
+    };
+
+    let out = novo(&[]);
+    assert!(!out.status.success());
+    assert!(String::from_utf8_lossy(&out.stderr).contains("LICENCA"));
+
+    let out = novo(&["--var", "LICENCA=MIT"]);
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    let projeto = temp.path().join("minha");
+    assert_eq!(
+        fs::read_to_string(projeto.join("src").join("lib.pr")).unwrap(),
+        "// minha - MIT\n"
+    );
+    assert!(!projeto.join("template.toml").exists());
+}
+
+#[test]
+fn new_template_remove_bom_e_mantem_quebras_de_linha() {
+    let bin = bin_path();
+    let temp = tempfile::tempdir().unwrap();
+    let templates = temp.path().join("templates");
+    let tpl = templates.join("win");
+    fs::create_dir_all(tpl.join("src")).unwrap();
+    fs::write(
+        tpl.join("LEIAME.md.tpl"),
+        b"\xEF\xBB\xBF{{PROJECT_NAME}}\r\n\r\nFeito no Windows.\r\n",
+    )
+    .unwrap();
+    let binario = [0xEFu8, 0xBB, 0xBF, 0xFF, 0x00, 0x7B, 0x7B];
+    fs::write(tpl.join("icone.bin"), binario).unwrap();
+
+    let out = Command::new(&bin)
+        .args(["new", "win", "-n", "janela", "-o"])
+        .arg(temp.path())
+        .env("PORDOSOL_TEMPLATES_PATH", &templates)
+        .output()
+        .expect("run new win");
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    let projeto = temp.path().join("janela");
+    assert_eq!(
+        fs::read(projeto.join("LEIAME.md")).unwrap(),
+        b"janela\r\n\r\nFeito no Windows.\r\n"
+    );
+    assert_eq!(fs::read(projeto.join("icone.bin")).unwrap(), binario);
+}
+
 #[test]
 fn new_console_renderiza_placeholders() {
     let bin = bin_path();
@@ -148,12 +414,41 @@ fn new_console_renderiza_placeholders() {
     assert!(readme_txt.contains("`Meu.App`"));
 }
 
+#[test]
+fn new_namespace_escapa_palavras_reservadas() {
+    let bin = bin_path();
+    let temp = tempfile::tempdir().unwrap();
+
+    for (pasta, esperado) in [
+        ("classe", "Classe_"),
+        ("funcao", "Funcao_"),
+        ("meu-publico", "Meu.Publico_"),
+        ("classes", "Classes"),
+    ] {
+        let status = Command::new(&bin)
+            .args(["new", "console", "-n", pasta, "-o"])
+            .arg(temp.path())
+            .status()
+            .expect("run new console");
+        assert!(status.success());
+        let programa =
+            fs::read_to_string(temp.path().join(pasta).join("src").join("programa.pr")).unwrap();
+        assert!(
+            programa.starts_with(&format!("// Namespace: {}\n", esperado)),
+            "{}: {}",
+            pasta,
+            programa
+        );
+    }
+}
+
 #[test]
 fn doctor_exibe_diagnostico() {
     let bin = bin_path();
 
     let out = Command::new(&bin)
         .arg("doctor")
+        .arg("--nao-falhar")
         .output()
         .expect("run doctor");
     assert!(out.status.success());
@@ -198,3 +493,1162 @@ fn workflows_e_scripts_release_existem() {
     assert!(raiz.join("MATRIZ-COMPATIBILIDADE.md").exists());
     assert!(raiz.join("CHECKLIST-RELEASE.md").exists());
 }
+
+#[test]
+fn toolchain_ausente_lista_os_locais_tentados() {
+    let bin = bin_path();
+    let temp = tempfile::tempdir().unwrap();
+    let projeto = temp.path().join("proj");
+    fs::create_dir_all(projeto.join("src")).unwrap();
+    fs::write(projeto.join("src").join("programa.pr"), "// vazio\n").unwrap();
+    let inexistente = temp.path().join("nao-existe");
+
+    let out = Command::new(&bin)
+        .arg("build")
+        .arg(&projeto)
+        .env("PORDOSOL_COMPILADOR_PATH", &inexistente)
+        .env_remove("PORDOSOL_HOME")
+        .output()
+        .expect("run build");
+    assert_eq!(out.status.code(), Some(2));
+    let err = String::from_utf8_lossy(&out.stderr);
+    assert!(
+        err.contains("Compilador nao encontrado. Locais tentados:"),
+        "{}",
+        err
+    );
+    assert!(
+        err.contains(&format!(
+            "- {} (env:PORDOSOL_COMPILADOR_PATH (invalido))",
+            inexistente.display()
+        )),
+        "{}",
+        err
+    );
+    assert!(err.contains("(fallback:./lib (ausente))"), "{}", err);
+
+    let out = Command::new(&bin)
+        .arg("info")
+        .arg(&projeto)
+        .env("PORDOSOL_COMPILADOR_PATH", &inexistente)
+        .env_remove("PORDOSOL_HOME")
+        .output()
+        .expect("run info");
+    let s = String::from_utf8_lossy(&out.stdout);
+    assert!(s.contains("  Locais tentados:"), "{}", s);
+    assert!(s.contains("(fallback:./lib (ausente))"), "{}", s);
+}
+
+#[test]
+fn codigos_de_saida_distintos() {
+    let bin = bin_path();
+    let temp = tempfile::tempdir().unwrap();
+    let projeto = temp.path().join("proj");
+    fs::create_dir_all(projeto.join("src")).unwrap();
+    fs::write(projeto.join("src").join("programa.pr"), "// vazio\n").unwrap();
+
+    let out = Command::new(&bin)
+        .arg("build")
+        .arg(&projeto)
+        .env("PORDOSOL_COMPILADOR_PATH", temp.path().join("nao-existe"))
+        .env_remove("PORDOSOL_HOME")
+        .output()
+        .expect("run build");
+    assert_eq!(out.status.code(), Some(2), "toolchain ausente");
+
+    let out = Command::new(&bin)
+        .arg("dep")
+        .arg("list")
+        .arg("--caminho-projeto")
+        .arg(&projeto)
+        .output()
+        .expect("run dep");
+    assert_eq!(out.status.code(), Some(3), "pordosol.proj ausente");
+
+    fs::write(projeto.join("pordosol.proj"), "[1, 2]").unwrap();
+    let out = Command::new(&bin)
+        .arg("dep")
+        .arg("list")
+        .arg("--caminho-projeto")
+        .arg(&projeto)
+        .output()
+        .expect("run dep");
+    assert_eq!(out.status.code(), Some(3), "pordosol.proj invalido");
+}
+
+fn git(dir: &std::path::Path, args: &[&str]) {
+    let status = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .env("GIT_AUTHOR_NAME", "teste")
+        .env("GIT_AUTHOR_EMAIL", "teste@exemplo.com")
+        .env("GIT_COMMITTER_NAME", "teste")
+        .env("GIT_COMMITTER_EMAIL", "teste@exemplo.com")
+        .status()
+        .expect("run git");
+    assert!(status.success(), "git {:?}", args);
+}
+
+#[test]
+fn new_from_git_clona_e_renomeia() {
+    if Command::new("git").arg("--version").output().is_err() {
+        eprintln!("git ausente, pulando teste");
+        return;
+    }
+    let bin = bin_path();
+    let temp = tempfile::tempdir().unwrap();
+    let origem = temp.path().join("exemplo");
+    fs::create_dir_all(origem.join("src")).unwrap();
+    fs::write(
+        origem.join("pordosol.proj"),
+        "{\n    \"nome\": \"exemplo\",\n    \"tipo\": \"console\"\n}\n",
+    )
+    .unwrap();
+    fs::write(origem.join("src").join("programa.pr"), "// v1\n").unwrap();
+    git(&origem, &["init", "--quiet"]);
+    git(&origem, &["add", "-A"]);
+    git(&origem, &["commit", "--quiet", "-m", "v1"]);
+    git(&origem, &["tag", "v1"]);
+    fs::write(origem.join("src").join("programa.pr"), "// v2\n").unwrap();
+    git(&origem, &["commit", "--quiet", "-am", "v2"]);
+
+    let destino = temp.path().join("minha_app");
+    let out = Command::new(&bin)
+        .arg("new")
+        .arg(&destino)
+        .arg("--from-git")
+        .arg(&origem)
+        .arg("--rev")
+        .arg("v1")
+        .output()
+        .expect("run new --from-git");
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    assert!(!destino.join(".git").exists());
+    assert_eq!(
+        fs::read_to_string(destino.join("src").join("programa.pr")).unwrap(),
+        "// v1\n"
+    );
+    let proj = fs::read_to_string(destino.join("pordosol.proj")).unwrap();
+    assert!(proj.contains("\"nome\": \"minha_app\""), "{}", proj);
+    assert!(proj.find("\"nome\"").unwrap() < proj.find("\"tipo\"").unwrap());
+
+    let falho = temp.path().join("falho");
+    let out = Command::new(&bin)
+        .arg("new")
+        .arg(&falho)
+        .arg("--from-git")
+        .arg(temp.path().join("nao-existe"))
+        .output()
+        .expect("run new --from-git invalido");
+    assert!(!out.status.success());
+    assert!(!falho.exists(), "diretorio parcial deve ser removido");
+}
+
+#[test]
+fn offline_bloqueia_clone_remoto_antes_de_tentar() {
+    let bin = bin_path();
+    let temp = tempfile::tempdir().unwrap();
+    let destino = temp.path().join("app");
+
+    let out = Command::new(&bin)
+        .arg("new")
+        .arg(&destino)
+        .args([
+            "--from-git",
+            "https://example.invalid/modelo.git",
+            "--offline",
+        ])
+        .output()
+        .expect("run new --from-git --offline");
+    assert!(!out.status.success());
+    let err = String::from_utf8_lossy(&out.stderr);
+    assert!(err.contains("--offline esta ativo"), "{}", err);
+    assert!(
+        err.contains("https://example.invalid/modelo.git"),
+        "{}",
+        err
+    );
+    assert!(!destino.exists());
+}
+
+#[test]
+fn doctor_json_serializa_diagnostico() {
+    let bin = bin_path();
+
+    let out = Command::new(&bin)
+        .arg("doctor")
+        .arg("--json")
+        .arg("--nao-falhar")
+        .output()
+        .expect("run doctor --json");
+    assert!(out.status.success());
+
+    let json: serde_json::Value = serde_json::from_slice(&out.stdout).expect("json valido");
+    for chave in ["compilador", "interpretador", "stdlib"] {
+        let item = &json[chave];
+        assert!(item["nome"].is_string(), "{}", chave);
+        assert!(item["caminho"].is_string(), "{}", chave);
+        assert!(item["origem"].is_string(), "{}", chave);
+        assert!(item["encontrado"].is_boolean(), "{}", chave);
+    }
+    assert!(json["pronto"].is_boolean());
+}
+
+#[test]
+fn doctor_sem_toolchain_sai_com_codigo_2() {
+    let bin = bin_path();
+    let temp = tempfile::tempdir().unwrap();
+    let doctor = |extras: &[&str]| {
+        Command::new(&bin)
+            .arg("doctor")
+            .arg("--json")
+            .args(extras)
+            .current_dir(temp.path())
+            .env("HOME", temp.path())
+            .env("PATH", temp.path())
+            .env_remove("PORDOSOL_HOME")
+            .env_remove("PORDOSOL_COMPILADOR_PATH")
+            .env_remove("PORDOSOL_INTERPRETADOR_PATH")
+            .env_remove("PORDOSOL_STDLIB_PATH")
+            .env_remove("PORDOSOL_BIBLIOTECA_PADRAO_PATH")
+            .output()
+            .expect("run doctor --json")
+    };
+
+    let out = doctor(&[]);
+    assert_eq!(out.status.code(), Some(2));
+    let json: serde_json::Value = serde_json::from_slice(&out.stdout).expect("json valido");
+    assert_eq!(json["pronto"], false);
+    for chave in ["compilador", "interpretador", "stdlib"] {
+        assert_eq!(json[chave]["encontrado"], false, "{}", chave);
+        assert!(json[chave]["origem"].is_string(), "{}", chave);
+        assert!(json[chave].get("versao").is_some(), "{}", chave);
+    }
+
+    assert!(doctor(&["--nao-falhar"]).status.success());
+}
+
+#[test]
+fn changelog_agrupa_commits_desde_tag() {
+    if Command::new("git").arg("--version").output().is_err() {
+        eprintln!("git ausente, pulando teste");
+        return;
+    }
+    let bin = bin_path();
+    let temp = tempfile::tempdir().unwrap();
+    let proj = temp.path().join("app");
+    fs::create_dir_all(&proj).unwrap();
+    fs::write(
+        proj.join("pordosol.proj"),
+        "{\n    \"nome\": \"app\",\n    \"versao\": \"1.1.0\"\n}\n",
+    )
+    .unwrap();
+    git(&proj, &["init", "--quiet"]);
+    git(&proj, &["add", "-A"]);
+    git(&proj, &["commit", "--quiet", "-m", "inicial"]);
+    git(&proj, &["tag", "v1.0.0"]);
+    for (arquivo, msg) in [
+        ("a.pr", "feat(cli): comando novo"),
+        ("b.pr", "fix: corrige saida"),
+        ("c.pr", "Atualiza documentacao"),
+    ] {
+        fs::write(proj.join(arquivo), "// x\n").unwrap();
+        git(&proj, &["add", "-A"]);
+        git(&proj, &["commit", "--quiet", "-m", msg]);
+    }
+
+    let out = Command::new(&bin)
+        .args(["changelog", "--desde", "v1.0.0", "--dry-run"])
+        .arg(&proj)
+        .output()
+        .expect("run changelog --dry-run");
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    let secao = String::from_utf8_lossy(&out.stdout);
+    assert!(secao.starts_with("## [1.1.0] - "), "{}", secao);
+    assert!(
+        secao.contains("### Novidades\n\n- comando novo"),
+        "{}",
+        secao
+    );
+    assert!(
+        secao.contains("### Correcoes\n\n- corrige saida"),
+        "{}",
+        secao
+    );
+    assert!(
+        secao.contains("### Outros\n\n- Atualiza documentacao"),
+        "{}",
+        secao
+    );
+    assert!(!secao.contains("inicial"), "{}", secao);
+    assert!(!proj.join("CHANGELOG.md").exists());
+
+    // Tag inexistente: avisa e cai para a ultima mudanca de versao do pordosol.proj
+    let out = Command::new(&bin)
+        .args(["changelog", "--desde", "v9.9.9", "--versao", "1.2.0"])
+        .arg(&proj)
+        .output()
+        .expect("run changelog");
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    assert!(String::from_utf8_lossy(&out.stderr).contains("nao encontrada"));
+    let changelog = fs::read_to_string(proj.join("CHANGELOG.md")).unwrap();
+    assert!(
+        changelog.starts_with("# Changelog\n\n## [1.2.0] - "),
+        "{}",
+        changelog
+    );
+    assert!(changelog.contains("- comando novo"), "{}", changelog);
+    assert!(!changelog.contains("inicial"), "{}", changelog);
+}
+
+#[test]
+fn clean_dry_run_json_lista_sem_remover() {
+    let bin = bin_path();
+    let temp = tempfile::tempdir().unwrap();
+    let proj = temp.path().join("app");
+    fs::create_dir_all(proj.join("build").join("producao")).unwrap();
+    fs::write(proj.join("pordosol.proj"), "{\n    \"nome\": \"app\"\n}\n").unwrap();
+    fs::write(proj.join("build").join("app.pbc"), "1234").unwrap();
+    fs::write(
+        proj.join("build").join("producao").join("app.pbc"),
+        "123456",
+    )
+    .unwrap();
+
+    let out = Command::new(&bin)
+        .args(["clean", "--dry-run", "--json"])
+        .arg(&proj)
+        .output()
+        .expect("run clean --dry-run --json");
+    assert!(out.status.success());
+    let v: serde_json::Value = serde_json::from_slice(&out.stdout).unwrap();
+    assert_eq!(v["dry_run"], true);
+    assert_eq!(v["itens"].as_array().unwrap().len(), 2);
+    assert_eq!(v["total_bytes"], 10);
+    assert!(
+        proj.join("build").join("app.pbc").exists(),
+        "dry-run nao remove"
+    );
+
+    let out = Command::new(&bin)
+        .args(["clean", "--json"])
+        .arg(&proj)
+        .output()
+        .expect("run clean --json");
+    assert!(!out.status.success(), "--json exige --dry-run");
+
+    let out = Command::new(&bin)
+        .arg("clean")
+        .arg(&proj)
+        .output()
+        .expect("run clean");
+    assert!(out.status.success());
+    assert_eq!(fs::read_dir(proj.join("build")).unwrap().count(), 0);
+
+    // Sem pasta de saida o dry-run continua saindo com 0
+    fs::remove_dir_all(proj.join("build")).unwrap();
+    let out = Command::new(&bin)
+        .args(["clean", "--dry-run", "--json"])
+        .arg(&proj)
+        .output()
+        .expect("run clean --dry-run --json");
+    assert!(out.status.success());
+    let v: serde_json::Value = serde_json::from_slice(&out.stdout).unwrap();
+    assert_eq!(v["total_bytes"], 0);
+}
+
+#[test]
+fn rename_atualiza_nome_namespace_e_readme() {
+    let bin = bin_path();
+    let temp = tempfile::tempdir().unwrap();
+    let status = Command::new(&bin)
+        .args(["new", "console", "-n", "loja_antiga", "-o"])
+        .arg(temp.path())
+        .status()
+        .expect("run new");
+    assert!(status.success());
+    let proj = temp.path().join("loja_antiga");
+
+    let out = Command::new(&bin)
+        .args(["rename", "minha-loja", "--readme", "--project"])
+        .arg(&proj)
+        .output()
+        .expect("run rename");
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    assert!(String::from_utf8_lossy(&out.stderr).contains("nao foi renomeada"));
+
+    let conf: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(proj.join("pordosol.proj")).unwrap()).unwrap();
+    assert_eq!(conf["nome"], "minha-loja");
+    let programa = fs::read_to_string(proj.join("src").join("programa.pr")).unwrap();
+    assert!(
+        programa.starts_with("// Namespace: Minha.Loja\n"),
+        "{}",
+        programa
+    );
+    let readme = fs::read_to_string(proj.join("README.md")).unwrap();
+    assert!(readme.starts_with("# minha-loja"), "{}", readme);
+    assert!(readme.contains("`Minha.Loja`"), "{}", readme);
+    assert!(!readme.contains("loja_antiga") && !readme.contains("Loja.Antiga"));
+
+    let out = Command::new(&bin)
+        .args(["rename", "%%", "--project"])
+        .arg(&proj)
+        .output()
+        .expect("run rename invalido");
+    assert!(!out.status.success());
+    assert!(String::from_utf8_lossy(&out.stderr).contains("namespace valido"));
+}
+
+#[test]
+fn listar_respeita_exclusoes_do_proj_e_da_flag() {
+    let bin = bin_path();
+    let temp = tempfile::tempdir().unwrap();
+    let proj = temp.path().join("app");
+    fs::create_dir_all(proj.join("src").join("gerado")).unwrap();
+    fs::write(
+        proj.join("pordosol.proj"),
+        "{\n    \"nome\": \"app\",\n    \"excluir\": [\"gerado/**\"]\n}\n",
+    )
+    .unwrap();
+    for rel in ["programa.pr", "util_old.pr", "gerado/tabela.pr"] {
+        fs::write(proj.join("src").join(rel), "").unwrap();
+    }
+
+    let listar = |args: &[&str]| {
+        let out = Command::new(&bin)
+            .arg("listar")
+            .arg(&proj)
+            .args(args)
+            .output()
+            .expect("run listar");
+        assert!(out.status.success());
+        String::from_utf8_lossy(&out.stdout).to_string()
+    };
+
+    let s = listar(&["--excluir", "**/*_old.pr"]);
+    assert!(s.contains("programa.pr"), "{}", s);
+    assert!(!s.contains("util_old.pr"), "{}", s);
+    assert!(!s.contains("tabela.pr"), "{}", s);
+
+    let s = listar(&["--excluir", "**/*_old.pr", "--mostrar-excluidos"]);
+    assert!(s.contains("util_old.pr (0 bytes) (excluido)"), "{}", s);
+    assert!(s.contains("tabela.pr (0 bytes) (excluido)"), "{}", s);
+    assert!(!s.contains("programa.pr (0 bytes) (excluido)"), "{}", s);
+
+    // Sem nenhum fonte restante depois dos filtros, a mensagem de projeto vazio aparece
+    let s = listar(&["--excluir", "**/*.pr"]);
+    assert!(s.contains("Nenhum arquivo .pr encontrado"), "{}", s);
+    fs::File::options()
+        .write(true)
+        .open(proj.join("src").join("programa.pr"))
+        .unwrap()
+        .set_modified(std::time::SystemTime::now() - std::time::Duration::from_secs(72 * 3600))
+        .unwrap();
+    let s = listar(&["--excluir", "**/*_old.pr", "--recentes"]);
+    assert!(s.contains("Nenhum arquivo .pr encontrado"), "{}", s);
+}
+
+#[test]
+fn listar_json_marca_preferido_e_respeita_recentes() {
+    let bin = bin_path();
+    let temp = tempfile::tempdir().unwrap();
+    let proj = temp.path().join("app");
+    fs::create_dir_all(proj.join("src")).unwrap();
+    fs::write(proj.join("pordosol.proj"), "{\"nome\": \"app\"}\n").unwrap();
+    fs::write(proj.join("src").join("programa.pr"), "// main\n").unwrap();
+    fs::write(proj.join("src").join("util.pr"), "").unwrap();
+
+    let listar = |args: &[&str]| -> serde_json::Value {
+        let out = Command::new(&bin)
+            .arg("listar")
+            .arg(&proj)
+            .arg("--json")
+            .args(args)
+            .output()
+            .expect("run listar");
+        assert!(out.status.success());
+        serde_json::from_slice(&out.stdout).unwrap()
+    };
+
+    let itens = listar(&[]);
+    let itens = itens.as_array().unwrap();
+    assert_eq!(itens.len(), 2);
+    assert_eq!(itens[0]["caminho"], "src/programa.pr");
+    assert_eq!(itens[0]["bytes"], 8);
+    assert_eq!(itens[0]["preferido"], true);
+    assert!(itens[0]["modificado_epoch"].as_u64().unwrap() > 0);
+    assert_eq!(itens[1]["caminho"], "src/util.pr");
+    assert_eq!(itens[1]["preferido"], false);
+
+    // Recem-criados: --recentes mantem os dois
+    assert_eq!(listar(&["--recentes"]).as_array().unwrap().len(), 2);
+}
+
+#[test]
+fn listar_ordena_por_criterio_e_compoe_com_recentes() {
+    use std::time::{Duration, SystemTime};
+
+    let bin = bin_path();
+    let temp = tempfile::tempdir().unwrap();
+    let proj = temp.path().join("app");
+    fs::create_dir_all(proj.join("src").join("sub")).unwrap();
+    fs::write(proj.join("pordosol.proj"), "{\"nome\": \"app\"}\n").unwrap();
+    let agora = SystemTime::now();
+    for (rel, bytes, idade_h) in [("a.pr", 300, 72), ("b.pr", 10, 1), ("sub/c.pr", 100, 2)] {
+        let caminho = proj.join("src").join(rel);
+        fs::write(&caminho, "x".repeat(bytes)).unwrap();
+        fs::File::options()
+            .write(true)
+            .open(&caminho)
+            .unwrap()
+            .set_modified(agora - Duration::from_secs(idade_h * 3600))
+            .unwrap();
+    }
+
+    let listar = |args: &[&str]| -> Vec<String> {
+        let out = Command::new(&bin)
+            .arg("listar")
+            .arg(&proj)
+            .arg("--json")
+            .args(args)
+            .output()
+            .expect("run listar");
+        assert!(
+            out.status.success(),
+            "{}",
+            String::from_utf8_lossy(&out.stderr)
+        );
+        let itens: serde_json::Value = serde_json::from_slice(&out.stdout).unwrap();
+        itens
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|i| i["caminho"].as_str().unwrap().to_string())
+            .collect()
+    };
+
+    assert_eq!(
+        listar(&["--ordenar", "nome"]),
+        ["src/a.pr", "src/b.pr", "src/sub/c.pr"]
+    );
+    assert_eq!(
+        listar(&["--ordenar", "tamanho"]),
+        ["src/b.pr", "src/sub/c.pr", "src/a.pr"]
+    );
+    assert_eq!(
+        listar(&["--ordenar", "modificado", "--reverso"]),
+        ["src/b.pr", "src/sub/c.pr", "src/a.pr"]
+    );
+    assert_eq!(
+        listar(&["--recentes", "--ordenar", "tamanho", "--reverso"]),
+        ["src/sub/c.pr", "src/b.pr"]
+    );
+
+    let out = Command::new(&bin)
+        .arg("listar")
+        .arg(&proj)
+        .args(["--ordenar", "tamanho", "--reverso"])
+        .output()
+        .expect("run listar");
+    let s = String::from_utf8_lossy(&out.stdout);
+    let pos = |nome: &str| s.find(nome).unwrap();
+    assert!(
+        pos("a.pr (300") < pos("c.pr (100") && pos("c.pr (100") < pos("b.pr (10"),
+        "{}",
+        s
+    );
+
+    let out = Command::new(&bin)
+        .args(["listar", "--reverso"])
+        .arg(&proj)
+        .output()
+        .expect("run listar --reverso");
+    assert!(!out.status.success());
+}
+
+#[test]
+fn listar_avisa_nomes_problematicos_e_falha_com_strict() {
+    let bin = bin_path();
+    let temp = tempfile::tempdir().unwrap();
+    let proj = temp.path().join("app");
+    fs::create_dir_all(proj.join("src")).unwrap();
+    fs::write(proj.join("pordosol.proj"), "{\"nome\": \"app\"}\n").unwrap();
+    for rel in ["programa.pr", "meu modulo.pr"] {
+        fs::write(proj.join("src").join(rel), "").unwrap();
+    }
+
+    let out = Command::new(&bin)
+        .arg("listar")
+        .arg(&proj)
+        .output()
+        .expect("run listar");
+    assert!(out.status.success());
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(
+        stderr.contains("meu modulo.pr: nome com espacos"),
+        "{}",
+        stderr
+    );
+
+    let out = Command::new(&bin)
+        .arg("listar")
+        .arg(&proj)
+        .arg("--strict")
+        .output()
+        .expect("run listar --strict");
+    assert_eq!(out.status.code(), Some(3));
+    assert!(String::from_utf8_lossy(&out.stderr).contains("Nomes de fonte problematicos"));
+}
+
+#[test]
+fn saida_sobre_os_fontes_bloqueia_build_e_clean() {
+    let bin = bin_path();
+    let temp = tempfile::tempdir().unwrap();
+    let dir = temp.path().join("app");
+    fs::create_dir_all(dir.join("src")).unwrap();
+    fs::write(dir.join("src").join("programa.pr"), "").unwrap();
+    fs::write(
+        dir.join("pordosol.proj"),
+        r#"{"nome": "app", "configuracao": {"pasta_saida": "src"}}"#,
+    )
+    .unwrap();
+
+    for comando in ["build", "clean"] {
+        let out = Command::new(&bin)
+            .arg(comando)
+            .arg(&dir)
+            .output()
+            .expect("run comando");
+        assert_eq!(out.status.code(), Some(3), "{}", comando);
+        let stderr = String::from_utf8_lossy(&out.stderr);
+        assert!(
+            stderr.contains("e a mesma que a pasta de fontes"),
+            "{}",
+            stderr
+        );
+    }
+    assert!(dir.join("src").join("programa.pr").is_file());
+
+    let out = Command::new(&bin)
+        .args(["clean", "--dry-run", "--permitir-saida-perigosa"])
+        .arg(&dir)
+        .output()
+        .expect("run clean");
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+}
+
+#[test]
+fn build_explain_flags_mostra_origem_sem_compilar() {
+    let bin = bin_path();
+    let temp = tempfile::tempdir().unwrap();
+    let dir = temp.path().join("app");
+    fs::create_dir_all(dir.join("src")).unwrap();
+    fs::write(dir.join("src").join("programa.pr"), "").unwrap();
+    fs::write(
+        dir.join("pordosol.proj"),
+        r#"{"nome": "app", "configuracao": {"target_padrao": "llvm-ir"}}"#,
+    )
+    .unwrap();
+    let explicar = |extras: &[&str]| {
+        let out = Command::new(&bin)
+            .args(["compilar", "--explain-flags", "--project"])
+            .arg(&dir)
+            .args(extras)
+            .output()
+            .expect("run compilar --explain-flags");
+        assert!(
+            out.status.success(),
+            "{}",
+            String::from_utf8_lossy(&out.stderr)
+        );
+        String::from_utf8_lossy(&out.stdout).to_string()
+    };
+
+    let s = explicar(&[]);
+    assert!(s.contains("target: --target=llvm-ir"), "{}", s);
+    assert!(
+        s.contains("origem: pordosol.proj configuracao.target_padrao = llvm-ir"),
+        "{}",
+        s
+    );
+    assert!(s.contains("sobrescrito: padrao = bytecode"), "{}", s);
+
+    let s = explicar(&["--target", "console"]);
+    assert!(s.contains("target: --target=console"), "{}", s);
+    assert!(s.contains("origem: flag --target = console"), "{}", s);
+    assert!(!dir.join("build").exists(), "explain-flags nao compila");
+}
+
+#[test]
+fn manutencao_relata_e_so_apara_com_aplicar() {
+    let bin = bin_path();
+    let temp = tempfile::tempdir().unwrap();
+    let dir = temp.path();
+    fs::write(
+        dir.join("pordosol.proj"),
+        r#"{"nome": "app", "manutencao": {"historico_entradas": 2}}"#,
+    )
+    .unwrap();
+    fs::create_dir_all(dir.join("build")).unwrap();
+    let historico = dir.join("build").join(".timings.jsonl");
+    fs::write(&historico, "{\"n\":1}\n{\"n\":2}\n{\"n\":3}\n{\"n\":4}\n").unwrap();
+
+    let rodar = |extras: &[&str]| {
+        let out = Command::new(&bin)
+            .arg("manutencao")
+            .arg(dir)
+            .args(extras)
+            .output()
+            .expect("run manutencao");
+        assert!(
+            out.status.success(),
+            "{}",
+            String::from_utf8_lossy(&out.stderr)
+        );
+        String::from_utf8_lossy(&out.stdout).to_string()
+    };
+
+    let s = rodar(&[]);
+    assert!(s.contains("retencao: 2 entradas"), "{}", s);
+    assert!(s.contains("2 entrada(s) alem do limite"), "{}", s);
+    assert!(s.contains("Use --aplicar"), "{}", s);
+    assert_eq!(fs::read_to_string(&historico).unwrap().lines().count(), 4);
+
+    let s = rodar(&["--aplicar"]);
+    assert!(s.contains("1 item(ns) alterado(s)."), "{}", s);
+    assert_eq!(
+        fs::read_to_string(&historico).unwrap(),
+        "{\"n\":3}\n{\"n\":4}\n"
+    );
+}
+
+#[test]
+fn new_nome_e_saida_definem_pasta_nome_e_namespace() {
+    let bin = bin_path();
+    let temp = tempfile::tempdir().unwrap();
+    let saida = temp.path().join("workspace");
+
+    for (nome, esperado) in [("meu-app", "Meu.App"), ("Pão-de-Açúcar", "Pao.De.Acucar")] {
+        let out = Command::new(&bin)
+            .args(["new", "console", "--nome", nome, "--saida"])
+            .arg(&saida)
+            .output()
+            .expect("run new --nome --saida");
+        assert!(
+            out.status.success(),
+            "{}",
+            String::from_utf8_lossy(&out.stderr)
+        );
+        let projeto = saida.join(nome);
+        let proj = fs::read_to_string(projeto.join("pordosol.proj")).unwrap();
+        assert!(
+            proj.contains(&format!("\"nome\": \"{}\"", nome)),
+            "{}",
+            proj
+        );
+        let programa = fs::read_to_string(projeto.join("src").join("programa.pr")).unwrap();
+        assert!(
+            programa.starts_with(&format!("// Namespace: {}\n", esperado)),
+            "{}: {}",
+            nome,
+            programa
+        );
+    }
+}
+
+#[test]
+fn new_em_pasta_com_conteudo_exige_forcar() {
+    let bin = bin_path();
+    let temp = tempfile::tempdir().unwrap();
+    let projeto = temp.path().join("app");
+    fs::create_dir_all(&projeto).unwrap();
+    fs::write(projeto.join("notas.txt"), "meu").unwrap();
+
+    let novo = |extras: &[&str]| {
+        Command::new(&bin)
+            .args(["new", "console", "-n", "app", "-o"])
+            .arg(temp.path())
+            .args(extras)
+            .output()
+            .expect("run new")
+    };
+
+    let out = novo(&[]);
+    assert!(!out.status.success());
+    assert!(
+        String::from_utf8_lossy(&out.stderr).contains("--forcar"),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    assert!(!projeto.join("pordosol.proj").exists());
+
+    let out = novo(&["--forcar"]);
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    assert!(projeto.join("pordosol.proj").is_file());
+    assert_eq!(
+        fs::read_to_string(projeto.join("notas.txt")).unwrap(),
+        "meu"
+    );
+}
+
+#[test]
+fn explain_le_pordosol_home_e_sugere_codigos_parecidos() {
+    let bin = bin_path();
+    let temp = tempfile::tempdir().unwrap();
+    let erros = temp.path().join("erros");
+    fs::create_dir_all(&erros).unwrap();
+    fs::write(
+        erros.join("E0012.md"),
+        "Divisao por zero constante\n\nO divisor e o literal 0.\n",
+    )
+    .unwrap();
+
+    let explain = |codigo: &str| {
+        Command::new(&bin)
+            .args(["explain", codigo])
+            .env("PORDOSOL_HOME", temp.path())
+            .output()
+            .expect("run explain")
+    };
+
+    let out = explain("e12");
+    assert!(out.status.success());
+    let s = String::from_utf8_lossy(&out.stdout);
+    assert!(
+        s.starts_with("E0012: Divisao por zero constante\n"),
+        "{}",
+        s
+    );
+
+    let out = explain("E0013");
+    assert!(!out.status.success());
+    let err = String::from_utf8_lossy(&out.stderr);
+    assert!(
+        err.contains("'E0013' nao reconhecido. Codigos parecidos: E0003, E0012, E0001"),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn new_de_arquivo_copia_o_fonte_como_programa() {
+    let bin = bin_path();
+    let temp = tempfile::tempdir().unwrap();
+    let rascunho = temp.path().join("analise.pr");
+    let fonte = "funcao vazio Principal()\n{\n    imprima(\"analise\");\n}\n";
+    fs::write(&rascunho, fonte).unwrap();
+    let dir = temp.path().join("meu-projeto");
+
+    let out = Command::new(&bin)
+        .arg("novo")
+        .arg(&dir)
+        .arg("--de-arquivo")
+        .arg(&rascunho)
+        .output()
+        .expect("run novo --de-arquivo");
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    assert!(!String::from_utf8_lossy(&out.stderr).contains("Principal"));
+    assert_eq!(
+        fs::read_to_string(dir.join("src").join("programa.pr")).unwrap(),
+        fonte
+    );
+    assert!(dir.join("pordosol.proj").is_file());
+    assert!(dir.join("README.md").is_file());
+    assert_eq!(fs::read_to_string(&rascunho).unwrap(), fonte);
+}
+
+#[test]
+fn new_de_arquivo_manter_nome_define_entrada_e_avisa_sem_principal() {
+    let bin = bin_path();
+    let temp = tempfile::tempdir().unwrap();
+    let rascunho = temp.path().join("analise.pr");
+    let fonte = "funcao inteiro Somar(inteiro a, inteiro b)\n{\n    retorne a + b;\n}\n";
+    fs::write(&rascunho, fonte).unwrap();
+
+    let out = Command::new(&bin)
+        .args(["new", "console", "-n", "analise", "-o"])
+        .arg(temp.path().join("projetos"))
+        .arg("--de-arquivo")
+        .arg(&rascunho)
+        .arg("--manter-nome")
+        .output()
+        .expect("run new --de-arquivo --manter-nome");
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    let err = String::from_utf8_lossy(&out.stderr);
+    assert!(err.contains("nao declara uma funcao Principal"), "{}", err);
+
+    let dir = temp.path().join("projetos").join("analise");
+    assert_eq!(
+        fs::read_to_string(dir.join("src").join("analise.pr")).unwrap(),
+        fonte
+    );
+    assert!(!dir.join("src").join("programa.pr").exists());
+    let proj: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(dir.join("pordosol.proj")).unwrap()).unwrap();
+    assert_eq!(proj["entrada"], "src/analise.pr");
+    assert_eq!(fs::read_to_string(&rascunho).unwrap(), fonte);
+}
+
+#[test]
+fn workspace_itera_membros_em_ordem_de_dependencia() {
+    let bin = bin_path();
+    let temp = tempfile::tempdir().unwrap();
+    let ws = temp.path();
+    fs::write(
+        ws.join("pordosol.workspace"),
+        r#"{"membros": ["servicos/*", "lib/comum"]}"#,
+    )
+    .unwrap();
+    for (relativo, proj) in [
+        (
+            "servicos/api",
+            r#"{"nome": "api", "dependencias": {"comum": {"path": "../../lib/comum"}}}"#,
+        ),
+        ("servicos/worker", r#"{"nome": "worker"}"#),
+        ("lib/comum", r#"{"nome": "comum"}"#),
+    ] {
+        let pasta = ws.join(relativo);
+        fs::create_dir_all(pasta.join("src")).unwrap();
+        fs::write(pasta.join("pordosol.proj"), proj).unwrap();
+        fs::write(pasta.join("src").join("programa.pr"), "").unwrap();
+    }
+
+    // Na raiz do workspace, listar percorre os membros com cabecalho e relatorio
+    let out = Command::new(&bin)
+        .arg("listar")
+        .arg(ws)
+        .output()
+        .expect("run listar");
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    let s = String::from_utf8_lossy(&out.stdout);
+    let comum = s.find("== comum (lib/comum) ==").expect(&s);
+    let api = s.find("== api (servicos/api) ==").expect(&s);
+    let worker = s.find("== worker (servicos/worker) ==").expect(&s);
+    assert!(comum < api && api < worker, "{}", s);
+    let resumo = &s[s.find("=== Resumo da execucao ===").expect(&s)..];
+    assert_eq!(resumo.matches("sucesso").count(), 3, "{}", resumo);
+
+    // De dentro de um membro, so ele; com --todos, o workspace inteiro
+    let membro = ws.join("servicos").join("worker");
+    let out = Command::new(&bin)
+        .args(["clean", "--dry-run"])
+        .arg(&membro)
+        .output()
+        .expect("run clean");
+    assert!(!String::from_utf8_lossy(&out.stdout).contains("Resumo da execucao"));
+    let out = Command::new(&bin)
+        .args(["clean", "--dry-run", "--todos"])
+        .arg(&membro)
+        .output()
+        .expect("run clean --todos");
+    assert!(String::from_utf8_lossy(&out.stdout).contains("Resumo da execucao"));
+
+    // Sem compilador, o build para no primeiro membro; --continuar tenta todos
+    let compilar = |extras: &[&str]| {
+        let out = Command::new(&bin)
+            .arg("compilar")
+            .arg(ws)
+            .args(["--json"])
+            .args(extras)
+            .env("PORDOSOL_COMPILADOR_PATH", ws.join("nao-existe"))
+            .output()
+            .expect("run compilar");
+        // Codigo do primeiro membro que falhou (toolchain ausente)
+        assert_eq!(out.status.code(), Some(2));
+        let relatorio: serde_json::Value = serde_json::from_slice(&out.stdout).unwrap();
+        relatorio["unidades"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|u| {
+                format!(
+                    "{} {}",
+                    u["membro"].as_str().unwrap(),
+                    u["status"].as_str().unwrap()
+                )
+            })
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        compilar(&[]),
+        ["comum falha", "api nao executado", "worker nao executado"]
+    );
+    assert_eq!(
+        compilar(&["--continuar"]),
+        ["comum falha", "api falha", "worker falha"]
+    );
+    let out = Command::new(&bin)
+        .arg("compilar")
+        .arg(ws)
+        .env("PORDOSOL_COMPILADOR_PATH", ws.join("nao-existe"))
+        .output()
+        .expect("run compilar");
+    let erro = String::from_utf8_lossy(&out.stderr);
+    assert!(erro.contains("Error: comum:"), "{}", erro);
+    assert!(
+        erro.contains("1 membro(s) do workspace falharam: comum"),
+        "{}",
+        erro
+    );
+
+    // run na raiz exige escolher o membro
+    let out = Command::new(&bin)
+        .arg("run")
+        .current_dir(ws)
+        .output()
+        .expect("run run");
+    assert_eq!(out.status.code(), Some(3));
+    let erro = String::from_utf8_lossy(&out.stderr);
+    assert!(
+        erro.contains("--projeto <nome> (comum, api, worker)"),
+        "{}",
+        erro
+    );
+
+    // --projeto aceita o nome do membro
+    let out = Command::new(&bin)
+        .args(["run", "--projeto", "api"])
+        .current_dir(ws)
+        .env("PORDOSOL_COMPILADOR_PATH", ws.join("nao-existe"))
+        .output()
+        .expect("run run --projeto");
+    let erro = String::from_utf8_lossy(&out.stderr);
+    assert!(!erro.contains("raiz de um workspace"), "{}", erro);
+    assert!(!erro.contains("nao encontrado: api"), "{}", erro);
+}
+
+#[test]
+fn target_desconhecido_falha_com_sugestao_ou_passa_com_target_livre() {
+    let bin = bin_path();
+    let temp = tempfile::tempdir().unwrap();
+    let dir = temp.path().join("app");
+    fs::create_dir_all(dir.join("src")).unwrap();
+    fs::write(dir.join("src").join("programa.pr"), "").unwrap();
+    fs::write(dir.join("pordosol.proj"), r#"{"nome": "app"}"#).unwrap();
+    let executar = |args: &[&str]| {
+        Command::new(&bin)
+            .args(args)
+            .arg(&dir)
+            .env("PORDOSOL_COMPILADOR_PATH", temp.path().join("nao-existe"))
+            .output()
+            .expect("run comando")
+    };
+
+    for (args, sugestao) in [
+        (&["compilar", "--target", "bytcode"][..], "'bytecode'"),
+        (&["producao", "--target", "llvm-i"][..], "'llvm-ir'"),
+    ] {
+        let out = executar(args);
+        assert!(!out.status.success());
+        let erro = String::from_utf8_lossy(&out.stderr);
+        assert!(erro.contains("Target desconhecido"), "{}", erro);
+        assert!(
+            erro.contains(&format!("Você quis dizer {}?", sugestao)),
+            "{}",
+            erro
+        );
+    }
+
+    // Com --target-livre o alvo segue adiante (aqui falha so por falta do compilador)
+    let out = executar(&["compilar", "--target", "wasm", "--target-livre"]);
+    let erro = String::from_utf8_lossy(&out.stderr);
+    assert!(!erro.contains("Target desconhecido"), "{}", erro);
+}
+
+#[test]
+fn new_template_desconhecido_sugere_o_mais_proximo() {
+    let temp = tempfile::tempdir().unwrap();
+    let out = Command::new(bin_path())
+        .args(["new", "consle", "-n", "app", "-o"])
+        .arg(temp.path())
+        .output()
+        .expect("run new");
+    assert!(!out.status.success());
+    let erro = String::from_utf8_lossy(&out.stderr);
+    assert!(
+        erro.contains("Template 'consle' nao encontrado. Você quis dizer 'console'?"),
+        "{}",
+        erro
+    );
+}
+
+// As marcas viram [ok]/[faltando] no console classico do Windows
+#[cfg(unix)]
+#[test]
+fn info_deps_marca_paths_quebrados() {
+    let temp = tempfile::tempdir().unwrap();
+    let app = temp.path().join("app");
+    fs::create_dir_all(app.join("src")).unwrap();
+    fs::create_dir_all(temp.path().join("comum")).unwrap();
+    fs::write(temp.path().join("comum").join("pordosol.proj"), "{}").unwrap();
+    fs::create_dir_all(temp.path().join("solta")).unwrap();
+    fs::write(
+        app.join("pordosol.proj"),
+        r#"{"nome": "app", "dependencias": {
+            "comum": {"path": "../comum"},
+            "solta": {"path": "../solta"},
+            "sumiu": {"path": "../sumiu"},
+            "json": "1.2"
+        }}"#,
+    )
+    .unwrap();
+
+    let out = Command::new(bin_path())
+        .args(["info", "--deps"])
+        .arg(&app)
+        .output()
+        .expect("run info --deps");
+    assert!(out.status.success());
+    let s = String::from_utf8_lossy(&out.stdout);
+    assert!(s.contains("=== Dependencias ==="), "{}", s);
+    assert!(s.contains("✓ comum: path ../comum\n"), "{}", s);
+    assert!(
+        s.contains("✗ solta: path ../solta (sem pordosol.proj)"),
+        "{}",
+        s
+    );
+    assert!(
+        s.contains("✗ sumiu: path ../sumiu (pasta nao existe)"),
+        "{}",
+        s
+    );
+    assert!(s.contains("- json: versao 1.2"), "{}", s);
+}
@@ -0,0 +1,472 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+fn bin_path() -> PathBuf {
+    let mut p = PathBuf::from(env!("CARGO_BIN_EXE_pordosol"));
+    if cfg!(windows) && p.extension().is_none() {
+        p.set_extension("exe");
+    }
+    p
+}
+
+fn criar_projeto(dir: &Path, proj: &str) {
+    fs::create_dir_all(dir.join("src")).unwrap();
+    fs::write(dir.join("pordosol.proj"), proj).unwrap();
+}
+
+fn dep(dir: &Path, args: &[&str]) -> Output {
+    Command::new(bin_path())
+        .arg("dep")
+        .args(args)
+        .arg("--caminho-projeto")
+        .arg(dir)
+        .output()
+        .expect("run dep")
+}
+
+#[test]
+fn dep_add_cria_campo_ausente() {
+    let temp = tempfile::tempdir().unwrap();
+    let dir = temp.path().join("proj");
+    criar_projeto(
+        &dir,
+        "{\n    \"nome\": \"app\",\n    \"versao\": \"1.0.0\",\n    \"configuracao\": {\n        \"target_padrao\": \"bytecode\",\n        \"otimizacao\": false\n    }\n}\n",
+    );
+
+    let out = dep(&dir, &["add", "foo", "--versao", "1.0"]);
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+
+    let txt = fs::read_to_string(dir.join("pordosol.proj")).unwrap();
+    let json: serde_json::Value = serde_json::from_str(&txt).unwrap();
+    assert_eq!(json["dependencias"]["foo"], "1.0");
+    assert!(txt.ends_with("}\n"), "newline final deve ser preservado");
+
+    let pos_nome = txt.find("\"nome\"").unwrap();
+    let pos_versao = txt.find("\"versao\"").unwrap();
+    let pos_config = txt.find("\"configuracao\"").unwrap();
+    let pos_target = txt.find("\"target_padrao\"").unwrap();
+    let pos_otim = txt.find("\"otimizacao\"").unwrap();
+    assert!(pos_nome < pos_versao && pos_versao < pos_config);
+    assert!(
+        pos_target < pos_otim,
+        "ordem de chaves aninhadas preservada"
+    );
+}
+
+#[test]
+fn dep_add_mantem_chaves_de_topo_antes_de_dependencias() {
+    let temp = tempfile::tempdir().unwrap();
+    let dir = temp.path().join("proj");
+    criar_projeto(
+        &dir,
+        "{\n  \"nome\": \"app\",\n  \"tipo\": \"console\",\n  \"versao\": \"1.0.0\",\n  \"dependencias\": {\n    \"zeta\": \"1.0\"\n  }\n}\n",
+    );
+
+    let out = dep(&dir, &["add", "alfa", "--versao", "2.0"]);
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+
+    let txt = fs::read_to_string(dir.join("pordosol.proj")).unwrap();
+    let posicao = |chave: &str| txt.find(&format!("\"{}\"", chave)).unwrap();
+    assert!(
+        posicao("nome") < posicao("tipo")
+            && posicao("tipo") < posicao("versao")
+            && posicao("versao") < posicao("dependencias"),
+        "{}",
+        txt
+    );
+    // A nova dependencia entra no fim, sem reordenar as existentes
+    assert!(posicao("zeta") < posicao("alfa"), "{}", txt);
+
+    // A leitura como serde_json::Value tambem mantem a ordem do arquivo
+    let json: serde_json::Value = serde_json::from_str(&txt).unwrap();
+    let chaves: Vec<&str> = json
+        .as_object()
+        .unwrap()
+        .keys()
+        .map(String::as_str)
+        .collect();
+    assert_eq!(chaves, ["nome", "tipo", "versao", "dependencias"]);
+}
+
+#[test]
+fn dep_add_aceita_dependencias_null() {
+    let temp = tempfile::tempdir().unwrap();
+    let dir = temp.path().join("proj");
+    criar_projeto(&dir, "{\"nome\": \"app\", \"dependencias\": null}");
+
+    let out = dep(&dir, &["add", "foo", "--versao", "2.0"]);
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+
+    let txt = fs::read_to_string(dir.join("pordosol.proj")).unwrap();
+    let json: serde_json::Value = serde_json::from_str(&txt).unwrap();
+    assert_eq!(json["dependencias"]["foo"], "2.0");
+    assert!(
+        !txt.ends_with('\n'),
+        "arquivo sem newline final continua sem"
+    );
+}
+
+#[test]
+fn dep_add_rejeita_dependencias_array() {
+    let temp = tempfile::tempdir().unwrap();
+    let dir = temp.path().join("proj");
+    let original = "{\"nome\": \"app\", \"dependencias\": []}\n";
+    criar_projeto(&dir, original);
+
+    let out = dep(&dir, &["add", "foo"]);
+    assert!(!out.status.success());
+    let err = String::from_utf8_lossy(&out.stderr);
+    assert!(err.contains("dependencias"), "{}", err);
+    assert_eq!(
+        fs::read_to_string(dir.join("pordosol.proj")).unwrap(),
+        original
+    );
+}
+
+#[test]
+fn dep_resolve_lista_fontes_e_conflitos() {
+    let temp = tempfile::tempdir().unwrap();
+    let dir = temp.path().join("proj");
+    criar_projeto(
+        &dir,
+        "{\"nome\": \"app\", \"dependencias\": {\"a\": {\"path\": \"../a\"}, \"b\": {\"path\": \"../b\"}, \"c\": \"1.0\"}}\n",
+    );
+    for (lib, arquivos) in [("a", &["util.pr", "a.pr"]), ("b", &["util.pr", "b.pr"])] {
+        let src = temp.path().join(lib).join("src");
+        fs::create_dir_all(&src).unwrap();
+        for arq in arquivos {
+            fs::write(src.join(arq), "funcao f() {}\n").unwrap();
+        }
+    }
+
+    let out = dep(&dir, &["resolve", "--json"]);
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    let json: serde_json::Value = serde_json::from_slice(&out.stdout).unwrap();
+    let deps = json["dependencias"].as_array().unwrap();
+    let nomes: Vec<&str> = deps.iter().map(|d| d["nome"].as_str().unwrap()).collect();
+    assert_eq!(nomes, ["a", "b", "c"]);
+    assert_eq!(deps[0]["origem"]["tipo"], "path");
+    assert_eq!(deps[0]["arquivos"].as_array().unwrap().len(), 2);
+    assert_eq!(deps[0]["includes"].as_array().unwrap().len(), 1);
+    assert_eq!(deps[2]["origem"]["tipo"], "registro");
+    assert_eq!(json["conflitos"][0]["stem"], "util");
+    assert_eq!(
+        json["conflitos"][0]["dependencias"],
+        serde_json::json!(["a", "b"])
+    );
+
+    let out = dep(&dir, &["resolve"]);
+    let s = String::from_utf8_lossy(&out.stdout);
+    assert!(s.contains("Conflitos de stem"), "{}", s);
+    assert!(s.contains("util: a, b"), "{}", s);
+}
+
+#[test]
+fn dep_resolve_falha_com_path_inexistente() {
+    let temp = tempfile::tempdir().unwrap();
+    let dir = temp.path().join("proj");
+    criar_projeto(
+        &dir,
+        "{\"nome\": \"app\", \"dependencias\": {\"x\": {\"path\": \"../nao-existe\"}}}\n",
+    );
+
+    let out = dep(&dir, &["resolve"]);
+    assert_eq!(out.status.code(), Some(3));
+    assert!(String::from_utf8_lossy(&out.stderr).contains("nao encontrada"));
+}
+
+#[test]
+fn dep_tree_segue_dependencias_path_e_marca_ciclos() {
+    let temp = tempfile::tempdir().unwrap();
+    let ws = temp.path();
+    criar_projeto(
+        &ws.join("a"),
+        "{\"nome\": \"a\", \"dependencias\": {\"b\": {\"path\": \"../b\"}, \"log\": \"1.0\"}}\n",
+    );
+    criar_projeto(
+        &ws.join("b"),
+        "{\"nome\": \"b\", \"dependencias\": {\"c\": {\"path\": \"../c\"}}}\n",
+    );
+    criar_projeto(&ws.join("c"), "{\"nome\": \"c\"}\n");
+    criar_projeto(
+        &ws.join("x"),
+        "{\"nome\": \"x\", \"dependencias\": {\"y\": {\"path\": \"../y\"}}}\n",
+    );
+    criar_projeto(
+        &ws.join("y"),
+        "{\"nome\": \"y\", \"dependencias\": {\"x\": {\"path\": \"../x\"}}}\n",
+    );
+
+    let out = dep(&ws.join("a"), &["tree"]);
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    let s = String::from_utf8_lossy(&out.stdout);
+    let linhas: Vec<&str> = s.lines().collect();
+    assert_eq!(linhas[0], "a");
+    assert!(linhas[1].starts_with("  - b (path "), "{}", s);
+    assert!(linhas[2].starts_with("    - c (path "), "{}", s);
+    assert!(linhas[3].starts_with("  - log (registro"), "{}", s);
+
+    let out = dep(&ws.join("a"), &["tree", "--json"]);
+    let json: serde_json::Value = serde_json::from_slice(&out.stdout).unwrap();
+    assert_eq!(json[0]["nome"], "b");
+    assert_eq!(json[0]["dependencias"][0]["nome"], "c");
+    assert_eq!(json[1]["origem"]["tipo"], "registro");
+    assert_eq!(json[1]["dependencias"], serde_json::json!([]));
+
+    let out = dep(&ws.join("a"), &["tree", "--json", "--profundidade", "1"]);
+    let json: serde_json::Value = serde_json::from_slice(&out.stdout).unwrap();
+    assert_eq!(json[0]["truncado"], true);
+    assert_eq!(json[0]["dependencias"], serde_json::json!([]));
+
+    let out = dep(&ws.join("x"), &["tree"]);
+    assert!(out.status.success(), "ciclos nao devem recursar");
+    let s = String::from_utf8_lossy(&out.stdout);
+    assert!(s.contains("    - x (path "), "{}", s);
+    assert!(s.contains("[ciclo]"), "{}", s);
+    let out = dep(&ws.join("x"), &["tree", "--json"]);
+    let json: serde_json::Value = serde_json::from_slice(&out.stdout).unwrap();
+    assert_eq!(json[0]["dependencias"][0]["ciclo"], true);
+}
+
+#[test]
+fn dep_add_valida_versao_e_caminho() {
+    let temp = tempfile::tempdir().unwrap();
+    let dir = temp.path().join("proj");
+    criar_projeto(&dir, "{\"nome\": \"app\"}");
+
+    for valida in [
+        "*",
+        "1.2.3",
+        "^1.2",
+        "~1.2.3",
+        ">=1.0, <2.0",
+        "1.*",
+        "1.0.0-beta.1+abc",
+    ] {
+        let out = dep(&dir, &["add", "foo", "--versao", valida]);
+        assert!(
+            out.status.success(),
+            "{}: {}",
+            valida,
+            String::from_utf8_lossy(&out.stderr)
+        );
+    }
+    for invalida in ["abc", "1.2.3.4", "01.2", ">=1.*", "1.2-beta", "^"] {
+        let out = dep(&dir, &["add", "foo", "--versao", invalida]);
+        assert!(!out.status.success(), "{} deveria ser rejeitada", invalida);
+        assert!(String::from_utf8_lossy(&out.stderr).contains("Versao invalida"));
+    }
+
+    let out = dep(&dir, &["add", "futura", "--caminho", "../ainda-nao"]);
+    assert!(out.status.success());
+    assert!(String::from_utf8_lossy(&out.stderr).contains("ainda nao existe"));
+
+    fs::create_dir_all(temp.path().join("solta")).unwrap();
+    let out = dep(&dir, &["add", "solta", "--caminho", "../solta"]);
+    assert!(!out.status.success());
+    assert!(String::from_utf8_lossy(&out.stderr).contains("pordosol.proj ausente"));
+
+    criar_projeto(&temp.path().join("lib"), "{\"nome\": \"lib\"}");
+    let out = dep(&dir, &["add", "lib", "--caminho", "../lib"]);
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+}
+
+#[test]
+fn dep_install_copia_fontes_path_e_limpa_removidas() {
+    let temp = tempfile::tempdir().unwrap();
+    let lib = temp.path().join("lib");
+    criar_projeto(&lib, "{\"nome\": \"lib\"}\n");
+    fs::create_dir_all(lib.join("src").join("sub")).unwrap();
+    fs::write(lib.join("src").join("util.pr"), "// util\n").unwrap();
+    fs::write(lib.join("src").join("sub").join("texto.pr"), "// texto\n").unwrap();
+
+    let app = temp.path().join("app");
+    criar_projeto(
+        &app,
+        "{\"nome\": \"app\", \"dependencias\": {\"lib\": {\"path\": \"../lib\"}, \"foo\": \"1.0\"}}\n",
+    );
+
+    let out = dep(&app, &["install"]);
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    assert!(
+        stdout.contains("lib: 2 arquivo(s) copiado(s)"),
+        "{}",
+        stdout
+    );
+    assert!(stdout.contains("foo: ignorada"), "{}", stdout);
+    assert_eq!(
+        fs::read_to_string(app.join("deps").join("lib").join("util.pr")).unwrap(),
+        "// util\n"
+    );
+    assert!(app
+        .join("deps")
+        .join("lib")
+        .join("sub")
+        .join("texto.pr")
+        .is_file());
+
+    let lock: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(app.join("pordosol.lock")).unwrap()).unwrap();
+    assert_eq!(lock["dependencias"]["lib"]["path"], "../lib");
+    assert_eq!(
+        lock["dependencias"]["lib"]["arquivos"],
+        serde_json::json!(["sub/texto.pr", "util.pr"])
+    );
+    assert!(lock["dependencias"].get("foo").is_none());
+
+    // Sem mudancas: nada e copiado de novo
+    let out = dep(&app, &["restaurar"]);
+    assert!(String::from_utf8_lossy(&out.stdout).contains("lib: sem mudancas"));
+
+    // Fonte alterada: hash muda e a copia e refeita
+    fs::write(lib.join("src").join("util.pr"), "// util v2\n").unwrap();
+    let out = dep(&app, &["install"]);
+    assert!(String::from_utf8_lossy(&out.stdout).contains("lib: 2 arquivo(s) copiado(s)"));
+    assert_eq!(
+        fs::read_to_string(app.join("deps").join("lib").join("util.pr")).unwrap(),
+        "// util v2\n"
+    );
+
+    // Dependencia removida do proj: a pasta vendorizada sai no proximo install
+    assert!(dep(&app, &["remove", "lib"]).status.success());
+    let out = dep(&app, &["install"]);
+    assert!(String::from_utf8_lossy(&out.stdout).contains("lib: removida"));
+    assert!(!app.join("deps").join("lib").exists());
+}
+
+#[test]
+fn ambiente_mescla_dependencias_e_configuracao() {
+    let temp = tempfile::tempdir().unwrap();
+    let dir = temp.path().join("proj");
+    criar_projeto(
+        &dir,
+        r#"{"nome": "app", "configuracao": {"pasta_saida": "build", "otimizacao": false}, "dependencias": {"log": "1.0.0", "util": "1.0.0"}}"#,
+    );
+    fs::write(
+        dir.join("pordosol.ci.proj"),
+        r#"{"configuracao": {"otimizacao": true}, "dependencias": {"util": "2.0.0", "http": "0.3.0"}}"#,
+    )
+    .unwrap();
+
+    let resolve = |ambiente: Option<&str>| {
+        let mut cmd = Command::new(bin_path());
+        cmd.args(["dep", "resolve", "--json", "--caminho-projeto"])
+            .arg(&dir)
+            .env_remove("PORDOSOL_AMBIENTE");
+        if let Some(a) = ambiente {
+            cmd.env("PORDOSOL_AMBIENTE", a);
+        }
+        let out = cmd.output().expect("run dep resolve");
+        assert!(
+            out.status.success(),
+            "{}",
+            String::from_utf8_lossy(&out.stderr)
+        );
+        let plano: serde_json::Value = serde_json::from_slice(&out.stdout).unwrap();
+        plano["dependencias"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|d| d["nome"].as_str().unwrap().to_string())
+            .collect::<Vec<_>>()
+    };
+    // `util` muda de versao e continua na posicao da base; `http` entra no fim
+    assert_eq!(resolve(None), ["log", "util"]);
+    assert_eq!(resolve(Some("ci")), ["log", "util", "http"]);
+
+    let out = Command::new(bin_path())
+        .args([
+            "config",
+            "origem",
+            "otimizacao",
+            "--ambiente",
+            "ci",
+            "--project",
+        ])
+        .arg(&dir)
+        .output()
+        .expect("run config origem");
+    let s = String::from_utf8_lossy(&out.stdout);
+    assert!(s.contains("otimizacao = true"), "{}", s);
+    assert!(
+        s.contains("pordosol.ci.proj configuracao.otimizacao: true  <- vence"),
+        "{}",
+        s
+    );
+    assert!(
+        s.contains("pordosol.proj configuracao.otimizacao: false  (sobrescrito)"),
+        "{}",
+        s
+    );
+
+    let out = Command::new(bin_path())
+        .args([
+            "config",
+            "origem",
+            "otimizacao",
+            "--ambiente",
+            "qa",
+            "--project",
+        ])
+        .arg(&dir)
+        .output()
+        .expect("run config origem sem arquivo");
+    assert!(String::from_utf8_lossy(&out.stdout).contains("otimizacao = false"));
+    assert!(String::from_utf8_lossy(&out.stderr).contains("pordosol.qa.proj nao existe"));
+
+    let out = Command::new(bin_path())
+        .args(["config", "origem", "otimizacao", "--ambiente", "../x"])
+        .output()
+        .expect("run config origem ambiente invalido");
+    assert_eq!(out.status.code(), Some(3));
+}
+
+#[test]
+fn dep_acao_desconhecida_sugere_a_mais_proxima() {
+    let temp = tempfile::tempdir().unwrap();
+    let dir = temp.path().join("proj");
+    criar_projeto(&dir, r#"{"nome": "app"}"#);
+
+    let out = dep(&dir, &["lst"]);
+    assert!(!out.status.success());
+    let erro = String::from_utf8_lossy(&out.stderr);
+    assert!(
+        erro.contains("Ação desconhecida: 'lst'. Você quis dizer 'list'?"),
+        "{}",
+        erro
+    );
+
+    let out = dep(&dir, &["publicar"]);
+    let erro = String::from_utf8_lossy(&out.stderr);
+    assert!(!erro.contains("quis dizer"), "{}", erro);
+}
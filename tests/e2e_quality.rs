@@ -1,6 +1,6 @@
 use std::fs;
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::process::{Command, Output};
 
 fn bin_path() -> PathBuf {
     let mut p = PathBuf::from(env!("CARGO_BIN_EXE_pordosol"));
@@ -79,6 +79,49 @@ echo "[fake interpreter] $1"
     }
 }
 
+/// Toolchain fake cujo compilador registra cada chamada em `log`: a pasta de trabalho na
+/// primeira linha e um argumento por linha. O log e reescrito a cada compilacao; sondagens
+/// (`--versao`, `--ajuda`) nao o tocam; cada pedido de ajuda vai para `<log>.ajuda` e imprime
+/// `AJUDA_COMPILADOR_FAKE`. `PORDOSOL_JOBS` de cada compilacao fica em `<log>.jobs`.
+#[cfg(unix)]
+fn criar_compilador_com_log(dir: &Path, log: &Path) -> (PathBuf, PathBuf) {
+    use std::os::unix::fs::PermissionsExt;
+
+    let tools = criar_toolchain_fake(dir);
+    let script = format!(
+        r#"#!/usr/bin/env bash
+case "${{1:-}}" in
+  --versao|--version|-V) exit 0 ;;
+  --ajuda|--help) printf '%s\n' "$1" >> '{log}.ajuda'; printf '%s\n' "${{AJUDA_COMPILADOR_FAKE:-}}"; exit 0 ;;
+esac
+{{ pwd; printf '%s\n' "$@"; }} > '{log}'
+printf '%s %s\n' "$(pwd)" "$*" >> '{log}.chamadas'
+printf '%s' "${{PORDOSOL_JOBS:-}}" > '{log}.jobs'
+for arg in "$@"; do
+  case "$arg" in
+    *.pr)
+      b="$(basename "${{arg%.*}}")"; printf x > "$b.pbc"; printf x > "$b.ll"
+      # Como o compilador real: com SO_PRIMEIRO_FONTE_FAKE, o artefato leva o nome do primeiro fonte
+      if [ -n "${{SO_PRIMEIRO_FONTE_FAKE:-}}" ]; then break; fi ;;
+  esac
+done
+"#,
+        log = log.display()
+    );
+    fs::write(&tools.0, script).unwrap();
+    fs::set_permissions(&tools.0, fs::Permissions::from_mode(0o755)).unwrap();
+    tools
+}
+
+/// Le o log de [`criar_compilador_com_log`]: pasta de trabalho e argumentos da ultima compilacao.
+#[cfg(unix)]
+fn ler_log_compilador(log: &Path) -> (PathBuf, Vec<String>) {
+    let registro = fs::read_to_string(log).unwrap();
+    let mut linhas = registro.lines().map(str::to_string);
+    let pasta = PathBuf::from(linhas.next().unwrap_or_default());
+    (pasta, linhas.collect())
+}
+
 fn copiar_diretorio(origem: &Path, destino: &Path) {
     fs::create_dir_all(destino).unwrap();
     for entry in fs::read_dir(origem).unwrap() {
@@ -214,6 +257,13 @@ fn e2e_instalacao_layout_limpo() {
     let interp_path = tools_dir.join(format!("interpretador{}", extensao_executavel()));
     fs::write(&comp_path, b"stub").unwrap();
     fs::write(&interp_path, b"stub").unwrap();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        for p in [&comp_path, &interp_path] {
+            fs::set_permissions(p, fs::Permissions::from_mode(0o755)).unwrap();
+        }
+    }
 
     let stdlib = tools_dir.join("stdlib");
     fs::create_dir_all(&stdlib).unwrap();
@@ -242,3 +292,3118 @@ fn e2e_instalacao_layout_limpo() {
     assert!(s.contains("console"));
     assert!(s.contains("web"));
 }
+
+fn criar_projeto_console(base: &Path, nome: &str) -> PathBuf {
+    let status = Command::new(bin_path())
+        .arg("new")
+        .arg("console")
+        .arg("-n")
+        .arg(nome)
+        .arg("-o")
+        .arg(base)
+        .status()
+        .expect("run new");
+    assert!(status.success());
+    base.join(nome)
+}
+
+fn run_com_toolchain(projeto: &Path, tools: &(PathBuf, PathBuf), args: &[&str]) -> Output {
+    Command::new(bin_path())
+        .arg("run")
+        .arg("--project")
+        .arg(projeto)
+        .args(args)
+        .env("PORDOSOL_COMPILADOR_PATH", &tools.0)
+        .env("PORDOSOL_INTERPRETADOR_PATH", &tools.1)
+        .output()
+        .expect("run run")
+}
+
+#[test]
+fn e2e_run_assert_exit_e_output() {
+    let temp = tempfile::tempdir().unwrap();
+    let tools = criar_toolchain_fake(&temp.path().join("fake-tools"));
+    let projeto = criar_projeto_console(&temp.path().join("workspace"), "app");
+
+    let out = run_com_toolchain(&projeto, &tools, &["--assert-exit", "0"]);
+    assert!(out.status.success());
+
+    let out = run_com_toolchain(&projeto, &tools, &["--assert-exit", "3"]);
+    assert!(!out.status.success());
+    let err = String::from_utf8_lossy(&out.stderr);
+    assert!(err.contains("esperado 3"), "{}", err);
+
+    let out = run_com_toolchain(
+        &projeto,
+        &tools,
+        &["--assert-exit", "0", "--assert-output", "fake interpreter"],
+    );
+    assert!(out.status.success());
+
+    let out = run_com_toolchain(&projeto, &tools, &["--assert-output", "nao-aparece"]);
+    assert!(!out.status.success());
+    let err = String::from_utf8_lossy(&out.stderr);
+    assert!(err.contains("nao-aparece"), "{}", err);
+}
+
+#[test]
+fn e2e_run_arquivo_pbc_em_projeto_sem_fontes() {
+    let temp = tempfile::tempdir().unwrap();
+    let tools = criar_toolchain_fake(&temp.path().join("fake-tools"));
+    let projeto = criar_projeto_console(&temp.path().join("workspace"), "app");
+    for entry in fs::read_dir(projeto.join("src")).unwrap() {
+        let caminho = entry.unwrap().path();
+        if caminho.extension().and_then(|e| e.to_str()) == Some("pr") {
+            fs::remove_file(caminho).unwrap();
+        }
+    }
+    fs::create_dir_all(projeto.join("build")).unwrap();
+    fs::write(projeto.join("build").join("prog.pbc"), "fake-bytecode").unwrap();
+
+    let out = run_com_toolchain(&projeto, &tools, &["--arquivo", "build/prog.pbc"]);
+    assert!(
+        out.status.success(),
+        "stdout: {}\nstderr: {}",
+        String::from_utf8_lossy(&out.stdout),
+        String::from_utf8_lossy(&out.stderr)
+    );
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(stdout.contains("prog.pbc"), "{}", stdout);
+}
+
+#[test]
+fn e2e_run_perfil_producao_usa_pasta_propria() {
+    let temp = tempfile::tempdir().unwrap();
+    let tools = criar_toolchain_fake(&temp.path().join("fake-tools"));
+    let projeto = criar_projeto_console(&temp.path().join("workspace"), "app");
+
+    let out = run_com_toolchain(&projeto, &tools, &["--perfil", "producao", "--no-build"]);
+    assert!(
+        !out.status.success(),
+        "sem artefato, --no-build deve falhar"
+    );
+
+    let out = run_com_toolchain(&projeto, &tools, &["--perfil", "producao"]);
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    let s = String::from_utf8_lossy(&out.stdout);
+    assert!(s.contains("perfil producao"), "{}", s);
+    assert!(projeto
+        .join("build")
+        .join("producao")
+        .join("programa.pbc")
+        .exists());
+    assert!(!projeto.join("build").join("programa.pbc").exists());
+
+    let out = run_com_toolchain(&projeto, &tools, &["--perfil", "producao"]);
+    assert!(out.status.success());
+    let s = String::from_utf8_lossy(&out.stdout);
+    assert!(s.contains("atualizado"), "{}", s);
+
+    let out = run_com_toolchain(&projeto, &tools, &["--perfil", "producao", "--no-build"]);
+    assert!(out.status.success());
+}
+
+#[test]
+fn e2e_nome_saida_do_projeto_e_flag() {
+    let temp = tempfile::tempdir().unwrap();
+    let tools = criar_toolchain_fake(&temp.path().join("fake-tools"));
+    let projeto = criar_projeto_console(&temp.path().join("workspace"), "loja");
+
+    let build = |args: &[&str]| {
+        Command::new(bin_path())
+            .arg("build")
+            .arg("--project")
+            .arg(&projeto)
+            .args(args)
+            .env("PORDOSOL_COMPILADOR_PATH", &tools.0)
+            .env("PORDOSOL_INTERPRETADOR_PATH", &tools.1)
+            .output()
+            .expect("run build")
+    };
+
+    assert!(build(&[]).status.success());
+    assert!(projeto.join("build").join("loja.pbc").exists());
+
+    assert!(build(&["--nome-saida", "vitrine"]).status.success());
+    assert!(projeto.join("build").join("vitrine.pbc").exists());
+
+    let out = build(&["--nome-saida", "meu app"]);
+    assert!(!out.status.success());
+    assert!(String::from_utf8_lossy(&out.stderr).contains("invalido"));
+    let out = build(&["--nome-saida", "a/b"]);
+    assert!(!out.status.success());
+
+    let out = run_com_toolchain(&projeto, &tools, &["--no-build", "--nome-saida", "vitrine"]);
+    assert!(out.status.success());
+    assert!(String::from_utf8_lossy(&out.stdout).contains("vitrine.pbc"));
+}
+
+#[test]
+fn e2e_pasta_saida_configurada_no_proj() {
+    let temp = tempfile::tempdir().unwrap();
+    let tools = criar_toolchain_fake(&temp.path().join("fake-tools"));
+    let projeto = criar_projeto_console(&temp.path().join("workspace"), "app");
+
+    let proj_path = projeto.join("pordosol.proj");
+    let mut proj: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&proj_path).unwrap()).unwrap();
+    proj["configuracao"]["pasta_saida"] = serde_json::json!("saida/bin");
+    fs::write(&proj_path, serde_json::to_string_pretty(&proj).unwrap()).unwrap();
+
+    let status = Command::new(bin_path())
+        .arg("build")
+        .arg("--project")
+        .arg(&projeto)
+        .env("PORDOSOL_COMPILADOR_PATH", &tools.0)
+        .env("PORDOSOL_INTERPRETADOR_PATH", &tools.1)
+        .status()
+        .expect("run build");
+    assert!(status.success());
+    assert!(projeto.join("saida").join("bin").join("app.pbc").exists());
+    assert!(!projeto.join("build").join("app.pbc").exists());
+
+    let out = run_com_toolchain(&projeto, &tools, &["--no-build"]);
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+
+    let outra = temp.path().join("explicita");
+    let status = Command::new(bin_path())
+        .arg("build")
+        .arg("--project")
+        .arg(&projeto)
+        .arg("--saida")
+        .arg(&outra)
+        .env("PORDOSOL_COMPILADOR_PATH", &tools.0)
+        .status()
+        .expect("run build --saida");
+    assert!(status.success());
+    assert!(outra.join("app.pbc").exists(), "--saida tem precedencia");
+}
+
+#[test]
+fn e2e_build_multi_target_relatorio() {
+    let temp = tempfile::tempdir().unwrap();
+    let tools = criar_toolchain_fake(&temp.path().join("fake-tools"));
+    let projeto = criar_projeto_console(&temp.path().join("workspace"), "app");
+
+    let out = Command::new(bin_path())
+        .arg("build")
+        .arg("--project")
+        .arg(&projeto)
+        .arg("--target")
+        .arg("bytecode,llvm-ir")
+        .env("PORDOSOL_COMPILADOR_PATH", &tools.0)
+        .output()
+        .expect("run build multi-target");
+    assert!(out.status.success());
+    let s = String::from_utf8_lossy(&out.stdout);
+    assert!(s.contains("Resumo da execucao"), "{}", s);
+    assert!(s.contains("llvm-ir"));
+
+    let out = Command::new(bin_path())
+        .arg("build")
+        .arg("--project")
+        .arg(&projeto)
+        .arg("--target")
+        .arg("bytecode,llvm-ir")
+        .arg("--fail-fast")
+        .env("PORDOSOL_COMPILADOR_PATH", temp.path().join("nao-existe"))
+        .output()
+        .expect("run build multi-target sem compilador");
+    assert!(!out.status.success());
+    let s = String::from_utf8_lossy(&out.stdout);
+    assert!(s.contains("FALHA"), "{}", s);
+    assert!(s.contains("nao executado"), "{}", s);
+}
+
+#[test]
+fn e2e_doctor_export_e_compare() {
+    let temp = tempfile::tempdir().unwrap();
+    let tools_a = criar_toolchain_fake(&temp.path().join("tools-a"));
+    let tools_b = criar_toolchain_fake(&temp.path().join("tools-b"));
+    let snapshot = temp.path().join("esperado.json");
+
+    let doctor = |tools: &(PathBuf, PathBuf), args: &[&str]| {
+        Command::new(bin_path())
+            .arg("doctor")
+            .arg(temp.path())
+            .args(args)
+            .env("PORDOSOL_COMPILADOR_PATH", &tools.0)
+            .env("PORDOSOL_INTERPRETADOR_PATH", &tools.1)
+            .output()
+            .expect("run doctor")
+    };
+
+    let out = doctor(&tools_a, &["--export", snapshot.to_str().unwrap()]);
+    assert!(out.status.success());
+    assert!(snapshot.exists());
+
+    let cmp = ["--compare", snapshot.to_str().unwrap()];
+    assert!(doctor(&tools_a, &cmp).status.success());
+
+    let out = doctor(&tools_b, &cmp);
+    assert!(!out.status.success());
+    let s = String::from_utf8_lossy(&out.stdout);
+    assert!(s.contains("compilador.caminho"), "{}", s);
+
+    let out = doctor(&tools_b, &[cmp[0], cmp[1], "--ignore-paths"]);
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stdout)
+    );
+}
+
+#[test]
+fn e2e_doctor_avisa_templates_quebrados_e_estrito_promove() {
+    let temp = tempfile::tempdir().unwrap();
+    let tools = criar_toolchain_fake(&temp.path().join("tools"));
+    let templates = temp.path().join("templates");
+    fs::create_dir_all(templates.join("api").join("src")).unwrap();
+    fs::write(
+        templates.join("api").join("src").join("programa.pr.tpl"),
+        "// {{PROJETC_NAME}}\n",
+    )
+    .unwrap();
+    fs::create_dir_all(templates.join("vazio")).unwrap();
+    let stdlib = temp.path().join("stdlib");
+    fs::create_dir_all(&stdlib).unwrap();
+    fs::write(stdlib.join("Sistema.toml"), "versao = \"0.4.1\"\n").unwrap();
+
+    let pordosol = |args: &[&str], templates: &Path| {
+        Command::new(bin_path())
+            .args(args)
+            .env("PORDOSOL_COMPILADOR_PATH", &tools.0)
+            .env("PORDOSOL_INTERPRETADOR_PATH", &tools.1)
+            .env("PORDOSOL_STDLIB_PATH", &stdlib)
+            .env("PORDOSOL_TEMPLATES_PATH", templates)
+            .current_dir(temp.path())
+            .output()
+            .expect("run pordosol")
+    };
+
+    // Avisos nao bloqueiam o ambiente...
+    let out = pordosol(&["doctor"], &templates);
+    let s = String::from_utf8_lossy(&out.stdout);
+    assert!(out.status.success(), "{}", s);
+    assert!(s.contains("origem: env:PORDOSOL_TEMPLATES_PATH"), "{}", s);
+    assert!(s.contains("templates: 2"), "{}", s);
+    assert!(
+        s.contains("{{PROJETC_NAME}} (quis dizer {{PROJECT_NAME}}?)"),
+        "{}",
+        s
+    );
+    assert!(s.contains("nenhum arquivo alem do manifesto"), "{}", s);
+
+    // ...a nao ser com --estrito
+    let out = pordosol(&["doctor", "--estrito", "--json"], &templates);
+    assert_eq!(out.status.code(), Some(2));
+    let json: serde_json::Value = serde_json::from_slice(&out.stdout).expect("json valido");
+    assert_eq!(json["pronto"], false);
+    assert_eq!(json["templates"]["templates"][0]["nome"], "api");
+
+    let out = pordosol(&["template", "verificar"], &templates);
+    assert!(!out.status.success());
+    assert!(String::from_utf8_lossy(&out.stderr).contains("2 de 2 template(s) com problemas"));
+
+    // PORDOSOL_TEMPLATES_PATH quebrado nao passa mais em silencio
+    let out = pordosol(&["doctor"], &temp.path().join("nao-existe"));
+    let s = String::from_utf8_lossy(&out.stdout);
+    assert!(s.contains("nao-existe nao e um diretorio; usando"), "{}", s);
+}
+
+#[cfg(unix)]
+#[test]
+fn e2e_doctor_ambiente_detecta_binario_nao_executavel() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp = tempfile::tempdir().unwrap();
+    let tools = criar_toolchain_fake(&temp.path().join("fake-tools"));
+    let projeto = criar_projeto_console(&temp.path().join("workspace"), "app");
+
+    let doctor = |args: &[&str]| {
+        Command::new(bin_path())
+            .arg("doctor")
+            .arg(&projeto)
+            .arg("--nao-falhar")
+            .args(args)
+            .env("PORDOSOL_COMPILADOR_PATH", &tools.0)
+            .env("PORDOSOL_INTERPRETADOR_PATH", &tools.1)
+            .env_remove("PORDOSOL_HOME")
+            .output()
+            .expect("run doctor")
+    };
+
+    let out = doctor(&["--json"]);
+    assert!(out.status.success());
+    let json: serde_json::Value = serde_json::from_slice(&out.stdout).expect("json valido");
+    let ambiente = json["ambiente"].as_array().expect("ambiente");
+    assert!(ambiente.iter().all(|v| v["ok"] == true), "{:?}", ambiente);
+
+    fs::set_permissions(&tools.0, fs::Permissions::from_mode(0o644)).unwrap();
+
+    let out = doctor(&["--json"]);
+    let json: serde_json::Value = serde_json::from_slice(&out.stdout).expect("json valido");
+    assert_eq!(json["pronto"], false);
+    let item = json["ambiente"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|v| v["nome"] == "compilador executavel")
+        .expect("verificacao do compilador");
+    assert_eq!(item["ok"], false);
+
+    let s = String::from_utf8_lossy(&doctor(&[]).stdout).to_string();
+    assert!(s.contains("compilador executavel: FALHA"), "{}", s);
+    assert!(s.contains("chmod +x"), "{}", s);
+    assert!(s.contains("ambiente com pendencias"), "{}", s);
+}
+
+#[test]
+fn e2e_build_inclui_fontes_de_dependencias_path() {
+    let temp = tempfile::tempdir().unwrap();
+    let tools = criar_toolchain_fake(&temp.path().join("fake-tools"));
+    let workspace = temp.path().join("workspace");
+    let projeto = criar_projeto_console(&workspace, "app");
+
+    let lib_src = workspace.join("lib").join("src");
+    fs::create_dir_all(&lib_src).unwrap();
+    fs::write(lib_src.join("utilidades.pr"), "funcao ajuda() {}\n").unwrap();
+
+    let proj = projeto.join("pordosol.proj");
+    let mut json: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&proj).unwrap()).unwrap();
+    json["dependencias"] = serde_json::json!({"lib": {"path": "../lib"}});
+    fs::write(&proj, serde_json::to_string_pretty(&json).unwrap()).unwrap();
+
+    let out = Command::new(bin_path())
+        .arg("build")
+        .arg(&projeto)
+        .env("PORDOSOL_COMPILADOR_PATH", &tools.0)
+        .env("PORDOSOL_INTERPRETADOR_PATH", &tools.1)
+        .output()
+        .expect("run build");
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    assert!(projeto.join("build").join("utilidades.pbc").exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn e2e_doctor_mostra_versao_das_ferramentas() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp = tempfile::tempdir().unwrap();
+    let (compilador, interpretador) = criar_toolchain_fake(&temp.path().join("fake-tools"));
+    fs::write(
+        &compilador,
+        "#!/usr/bin/env bash\necho \"Compilador Por do Sol (v1.2.3)\"\n",
+    )
+    .unwrap();
+    fs::set_permissions(&compilador, fs::Permissions::from_mode(0o755)).unwrap();
+
+    let doctor = |args: &[&str]| {
+        Command::new(bin_path())
+            .arg("doctor")
+            .arg(temp.path())
+            .arg("--nao-falhar")
+            .args(args)
+            .env("PORDOSOL_COMPILADOR_PATH", &compilador)
+            .env("PORDOSOL_INTERPRETADOR_PATH", &interpretador)
+            .output()
+            .expect("run doctor")
+    };
+
+    let s = String::from_utf8_lossy(&doctor(&[]).stdout).to_string();
+    assert!(
+        s.contains("compilador: OK (v1.2.3) via env:PORDOSOL_COMPILADOR_PATH"),
+        "{}",
+        s
+    );
+    assert!(
+        s.contains("interpretador: OK (versao desconhecida)"),
+        "{}",
+        s
+    );
+
+    let json: serde_json::Value = serde_json::from_slice(&doctor(&["--json"]).stdout).unwrap();
+    assert_eq!(json["compilador"]["versao"], "v1.2.3");
+    assert!(json["interpretador"]["versao"].is_null());
+}
+
+#[cfg(unix)]
+#[test]
+fn e2e_doctor_min_version_reporta_ferramenta_abaixo() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp = tempfile::tempdir().unwrap();
+    let (compilador, interpretador) = criar_toolchain_fake(&temp.path().join("fake-tools"));
+    fs::write(
+        &compilador,
+        "#!/usr/bin/env bash\necho \"Compilador Por do Sol (v1.2.3)\"\n",
+    )
+    .unwrap();
+    fs::set_permissions(&compilador, fs::Permissions::from_mode(0o755)).unwrap();
+
+    let doctor = |args: &[&str]| {
+        Command::new(bin_path())
+            .arg("doctor")
+            .arg(temp.path())
+            .arg("--nao-falhar")
+            .args(args)
+            .env("PORDOSOL_COMPILADOR_PATH", &compilador)
+            .env("PORDOSOL_INTERPRETADOR_PATH", &interpretador)
+            .output()
+            .expect("run doctor")
+    };
+
+    let ok = doctor(&["--min-version", "compilador=1.2.0"]);
+    assert!(
+        ok.status.success(),
+        "{}",
+        String::from_utf8_lossy(&ok.stderr)
+    );
+    assert!(String::from_utf8_lossy(&ok.stdout).contains("compilador >= 1.2.0: OK (v1.2.3)"));
+
+    let falha = doctor(&[
+        "--min-version",
+        "compilador=1.3",
+        "--min-version",
+        "interpretador=0.1",
+    ]);
+    assert!(!falha.status.success());
+    let erro = String::from_utf8_lossy(&falha.stderr);
+    assert!(
+        erro.contains("compilador v1.2.3 (minimo 1.3.0)"),
+        "{}",
+        erro
+    );
+    assert!(
+        erro.contains("interpretador sem versao detectada (minimo 0.1.0)"),
+        "{}",
+        erro
+    );
+
+    let json = doctor(&["--json", "--min-version", "compilador=2.0.0"]);
+    assert!(!json.status.success());
+    let json: serde_json::Value = serde_json::from_slice(&json.stdout).unwrap();
+    assert_eq!(json["versoes_minimas"][0]["ferramenta"], "compilador");
+    assert_eq!(json["versoes_minimas"][0]["ok"], false);
+
+    let invalido = doctor(&["--min-version", "stdlib=1.0"]);
+    assert!(String::from_utf8_lossy(&invalido.stderr).contains("Ferramenta desconhecida"));
+}
+
+#[cfg(unix)]
+#[test]
+fn e2e_pre_checks_bloqueiam_build_e_run() {
+    let temp = tempfile::tempdir().unwrap();
+    let tools = criar_toolchain_fake(&temp.path().join("fake-tools"));
+    let projeto = criar_projeto_console(&temp.path().join("workspace"), "app");
+
+    let proj = projeto.join("pordosol.proj");
+    let mut json: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&proj).unwrap()).unwrap();
+    json["configuracao"]["pre_checks"] = serde_json::json!(["true", "test -f LIBERADO"]);
+    fs::write(&proj, serde_json::to_string_pretty(&json).unwrap()).unwrap();
+
+    let build = |args: &[&str]| {
+        Command::new(bin_path())
+            .arg("build")
+            .arg(&projeto)
+            .args(args)
+            .env("PORDOSOL_COMPILADOR_PATH", &tools.0)
+            .env("PORDOSOL_INTERPRETADOR_PATH", &tools.1)
+            .output()
+            .expect("run build")
+    };
+
+    let out = build(&[]);
+    assert!(!out.status.success());
+    let err = String::from_utf8_lossy(&out.stderr);
+    assert!(err.contains("test -f LIBERADO"), "{}", err);
+    assert!(!projeto.join("build").join("app.pbc").exists());
+
+    let out = run_com_toolchain(&projeto, &tools, &[]);
+    assert!(!out.status.success());
+
+    assert!(build(&["--skip-checks"]).status.success());
+
+    fs::write(projeto.join("LIBERADO"), "").unwrap();
+    let out = run_com_toolchain(&projeto, &tools, &["--force"]);
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+}
+
+#[test]
+fn e2e_run_repassa_argumentos_apos_separador() {
+    let temp = tempfile::tempdir().unwrap();
+    let tools = criar_toolchain_fake(&temp.path().join("fake-tools"));
+    let projeto = criar_projeto_console(&temp.path().join("workspace"), "app");
+
+    let out = run_com_toolchain(&projeto, &tools, &["--", "--porta", "8080"]);
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn e2e_run_watch_recompila_ao_mudar_fonte() {
+    use std::io::{BufRead, BufReader};
+    use std::os::unix::fs::PermissionsExt;
+    use std::process::Stdio;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    let temp = tempfile::tempdir().unwrap();
+    let (compilador, interpretador) = criar_toolchain_fake(&temp.path().join("fake-tools"));
+    fs::write(
+        &interpretador,
+        "#!/usr/bin/env bash\nshift\necho \"[fake interpreter] args: $*\"\n",
+    )
+    .unwrap();
+    fs::set_permissions(&interpretador, fs::Permissions::from_mode(0o755)).unwrap();
+    let projeto = criar_projeto_console(&temp.path().join("workspace"), "app");
+
+    let mut filho = Command::new(bin_path())
+        .arg("run")
+        .arg("--project")
+        .arg(&projeto)
+        .args(["--watch", "--", "--porta", "8080"])
+        .env("PORDOSOL_COMPILADOR_PATH", &compilador)
+        .env("PORDOSOL_INTERPRETADOR_PATH", &interpretador)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("run --watch");
+
+    let (tx, rx) = mpsc::channel();
+    let stdout = filho.stdout.take().unwrap();
+    std::thread::spawn(move || {
+        for linha in BufReader::new(stdout).lines().map_while(Result::ok) {
+            if tx.send(linha).is_err() {
+                break;
+            }
+        }
+    });
+    let esperar = |trecho: &str| loop {
+        let linha = rx
+            .recv_timeout(Duration::from_secs(10))
+            .unwrap_or_else(|_| panic!("timeout esperando {:?}", trecho));
+        if linha.contains(trecho) {
+            return;
+        }
+    };
+
+    esperar("args: --porta 8080");
+    // Garante mtime mais novo que o bytecode mesmo em sistemas de arquivos com resolucao grossa
+    std::thread::sleep(Duration::from_millis(1100));
+    let fonte = projeto.join("src").join("programa.pr");
+    let mut texto = fs::read_to_string(&fonte).unwrap();
+    texto.push_str("\n// alterado\n");
+    fs::write(&fonte, texto).unwrap();
+    esperar("ciclo 2");
+    esperar("Compilando");
+    esperar("args: --porta 8080");
+
+    filho.kill().ok();
+    filho.wait().ok();
+}
+
+#[cfg(unix)]
+#[test]
+fn e2e_build_watch_recompila_e_conta_builds() {
+    use std::io::{BufRead, BufReader};
+    use std::process::Stdio;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    let temp = tempfile::tempdir().unwrap();
+    let log = temp.path().join("compilador.log");
+    let (compilador, interpretador) =
+        criar_compilador_com_log(&temp.path().join("fake-tools"), &log);
+    let projeto = criar_projeto_console(&temp.path().join("workspace"), "app");
+    fs::write(projeto.join("src").join("extra.pr"), "// extra\n").unwrap();
+    fs::write(projeto.join("src").join("util.pr"), "// util\n").unwrap();
+
+    let mut filho = Command::new(bin_path())
+        .arg("build")
+        .arg("--project")
+        .arg(&projeto)
+        .arg("--watch")
+        .env("PORDOSOL_COMPILADOR_PATH", &compilador)
+        .env("PORDOSOL_INTERPRETADOR_PATH", &interpretador)
+        .env(
+            "AJUDA_COMPILADOR_FAKE",
+            "  --incremental  recompila so os fontes dados",
+        )
+        .env("SO_PRIMEIRO_FONTE_FAKE", "1")
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("build --watch");
+
+    let (tx, rx) = mpsc::channel();
+    let stdout = filho.stdout.take().unwrap();
+    std::thread::spawn(move || {
+        for linha in BufReader::new(stdout).lines().map_while(Result::ok) {
+            if tx.send(linha).is_err() {
+                break;
+            }
+        }
+    });
+    let esperar = |trecho: &str| loop {
+        let linha = rx
+            .recv_timeout(Duration::from_secs(10))
+            .unwrap_or_else(|_| panic!("timeout esperando {:?}", trecho));
+        if linha.contains(trecho) {
+            return;
+        }
+    };
+
+    let enviados = || {
+        let (_, args) = ler_log_compilador(&log);
+        let incremental = args.iter().any(|a| a == "--incremental");
+        let nomes: Vec<String> = args
+            .iter()
+            .filter(|a| a.ends_with(".pr"))
+            .map(|a| {
+                Path::new(a)
+                    .file_name()
+                    .unwrap()
+                    .to_string_lossy()
+                    .to_string()
+            })
+            .collect();
+        (incremental, nomes)
+    };
+    let editar = |nome: &str| {
+        std::thread::sleep(Duration::from_millis(1100));
+        let fonte = projeto.join("src").join(nome);
+        let mut texto = fs::read_to_string(&fonte).unwrap();
+        texto.push_str("\n// alterado\n");
+        fs::write(&fonte, texto).unwrap();
+    };
+
+    // O primeiro build e sempre completo, com o principal na frente
+    esperar("build --watch: build 1");
+    esperar("Build 1 concluido em");
+    let (incremental, mut nomes) = enviados();
+    assert!(!incremental);
+    assert_eq!(nomes[0], "programa.pr");
+    nomes.sort();
+    assert_eq!(nomes, ["extra.pr", "programa.pr", "util.pr"]);
+
+    // O compilador anuncia --incremental: o rebuild leva so o fonte editado
+    editar("programa.pr");
+    esperar("build --watch: build 2");
+    esperar("Alterado: src/programa.pr");
+    esperar("com 1 de 3 arquivo(s), o principal e os alterados (--incremental)");
+    esperar("Build 2 concluido em");
+    assert_eq!(enviados(), (true, vec!["programa.pr".into()]));
+
+    // Editar outro fonte ainda gera o artefato do principal: ele vai na frente do alterado
+    editar("extra.pr");
+    esperar("build --watch: build 3");
+    esperar("Alterado: src/extra.pr");
+    esperar("com 2 de 3 arquivo(s), o principal e os alterados (--incremental)");
+    esperar("Build 3 concluido em");
+    assert_eq!(
+        enviados(),
+        (true, vec!["programa.pr".into(), "extra.pr".into()])
+    );
+
+    // Um fonte removido volta a lista completa
+    fs::remove_file(projeto.join("src").join("util.pr")).unwrap();
+    esperar("build --watch: build 4");
+    esperar("Build 4 concluido em");
+    let (incremental, mut nomes) = enviados();
+    assert!(!incremental);
+    nomes.sort();
+    assert_eq!(nomes, ["extra.pr", "programa.pr"]);
+
+    // A ajuda do compilador foi sondada uma vez na sessao (`--ajuda` e `--help`)
+    let sondagens = fs::read_to_string(log.with_extension("log.ajuda")).unwrap();
+    assert_eq!(sondagens.lines().count(), 2, "{}", sondagens);
+
+    filho.kill().ok();
+    filho.wait().ok();
+}
+
+#[test]
+fn e2e_build_de_dentro_da_pasta_build_usa_raiz() {
+    let temp = tempfile::tempdir().unwrap();
+    let tools = criar_toolchain_fake(&temp.path().join("fake-tools"));
+    let projeto = criar_projeto_console(&temp.path().join("workspace"), "app");
+    let build_dir = projeto.join("build").join("producao");
+    fs::create_dir_all(&build_dir).unwrap();
+
+    let out = Command::new(bin_path())
+        .arg("build")
+        .current_dir(&build_dir)
+        .env("PORDOSOL_COMPILADOR_PATH", &tools.0)
+        .env("PORDOSOL_INTERPRETADOR_PATH", &tools.1)
+        .output()
+        .expect("run build");
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    let err = String::from_utf8_lossy(&out.stderr);
+    assert!(err.contains("dentro da pasta de saida"), "{}", err);
+    assert!(projeto.join("build").join("app.pbc").exists());
+    assert!(!build_dir.join("build").exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn e2e_versao_incompativel_do_compilador() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp = tempfile::tempdir().unwrap();
+    let tools = criar_toolchain_fake(&temp.path().join("fake-tools"));
+    let original = fs::read_to_string(&tools.0).unwrap();
+    fs::write(
+        &tools.0,
+        original.replacen(
+            "set -euo pipefail\n",
+            "set -euo pipefail\n[[ \"${1:-}\" == \"--versao\" ]] && { echo \"compilador (v9.0.0)\"; exit 0; }\n",
+            1,
+        ),
+    )
+    .unwrap();
+    fs::set_permissions(&tools.0, fs::Permissions::from_mode(0o755)).unwrap();
+    let projeto = criar_projeto_console(&temp.path().join("workspace"), "app");
+
+    let build = |args: &[&str]| {
+        Command::new(bin_path())
+            .arg("build")
+            .arg(&projeto)
+            .args(args)
+            .env("PORDOSOL_COMPILADOR_PATH", &tools.0)
+            .env("PORDOSOL_INTERPRETADOR_PATH", &tools.1)
+            .output()
+            .expect("run build")
+    };
+
+    let out = build(&[]);
+    assert!(out.status.success());
+    let err = String::from_utf8_lossy(&out.stderr);
+    assert!(err.contains("Aviso") && err.contains("v9.0.0"), "{}", err);
+
+    let out = build(&["--strict-versao"]);
+    assert!(!out.status.success());
+    assert!(String::from_utf8_lossy(&out.stderr).contains("--strict-versao"));
+
+    let out = Command::new(bin_path())
+        .arg("doctor")
+        .arg(&projeto)
+        .env("PORDOSOL_COMPILADOR_PATH", &tools.0)
+        .env("PORDOSOL_INTERPRETADOR_PATH", &tools.1)
+        .output()
+        .expect("run doctor");
+    let s = String::from_utf8_lossy(&out.stdout);
+    assert!(s.contains("compilador compativel: FALHA"), "{}", s);
+}
+
+#[test]
+fn e2e_toolchain_which_para_scripts() {
+    let temp = tempfile::tempdir().unwrap();
+    let tools = criar_toolchain_fake(&temp.path().join("fake-tools"));
+
+    let which = |args: &[&str]| {
+        Command::new(bin_path())
+            .args(["toolchain", "which"])
+            .args(args)
+            .current_dir(temp.path())
+            .env("PORDOSOL_COMPILADOR_PATH", &tools.0)
+            .env("PORDOSOL_FORMATADOR_PATH", temp.path().join("nao-existe"))
+            .env_remove("PORDOSOL_HOME")
+            .env("PATH", "")
+            .output()
+            .expect("run toolchain which")
+    };
+
+    let out = which(&["compilador"]);
+    assert!(out.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&out.stdout),
+        format!("{}\n", tools.0.display())
+    );
+
+    let out = which(&["compilador", "--origem"]);
+    assert_eq!(
+        String::from_utf8_lossy(&out.stdout),
+        "env:PORDOSOL_COMPILADOR_PATH\n"
+    );
+
+    let out = which(&["formatador"]);
+    assert_eq!(out.status.code(), Some(1));
+    assert!(out.stdout.is_empty());
+
+    let out = which(&["formatador", "--todos"]);
+    assert_eq!(out.status.code(), Some(1));
+    let s = String::from_utf8_lossy(&out.stdout);
+    let primeira = s.lines().next().unwrap();
+    assert!(primeira.starts_with("[ ] "), "{}", s);
+    assert!(
+        primeira.contains("env:PORDOSOL_FORMATADOR_PATH (invalido)"),
+        "{}",
+        s
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn e2e_run_release_liga_o_ll_com_clang_do_path() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp = tempfile::tempdir().unwrap();
+    let tools = criar_toolchain_fake(&temp.path().join("fake-tools"));
+    let projeto = criar_projeto_console(&temp.path().join("workspace"), "app");
+    let bin = temp.path().join("bin");
+    fs::create_dir_all(&bin).unwrap();
+    let executavel = |caminho: &Path, texto: &str| {
+        fs::write(caminho, texto).unwrap();
+        fs::set_permissions(caminho, fs::Permissions::from_mode(0o755)).unwrap();
+    };
+    // Compilador que, com llvm-ir, so emite o .ll
+    executavel(
+        &tools.0,
+        r#"#!/usr/bin/env bash
+for arg in "$@"; do
+  case "$arg" in
+    *.pr) printf '; modulo\n' > "$(basename "${arg%.*}").ll" ;;
+  esac
+done
+"#,
+    );
+    // clang falso: o "binario" ligado ecoa os argumentos e sai com o codigo pedido
+    executavel(
+        &bin.join("clang"),
+        r#"#!/usr/bin/env bash
+echo "clang $*" >> "$(dirname "$0")/chamadas"
+while [[ $# -gt 0 ]]; do
+  if [[ "$1" == -o ]]; then
+    printf '#!/usr/bin/env bash\necho "[ligado] $*"\nexit "${SAIDA:-0}"\n' > "$2"
+    chmod +x "$2"
+  fi
+  shift
+done
+"#,
+    );
+
+    let path = format!(
+        "{}:{}",
+        bin.display(),
+        std::env::var("PATH").unwrap_or_default()
+    );
+    let run = |args: &[&str], saida: &str| {
+        Command::new(bin_path())
+            .args(["run", "--project"])
+            .arg(&projeto)
+            .args(args)
+            .env("PORDOSOL_COMPILADOR_PATH", &tools.0)
+            .env("PORDOSOL_INTERPRETADOR_PATH", &tools.1)
+            .env("PATH", &path)
+            .env("SAIDA", saida)
+            .output()
+            .expect("run --release")
+    };
+
+    let out = run(&["--release", "--", "a", "b"], "0");
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    let s = String::from_utf8_lossy(&out.stdout);
+    assert!(s.contains("[ligado] a b"), "{}", s);
+    let ligado = projeto.join("build").join("release").join("programa");
+    assert!(ligado.is_file());
+
+    // Artefato em dia: nem compila nem liga de novo, e o codigo de saida e propagado
+    let out = run(&["--release", "--", "x"], "7");
+    assert_eq!(out.status.code(), Some(7));
+    assert!(String::from_utf8_lossy(&out.stdout).contains("[ligado] x"));
+    let chamadas = fs::read_to_string(bin.join("chamadas")).unwrap();
+    assert_eq!(chamadas.lines().count(), 1, "{}", chamadas);
+}
+
+#[cfg(unix)]
+#[test]
+fn e2e_run_release_exige_binario_nativo() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp = tempfile::tempdir().unwrap();
+    let tools = criar_toolchain_fake(&temp.path().join("fake-tools"));
+    let projeto = criar_projeto_console(&temp.path().join("workspace"), "app");
+
+    let out = run_com_toolchain(&projeto, &tools, &["--release"]);
+    assert!(!out.status.success());
+    let err = String::from_utf8_lossy(&out.stderr);
+    assert!(err.contains("nao sao executaveis via `run`"), "{}", err);
+
+    // Compilador que, com llvm-ir, gera um binario nativo com o stem da fonte
+    fs::write(
+        &tools.0,
+        r#"#!/usr/bin/env bash
+set -euo pipefail
+for arg in "$@"; do
+  case "$arg" in
+    *.pr)
+      stem="$(basename "${arg%.*}")"
+      printf '#!/usr/bin/env bash\necho "[nativo] $*"\n' > "${stem}"
+      chmod +x "${stem}"
+      ;;
+  esac
+done
+"#,
+    )
+    .unwrap();
+    fs::set_permissions(&tools.0, fs::Permissions::from_mode(0o755)).unwrap();
+
+    let out = run_com_toolchain(&projeto, &tools, &["--release", "--", "a", "b"]);
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    let s = String::from_utf8_lossy(&out.stdout);
+    assert!(s.contains("Executando binario nativo"), "{}", s);
+    assert!(s.contains("[nativo] a b"), "{}", s);
+}
+
+#[test]
+fn e2e_run_no_build_avisa_bytecode_desatualizado() {
+    let temp = tempfile::tempdir().unwrap();
+    let tools = criar_toolchain_fake(&temp.path().join("fake-tools"));
+    let projeto = criar_projeto_console(&temp.path().join("workspace"), "app");
+
+    let out = run_com_toolchain(&projeto, &tools, &[]);
+    assert!(out.status.success());
+    let out = run_com_toolchain(&projeto, &tools, &["--no-build"]);
+    assert!(!String::from_utf8_lossy(&out.stderr).contains("aviso:"));
+
+    // Fonte editada depois do build: mtime no futuro evita depender da resolucao do relogio
+    let fonte = projeto.join("src").join("programa.pr");
+    fs::File::options()
+        .append(true)
+        .open(&fonte)
+        .unwrap()
+        .set_modified(std::time::SystemTime::now() + std::time::Duration::from_secs(60))
+        .unwrap();
+
+    let out = run_com_toolchain(&projeto, &tools, &["--no-build"]);
+    assert!(out.status.success(), "sem --strict apenas avisa");
+    let err = String::from_utf8_lossy(&out.stderr);
+    assert!(
+        err.contains("aviso: 1 arquivo(s) .pr mais novos que o bytecode"),
+        "{}",
+        err
+    );
+    assert!(err.contains("programa.pr"), "{}", err);
+    assert!(String::from_utf8_lossy(&out.stdout).contains("[fake interpreter]"));
+
+    let out = run_com_toolchain(&projeto, &tools, &["--no-build", "--strict"]);
+    assert!(!out.status.success());
+    let err = String::from_utf8_lossy(&out.stderr);
+    assert!(err.contains("mais novos que o bytecode"), "{}", err);
+    assert!(!String::from_utf8_lossy(&out.stdout).contains("[fake interpreter]"));
+
+    let out = run_com_toolchain(&projeto, &tools, &["--strict"]);
+    assert!(!out.status.success(), "--strict exige --no-build");
+}
+
+#[cfg(unix)]
+#[test]
+fn e2e_run_input_json_envia_stdin_validado() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp = tempfile::tempdir().unwrap();
+    let tools = criar_toolchain_fake(&temp.path().join("fake-tools"));
+    fs::write(
+        &tools.1,
+        "#!/usr/bin/env bash\necho \"[fake interpreter] stdin: $(cat)\"\n",
+    )
+    .unwrap();
+    fs::set_permissions(&tools.1, fs::Permissions::from_mode(0o755)).unwrap();
+    let projeto = criar_projeto_console(&temp.path().join("workspace"), "app");
+
+    let out = run_com_toolchain(
+        &projeto,
+        &tools,
+        &[
+            "--input-json",
+            r#"{"x":1}"#,
+            "--assert-output",
+            r#"stdin: {"x":1}"#,
+        ],
+    );
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+
+    let arquivo = temp.path().join("entrada.json");
+    fs::write(&arquivo, "[1, 2, 3]\n").unwrap();
+    let out = run_com_toolchain(
+        &projeto,
+        &tools,
+        &[
+            "--input-json",
+            arquivo.to_str().unwrap(),
+            "--assert-output",
+            "stdin: [1, 2, 3]",
+        ],
+    );
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+
+    // JSON malformado falha antes de compilar ou executar
+    fs::remove_dir_all(projeto.join("build")).unwrap();
+    let out = run_com_toolchain(&projeto, &tools, &["--input-json", "{x:1"]);
+    assert!(!out.status.success());
+    let err = String::from_utf8_lossy(&out.stderr);
+    assert!(err.contains("--input-json invalido"), "{}", err);
+    assert!(!String::from_utf8_lossy(&out.stdout).contains("Compilando"));
+    assert!(!projeto.join("build").join("app.pbc").exists());
+}
+
+#[test]
+fn e2e_verboso_detalha_comandos_origens_e_etapas() {
+    let temp = tempfile::tempdir().unwrap();
+    let tools = criar_toolchain_fake(&temp.path().join("fake-tools"));
+    let projeto = criar_projeto_console(&temp.path().join("workspace"), "app");
+    let build = |flag: &str| {
+        Command::new(bin_path())
+            .args(["build", flag, "--project"])
+            .arg(&projeto)
+            .env("PORDOSOL_COMPILADOR_PATH", &tools.0)
+            .env("PORDOSOL_INTERPRETADOR_PATH", &tools.1)
+            .output()
+            .expect("run build")
+    };
+
+    let out = build("--silencioso");
+    assert!(out.status.success());
+    assert!(
+        out.stdout.is_empty(),
+        "{}",
+        String::from_utf8_lossy(&out.stdout)
+    );
+
+    let out = build("-v");
+    assert!(out.status.success());
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(
+        stderr.contains("[verboso] compilador: ")
+            && stderr.contains("(origem env:PORDOSOL_COMPILADOR_PATH)"),
+        "{}",
+        stderr
+    );
+    assert!(stderr.contains("[verboso] Executando: "), "{}", stderr);
+    assert!(stderr.contains("--target=bytecode"), "{}", stderr);
+    assert!(
+        stderr.contains("[verboso] Etapa compilador: "),
+        "{}",
+        stderr
+    );
+    assert_eq!(
+        stderr.matches("[verboso] compilador: ").count(),
+        1,
+        "{}",
+        stderr
+    );
+    assert!(!String::from_utf8_lossy(&out.stdout).contains("[verboso]"));
+
+    let out = build("-qv");
+    assert!(!out.status.success(), "--quiet e --verboso sao exclusivos");
+}
+
+#[test]
+fn e2e_quiet_suprime_mensagens_de_progresso() {
+    let temp = tempfile::tempdir().unwrap();
+    let tools = criar_toolchain_fake(&temp.path().join("fake-tools"));
+    let base = temp.path().join("workspace");
+
+    let out = Command::new(bin_path())
+        .args(["--quiet", "new", "console", "-n", "app", "-o"])
+        .arg(&base)
+        .output()
+        .expect("run new --quiet");
+    assert!(out.status.success());
+    assert!(
+        out.stdout.is_empty(),
+        "{}",
+        String::from_utf8_lossy(&out.stdout)
+    );
+    let projeto = base.join("app");
+    assert!(projeto.join("pordosol.proj").exists());
+
+    let out = Command::new(bin_path())
+        .args(["build", "-q", "--project"])
+        .arg(&projeto)
+        .env("PORDOSOL_COMPILADOR_PATH", &tools.0)
+        .env("PORDOSOL_INTERPRETADOR_PATH", &tools.1)
+        .output()
+        .expect("run build -q");
+    assert!(out.status.success());
+    assert!(
+        out.stdout.is_empty(),
+        "{}",
+        String::from_utf8_lossy(&out.stdout)
+    );
+
+    let out = run_com_toolchain(&projeto, &tools, &["-q", "--force"]);
+    assert!(out.status.success());
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(stdout.starts_with("[fake interpreter]"), "{}", stdout);
+    assert_eq!(stdout.lines().count(), 1, "{}", stdout);
+
+    let out = Command::new(bin_path())
+        .args(["clean", "-q"])
+        .arg(&projeto)
+        .output()
+        .expect("run clean -q");
+    assert!(out.status.success());
+    assert!(out.stdout.is_empty());
+
+    // Erros continuam aparecendo no stderr
+    let out = run_com_toolchain(&projeto, &tools, &["-q", "--no-build"]);
+    assert!(!out.status.success());
+    assert!(String::from_utf8_lossy(&out.stderr).contains("Bytecode nao encontrado"));
+}
+
+#[cfg(unix)]
+#[test]
+fn e2e_test_lista_e_seleciona_por_nome_ou_indice() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp = tempfile::tempdir().unwrap();
+    let tools = criar_toolchain_fake(&temp.path().join("fake-tools"));
+    fs::write(&tools.1, "#!/usr/bin/env bash\necho \"ola $(cat)\"\n").unwrap();
+    fs::set_permissions(&tools.1, fs::Permissions::from_mode(0o755)).unwrap();
+    let projeto = criar_projeto_console(&temp.path().join("workspace"), "app");
+    let testes = projeto.join("testes");
+    fs::create_dir_all(&testes).unwrap();
+    fs::write(testes.join("saudacao.pr"), "// tags: rapido\n").unwrap();
+    fs::write(testes.join("saudacao.entrada"), "mundo\n").unwrap();
+    fs::write(testes.join("saudacao.saida"), "ola mundo\n").unwrap();
+    fs::write(testes.join("saudacao_errada.pr"), "").unwrap();
+    fs::write(testes.join("saudacao_errada.saida"), "tchau\n").unwrap();
+
+    let test = |args: &[&str]| {
+        Command::new(bin_path())
+            .arg("test")
+            .arg("--project")
+            .arg(&projeto)
+            .args(args)
+            .env("PORDOSOL_COMPILADOR_PATH", &tools.0)
+            .env("PORDOSOL_INTERPRETADOR_PATH", &tools.1)
+            .output()
+            .expect("run test")
+    };
+
+    let out = test(&["--lista"]);
+    assert!(out.status.success());
+    let s = String::from_utf8_lossy(&out.stdout);
+    assert!(
+        s.contains("1. saudacao  tags: rapido  golden: sim  entrada: sim"),
+        "{}",
+        s
+    );
+    assert!(
+        s.contains("2. saudacao_errada  tags: -  golden: sim  entrada: nao"),
+        "{}",
+        s
+    );
+    assert!(
+        !projeto.join("build").join("testes").exists(),
+        "--lista nao compila"
+    );
+
+    let out = test(&["saudacao"]);
+    assert!(
+        out.status.success(),
+        "{}{}",
+        String::from_utf8_lossy(&out.stdout),
+        String::from_utf8_lossy(&out.stderr)
+    );
+    assert!(String::from_utf8_lossy(&out.stdout).contains("1 teste(s): 1 ok"));
+
+    let out = test(&["--indice", "2"]);
+    assert!(!out.status.success());
+    assert!(String::from_utf8_lossy(&out.stdout).contains("FALHA saudacao_errada"));
+
+    let out = test(&["sau"]);
+    assert!(!out.status.success());
+    assert!(String::from_utf8_lossy(&out.stderr).contains("ambiguo"));
+
+    let out = test(&["--indice", "3"]);
+    assert!(!out.status.success());
+    assert!(String::from_utf8_lossy(&out.stderr).contains("fora do intervalo"));
+
+    let out = test(&[]);
+    assert!(!out.status.success());
+    assert!(String::from_utf8_lossy(&out.stdout).contains("2 teste(s): 1 ok, 1 falha(s)"));
+}
+
+#[cfg(unix)]
+#[test]
+fn e2e_artefato_em_subpasta_do_compilador() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp = tempfile::tempdir().unwrap();
+    let tools = criar_toolchain_fake(&temp.path().join("fake-tools"));
+    fs::write(
+        &tools.0,
+        "#!/usr/bin/env bash\nset -euo pipefail\ncase \"${1:-}\" in --versao|--version|-V) exit 0 ;; esac\nmkdir -p out\nfor arg in \"$@\"; do\n  case \"$arg\" in\n    *.pr) printf \"fake-bytecode\\n\" > \"out/$(basename \"${arg%.*}\").pbc\" ;;\n  esac\ndone\n",
+    )
+    .unwrap();
+    fs::set_permissions(&tools.0, fs::Permissions::from_mode(0o755)).unwrap();
+    let projeto = criar_projeto_console(&temp.path().join("workspace"), "app");
+    let aninhado = projeto.join("build").join("out").join("programa.pbc");
+
+    let out = run_com_toolchain(&projeto, &tools, &["--nome-saida", "programa"]);
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    assert!(String::from_utf8_lossy(&out.stdout).contains("[fake interpreter]"));
+    assert!(aninhado.exists());
+    let manifesto = fs::read_to_string(projeto.join("build").join(".pordosol-build.json")).unwrap();
+    assert!(
+        manifesto.contains("\"programa.pbc\": \"out/programa.pbc\""),
+        "{}",
+        manifesto
+    );
+
+    // Execucoes seguintes olham primeiro o local registrado (sem recompilar)
+    let out = run_com_toolchain(&projeto, &tools, &["--nome-saida", "programa"]);
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(
+        stdout.contains("atualizado, pulando compilacao"),
+        "{}",
+        stdout
+    );
+    assert!(stdout.contains("out/programa.pbc"), "{}", stdout);
+
+    // Nenhum artefato gerado: erro listando o que foi encontrado. A espera garante
+    // que o artefato anterior fique mais antigo que a compilacao em FS de mtime grosso.
+    std::thread::sleep(std::time::Duration::from_millis(1100));
+    fs::write(&tools.0, "#!/usr/bin/env bash\nexit 0\n").unwrap();
+    let out = run_com_toolchain(&projeto, &tools, &["--nome-saida", "programa", "--force"]);
+    assert!(!out.status.success());
+    let err = String::from_utf8_lossy(&out.stderr);
+    assert!(err.contains("Nenhum artefato programa.pbc"), "{}", err);
+    assert!(err.contains("out/programa.pbc (antigo)"), "{}", err);
+
+    // Dois artefatos recentes com o mesmo nome sao ambiguos
+    fs::write(
+        &tools.0,
+        "#!/usr/bin/env bash\ncase \"${1:-}\" in --versao|--version|-V) exit 0 ;; esac\nmkdir -p a b\necho x > a/programa.pbc\necho x > b/programa.pbc\n",
+    )
+    .unwrap();
+    let out = run_com_toolchain(&projeto, &tools, &["--nome-saida", "programa", "--force"]);
+    assert!(!out.status.success());
+    let err = String::from_utf8_lossy(&out.stderr);
+    assert!(err.contains("Mais de um artefato"), "{}", err);
+}
+
+#[test]
+fn e2e_flags_compiler_e_interpreter_tem_precedencia() {
+    let temp = tempfile::tempdir().unwrap();
+    let tools = criar_toolchain_fake(&temp.path().join("fake-tools"));
+    let projeto = criar_projeto_console(&temp.path().join("workspace"), "app");
+    let inexistente = temp.path().join("nao-existe");
+
+    let out = Command::new(bin_path())
+        .arg("run")
+        .arg("--project")
+        .arg(&projeto)
+        .arg("--compiler")
+        .arg(&tools.0)
+        .arg("--interpreter")
+        .arg(&tools.1)
+        .env("PORDOSOL_COMPILADOR_PATH", &inexistente)
+        .env("PORDOSOL_INTERPRETADOR_PATH", &inexistente)
+        .output()
+        .expect("run com overrides");
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    assert!(String::from_utf8_lossy(&out.stdout).contains("[fake interpreter]"));
+
+    let out = Command::new(bin_path())
+        .args(["toolchain", "which", "compilador", "--origem", "--project"])
+        .arg(&projeto)
+        .arg("--compiler")
+        .arg(&tools.0)
+        .output()
+        .expect("run which");
+    assert_eq!(
+        String::from_utf8_lossy(&out.stdout).trim(),
+        "flag:--compiler"
+    );
+
+    let out = Command::new(bin_path())
+        .arg("build")
+        .arg("--project")
+        .arg(&projeto)
+        .arg("--compiler")
+        .arg(&inexistente)
+        .env("PORDOSOL_COMPILADOR_PATH", &tools.0)
+        .output()
+        .expect("run build com override invalido");
+    assert_eq!(out.status.code(), Some(2));
+    let err = String::from_utf8_lossy(&out.stderr);
+    assert!(err.contains("--compiler"), "{}", err);
+    assert!(err.contains("nao encontrado"), "{}", err);
+
+    #[cfg(unix)]
+    {
+        let nao_exec = temp.path().join("comp.txt");
+        fs::write(&nao_exec, "texto").unwrap();
+        let out = Command::new(bin_path())
+            .arg("build")
+            .arg("--project")
+            .arg(&projeto)
+            .arg("--compiler")
+            .arg(&nao_exec)
+            .output()
+            .expect("run build com override nao executavel");
+        assert_eq!(out.status.code(), Some(2));
+        assert!(String::from_utf8_lossy(&out.stderr).contains("nao e executavel"));
+    }
+}
+
+#[test]
+fn e2e_build_timings_registra_historico() {
+    let temp = tempfile::tempdir().unwrap();
+    let tools = criar_toolchain_fake(&temp.path().join("fake-tools"));
+    let projeto = criar_projeto_console(&temp.path().join("workspace"), "app");
+    let build = |sub: &str| {
+        Command::new(bin_path())
+            .arg(sub)
+            .arg("--timings")
+            .arg(if sub == "build" {
+                "--project"
+            } else {
+                "--target"
+            })
+            .arg(if sub == "build" {
+                projeto.as_os_str()
+            } else {
+                "console".as_ref()
+            })
+            .current_dir(&projeto)
+            .env("PORDOSOL_COMPILADOR_PATH", &tools.0)
+            .env("PORDOSOL_INTERPRETADOR_PATH", &tools.1)
+            .output()
+            .expect("run --timings")
+    };
+
+    let out = build("build");
+    assert!(out.status.success());
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(stdout.contains("Tempos (build bytecode):"), "{}", stdout);
+    for etapa in [
+        "descoberta de fontes",
+        "compilador",
+        "listagem de artefatos",
+        "total",
+    ] {
+        assert!(stdout.contains(etapa), "{}", stdout);
+    }
+    assert!(build("build").status.success());
+    assert!(!String::from_utf8_lossy(&build("build").stdout).contains(".timings.jsonl"));
+
+    let out = build("producao");
+    assert!(out.status.success());
+    assert!(String::from_utf8_lossy(&out.stdout).contains("Tempos (producao console):"));
+
+    let historico = fs::read_to_string(projeto.join("build").join(".timings.jsonl")).unwrap();
+    let registros: Vec<serde_json::Value> = historico
+        .lines()
+        .map(|l| serde_json::from_str(l).unwrap())
+        .collect();
+    assert_eq!(registros.len(), 4);
+    assert_eq!(registros[0]["comando"], "build");
+    assert_eq!(registros[0]["etapas"][1]["etapa"], "compilador");
+    assert_eq!(registros[3]["comando"], "producao");
+
+    // O historico nao afeta a checagem de bytecode atualizado nem o clean
+    let out = run_com_toolchain(&projeto, &tools, &[]);
+    assert!(String::from_utf8_lossy(&out.stdout).contains("atualizado, pulando compilacao"));
+    let status = Command::new(bin_path())
+        .arg("clean")
+        .arg(&projeto)
+        .status()
+        .expect("run clean");
+    assert!(status.success());
+    assert!(!projeto.join("build").join(".timings.jsonl").exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn e2e_builds_simultaneos_sao_serializados_pela_trava() {
+    use std::os::unix::fs::PermissionsExt;
+    use std::process::Stdio;
+
+    let temp = tempfile::tempdir().unwrap();
+    let (compilador, interpretador) = criar_toolchain_fake(&temp.path().join("fake-tools"));
+    let log = temp.path().join("compilacoes.log");
+    fs::write(
+        &compilador,
+        format!(
+            "#!/usr/bin/env bash\nset -euo pipefail\ncase \"${{1:-}}\" in --versao|--version|-V) exit 0 ;; esac\necho inicio >> '{log}'\nsleep 0.5\nfor arg in \"$@\"; do\n  case \"$arg\" in\n    *.pr) printf 'fake-bytecode\\n' > \"$(basename \"${{arg%.*}}\").pbc\" ;;\n  esac\ndone\necho fim >> '{log}'\n",
+            log = log.display()
+        ),
+    )
+    .unwrap();
+    fs::set_permissions(&compilador, fs::Permissions::from_mode(0o755)).unwrap();
+    let projeto = criar_projeto_console(&temp.path().join("workspace"), "app");
+
+    let build = |extra: &[&str]| {
+        Command::new(bin_path())
+            .arg("build")
+            .arg("--project")
+            .arg(&projeto)
+            .args(extra)
+            .env("PORDOSOL_COMPILADOR_PATH", &compilador)
+            .env("PORDOSOL_INTERPRETADOR_PATH", &interpretador)
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .expect("spawn build")
+    };
+
+    let a = build(&[]);
+    std::thread::sleep(std::time::Duration::from_millis(150));
+    let b = build(&[]);
+    let (a, b) = (a.wait_with_output().unwrap(), b.wait_with_output().unwrap());
+    assert!(a.status.success() && b.status.success());
+    assert_eq!(
+        fs::read_to_string(&log).unwrap(),
+        "inicio\nfim\ninicio\nfim\n",
+        "compilacoes intercaladas"
+    );
+    let espera = String::from_utf8_lossy(&b.stderr);
+    assert!(
+        espera.contains("Outro processo pordosol esta construindo este projeto (PID"),
+        "{}",
+        espera
+    );
+    assert!(!projeto.join("build").join(".lock").exists());
+
+    // --sem-lock deixa os dois compilarem ao mesmo tempo
+    fs::remove_file(&log).unwrap();
+    let a = build(&["--sem-lock"]);
+    std::thread::sleep(std::time::Duration::from_millis(150));
+    let b = build(&["--sem-lock"]);
+    a.wait_with_output().unwrap();
+    b.wait_with_output().unwrap();
+    assert_eq!(
+        fs::read_to_string(&log).unwrap(),
+        "inicio\ninicio\nfim\nfim\n"
+    );
+
+    // Trava que sobrou de um processo morto e reaproveitada sem espera
+    fs::write(projeto.join("build").join(".lock"), "999999999\n").unwrap();
+    let out = build(&[]).wait_with_output().unwrap();
+    assert!(out.status.success());
+    assert!(String::from_utf8_lossy(&out.stderr).contains("trava abandonada pelo PID 999999999"));
+}
+
+#[cfg(unix)]
+#[test]
+fn e2e_check_valida_sem_tocar_build() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp = tempfile::tempdir().unwrap();
+    let tools = criar_toolchain_fake(&temp.path().join("fake-tools"));
+    let projeto = criar_projeto_console(&temp.path().join("workspace"), "app");
+    let build_vazia = || fs::read_dir(projeto.join("build")).unwrap().count() == 0;
+    let check_com = |args: &[&str]| {
+        Command::new(bin_path())
+            .arg("check")
+            .arg(&projeto)
+            .args(args)
+            .env("PORDOSOL_COMPILADOR_PATH", &tools.0)
+            .env("PORDOSOL_INTERPRETADOR_PATH", &tools.1)
+            .output()
+            .expect("run check")
+    };
+    let check = || check_com(&[]);
+
+    // O compilador fake padrao nao anuncia verificacao sem emissao: compila num temporario
+    let out = check();
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    assert!(build_vazia());
+    let json: serde_json::Value =
+        serde_json::from_slice(&check_com(&["--formato-mensagens", "json"]).stdout).unwrap();
+    assert_eq!(json["ok"], true);
+    assert_eq!(json["modo"], "bytecode-descartado");
+
+    fs::write(
+        &tools.0,
+        "#!/usr/bin/env bash\nset -euo pipefail\ncase \"${1:-}\" in\n  --ajuda|--help) echo \"  --apenas-verificar  valida sem gerar artefatos\"; exit 0 ;;\n  --apenas-verificar) shift; grep -q ERRO \"$@\" && { echo \"erro de sintaxe\" >&2; exit 1; }; exit 0 ;;\nesac\nexit 3\n",
+    )
+    .unwrap();
+    fs::set_permissions(&tools.0, fs::Permissions::from_mode(0o755)).unwrap();
+
+    let out = check();
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    assert!(String::from_utf8_lossy(&out.stdout).contains("Verificacao concluida: 1 arquivo(s)"));
+    assert!(build_vazia());
+
+    fs::write(projeto.join("src").join("programa.pr"), "ERRO\n").unwrap();
+    let out = check();
+    assert!(!out.status.success());
+    assert!(String::from_utf8_lossy(&out.stderr).contains("Verificacao falhou"));
+    assert!(build_vazia());
+
+    let out = check_com(&["--formato-mensagens", "json"]);
+    assert!(!out.status.success());
+    let json: serde_json::Value = serde_json::from_slice(&out.stdout).unwrap();
+    assert_eq!(json["ok"], false);
+    assert_eq!(json["modo"], "sem-emissao");
+    assert_eq!(json["diagnosticos"], serde_json::json!(["erro de sintaxe"]));
+}
+
+#[cfg(unix)]
+#[test]
+fn e2e_build_oneline_imprime_uma_linha() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp = tempfile::tempdir().unwrap();
+    let tools = criar_toolchain_fake(&temp.path().join("fake-tools"));
+    let projeto = criar_projeto_console(&temp.path().join("workspace"), "app");
+    let build = || {
+        Command::new(bin_path())
+            .args(["build", "--oneline", "--project"])
+            .arg(&projeto)
+            .env("PORDOSOL_COMPILADOR_PATH", &tools.0)
+            .env("PORDOSOL_INTERPRETADOR_PATH", &tools.1)
+            .output()
+            .expect("run build --oneline")
+    };
+
+    let out = build();
+    assert!(out.status.success());
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert_eq!(stdout.lines().count(), 1, "{}", stdout);
+    assert!(
+        stdout.starts_with("OK: 1 arquivo(s), ") && stdout.contains(" artefato(s), "),
+        "{}",
+        stdout
+    );
+
+    fs::write(
+        &tools.0,
+        "#!/usr/bin/env bash\ncase \"${1:-}\" in --versao|--version|-V) exit 0 ;; esac\necho \"compilando...\"\necho \"$2:3: erro: token inesperado\" >&2\nexit 1\n",
+    )
+    .unwrap();
+    fs::set_permissions(&tools.0, fs::Permissions::from_mode(0o755)).unwrap();
+
+    let out = build();
+    assert!(!out.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&out.stdout),
+        format!(
+            "FALHOU: erro em {}\n",
+            Path::new("src").join("programa.pr").display()
+        )
+    );
+    assert!(
+        out.stderr.is_empty(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn e2e_script_compila_fora_de_projeto_e_limpa() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp = tempfile::tempdir().unwrap();
+    let tools = criar_toolchain_fake(&temp.path().join("fake-tools"));
+    fs::write(
+        &tools.1,
+        "#!/usr/bin/env bash\necho \"[fake interpreter] $*\"\n",
+    )
+    .unwrap();
+    fs::set_permissions(&tools.1, fs::Permissions::from_mode(0o755)).unwrap();
+    let solto = temp.path().join("solto");
+    fs::create_dir_all(&solto).unwrap();
+    let arquivo = solto.join("ola.pr");
+    fs::write(&arquivo, "// script\n").unwrap();
+    let tmp = temp.path().join("tmp");
+    fs::create_dir_all(&tmp).unwrap();
+    let script = |extras: &[&str]| {
+        Command::new(bin_path())
+            .arg("script")
+            .arg(&arquivo)
+            .args(extras)
+            .env("PORDOSOL_COMPILADOR_PATH", &tools.0)
+            .env("PORDOSOL_INTERPRETADOR_PATH", &tools.1)
+            .env("TMPDIR", &tmp)
+            .output()
+            .expect("run script")
+    };
+
+    let out = script(&["--", "a", "b"]);
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(stdout.contains("ola.pbc a b"), "{}", stdout);
+    assert_eq!(
+        fs::read_dir(&solto).unwrap().count(),
+        1,
+        "nada deve ser criado ao lado do script"
+    );
+    let cache = tmp.join("pordosol-cache");
+    assert_eq!(fs::read_dir(&cache).unwrap().count(), 0);
+
+    let out = script(&["--manter"]);
+    assert!(out.status.success());
+    let mantidos: Vec<PathBuf> = fs::read_dir(&cache)
+        .unwrap()
+        .map(|e| e.unwrap().path())
+        .collect();
+    assert_eq!(mantidos.len(), 1);
+    assert!(mantidos[0].join("ola.pbc").is_file());
+    assert!(String::from_utf8_lossy(&out.stdout).contains("Artefatos mantidos em"));
+}
+
+#[cfg(unix)]
+#[test]
+fn e2e_run_stdin_compila_o_codigo_recebido_e_limpa() {
+    use std::io::Write;
+    use std::os::unix::fs::PermissionsExt;
+    use std::process::Stdio;
+
+    let temp = tempfile::tempdir().unwrap();
+    let tools = criar_toolchain_fake(&temp.path().join("fake-tools"));
+    // O "bytecode" e o proprio fonte, para o interpretador mostrar o que foi compilado
+    fs::write(
+        &tools.0,
+        "#!/usr/bin/env bash\nfor a in \"$@\"; do case \"$a\" in *.pr) cp \"$a\" \"$(basename \"${a%.*}\").pbc\";; esac; done\n",
+    )
+    .unwrap();
+    fs::write(
+        &tools.1,
+        "#!/usr/bin/env bash\ncat \"$1\"; shift; echo \"args: $* MODO=$MODO\"\n",
+    )
+    .unwrap();
+    for t in [&tools.0, &tools.1] {
+        fs::set_permissions(t, fs::Permissions::from_mode(0o755)).unwrap();
+    }
+    let tmp = temp.path().join("tmp");
+    fs::create_dir_all(&tmp).unwrap();
+
+    let run = |codigo: &str, extras: &[&str]| {
+        let mut filho = Command::new(bin_path())
+            .args(["run", "-"])
+            .args(extras)
+            .current_dir(temp.path())
+            .env("PORDOSOL_COMPILADOR_PATH", &tools.0)
+            .env("PORDOSOL_INTERPRETADOR_PATH", &tools.1)
+            .env("TMPDIR", &tmp)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .expect("run -");
+        filho
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(codigo.as_bytes())
+            .unwrap();
+        filho.wait_with_output().unwrap()
+    };
+
+    let out = run(
+        "funcao vazio Principal() { }\n",
+        &["--env", "MODO=teste", "--", "a", "b"],
+    );
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(
+        stdout.contains("funcao vazio Principal() { }"),
+        "{}",
+        stdout
+    );
+    assert!(stdout.contains("args: a b MODO=teste"), "{}", stdout);
+    assert_eq!(
+        fs::read_dir(tmp.join("pordosol-cache")).unwrap().count(),
+        0,
+        "o temporario deve ser removido"
+    );
+
+    let out = run("  \n", &[]);
+    assert!(!out.status.success());
+    assert!(String::from_utf8_lossy(&out.stderr).contains("Nenhum codigo recebido no stdin"));
+
+    let out = run("funcao vazio Principal() { }\n", &["--watch"]);
+    assert!(!out.status.success());
+}
+
+#[cfg(unix)]
+#[test]
+fn e2e_entrada_do_proj_vem_primeiro_e_valida_o_campo() {
+    let temp = tempfile::tempdir().unwrap();
+    let log = temp.path().join("compilador.log");
+    let tools = criar_compilador_com_log(&temp.path().join("fake-tools"), &log);
+    let projeto = criar_projeto_console(&temp.path().join("workspace"), "app");
+    fs::write(projeto.join("src").join("principal.pr"), "// entrada\n").unwrap();
+
+    let proj_path = projeto.join("pordosol.proj");
+    let mut proj: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&proj_path).unwrap()).unwrap();
+    proj["entrada"] = serde_json::json!("src/principal.pr");
+    fs::write(&proj_path, serde_json::to_string_pretty(&proj).unwrap()).unwrap();
+
+    let out = run_com_toolchain(&projeto, &tools, &[]);
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    let (_, args) = ler_log_compilador(&log);
+    let primeiro = args.iter().find(|a| a.ends_with(".pr")).map(Path::new);
+    assert_eq!(
+        primeiro.and_then(Path::file_name),
+        Some("principal.pr".as_ref()),
+        "{:?}",
+        args
+    );
+
+    proj["entrada"] = serde_json::json!("src/sumiu.pr");
+    fs::write(&proj_path, serde_json::to_string_pretty(&proj).unwrap()).unwrap();
+    let out = run_com_toolchain(&projeto, &tools, &["--force"]);
+    assert_eq!(out.status.code(), Some(3));
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(
+        stderr.contains("src/sumiu.pr") && stderr.contains("campo `entrada`"),
+        "{}",
+        stderr
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn e2e_run_env_clear_repassa_so_variaveis_herdadas() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp = tempfile::tempdir().unwrap();
+    let tools = criar_toolchain_fake(&temp.path().join("fake-tools"));
+    fs::write(&tools.1, "#!/bin/sh\n/usr/bin/env\n").unwrap();
+    fs::set_permissions(&tools.1, fs::Permissions::from_mode(0o755)).unwrap();
+    let projeto = criar_projeto_console(&temp.path().join("workspace"), "app");
+    let run = |args: &[&str]| {
+        Command::new(bin_path())
+            .args(["run", "--project"])
+            .arg(&projeto)
+            .args(args)
+            .env("PORDOSOL_COMPILADOR_PATH", &tools.0)
+            .env("PORDOSOL_INTERPRETADOR_PATH", &tools.1)
+            .env("PORDOSOL_TESTE_MANTIDA", "sim")
+            .env("PORDOSOL_TESTE_DESCARTADA", "nao")
+            .env_remove("PORDOSOL_TESTE_AUSENTE")
+            .output()
+            .expect("run run")
+    };
+
+    let out = run(&[]);
+    assert!(out.status.success());
+    assert!(String::from_utf8_lossy(&out.stdout).contains("PORDOSOL_TESTE_DESCARTADA=nao"));
+
+    let out = run(&[
+        "--env-clear",
+        "--env-inherit",
+        "PORDOSOL_TESTE_MANTIDA,PORDOSOL_TESTE_AUSENTE",
+    ]);
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(stdout.contains("PORDOSOL_TESTE_MANTIDA=sim"), "{}", stdout);
+    assert!(!stdout.contains("PORDOSOL_TESTE_DESCARTADA"), "{}", stdout);
+    assert!(!stdout.contains("PORDOSOL_COMPILADOR_PATH"), "{}", stdout);
+    assert!(String::from_utf8_lossy(&out.stderr)
+        .contains("variavel PORDOSOL_TESTE_AUSENTE nao esta definida"));
+}
+
+#[cfg(unix)]
+#[test]
+fn e2e_run_env_e_env_file_chegam_ao_programa() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp = tempfile::tempdir().unwrap();
+    let tools = criar_toolchain_fake(&temp.path().join("fake-tools"));
+    fs::write(&tools.1, "#!/bin/sh\n/usr/bin/env\n").unwrap();
+    fs::set_permissions(&tools.1, fs::Permissions::from_mode(0o755)).unwrap();
+    let projeto = criar_projeto_console(&temp.path().join("workspace"), "app");
+    let env_file = temp.path().join("app.env");
+    fs::write(
+        &env_file,
+        "# configuracao local\nPORDOSOL_TESTE_ARQUIVO=do_arquivo\n\nPORDOSOL_TESTE_SOBRESCRITA=arquivo\n",
+    )
+    .unwrap();
+
+    let out = run_com_toolchain(
+        &projeto,
+        &tools,
+        &[
+            "--env",
+            "PORDOSOL_TESTE_URL=a=b=c",
+            "--env-file",
+            env_file.to_str().unwrap(),
+            "--env",
+            "PORDOSOL_TESTE_SOBRESCRITA=cli",
+        ],
+    );
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(stdout.contains("PORDOSOL_TESTE_URL=a=b=c\n"), "{}", stdout);
+    assert!(
+        stdout.contains("PORDOSOL_TESTE_ARQUIVO=do_arquivo\n"),
+        "{}",
+        stdout
+    );
+    assert!(
+        stdout.contains("PORDOSOL_TESTE_SOBRESCRITA=cli\n"),
+        "{}",
+        stdout
+    );
+
+    let out = run_com_toolchain(&projeto, &tools, &["--env", "SEM_IGUAL"]);
+    assert!(!out.status.success());
+    assert!(String::from_utf8_lossy(&out.stderr).contains("esperado CHAVE=VALOR"));
+}
+
+#[test]
+fn e2e_listar_mudancas_compara_com_ultimo_build() {
+    let temp = tempfile::tempdir().unwrap();
+    let tools = criar_toolchain_fake(&temp.path().join("fake-tools"));
+    let projeto = criar_projeto_console(&temp.path().join("workspace"), "app");
+    fs::write(projeto.join("src").join("util.pr"), "// util\n").unwrap();
+
+    let mudancas = |args: &[&str]| {
+        let out = Command::new(bin_path())
+            .arg("listar")
+            .arg(&projeto)
+            .arg("--mudancas")
+            .args(args)
+            .output()
+            .expect("run listar");
+        assert!(out.status.success());
+        String::from_utf8_lossy(&out.stdout).to_string()
+    };
+
+    let antes = mudancas(&[]);
+    assert!(
+        antes.contains("Nenhum build anterior registrado"),
+        "{}",
+        antes
+    );
+    assert!(antes.contains("2 novo(s)"), "{}", antes);
+
+    let out = run_com_toolchain(&projeto, &tools, &[]);
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+
+    fs::write(projeto.join("src").join("util.pr"), "// util v2\n").unwrap();
+    fs::remove_file(projeto.join("src").join("programa.pr")).unwrap();
+    fs::write(projeto.join("src").join("extra.pr"), "// extra\n").unwrap();
+
+    let depois = mudancas(&[]);
+    assert!(
+        depois.contains("1 novo(s), 1 modificado(s), 0 inalterado(s), 1 removido(s)"),
+        "{}",
+        depois
+    );
+
+    let json: serde_json::Value =
+        serde_json::from_str(&mudancas(&["--somente", "modificados", "--json"])).unwrap();
+    assert_eq!(json["registro"], true);
+    assert_eq!(json["contagem"]["removido"], 1);
+    assert_eq!(
+        json["arquivos"],
+        serde_json::json!([{"arquivo": "src/util.pr", "situacao": "modificado"}])
+    );
+}
+
+#[test]
+fn e2e_run_arquivo_relativo_de_subpasta_resolve_pela_raiz() {
+    let temp = tempfile::tempdir().unwrap();
+    let tools = criar_toolchain_fake(&temp.path().join("fake-tools"));
+    let projeto = criar_projeto_console(&temp.path().join("workspace"), "app");
+    assert!(run_com_toolchain(&projeto, &tools, &[]).status.success());
+
+    let subpasta = projeto.join("src");
+    let run_de_subpasta = |arquivo: &str| {
+        Command::new(bin_path())
+            .current_dir(&subpasta)
+            .args(["run", "--arquivo", arquivo])
+            .env("PORDOSOL_COMPILADOR_PATH", &tools.0)
+            .env("PORDOSOL_INTERPRETADOR_PATH", &tools.1)
+            .output()
+            .expect("run run")
+    };
+
+    let out = run_de_subpasta("build/app.pbc");
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    let pbc = projeto.join("build").join("app.pbc");
+    assert!(
+        stdout.contains(&format!(
+            "[fake interpreter] {}",
+            pbc.canonicalize().unwrap().display()
+        )) || stdout.contains(&format!("[fake interpreter] {}", pbc.display())),
+        "{}",
+        stdout
+    );
+
+    // .pr relativo ao cwd tem prioridade; sem ele, vale a raiz do projeto
+    let out = run_de_subpasta("programa.pr");
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    let out = run_de_subpasta("src/programa.pr");
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+
+    let out = run_de_subpasta("build/nada.pbc");
+    assert!(!out.status.success());
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(stderr.contains("relativo ao diretorio atual"), "{}", stderr);
+    assert!(stderr.contains("relativo a raiz do projeto"), "{}", stderr);
+}
+
+#[cfg(unix)]
+#[test]
+fn e2e_producao_tem_paridade_de_flags_com_build() {
+    let temp = tempfile::tempdir().unwrap();
+    let log = temp.path().join("compilador.log");
+    let tools = criar_compilador_com_log(&temp.path().join("fake-tools"), &log);
+    let projeto = criar_projeto_console(&temp.path().join("workspace"), "app");
+    fs::write(projeto.join("src").join("extra.pr"), "// extra\n").unwrap();
+
+    let executar = |comando: &str, args: &[&str]| {
+        Command::new(bin_path())
+            .arg(comando)
+            .arg(&projeto)
+            .args(args)
+            .env("PORDOSOL_COMPILADOR_PATH", &tools.0)
+            .env("PORDOSOL_INTERPRETADOR_PATH", &tools.1)
+            .output()
+            .expect("run comando")
+    };
+
+    for comando in ["build", "producao"] {
+        // --saida: o compilador roda na pasta pedida
+        let saida = temp.path().join(format!("saida-{}", comando));
+        let out = executar(comando, &["--saida", saida.to_str().unwrap()]);
+        assert!(
+            out.status.success(),
+            "{}: {}",
+            comando,
+            String::from_utf8_lossy(&out.stderr)
+        );
+        let (pasta, args) = ler_log_compilador(&log);
+        assert_eq!(pasta, saida.canonicalize().unwrap(), "{}", comando);
+        let extra = |args: &[String]| args.iter().any(|a| a.ends_with("extra.pr"));
+        assert!(extra(&args), "{}: {:?}", comando, args);
+
+        // --excluir: o fonte nao chega ao compilador
+        let out = executar(comando, &["--excluir", "extra.pr"]);
+        assert!(out.status.success(), "{}", comando);
+        let (_, args) = ler_log_compilador(&log);
+        assert!(!extra(&args), "{}: {:?}", comando, args);
+    }
+
+    // pre_checks bloqueiam os dois; --skip-checks libera os dois
+    let proj_path = projeto.join("pordosol.proj");
+    let mut proj: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&proj_path).unwrap()).unwrap();
+    proj["configuracao"]["pre_checks"] = serde_json::json!(["false"]);
+    fs::write(&proj_path, serde_json::to_string_pretty(&proj).unwrap()).unwrap();
+    for comando in ["build", "producao"] {
+        assert!(!executar(comando, &[]).status.success(), "{}", comando);
+        assert!(
+            executar(comando, &["--skip-checks"]).status.success(),
+            "{}",
+            comando
+        );
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn e2e_producao_e_build_tem_a_mesma_superficie_de_flags() {
+    let temp = tempfile::tempdir().unwrap();
+    let log = temp.path().join("compilador.log");
+    let tools = criar_compilador_com_log(&temp.path().join("fake-tools"), &log);
+    let projeto = criar_projeto_console(&temp.path().join("workspace"), "app");
+    fs::write(projeto.join("src").join("extra.pr"), "// extra\n").unwrap();
+    let build = projeto.join("build");
+
+    let executar = |comando: &str, args: &[&str]| {
+        fs::remove_file(&log).ok();
+        let out = Command::new(bin_path())
+            .arg(comando)
+            .arg(&projeto)
+            .args(args)
+            .env("PORDOSOL_COMPILADOR_PATH", &tools.0)
+            .env("PORDOSOL_INTERPRETADOR_PATH", &tools.1)
+            .output()
+            .expect("run comando");
+        assert!(
+            out.status.success(),
+            "{} {:?}: {}",
+            comando,
+            args,
+            String::from_utf8_lossy(&out.stderr)
+        );
+        String::from_utf8_lossy(&out.stdout).to_string()
+    };
+    // Pasta de trabalho e argumentos da compilacao, sem o caminho absoluto dos fontes
+    let compilacao = || {
+        let (pasta, args) = ler_log_compilador(&log);
+        let args: Vec<String> = args
+            .iter()
+            .map(|a| match Path::new(a).file_name() {
+                Some(nome) if a.ends_with(".pr") => nome.to_string_lossy().to_string(),
+                _ => a.clone(),
+            })
+            .collect();
+        (pasta, args)
+    };
+
+    // --dry-run: mostra o comando sem compilar nem gravar nada na pasta de saida
+    for comando in ["build", "producao"] {
+        let stdout = executar(comando, &["--dry-run"]);
+        assert!(stdout.contains("Executaria: cd"), "{}: {}", comando, stdout);
+        assert!(stdout.contains("programa.pr"), "{}: {}", comando, stdout);
+        assert!(!log.exists(), "{}", comando);
+        assert_eq!(fs::read_dir(&build).unwrap().count(), 0, "{}", comando);
+    }
+
+    // --perfil: `producao` e o `build` com o perfil de producao preselecionado
+    for (comando, perfil, pasta, target) in [
+        ("build", "dev", build.clone(), "--target=bytecode"),
+        (
+            "producao",
+            "producao",
+            build.join("producao"),
+            "--target=llvm-ir",
+        ),
+    ] {
+        executar(comando, &[]);
+        let padrao = compilacao();
+        assert_eq!(padrao.0, pasta.canonicalize().unwrap(), "{}", comando);
+        assert_eq!(padrao.1[0], target, "{}", comando);
+        for outro in ["build", "producao"] {
+            executar(outro, &["--perfil", perfil]);
+            assert_eq!(compilacao(), padrao, "{} --perfil {}", outro, perfil);
+        }
+
+        // --checksums (padrao): um hash por artefato gerado, na pasta do perfil
+        let checksums = fs::read_to_string(pasta.join("checksums.txt")).unwrap();
+        assert!(!checksums.is_empty(), "{}", comando);
+        for linha in checksums.lines() {
+            let (hash, artefato) = linha.split_once("  ").unwrap();
+            assert_eq!(hash.len(), 16, "{}: {}", comando, linha);
+            assert!(pasta.join(artefato).is_file(), "{}: {}", comando, linha);
+        }
+        executar(comando, &["--checksums=false"]);
+        assert!(!pasta.join("checksums.txt").exists(), "{}", comando);
+    }
+
+    for comando in ["build", "producao"] {
+        // --message-format json: so o objeto com os diagnosticos no stdout
+        let stdout = executar(comando, &["--message-format", "json"]);
+        let resultado: serde_json::Value = serde_json::from_str(&stdout)
+            .unwrap_or_else(|e| panic!("{}: {}: {}", comando, e, stdout));
+        assert_eq!(resultado["ok"], true, "{}", comando);
+        assert_eq!(resultado["arquivos"], 2, "{}", comando);
+        assert!(resultado["diagnosticos"].is_array(), "{}", comando);
+
+        // --jobs vale num projeto: o compilador recebe o valor
+        executar(comando, &["--jobs", "3"]);
+        let jobs = fs::read_to_string(log.with_extension("log.jobs")).unwrap();
+        assert_eq!(jobs, "3", "{}", comando);
+        executar(comando, &[]);
+        let jobs = fs::read_to_string(log.with_extension("log.jobs")).unwrap();
+        assert_eq!(jobs, "", "{}", comando);
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn e2e_versao_all_lista_toda_a_toolchain() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp = tempfile::tempdir().unwrap();
+    let tools = criar_toolchain_fake(&temp.path().join("fake-tools"));
+    fs::write(
+        &tools.0,
+        "#!/usr/bin/env bash\necho \"Compilador Por do Sol (v1.2.3)\"\n",
+    )
+    .unwrap();
+    fs::set_permissions(&tools.0, fs::Permissions::from_mode(0o755)).unwrap();
+    let stdlib = temp.path().join("stdlib");
+    fs::create_dir_all(&stdlib).unwrap();
+    fs::write(stdlib.join("Sistema.toml"), "versao = \"0.4.1\"\n").unwrap();
+
+    let out = Command::new(bin_path())
+        .args(["version", "--all"])
+        .current_dir(temp.path())
+        .env("PORDOSOL_COMPILADOR_PATH", &tools.0)
+        .env(
+            "PORDOSOL_INTERPRETADOR_PATH",
+            temp.path().join("nao-existe"),
+        )
+        .env("PORDOSOL_STDLIB_PATH", &stdlib)
+        .output()
+        .expect("run version --all");
+    assert!(out.status.success());
+    let s = String::from_utf8_lossy(&out.stdout);
+    assert!(s.starts_with("pordosol CLI v"), "{}", s);
+    assert!(
+        s.contains("compilador: v1.2.3 (encontrado em")
+            && s.contains("origem env:PORDOSOL_COMPILADOR_PATH"),
+        "{}",
+        s
+    );
+    assert!(s.contains("interpretador: nao encontrado"), "{}", s);
+    assert!(
+        s.contains("biblioteca padrao: 0.4.1 (encontrado em"),
+        "{}",
+        s
+    );
+}
+
+#[test]
+fn e2e_build_emit_deps_grava_regra_make() {
+    let temp = tempfile::tempdir().unwrap();
+    let tools = criar_toolchain_fake(&temp.path().join("fake-tools"));
+    let projeto = criar_projeto_console(&temp.path().join("workspace"), "loja");
+    fs::write(projeto.join("src").join("meu modulo.pr"), "// modulo\n").unwrap();
+    let destino = projeto.join("build").join("programa.d");
+
+    let out = Command::new(bin_path())
+        .args(["compilar", "--project"])
+        .arg(&projeto)
+        .arg("--emit-deps")
+        .arg(&destino)
+        .env("PORDOSOL_COMPILADOR_PATH", &tools.0)
+        .env("PORDOSOL_INTERPRETADOR_PATH", &tools.1)
+        .output()
+        .expect("run build --emit-deps");
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+
+    let regra = fs::read_to_string(&destino).unwrap();
+    let linhas: Vec<&str> = regra.lines().collect();
+    assert!(linhas[0].ends_with("/build/loja.pbc: \\"), "{}", regra);
+    assert!(
+        linhas.iter().any(|l| l.ends_with("/src/programa.pr \\")),
+        "{}",
+        regra
+    );
+    assert!(
+        linhas.iter().any(|l| l.ends_with("/src/meu\\ modulo.pr")),
+        "{}",
+        regra
+    );
+}
+
+#[test]
+fn e2e_build_grava_manifesto_lido_pelo_info_e_removido_pelo_clean() {
+    let temp = tempfile::tempdir().unwrap();
+    let tools = criar_toolchain_fake(&temp.path().join("fake-tools"));
+    let projeto = criar_projeto_console(&temp.path().join("workspace"), "loja");
+
+    let pordosol = |args: &[&str]| {
+        let out = Command::new(bin_path())
+            .args(args)
+            .arg(&projeto)
+            .env("PORDOSOL_COMPILADOR_PATH", &tools.0)
+            .env("PORDOSOL_INTERPRETADOR_PATH", &tools.1)
+            .output()
+            .expect("run pordosol");
+        assert!(
+            out.status.success(),
+            "{}",
+            String::from_utf8_lossy(&out.stderr)
+        );
+        String::from_utf8_lossy(&out.stdout).to_string()
+    };
+
+    let s = pordosol(&["compilar", "--project"]);
+    assert!(!s.contains("manifest.json"), "{}", s);
+    let caminho = projeto.join("build").join("manifest.json");
+    let manifesto: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&caminho).unwrap()).unwrap();
+    assert_eq!(manifesto["perfil"], "build");
+    assert_eq!(manifesto["target"], "bytecode");
+    assert!(manifesto["timestamp"].as_u64().unwrap() > 0);
+    assert_eq!(
+        manifesto["compilador"]["caminho"],
+        tools.0.display().to_string()
+    );
+    assert!(manifesto["compilador"].get("versao").is_some());
+
+    let fontes = manifesto["fontes"].as_array().unwrap();
+    assert_eq!(fontes.len(), 1, "{}", manifesto);
+    assert_eq!(fontes[0]["caminho"], "src/programa.pr");
+    assert_eq!(fontes[0]["hash"].as_str().unwrap().len(), 16);
+    assert!(fontes[0]["bytes"].as_u64().unwrap() > 0);
+
+    let artefatos: Vec<(&str, u64)> = manifesto["artefatos"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|a| {
+            assert_eq!(a["hash"].as_str().unwrap().len(), 16);
+            (a["caminho"].as_str().unwrap(), a["bytes"].as_u64().unwrap())
+        })
+        .collect();
+    assert_eq!(
+        artefatos,
+        vec![("loja.pbc", 14), ("programa.pbc", 14)],
+        "{}",
+        manifesto
+    );
+
+    let s = pordosol(&["info"]);
+    assert!(
+        s.contains("Ultimo build: bytecode ha ") && s.contains(", 2 artefato(s)"),
+        "{}",
+        s
+    );
+
+    pordosol(&["clean"]);
+    assert!(!caminho.exists());
+    let s = pordosol(&["info"]);
+    assert!(!s.contains("Ultimo build"), "{}", s);
+}
+
+#[test]
+fn e2e_mudanca_na_stdlib_invalida_o_bytecode() {
+    let temp = tempfile::tempdir().unwrap();
+    let tools = criar_toolchain_fake(&temp.path().join("fake-tools"));
+    let projeto = criar_projeto_console(&temp.path().join("workspace"), "loja");
+    let stdlib = temp.path().join("stdlib");
+    fs::create_dir_all(&stdlib).unwrap();
+    fs::write(stdlib.join("Sistema.toml"), "versao = \"1.0.0\"\n").unwrap();
+
+    let pordosol = |args: &[&str]| {
+        let out = Command::new(bin_path())
+            .args(args)
+            .arg(&projeto)
+            .env("PORDOSOL_COMPILADOR_PATH", &tools.0)
+            .env("PORDOSOL_INTERPRETADOR_PATH", &tools.1)
+            .env("PORDOSOL_STDLIB_PATH", &stdlib)
+            .output()
+            .expect("run pordosol");
+        assert!(
+            out.status.success(),
+            "{}",
+            String::from_utf8_lossy(&out.stderr)
+        );
+        format!(
+            "{}{}",
+            String::from_utf8_lossy(&out.stdout),
+            String::from_utf8_lossy(&out.stderr)
+        )
+    };
+
+    let s = pordosol(&["run", "--project"]);
+    assert!(s.contains("Compilando..."), "{}", s);
+    let s = pordosol(&["run", "--project"]);
+    assert!(s.contains("Bytecode esta atualizado"), "{}", s);
+
+    // Atualizacao da stdlib: o Sistema.toml muda
+    fs::write(stdlib.join("Sistema.toml"), "versao = \"1.1.0\"\n").unwrap();
+
+    let s = pordosol(&["listar", "--mudancas"]);
+    assert!(
+        s.contains("Todos os artefatos estao desatualizados: biblioteca padrao mudou."),
+        "{}",
+        s
+    );
+    let s = pordosol(&["run", "--ignorar-stdlib-na-impressao-digital", "--project"]);
+    assert!(s.contains("Bytecode esta atualizado"), "{}", s);
+
+    let s = pordosol(&["run", "--project"]);
+    assert!(
+        s.contains("Artefatos desatualizados: biblioteca padrao mudou."),
+        "{}",
+        s
+    );
+    assert!(s.contains("Compilando..."), "{}", s);
+    let s = pordosol(&["run", "--project"]);
+    assert!(s.contains("Bytecode esta atualizado"), "{}", s);
+}
+
+#[cfg(unix)]
+#[test]
+fn e2e_run_propaga_o_codigo_de_saida_do_programa() {
+    let temp = tempfile::tempdir().unwrap();
+    let tools = criar_toolchain_fake(&temp.path().join("fake-tools"));
+    let projeto = criar_projeto_console(&temp.path().join("workspace"), "app");
+
+    fs::write(&tools.1, "#!/usr/bin/env bash\nexit 42\n").unwrap();
+    let out = run_com_toolchain(&projeto, &tools, &[]);
+    assert_eq!(out.status.code(), Some(42));
+    let err = String::from_utf8_lossy(&out.stderr);
+    assert!(err.contains("Execucao falhou"), "{}", err);
+
+    // Codigos que coincidem com os da CLI tambem passam adiante sem traducao
+    fs::write(&tools.1, "#!/usr/bin/env bash\nexit 3\n").unwrap();
+    let out = run_com_toolchain(&projeto, &tools, &[]);
+    assert_eq!(out.status.code(), Some(3));
+
+    // Morto por sinal: 128 + sinal, como os shells
+    fs::write(&tools.1, "#!/usr/bin/env bash\nkill -TERM $$\n").unwrap();
+    let out = run_com_toolchain(&projeto, &tools, &[]);
+    assert_eq!(out.status.code(), Some(128 + 15));
+}
+
+#[test]
+fn e2e_target_dir_flag_e_env_substituem_build() {
+    let temp = tempfile::tempdir().unwrap();
+    let tools = criar_toolchain_fake(&temp.path().join("fake-tools"));
+    let projeto = criar_projeto_console(&temp.path().join("workspace"), "app");
+    let alvo_env = temp.path().join("alvo-env");
+    let alvo_flag = temp.path().join("alvo-flag");
+
+    let pordosol = |args: &[&str]| {
+        Command::new(bin_path())
+            .args(args)
+            .arg(&projeto)
+            .env("PORDOSOL_COMPILADOR_PATH", &tools.0)
+            .env("PORDOSOL_INTERPRETADOR_PATH", &tools.1)
+            .env("PORDOSOL_TARGET_DIR", &alvo_env)
+            .output()
+            .expect("run pordosol")
+    };
+    let ok = |out: Output| {
+        assert!(
+            out.status.success(),
+            "{}",
+            String::from_utf8_lossy(&out.stderr)
+        );
+    };
+
+    ok(pordosol(&["compilar", "--project"]));
+    assert!(alvo_env.join("programa.pbc").is_file());
+    assert!(!projeto.join("build").join("programa.pbc").exists());
+
+    // run deduz o .pbc na mesma pasta
+    ok(pordosol(&["run", "--no-build", "--project"]));
+
+    // A flag vence a variavel
+    let alvo = alvo_flag.display().to_string();
+    ok(pordosol(&["--target-dir", &alvo, "compilar", "--project"]));
+    assert!(alvo_flag.join("programa.pbc").is_file());
+
+    ok(pordosol(&["--target-dir", &alvo, "clean"]));
+    assert!(!alvo_flag.join("programa.pbc").exists());
+    assert!(alvo_env.join("programa.pbc").is_file());
+}
+
+#[cfg(unix)]
+#[test]
+fn e2e_run_aplica_args_e_env_da_secao_run_do_projeto() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp = tempfile::tempdir().unwrap();
+    let tools = criar_toolchain_fake(&temp.path().join("fake-tools"));
+    fs::write(
+        &tools.1,
+        "#!/bin/sh\nshift\necho \"ARGV: $*\"\n/usr/bin/env\n",
+    )
+    .unwrap();
+    fs::set_permissions(&tools.1, fs::Permissions::from_mode(0o755)).unwrap();
+    let projeto = criar_projeto_console(&temp.path().join("workspace"), "app");
+    let proj = projeto.join("pordosol.proj");
+    let mut config: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&proj).unwrap()).unwrap();
+    config["run"] = serde_json::json!({
+        "args": ["--porta", "8080"],
+        "env": {"PORDOSOL_TESTE_MODO": "dev", "PORDOSOL_TESTE_NIVEL": 3}
+    });
+    fs::write(&proj, serde_json::to_string_pretty(&config).unwrap()).unwrap();
+
+    let out = run_com_toolchain(
+        &projeto,
+        &tools,
+        &[
+            "--verboso",
+            "--env",
+            "PORDOSOL_TESTE_MODO=cli",
+            "--",
+            "--extra",
+        ],
+    );
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(out.status.success(), "{}", stderr);
+    assert!(stdout.contains("ARGV: --porta 8080 --extra"), "{}", stdout);
+    assert!(stdout.contains("PORDOSOL_TESTE_MODO=cli"), "{}", stdout);
+    assert!(stdout.contains("PORDOSOL_TESTE_NIVEL=3"), "{}", stdout);
+    assert!(
+        stderr.contains("PORDOSOL_TESTE_MODO=cli, PORDOSOL_TESTE_NIVEL=3"),
+        "{}",
+        stderr
+    );
+
+    config["run"] = serde_json::json!({"args": "--porta"});
+    fs::write(&proj, serde_json::to_string_pretty(&config).unwrap()).unwrap();
+    let out = run_com_toolchain(&projeto, &tools, &[]);
+    assert_eq!(out.status.code(), Some(3));
+    assert!(String::from_utf8_lossy(&out.stderr).contains("campo `run` invalido"));
+}
+
+#[cfg(unix)]
+#[test]
+fn e2e_run_comparar_com_relata_diff_das_saidas() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp = tempfile::tempdir().unwrap();
+    let tools = criar_toolchain_fake(&temp.path().join("fake-tools"));
+    fs::write(
+        &tools.1,
+        "#!/bin/sh\necho \"pid: $$\"\ncat \"$1\"\necho \"args: $2\"\n",
+    )
+    .unwrap();
+    fs::set_permissions(&tools.1, fs::Permissions::from_mode(0o755)).unwrap();
+    let projeto = criar_projeto_console(&temp.path().join("workspace"), "app");
+    let antigo = temp.path().join("antigo.pbc");
+    fs::write(&antigo, "fake-bytecode\n").unwrap();
+    let antigo_str = antigo.display().to_string();
+
+    let comparar = |extra: &[&str]| {
+        let mut args = vec!["--comparar-com", antigo_str.as_str()];
+        args.extend_from_slice(extra);
+        args.extend_from_slice(&["--", "x"]);
+        run_com_toolchain(&projeto, &tools, &args)
+    };
+
+    // So o pid muda entre as execucoes: mascarado, as saidas sao iguais
+    let out = comparar(&["--ignorar-regex", r"pid: \d+"]);
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    assert!(stdout.contains("Saidas identicas."), "{}", stdout);
+    assert!(stdout.contains("atual: status 0"), "{}", stdout);
+
+    fs::write(&antigo, "bytecode-antigo\n").unwrap();
+    let out = comparar(&["--ignorar-regex", r"pid: \d+"]);
+    assert_eq!(out.status.code(), Some(1));
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(
+        stdout.contains(&format!("--- {}\n+++ atual\n", antigo_str)),
+        "{}",
+        stdout
+    );
+    assert!(
+        stdout.contains(" <ignorado>\n-bytecode-antigo\n+fake-bytecode\n args: x"),
+        "{}",
+        stdout
+    );
+
+    let out = comparar(&["--ignorar-regex", r"pid: \d+", "--somente-relatar"]);
+    assert!(out.status.success());
+
+    let out = comparar(&["--ignorar-regex", "(pid)"]);
+    assert!(!out.status.success());
+    assert!(String::from_utf8_lossy(&out.stderr).contains("--ignorar-regex invalido"));
+}
+
+#[cfg(unix)]
+#[test]
+fn e2e_info_build_gerado_entra_na_compilacao_sem_sujar_o_build() {
+    let temp = tempfile::tempdir().unwrap();
+    let log = temp.path().join("compilador.log");
+    let tools = criar_compilador_com_log(&temp.path().join("fake-tools"), &log);
+    let base = temp.path().join("workspace");
+    let status = Command::new(bin_path())
+        .args(["new", "console", "-n", "app", "--info-build", "-o"])
+        .arg(&base)
+        .status()
+        .expect("run new");
+    assert!(status.success());
+    let projeto = base.join("app");
+    assert_eq!(
+        fs::read_to_string(projeto.join(".gitignore")).unwrap(),
+        "/src/gerado/info_build.pr\n"
+    );
+
+    // Fora de src/ o arquivo tambem entra nos fontes
+    let proj_path = projeto.join("pordosol.proj");
+    let mut proj: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&proj_path).unwrap()).unwrap();
+    assert_eq!(
+        proj["configuracao"]["gerar_info_build"],
+        "src/gerado/info_build.pr"
+    );
+    proj["configuracao"]["gerar_info_build"] = serde_json::json!("gerado/info.pr");
+    proj["versao"] = serde_json::json!("2.3.4");
+    fs::write(&proj_path, serde_json::to_string_pretty(&proj).unwrap()).unwrap();
+
+    let out = run_com_toolchain(&projeto, &tools, &[]);
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    let gerado = projeto.join("gerado").join("info.pr");
+    let (_, args) = ler_log_compilador(&log);
+    assert!(args.iter().any(|a| Path::new(a) == gerado), "{:?}", args);
+    let info = fs::read_to_string(&gerado).unwrap();
+    assert!(info.contains("constante texto NOME = \"app\";"), "{}", info);
+    assert!(
+        info.contains("constante texto VERSAO = \"2.3.4\";"),
+        "{}",
+        info
+    );
+    assert!(
+        info.contains("constante texto PERFIL = \"dev\";"),
+        "{}",
+        info
+    );
+
+    // Regenerar com os mesmos metadados nao regrava o arquivo nem invalida o bytecode
+    let out = run_com_toolchain(&projeto, &tools, &[]);
+    assert!(String::from_utf8_lossy(&out.stdout).contains("Bytecode esta atualizado"));
+    assert_eq!(fs::read_to_string(&gerado).unwrap(), info);
+}
+
+#[cfg(unix)]
+#[test]
+fn e2e_flags_compilador_repassadas_apos_target() {
+    let temp = tempfile::tempdir().unwrap();
+    let log = temp.path().join("compilador.log");
+    let tools = criar_compilador_com_log(&temp.path().join("fake-tools"), &log);
+    let projeto = criar_projeto_console(&temp.path().join("workspace"), "app");
+    let proj_path = projeto.join("pordosol.proj");
+    let mut proj: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&proj_path).unwrap()).unwrap();
+    proj["configuracao"]["flags_compilador"] = serde_json::json!(["--otimizacao=3"]);
+    fs::write(&proj_path, serde_json::to_string_pretty(&proj).unwrap()).unwrap();
+
+    // `check` tambem: um pre-commit nao pode aceitar o que o build rejeita
+    for comando in ["build", "producao", "check", "run"] {
+        fs::remove_file(&log).ok();
+        // `run` reaproveitaria o bytecode do `build` acima sem recompilar
+        let extras: &[&str] = if comando == "run" { &["--force"] } else { &[] };
+        let out = Command::new(bin_path())
+            .arg(comando)
+            .arg(&projeto)
+            .args(extras)
+            .args(["--arg-compilador", "--sem-avisos"])
+            .env("PORDOSOL_COMPILADOR_PATH", &tools.0)
+            .env("PORDOSOL_INTERPRETADOR_PATH", &tools.1)
+            .output()
+            .expect("run comando");
+        assert!(
+            out.status.success(),
+            "{}: {}",
+            comando,
+            String::from_utf8_lossy(&out.stderr)
+        );
+        let (_, args) = ler_log_compilador(&log);
+        assert!(args[0].starts_with("--target="), "{}: {:?}", comando, args);
+        assert_eq!(
+            &args[1..3],
+            ["--otimizacao=3", "--sem-avisos"],
+            "{}",
+            comando
+        );
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn e2e_build_ignora_link_fisico_de_fonte() {
+    let temp = tempfile::tempdir().unwrap();
+    let log = temp.path().join("compilador.log");
+    let tools = criar_compilador_com_log(&temp.path().join("fake-tools"), &log);
+    let projeto = criar_projeto_console(&temp.path().join("workspace"), "app");
+    let backup = projeto.join("src").join("backup");
+    fs::create_dir_all(&backup).unwrap();
+    fs::hard_link(
+        projeto.join("src").join("programa.pr"),
+        backup.join("programa.pr"),
+    )
+    .unwrap();
+
+    let compilar = |extras: &[&str]| {
+        let out = Command::new(bin_path())
+            .arg("build")
+            .arg(&projeto)
+            .args(extras)
+            .env("PORDOSOL_COMPILADOR_PATH", &tools.0)
+            .env("PORDOSOL_INTERPRETADOR_PATH", &tools.1)
+            .output()
+            .expect("run build");
+        assert!(
+            out.status.success(),
+            "{}",
+            String::from_utf8_lossy(&out.stderr)
+        );
+        let (_, args) = ler_log_compilador(&log);
+        let fontes: Vec<String> = args.into_iter().filter(|a| a.ends_with(".pr")).collect();
+        (String::from_utf8_lossy(&out.stderr).to_string(), fontes)
+    };
+
+    let (stderr, fontes) = compilar(&[]);
+    assert_eq!(fontes.len(), 1, "{:?}", fontes);
+    assert!(fontes[0].ends_with("src/programa.pr"), "{:?}", fontes);
+    assert!(
+        stderr.contains("backup/programa.pr (mesmo arquivo que src/programa.pr)"),
+        "{}",
+        stderr
+    );
+
+    let (_, fontes) = compilar(&["--permitir-duplicados"]);
+    assert_eq!(fontes.len(), 2, "{:?}", fontes);
+}
+
+#[test]
+fn e2e_env_exporta_toolchain_em_sh_e_powershell() {
+    let temp = tempfile::tempdir().unwrap();
+    let tools = criar_toolchain_fake(&temp.path().join("tools"));
+    let projeto = criar_projeto_console(temp.path(), "app_env");
+
+    let env = |extras: &[&str]| {
+        let out = Command::new(bin_path())
+            .arg("env")
+            .arg(&projeto)
+            .args(extras)
+            .env("PORDOSOL_COMPILADOR_PATH", &tools.0)
+            .env("PORDOSOL_INTERPRETADOR_PATH", &tools.1)
+            .env("PORDOSOL_HOME", temp.path())
+            .env_remove("PORDOSOL_STDLIB_PATH")
+            .env_remove("PORDOSOL_BIBLIOTECA_PADRAO_PATH")
+            .output()
+            .expect("run env");
+        assert!(
+            out.status.success(),
+            "{}",
+            String::from_utf8_lossy(&out.stderr)
+        );
+        String::from_utf8_lossy(&out.stdout).to_string()
+    };
+
+    let sh = env(&[]);
+    assert!(
+        sh.contains(&format!(
+            "export PORDOSOL_COMPILADOR_PATH='{}'",
+            tools.0.display()
+        )),
+        "{}",
+        sh
+    );
+    assert!(
+        sh.contains(&format!("export PORDOSOL_HOME='{}'", temp.path().display())),
+        "{}",
+        sh
+    );
+    // Sem stdlib: comentario em vez de um export quebrado
+    assert!(
+        sh.lines()
+            .any(|l| l.starts_with("# PORDOSOL_STDLIB_PATH nao resolvido")),
+        "{}",
+        sh
+    );
+
+    let ps = env(&["--shell", "powershell"]);
+    assert!(
+        ps.contains(&format!(
+            "$env:PORDOSOL_INTERPRETADOR_PATH = '{}'",
+            tools.1.display()
+        )),
+        "{}",
+        ps
+    );
+    assert!(!ps.contains("export "), "{}", ps);
+
+    let json: serde_json::Value = serde_json::from_str(&env(&["--json"])).unwrap();
+    let compilador = json
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|v| v["nome"] == "PORDOSOL_COMPILADOR_PATH")
+        .unwrap();
+    assert_eq!(compilador["origem"], "env:PORDOSOL_COMPILADOR_PATH");
+}
+
+#[cfg(unix)]
+#[test]
+fn e2e_resultado_grava_resumo_de_build_e_test_no_sucesso_e_na_falha() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp = tempfile::tempdir().unwrap();
+    let tools = criar_toolchain_fake(&temp.path().join("fake-tools"));
+    fs::write(&tools.1, "#!/usr/bin/env bash\necho \"ola $(cat)\"\n").unwrap();
+    fs::set_permissions(&tools.1, fs::Permissions::from_mode(0o755)).unwrap();
+    let quebrado = temp.path().join("fake-tools").join("compilador-quebrado");
+    fs::write(&quebrado, "#!/bin/sh\necho 'erro de sintaxe' >&2\nexit 1\n").unwrap();
+    fs::set_permissions(&quebrado, fs::Permissions::from_mode(0o755)).unwrap();
+    let projeto = criar_projeto_console(&temp.path().join("workspace"), "app");
+    let testes = projeto.join("testes");
+    fs::create_dir_all(&testes).unwrap();
+    fs::write(testes.join("saudacao.pr"), "").unwrap();
+    fs::write(testes.join("saudacao.entrada"), "mundo\n").unwrap();
+    fs::write(testes.join("saudacao.saida"), "ola mundo\n").unwrap();
+    let arquivo = temp.path().join("relatorios").join("resultado.json");
+
+    let executar = |args: &[&str], compilador: &Path| {
+        let out = Command::new(bin_path())
+            .args(args)
+            .arg("--resultado")
+            .arg(&arquivo)
+            .env("PORDOSOL_COMPILADOR_PATH", compilador)
+            .env("PORDOSOL_INTERPRETADOR_PATH", &tools.1)
+            .output()
+            .expect("run pordosol");
+        let json: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&arquivo).unwrap()).unwrap();
+        (out.status.code(), json)
+    };
+    let projeto_str = projeto.to_str().unwrap();
+
+    let (codigo, json) = executar(&["build", projeto_str], &tools.0);
+    assert_eq!(codigo, Some(0));
+    assert_eq!(json["comando"], "build");
+    assert_eq!(json["sucesso"], true);
+    assert_eq!(json["codigo_saida"], 0);
+    assert_eq!(json["argumentos"][1], projeto_str);
+    assert!(json["inicio"].as_str().unwrap().ends_with('Z'), "{}", json);
+    assert_eq!(json["erros"], serde_json::json!([]));
+    let artefatos = &json["dados"]["builds"][0]["artefatos"];
+    assert_eq!(artefatos[0]["caminho"], "app.pbc", "{}", json);
+
+    let (codigo, json) = executar(&["build", projeto_str], &quebrado);
+    assert_eq!(codigo, Some(1));
+    assert_eq!(json["sucesso"], false);
+    assert_eq!(json["codigo_saida"], 1);
+    assert!(
+        json["erros"][0]
+            .as_str()
+            .unwrap()
+            .contains("Compilacao falhou"),
+        "{}",
+        json
+    );
+
+    let (codigo, json) = executar(&["test", "--project", projeto_str], &tools.0);
+    assert_eq!(codigo, Some(0));
+    assert_eq!(json["comando"], "test");
+    assert_eq!(
+        json["dados"]["testes"],
+        serde_json::json!({ "total": 1, "ok": 1, "falhas": [] })
+    );
+
+    fs::write(testes.join("saudacao.saida"), "tchau\n").unwrap();
+    let (codigo, json) = executar(&["test", "--project", projeto_str], &tools.0);
+    assert_eq!(codigo, Some(1));
+    assert_eq!(json["sucesso"], false);
+    assert_eq!(
+        json["dados"]["testes"]["falhas"],
+        serde_json::json!(["saudacao"])
+    );
+    assert!(
+        json["erros"][0].as_str().unwrap().contains("saudacao"),
+        "{}",
+        json
+    );
+
+    // Nada de temporarios esquecidos ao lado do resultado
+    let nomes: Vec<_> = fs::read_dir(arquivo.parent().unwrap())
+        .unwrap()
+        .map(|e| e.unwrap().file_name())
+        .collect();
+    assert_eq!(nomes, ["resultado.json"]);
+}
+
+#[cfg(unix)]
+#[test]
+fn e2e_hooks_pre_e_post_build_rodam_na_raiz_com_variaveis() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp = tempfile::tempdir().unwrap();
+    let tools = criar_toolchain_fake(&temp.path().join("fake-tools"));
+    let projeto = criar_projeto_console(&temp.path().join("workspace"), "app");
+    let scripts = projeto.join("scripts");
+    fs::create_dir_all(&scripts).unwrap();
+    for (nome, corpo) in [
+        (
+            "pre.sh",
+            "echo \"pre $PORDOSOL_TARGET $PORDOSOL_RAIZ $PORDOSOL_SAIDA $(pwd -P)\" >> hooks.log\n",
+        ),
+        // So passa se o artefato ja existe: roda depois do compilador
+        (
+            "post.sh",
+            "test -f \"$PORDOSOL_SAIDA/app.pbc\" && echo post >> hooks.log\n",
+        ),
+    ] {
+        let caminho = scripts.join(nome);
+        fs::write(&caminho, format!("#!/bin/sh\nset -e\n{}", corpo)).unwrap();
+        fs::set_permissions(&caminho, fs::Permissions::from_mode(0o755)).unwrap();
+    }
+    let proj = projeto.join("pordosol.proj");
+    let mut json: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&proj).unwrap()).unwrap();
+    json["hooks"] = serde_json::json!({
+        "pre_build": ["scripts/pre.sh"],
+        "post_build": ["scripts/post.sh"],
+    });
+    fs::write(&proj, serde_json::to_string_pretty(&json).unwrap()).unwrap();
+    let log = projeto.join("hooks.log");
+
+    let build = |args: &[&str]| {
+        Command::new(bin_path())
+            .arg("build")
+            .arg(&projeto)
+            .args(args)
+            .env("PORDOSOL_COMPILADOR_PATH", &tools.0)
+            .env("PORDOSOL_INTERPRETADOR_PATH", &tools.1)
+            .output()
+            .expect("run build")
+    };
+
+    let out = build(&[]);
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    let registro = fs::read_to_string(&log).unwrap();
+    assert_eq!(
+        registro,
+        format!(
+            "pre bytecode {} {} {}\npost\n",
+            projeto.display(),
+            projeto.join("build").display(),
+            fs::canonicalize(&projeto).unwrap().display()
+        )
+    );
+
+    // O passo de compilacao do run tambem roda os hooks
+    let out = run_com_toolchain(&projeto, &tools, &["--force"]);
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    assert_eq!(fs::read_to_string(&log).unwrap().lines().count(), 4);
+
+    assert!(build(&["--sem-hooks"]).status.success());
+    assert_eq!(fs::read_to_string(&log).unwrap().lines().count(), 4);
+
+    json["hooks"]["pre_build"] = serde_json::json!(["exit 7"]);
+    fs::write(&proj, serde_json::to_string_pretty(&json).unwrap()).unwrap();
+    fs::remove_dir_all(projeto.join("build")).unwrap();
+    let out = build(&[]);
+    assert_eq!(out.status.code(), Some(1));
+    let err = String::from_utf8_lossy(&out.stderr);
+    assert!(
+        err.contains("Hook pre_build falhou: `exit 7` (codigo 7)"),
+        "{}",
+        err
+    );
+    assert!(
+        !projeto.join("build").join("app.pbc").exists(),
+        "o compilador nao deve rodar apos um hook falhar"
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn e2e_producao_no_workspace_compila_dependencia_compartilhada_uma_vez() {
+    let temp = tempfile::tempdir().unwrap();
+    let log = temp.path().join("compilador.log");
+    let chamadas = temp.path().join("compilador.log.chamadas");
+    let tools = criar_compilador_com_log(&temp.path().join("fake-tools"), &log);
+    let ws = temp.path().join("ws");
+    fs::create_dir_all(&ws).unwrap();
+    fs::write(
+        ws.join("pordosol.workspace"),
+        r#"{"membros": ["*"], "cache_dependencias": true}"#,
+    )
+    .unwrap();
+    let comum = criar_projeto_console(&ws, "comum");
+    for nome in ["api", "worker"] {
+        let projeto = criar_projeto_console(&ws, nome);
+        let proj_path = projeto.join("pordosol.proj");
+        let mut proj: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&proj_path).unwrap()).unwrap();
+        proj["dependencias"] = serde_json::json!({ "comum": { "path": "../comum" } });
+        fs::write(&proj_path, serde_json::to_string_pretty(&proj).unwrap()).unwrap();
+    }
+
+    let executar = |comando: &str, extras: &[&str]| {
+        let out = Command::new(bin_path())
+            .arg(comando)
+            .arg(&ws)
+            .args(extras)
+            .env("PORDOSOL_COMPILADOR_PATH", &tools.0)
+            .env("PORDOSOL_INTERPRETADOR_PATH", &tools.1)
+            .output()
+            .expect("run comando");
+        assert!(
+            out.status.success(),
+            "{}: {}",
+            comando,
+            String::from_utf8_lossy(&out.stderr)
+        );
+        String::from_utf8_lossy(&out.stdout).to_string()
+    };
+    // Chamadas ao compilador desde a ultima leitura: pasta de trabalho e argumentos
+    let ler_chamadas = || {
+        let texto = fs::read_to_string(&chamadas).unwrap_or_default();
+        fs::remove_file(&chamadas).ok();
+        texto.lines().map(str::to_string).collect::<Vec<_>>()
+    };
+    let pasta_deps = ws.join(".pordosol").join("cache").join("deps");
+    let da_dependencia = |lista: &[String]| {
+        lista
+            .iter()
+            .filter(|c| c.starts_with(&*pasta_deps.to_string_lossy()))
+            .count()
+    };
+    // Situacao do cache de cada membro, pela linha dele no relatorio
+    let cache = |saida: &str, membro: &str| {
+        saida
+            .lines()
+            .find(|l| l.split_whitespace().next() == Some(membro))
+            .and_then(|l| {
+                ["reaproveitado", "recompilado"]
+                    .into_iter()
+                    .find(|c| l.contains(c))
+            })
+            .unwrap_or_else(|| panic!("{} sem situacao no relatorio:\n{}", membro, saida))
+    };
+    let fonte_comum = comum.join("src").join("programa.pr");
+
+    // Os dois membros em paralelo: comum compila uma vez, so para o primeiro deles
+    let saida = executar("producao", &["--jobs", "2"]);
+    let lista = ler_chamadas();
+    assert_eq!(da_dependencia(&lista), 1, "{:#?}", lista);
+    let membros: Vec<_> = lista
+        .iter()
+        .filter(|c| {
+            c.starts_with(&*ws.join("api").to_string_lossy())
+                || c.starts_with(&*ws.join("worker").to_string_lossy())
+        })
+        .collect();
+    assert_eq!(membros.len(), 2, "{:#?}", lista);
+    for chamada in membros {
+        assert!(
+            chamada.contains(&*pasta_deps.to_string_lossy()),
+            "{}",
+            chamada
+        );
+        assert!(
+            !chamada.contains(&*fonte_comum.to_string_lossy()),
+            "{}",
+            chamada
+        );
+    }
+    let mut situacoes = [cache(&saida, "api"), cache(&saida, "worker")];
+    situacoes.sort();
+    assert_eq!(situacoes, ["reaproveitado", "recompilado"], "{}", saida);
+
+    // Entre invocacoes: nenhuma compilacao nova da dependencia
+    let saida = executar("producao", &[]);
+    assert_eq!(da_dependencia(&ler_chamadas()), 0);
+    assert_eq!(cache(&saida, "api"), "reaproveitado", "{}", saida);
+    assert_eq!(cache(&saida, "worker"), "reaproveitado", "{}", saida);
+
+    // Fonte da dependencia alterada: uma compilacao nova, de novo compartilhada
+    let mut texto = fs::read_to_string(&fonte_comum).unwrap();
+    texto.push_str("\n// alterado\n");
+    fs::write(&fonte_comum, texto).unwrap();
+    executar("producao", &[]);
+    assert_eq!(da_dependencia(&ler_chamadas()), 1);
+
+    // clean --tudo leva o cache do workspace
+    executar("clean", &["--tudo"]);
+    assert!(!ws.join(".pordosol/cache").exists());
+    executar("producao", &[]);
+    assert_eq!(da_dependencia(&ler_chamadas()), 1);
+}